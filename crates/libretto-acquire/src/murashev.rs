@@ -372,7 +372,6 @@ fn walk_node(
                         }
                         elements.push(ContentElement::ActHeader(trimmed));
                     }
-                    return; // Don't recurse into children, we already collected text
                 }
                 "b" => {
                     let text = collect_all_text(node_id, tree);
@@ -389,7 +388,6 @@ fn walk_node(
                         }
                         elements.push(ContentElement::NumberLabel(trimmed));
                     }
-                    return;
                 }
                 "i" => {
                     let text = collect_all_text(node_id, tree);
@@ -406,28 +404,24 @@ fn walk_node(
                         }
                         elements.push(ContentElement::Direction(trimmed));
                     }
-                    return;
                 }
                 "span" => {
                     // <span class="act"> wraps <act>, just recurse
                     for child in node.children() {
                         walk_node(child.id(), tree, elements, pending_text, consecutive_br);
                     }
-                    return;
                 }
                 "td" | "div" | "p" | "a" => {
                     // Container elements — recurse into children
                     for child in node.children() {
                         walk_node(child.id(), tree, elements, pending_text, consecutive_br);
                     }
-                    return;
                 }
                 _ => {
                     // Unknown element — collect its text content
                     for child in node.children() {
                         walk_node(child.id(), tree, elements, pending_text, consecutive_br);
                     }
-                    return;
                 }
             }
         }
@@ -14,6 +14,13 @@ pub fn normalize_text(input: &str) -> String {
         .join("\n")
 }
 
+/// Optional cleanup pass for text from badly encoded pages: decodes HTML
+/// entities, re-decodes UTF-8 that was read as Latin-1, and drops control
+/// characters. Text that is already clean comes back unchanged.
+pub fn repair_artifacts(input: &str) -> String {
+    libretto_model::artifacts::repair_artifacts(input)
+}
+
 /// Collapse multiple consecutive blank lines into a single blank line.
 pub fn collapse_blank_lines(input: &str) -> String {
     let mut result = String::with_capacity(input.len());
@@ -53,6 +60,12 @@ mod tests {
         assert_eq!(result, "hello\nworld");
     }
 
+    #[test]
+    fn test_repair_artifacts() {
+        assert_eq!(repair_artifacts("Non piÃ¹ andrai &amp; cos&igrave;"), "Non più andrai & così");
+        assert_eq!(repair_artifacts("Non più andrai"), "Non più andrai");
+    }
+
     #[test]
     fn test_collapse_blank_lines() {
         let input = "line 1\n\n\n\nline 2\n\nline 3";
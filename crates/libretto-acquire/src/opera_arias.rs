@@ -18,17 +18,8 @@ pub async fn acquire(opera: &str, lang: &str, output_dir: &str) -> Result<()> {
     let langs: Vec<&str> = lang.split(',').map(|s| s.trim()).collect();
 
     for lang_code in &langs {
-        let (url, div_class) = match *lang_code {
-            "it" => (
-                format!("{BASE_URL}/{opera}/libretto/"),
-                "libretto_div",
-            ),
-            "en" => (
-                format!("{BASE_URL}/{opera}/libretto/english/"),
-                "translation_div",
-            ),
-            other => anyhow::bail!("Unsupported language for opera-arias.com: {other}"),
-        };
+        let (path, div_class) = page(lang_code)?;
+        let url = format!("{BASE_URL}/{opera}/{path}");
 
         tracing::info!(url = %url, lang = lang_code, "Fetching from opera-arias.com");
         let html = fetch_page(&url).await?;
@@ -48,6 +39,23 @@ pub async fn acquire(opera: &str, lang: &str, output_dir: &str) -> Result<()> {
     Ok(())
 }
 
+/// Path of the libretto page in `lang` under the opera's, and the class of
+/// the div holding its text.
+fn page(lang: &str) -> Result<(&'static str, &'static str)> {
+    match lang {
+        "it" => Ok(("libretto/", "libretto_div")),
+        "en" => Ok(("libretto/english/", "translation_div")),
+        other => anyhow::bail!("Unsupported language for opera-arias.com: {other}"),
+    }
+}
+
+/// Parse a libretto page in `lang` ("it" or "en"), as `acquire` fetches
+/// it, into content elements, e.g. from a cached `raw_it.html`.
+pub fn parse_page(html: &str, lang: &str) -> Result<Vec<ContentElement>> {
+    let (_, div_class) = page(lang)?;
+    parse_libretto_page(html, div_class)
+}
+
 async fn fetch_page(url: &str) -> Result<String> {
     let client = reqwest::Client::builder()
         .user_agent("libretto/0.1 (opera libretto tool)")
@@ -158,7 +166,6 @@ fn walk_node(
                             elements.push(ContentElement::NumberLabel(trimmed));
                         }
                     }
-                    return;
                 }
                 "i" => {
                     let text = collect_all_text(node_id, tree);
@@ -167,22 +174,18 @@ fn walk_node(
                         flush_text(pending_text, elements);
                         elements.push(ContentElement::Direction(trimmed));
                     }
-                    return;
                 }
                 "h1" | "h2" => {
                     // Skip title headers — they're page chrome, not libretto text
-                    return;
                 }
                 "script" | "ins" | "style" => {
                     // Skip ad/script elements
-                    return;
                 }
                 _ => {
                     // Container elements (div, p, span, a, etc.) — recurse
                     for child in node.children() {
                         walk_node(child.id(), tree, elements, pending_text, consecutive_br);
                     }
-                    return;
                 }
             }
         }
@@ -0,0 +1,266 @@
+// `libretto.toml`: per-project defaults.
+//
+// An opera project folder holds one base libretto, its timing overlay and
+// annotations, the recording's audio, and the exports made from them. A
+// `libretto.toml` at the top of the folder names them once, so commands
+// run anywhere inside it need few or no flags:
+//
+//     [paths]
+//     raw = "raw"                        # acquire output, parse input
+//     base = "figaro.libretto.json"
+//     timing = "giulini.timing.json"
+//     annotations = "figaro.annotations.json"
+//     pronunciation = "figaro.ipa.json"
+//     synopsis = "figaro.synopsis.json"
+//     audio = "audio/giulini-1959"
+//     out_dir = "export"
+//
+//     [contributor]
+//     name = "Ada Lovelace"              # credited in timing overlays
+//
+//     [language]
+//     original = "it"
+//     translation = "en"
+//
+//     [parse]                            # set on the parsed libretto
+//     title = "Le nozze di Figaro"
+//     composer = "W. A. Mozart"
+//
+//     [write]
+//     backup = "numbered"                # or "bak" (default), "none"
+//
+//     [merge]
+//     duplicates = "allow"               # or "warn" (default), "error", "keep-first"
+//
+// Paths are relative to the folder the file is in. A flag given on the
+// command line always wins over the file. Only the nearest file is read;
+// if it cannot be, commands run without it, with a warning naming it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use libretto_model::merge::DuplicateStrategy;
+use libretto_parse::ParseOverrides;
+use serde::Deserialize;
+
+use crate::save::Backup;
+
+/// Conventional file name of the project configuration.
+pub const PROJECT_CONFIG_FILE: &str = "libretto.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    paths: Paths,
+    #[serde(default)]
+    contributor: Contributor,
+    #[serde(default)]
+    language: Languages,
+    /// Opera details for `libretto parse` to set.
+    #[serde(default)]
+    pub parse: ParseOverrides,
+    #[serde(default)]
+    write: Write,
+    #[serde(default)]
+    merge: Merge,
+    /// Folder the file was found in, which its paths are relative to;
+    /// empty for the working directory.
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Paths {
+    raw: Option<String>,
+    base: Option<String>,
+    timing: Option<String>,
+    annotations: Option<String>,
+    pronunciation: Option<String>,
+    synopsis: Option<String>,
+    audio: Option<String>,
+    out_dir: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Contributor {
+    name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Languages {
+    original: Option<String>,
+    translation: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Write {
+    backup: Option<Backup>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Merge {
+    duplicates: Option<DuplicateStrategy>,
+}
+
+impl ProjectConfig {
+    /// Load a project configuration file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut config: ProjectConfig = toml::from_str(&contents)
+            .with_context(|| format!("Invalid project configuration {}", path.display()))?;
+        config.root = path.parent().unwrap_or(Path::new("")).to_path_buf();
+        Ok(config)
+    }
+
+    /// Load the nearest `libretto.toml` at or above `start`, or no
+    /// defaults if there is none. One that cannot be loaded gives no
+    /// defaults either, with a warning, rather than failing commands that
+    /// may not need it; the search does not go on past it.
+    pub fn discover(start: &Path) -> Self {
+        let Some(dir) = start.ancestors().find(|dir| dir.join(PROJECT_CONFIG_FILE).exists()) else {
+            return Self::default();
+        };
+        let path = dir.join(PROJECT_CONFIG_FILE);
+        match Self::load(&path) {
+            Ok(mut config) => {
+                tracing::debug!(path = %path.display(), "Using project configuration");
+                if dir == start {
+                    config.root = PathBuf::new();
+                }
+                config
+            }
+            Err(e) => {
+                tracing::warn!("Ignoring {}: {e:#}", path.display());
+                Self::default()
+            }
+        }
+    }
+
+    /// Directory of raw text: `flag`, or `paths.raw`.
+    pub fn raw(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.raw))
+    }
+
+    /// Base libretto: `flag`, or `paths.base`.
+    pub fn base(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.base))
+    }
+
+    /// Timing overlay: `flag`, or `paths.timing`.
+    pub fn timing(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.timing))
+    }
+
+    /// Annotation overlay: `flag`, or `paths.annotations`.
+    pub fn annotations(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.annotations))
+    }
+
+    /// Pronunciation overlay: `flag`, or `paths.pronunciation`.
+    pub fn pronunciation(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.pronunciation))
+    }
+
+    /// Synopsis overlay: `flag`, or `paths.synopsis`.
+    pub fn synopsis(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.synopsis))
+    }
+
+    /// Audio directory: `flag`, or `paths.audio`.
+    pub fn audio(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.audio))
+    }
+
+    /// Export directory: `flag`, or `paths.out_dir`.
+    pub fn out_dir(&self, flag: Option<String>) -> Option<String> {
+        flag.or_else(|| self.path(&self.paths.out_dir))
+    }
+
+    /// Who to credit in the overlays timing commands write.
+    pub fn contributor(&self) -> Option<&str> {
+        self.contributor.name.as_deref()
+    }
+
+    /// Language of the opera's text, e.g. `it`.
+    pub fn original_language(&self) -> Option<&str> {
+        self.language.original.as_deref()
+    }
+
+    /// Language of its translation, e.g. `en`.
+    pub fn translation_language(&self) -> Option<&str> {
+        self.language.translation.as_deref()
+    }
+
+    /// What to keep of files commands overwrite.
+    pub fn backup(&self) -> Backup {
+        self.write.backup.unwrap_or_default()
+    }
+
+    /// What `timing merge` does with repeated segment references.
+    pub fn duplicates(&self) -> DuplicateStrategy {
+        self.merge.duplicates.unwrap_or_default()
+    }
+
+    fn path(&self, configured: &Option<String>) -> Option<String> {
+        configured.as_ref().map(|p| self.root.join(p).to_string_lossy().into_owned())
+    }
+}
+
+/// `value`, or an error naming the flag and the `libretto.toml` key that
+/// could have given it.
+pub fn required(value: Option<String>, flag: &str, key: &str) -> Result<String> {
+    value.with_context(|| format!("--{flag} is required (or set {key} in {PROJECT_CONFIG_FILE})"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover() {
+        let root = std::env::temp_dir().join(format!("libretto-config-{}", std::process::id()));
+        let project = root.join("figaro");
+        let nested = project.join("recordings/giulini-1959");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join(PROJECT_CONFIG_FILE), "[paths]\nbase = \"outer.libretto.json\"\n").unwrap();
+        std::fs::write(project.join(PROJECT_CONFIG_FILE), "[paths]\nbase = \"base.libretto.json\"\n").unwrap();
+
+        // The nearest file, with paths relative to its folder
+        let config = ProjectConfig::discover(&nested);
+        assert_eq!(config.root, project);
+        assert_eq!(config.base(None), Some(project.join("base.libretto.json").to_string_lossy().into_owned()));
+        // From the folder itself, paths stay relative to the working directory
+        assert_eq!(ProjectConfig::discover(&project).base(None).as_deref(), Some("base.libretto.json"));
+
+        // A malformed file gives no defaults, and the one above it is not used instead
+        std::fs::write(project.join(PROJECT_CONFIG_FILE), "[paths]\nbse = \"base.libretto.json\"\n").unwrap();
+        assert_eq!(ProjectConfig::discover(&nested).base(None), None);
+        assert!(ProjectConfig::load(&project.join(PROJECT_CONFIG_FILE)).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_flags_win() {
+        let mut config: ProjectConfig = toml::from_str(
+            "[paths]\ntiming = \"giulini.timing.json\"\nout_dir = \"export\"\n[write]\nbackup = \"none\"\n",
+        ).unwrap();
+        config.root = PathBuf::from("figaro");
+        let joined = |p: &str| Path::new("figaro").join(p).to_string_lossy().into_owned();
+        assert_eq!(config.timing(None), Some(joined("giulini.timing.json")));
+        assert_eq!(config.timing(Some("other.timing.json".to_string())).as_deref(), Some("other.timing.json"));
+        assert_eq!(config.out_dir(None), Some(joined("export")));
+        assert_eq!(config.base(None), None);
+        assert_eq!(config.backup(), Backup::None);
+
+        let error = required(config.base(None), "base", "paths.base").unwrap_err().to_string();
+        assert_eq!(error, "--base is required (or set paths.base in libretto.toml)");
+    }
+}
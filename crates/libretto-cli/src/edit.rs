@@ -0,0 +1,385 @@
+// `libretto timing edit`: fix one track's segment times in a table at
+// the terminal.
+//
+// Shows the track's segments as a table (row, start time, how it was
+// made, segment ID, first words) with one row selected, and edits it a
+// key at a time:
+//
+//     ↑ ↓ PgUp PgDn     select a row (also j / k, Home / End)
+//     + -               nudge the start half a second later / earlier
+//     > <               nudge by a tenth of a second
+//     Enter, or a digit type a new start (m:ss.s or seconds)
+//     v                 mark the row verified
+//     u                 undo the last change
+//     w                 write the overlay
+//     q                 write if changed, and quit
+//     x, Esc            quit without writing (pressed twice when there
+//                       are unwritten changes)
+//
+// A change that would move a start past a neighbour's is refused, so the
+// segments stay in their order. Changed times are recorded as tapped.
+
+use std::io::{IsTerminal, Write};
+
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use libretto_model::timing_overlay::format_time;
+use libretto_model::track_edit::{parse_start, EditError, TrackEditor};
+use libretto_model::{BaseLibretto, SegmentTime, TimingOverlay};
+
+/// Seconds `+` and `-` nudge by.
+const NUDGE: f64 = 0.5;
+
+/// Seconds `>` and `<` nudge by.
+const FINE_NUDGE: f64 = 0.1;
+
+/// Screen lines that are not table rows: the heading, a blank line, the
+/// column headings, and the status line.
+const FRAME_LINES: u16 = 4;
+
+const HELP: &str = "↑↓ select  +/- nudge 0.5s  >/< 0.1s  Enter set start  v verify  u undo  w write  q quit  x discard";
+
+/// Edit track `index` of `overlay`, calling `save` with the updated
+/// overlay on `w`, and on `q` if anything changed since. Returns whether
+/// anything was saved.
+pub fn edit_track(
+    base: &BaseLibretto,
+    overlay: &mut TimingOverlay,
+    index: usize,
+    mut save: impl FnMut(&mut TimingOverlay) -> Result<()>,
+) -> Result<bool> {
+    let mut track = overlay.track_timings[index].clone();
+    if track.segment_times.is_empty() {
+        anyhow::bail!("Track {} has no segment times to edit; run timing estimate first", track.track_id(index));
+    }
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        anyhow::bail!("timing edit needs a terminal");
+    }
+    let heading = format!("{} {}", track.track_id(index), track.track_title);
+    let mut editor = TrackEditor::new(&mut track);
+    let mut table = Table::default();
+    let mut saved = false;
+
+    let _screen = Screen::enter()?;
+    let mut out = std::io::stdout();
+    loop {
+        let (width, height) = terminal::size()?;
+        let page = usize::from(height.saturating_sub(FRAME_LINES).max(1));
+        table.scroll(page);
+        draw(&mut out, &heading, base, &editor, &table, width, height)?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let action = table.handle(key, &mut editor, page);
+        if matches!(action, Action::Write) || (matches!(action, Action::Quit) && editor.is_changed()) {
+            overlay.track_timings[index].segment_times = editor.rows().to_vec();
+            match save(overlay) {
+                Ok(()) => {
+                    editor.mark_saved();
+                    saved = true;
+                    table.message = "Written".to_string();
+                }
+                // Stay in the table, so the edits are not lost
+                Err(e) => {
+                    table.message = format!("Could not write: {e:#}");
+                    continue;
+                }
+            }
+        }
+        if matches!(action, Action::Quit | Action::Discard) {
+            break;
+        }
+    }
+    Ok(saved)
+}
+
+/// What to do after a key.
+#[derive(Debug, PartialEq)]
+enum Action {
+    Stay,
+    Write,
+    Quit,
+    Discard,
+}
+
+/// The table's state between keys.
+#[derive(Default)]
+struct Table {
+    /// Selected row, counting from 0.
+    selected: usize,
+    /// First row on screen.
+    top: usize,
+    /// The start being typed, if any.
+    input: Option<String>,
+    /// Shown on the status line until the next key.
+    message: String,
+    /// Whether the last key asked to discard unwritten changes.
+    discarding: bool,
+}
+
+impl Table {
+    /// Apply one key to the table and editor. `page` is the number of
+    /// rows on screen.
+    fn handle(&mut self, key: KeyEvent, editor: &mut TrackEditor, page: usize) -> Action {
+        let discarding = std::mem::take(&mut self.discarding);
+        self.message.clear();
+        let row = self.selected + 1;
+
+        if let Some(mut input) = self.input.take() {
+            match key.code {
+                KeyCode::Enter => match parse_start(&input) {
+                    Some(start) => self.report(editor.set_start(row, start)),
+                    None => self.message = format!("Not a time (m:ss.s or seconds): {input}"),
+                },
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.input = Some(input);
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.input = Some(input);
+                }
+                _ => self.input = Some(input),
+            }
+            return Action::Stay;
+        }
+
+        let last = editor.rows().len() - 1;
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Action::Discard,
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.selected = (self.selected + 1).min(last),
+            KeyCode::PageUp => self.selected = self.selected.saturating_sub(page),
+            KeyCode::PageDown => self.selected = (self.selected + page).min(last),
+            KeyCode::Home => self.selected = 0,
+            KeyCode::End => self.selected = last,
+            KeyCode::Char('+' | '=') => self.report(editor.nudge(row, NUDGE)),
+            KeyCode::Char('-') => self.report(editor.nudge(row, -NUDGE)),
+            KeyCode::Char('>') => self.report(editor.nudge(row, FINE_NUDGE)),
+            KeyCode::Char('<') => self.report(editor.nudge(row, -FINE_NUDGE)),
+            KeyCode::Enter => self.input = Some(String::new()),
+            KeyCode::Char(c) if c.is_ascii_digit() => self.input = Some(c.to_string()),
+            KeyCode::Char('v') => self.report(editor.verify(row)),
+            KeyCode::Char('u') => match editor.undo() {
+                Ok(row) => {
+                    self.selected = row - 1;
+                    self.message = format!("Undid row {row}");
+                }
+                Err(e) => self.message = e.to_string(),
+            },
+            KeyCode::Char('w') => return Action::Write,
+            KeyCode::Char('q') => return Action::Quit,
+            KeyCode::Char('x') | KeyCode::Esc => {
+                if !editor.is_changed() || discarding {
+                    return Action::Discard;
+                }
+                self.discarding = true;
+                self.message = "Unwritten changes: press x again to discard them".to_string();
+            }
+            _ => {}
+        }
+        Action::Stay
+    }
+
+    fn report(&mut self, result: Result<(), EditError>) {
+        if let Err(e) = result {
+            self.message = e.to_string();
+        }
+    }
+
+    /// Move the window of `page` rows on screen to keep the selected row
+    /// in it.
+    fn scroll(&mut self, page: usize) {
+        if self.selected < self.top {
+            self.top = self.selected;
+        } else if self.selected >= self.top + page {
+            self.top = self.selected + 1 - page;
+        }
+    }
+}
+
+/// The alternate screen in raw mode, given back to the shell when
+/// dropped, errors and panics included.
+struct Screen;
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode()?;
+        let screen = Screen;
+        execute!(std::io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(std::io::stdout(), cursor::Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(
+    out: &mut impl Write,
+    heading: &str,
+    base: &BaseLibretto,
+    editor: &TrackEditor,
+    table: &Table,
+    width: u16,
+    height: u16,
+) -> Result<()> {
+    let fit = |line: &str| line.chars().take(usize::from(width)).collect::<String>();
+    let changed = if editor.is_changed() { "  (changed)" } else { "" };
+    queue!(out, Clear(ClearType::All), cursor::MoveTo(0, 0), Print(fit(&format!("{heading}{changed}"))))?;
+    queue!(out, cursor::MoveTo(0, 2), Print(fit(&format!("{:>4} {:>8} {:<9} {:<20} WORDS", "ROW", "START", "SOURCE", "SEGMENT"))))?;
+
+    let page = usize::from(height.saturating_sub(FRAME_LINES).max(1));
+    for (y, (i, time)) in (3..).zip(editor.rows().iter().enumerate().skip(table.top).take(page)) {
+        queue!(out, cursor::MoveTo(0, y))?;
+        if i == table.selected {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(out, Print(fit(&row_text(i + 1, time, base))), SetAttribute(Attribute::Reset))?;
+    }
+
+    let status = match &table.input {
+        Some(input) => format!("Start for row {} (m:ss.s or seconds): {input}_", table.selected + 1),
+        None if !table.message.is_empty() => table.message.clone(),
+        None => HELP.to_string(),
+    };
+    queue!(out, cursor::MoveTo(0, height.saturating_sub(1)), Print(fit(&status)))?;
+    out.flush()?;
+    Ok(())
+}
+
+fn row_text(row: usize, time: &SegmentTime, base: &BaseLibretto) -> String {
+    let source = time.source.map(|s| format!("{s:?}").to_lowercase()).unwrap_or_else(|| "-".to_string());
+    let words = base.find_segment(&time.segment_id)
+        .and_then(|s| s.text.as_deref().or(s.direction.as_deref()))
+        .map(first_words)
+        .unwrap_or_default();
+    format!("{row:>4} {:>8} {source:<9} {:<20} {words}", format_time(time.start), time.segment_id)
+}
+
+/// The start of `text` on one line, cut at a word near 40 characters.
+fn first_words(text: &str) -> String {
+    let mut out = String::new();
+    for word in text.split_whitespace() {
+        if out.chars().count() + word.chars().count() > 40 {
+            out.push('…');
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libretto_model::{TimingSource, TrackTiming};
+
+    fn track() -> TrackTiming {
+        let time = |id: &str, start: f64| SegmentTime {
+            segment_id: id.to_string(),
+            start,
+            end: None,
+            source: Some(TimingSource::Estimated),
+        };
+        TrackTiming {
+            track_title: "Non più andrai".to_string(),
+            duration_seconds: Some(200.0),
+            segment_times: vec![time("no-9-aria-001", 3.0), time("no-9-aria-002", 10.0), time("no-9-aria-003", 20.0)],
+            ..Default::default()
+        }
+    }
+
+    fn press(table: &mut Table, editor: &mut TrackEditor, keys: &[KeyCode]) -> Action {
+        let mut action = Action::Stay;
+        for &code in keys {
+            action = table.handle(KeyEvent::new(code, KeyModifiers::NONE), editor, 2);
+        }
+        action
+    }
+
+    #[test]
+    fn test_keys_edit_selected_row() {
+        let mut track = track();
+        let mut editor = TrackEditor::new(&mut track);
+        let mut table = Table::default();
+
+        press(&mut table, &mut editor, &[KeyCode::Down, KeyCode::Char('+'), KeyCode::Char('<')]);
+        assert_eq!(editor.rows()[1].start, 10.4);
+        assert_eq!(editor.rows()[1].source, Some(TimingSource::Tapped));
+
+        // Typing a start, with a correction
+        press(&mut table, &mut editor, &[KeyCode::End, KeyCode::Char('1'), KeyCode::Char('9')]);
+        assert_eq!(table.input.as_deref(), Some("19"));
+        let keys = [KeyCode::Backspace, KeyCode::Char('8'), KeyCode::Char('.'), KeyCode::Char('5'), KeyCode::Enter];
+        press(&mut table, &mut editor, &keys);
+        assert_eq!(table.input, None);
+        assert_eq!(editor.rows()[2].start, 18.5);
+
+        // A refused edit is reported and changes nothing
+        press(&mut table, &mut editor, &[KeyCode::Enter, KeyCode::Char('2'), KeyCode::Enter]);
+        assert!(table.message.contains("before no-9-aria-002's start"), "{}", table.message);
+        assert_eq!(editor.rows()[2].start, 18.5);
+
+        press(&mut table, &mut editor, &[KeyCode::Home, KeyCode::Char('v'), KeyCode::Char('u'), KeyCode::Char('u')]);
+        assert_eq!(table.selected, 2);
+        assert_eq!(editor.rows()[0].source, Some(TimingSource::Estimated));
+        assert_eq!(editor.rows()[2].start, 20.0);
+    }
+
+    #[test]
+    fn test_quit_keys() {
+        let mut track = track();
+        let mut editor = TrackEditor::new(&mut track);
+        let mut table = Table::default();
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Char('x')]), Action::Discard);
+
+        // Unwritten changes need a second x, and any other key in between cancels it
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Char('-'), KeyCode::Char('x')]), Action::Stay);
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Down, KeyCode::Esc]), Action::Stay);
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Esc]), Action::Discard);
+
+        // Undoing back to the saved rows leaves nothing to discard
+        press(&mut table, &mut editor, &[KeyCode::Char('u')]);
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Char('x')]), Action::Discard);
+
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Char('w')]), Action::Write);
+        assert_eq!(press(&mut table, &mut editor, &[KeyCode::Char('q')]), Action::Quit);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(table.handle(ctrl_c, &mut editor, 2), Action::Discard);
+    }
+
+    #[test]
+    fn test_scroll_follows_selection() {
+        let mut track = track();
+        let mut editor = TrackEditor::new(&mut track);
+        let mut table = Table::default();
+        press(&mut table, &mut editor, &[KeyCode::PageDown]);
+        table.scroll(2);
+        assert_eq!((table.selected, table.top), (2, 1));
+        press(&mut table, &mut editor, &[KeyCode::Up, KeyCode::Up]);
+        table.scroll(2);
+        assert_eq!((table.selected, table.top), (0, 0));
+    }
+
+    #[test]
+    fn test_first_words() {
+        assert_eq!(first_words("Non più andrai,\nfarfallone amoroso"), "Non più andrai, farfallone amoroso");
+        let long = "Notte e giorno faticar per chi nulla sa gradir, piova e vento sopportar";
+        assert_eq!(first_words(long), "Notte e giorno faticar per chi nulla sa…");
+    }
+}
@@ -0,0 +1,224 @@
+// `libretto init`: start an opera project in the conventional layout.
+//
+//     mozart/le-nozze-di-figaro/
+//       libretto.toml               the paths below, for the other commands
+//       acquired/                   acquire output, parse input
+//       base.libretto.json          placeholder until `libretto parse`
+//       recordings/giulini-1959/
+//         timing.json               placeholder until `timing init`
+//         audio/                    the recording's audio files
+//
+// Files already there are left as they are, so running it again with
+// another `--recording` adds that recording to an existing project.
+
+use std::path::Path;
+
+use anyhow::Result;
+use libretto_model::{BaseLibretto, OperaMetadata, TimingOverlay};
+
+use crate::config::PROJECT_CONFIG_FILE;
+use crate::save;
+
+const RAW_DIR: &str = "acquired";
+const BASE_FILE: &str = "base.libretto.json";
+const RECORDINGS_DIR: &str = "recordings";
+const TIMING_FILE: &str = "timing.json";
+const AUDIO_DIR: &str = "audio";
+
+/// What the project's placeholder files and `libretto.toml` say about the
+/// opera, beyond its identifier.
+pub struct Details {
+    pub title: Option<String>,
+    pub composer: Option<String>,
+    pub language: Option<String>,
+    pub translation: Option<String>,
+}
+
+/// Lay out the project for `opera` (`<composer>/<opera>`) in `dir`, with a
+/// recording folder for `recording` if given.
+pub fn init_project(opera: &str, dir: &Path, recording: Option<&str>, details: &Details) -> Result<()> {
+    let Some((composer, work)) = opera.split_once('/').filter(|(c, w)| is_name(c) && is_name(w)) else {
+        anyhow::bail!("Opera identifier must be <composer>/<opera>, e.g. mozart/le-nozze-di-figaro (got '{opera}')");
+    };
+    if let Some(name) = recording.filter(|name| name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.')) {
+        anyhow::bail!("Recording name must be one folder name, e.g. giulini-1959 (got '{name}')");
+    }
+
+    save::create_dir_all(dir.join(RAW_DIR))?;
+
+    let base = BaseLibretto::new(OperaMetadata {
+        title: details.title.clone().unwrap_or_else(|| from_slug(work)),
+        composer: details.composer.clone().unwrap_or_else(|| from_slug(composer)),
+        librettist: None,
+        language: details.language.clone().unwrap_or_default(),
+        translation_language: details.translation.clone(),
+        year: None,
+    });
+    write_new(&dir.join(BASE_FILE), &serde_json::to_string_pretty(&base)?)?;
+
+    if let Some(name) = recording {
+        let recording_dir = dir.join(RECORDINGS_DIR).join(name);
+        save::create_dir_all(recording_dir.join(AUDIO_DIR))?;
+        // The reference `timing init` will write in its place
+        let timing = recording_dir.join(TIMING_FILE);
+        let overlay = TimingOverlay::new(&libretto_model::library::reference_from(&timing, &dir.join(BASE_FILE)));
+        write_new(&timing, &serde_json::to_string_pretty(&overlay)?)?;
+    }
+
+    let config = dir.join(PROJECT_CONFIG_FILE);
+    if !write_new(&config, &project_config(opera, recording, details))? {
+        if let Some(name) = recording {
+            tracing::info!(
+                path = %config.display(),
+                "To time {name} by default, set paths.timing to {RECORDINGS_DIR}/{name}/{TIMING_FILE}"
+            );
+        }
+    }
+    tracing::info!(
+        dir = %dir.display(),
+        "Next: libretto acquire --opera {opera}, then libretto parse, then libretto timing init"
+    );
+    Ok(())
+}
+
+/// Write `contents` to `path` unless a file is there already, returning
+/// whether it was written.
+fn write_new(path: &Path, contents: &str) -> Result<bool> {
+    if path.exists() {
+        tracing::info!(path = %path.display(), "Keeping existing file");
+        return Ok(false);
+    }
+    save::write(path, contents)?;
+    if !save::dry_run() {
+        tracing::info!(path = %path.display(), "Created file");
+    }
+    Ok(true)
+}
+
+/// The `libretto.toml` template: the project's paths, and the settings
+/// worth filling in, commented out where not given.
+fn project_config(opera: &str, recording: Option<&str>, details: &Details) -> String {
+    let (composer, work) = opera.split_once('/').unwrap_or((opera, opera));
+    let setting = |key: &str, value: &Option<String>, example: &str| match value {
+        Some(value) => format!("{key} = {}", quoted(value)),
+        None => format!("# {key} = {}", quoted(example)),
+    };
+    let recording_paths = match recording {
+        Some(name) => {
+            let dir = format!("{RECORDINGS_DIR}/{name}");
+            [
+                format!("timing = {}", quoted(&format!("{dir}/{TIMING_FILE}"))),
+                format!("audio = {}", quoted(&format!("{dir}/{AUDIO_DIR}"))),
+                format!("out_dir = {}", quoted(&format!("{dir}/export"))),
+            ]
+        }
+        None => [
+            format!("# timing = \"{RECORDINGS_DIR}/<name>/{TIMING_FILE}\"   # libretto init {opera} --recording <name>"),
+            format!("# audio = \"{RECORDINGS_DIR}/<name>/{AUDIO_DIR}\""),
+            "out_dir = \"export\"".to_string(),
+        ],
+    };
+    let lines = [
+        format!("# Opera project {opera}. Paths are relative to this file; a flag"),
+        "# given on the command line always wins.".to_string(),
+        String::new(),
+        "[paths]".to_string(),
+        format!("raw = \"{RAW_DIR}\""),
+        format!("base = \"{BASE_FILE}\""),
+    ]
+    .into_iter()
+    .chain(recording_paths)
+    .chain([
+        "# annotations = \"annotations.json\"".to_string(),
+        "# pronunciation = \"pronunciation.json\"".to_string(),
+        "# synopsis = \"synopsis.json\"".to_string(),
+        String::new(),
+        "[contributor]                      # credited in timing overlays".to_string(),
+        setting("name", &None, "Your Name"),
+        String::new(),
+        "[language]".to_string(),
+        setting("original", &details.language, "it"),
+        setting("translation", &details.translation, "en"),
+        String::new(),
+        "[parse]                            # set on the parsed libretto".to_string(),
+        setting("title", &details.title, &from_slug(work)),
+        setting("composer", &details.composer, &from_slug(composer)),
+    ]);
+    lines.map(|line| line + "\n").collect()
+}
+
+fn quoted(value: &str) -> String {
+    toml::Value::from(value).to_string()
+}
+
+/// A lowercase, hyphenated folder name: `le-nozze-di-figaro`.
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with(['-', '.'])
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+}
+
+/// A first guess at a name from its folder name: `le-nozze-di-figaro`
+/// gives `Le nozze di figaro`.
+fn from_slug(slug: &str) -> String {
+    let words = slug.replace('-', " ");
+    let mut chars = words.chars();
+    chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details() -> Details {
+        Details { title: None, composer: None, language: Some("it".to_string()), translation: None }
+    }
+
+    #[test]
+    fn test_is_name() {
+        assert!(is_name("le-nozze-di-figaro"));
+        assert!(is_name("k.492"));
+        assert!(!is_name(""));
+        assert!(!is_name("-figaro"));
+        assert!(!is_name(".figaro"));
+        assert!(!is_name("Le-Nozze"));
+        assert!(!is_name("le nozze"));
+    }
+
+    #[test]
+    fn test_bad_names() {
+        let dir = std::env::temp_dir().join(format!("libretto-init-names-{}", std::process::id()));
+        assert!(init_project("mozart", &dir, None, &details()).is_err());
+        assert!(init_project("mozart/Figaro", &dir, None, &details()).is_err());
+        for recording in ["", "giulini/1959", "..", ".hidden", "giulini\\1959"] {
+            let error = init_project("mozart/le-nozze-di-figaro", &dir, Some(recording), &details()).unwrap_err();
+            assert!(error.to_string().starts_with("Recording name must be one folder name"), "{recording}: {error}");
+        }
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_init_again() {
+        let dir = std::env::temp_dir().join(format!("libretto-init-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        init_project("mozart/le-nozze-di-figaro", &dir, Some("giulini-1959"), &details()).unwrap();
+        let config = std::fs::read_to_string(dir.join(PROJECT_CONFIG_FILE)).unwrap();
+        assert!(config.contains("timing = \"recordings/giulini-1959/timing.json\"\n"));
+        assert!(config.contains("original = \"it\"\n"));
+        let timing = dir.join("recordings/giulini-1959/timing.json");
+        let overlay: TimingOverlay = serde_json::from_str(&std::fs::read_to_string(&timing).unwrap()).unwrap();
+        assert_eq!(overlay.base_libretto, "../../base.libretto.json");
+
+        // Existing files are kept; a second recording gets its own folder
+        std::fs::write(dir.join(BASE_FILE), "parsed").unwrap();
+        init_project("mozart/le-nozze-di-figaro", &dir, Some("karajan-1950"), &details()).unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join(BASE_FILE)).unwrap(), "parsed");
+        assert_eq!(std::fs::read_to_string(dir.join(PROJECT_CONFIG_FILE)).unwrap(), config);
+        assert!(dir.join("recordings/karajan-1950/timing.json").is_file());
+        assert!(dir.join("recordings/karajan-1950/audio").is_dir());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
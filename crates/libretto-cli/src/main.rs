@@ -1,5 +1,18 @@
-use anyhow::Result;
-use clap::{Parser, Subcommand};
+use anyhow::{Context, Result};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use tracing_subscriber::prelude::*;
+
+mod config;
+mod edit;
+mod init;
+mod save;
+mod porcelain;
+mod progress;
+#[cfg(feature = "serve")]
+mod serve;
+mod show;
+
+use config::{required, ProjectConfig};
 
 #[derive(Parser)]
 #[command(name = "libretto")]
@@ -14,10 +27,39 @@ struct Cli {
     #[arg(long, global = true)]
     utc: bool,
 
+    /// Also append logs to this file, without colours, at the same level as stderr
+    #[arg(long, global = true)]
+    log_file: Option<String>,
+
+    /// Format of the --log-file lines: text as on stderr, or one JSON object per event
+    #[arg(long, global = true, value_enum, default_value = "text", requires = "log_file")]
+    log_format: LogFormat,
+
+    /// Print one JSON object on stdout when the command ends: its result and warning events, and any report it
+    /// would print, in place of tables (logs still go to stderr)
+    #[arg(long, global = true)]
+    porcelain: bool,
+
+    /// Report what each file written would become, as new, unchanged, or the lines that would
+    /// change, and write nothing
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Project configuration of default paths and settings (default: nearest libretto.toml at or
+    /// above the current directory)
+    #[arg(long)]
+    config: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum LogFormat {
+    Text,
+    Json,
+}
+
 #[derive(Clone, clap::ValueEnum)]
 enum LogLevel {
     Error,
@@ -29,6 +71,37 @@ enum LogLevel {
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Start an opera project: acquired/, a placeholder base libretto, a recording's placeholder timing
+    /// overlay, and a libretto.toml naming them (existing files are kept)
+    Init {
+        /// Opera identifier (e.g., "mozart/le-nozze-di-figaro")
+        opera: String,
+
+        /// Project directory (default: the opera identifier, under the current directory)
+        #[arg(long)]
+        dir: Option<String>,
+
+        /// Recording to set up under recordings/ (e.g., "giulini-1959")
+        #[arg(short, long)]
+        recording: Option<String>,
+
+        /// Opera title (default: from the identifier)
+        #[arg(long)]
+        title: Option<String>,
+
+        /// Composer (default: from the identifier)
+        #[arg(long)]
+        composer: Option<String>,
+
+        /// Language of the original text (e.g., "it")
+        #[arg(long)]
+        language: Option<String>,
+
+        /// Language of the translation (e.g., "en")
+        #[arg(long)]
+        translation: Option<String>,
+    },
+
     /// Acquire raw libretto text from online sources
     Acquire {
         /// Source site to fetch from
@@ -40,64 +113,395 @@ enum Commands {
         opera: String,
 
         /// Languages: "it,en" (opera-arias, one per page), "en+it" (murashev, side-by-side), or "en"/"it" (single language)
-        #[arg(short, long, default_value = "it,en")]
-        lang: String,
+        /// (default: from [language] in libretto.toml, else "it,en")
+        #[arg(short, long)]
+        lang: Option<String>,
 
-        /// Output directory for raw text files
-        #[arg(short = 'O', long, default_value = ".")]
-        output_dir: String,
+        /// Output directory for raw text files (default: paths.raw in libretto.toml, else .)
+        #[arg(short = 'O', long)]
+        output_dir: Option<String>,
     },
 
     /// Parse raw libretto text into structured base libretto JSON
     Parse {
         /// Input directory containing raw text files (italian.txt, english.txt)
+        /// (default: paths.raw in libretto.toml)
         #[arg(short, long)]
-        input: String,
+        input: Option<String>,
 
-        /// Output file path for the base libretto JSON
-        #[arg(short, long, default_value = "base.libretto.json")]
-        output: String,
+        /// Output file path for the base libretto JSON (default: paths.base in libretto.toml, else base.libretto.json)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
-    /// Validate a base libretto or timing overlay file
+    /// Validate a base libretto, timing overlay, annotation overlay, or interchange file
     Validate {
-        /// Path to the file to validate (.libretto.json or .timing.json)
+        /// Path to the file to validate (.libretto.json, .timing.json, .annotations.json, .pronunciation.json,
+        /// .synopsis.json, or .timed.libretto.json/.cbor/.msgpack)
         file: String,
 
-        /// For timing overlays: path to the base libretto to check segment references against
+        /// For overlays: path to the base libretto to check segment references against
+        #[arg(short, long, conflicts_with = "recursive")]
+        base: Option<String>,
+
+        /// Treat FILE as a directory and validate every document under it,
+        /// checking each overlay against its declared base libretto
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// With --recursive: files to validate at once (default: one per CPU)
+        #[arg(short, long, requires = "recursive")]
+        jobs: Option<usize>,
+
+        /// Treat warnings as failures
+        #[arg(long)]
+        strict: bool,
+
+        /// Lint configuration (default: nearest libretto-lint.toml at or above the current directory)
+        #[arg(long)]
+        config: Option<String>,
+
+        /// Output format: log lines, or a JSON report on stdout
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+
+        /// For timing overlays: directory of the recording's audio files to
+        /// check track durations against
+        #[arg(long, conflicts_with = "recursive")]
+        audio: Option<String>,
+
+        /// Allowed difference between overlay and audio track durations, in seconds
+        #[arg(long, default_value_t = libretto_validate::audio::DEFAULT_AUDIO_TOLERANCE_SECONDS)]
+        audio_tolerance: f64,
+
+        /// For timing overlays: first rewrite the file fixing mechanical problems
+        /// (segment order, exact duplicates, float noise, number ID order)
+        #[arg(long, conflicts_with = "recursive")]
+        fix: bool,
+
+        /// Leave out list entries that fail to load (a malformed track or segment time), reporting
+        /// each with its JSON path, and validate the rest instead of stopping at the first
+        #[arg(long, conflicts_with_all = ["recursive", "fix"])]
+        lenient: bool,
+    },
+
+    /// Split an interchange libretto back into a base libretto + timing overlay
+    Decompile {
+        /// Path to the interchange libretto (.json, .cbor, or .msgpack by extension)
+        input: String,
+
+        /// Output path for the reconstructed base libretto JSON
+        #[arg(long, default_value = "base.libretto.json")]
+        base_output: String,
+
+        /// Output path for the reconstructed timing overlay JSON
+        #[arg(long, default_value = "timing.overlay.json")]
+        timing_output: String,
+    },
+
+    /// Write an interchange libretto as per-track lyric or subtitle files, or bundle the interchange
+    /// librettos of several recordings into one
+    Export {
+        /// Path to the interchange libretto (.json, .cbor, or .msgpack by extension),
+        /// the timing overlay for --format cue, or the base libretto for --format html, md, latex, tei, csv, surtitles, and qlab
+        #[arg(required_unless_present = "bundle")]
+        input: Option<String>,
+
+        /// Write one <name>.bundle.json holding the text of these interchange librettos once and each one's
+        /// times, for clients that switch recording; each is named after its file name up to the first dot
+        #[arg(long, num_args = 1.., value_name = "INTERCHANGE", conflicts_with = "input")]
+        bundle: Vec<String>,
+
+        /// Format of the exported files
+        #[arg(long, value_enum, default_value = "lrc")]
+        format: ExportFileFormat,
+
+        /// Directory to write one file per track (per act for chapter formats) into (created if missing)
+        /// (default: paths.out_dir in libretto.toml, else .)
+        #[arg(long)]
+        out_dir: Option<String>,
+
+        /// Which text to show: the original, the translation, or both
+        #[arg(long, value_enum, default_value = "original")]
+        layout: ExportLayout,
+
+        /// Follow each line with its translation: the same as --layout both
+        #[arg(long, conflicts_with = "layout")]
+        translation: bool,
+
+        /// WebVTT: give each character a CSS class and color in a STYLE block
+        #[arg(long)]
+        voice_classes: bool,
+
+        /// LRC and WebVTT: time each line of a segment, spreading the segment over its lines by word count
+        #[arg(long)]
+        per_line: bool,
+
+        /// ASS: TOML file of character colors ([colors] FIGARO = "#ffd75e"); others are derived from the name
+        #[arg(long)]
+        ass_config: Option<String>,
+
+        /// Chapter formats: start a chapter at each musical number or each ensemble group
+        #[arg(long, value_enum, default_value = "number")]
+        chapters: ChapterSplit,
+
+        /// Cue sheets: base libretto whose number labels are listed under each track
+        #[arg(long)]
+        base: Option<String>,
+
+        /// Cue sheets: audio file each disc's sheet refers to; {disc} is replaced by the disc number
+        #[arg(long, default_value = "disc{disc}.flac")]
+        cue_file: String,
+
+        /// HTML, CSV, and surtitles: timing overlay whose track starts and segment times are included
+        #[arg(long)]
+        timing: Option<String>,
+
+        /// LaTeX: template with {{title}}, {{author}}, {{cast}}, {{body}}, ... placeholders (default: built-in article)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Surtitles: start a slide at each sung segment, or at each run of one character's segments
+        #[arg(long, value_enum, default_value = "segment")]
+        slides: SurtitleSplit,
+
+        /// Surtitles: wrap lines longer than this many characters
+        #[arg(long, default_value = "40")]
+        max_line_chars: usize,
+
+        /// Surtitles: most lines of each language on one slide; longer text continues on the next
+        #[arg(long, default_value = "2")]
+        max_lines: usize,
+
+        /// roon-rd: directory name for the recording under data/ (default: the input file name up to its first dot);
+        /// --bundle: file name before .bundle.json (default: libretto)
+        #[arg(long)]
+        name: Option<String>,
+    },
+
+    /// Apply a spreadsheet of segment edits back to a base libretto and timing overlay
+    Import {
+        /// File to import
+        input: String,
+
+        /// Format of the imported file
+        #[arg(long, value_enum, default_value = "csv")]
+        format: ImportFileFormat,
+
+        /// Base libretto that text, character, and translation edits go to (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Timing overlay that start time edits go to
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Output path for the updated base libretto (defaults to updating --base in place)
+        #[arg(long)]
+        base_output: Option<String>,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(long)]
+        timing_output: Option<String>,
+    },
+
+    /// Build a segment ID cross-walk map between two base librettos of the same opera
+    Map {
+        /// Base libretto whose segment IDs are mapped from
+        #[arg(short, long)]
+        source: String,
+
+        /// Base libretto whose segment IDs are mapped to
+        #[arg(short, long)]
+        target: String,
+
+        /// Output path for the segment map JSON
+        #[arg(short, long, default_value = "segment.map.json")]
+        output: String,
+    },
+
+    /// Give a base libretto's segments new IDs under a scheme, rewriting the overlays that
+    /// reference them to match
+    Reid {
+        /// Path to the base libretto JSON, updated in place unless --output (default: paths.base in
+        /// libretto.toml)
         #[arg(short, long)]
         base: Option<String>,
+
+        /// Timing, annotation and pronunciation overlays to rewrite in place, pointed at --output
+        /// when given (default: paths.timing, paths.annotations and paths.pronunciation in
+        /// libretto.toml)
+        #[arg(long, num_args = 1..)]
+        overlays: Vec<String>,
+
+        /// How to name segments
+        #[arg(long, value_enum, default_value = "content")]
+        scheme: IdSchemeArg,
+
+        /// Output path for the map from old segment IDs to new, for `timing retarget` of overlays
+        /// not rewritten here
+        #[arg(long, default_value = "reid.map.json")]
+        map: String,
+
+        /// Output path for the updated base libretto
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
     /// Timing overlay tools: init, validate, merge
     Timing {
+        /// Don't credit the person running the command in the overlays it writes
+        #[arg(long, global = true)]
+        no_contributor: bool,
+
         #[command(subcommand)]
         action: TimingAction,
     },
+
+    /// Report the size of a base libretto per act, number and character, with its translation coverage,
+    /// the recording time a timing overlay gives each act and number, or the gaps and overlaps
+    /// between an interchange document's segments per track
+    Stats {
+        /// Path to the base libretto, timing overlay, or interchange document
+        file: String,
+
+        /// For timing overlays: the base libretto whose acts and numbers times are totaled by
+        /// (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Output format: a table, or JSON on stdout
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+
+    /// Print one number, segment, or recording track of a base libretto readably, with start times
+    /// from a timing overlay
+    #[command(group(clap::ArgGroup::new("unit").required(true)))]
+    Show {
+        /// Path to the base libretto (default: paths.base in libretto.toml)
+        file: Option<String>,
+
+        /// Musical number to show, e.g. no-9-aria
+        #[arg(long, group = "unit")]
+        number: Option<String>,
+
+        /// Segment to show, under its number's heading
+        #[arg(long, group = "unit")]
+        segment: Option<String>,
+
+        /// Recording track to show, e.g. d1-t12: the segments timed in it, by number (needs a timing overlay)
+        #[arg(long, group = "unit")]
+        track: Option<String>,
+
+        /// Timing overlay to show start times from (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+    },
+
+    /// Library catalog tools: index
+    Library {
+        #[command(subcommand)]
+        action: LibraryAction,
+    },
+
+    /// Run acquire, parse, resolve, estimate and merge on a bundled miniature opera and compare each
+    /// output with its golden file
+    Selftest {
+        /// Also write each output into this directory, under its golden file's name
+        #[arg(long)]
+        write: Option<String>,
+    },
+
+    /// Serve a library over HTTP: its operas and recordings, interchange documents, and the segment
+    /// playing at a time in a track
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Library root directory (its library.json is used if present, otherwise the tree is scanned)
+        #[arg(long)]
+        root: String,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        listen: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum LibraryAction {
+    /// Scan a library directory tree and write its library.json index
+    Index {
+        /// Library root directory
+        root: String,
+
+        /// Output path for the index (defaults to <root>/library.json)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
 enum TimingAction {
-    /// Generate a scaffold timing overlay from a base libretto
+    /// Generate a scaffold timing overlay from a base libretto, or from a recording's cue sheets
+    /// or track listing
     Init {
-        /// Path to the base libretto JSON
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
         #[arg(short, long)]
-        base: String,
+        base: Option<String>,
 
-        /// Output path for the timing overlay JSON
-        #[arg(short, long, default_value = "timing.overlay.json")]
-        output: String,
+        /// CD cue sheet giving the recording's tracks and their exact durations
+        /// (repeat for each disc, in order)
+        #[arg(long)]
+        cue: Vec<String>,
+
+        /// Text file listing the recording's tracks, one per line ("1-05 Title 4:32"),
+        /// as pasted from a streaming service or booklet
+        #[arg(long, conflicts_with = "cue")]
+        tracks: Option<String>,
+
+        /// Put each recitative on the same track as the number after it, as recordings usually do
+        #[arg(long, conflicts_with_all = ["cue", "tracks"])]
+        group_recits: bool,
+
+        /// Output path for the timing overlay JSON (default: paths.timing in libretto.toml, else timing.overlay.json)
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
     /// Resolve track title anchors to segment IDs (populates start_segment_id)
     Resolve {
-        /// Path to the base libretto JSON
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
         #[arg(short, long)]
-        base: String,
+        base: Option<String>,
 
-        /// Path to the timing overlay JSON
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
         #[arg(short, long)]
-        timing: String,
+        timing: Option<String>,
+
+        /// Minimum similarity (0 to 1) for matching an anchor that nearly
+        /// matches a segment's opening words, tried when nothing matches
+        /// outright
+        #[arg(long, default_value_t = 0.8)]
+        fuzzy_threshold: f64,
+
+        /// Do not fall back to near matches
+        #[arg(long, conflicts_with = "fuzzy_threshold")]
+        no_fuzzy: bool,
+
+        /// Write a per-track report of anchors, matches and unmatched
+        /// anchors to this file: JSON for a `.json` path, Markdown otherwise
+        #[arg(long)]
+        report: Option<String>,
+
+        /// Ask which segment a track starts at when its anchor matches
+        /// several segments equally well or none
+        #[arg(long)]
+        interactive: bool,
+
+        /// Resolve again every track's start segment except those marked
+        /// `resolved_by: manual`
+        #[arg(long)]
+        force: bool,
 
         /// Output path for the resolved timing overlay
         #[arg(short, long, default_value = "resolved.timing.json")]
@@ -106,80 +510,605 @@ enum TimingAction {
 
     /// Estimate segment timings from track durations and word counts
     Estimate {
-        /// Path to the base libretto JSON
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
         #[arg(short, long)]
-        base: String,
+        base: Option<String>,
 
-        /// Path to the timing overlay JSON (must have duration_seconds on tracks)
+        /// Path to the timing overlay JSON (must have duration_seconds on tracks; existing
+        /// non-estimated segment times are kept and the estimates fitted between them) (default: paths.timing in libretto.toml)
         #[arg(short, long)]
-        timing: String,
+        timing: Option<String>,
+
+        /// What each segment's share of its track's time is proportional to
+        #[arg(long, value_enum, default_value = "words")]
+        weight: EstimateWeight,
+
+        /// TOML pacing table overriding the relative time per word for number and segment types
+        #[arg(long)]
+        pacing: Option<String>,
+
+        /// Seconds without text at the start of every track (orchestral introductions);
+        /// saved in the overlay, where single tracks can override it
+        #[arg(long)]
+        head_padding: Option<f64>,
+
+        /// Seconds without text at the end of every track (applause); saved like --head-padding
+        #[arg(long)]
+        tail_padding: Option<f64>,
+
+        /// Move estimated starts to where sound resumes after a silence (requires --audio)
+        #[arg(long)]
+        snap_to_silence: bool,
+
+        /// Directory of the recording's audio files, for --snap-to-silence (default: paths.audio in libretto.toml)
+        #[arg(long)]
+        audio: Option<String>,
+
+        /// How far an estimated start may move to reach a silence, in seconds
+        #[arg(long, default_value_t = libretto_validate::silence::DEFAULT_SNAP_WINDOW_SECONDS)]
+        snap_window: f64,
+
+        /// Write a per-segment CSV (weight, start, implied duration, words/sec) for spotting outliers
+        #[arg(long)]
+        report: Option<String>,
 
         /// Output path for the updated timing overlay with estimated segment_times
         #[arg(short, long, default_value = "estimated.timing.json")]
         output: String,
     },
 
+    /// Fill one track's segment_times from audio editor labels, LRC lyrics, or subtitles
+    Import {
+        /// File to import
+        input: String,
+
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Track to fill, e.g. d1-t2 (or t2 without a disc number)
+        #[arg(long)]
+        track: String,
+
+        /// Format of the imported file
+        #[arg(long, value_enum, default_value = "audacity")]
+        format: ImportFormat,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Write one track's segment_times as labels for review in an audio editor
+    Export {
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Track to export, e.g. d1-t2 (or t2 without a disc number)
+        #[arg(long)]
+        track: String,
+
+        /// Format of the exported file
+        #[arg(long, value_enum, default_value = "audacity")]
+        format: ExportFormat,
+
+        /// Output path (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Set track duration_seconds from the recording's audio files
+    Durations {
+        /// Directory of audio files (searched recursively; matched by disc/track tags, then title)
+        /// (default: paths.audio in libretto.toml)
+        #[arg(long)]
+        audio: Option<String>,
+
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Move segment times earlier or later, e.g. to match another mastering of the recording
+    Shift {
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Track to shift, e.g. d1-t5 (or t5 without a disc number)
+        #[arg(long, required_unless_present = "all_tracks", conflicts_with = "all_tracks")]
+        track: Option<String>,
+
+        /// Shift every track
+        #[arg(long)]
+        all_tracks: bool,
+
+        /// Seconds to add to every start and end (negative moves earlier, but not before 0)
+        #[arg(long, allow_negative_numbers = true)]
+        by: f64,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Stretch or compress segment times, e.g. for a mastering that runs at a slightly different speed
+    Scale {
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Track to scale, e.g. d1-t5 (or t5 without a disc number)
+        #[arg(long, required_unless_present = "all_tracks", conflicts_with = "all_tracks")]
+        track: Option<String>,
+
+        /// Scale every track
+        #[arg(long)]
+        all_tracks: bool,
+
+        /// Factor to multiply every start and end by (e.g. 0.98)
+        #[arg(long)]
+        factor: f64,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Fix one track's segment times in a table at the terminal: set, nudge, or verify
+    /// each start, keeping the segments in order
+    Edit {
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Track to edit, e.g. d1-t7 (or t7 without a disc number)
+        #[arg(long)]
+        track: String,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Time segments by running a forced aligner on each track's audio and text
+    AlignAudio {
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Path to the timing overlay JSON (tracks must have number_ids) (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Directory of audio files (searched recursively; matched by disc/track tags, then title)
+        /// (default: paths.audio in libretto.toml)
+        #[arg(long)]
+        audio: Option<String>,
+
+        /// Track to align, e.g. d1-t2 (defaults to every track with number_ids)
+        #[arg(long)]
+        track: Option<String>,
+
+        /// Aligner command; {audio}, {text}, {output} and {language} are filled in per track
+        #[arg(long, default_value = libretto_validate::align::DEFAULT_ALIGNER)]
+        aligner: String,
+
+        /// Language code passed to the aligner (defaults to the opera's language)
+        #[arg(long)]
+        language: Option<String>,
+
+        /// Warn about segments aligned with less confidence than this
+        #[arg(long, default_value_t = libretto_validate::align::DEFAULT_MIN_CONFIDENCE)]
+        min_confidence: f64,
+
+        /// Write each aligned segment's times and confidence to this JSON file
+        #[arg(long)]
+        report: Option<String>,
+
+        /// Output path for the updated timing overlay (defaults to updating --timing in place)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Combine two timing overlays of the same recording, preferring tapped and verified times over estimates
+    Combine {
+        /// Timing overlay whose times win when both are equally trusted
+        first: String,
+
+        /// Timing overlay to combine into it
+        second: String,
+
+        /// Report times for the same segment further apart than this many seconds as conflicts
+        #[arg(long, default_value_t = libretto_model::combine::DEFAULT_CONFLICT_TOLERANCE_SECONDS)]
+        tolerance: f64,
+
+        /// Output path for the combined timing overlay
+        #[arg(short, long, default_value = "combined.timing.json")]
+        output: String,
+    },
+
+    /// Report how far a timing overlay has got, per track and per act
+    Status {
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Output format: tables, or JSON on stdout
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+
+    /// Compare two recordings' timing overlays number by number
+    Compare {
+        /// Timing overlay of the first recording
+        first: String,
+
+        /// Timing overlay of the second recording
+        second: String,
+
+        /// Path to the base libretto JSON both overlays were made against (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Output format: a table, or JSON on stdout
+        #[arg(long, value_enum, default_value = "text")]
+        format: ReportFormat,
+    },
+
+    /// Rewrite a timing, annotation or pronunciation overlay against a different base libretto using a
+    /// segment map
+    Retarget {
+        /// Path to the overlay JSON (made against the map's source base) (default: paths.timing in libretto.toml)
+        #[arg(short, long)]
+        timing: Option<String>,
+
+        /// Path to the segment map JSON (from `libretto map`)
+        #[arg(short, long)]
+        map: String,
+
+        /// Output path for the retargeted overlay
+        #[arg(short, long, default_value = "retargeted.timing.json")]
+        output: String,
+    },
+
     /// Merge a base libretto + timing overlay into an interchange libretto
     Merge {
-        /// Path to the base libretto JSON
+        /// Path to the base libretto JSON (default: paths.base in libretto.toml)
+        #[arg(short, long)]
+        base: Option<String>,
+
+        /// Path to the timing overlay JSON (default: paths.timing in libretto.toml)
         #[arg(short, long)]
-        base: String,
+        timing: Option<String>,
 
-        /// Path to the timing overlay JSON
+        /// Annotation overlay JSON to merge into the interchange for display (default: paths.annotations in libretto.toml)
         #[arg(short, long)]
-        timing: String,
+        annotations: Option<String>,
+
+        /// Pronunciation overlay JSON to merge into the interchange, shown under each segment's text (default:
+        /// paths.pronunciation in libretto.toml)
+        #[arg(long)]
+        pronunciation: Option<String>,
+
+        /// Synopsis overlay JSON whose synopsis of each number is put before the number's first segment, as a
+        /// "synopsis" segment over any introduction (default: paths.synopsis in libretto.toml)
+        #[arg(long)]
+        synopsis: Option<String>,
+
+        /// Add album-level start times (cumulative across track durations) to every segment
+        #[arg(long)]
+        absolute_times: bool,
 
-        /// Output path for the interchange libretto JSON
+        /// Keep base segments the overlay has no time for, in their place with a null start and
+        /// `untimed: true`, so players can show the whole text and contributors what is missing
+        #[arg(long)]
+        include_untimed: bool,
+
+        /// What to do with a segment the overlay references more than once (default: merge.duplicates in
+        /// libretto.toml, else warn)
+        #[arg(long, value_enum)]
+        duplicates: Option<DuplicatesArg>,
+
+        /// Output path for the interchange libretto (.json, .cbor, or .msgpack by extension)
         #[arg(short, long, default_value = "timed.libretto.json")]
         output: String,
+
+        /// Instead of --output, write one interchange file per track into this directory (d1-t02.segments.json)
+        #[arg(long)]
+        per_track: Option<String>,
+
+        /// Write one interchange file per act, named after --output (timed.act-1.libretto.json); a track
+        /// with no act goes with the act before it
+        #[arg(long, value_enum, conflicts_with = "per_track")]
+        split_by: Option<SplitBy>,
+
+        /// With --per-track: name each track's file after its audio file in this directory
+        /// (searched recursively; matched by disc/track tags, then title)
+        #[arg(long, requires = "per_track")]
+        audio: Option<String>,
     },
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum DuplicatesArg {
+    /// Include every reference, as for a number running across two tracks
+    Allow,
+    /// Include every reference, with a warning for each repeat
+    Warn,
+    /// Stop without writing, listing every repeat
+    Error,
+    /// Include only the first reference of each segment
+    KeepFirst,
+}
+
 #[derive(Clone, clap::ValueEnum)]
-enum AcquireSource {
-    /// opera-arias.com (server-rendered, one page per language)
-    OperaArias,
-    /// murashev.com (server-rendered, side-by-side bilingual tables)
-    Murashev,
+enum SplitBy {
+    /// One file per act, from the act of each track's first segment
+    Act,
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+#[derive(Clone, clap::ValueEnum)]
+enum ReportFormat {
+    /// Findings as log lines
+    Text,
+    /// Machine-readable JSON on stdout
+    Json,
+}
 
-    // Map log level, suppressing noisy HTML-parsing crates at debug/trace
-    let level = match cli.log_level {
+#[derive(Clone, clap::ValueEnum)]
+enum ImportFormat {
+    /// Audacity label track export (start, end, label; tab-separated)
+    Audacity,
+    /// LRC lyrics with [mm:ss.xx] line timestamps, matched to segments by text
+    Lrc,
+    /// SRT subtitles; cue text is matched to segment text or translation
+    Srt,
+    /// WebVTT subtitles; cue text is matched to segment text or translation
+    Vtt,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum EstimateWeight {
+    /// Words in the segment's text
+    Words,
+    /// Syllables in the segment's text (better for melismatic Italian)
+    Syllables,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum ExportFormat {
+    /// Audacity label track (region labels named by segment ID)
+    Audacity,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum ExportFileFormat {
+    /// LRC lyrics, one [mm:ss.xx] line per segment, named by disc/track (d1-t2.lrc)
+    Lrc,
+    /// SRT subtitles, one cue per segment with its start and end (d1-t2.srt)
+    Srt,
+    /// WebVTT subtitles for browser <track> elements, cues identified by segment ID (d1-t2.vtt)
+    Vtt,
+    /// TTML in the IMSC 1 text profile; with --layout both, original at the top and translation at the bottom (d1-t2.ttml)
+    Ttml,
+    /// Advanced SubStation Alpha for Aegisub and video overlays, a style per character (d1-t2.ass)
+    Ass,
+    /// FFmpeg FFMETADATA chapters for one-file-per-act rips, one file per act (act-1.ffmetadata)
+    Ffmetadata,
+    /// Matroska chapter XML for one-file-per-act rips, one file per act (act-1.xml)
+    MkvChapters,
+    /// Cue sheets from a timing overlay's tracks and durations, one file per disc (d1.cue)
+    Cue,
+    /// A printable bilingual libretto page from a base libretto (libretto.html)
+    Html,
+    /// A Markdown libretto from a base libretto for review, git diffs, and static sites (libretto.md)
+    Md,
+    /// A static web player (index.html, player.js, the libretto) that follows local audio files in a browser
+    Webapp,
+    /// roon-rd's layout, with --out-dir as its root: the libretto under data/<name>/ and each track mapped
+    /// in config/track-map.json, replacing earlier mappings to the same file
+    Roon,
+    /// A bilingual LaTeX libretto for pdflatex from a base libretto, with cast page and per-act number tables (libretto.tex)
+    Latex,
+    /// TEI P5 XML from a base libretto: castList, a div per act and number, sp/speaker/l and stage (libretto.tei.xml)
+    Tei,
+    /// One spreadsheet row per segment of a base libretto: segment_id, number, character, text,
+    /// translation, start (segments.csv)
+    Csv,
+    /// A numbered surtitle cue deck for live projection from a base libretto, a blank slide after each number (surtitles.txt)
+    Surtitles,
+    /// The surtitle deck as a CSV cue list for QLab's cue import, one Text cue per slide (surtitles-qlab.csv)
+    Qlab,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum SurtitleSplit {
+    /// A slide per sung segment
+    Segment,
+    /// Consecutive segments of one character share slides
+    Stanza,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum ImportFileFormat {
+    /// A segments CSV as written by export --format csv, columns matched by header name
+    Csv,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum ChapterSplit {
+    /// A chapter per musical number
+    Number,
+    /// A chapter per ensemble group, and per segment outside one
+    Group,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum ExportLayout {
+    /// The original text
+    Original,
+    /// The translation, falling back to the original where there is none
+    Translation,
+    /// The original followed by its translation
+    Both,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum IdSchemeArg {
+    /// <number>-001, <number>-002, ... in order, as `libretto parse` names them
+    Sequential,
+    /// <number>-<hash of the text>, unchanged when other segments are added or removed
+    Content,
+}
+
+#[derive(Clone, clap::ValueEnum)]
+enum AcquireSource {
+    /// opera-arias.com (server-rendered, one page per language)
+    OperaArias,
+    /// murashev.com (server-rendered, side-by-side bilingual tables)
+    Murashev,
+}
+
+/// The `--log-file` layer: lines as on stderr without colours, or JSON
+/// objects with RFC 3339 timestamps.
+fn file_log_layer<S>(
+    file: std::fs::File,
+    format: LogFormat,
+    utc: bool,
+    time_format: &str,
+) -> Box<dyn tracing_subscriber::Layer<S> + Send + Sync>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    use tracing_subscriber::fmt::time::{ChronoLocal, ChronoUtc};
+
+    let fmt = tracing_subscriber::fmt::layer()
+        .with_writer(std::sync::Mutex::new(file))
+        .with_ansi(false);
+    match (format, utc) {
+        (LogFormat::Text, true) => fmt.with_timer(ChronoUtc::new(time_format.to_string())).boxed(),
+        (LogFormat::Text, false) => fmt.with_timer(ChronoLocal::new(time_format.to_string())).boxed(),
+        (LogFormat::Json, true) => fmt.json().with_timer(ChronoUtc::rfc_3339()).boxed(),
+        (LogFormat::Json, false) => fmt.json().with_timer(ChronoLocal::rfc_3339()).boxed(),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // Map log level, suppressing noisy HTML-parsing crates at debug/trace
+    let level = match cli.log_level {
         LogLevel::Error => "error",
         LogLevel::Warn  => "warn",
         LogLevel::Info  => "info",
         LogLevel::Debug => "debug,selectors=warn,html5ever=warn",
         LogLevel::Trace => "trace,selectors=warn,html5ever=warn",
     };
-    let env_filter = tracing_subscriber::EnvFilter::try_from_default_env()
+    let env_filter = || tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(level));
 
+    // Logs go to stderr so stdout stays clean for machine-readable output.
     // Timestamp format: 2026-02-14 19:44:09.123 -08:00
     let time_format = "%Y-%m-%d %H:%M:%S%.3f %:z";
 
-    if cli.utc {
-        tracing_subscriber::fmt()
-            .with_env_filter(env_filter)
-            .with_timer(tracing_subscriber::fmt::time::ChronoUtc::new(time_format.to_string()))
-            .init();
+    let fmt = tracing_subscriber::fmt::layer().with_writer(|| progress::LogWriter);
+    let fmt = if cli.utc {
+        fmt.with_timer(tracing_subscriber::fmt::time::ChronoUtc::new(time_format.to_string())).boxed()
     } else {
-        tracing_subscriber::fmt()
-            .with_env_filter(env_filter)
-            .with_timer(tracing_subscriber::fmt::time::ChronoLocal::new(time_format.to_string()))
-            .init();
+        fmt.with_timer(tracing_subscriber::fmt::time::ChronoLocal::new(time_format.to_string())).boxed()
+    };
+    // The porcelain summary collects events from info up whatever the log level
+    let collector = cli.porcelain
+        .then(|| porcelain::Collector.with_filter(tracing_subscriber::filter::LevelFilter::INFO));
+    let log_file = cli.log_file.as_deref()
+        .map(|path| -> Result<_> {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open log file {path}"))?;
+            Ok(file_log_layer(file, cli.log_format, cli.utc, time_format).with_filter(env_filter()))
+        })
+        .transpose()?;
+    tracing_subscriber::registry()
+        .with(fmt.with_filter(env_filter()))
+        .with(log_file)
+        .with(collector)
+        .init();
+
+    if cli.porcelain {
+        let mut names = Vec::new();
+        let mut sub = &matches;
+        while let Some((name, next)) = sub.subcommand() {
+            names.push(name);
+            sub = next;
+        }
+        porcelain::enable(&names.join(" "));
     }
+    let result = run(cli).await;
+    if porcelain::enabled() {
+        porcelain::finish(&result);
+        if result.is_err() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    result
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let project = match &cli.config {
+        Some(path) => ProjectConfig::load(std::path::Path::new(path))?,
+        None => ProjectConfig::discover(&std::env::current_dir()?),
+    };
+    save::configure(cli.dry_run, project.backup());
 
     match cli.command {
+        Commands::Init { opera, dir, recording, title, composer, language, translation } => {
+            let dir = dir.unwrap_or_else(|| opera.clone());
+            tracing::info!(opera = %opera, dir = %dir, "Starting opera project");
+            let details = init::Details { title, composer, language, translation };
+            init::init_project(&opera, std::path::Path::new(&dir), recording.as_deref(), &details)?;
+        }
         Commands::Acquire {
             source,
             opera,
             lang,
             output_dir,
         } => {
+            let lang = lang.unwrap_or_else(|| {
+                match (project.original_language(), project.translation_language(), &source) {
+                    (Some(original), Some(translation), AcquireSource::OperaArias) => format!("{original},{translation}"),
+                    (Some(original), Some(translation), AcquireSource::Murashev) => format!("{translation}+{original}"),
+                    (Some(original), None, _) => original.to_string(),
+                    _ => "it,en".to_string(),
+                }
+            });
+            let output_dir = project.raw(output_dir).unwrap_or_else(|| ".".to_string());
+            if save::dry_run() {
+                tracing::info!(opera = %opera, lang = %lang, dir = %output_dir, "Would acquire libretto text");
+                return Ok(());
+            }
             tracing::info!(opera = %opera, lang = %lang, "Acquiring libretto text");
             match source {
                 AcquireSource::OperaArias => {
@@ -191,22 +1120,476 @@ async fn main() -> Result<()> {
             }
         }
         Commands::Parse { input, output } => {
+            let input = required(project.raw(input), "input", "paths.raw")?;
+            let output = project.base(output).unwrap_or_else(|| "base.libretto.json".to_string());
             tracing::info!(input = %input, output = %output, "Parsing raw text");
-            libretto_parse::parse(&input, &output)?;
+            let libretto = libretto_parse::parse(&input, &project.parse)?;
+            save::write(&output, serde_json::to_string_pretty(&libretto)?)?;
+            tracing::info!(
+                path = %output,
+                numbers = libretto.numbers.len(),
+                segments = libretto.segment_ids().len(),
+                "Wrote base libretto JSON"
+            );
         }
-        Commands::Validate { file, base } => {
+        Commands::Validate { file, base, recursive, jobs, strict, config, format, audio, audio_tolerance, fix, lenient } => {
             tracing::info!(file = %file, "Validating");
-            libretto_validate::validate(&file, base.as_deref())?;
+            // Validation failures exit 1; files that cannot be read or parsed exit 2
+            let run = || -> Result<bool> {
+                let lint_config = load_lint_config(config.as_deref())?;
+                if recursive {
+                    let started = std::time::Instant::now();
+                    let progress = progress::Progress::new("Validating");
+                    let update = |done: usize, total: usize, item: &str| progress.update(done, total, item);
+                    let options = libretto_validate::batch::BatchOptions { jobs: jobs.unwrap_or(0), progress: Some(&update) };
+                    let batch = libretto_validate::batch::validate_tree_with(std::path::Path::new(&file), &lint_config, strict, &options)?;
+                    progress.finish();
+                    tracing::info!(
+                        files = batch.files,
+                        failed = batch.failed,
+                        seconds = (started.elapsed().as_secs_f64() * 10.0).round() / 10.0,
+                        "Validated library"
+                    );
+                    match format {
+                        _ if porcelain::enabled() => porcelain::report(&batch)?,
+                        ReportFormat::Text => print_batch_summary(&batch),
+                        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&batch)?),
+                    }
+                    if !batch.load_errors.is_empty() {
+                        anyhow::bail!("{} files could not be loaded", batch.load_errors.len());
+                    }
+                    return Ok(batch.passed);
+                }
+                if fix {
+                    let (overlay, summary) = libretto_validate::fix::fix_file(
+                        std::path::Path::new(&file),
+                        base.as_deref().map(std::path::Path::new),
+                    )?;
+                    if summary.is_empty() {
+                        tracing::info!("No fixes needed");
+                    } else {
+                        save::write(&file, serde_json::to_string_pretty(&overlay)? + "\n")?;
+                    }
+                    for line in summary.lines() {
+                        if save::dry_run() {
+                            tracing::info!(path = %file, "Would fix: {line}");
+                        } else {
+                            tracing::info!(path = %file, "Fixed: {line}");
+                        }
+                    }
+                }
+                let options = libretto_validate::ValidateOptions { lenient };
+                let mut report = libretto_validate::validate_file_with(&file, base.as_deref(), &lint_config, strict, &options)?;
+                if let Some(dir) = &audio {
+                    libretto_validate::validate_audio(&mut report, std::path::Path::new(dir), audio_tolerance, &lint_config)?;
+                }
+                match format {
+                    _ if porcelain::enabled() => porcelain::report(&report)?,
+                    ReportFormat::Text => {
+                        libretto_validate::log_report(&report);
+                        println!("{}", report.summary());
+                    }
+                    ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+                }
+                Ok(report.passed)
+            };
+            match run() {
+                Ok(true) => {}
+                Ok(false) => porcelain::exit(EXIT_INVALID, "validation failed"),
+                Err(e) => {
+                    tracing::error!("{e:#}");
+                    porcelain::exit(EXIT_IO_FAILURE, &format!("{e:#}"));
+                }
+            }
         }
-        Commands::Timing { action } => match action {
-            TimingAction::Init { base, output } => {
+        Commands::Decompile { input, base_output, timing_output } => {
+            tracing::info!(input = %input, "Decompiling interchange libretto");
+            let doc = libretto_model::encoding::read_interchange(std::path::Path::new(&input))?;
+
+            let result = libretto_model::decompile::decompile(&doc, &base_output);
+            for w in &result.warnings {
+                tracing::warn!("{w}");
+            }
+            let base_json = serde_json::to_string_pretty(&result.base)?;
+            save::write(&base_output, &base_json)?;
+            let mut overlay = result.overlay;
+            overlay.base_sha256 = Some(libretto_model::library::sha256_hex(base_json.as_bytes()));
+            save::write(&timing_output, serde_json::to_string_pretty(&overlay)?)?;
+            tracing::info!(
+                numbers = result.base.numbers.len(),
+                segments = result.base.segment_ids().len(),
+                path = %base_output,
+                "Wrote base libretto"
+            );
+            tracing::info!(
+                tracks = overlay.track_timings.len(),
+                path = %timing_output,
+                "Wrote timing overlay"
+            );
+        }
+        Commands::Export { input, bundle, format, out_dir, layout, translation, voice_classes, per_line, ass_config, chapters, base, cue_file, timing, template, slides, max_line_chars, max_lines, name } => {
+            use libretto_model::export::{self, Layout};
+            let out_dir = project.out_dir(out_dir).unwrap_or_else(|| ".".to_string());
+            let layout = match layout {
+                _ if translation => Layout::Both,
+                ExportLayout::Original => Layout::Original,
+                ExportLayout::Translation => Layout::Translation,
+                ExportLayout::Both => Layout::Both,
+            };
+            save::create_dir_all(&out_dir)?;
+            let Some(input) = input else {
+                let recordings = bundle.iter()
+                    .map(|path| {
+                        let path = std::path::Path::new(path);
+                        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+                        let id = file_name.split('.').next().unwrap_or_default().to_string();
+                        Ok((id, libretto_model::encoding::read_interchange(path)?))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let result = libretto_model::bundle::bundle(recordings)?;
+                let path = std::path::Path::new(&out_dir).join(format!("{}.bundle.json", name.as_deref().unwrap_or("libretto")));
+                save::write(&path, serde_json::to_string_pretty(&result)?)?;
+                tracing::info!(
+                    recordings = result.recordings.len(),
+                    segments = result.segments.len(),
+                    path = %path.display(),
+                    "Wrote interchange bundle"
+                );
+                return Ok(());
+            };
+            let read_doc = || -> Result<libretto_model::InterchangeLibretto> {
+                let mut doc = libretto_model::encoding::read_interchange(std::path::Path::new(&input))?;
+                let untimed = doc.drop_untimed();
+                if untimed > 0 {
+                    tracing::info!(segments = untimed, "Leaving out untimed segments");
+                }
+                Ok(doc)
+            };
+            let files: Vec<(String, String)> = match format {
+                ExportFileFormat::Cue => {
+                    let overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&input)?;
+                    let base: Option<libretto_model::BaseLibretto> = base.as_deref()
+                        .map(libretto_model::io::load_json)
+                        .transpose()?;
+                    let result = libretto_model::cue::export_cue_sheets(&overlay, base.as_ref(), &cue_file);
+                    for w in &result.warnings {
+                        tracing::warn!("{w}");
+                    }
+                    result.sheets.into_iter().map(|sheet| (sheet.file_name(), sheet.contents)).collect()
+                }
+                ExportFileFormat::Html => {
+                    let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&input)?;
+                    let overlay: Option<libretto_model::TimingOverlay> = timing.as_deref()
+                        .map(libretto_model::io::load_json)
+                        .transpose()?;
+                    vec![("libretto.html".to_string(), libretto_model::html::export_html(&base, overlay.as_ref()))]
+                }
+                ExportFileFormat::Csv => {
+                    let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&input)?;
+                    let overlay: Option<libretto_model::TimingOverlay> = timing.as_deref()
+                        .map(libretto_model::io::load_json)
+                        .transpose()?;
+                    vec![("segments.csv".to_string(), libretto_model::segment_csv::export_segments_csv(&base, overlay.as_ref()))]
+                }
+                ExportFileFormat::Latex => {
+                    let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&input)?;
+                    let template = match &template {
+                        Some(path) => std::fs::read_to_string(path)
+                            .with_context(|| format!("Failed to read {path}"))?,
+                        None => libretto_model::latex::DEFAULT_TEMPLATE.to_string(),
+                    };
+                    vec![("libretto.tex".to_string(), libretto_model::latex::export_latex(&base, &template))]
+                }
+                ExportFileFormat::Md => {
+                    let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&input)?;
+                    vec![("libretto.md".to_string(), libretto_model::markdown::export_markdown(&base))]
+                }
+                ExportFileFormat::Tei => {
+                    let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&input)?;
+                    vec![("libretto.tei.xml".to_string(), libretto_model::tei::export_tei(&base))]
+                }
+                ExportFileFormat::Surtitles | ExportFileFormat::Qlab => {
+                    use libretto_model::surtitles;
+                    let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&input)?;
+                    let overlay: Option<libretto_model::TimingOverlay> = timing.as_deref()
+                        .map(libretto_model::io::load_json)
+                        .transpose()?;
+                    let options = surtitles::SurtitleOptions {
+                        layout,
+                        split: match slides {
+                            SurtitleSplit::Segment => surtitles::SlideSplit::Segment,
+                            SurtitleSplit::Stanza => surtitles::SlideSplit::Stanza,
+                        },
+                        max_line_chars,
+                        max_lines,
+                    };
+                    let deck = surtitles::surtitle_slides(&base, overlay.as_ref(), &options);
+                    match format {
+                        ExportFileFormat::Surtitles => vec![("surtitles.txt".to_string(), surtitles::deck_text(&base, &deck))],
+                        _ => vec![("surtitles-qlab.csv".to_string(), surtitles::deck_qlab(&deck))],
+                    }
+                }
+                ExportFileFormat::Webapp => libretto_model::webapp::webapp_files(&read_doc()?)?,
+                ExportFileFormat::Roon => {
+                    use libretto_model::roon;
+                    let doc = read_doc()?;
+                    let name = name.unwrap_or_else(|| {
+                        let file_name = std::path::Path::new(&input).file_name().unwrap_or_default().to_string_lossy();
+                        file_name.split('.').next().unwrap_or_default().to_string()
+                    });
+                    let map_path = std::path::Path::new(&out_dir).join(roon::TRACK_MAP_PATH);
+                    let track_map: roon::TrackMap = if map_path.exists() {
+                        libretto_model::io::load_json(&map_path)?
+                    } else {
+                        Default::default()
+                    };
+                    let export = roon::roon_files(&doc, &name, track_map)?;
+                    tracing::info!(mappings = export.mappings, replaced = export.replaced, "Updated roon-rd track map");
+                    export.files
+                }
+                ExportFileFormat::Ffmetadata | ExportFileFormat::MkvChapters => {
+                    use libretto_model::chapters;
+                    let doc = read_doc()?;
+                    let split = match chapters {
+                        ChapterSplit::Number => chapters::ChapterSplit::Number,
+                        ChapterSplit::Group => chapters::ChapterSplit::Group,
+                    };
+                    let result = chapters::act_chapters(&doc, split);
+                    for w in &result.warnings {
+                        tracing::warn!("{w}");
+                    }
+                    let files: Vec<(String, String)> = result.acts.iter()
+                        .enumerate()
+                        .map(|(i, act)| match format {
+                            ExportFileFormat::Ffmetadata => (act.file_name(i, "ffmetadata"), chapters::ffmetadata(&doc, act)),
+                            _ => (act.file_name(i, "xml"), chapters::mkv_chapters(act)),
+                        })
+                        .collect();
+                    // Acts whose names differ only in case or punctuation
+                    // share a file name; refuse rather than keep the last
+                    for (i, (name, _)) in files.iter().enumerate() {
+                        if let Some(other) = files[..i].iter().position(|(n, _)| n == name) {
+                            let act = |a: &chapters::ActChapters| a.act.clone().unwrap_or_default();
+                            anyhow::bail!(
+                                "Acts {} and {} would both be written to {name}; nothing written",
+                                act(&result.acts[other]),
+                                act(&result.acts[i])
+                            );
+                        }
+                    }
+                    files
+                }
+                _ => {
+                    let doc = read_doc()?;
+                    let ass_config: export::AssConfig = match &ass_config {
+                        Some(path) => {
+                            let contents = std::fs::read_to_string(path)
+                                .with_context(|| format!("Failed to read {path}"))?;
+                            toml::from_str(&contents).with_context(|| format!("Invalid ASS config {path}"))?
+                        }
+                        None => Default::default(),
+                    };
+                    doc.tracks.iter()
+                        .map(|track| match format {
+                            ExportFileFormat::Lrc => (export::track_file_name(track, "lrc"), export::export_lrc(&doc, track, layout, per_line)),
+                            ExportFileFormat::Srt => (export::track_file_name(track, "srt"), export::export_srt(track, layout)),
+                            ExportFileFormat::Vtt => (export::track_file_name(track, "vtt"), export::export_vtt(track, layout, voice_classes, per_line)),
+                            ExportFileFormat::Ass => (export::track_file_name(track, "ass"), export::export_ass(&doc, track, layout, &ass_config)),
+                            _ => (export::track_file_name(track, "ttml"), export::export_ttml(&doc, track, layout)),
+                        })
+                        .collect()
+                }
+            };
+            for (name, contents) in &files {
+                let path = std::path::Path::new(&out_dir).join(name);
+                if let Some(parent) = path.parent() {
+                    save::create_dir_all(parent)?;
+                }
+                save::write(&path, contents)?;
+            }
+            tracing::info!(files = files.len(), dir = %out_dir, "Wrote export files");
+        }
+        Commands::Import { input, format, base, timing, base_output, timing_output } => {
+            use libretto_model::segment_csv;
+            let base = required(project.base(base), "base", "paths.base")?;
+            let contents = std::fs::read_to_string(&input)
+                .with_context(|| format!("Failed to read {input}"))?;
+            let rows = match format {
+                ImportFileFormat::Csv => segment_csv::parse_segments_csv(&contents)?,
+            };
+            let mut base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+            let mut overlay: Option<libretto_model::TimingOverlay> = timing.as_deref()
+                .map(libretto_model::io::load_json)
+                .transpose()?;
+
+            let result = segment_csv::apply_segments_csv(&mut base_libretto, overlay.as_mut(), &rows);
+            for w in &result.warnings {
+                tracing::warn!("{w}");
+            }
+            if result.base_changes > 0 {
+                let path = base_output.unwrap_or(base);
+                save::write(&path, serde_json::to_string_pretty(&base_libretto)?)?;
+                tracing::info!(segments = result.base_changes, path = %path, "Wrote base libretto");
+            }
+            if let (Some(overlay), Some(timing), true) = (&overlay, timing, result.timing_changes > 0) {
+                let path = timing_output.unwrap_or(timing);
+                save::write(&path, serde_json::to_string_pretty(overlay)?)?;
+                tracing::info!(times = result.timing_changes, path = %path, "Wrote timing overlay");
+            }
+            tracing::info!(rows = rows.len(), base_changes = result.base_changes, timing_changes = result.timing_changes, "Imported segments");
+        }
+        Commands::Map { source, target, output } => {
+            tracing::info!(source = %source, target = %target, "Mapping segment IDs");
+            let source_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&source)?;
+            let target_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&target)?;
+
+            let result = libretto_model::crosswalk::build_segment_map(
+                &source_libretto, &target_libretto, &source, &target,
+            );
+            for id in &result.unmapped {
+                tracing::warn!(segment = %id, "No counterpart in target base");
+            }
+            for warning in &result.warnings {
+                tracing::warn!("{warning}");
+            }
+            let json = serde_json::to_string_pretty(&result.map)?;
+            save::write(&output, &json)?;
+            tracing::info!(
+                mapped = result.map.segments.len(),
+                unmapped = result.unmapped.len(),
+                numbers = result.map.numbers.len(),
+                path = %output,
+                "Wrote segment map"
+            );
+        }
+        Commands::Reid { base, overlays, scheme, map, output } => {
+            use libretto_model::reid::IdScheme;
+            use libretto_validate::DocumentKind;
+
+            let base = required(project.base(base), "base", "paths.base")?;
+            let output = output.unwrap_or_else(|| base.clone());
+            let overlays = match overlays.is_empty() {
+                true => project.timing(None).into_iter()
+                    .chain(project.annotations(None))
+                    .chain(project.pronunciation(None))
+                    .collect(),
+                false => overlays,
+            };
+            let scheme = match scheme {
+                IdSchemeArg::Sequential => IdScheme::Sequential,
+                IdSchemeArg::Content => IdScheme::Content,
+            };
+            tracing::info!(base = %base, overlays = overlays.len(), "Regenerating segment IDs");
+            let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+            let mut result = libretto_model::reid::reid(&base_libretto, scheme, &base);
+            result.map.target_base = output.clone();
+            let base_json = serde_json::to_string_pretty(&result.base)?;
+            // Written elsewhere, the renamed base is a new document: point
+            // the rewritten overlays at it and leave the old one's alone
+            let target = |current: &str| match output == base {
+                true => current.to_string(),
+                false => output.clone(),
+            };
+
+            // Rewrite every overlay before writing anything, so a reference
+            // the map cannot carry over stops the whole operation
+            let mut rewritten = Vec::new();
+            let mut unmapped = 0;
+            for path in &overlays {
+                let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+                let kind = DocumentKind::detect(&contents).with_context(|| format!("{path} is not valid JSON"))?;
+                let (json, warnings) = match kind {
+                    Some(DocumentKind::TimingOverlay) => {
+                        let overlay: libretto_model::TimingOverlay = libretto_model::io::from_json_str(&contents, path)?;
+                        let mut retargeted = libretto_model::crosswalk::retarget_overlay(&overlay, &result.map, &target(&overlay.base_libretto));
+                        if overlay.base_sha256.is_some() {
+                            retargeted.overlay.base_sha256 = Some(libretto_model::library::sha256_hex(base_json.as_bytes()));
+                        }
+                        (serde_json::to_string_pretty(&retargeted.overlay)?, retargeted.warnings)
+                    }
+                    Some(DocumentKind::AnnotationOverlay) => {
+                        let overlay: libretto_model::AnnotationOverlay = libretto_model::io::from_json_str(&contents, path)?;
+                        let (overlay, warnings) =
+                            libretto_model::crosswalk::retarget_annotations(&overlay, &result.map, &target(&overlay.base_libretto));
+                        (serde_json::to_string_pretty(&overlay)?, warnings)
+                    }
+                    Some(DocumentKind::PronunciationOverlay) => {
+                        let overlay: libretto_model::PronunciationOverlay = libretto_model::io::from_json_str(&contents, path)?;
+                        let (overlay, warnings) =
+                            libretto_model::crosswalk::retarget_pronunciations(&overlay, &result.map, &target(&overlay.base_libretto));
+                        (serde_json::to_string_pretty(&overlay)?, warnings)
+                    }
+                    _ => anyhow::bail!("{path} is not a timing, annotation or pronunciation overlay"),
+                };
+                for w in &warnings {
+                    tracing::warn!(path = %path, "{w}");
+                }
+                unmapped += warnings.len();
+                rewritten.push((path, json));
+            }
+            if unmapped > 0 {
+                anyhow::bail!(
+                    "{unmapped} overlay references are not segments or numbers of {base}; nothing written \
+                     (validate the overlays against it first)"
+                );
+            }
+
+            save::write(&output, &base_json)?;
+            tracing::info!(
+                segments = result.map.segments.len(),
+                changed = result.changed,
+                path = %output,
+                "Wrote base libretto with new segment IDs"
+            );
+            for (path, json) in &rewritten {
+                save::write(path, json)?;
+                tracing::info!(path = %path, "Rewrote overlay");
+            }
+            save::write(&map, serde_json::to_string_pretty(&result.map)?)?;
+            tracing::info!(path = %map, "Wrote segment map");
+        }
+        Commands::Timing { no_contributor, action } => match action {
+            TimingAction::Init { base, cue, tracks, group_recits, output } => {
+                let base = required(project.base(base), "base", "paths.base")?;
+                let output = project.timing(output).unwrap_or_else(|| "timing.overlay.json".to_string());
                 tracing::info!(base = %base, output = %output, "Generating scaffold timing overlay");
-                let base_contents = std::fs::read_to_string(&base)?;
-                let base_libretto: libretto_model::BaseLibretto =
-                    serde_json::from_str(&base_contents)?;
-                let overlay = libretto_model::merge::scaffold_overlay(&base_libretto, &base);
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let reference = libretto_model::library::reference_from(std::path::Path::new(&output), std::path::Path::new(&base));
+                let mut overlay = if let Some(path) = &tracks {
+                    let contents = std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+                    let listed = libretto_model::tracklist::parse_track_listing(&contents)
+                        .with_context(|| format!("Invalid track listing {path}"))?;
+                    let without = listed.iter().filter(|t| t.duration.is_none()).count();
+                    if without > 0 {
+                        tracing::warn!(tracks = without, "Tracks listed without a duration; fill in with `timing durations`");
+                    }
+                    tracing::info!("Fill in each track's number_ids, then run `timing resolve`");
+                    libretto_model::tracklist::scaffold_from_listing(&listed, &reference)
+                } else if cue.is_empty() {
+                    let options = libretto_model::merge::ScaffoldOptions { group_recitatives: group_recits };
+                    libretto_model::merge::scaffold_overlay_with(&base_libretto, &reference, &options)
+                } else {
+                    let sheets = cue.iter()
+                        .map(|path| {
+                            let bytes = std::fs::read(path).with_context(|| format!("Failed to read {path}"))?;
+                            libretto_model::cue::parse_cue_sheet(&libretto_model::cue::decode_cue_sheet(&bytes))
+                                .with_context(|| format!("Invalid cue sheet {path}"))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    let overlay = libretto_model::cue::scaffold_from_cue(&sheets, &reference);
+                    let without = overlay.track_timings.iter().filter(|t| t.duration_seconds.is_none()).count();
+                    if without > 0 {
+                        tracing::warn!(
+                            tracks = without,
+                            "Last track of each cue file has no duration; fill in with `timing durations`"
+                        );
+                    }
+                    tracing::info!("Fill in each track's number_ids, then run `timing resolve`");
+                    overlay
+                };
+                overlay.base_sha256 = Some(libretto_model::library::sha256_hex(&std::fs::read(&base)?));
                 let json = serde_json::to_string_pretty(&overlay)?;
-                std::fs::write(&output, &json)?;
+                save::write(&output, &json)?;
                 let seg_count: usize = overlay.track_timings.iter()
                     .map(|t| t.segment_times.len())
                     .sum();
@@ -217,19 +1600,32 @@ async fn main() -> Result<()> {
                     "Wrote scaffold timing overlay"
                 );
             }
-            TimingAction::Resolve { base, timing, output } => {
+            TimingAction::Resolve {
+                base, timing, fuzzy_threshold, no_fuzzy, report, interactive, force, output,
+            } => {
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
                 tracing::info!(base = %base, timing = %timing, output = %output, "Resolving track anchors");
-                let base_contents = std::fs::read_to_string(&base)?;
-                let base_libretto: libretto_model::BaseLibretto =
-                    serde_json::from_str(&base_contents)?;
-                let overlay_contents = std::fs::read_to_string(&timing)?;
-                let overlay: libretto_model::TimingOverlay =
-                    serde_json::from_str(&overlay_contents)?;
-
-                let result = libretto_model::resolve::resolve_anchors(&base_libretto, &overlay);
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+
+                if !(0.0..=1.0).contains(&fuzzy_threshold) {
+                    anyhow::bail!("--fuzzy-threshold must be between 0 and 1, got {fuzzy_threshold}");
+                }
+                let options = libretto_model::resolve::ResolveOptions {
+                    fuzzy_threshold: (!no_fuzzy).then_some(fuzzy_threshold),
+                    force,
+                };
+                let mut result = libretto_model::resolve::resolve_anchors_with(&base_libretto, &overlay, &options);
                 for w in &result.warnings {
                     tracing::warn!("{w}");
                 }
+                if interactive {
+                    if porcelain::enabled() {
+                        anyhow::bail!("--interactive asks questions on stdout and cannot be used with --porcelain");
+                    }
+                    choose_segments_interactively(&base_libretto, &mut result)?;
+                }
                 let mut resolved = 0;
                 let mut unresolved = 0;
                 for res in &result.resolutions {
@@ -245,11 +1641,16 @@ async fn main() -> Result<()> {
                                 track = track,
                                 segment = %seg_id,
                                 method = %method,
+                                confidence = res.confidence.unwrap_or_default(),
                                 anchors = ?res.anchors,
                                 "Resolved"
                             );
                             resolved += 1;
                         }
+                        None if res.instrumental => {
+                            tracing::info!(disc = disc, track = track, "Instrumental");
+                            resolved += 1;
+                        }
                         None => {
                             tracing::warn!(
                                 disc = disc,
@@ -261,8 +1662,18 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                if let Some(path) = &report {
+                    let contents = if path.ends_with(".json") {
+                        serde_json::to_string_pretty(&result.resolutions)?
+                    } else {
+                        libretto_model::resolve::resolve_report_markdown(&result.resolutions)
+                    };
+                    save::write(path, contents)?;
+                    tracing::info!(path = %path, "Wrote resolve report");
+                }
+                stamp_contributor(&mut result.overlay, "resolve", no_contributor, &project);
                 let json = serde_json::to_string_pretty(&result.overlay)?;
-                std::fs::write(&output, &json)?;
+                save::write(&output, &json)?;
                 tracing::info!(
                     resolved = resolved,
                     unresolved = unresolved,
@@ -271,16 +1682,44 @@ async fn main() -> Result<()> {
                     "Wrote resolved timing overlay"
                 );
             }
-            TimingAction::Estimate { base, timing, output } => {
+            TimingAction::Estimate {
+                base, timing, weight, pacing, head_padding, tail_padding,
+                snap_to_silence, audio, snap_window, report, output,
+            } => {
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let audio = project.audio(audio);
+                if snap_to_silence && audio.is_none() {
+                    anyhow::bail!("--snap-to-silence needs --audio (or paths.audio in libretto.toml)");
+                }
                 tracing::info!(base = %base, timing = %timing, output = %output, "Estimating segment timings");
-                let base_contents = std::fs::read_to_string(&base)?;
-                let base_libretto: libretto_model::BaseLibretto =
-                    serde_json::from_str(&base_contents)?;
-                let overlay_contents = std::fs::read_to_string(&timing)?;
-                let overlay: libretto_model::TimingOverlay =
-                    serde_json::from_str(&overlay_contents)?;
-
-                let result = libretto_model::estimate::estimate_timings(&base_libretto, &overlay);
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let mut overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                if head_padding.is_some() || tail_padding.is_some() {
+                    let mut padding = overlay.padding.unwrap_or_default();
+                    padding.head_seconds = head_padding.unwrap_or(padding.head_seconds);
+                    padding.tail_seconds = tail_padding.unwrap_or(padding.tail_seconds);
+                    if ![padding.head_seconds, padding.tail_seconds].iter().all(|s| s.is_finite() && *s >= 0.0) {
+                        anyhow::bail!("Padding must be a number of seconds, not negative");
+                    }
+                    overlay.padding = Some(padding);
+                }
+
+                let options = libretto_model::estimate::EstimateOptions {
+                    weighting: match weight {
+                        EstimateWeight::Words => libretto_model::estimate::Weighting::Words,
+                        EstimateWeight::Syllables => libretto_model::estimate::Weighting::Syllables,
+                    },
+                    pacing: match &pacing {
+                        Some(path) => {
+                            let contents = std::fs::read_to_string(path)
+                                .with_context(|| format!("Failed to read {path}"))?;
+                            toml::from_str(&contents).with_context(|| format!("Invalid pacing table {path}"))?
+                        }
+                        None => Default::default(),
+                    },
+                };
+                let result = libretto_model::estimate::estimate_timings_with(&base_libretto, &overlay, &options);
                 for w in &result.warnings {
                     tracing::warn!("{w}");
                 }
@@ -291,13 +1730,34 @@ async fn main() -> Result<()> {
                         num = ?stat.track_number,
                         duration = stat.duration,
                         segments = stat.segments_estimated,
+                        anchors = stat.anchors,
+                        skipped = stat.segments_skipped,
                         word_weight = format!("{:.1}", stat.total_word_weight),
                         "Estimated"
                     );
                 }
                 let total_segs: usize = result.stats.iter().map(|s| s.segments_estimated).sum();
-                let json = serde_json::to_string_pretty(&result.overlay)?;
-                std::fs::write(&output, &json)?;
+                let mut overlay = result.overlay;
+                if let (true, Some(dir)) = (snap_to_silence, &audio) {
+                    let files = libretto_validate::audio::scan_audio_dir(std::path::Path::new(dir))?;
+                    let options = libretto_validate::silence::SilenceOptions {
+                        snap_window_seconds: snap_window,
+                        ..Default::default()
+                    };
+                    let summary = libretto_validate::silence::snap_overlay_to_silence(&mut overlay, &files, &options);
+                    for title in &summary.skipped {
+                        tracing::warn!(track = %title, "No decodable audio for track; estimates left as they are");
+                    }
+                    tracing::info!(snapped = summary.snapped, segments = summary.segments, "Snapped starts to silences");
+                }
+                if let Some(path) = &report {
+                    let rows = libretto_model::estimate::diagnostics(&base_libretto, &overlay, &result.weights);
+                    save::write(path, libretto_model::estimate::diagnostics_csv(&rows))?;
+                    tracing::info!(rows = rows.len(), path = %path, "Wrote estimate report");
+                }
+                stamp_contributor(&mut overlay, "estimate", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&overlay)?;
+                save::write(&output, &json)?;
                 tracing::info!(
                     segments = total_segs,
                     tracks = result.stats.len(),
@@ -305,35 +1765,573 @@ async fn main() -> Result<()> {
                     "Wrote estimated timing overlay"
                 );
             }
-            TimingAction::Merge { base, timing, output } => {
+            TimingAction::Import { input, base, timing, track, format, output } => {
+                use libretto_model::import;
+
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let output = output.unwrap_or_else(|| timing.clone());
+                tracing::info!(input = %input, track = %track, output = %output, "Importing segment timings");
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let contents = std::fs::read_to_string(&input)
+                    .with_context(|| format!("Failed to read {input}"))?;
+                let mut result = match format {
+                    ImportFormat::Audacity => import::parse_audacity_labels(&contents)
+                        .and_then(|labels| import::import_labels(&base_libretto, &overlay, &track, &labels)),
+                    ImportFormat::Lrc => import::parse_lrc(&contents)
+                        .and_then(|lines| import::import_timed_text(&base_libretto, &overlay, &track, &lines)),
+                    ImportFormat::Srt => import::parse_srt(&contents)
+                        .and_then(|cues| import::import_timed_text(&base_libretto, &overlay, &track, &cues)),
+                    ImportFormat::Vtt => import::parse_vtt(&contents)
+                        .and_then(|cues| import::import_timed_text(&base_libretto, &overlay, &track, &cues)),
+                }
+                .with_context(|| format!("Failed to import {input}"))?;
+                for w in &result.warnings {
+                    tracing::warn!("{w}");
+                }
+                stamp_contributor(&mut result.overlay, "import", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&result.overlay)?;
+                save::write(&output, &json)?;
+                tracing::info!(
+                    matched = result.matched,
+                    unmatched = result.unmatched,
+                    path = %output,
+                    "Wrote imported timing overlay"
+                );
+            }
+            TimingAction::Export { timing, track, format, output } => {
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let contents = match format {
+                    ExportFormat::Audacity => libretto_model::import::export_audacity_labels(&overlay, &track)?,
+                };
+                match output {
+                    Some(path) => {
+                        save::write(&path, &contents)?;
+                        tracing::info!(track = %track, path = %path, "Wrote labels");
+                    }
+                    None if porcelain::enabled() => porcelain::report(&contents)?,
+                    None => print!("{contents}"),
+                }
+            }
+            TimingAction::Durations { audio, timing, output } => {
+                let audio = required(project.audio(audio), "audio", "paths.audio")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let output = output.unwrap_or_else(|| timing.clone());
+                tracing::info!(audio = %audio, timing = %timing, output = %output, "Reading track durations");
+                let mut overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let files = libretto_validate::audio::scan_audio_dir(std::path::Path::new(&audio))?;
+                let fill = libretto_validate::audio::fill_track_durations(&mut overlay, &files);
+                for title in &fill.unmatched {
+                    tracing::warn!(track = %title, "No audio file matches track");
+                }
+                stamp_contributor(&mut overlay, "durations", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&overlay)?;
+                save::write(&output, &json)?;
+                tracing::info!(
+                    files = files.len(),
+                    updated = fill.updated,
+                    unchanged = fill.unchanged,
+                    unmatched = fill.unmatched.len(),
+                    path = %output,
+                    "Wrote track durations"
+                );
+            }
+            TimingAction::Shift { timing, track, all_tracks: _, by, output } => {
+                if !by.is_finite() {
+                    anyhow::bail!("--by must be a number of seconds, got {by}");
+                }
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let output = output.unwrap_or_else(|| timing.clone());
+                let mut overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let tracks = select_tracks(&overlay, track.as_deref(), &timing)?;
+                for &index in &tracks {
+                    let track = &overlay.track_timings[index];
+                    if let Some(st) = track.shifts_below_zero(by) {
+                        anyhow::bail!(
+                            "Shifting by {by}s would move segment {} in track {} (at {}s) before the start of the track; nothing written",
+                            st.segment_id,
+                            track.track_id(index),
+                            st.start
+                        );
+                    }
+                }
+                for &index in &tracks {
+                    overlay.shift_track(index, by);
+                }
+                stamp_contributor(&mut overlay, "shift", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&overlay)?;
+                save::write(&output, &json)?;
+                tracing::info!(tracks = tracks.len(), seconds = by, path = %output, "Shifted segment times");
+            }
+            TimingAction::Scale { timing, track, all_tracks: _, factor, output } => {
+                if !(factor.is_finite() && factor > 0.0) {
+                    anyhow::bail!("--factor must be a positive number, got {factor}");
+                }
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let output = output.unwrap_or_else(|| timing.clone());
+                let mut overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let tracks = select_tracks(&overlay, track.as_deref(), &timing)?;
+                for &index in &tracks {
+                    overlay.scale_track(index, factor);
+                }
+                stamp_contributor(&mut overlay, "scale", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&overlay)?;
+                save::write(&output, &json)?;
+                tracing::info!(tracks = tracks.len(), factor = factor, path = %output, "Scaled segment times");
+            }
+            TimingAction::Edit { base, timing, track, output } => {
+                if porcelain::enabled() {
+                    anyhow::bail!("timing edit draws a table on the terminal and cannot be used with --porcelain");
+                }
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let output = output.unwrap_or_else(|| timing.clone());
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let mut overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let index = select_tracks(&overlay, Some(&track), &timing)?[0];
+                // Logged once the table is closed, as it would be drawn over
+                let saved = edit::edit_track(&base_libretto, &mut overlay, index, |overlay| {
+                    stamp_contributor(overlay, "edit", no_contributor, &project);
+                    let json = serde_json::to_string_pretty(overlay)?;
+                    save::write(&output, &json)
+                })?;
+                if saved {
+                    tracing::info!(track = %track, path = %output, "Wrote edited segment times");
+                }
+            }
+            TimingAction::AlignAudio {
+                base, timing, audio, track, aligner, language, min_confidence, report, output,
+            } => {
+                use libretto_validate::align;
+
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let audio = required(project.audio(audio), "audio", "paths.audio")?;
+                let output = output.unwrap_or_else(|| timing.clone());
+                tracing::info!(audio = %audio, timing = %timing, output = %output, "Aligning segment text to audio");
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let mut overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let files = libretto_validate::audio::scan_audio_dir(std::path::Path::new(&audio))?;
+                let options = align::AlignerOptions {
+                    command: aligner,
+                    language: language.unwrap_or_else(|| {
+                        align::aligner_language(project.original_language().unwrap_or(&base_libretto.opera.language))
+                    }),
+                    min_confidence,
+                };
+                let summary = align::align_overlay(&base_libretto, &mut overlay, track.as_deref(), &files, &options)?;
+                for id in &summary.skipped {
+                    tracing::warn!(track = %id, "No audio file or no text for track; not aligned");
+                }
+                for w in &summary.warnings {
+                    tracing::warn!("{w}");
+                }
+                if let Some(path) = &report {
+                    save::write(path, serde_json::to_string_pretty(&summary.tracks)?)?;
+                    tracing::info!(path = %path, "Wrote alignment report");
+                }
+                stamp_contributor(&mut overlay, "align-audio", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&overlay)?;
+                save::write(&output, &json)?;
+                tracing::info!(
+                    tracks = summary.tracks.len(),
+                    segments = summary.tracks.iter().map(|t| t.segments.len()).sum::<usize>(),
+                    skipped = summary.skipped.len(),
+                    path = %output,
+                    "Wrote aligned timing overlay"
+                );
+            }
+            TimingAction::Combine { first, second, tolerance, output } => {
+                tracing::info!(first = %first, second = %second, output = %output, "Combining timing overlays");
+                let a: libretto_model::TimingOverlay = libretto_model::io::load_json(&first)?;
+                let b: libretto_model::TimingOverlay = libretto_model::io::load_json(&second)?;
+                let mut result = libretto_model::combine::combine_overlays(&a, &b, tolerance)?;
+                for w in &result.warnings {
+                    tracing::warn!("{w}");
+                }
+                for c in &result.conflicts {
+                    let source = |t: &libretto_model::SegmentTime| format!("{:?}", t.trust()).to_lowercase();
+                    tracing::warn!(
+                        track = %c.track_id,
+                        segment = %c.kept.segment_id,
+                        kept = c.kept.start,
+                        kept_source = %source(&c.kept),
+                        dropped = c.dropped.start,
+                        dropped_source = %source(&c.dropped),
+                        "Conflicting times"
+                    );
+                }
+                stamp_contributor(&mut result.overlay, "combine", no_contributor, &project);
+                let json = serde_json::to_string_pretty(&result.overlay)?;
+                save::write(&output, &json)?;
+                tracing::info!(
+                    added = result.added,
+                    replaced = result.replaced,
+                    conflicts = result.conflicts.len(),
+                    contributors = result.overlay.contributors.len(),
+                    path = %output,
+                    "Wrote combined timing overlay"
+                );
+            }
+            TimingAction::Status { base, timing, format } => {
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+                let progress = libretto_validate::stats::TimingProgress::compute(&overlay, &base_libretto);
+                match format {
+                    _ if porcelain::enabled() => porcelain::report(&progress)?,
+                    ReportFormat::Text => print_timing_progress(&progress),
+                    ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&progress)?),
+                }
+            }
+            TimingAction::Compare { first, second, base, format } => {
+                let base = required(project.base(base), "base", "paths.base")?;
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let overlay_a: libretto_model::TimingOverlay = libretto_model::io::load_json(&first)?;
+                let overlay_b: libretto_model::TimingOverlay = libretto_model::io::load_json(&second)?;
+                if overlay_a.base_libretto != overlay_b.base_libretto {
+                    tracing::warn!(
+                        first = %overlay_a.base_libretto, second = %overlay_b.base_libretto,
+                        "Overlays name different base librettos"
+                    );
+                }
+                let rows = libretto_model::compare::compare_overlays(&base_libretto, &overlay_a, &overlay_b);
+                match format {
+                    _ if porcelain::enabled() => porcelain::report(&rows)?,
+                    ReportFormat::Text => print_comparison(&rows),
+                    ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+                }
+            }
+            TimingAction::Retarget { timing, map, output } => {
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                use libretto_model::crosswalk;
+                use libretto_validate::DocumentKind;
+
+                tracing::info!(timing = %timing, map = %map, output = %output, "Retargeting overlay");
+                let contents = std::fs::read_to_string(&timing).with_context(|| format!("Failed to read {timing}"))?;
+                let segment_map: crosswalk::SegmentMap = libretto_model::io::load_json(&map)?;
+                let target = &segment_map.target_base;
+                let kind = DocumentKind::detect(&contents).with_context(|| format!("{timing} is not valid JSON"))?;
+                let (base_libretto, json, warnings) = match kind {
+                    Some(DocumentKind::TimingOverlay) => {
+                        let overlay: libretto_model::TimingOverlay = libretto_model::io::from_json_str(&contents, &timing)?;
+                        let result = crosswalk::retarget_overlay(&overlay, &segment_map, target);
+                        tracing::info!(
+                            tracks = result.overlay.track_timings.len(),
+                            segments = result.overlay.segment_ids().len(),
+                            "Retargeted timing overlay"
+                        );
+                        (overlay.base_libretto, serde_json::to_string_pretty(&result.overlay)?, result.warnings)
+                    }
+                    Some(DocumentKind::AnnotationOverlay) => {
+                        let overlay: libretto_model::AnnotationOverlay = libretto_model::io::from_json_str(&contents, &timing)?;
+                        let (result, warnings) = crosswalk::retarget_annotations(&overlay, &segment_map, target);
+                        (overlay.base_libretto, serde_json::to_string_pretty(&result)?, warnings)
+                    }
+                    Some(DocumentKind::PronunciationOverlay) => {
+                        let overlay: libretto_model::PronunciationOverlay = libretto_model::io::from_json_str(&contents, &timing)?;
+                        let (result, warnings) = crosswalk::retarget_pronunciations(&overlay, &segment_map, target);
+                        (overlay.base_libretto, serde_json::to_string_pretty(&result)?, warnings)
+                    }
+                    _ => anyhow::bail!("{timing} is not a timing, annotation or pronunciation overlay"),
+                };
+                if base_libretto != segment_map.source_base {
+                    tracing::warn!(
+                        overlay_base = %base_libretto,
+                        map_source = %segment_map.source_base,
+                        "Overlay base does not match the map's source base"
+                    );
+                }
+                for w in &warnings {
+                    tracing::warn!("{w}");
+                }
+                save::write(&output, &json)?;
+                tracing::info!(dropped = warnings.len(), path = %output, "Wrote retargeted overlay");
+            }
+            TimingAction::Merge { base, timing, annotations, pronunciation, synopsis, absolute_times, include_untimed, duplicates, output, per_track, split_by, audio } => {
+                use libretto_model::merge::DuplicateStrategy;
+                let base = required(project.base(base), "base", "paths.base")?;
+                let timing = required(project.timing(timing), "timing", "paths.timing")?;
+                let annotations = project.annotations(annotations);
+                let pronunciation = project.pronunciation(pronunciation);
+                let synopsis = project.synopsis(synopsis);
                 tracing::info!(base = %base, timing = %timing, output = %output, "Merging");
-                let base_contents = std::fs::read_to_string(&base)?;
-                let base_libretto: libretto_model::BaseLibretto =
-                    serde_json::from_str(&base_contents)?;
-                let overlay_contents = std::fs::read_to_string(&timing)?;
-                let overlay: libretto_model::TimingOverlay =
-                    serde_json::from_str(&overlay_contents)?;
+                let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                let overlay: libretto_model::TimingOverlay = libretto_model::io::load_json(&timing)?;
+
+                // Validate before merging; warnings are logged but don't block.
+                // Repeated segment references are left to --duplicates.
+                let lint_config = load_lint_config(None)?;
+                let mut findings = lint_config.apply(libretto_validate::validate_timing_overlay(&overlay, &base_libretto)?);
+                findings.retain(|f| !matches!(f.error, libretto_validate::ValidationError::DuplicateSegmentReference { .. }));
+                libretto_validate::log_findings(&findings);
+                if libretto_validate::lint::is_failure(&findings, false) {
+                    anyhow::bail!("timing overlay has validation errors — fix before merging");
+                }
+
+                let duplicates = match duplicates {
+                    Some(DuplicatesArg::Allow) => DuplicateStrategy::Allow,
+                    Some(DuplicatesArg::Warn) => DuplicateStrategy::Warn,
+                    Some(DuplicatesArg::Error) => DuplicateStrategy::Error,
+                    Some(DuplicatesArg::KeepFirst) => DuplicateStrategy::KeepFirst,
+                    None => project.duplicates(),
+                };
+                let options = libretto_model::merge::MergeOptions { include_untimed, duplicates };
+                let mut result = libretto_model::merge::merge_with(&base_libretto, &overlay, &options)?;
+                for w in &result.warnings {
+                    tracing::warn!("{w}");
+                }
+                if include_untimed {
+                    tracing::info!(segments = result.stats.untimed_segments, "Included untimed segments");
+                }
 
-                // Validate before merging
-                let errors = libretto_validate::validate_timing_overlay(&overlay, &base_libretto)?;
-                if !errors.is_empty() {
-                    for e in &errors {
-                        tracing::error!("{e}");
+                if let Some(annotations_path) = annotations {
+                    let annotation_overlay: libretto_model::AnnotationOverlay =
+                        libretto_model::io::load_json(&annotations_path)?;
+                    let findings = lint_config.apply(
+                        libretto_validate::validate_annotation_overlay(&annotation_overlay, &base_libretto)?,
+                    );
+                    libretto_validate::log_findings(&findings);
+                    if libretto_validate::lint::is_failure(&findings, false) {
+                        anyhow::bail!("annotation overlay has validation errors — fix before merging");
                     }
-                    anyhow::bail!("{} validation errors — fix before merging", errors.len());
+                    for w in libretto_model::merge::merge_annotations(&mut result.libretto, &annotation_overlay) {
+                        tracing::warn!("{w}");
+                    }
+                    tracing::info!(annotations = result.libretto.annotations.len(), "Merged annotations");
                 }
 
-                let result = libretto_model::merge::merge(&base_libretto, &overlay);
+                if let Some(pronunciation_path) = pronunciation {
+                    let pronunciation_overlay: libretto_model::PronunciationOverlay =
+                        libretto_model::io::load_json(&pronunciation_path)?;
+                    let findings = lint_config.apply(
+                        libretto_validate::validate_pronunciation_overlay(&pronunciation_overlay, &base_libretto)?,
+                    );
+                    libretto_validate::log_findings(&findings);
+                    if libretto_validate::lint::is_failure(&findings, false) {
+                        anyhow::bail!("pronunciation overlay has validation errors — fix before merging");
+                    }
+                    for w in libretto_model::merge::merge_pronunciations(&mut result.libretto, &pronunciation_overlay) {
+                        tracing::warn!("{w}");
+                    }
+                    let segments = result.libretto.tracks.iter()
+                        .flat_map(|t| &t.segments)
+                        .filter(|s| s.pronunciation.is_some())
+                        .count();
+                    tracing::info!(segments, "Merged pronunciations");
+                }
+
+                if let Some(synopsis_path) = synopsis {
+                    let synopsis_overlay: libretto_model::SynopsisOverlay = libretto_model::io::load_json(&synopsis_path)?;
+                    let findings = lint_config.apply(
+                        libretto_validate::validate_synopsis_overlay(&synopsis_overlay, &base_libretto)?,
+                    );
+                    libretto_validate::log_findings(&findings);
+                    if libretto_validate::lint::is_failure(&findings, false) {
+                        anyhow::bail!("synopsis overlay has validation errors — fix before merging");
+                    }
+                    let warnings = libretto_model::merge::merge_synopses(&mut result.libretto, &synopsis_overlay);
+                    for w in &warnings {
+                        tracing::warn!("{w}");
+                    }
+                    tracing::info!(synopses = synopsis_overlay.synopses.len() - warnings.len(), "Merged synopses");
+                }
+
+                if absolute_times {
+                    for w in libretto_model::timeline::apply_absolute_times(&mut result.libretto) {
+                        tracing::warn!("{w}");
+                    }
+                }
+
+                if let Some(dir) = per_track {
+                    let files = match &audio {
+                        Some(audio) => libretto_validate::audio::scan_audio_dir(std::path::Path::new(audio))?,
+                        None => Vec::new(),
+                    };
+                    // Name every file before writing any, so two tracks
+                    // that come out with one name stop the whole operation
+                    let mut names: Vec<String> = Vec::new();
+                    for track in &result.libretto.tracks {
+                        let mut file_name = track.segments_file_name();
+                        if audio.is_some() {
+                            let stem = libretto_validate::audio::match_track(&track.title, track.disc_number, track.track_number, &files)
+                                .and_then(|f| f.path.file_stem())
+                                .map(|stem| stem.to_string_lossy().into_owned());
+                            match stem {
+                                Some(stem) => file_name = format!("{stem}.segments.json"),
+                                None => tracing::warn!(track = %track.track_id, file = %file_name, "No audio file for track; using the default name"),
+                            }
+                        }
+                        if let Some(other) = names.iter().position(|name| *name == file_name) {
+                            anyhow::bail!(
+                                "Tracks {} and {} would both be written to {file_name}; nothing written",
+                                result.libretto.tracks[other].track_id,
+                                track.track_id
+                            );
+                        }
+                        names.push(file_name);
+                    }
+                    save::create_dir_all(&dir)?;
+                    for (i, file_name) in names.iter().enumerate() {
+                        let path = std::path::Path::new(&dir).join(file_name);
+                        save::write(&path, libretto_model::encoding::to_bytes(&result.libretto.single_track(i), libretto_model::encoding::Encoding::from_path(&path))?)?;
+                    }
+                    tracing::info!(
+                        tracks = result.stats.tracks,
+                        segments = result.stats.merged_segments,
+                        dir = %dir,
+                        "Wrote per-track interchange files"
+                    );
+                } else if let Some(SplitBy::Act) = split_by {
+                    let encoding = libretto_model::encoding::Encoding::from_path(std::path::Path::new(&output));
+                    let acts = result.libretto.split_by_act();
+                    // Name every file before writing any, as for --per-track
+                    let paths: Vec<String> = acts.iter().enumerate().map(|(i, (act, _))| act_file_name(&output, i, act)).collect();
+                    for (i, path) in paths.iter().enumerate() {
+                        if let Some(other) = paths[..i].iter().position(|p| p == path) {
+                            anyhow::bail!("Acts {} and {} would both be written to {path}; nothing written", acts[other].0, acts[i].0);
+                        }
+                    }
+                    for ((act, doc), path) in acts.iter().zip(&paths) {
+                        save::write(path, libretto_model::encoding::to_bytes(doc, encoding)?)?;
+                        tracing::info!(act = %act, tracks = doc.tracks.len(), path = %path, "Wrote act interchange libretto");
+                    }
+                    tracing::info!(
+                        acts = acts.len(),
+                        tracks = result.stats.tracks,
+                        segments = result.stats.merged_segments,
+                        encoding = %encoding,
+                        "Wrote per-act interchange files"
+                    );
+                } else {
+                    let encoding = libretto_model::encoding::Encoding::from_path(std::path::Path::new(&output));
+                    save::write(&output, libretto_model::encoding::to_bytes(&result.libretto, encoding)?)?;
+                    tracing::info!(
+                        tracks = result.stats.tracks,
+                        segments = result.stats.merged_segments,
+                        encoding = %encoding,
+                        path = %output,
+                        "Wrote interchange libretto"
+                    );
+                }
+            }
+        },
+        Commands::Stats { file, base, format } => {
+            use libretto_validate::stats;
+            use libretto_validate::DocumentKind;
+            let print_gaps = |doc: &libretto_model::InterchangeLibretto| -> Result<()> {
+                let tracks = libretto_model::gaps::analyze(doc);
+                match format {
+                    _ if porcelain::enabled() => porcelain::report(&tracks)?,
+                    ReportFormat::Text => print_track_gaps(&tracks),
+                    ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&tracks)?),
+                }
+                Ok(())
+            };
+            use libretto_model::encoding::{self, Encoding};
+            let path = std::path::Path::new(&file);
+            if Encoding::from_path(path) != Encoding::Json {
+                return print_gaps(&encoding::read_interchange(path)?);
+            }
+            let contents = std::fs::read_to_string(&file).with_context(|| format!("Failed to read {file}"))?;
+            match DocumentKind::detect(&contents).with_context(|| format!("{file} is not valid JSON"))? {
+                Some(DocumentKind::BaseLibretto) => {
+                    let libretto: libretto_model::BaseLibretto = libretto_model::io::from_json_str(&contents, &file)?;
+                    let stats = stats::LibrettoStats::compute(&libretto);
+                    match format {
+                        _ if porcelain::enabled() => porcelain::report(&stats)?,
+                        ReportFormat::Text => print_libretto_stats(&libretto.opera.title, &stats),
+                        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&stats)?),
+                    }
+                }
+                Some(DocumentKind::TimingOverlay) => {
+                    let base = required(project.base(base), "base", "paths.base")?;
+                    let overlay: libretto_model::TimingOverlay = libretto_model::io::from_json_str(&contents, &file)?;
+                    let base_libretto: libretto_model::BaseLibretto = libretto_model::io::load_json(&base)?;
+                    let durations = stats::TimedDurations::compute(&overlay, &base_libretto);
+                    match format {
+                        _ if porcelain::enabled() => porcelain::report(&durations)?,
+                        ReportFormat::Text => print_timed_durations(&durations),
+                        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&durations)?),
+                    }
+                }
+                Some(DocumentKind::Interchange) => {
+                    print_gaps(&libretto_model::io::from_json_str(&contents, &file)?)?;
+                }
+                _ => anyhow::bail!("{file}: not a base libretto, timing overlay, or interchange document"),
+            }
+        }
+        #[cfg(feature = "serve")]
+        Commands::Serve { root, listen } => serve::serve(&root, &listen).await?,
+        Commands::Show { file, number, segment, track, timing } => {
+            let file = required(project.base(file), "file", "paths.base")?;
+            let base: libretto_model::BaseLibretto = libretto_model::io::load_json(&file)?;
+            let overlay: Option<libretto_model::TimingOverlay> = project.timing(timing).as_deref()
+                .map(libretto_model::io::load_json)
+                .transpose()?;
+            let unit = match (&number, &segment, &track) {
+                (Some(id), _, _) => show::Unit::Number(id),
+                (_, Some(id), _) => show::Unit::Segment(id),
+                (_, _, Some(id)) => show::Unit::Track(id),
+                _ => unreachable!("clap requires one of --number, --segment, --track"),
+            };
+            let text = show::show(&base, overlay.as_ref(), unit)?;
+            if porcelain::enabled() {
+                porcelain::report(&text)?;
+            } else {
+                print!("{text}");
+            }
+        }
+        Commands::Selftest { write } => {
+            let results = libretto_selftest::run()?;
+            for result in &results {
                 for w in &result.warnings {
+                    tracing::debug!(stage = result.stage, "{w}");
+                }
+            }
+            if let Some(dir) = &write {
+                save::create_dir_all(dir)?;
+                for result in &results {
+                    save::write(std::path::Path::new(dir).join(result.file), &result.output)?;
+                }
+                tracing::info!(dir = %dir, files = results.len(), "Wrote pipeline outputs");
+            }
+            if porcelain::enabled() {
+                porcelain::report(&results)?;
+            } else {
+                print_selftest(&results);
+            }
+            let failed = results.iter().filter(|r| !r.passed).count();
+            if failed > 0 {
+                anyhow::bail!("{failed} of {} pipeline outputs differ from their golden files", results.len());
+            }
+            tracing::info!(outputs = results.len(), "Pipeline outputs match their golden files");
+        }
+        Commands::Library { action } => match action {
+            LibraryAction::Index { root, output } => {
+                tracing::info!(root = %root, "Scanning library");
+                let scan = libretto_model::library::scan_library(std::path::Path::new(&root))?;
+                for w in &scan.warnings {
                     tracing::warn!("{w}");
                 }
-                let json = serde_json::to_string_pretty(&result.libretto)?;
-                std::fs::write(&output, &json)?;
+                let output = output.unwrap_or_else(|| {
+                    std::path::Path::new(&root)
+                        .join(libretto_model::library::LIBRARY_INDEX_FILE)
+                        .to_string_lossy()
+                        .into_owned()
+                });
+                let json = serde_json::to_string_pretty(&scan.index)?;
+                save::write(&output, &json)?;
+                let bases: usize = scan.index.operas.iter().map(|o| o.bases.len()).sum();
                 tracing::info!(
-                    tracks = result.stats.tracks,
-                    segments = result.stats.merged_segments,
+                    operas = scan.index.operas.len(),
+                    bases = bases,
+                    recordings = scan.index.recordings().count(),
                     path = %output,
-                    "Wrote interchange libretto"
+                    "Wrote library index"
                 );
             }
         },
@@ -341,3 +2339,326 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// `output` with `.act-<act>` after the first part of its file name:
+/// `figaro.timed.json` becomes `figaro.act-1.timed.json`. An act whose
+/// name has no letters or digits goes by `index + 1` instead.
+fn act_file_name(output: &str, index: usize, act: &str) -> String {
+    if act.is_empty() {
+        return output.to_string();
+    }
+    let slug = match libretto_model::export::slug(act) {
+        slug if slug.is_empty() => (index + 1).to_string(),
+        slug => slug,
+    };
+    let path = std::path::Path::new(output);
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match name.split_once('.') {
+        Some((stem, rest)) => format!("{stem}.act-{slug}.{rest}"),
+        None => format!("{name}.act-{slug}"),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Credit the person running a timing command in the overlay it writes,
+/// as `role`: named by `LIBRETTO_CONTRIBUTOR`, the project's `[contributor]`,
+/// or else git's `user.name`.
+fn stamp_contributor(overlay: &mut libretto_model::TimingOverlay, role: &str, disabled: bool, project: &ProjectConfig) {
+    if disabled {
+        return;
+    }
+    let name = std::env::var("LIBRETTO_CONTRIBUTOR").ok()
+        .or_else(|| project.contributor().map(str::to_string))
+        .or_else(|| {
+            let out = std::process::Command::new("git").args(["config", "user.name"]).output().ok()?;
+            out.status.success().then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    match name {
+        Some(name) => {
+            let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+            overlay.stamp_contributor(&name, role, &today);
+        }
+        None => tracing::warn!(
+            "No contributor recorded: set LIBRETTO_CONTRIBUTOR, [contributor] name in libretto.toml, or git's user.name, \
+             or pass --no-contributor"
+        ),
+    }
+}
+
+/// `libretto validate` exit status when validation fails (warnings alone exit 0 unless `--strict`).
+const EXIT_INVALID: i32 = 1;
+/// `libretto validate` exit status when a file or configuration cannot be read or parsed.
+const EXIT_IO_FAILURE: i32 = 2;
+
+/// Indexes of the overlay tracks to adjust: the one named by `track`, or all of them.
+fn select_tracks(overlay: &libretto_model::TimingOverlay, track: Option<&str>, timing: &str) -> Result<Vec<usize>> {
+    match track {
+        Some(id) => overlay.find_track(id)
+            .map(|index| vec![index])
+            .with_context(|| format!("No track '{id}' in {timing}")),
+        None => Ok((0..overlay.track_timings.len()).collect()),
+    }
+}
+
+/// Load the lint configuration from an explicit path, or discover the
+/// nearest `libretto-lint.toml` from the current directory.
+fn load_lint_config(path: Option<&str>) -> Result<libretto_validate::LintConfig> {
+    match path {
+        Some(p) => libretto_validate::LintConfig::load(std::path::Path::new(p)),
+        None => libretto_validate::LintConfig::discover(&std::env::current_dir()?),
+    }
+}
+
+/// Print a per-file pass/fail table for a batch validation.
+fn print_batch_summary(batch: &libretto_validate::batch::BatchReport) {
+    println!("{:<6} {:>6} {:>8}  FILE", "RESULT", "ERRORS", "WARNINGS");
+    for r in &batch.reports {
+        let result = if r.passed { "PASS" } else { "FAIL" };
+        match &r.base {
+            Some(base) => println!("{result:<6} {:>6} {:>8}  {} (base: {base})", r.errors, r.warnings, r.file),
+            None => println!("{result:<6} {:>6} {:>8}  {}", r.errors, r.warnings, r.file),
+        }
+    }
+    for e in &batch.load_errors {
+        println!("{:<6} {:>6} {:>8}  {}: {}", "LOAD", "-", "-", e.file, e.message);
+    }
+    println!("\n{} files, {} failed", batch.files, batch.failed);
+}
+
+/// Print timing progress as per-track and per-act tables of segment times
+/// by source, then the tracks and numbers still to do.
+fn print_timing_progress(progress: &libretto_validate::stats::TimingProgress) {
+    println!(
+        "{:<8} {:>8} {:>8} {:>5} {:>5} {:>5} {:>5} {:>5}  TITLE",
+        "TRACK", "DURATION", "RESOLVED", "EST", "ALN", "IMP", "TAP", "VER"
+    );
+    let yes_no = |b: bool| if b { "yes" } else { "-" };
+    for track in &progress.tracks {
+        let s = &track.sources;
+        println!(
+            "{:<8} {:>8} {:>8} {:>5} {:>5} {:>5} {:>5} {:>5}  {}",
+            track.track_id, yes_no(track.has_duration), yes_no(track.resolved),
+            s.estimated, s.aligned, s.imported, s.tapped, s.verified, track.track_title
+        );
+    }
+    println!(
+        "\n{:<8} {:>10} {:>8} {:>5} {:>5} {:>5} {:>5} {:>5}",
+        "ACT", "TIMED", "PERCENT", "EST", "ALN", "IMP", "TAP", "VER"
+    );
+    for act in &progress.by_act {
+        let s = &act.sources;
+        println!(
+            "{:<8} {:>10} {:>7.1}% {:>5} {:>5} {:>5} {:>5} {:>5}",
+            act.act, format!("{}/{}", s.total(), act.segments), act.percent(),
+            s.estimated, s.aligned, s.imported, s.tapped, s.verified
+        );
+    }
+    let count = |f: fn(&libretto_validate::stats::TrackProgress) -> bool| progress.tracks.iter().filter(|t| f(t)).count();
+    println!(
+        "\n{}/{} tracks with durations, {}/{} resolved",
+        count(|t| t.has_duration), progress.tracks.len(), count(|t| t.resolved), progress.tracks.len()
+    );
+    if progress.unaccounted_numbers.is_empty() {
+        println!("All numbers covered or omitted");
+    } else {
+        println!("Unaccounted numbers: {}", progress.unaccounted_numbers.join(", "));
+    }
+}
+
+/// Ask on the terminal for the start segment of each track whose first
+/// anchor is ambiguous or unmatched, recording answers as manual matches.
+fn choose_segments_interactively(
+    base: &libretto_model::BaseLibretto,
+    result: &mut libretto_model::resolve::ResolveResult,
+) -> anyhow::Result<()> {
+    use std::io::{BufRead, Write};
+
+    let mut lines = std::io::stdin().lock().lines();
+    for i in 0..result.resolutions.len() {
+        let res = &result.resolutions[i];
+        let Some(anchor) = res.anchors.first() else {
+            continue;
+        };
+        let candidates: Vec<String> = match &res.resolved_segment_id {
+            _ if res.match_method == Some(libretto_model::resolve::MatchMethod::Manual) => continue,
+            Some(id) if !res.alternatives.is_empty() => {
+                std::iter::once(id.clone()).chain(res.alternatives.iter().cloned()).collect()
+            }
+            Some(_) => continue,
+            None => libretto_model::resolve::suggest_segments(base, anchor, &result.overlay.track_timings[i].number_ids, 5),
+        };
+        let keep = match &res.resolved_segment_id {
+            Some(id) => format!("keep {id}"),
+            None => "skip".to_string(),
+        };
+
+        println!(
+            "\nD{}T{} {}\n  Anchor \"{}\" matches {}:",
+            res.disc_number.unwrap_or(0), res.track_number.unwrap_or(0), res.track_title, anchor,
+            if res.resolved_segment_id.is_some() { "several segments" } else { "no segment; closest" }
+        );
+        for (n, id) in candidates.iter().enumerate() {
+            let segment = base.find_segment(id);
+            let character = segment.and_then(|s| s.character.as_deref()).unwrap_or("");
+            let first_line = segment
+                .and_then(|s| s.text.as_deref().or(s.direction.as_deref()))
+                .and_then(|t| t.lines().next())
+                .unwrap_or("");
+            println!("  [{}] {:<16} {:<12} {}", n + 1, id, character, first_line);
+        }
+
+        loop {
+            print!("  Choose 1-{}, type a segment ID, Enter to {keep}, or q to stop: ", candidates.len());
+            std::io::stdout().flush()?;
+            let Some(line) = lines.next().transpose()? else {
+                return Ok(());
+            };
+            let answer = line.trim();
+            let chosen = match answer {
+                "" => break,
+                "q" => return Ok(()),
+                _ => match answer.parse::<usize>() {
+                    Ok(n) => candidates.get(n.wrapping_sub(1)).cloned(),
+                    Err(_) => base.find_segment(answer).map(|s| s.id.clone()),
+                },
+            };
+            match chosen {
+                Some(id) => {
+                    result.choose_segment(i, &id);
+                    break;
+                }
+                None => println!("  No choice or segment '{answer}'"),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_comparison(rows: &[libretto_model::compare::NumberComparison]) {
+    use libretto_model::compare::Presence;
+
+    println!("{:<12} {:>9} {:>9} {:>6}  LABEL", "NUMBER", "FIRST", "SECOND", "RATIO");
+    let seconds = |d: Option<f64>| d.map(|d| format!("{d:.1}")).unwrap_or_else(|| "-".to_string());
+    for row in rows {
+        let flag = match row.presence {
+            Presence::Both | Presence::Neither => "",
+            Presence::FirstOnly => "  <- cut in second",
+            Presence::SecondOnly => "  <- cut in first",
+        };
+        println!(
+            "{:<12} {:>9} {:>9} {:>6}  {}{}",
+            row.number_id, seconds(row.first), seconds(row.second),
+            row.ratio.map(|r| format!("{r:.2}")).unwrap_or_else(|| "-".to_string()), row.label, flag
+        );
+    }
+    let both: Vec<_> = rows.iter().filter(|r| r.presence == Presence::Both).collect();
+    let total = |f: fn(&libretto_model::compare::NumberComparison) -> Option<f64>| -> Option<f64> {
+        both.iter().map(|r| f(r)).sum()
+    };
+    if let (Some(a), Some(b)) = (total(|r| r.first), total(|r| r.second)) {
+        if b > 0.0 {
+            println!("\nNumbers in both: {a:.1}s vs {b:.1}s, ratio {:.2}", a / b);
+        }
+    }
+}
+
+fn print_libretto_stats(title: &str, stats: &libretto_validate::stats::LibrettoStats) {
+    println!(
+        "{title}: {} acts, {} numbers, {} segments, {} words",
+        stats.by_act.len(), stats.numbers, stats.segments, stats.words
+    );
+    println!("\n{:<24} {:>8} {:>9} {:>7}", "ACT", "NUMBERS", "SEGMENTS", "WORDS");
+    for act in &stats.by_act {
+        println!("{:<24} {:>8} {:>9} {:>7}", act.act, act.numbers, act.segments, act.words);
+    }
+    println!("\n{:<24} {:>8} {:>9} {:>7}", "NUMBER", "ACT", "SEGMENTS", "WORDS");
+    for number in &stats.by_number {
+        println!("{:<24} {:>8} {:>9} {:>7}", number.id, number.act, number.segments, number.words);
+    }
+    println!("\n{:<24} {:>8} {:>9} {:>7}", "CHARACTER", "LINES", "SEGMENTS", "WORDS");
+    for character in &stats.characters {
+        println!("{:<24} {:>8} {:>9} {:>7}", character.character, character.lines, character.segments, character.words);
+    }
+    if let Some(coverage) = &stats.translation {
+        println!();
+        print_translation_coverage(coverage);
+    }
+}
+
+fn print_timed_durations(durations: &libretto_validate::stats::TimedDurations) {
+    let minutes = |seconds: f64| format!("{}:{:02}", (seconds / 60.0) as u64, (seconds % 60.0) as u64);
+    println!("{:<24} {:>9} {:>9}", "ACT", "DURATION", "SEGMENTS");
+    for act in &durations.by_act {
+        println!("{:<24} {:>9} {:>9}", act.id, minutes(act.seconds), act.segments);
+    }
+    println!("\n{:<24} {:>9} {:>9}", "NUMBER", "DURATION", "SEGMENTS");
+    for number in &durations.by_number {
+        println!("{:<24} {:>9} {:>9}", number.id, minutes(number.seconds), number.segments);
+    }
+    println!("\n{} timed", minutes(durations.seconds));
+    if durations.open_segments > 0 {
+        println!(
+            "{} segments at the end of tracks without a duration are not counted (run `timing durations`)",
+            durations.open_segments
+        );
+    }
+}
+
+fn print_track_gaps(tracks: &[libretto_model::gaps::TrackGaps]) {
+    println!("{:<24} {:>9} {:>5} {:>9} {:>8} {:>9}", "TRACK", "COVERAGE", "GAPS", "GAP TIME", "LONGEST", "OVERLAPS");
+    for track in tracks {
+        let coverage = track.coverage.map(|c| format!("{c:.1}%")).unwrap_or_else(|| "-".to_string());
+        let longest = track.longest_gap().map(|g| format!("{:.1}s", g.seconds())).unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<24} {:>9} {:>5} {:>9} {:>8} {:>9}",
+            track.track_id, coverage, track.gaps.len(), format!("{:.1}s", track.gap_seconds()), longest, track.overlaps.len()
+        );
+    }
+    if tracks.iter().any(|t| !t.gaps.is_empty()) {
+        println!("\n{:<24} {:>9} {:>9}  BETWEEN", "GAP IN", "FROM", "TO");
+        for track in tracks {
+            for gap in &track.gaps {
+                println!("{:<24} {:>9.1} {:>9.1}  {} and {}", track.track_id, gap.start, gap.end, gap.after, gap.before);
+            }
+        }
+    }
+}
+
+fn print_selftest(results: &[libretto_selftest::StageResult]) {
+    println!("{:<10} {:<24} {:>8} {:>7}", "STAGE", "OUTPUT", "WARNINGS", "RESULT");
+    for result in results {
+        let outcome = if result.passed { "ok" } else { "DIFFERS" };
+        println!("{:<10} {:<24} {:>8} {:>7}", result.stage, result.file, result.warnings.len(), outcome);
+    }
+    for result in results {
+        if let Some(diff) = &result.difference {
+            let line = |l: &Option<String>| l.as_deref().map(|l| l.trim().to_string()).unwrap_or_else(|| "(end of file)".to_string());
+            println!("\n{} line {}:", result.file, diff.line);
+            println!("  expected: {}", line(&diff.expected));
+            println!("  actual:   {}", line(&diff.actual));
+        }
+    }
+}
+
+/// Print translation coverage as per-act and per-number tables, marking
+/// numbers with no translation.
+fn print_translation_coverage(coverage: &libretto_validate::stats::TranslationCoverage) {
+    println!("{:<24} {:>10} {:>8}", "ACT", "TRANSLATED", "PERCENT");
+    for act in &coverage.by_act {
+        println!("{:<24} {:>10} {:>7.1}%", act.id, format!("{}/{}", act.translated, act.translatable), act.percent());
+    }
+    println!("\n{:<24} {:>10} {:>8}", "NUMBER", "TRANSLATED", "PERCENT");
+    for number in &coverage.by_number {
+        let flag = if number.translatable > 0 && number.translated == 0 { "  <- untranslated" } else { "" };
+        println!(
+            "{:<24} {:>10} {:>7.1}%{flag}",
+            number.id, format!("{}/{}", number.translated, number.translatable), number.percent()
+        );
+    }
+    println!(
+        "\n{}/{} segments translated ({:.1}%)",
+        coverage.translated, coverage.translatable, coverage.percent()
+    );
+}
@@ -0,0 +1,177 @@
+// `--porcelain`: one JSON document on stdout per run, for scripts and CI.
+//
+// Commands already report what they did as structured log events ("Wrote
+// resolved timing overlay" with resolved=12 and the path), and warn the
+// same way. With --porcelain those events are collected as well as
+// logged, and when the command ends a single JSON object goes to stdout:
+//
+//     {
+//       "command": "timing resolve",
+//       "ok": true,
+//       "events": [{ "message": "Wrote resolved timing overlay", "resolved": 12, ... }],
+//       "warnings": [{ "message": "Ambiguous anchor", "track": "d1-t5", ... }],
+//       "errors": [],
+//       "report": { ... }
+//     }
+//
+// `report` holds what the command would otherwise print: the validation
+// report, stats, `timing status` and `timing compare` tables as JSON,
+// labels from `timing export`, or the text of `libretto show`. A failed
+// command has "ok": false and its "error". Logs still go to stderr at the
+// chosen log level; events are collected from info up regardless, from
+// libretto's own crates only (not from hyper, reqwest and the like).
+
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+#[derive(Debug, Default, Serialize)]
+struct Summary {
+    command: String,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    events: Vec<Map<String, Value>>,
+    warnings: Vec<Map<String, Value>>,
+    errors: Vec<Map<String, Value>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<Value>,
+}
+
+/// The run's summary, present once porcelain output is enabled.
+static SUMMARY: Mutex<Option<Summary>> = Mutex::new(None);
+
+fn with_summary<T>(f: impl FnOnce(&mut Summary) -> T) -> Option<T> {
+    SUMMARY.lock().unwrap_or_else(|e| e.into_inner()).as_mut().map(f)
+}
+
+/// Start collecting for `command`, e.g. `timing resolve`.
+pub fn enable(command: &str) {
+    *SUMMARY.lock().unwrap_or_else(|e| e.into_inner()) = Some(Summary {
+        command: command.to_string(),
+        ..Default::default()
+    });
+}
+
+pub fn enabled() -> bool {
+    with_summary(|_| ()).is_some()
+}
+
+/// Set the run's report in place of printing it.
+pub fn report(value: &impl Serialize) -> Result<()> {
+    let value = serde_json::to_value(value)?;
+    with_summary(|summary| summary.report = Some(value));
+    Ok(())
+}
+
+/// Print the summary of a run that ended with `result`, if collecting.
+pub fn finish(result: &Result<()>) {
+    let Some(json) = summary_json(result) else {
+        return;
+    };
+    match json {
+        Ok(json) => println!("{json}"),
+        Err(e) => eprintln!("Failed to write the porcelain summary: {e}"),
+    }
+}
+
+fn summary_json(result: &Result<()>) -> Option<serde_json::Result<String>> {
+    with_summary(|summary| {
+        summary.ok = result.is_ok();
+        summary.error = result.as_ref().err().map(|e| format!("{e:#}"));
+        serde_json::to_string_pretty(summary)
+    })
+}
+
+/// Print the summary of a run that failed with `error`, then exit with
+/// `code`; for commands whose exit status tells failures apart.
+pub fn exit(code: i32, error: &str) -> ! {
+    finish(&Err(anyhow::anyhow!("{error}")));
+    std::process::exit(code)
+}
+
+/// Collects info, warning and error events from libretto's crates into
+/// the summary.
+pub struct Collector;
+
+impl<S: Subscriber> Layer<S> for Collector {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        // The binary's target is `libretto`, the libraries' `libretto_*`
+        if !event.metadata().target().starts_with("libretto") {
+            return;
+        }
+        let mut fields = Fields(Map::new());
+        event.record(&mut fields);
+        let level = *event.metadata().level();
+        with_summary(|summary| match level {
+            Level::ERROR => summary.errors.push(fields.0),
+            Level::WARN => summary.warnings.push(fields.0),
+            _ => summary.events.push(fields.0),
+        });
+    }
+}
+
+struct Fields(Map<String, Value>);
+
+impl Visit for Fields {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_string(), format!("{value:?}").into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_summary_json() {
+        let subscriber = tracing_subscriber::registry().with(Collector);
+        tracing::subscriber::with_default(subscriber, || {
+            enable("timing resolve");
+            tracing::info!(resolved = 12, path = "figaro.timing.json", "Wrote resolved timing overlay");
+            tracing::warn!(track = "d1-t5", "Ambiguous anchor");
+            tracing::warn!(target: "hyper::proto", "connection closed");
+        });
+        let json = summary_json(&Err(anyhow::anyhow!("1 track unresolved"))).unwrap().unwrap();
+        *SUMMARY.lock().unwrap() = None;
+
+        let summary: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            summary,
+            serde_json::json!({
+                "command": "timing resolve",
+                "ok": false,
+                "error": "1 track unresolved",
+                "events": [{ "message": "Wrote resolved timing overlay", "resolved": 12, "path": "figaro.timing.json" }],
+                "warnings": [{ "message": "Ambiguous anchor", "track": "d1-t5" }],
+                "errors": [],
+            })
+        );
+    }
+}
@@ -0,0 +1,77 @@
+// A progress line on stderr for commands that work through many files.
+//
+// When stderr is a terminal, the line is redrawn in place as each item is
+// done: `Validating [=========>          ] 48/103 puccini/tosca/base.libretto.json`.
+// Otherwise, and in porcelain output, nothing is drawn; the command's log
+// events say what it did. Log lines are written through `LogWriter`, which
+// clears the progress line first so the two do not run together.
+
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+
+const WIDTH: usize = 30;
+
+/// Length of the progress line on screen, 0 when there is none.
+static DRAWN: Mutex<usize> = Mutex::new(0);
+
+fn drawn() -> std::sync::MutexGuard<'static, usize> {
+    DRAWN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn clear(drawn: &mut usize) {
+    if *drawn > 0 {
+        eprint!("\r{}\r", " ".repeat(*drawn));
+        *drawn = 0;
+    }
+}
+
+pub struct Progress {
+    label: &'static str,
+    shown: bool,
+}
+
+impl Progress {
+    pub fn new(label: &'static str) -> Self {
+        let shown = std::io::stderr().is_terminal() && !crate::porcelain::enabled();
+        Progress { label, shown }
+    }
+
+    /// Redraw the line for `done` of `total` items, the latest being `item`.
+    pub fn update(&self, done: usize, total: usize, item: &str) {
+        if !self.shown || total == 0 {
+            return;
+        }
+        let filled = WIDTH * done / total;
+        let bar = match filled {
+            WIDTH => "=".repeat(WIDTH),
+            _ => format!("{}>{}", "=".repeat(filled), " ".repeat(WIDTH - filled - 1)),
+        };
+        let line = format!("{} [{bar}] {done}/{total} {item}", self.label);
+        let mut drawn = drawn();
+        let blank = drawn.saturating_sub(line.chars().count());
+        eprint!("\r{line}{}", " ".repeat(blank));
+        let _ = std::io::stderr().flush();
+        *drawn = line.chars().count();
+    }
+
+    /// Clear the line, for the summary to follow.
+    pub fn finish(&self) {
+        clear(&mut drawn());
+    }
+}
+
+/// Stderr for log lines; the next progress update redraws its line below
+/// them.
+pub struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut drawn = drawn();
+        clear(&mut drawn);
+        std::io::stderr().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
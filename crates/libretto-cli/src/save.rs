@@ -0,0 +1,252 @@
+// Writing output files: `--dry-run` and backups.
+//
+// Every file a command writes goes through `write`, so two safeguards
+// apply to all of them alike.
+//
+// With --dry-run nothing is written. Each file is reported instead as
+// new, unchanged, or changed, and for a changed file the lines that
+// differ are printed as `-` and `+` lines (from the first difference to
+// the last, so scattered edits show the lines between them too).
+//
+// Otherwise a file about to be overwritten with different contents is
+// first copied aside, as `[write] backup` in libretto.toml says (the new
+// contents go to a temporary file beside it that is then renamed over
+// it, so an interrupted write never leaves half a file):
+//
+//     [write]
+//     backup = "bak"       # figaro.timing.json.bak, replaced each time (default)
+//     backup = "numbered"  # figaro.timing.json.1.bak, .2.bak, ...: every version
+//     backup = "none"
+//
+// `libretto acquire` writes its raw text itself: with --dry-run it only
+// says where it would fetch to, and what it overwrites is not backed up,
+// as it can be fetched again.
+
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// What to keep of a file before overwriting it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backup {
+    /// One `.bak` copy, of the version before the latest write.
+    #[default]
+    Bak,
+    /// A new `.N.bak` copy each time.
+    Numbered,
+    None,
+}
+
+struct Options {
+    dry_run: bool,
+    backup: Backup,
+}
+
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+/// Most changed lines printed per side of a dry-run difference.
+const MAX_DIFF_LINES: usize = 20;
+
+/// Set how this run writes files.
+pub fn configure(dry_run: bool, backup: Backup) {
+    let _ = OPTIONS.set(Options { dry_run, backup });
+}
+
+pub fn dry_run() -> bool {
+    OPTIONS.get().is_some_and(|o| o.dry_run)
+}
+
+/// Write `contents` to `path`, backing up what it replaces; or, with
+/// --dry-run, report what would change.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<()> {
+    let default = Options { dry_run: false, backup: Backup::default() };
+    write_with(path.as_ref(), contents.as_ref(), OPTIONS.get().unwrap_or(&default))
+}
+
+fn write_with(path: &Path, contents: &[u8], options: &Options) -> Result<()> {
+    let existing = match std::fs::read(path) {
+        Ok(existing) => Some(existing),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let changed = existing.as_deref().is_some_and(|e| e != contents);
+
+    if options.dry_run {
+        match &existing {
+            None => tracing::info!(path = %path.display(), bytes = contents.len(), "Would write new file"),
+            Some(_) if !changed => tracing::info!(path = %path.display(), "Would leave file unchanged"),
+            Some(existing) => match LineDiff::new(existing, contents) {
+                None => tracing::info!(path = %path.display(), bytes = contents.len(), "Would change file (not text)"),
+                Some(diff) => {
+                    tracing::info!(
+                        path = %path.display(),
+                        removed = diff.removed.len(),
+                        added = diff.added.len(),
+                        "Would change file"
+                    );
+                    if !crate::porcelain::enabled() {
+                        diff.print(path);
+                    }
+                }
+            },
+        }
+        return Ok(());
+    }
+
+    if changed {
+        let backup = match options.backup {
+            Backup::Bak => Some(suffixed(path, ".bak")),
+            Backup::Numbered => (1..)
+                .map(|n| suffixed(path, &format!(".{n}.bak")))
+                .find(|p| !p.exists()),
+            Backup::None => None,
+        };
+        if let Some(backup) = backup {
+            std::fs::copy(path, &backup)
+                .with_context(|| format!("Failed to back up {} to {}", path.display(), backup.display()))?;
+            tracing::debug!(path = %backup.display(), "Backed up previous version");
+        }
+    }
+
+    let temp = suffixed(path, &format!(".{}.tmp", std::process::id()));
+    let replace = || -> std::io::Result<()> {
+        std::fs::write(&temp, contents)?;
+        if existing.is_some() {
+            std::fs::set_permissions(&temp, std::fs::metadata(path)?.permissions())?;
+        }
+        std::fs::rename(&temp, path)
+    };
+    replace().inspect_err(|_| {
+        let _ = std::fs::remove_file(&temp);
+    }).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Create `dir` and its parents, unless this is a dry run.
+pub fn create_dir_all(dir: impl AsRef<Path>) -> Result<()> {
+    let dir = dir.as_ref();
+    if dry_run() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// The lines between the first and last that differ, old and new.
+#[derive(Debug, PartialEq)]
+struct LineDiff<'a> {
+    /// Line number (from 1) of the first difference.
+    line: usize,
+    removed: Vec<&'a str>,
+    added: Vec<&'a str>,
+}
+
+impl<'a> LineDiff<'a> {
+    /// `None` unless both are UTF-8 text.
+    fn new(old: &'a [u8], new: &'a [u8]) -> Option<Self> {
+        let old: Vec<&str> = std::str::from_utf8(old).ok()?.lines().collect();
+        let new: Vec<&str> = std::str::from_utf8(new).ok()?.lines().collect();
+        let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+        let suffix = old[prefix..].iter().rev()
+            .zip(new[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Some(LineDiff {
+            line: prefix + 1,
+            removed: old[prefix..old.len() - suffix].to_vec(),
+            added: new[prefix..new.len() - suffix].to_vec(),
+        })
+    }
+
+    fn print(&self, path: &Path) {
+        println!("{} (from line {}):", path.display(), self.line);
+        for (sign, lines) in [('-', &self.removed), ('+', &self.added)] {
+            for line in lines.iter().take(MAX_DIFF_LINES) {
+                println!("{sign} {line}");
+            }
+            if lines.len() > MAX_DIFF_LINES {
+                println!("{sign} … {} more lines", lines.len() - MAX_DIFF_LINES);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libretto-save-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_backups() {
+        let dir = temp_dir("backups");
+        let path = dir.join("figaro.timing.json");
+        let bak = Options { dry_run: false, backup: Backup::Bak };
+        write_with(&path, b"one", &bak).unwrap();
+        write_with(&path, b"two", &bak).unwrap();
+        write_with(&path, b"three", &bak).unwrap();
+        // Unchanged contents are not backed up
+        write_with(&path, b"three", &bak).unwrap();
+        assert_eq!(names(&dir), ["figaro.timing.json", "figaro.timing.json.bak"]);
+        assert_eq!(std::fs::read(dir.join("figaro.timing.json.bak")).unwrap(), b"two");
+
+        let numbered = Options { dry_run: false, backup: Backup::Numbered };
+        write_with(&path, b"four", &numbered).unwrap();
+        write_with(&path, b"five", &numbered).unwrap();
+        assert_eq!(std::fs::read(dir.join("figaro.timing.json.1.bak")).unwrap(), b"three");
+        assert_eq!(std::fs::read(dir.join("figaro.timing.json.2.bak")).unwrap(), b"four");
+
+        write_with(&path, b"six", &Options { dry_run: false, backup: Backup::None }).unwrap();
+        assert_eq!(names(&dir).len(), 4);
+        assert_eq!(std::fs::read(&path).unwrap(), b"six");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_writes_nothing() {
+        let dir = temp_dir("dry-run");
+        let path = dir.join("figaro.timing.json");
+        let dry_run = Options { dry_run: true, backup: Backup::Numbered };
+        write_with(&path, b"one", &dry_run).unwrap();
+        assert!(names(&dir).is_empty());
+
+        std::fs::write(&path, b"one").unwrap();
+        write_with(&path, b"two", &dry_run).unwrap();
+        write_with(&path, b"\xff\xfe", &dry_run).unwrap();
+        assert_eq!(names(&dir), ["figaro.timing.json"]);
+        assert_eq!(std::fs::read(&path).unwrap(), b"one");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_line_diff() {
+        let old = b"{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}\n";
+        let new = b"{\n  \"a\": 1,\n  \"b\": 20,\n  \"b2\": 21,\n  \"c\": 3\n}\n";
+        let diff = LineDiff::new(old, new).unwrap();
+        assert_eq!(diff, LineDiff { line: 3, removed: vec!["  \"b\": 2,"], added: vec!["  \"b\": 20,", "  \"b2\": 21,"] });
+
+        let appended = LineDiff::new(b"a\nb", b"a\nb\nc").unwrap();
+        assert_eq!((appended.line, appended.removed.len(), appended.added), (3, 0, vec!["c"]));
+        assert_eq!(LineDiff::new(b"a", b"\xff"), None);
+    }
+}
@@ -0,0 +1,324 @@
+// `libretto serve`: a small read-only HTTP API over a library root.
+//
+// Display clients in other languages (the web player, Roon extensions)
+// can list what the library holds and fetch timed librettos without
+// bundling any Rust. The library index is `library.json` at the root,
+// or a fresh scan when there is none; every interchange document it
+// lists is loaded once at startup.
+//
+// The server can also keep several screens in step with one player. The
+// player (or a bridge from its now-playing events) posts its position
+// as it plays; every screen subscribed to the event stream is sent the
+// segment playing and the one after it, as each position arrives.
+//
+//   GET  /operas                      the index's operas, bases, and recordings
+//   GET  /recordings                  every recording, flattened, with its opera
+//   GET  /interchange/{path}          an interchange document by library path
+//   GET  /segment?track=ID&t=SECONDS  the segment playing at a time in a track;
+//                                     add &file=PATH when track IDs repeat
+//   POST /position                    {"track": ID, "t": SECONDS, "file": PATH?}
+//   GET  /events                      server-sent `now-playing` events
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path as UrlPath, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use libretto_model::library::{self, LibraryIndex};
+use libretto_model::{InterchangeLibretto, InterchangeSegment, InterchangeTrack};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tokio_stream::wrappers::WatchStream;
+use tokio_stream::{Stream, StreamExt};
+
+struct Library {
+    index: LibraryIndex,
+    /// Interchange documents by library-relative path.
+    documents: HashMap<String, InterchangeLibretto>,
+    /// The last position posted, for the event stream.
+    now_playing: watch::Sender<Option<NowPlaying>>,
+}
+
+/// What is playing, as sent to subscribed screens.
+#[derive(Debug, Clone, Serialize)]
+struct NowPlaying {
+    file: String,
+    track_id: String,
+    t: f64,
+    segment: Option<InterchangeSegment>,
+    next: Option<InterchangeSegment>,
+}
+
+impl Library {
+    /// The track `track_id`, in `file` if given, or the only document
+    /// that has it; the status and message to answer with otherwise.
+    fn find_track(&self, track_id: &str, file: Option<&str>) -> Result<(&str, &InterchangeTrack), (StatusCode, String)> {
+        let mut found: Vec<(&str, &InterchangeTrack)> = self.documents.iter()
+            .filter(|(path, _)| file.is_none_or(|f| f == path.as_str()))
+            .flat_map(|(path, doc)| doc.tracks.iter().filter(|t| t.track_id == track_id).map(move |t| (path.as_str(), t)))
+            .collect();
+        found.sort_by_key(|(path, _)| *path);
+        match found.as_slice() {
+            [] => Err((StatusCode::NOT_FOUND, format!("no track '{track_id}' in the library"))),
+            [only] => Ok(*only),
+            several => {
+                let paths: Vec<&str> = several.iter().map(|(p, _)| *p).collect();
+                Err((StatusCode::BAD_REQUEST, format!(
+                    "track '{track_id}' is in several documents ({}); pass file=",
+                    paths.join(", ")
+                )))
+            }
+        }
+    }
+}
+
+/// Load the library under `root` and serve it on `listen` until stopped.
+pub async fn serve(root: &str, listen: &str) -> Result<()> {
+    let library = Arc::new(load_library(Path::new(root))?);
+    tracing::info!(
+        operas = library.index.operas.len(),
+        documents = library.documents.len(),
+        "Loaded library"
+    );
+
+    let app = Router::new()
+        .route("/operas", get(operas))
+        .route("/recordings", get(recordings))
+        .route("/interchange/{*path}", get(interchange))
+        .route("/segment", get(segment))
+        .route("/position", post(position))
+        .route("/events", get(events))
+        .with_state(library);
+
+    let listener = tokio::net::TcpListener::bind(listen).await
+        .with_context(|| format!("Failed to listen on {listen}"))?;
+    tracing::info!(address = %listener.local_addr()?, root = %root, "Serving library");
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+fn load_library(root: &Path) -> Result<Library> {
+    let index_path = root.join(library::LIBRARY_INDEX_FILE);
+    let index: LibraryIndex = if index_path.exists() {
+        libretto_model::io::load_json(&index_path)?
+    } else {
+        tracing::info!(root = %root.display(), "No library index; scanning");
+        let scan = library::scan_library(root)?;
+        for w in &scan.warnings {
+            tracing::warn!("{w}");
+        }
+        scan.index
+    };
+
+    let mut documents = HashMap::new();
+    for file in index.interchange_files() {
+        match libretto_model::encoding::read_interchange(&root.join(&file.path)) {
+            Ok(doc) => {
+                documents.insert(file.path.clone(), doc);
+            }
+            Err(e) => tracing::warn!(path = %file.path, "Skipping unreadable interchange document: {e}"),
+        }
+    }
+    Ok(Library { index, documents, now_playing: watch::Sender::new(None) })
+}
+
+/// A recording with the opera and base libretto it belongs to.
+#[derive(Serialize)]
+struct RecordingEntry<'a> {
+    title: &'a str,
+    composer: &'a str,
+    base: &'a str,
+    #[serde(flatten)]
+    recording: &'a library::LibraryRecording,
+}
+
+async fn operas(State(library): State<Arc<Library>>) -> Response {
+    Json(&library.index.operas).into_response()
+}
+
+async fn recordings(State(library): State<Arc<Library>>) -> Response {
+    let entries: Vec<RecordingEntry> = library.index.operas.iter()
+        .flat_map(|o| o.bases.iter().map(move |b| (o, b)))
+        .flat_map(|(o, b)| {
+            b.recordings.iter().map(move |r| RecordingEntry {
+                title: &o.title,
+                composer: &o.composer,
+                base: &b.path,
+                recording: r,
+            })
+        })
+        .collect();
+    Json(entries).into_response()
+}
+
+async fn interchange(State(library): State<Arc<Library>>, UrlPath(path): UrlPath<String>) -> Response {
+    match library.documents.get(&path) {
+        Some(doc) => Json(doc).into_response(),
+        None => error(StatusCode::NOT_FOUND, format!("no interchange document '{path}' in the library")),
+    }
+}
+
+#[derive(Deserialize)]
+struct SegmentQuery {
+    track: String,
+    t: f64,
+    file: Option<String>,
+}
+
+/// The segment playing at `t` and where it was found.
+#[derive(Serialize)]
+struct SegmentAt<'a> {
+    file: &'a str,
+    track_id: &'a str,
+    segment: Option<&'a InterchangeSegment>,
+}
+
+async fn segment(State(library): State<Arc<Library>>, Query(query): Query<SegmentQuery>) -> Response {
+    match library.find_track(&query.track, query.file.as_deref()) {
+        Ok((path, track)) => Json(SegmentAt {
+            file: path,
+            track_id: &track.track_id,
+            segment: track.segment_at(query.t),
+        })
+        .into_response(),
+        Err((status, message)) => error(status, message),
+    }
+}
+
+/// Take a playback position, and send what is playing at it to every
+/// subscribed screen. The response carries the same.
+async fn position(State(library): State<Arc<Library>>, Json(update): Json<SegmentQuery>) -> Response {
+    let (path, track) = match library.find_track(&update.track, update.file.as_deref()) {
+        Ok(found) => found,
+        Err((status, message)) => return error(status, message),
+    };
+    let now = NowPlaying {
+        file: path.to_string(),
+        track_id: track.track_id.clone(),
+        t: update.t,
+        segment: track.segment_at(update.t).cloned(),
+        next: track.next_segment(update.t).cloned(),
+    };
+    library.now_playing.send_replace(Some(now.clone()));
+    Json(now).into_response()
+}
+
+/// Stream `now-playing` events, starting with the last position posted.
+/// A position that cannot be written as JSON is logged and not sent.
+async fn events(State(library): State<Arc<Library>>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = WatchStream::new(library.now_playing.subscribe())
+        .filter_map(|now| match Event::default().event("now-playing").json_data(now?) {
+            Ok(event) => Some(Ok(event)),
+            Err(e) => {
+                tracing::warn!("Not sending now-playing event: {e}");
+                None
+            }
+        });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+fn error(status: StatusCode, message: String) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> Library {
+        let segment = |id: &str, start: f64| InterchangeSegment {
+            start,
+            text: Some(format!("Text of {id}")),
+            segment_id: Some(id.to_string()),
+            ..Default::default()
+        };
+        let track = |track_id: &str, segments: Vec<InterchangeSegment>| InterchangeTrack {
+            track_id: track_id.to_string(),
+            title: track_id.to_string(),
+            segments,
+            ..Default::default()
+        };
+        let doc = |tracks: Vec<InterchangeTrack>| InterchangeLibretto { tracks, ..Default::default() };
+        let documents = HashMap::from([
+            (
+                "figaro/giulini.interchange.json".to_string(),
+                doc(vec![
+                    track("d1-t1", vec![segment("no-1-001", 0.0)]),
+                    track("d1-t2", vec![segment("no-2-001", 1.5), segment("no-2-002", 9.0)]),
+                ]),
+            ),
+            ("figaro/kleiber.interchange.json".to_string(), doc(vec![track("d1-t1", vec![segment("no-1-001", 2.0)])])),
+        ]);
+        let index = LibraryIndex { version: "1.0".to_string(), generated_at: None, operas: vec![] };
+        Library { index, documents, now_playing: watch::Sender::new(None) }
+    }
+
+    async fn body(response: Response) -> serde_json::Value {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn test_find_track() {
+        let library = library();
+        let (path, track) = library.find_track("d1-t2", None).unwrap();
+        assert_eq!((path, track.track_id.as_str()), ("figaro/giulini.interchange.json", "d1-t2"));
+
+        let (status, message) = library.find_track("d1-t1", None).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(message.contains("(figaro/giulini.interchange.json, figaro/kleiber.interchange.json)"), "{message}");
+        let (path, _) = library.find_track("d1-t1", Some("figaro/kleiber.interchange.json")).unwrap();
+        assert_eq!(path, "figaro/kleiber.interchange.json");
+
+        assert_eq!(library.find_track("d9-t1", None).unwrap_err().0, StatusCode::NOT_FOUND);
+        assert_eq!(library.find_track("d1-t2", Some("figaro/kleiber.interchange.json")).unwrap_err().0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_segment() {
+        let library = Arc::new(library());
+        let query = |track: &str, t: f64| Query(SegmentQuery { track: track.to_string(), t, file: None });
+
+        let response = segment(State(library.clone()), query("d1-t2", 10.0)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let json = body(response).await;
+        assert_eq!(json["file"], "figaro/giulini.interchange.json");
+        assert_eq!(json["segment"]["segment_id"], "no-2-002");
+
+        // Before the first segment
+        let json = body(segment(State(library.clone()), query("d1-t2", 1.0)).await).await;
+        assert_eq!(json["segment"], serde_json::Value::Null);
+
+        let response = segment(State(library), query("d1-t1", 0.0)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(body(response).await["error"].as_str().unwrap().contains("pass file="));
+    }
+
+    #[tokio::test]
+    async fn test_position() {
+        let library = Arc::new(library());
+        let mut screen = library.now_playing.subscribe();
+        let update = SegmentQuery { track: "d1-t2".to_string(), t: 5.0, file: None };
+
+        let json = body(position(State(library.clone()), Json(update)).await).await;
+        assert_eq!(json["segment"]["segment_id"], "no-2-001");
+        assert_eq!(json["next"]["segment_id"], "no-2-002");
+
+        // Subscribed screens are sent the same
+        assert!(screen.has_changed().unwrap());
+        let now = screen.borrow_and_update().clone().unwrap();
+        assert_eq!((now.track_id.as_str(), now.t), ("d1-t2", 5.0));
+        assert_eq!(now.next.and_then(|s| s.segment_id).as_deref(), Some("no-2-002"));
+
+        let unknown = SegmentQuery { track: "d9-t1".to_string(), t: 5.0, file: None };
+        assert_eq!(position(State(library.clone()), Json(unknown)).await.status(), StatusCode::NOT_FOUND);
+        assert!(!screen.has_changed().unwrap());
+    }
+}
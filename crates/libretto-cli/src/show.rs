@@ -0,0 +1,227 @@
+// `libretto show`: one number, segment, or track of a libretto, readably.
+//
+// Checking a parse or a timing by eye otherwise means picking through
+// the JSON with `jq`. This prints the unit asked for the way a printed
+// libretto sets it: the number's heading, each segment under its
+// character's name, directions in italics and translations dimmed
+// beneath the text. With a timing overlay, each segment shows the track
+// and time it starts at.
+//
+// Styles are ANSI escapes, left out when stdout is not a terminal, when
+// NO_COLOR is set, and in porcelain output.
+
+use std::collections::HashMap;
+use std::io::IsTerminal;
+
+use anyhow::{Context, Result};
+use libretto_model::timing_overlay::format_time;
+use libretto_model::{BaseLibretto, MusicalNumber, Segment, TimingOverlay};
+
+const DIM: &str = "2";
+const ITALIC: &str = "3";
+const HEADING: &str = "1;4";
+const CHARACTER: &str = "1;33";
+
+/// Which part of the libretto to show.
+pub enum Unit<'a> {
+    Number(&'a str),
+    Segment(&'a str),
+    Track(&'a str),
+}
+
+/// Render `unit` of `base`, with start times from `overlay` if given.
+pub fn show(base: &BaseLibretto, overlay: Option<&TimingOverlay>, unit: Unit) -> Result<String> {
+    let color = std::io::stdout().is_terminal()
+        && std::env::var_os("NO_COLOR").is_none()
+        && !crate::porcelain::enabled();
+    render(base, overlay, unit, color)
+}
+
+fn render(base: &BaseLibretto, overlay: Option<&TimingOverlay>, unit: Unit, color: bool) -> Result<String> {
+    let show = Show::new(overlay, color);
+    match unit {
+        Unit::Number(id) => {
+            let number = base.find_number(id).with_context(|| format!("No number '{id}' in the base libretto"))?;
+            Ok(show.number(number, |_| true))
+        }
+        Unit::Segment(id) => {
+            let number = base.numbers.iter()
+                .find(|n| n.segments.iter().any(|s| s.id == id))
+                .with_context(|| format!("No segment '{id}' in the base libretto"))?;
+            Ok(show.number(number, |s| s.id == id))
+        }
+        Unit::Track(id) => {
+            let overlay = overlay.context("--track needs a timing overlay")?;
+            let index = overlay.find_track(id).with_context(|| format!("No track '{id}' in the timing overlay"))?;
+            let track = &overlay.track_timings[index];
+            let mut out = show.paint(HEADING, &format!("{} {}", track.track_id(index), track.track_title));
+            if let Some(duration) = track.duration_seconds {
+                out.push_str(&show.paint(DIM, &format!("  ({})", format_time(duration))));
+            }
+            out.push('\n');
+            if track.instrumental {
+                out.push_str(&format!("\n{}\n", show.paint(ITALIC, "(instrumental)")));
+            }
+            // Only the segments timed in this track, when it has any; a
+            // number split over two tracks shows its part in each.
+            let timed: Vec<&str> = track.segment_times.iter().map(|st| st.segment_id.as_str()).collect();
+            for number_id in &track.number_ids {
+                match base.find_number(number_id) {
+                    Some(number) => {
+                        out.push('\n');
+                        out.push_str(&show.number(number, |s| timed.is_empty() || timed.contains(&s.id.as_str())));
+                    }
+                    None => tracing::warn!(number = %number_id, "Track names a number the base libretto does not have"),
+                }
+            }
+            Ok(out)
+        }
+    }
+}
+
+struct Show {
+    color: bool,
+    /// Segment ID → track ID and start.
+    times: HashMap<String, (String, f64)>,
+}
+
+impl Show {
+    fn new(overlay: Option<&TimingOverlay>, color: bool) -> Self {
+        let times = overlay.into_iter()
+            .flat_map(|o| o.track_timings.iter().enumerate())
+            .flat_map(|(i, track)| {
+                let track_id = track.track_id(i);
+                track.segment_times.iter().map(move |st| (st.segment_id.clone(), (track_id.clone(), st.start)))
+            })
+            .collect();
+        Show { color, times }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color { format!("\x1b[{style}m{text}\x1b[0m") } else { text.to_string() }
+    }
+
+    /// The number's heading and those of its segments `include` keeps.
+    fn number(&self, number: &MusicalNumber, include: impl Fn(&Segment) -> bool) -> String {
+        let mut out = self.paint(HEADING, &number.label);
+        let mut about = vec![number.id.clone(), format!("act {}", number.act)];
+        if let Some(scene) = &number.scene {
+            about.push(format!("scene {scene}"));
+        }
+        out.push_str(&self.paint(DIM, &format!("  ({})", about.join(", "))));
+        out.push('\n');
+        for segment in number.segments.iter().filter(|s| include(s)) {
+            out.push('\n');
+            out.push_str(&self.segment(segment));
+        }
+        out
+    }
+
+    fn segment(&self, segment: &Segment) -> String {
+        let mut heading = Vec::new();
+        if let Some((track, start)) = self.times.get(&segment.id) {
+            heading.push(self.paint(DIM, &format!("[{track} {}]", format_time(*start))));
+        }
+        if let Some(character) = &segment.character {
+            heading.push(self.paint(CHARACTER, character));
+        }
+        heading.push(self.paint(DIM, &segment.id));
+        if let Some(group) = &segment.group {
+            heading.push(self.paint(DIM, &format!("(ensemble {group})")));
+        }
+
+        let mut out = format!("  {}\n", heading.join(" "));
+        if let Some(direction) = &segment.direction {
+            for line in lines(direction) {
+                out.push_str(&format!("  {}\n", self.paint(ITALIC, line)));
+            }
+        }
+        if let Some(text) = &segment.text {
+            for line in lines(text) {
+                out.push_str(&format!("  {line}\n"));
+            }
+        }
+        if let Some(translation) = &segment.translation {
+            for line in lines(translation) {
+                out.push_str(&format!("    {}\n", self.paint(DIM, line)));
+            }
+        }
+        out
+    }
+}
+
+fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines().map(str::trim).filter(|l| !l.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> BaseLibretto {
+        serde_json::from_value(serde_json::json!({
+            "version": "1.0",
+            "opera": { "title": "Le nozze di Figaro", "composer": "Mozart", "language": "it" },
+            "cast": [],
+            "numbers": [{
+                "id": "no-1-duettino", "label": "No. 1 - Duettino", "number_type": "duettino", "act": "1",
+                "segments": [
+                    { "id": "no-1-duettino-001", "segment_type": "direction", "direction": "Figaro misura la camera." },
+                    { "id": "no-1-duettino-002", "segment_type": "sung", "character": "FIGARO",
+                      "text": "Cinque... dieci...\nventi... trenta...", "translation": "Five... ten..." },
+                    { "id": "no-1-duettino-003", "segment_type": "sung", "character": "SUSANNA", "group": "a",
+                      "text": "Ora sì ch'io son contenta" }
+                ]
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn overlay() -> TimingOverlay {
+        serde_json::from_value(serde_json::json!({
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [{
+                "track_title": "Cinque... dieci...", "disc_number": 1, "track_number": 1, "duration_seconds": 168.4,
+                "number_ids": ["no-1-duettino"],
+                "segment_times": [{ "segment_id": "no-1-duettino-002", "start": 3.25 }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_show_number() {
+        let text = render(&base(), None, Unit::Number("no-1-duettino"), false).unwrap();
+        assert_eq!(
+            text,
+            "No. 1 - Duettino  (no-1-duettino, act 1)\n\
+             \n  no-1-duettino-001\n  Figaro misura la camera.\n\
+             \n  FIGARO no-1-duettino-002\n  Cinque... dieci...\n  venti... trenta...\n    Five... ten...\n\
+             \n  SUSANNA no-1-duettino-003 (ensemble a)\n  Ora sì ch'io son contenta\n"
+        );
+        assert!(render(&base(), None, Unit::Number("no-9-aria"), false).is_err());
+    }
+
+    #[test]
+    fn test_show_segment_and_track() {
+        let overlay = overlay();
+        let text = render(&base(), Some(&overlay), Unit::Segment("no-1-duettino-002"), false).unwrap();
+        assert!(text.contains("\n  [d1-t1 0:03.3] FIGARO no-1-duettino-002\n"), "{text}");
+        assert!(!text.contains("SUSANNA"));
+
+        // A track with segment times shows only the segments it times
+        let text = render(&base(), Some(&overlay), Unit::Track("d1-t1"), false).unwrap();
+        assert!(text.starts_with("d1-t1 Cinque... dieci...  (2:48.4)\n"), "{text}");
+        assert!(text.contains("no-1-duettino-002") && !text.contains("no-1-duettino-003"));
+        assert!(render(&base(), None, Unit::Track("d1-t1"), false).is_err());
+    }
+
+    #[test]
+    fn test_color() {
+        let text = render(&base(), None, Unit::Segment("no-1-duettino-001"), true).unwrap();
+        assert!(text.starts_with("\x1b[1;4mNo. 1 - Duettino\x1b[0m"));
+        assert!(text.contains("\x1b[3mFigaro misura la camera.\x1b[0m"));
+    }
+}
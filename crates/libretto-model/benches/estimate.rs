@@ -0,0 +1,114 @@
+// Resolving anchors and estimating times for a whole opera, on one thread
+// and on all of them.
+//
+// The libretto is shaped like a long through-composed opera: 4 acts of 25
+// numbers, 50 segments each, recorded on 200 tracks that each hold half a
+// number and quote the text they start with, recitative then aria.
+//
+//     cargo bench -p libretto-model --bench estimate
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use libretto_model::estimate::estimate_timings;
+use libretto_model::resolve::resolve_anchors;
+use libretto_model::{
+    BaseLibretto, MusicalNumber, NumberType, OperaMetadata, Segment, SegmentType, TimingOverlay, TrackTiming,
+};
+
+const ACTS: usize = 4;
+const NUMBERS_PER_ACT: usize = 25;
+const SEGMENTS_PER_NUMBER: usize = 50;
+
+const SYLLABLES: &[&str] = &[
+    "ca", "ro", "mi", "a", "do", "lo", "re", "fe", "de", "le", "sor", "te", "cor", "pie", "ta", "ven", "det", "ta",
+    "spe", "me", "glo", "ria", "fi", "glio", "pa", "dre", "ciel", "ter", "ra", "on", "da", "not", "te",
+];
+
+/// Pseudo-Italian text, different for every seed.
+fn text(seed: usize, words: usize) -> String {
+    let mut state = seed as u64 * 6364136223846793005 + 1442695040888963407;
+    let mut next = || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state as usize
+    };
+    (0..words)
+        .map(|_| (0..2 + next() % 3).map(|_| SYLLABLES[next() % SYLLABLES.len()]).collect::<String>())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn opera() -> (BaseLibretto, TimingOverlay) {
+    let mut base = BaseLibretto::new(OperaMetadata {
+        title: "Opera".to_string(),
+        composer: "Composer".to_string(),
+        librettist: None,
+        language: "it".to_string(),
+        translation_language: None,
+        year: None,
+    });
+    let mut overlay = TimingOverlay::new("base.libretto.json");
+    for act in 1..=ACTS {
+        for n in 1..=NUMBERS_PER_ACT {
+            let id = format!("no-{act}-{n}");
+            let segments: Vec<Segment> = (0..SEGMENTS_PER_NUMBER)
+                .map(|i| Segment {
+                    id: format!("{id}-{:03}", i + 1),
+                    segment_type: SegmentType::Sung,
+                    character: Some("FIGARO".to_string()),
+                    text: Some(text((act * 100 + n) * 1000 + i, 6 + i % 9)),
+                    translation: None,
+                    direction: None,
+                    group: None,
+                })
+                .collect();
+            // Each half of the number on a track of its own, quoting where
+            // it starts and where its aria begins
+            for half in [0, SEGMENTS_PER_NUMBER / 2] {
+                let opening = |i: usize| segments[i].text.as_deref().unwrap().split(' ').take(3).collect::<Vec<_>>().join(" ");
+                overlay.track_timings.push(TrackTiming {
+                    track_title: format!(
+                        "No. {n} Recitativo: \"{}\" - Aria: \"{}\"",
+                        opening(half),
+                        opening(half + SEGMENTS_PER_NUMBER / 4),
+                    ),
+                    disc_number: Some(act as u32),
+                    track_number: Some((overlay.track_timings.len() + 1) as u32),
+                    duration_seconds: Some(240.0),
+                    number_ids: vec![id.clone()],
+                    ..Default::default()
+                });
+            }
+            base.numbers.push(MusicalNumber {
+                id,
+                label: format!("No. {n}"),
+                number_type: if n % 2 == 0 { NumberType::Aria } else { NumberType::Recitative },
+                act: act.to_string(),
+                scene: None,
+                segments,
+            });
+        }
+    }
+    (base, overlay)
+}
+
+fn bench(c: &mut Criterion) {
+    let (base, overlay) = opera();
+    let resolved = resolve_anchors(&base, &overlay).overlay;
+    assert!(resolved.track_timings.iter().all(|t| t.start_segment_id.is_some()));
+
+    let one_thread = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+    let threads = rayon::current_num_threads();
+    let mut group = c.benchmark_group(format!("{} tracks", overlay.track_timings.len()));
+    group.sample_size(10);
+    group.bench_function("resolve, 1 thread", |b| b.iter(|| one_thread.install(|| resolve_anchors(&base, &overlay))));
+    group.bench_function(format!("resolve, {threads} threads"), |b| b.iter(|| resolve_anchors(&base, &overlay)));
+    group.bench_function("estimate by numbers, 1 thread", |b| b.iter(|| one_thread.install(|| estimate_timings(&base, &overlay))));
+    group.bench_function(format!("estimate by numbers, {threads} threads"), |b| b.iter(|| estimate_timings(&base, &overlay)));
+    group.bench_function("estimate from starts, 1 thread", |b| b.iter(|| one_thread.install(|| estimate_timings(&base, &resolved))));
+    group.bench_function(format!("estimate from starts, {threads} threads"), |b| b.iter(|| estimate_timings(&base, &resolved)));
+    group.finish();
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,116 @@
+// Streaming against whole-document reads of a large interchange document.
+//
+// The document is shaped like a Ring cycle box set: 15 discs of 20 tracks,
+// 150 segments each. Each read finds the segments of one track near the
+// end, as a display device would for the track being played.
+//
+//     cargo bench -p libretto-model --bench stream
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use libretto_model::encoding::{from_slice, to_bytes, Encoding};
+use libretto_model::stream::{stream_interchange, InterchangeVisitor, Visit};
+use libretto_model::{InterchangeLibretto, InterchangeOpera, InterchangeSegment, InterchangeTrack};
+
+const DISCS: u32 = 15;
+const TRACKS_PER_DISC: u32 = 20;
+const SEGMENTS_PER_TRACK: usize = 150;
+const WANTED: &str = "d14-t10";
+
+fn box_set() -> InterchangeLibretto {
+    let tracks = (1..=DISCS)
+        .flat_map(|disc| (1..=TRACKS_PER_DISC).map(move |n| (disc, n)))
+        .map(|(disc, n)| InterchangeTrack {
+            track_id: format!("d{disc}-t{n}"),
+            title: format!("Scene {n}"),
+            album: Some("Der Ring des Nibelungen".to_string()),
+            disc_number: Some(disc),
+            track_number: Some(n),
+            duration_seconds: Some(SEGMENTS_PER_TRACK as f64 * 4.0),
+            act: Some("1".to_string()),
+            segments: (0..SEGMENTS_PER_TRACK)
+                .map(|i| InterchangeSegment {
+                    start: i as f64 * 4.0,
+                    character: Some("BRÜNNHILDE".to_string()),
+                    text: Some("Starke Scheite schichtet mir dort am Rande des Rheins zuhauf!".to_string()),
+                    translation: Some("Pile up mighty logs for me there on the bank of the Rhine!".to_string()),
+                    act: Some("3".to_string()),
+                    segment_id: Some(format!("no-{disc}-{n}-{i:03}")),
+                    number_id: Some(format!("no-{disc}-{n}")),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        })
+        .collect();
+    InterchangeLibretto {
+        version: "1.0".to_string(),
+        opera: InterchangeOpera {
+            title: "Der Ring des Nibelungen".to_string(),
+            composer: "Wagner".to_string(),
+            librettist: Some("Wagner".to_string()),
+            language: "de".to_string(),
+            translation_language: Some("en".to_string()),
+            year: Some(1876),
+        },
+        tracks,
+        ..Default::default()
+    }
+}
+
+/// Keeps the segments of one track and stops after it.
+#[derive(Default)]
+struct OneTrack {
+    reading: bool,
+    segments: Vec<InterchangeSegment>,
+}
+
+impl InterchangeVisitor for OneTrack {
+    fn track(&mut self, _index: usize, track: &InterchangeTrack) -> Visit {
+        if self.reading {
+            return Visit::Stop;
+        }
+        self.reading = track.track_id == WANTED;
+        match self.reading {
+            true => Visit::Continue,
+            false => Visit::Skip,
+        }
+    }
+
+    fn segment(&mut self, _track: usize, segment: InterchangeSegment) -> Visit {
+        self.segments.push(segment);
+        Visit::Continue
+    }
+}
+
+fn whole(bytes: &[u8], encoding: Encoding) -> usize {
+    let doc = from_slice(bytes, encoding).unwrap();
+    doc.tracks.into_iter().find(|t| t.track_id == WANTED).unwrap().segments.len()
+}
+
+fn streamed(bytes: &[u8], encoding: Encoding) -> usize {
+    let mut visitor = OneTrack::default();
+    stream_interchange(bytes, encoding, &mut visitor).unwrap();
+    visitor.segments.len()
+}
+
+fn bench(c: &mut Criterion) {
+    let doc = box_set();
+    let mut encodings = vec![Encoding::Json];
+    if cfg!(feature = "msgpack") {
+        encodings.push(Encoding::MessagePack);
+    }
+    for encoding in encodings {
+        let bytes = to_bytes(&doc, encoding).unwrap();
+        assert_eq!(whole(&bytes, encoding), SEGMENTS_PER_TRACK);
+        assert_eq!(streamed(&bytes, encoding), SEGMENTS_PER_TRACK);
+
+        let mut group = c.benchmark_group(format!("one track of {} KiB {encoding}", bytes.len() / 1024));
+        group.sample_size(10);
+        group.bench_function("whole document", |b| b.iter(|| whole(&bytes, encoding)));
+        group.bench_function("streamed", |b| b.iter(|| streamed(&bytes, encoding)));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench);
+criterion_main!(benches);
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rights::Rights;
+use crate::timing_overlay::Contributor;
+
+/// An annotation overlay: commentary attached to a base libretto's
+/// segment and number IDs.
+///
+/// Like a timing overlay, this is a separate layer over the base text, so
+/// scholarly notes, synopsis blurbs, and musical commentary can be authored
+/// and licensed independently of both the libretto and any recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotationOverlay {
+    pub version: String,
+    /// Path to the base libretto this overlay references (relative to library root).
+    pub base_libretto: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+    /// Licensing for the annotation text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rights: Option<Rights>,
+    pub annotations: Vec<Annotation>,
+}
+
+/// A single note attached to a segment or a whole musical number.
+///
+/// Exactly one of `segment_id` / `number_id` should be set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Annotation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_id: Option<String>,
+    #[serde(default)]
+    pub kind: AnnotationKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+}
+
+/// What sort of commentary an annotation carries.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationKind {
+    /// General scholarly or explanatory note.
+    #[default]
+    Note,
+    /// Plot summary for a number or scene.
+    Synopsis,
+    /// Commentary on the music itself (orchestration, form, key).
+    Musical,
+}
+
+impl AnnotationOverlay {
+    /// Annotations attached to the given segment ID.
+    pub fn for_segment<'a>(&'a self, segment_id: &'a str) -> impl Iterator<Item = &'a Annotation> {
+        self.annotations.iter().filter(move |a| a.segment_id.as_deref() == Some(segment_id))
+    }
+
+    /// Annotations attached to the given musical number ID.
+    pub fn for_number<'a>(&'a self, number_id: &'a str) -> impl Iterator<Item = &'a Annotation> {
+        self.annotations.iter().filter(move |a| a.number_id.as_deref() == Some(number_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_defaults_to_note() {
+        let json = r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "annotations": [
+                { "segment_id": "no-1-001", "text": "Figaro is measuring the room." },
+                { "number_id": "no-1", "kind": "synopsis", "text": "Figaro and Susanna prepare their room." }
+            ]
+        }"#;
+        let overlay: AnnotationOverlay = serde_json::from_str(json).unwrap();
+        assert_eq!(overlay.annotations[0].kind, AnnotationKind::Note);
+        assert_eq!(overlay.annotations[1].kind, AnnotationKind::Synopsis);
+        assert_eq!(overlay.for_segment("no-1-001").count(), 1);
+        assert_eq!(overlay.for_number("no-1").count(), 1);
+        assert_eq!(overlay.for_number("no-2").count(), 0);
+    }
+}
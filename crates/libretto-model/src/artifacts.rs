@@ -0,0 +1,208 @@
+// Detection and repair of text artifacts left over from acquisition.
+//
+// Scraped pages occasionally yield text that was never decoded properly:
+// HTML entities (`&agrave;`, `&amp;`), UTF-8 read as Latin-1 or
+// Windows-1252 ("perchÃ©", "lâ€™amore"), U+FFFD replacement characters,
+// and stray control characters. These survive parsing unnoticed and end
+// up on the display.
+
+/// A suspicious fragment found in libretto text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextArtifact {
+    /// An HTML entity such as `&agrave;` or `&#233;`.
+    HtmlEntity(String),
+    /// A UTF-8 sequence decoded as Latin-1/Windows-1252, e.g. `Ã©`.
+    Mojibake(String),
+    /// U+FFFD, left where bytes could not be decoded at all.
+    ReplacementCharacter,
+    /// A control character other than newline, tab, or carriage return.
+    ControlCharacter(char),
+}
+
+impl std::fmt::Display for TextArtifact {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextArtifact::HtmlEntity(e) => write!(f, "HTML entity '{e}'"),
+            TextArtifact::Mojibake(s) => write!(f, "mis-decoded text '{s}'"),
+            TextArtifact::ReplacementCharacter => write!(f, "replacement character U+FFFD"),
+            TextArtifact::ControlCharacter(c) => write!(f, "control character U+{:04X}", *c as u32),
+        }
+    }
+}
+
+/// Named entities seen in libretto sources, with their characters.
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'), ("lt", '<'), ("gt", '>'), ("quot", '"'), ("apos", '\''), ("nbsp", '\u{a0}'),
+    ("agrave", 'à'), ("aacute", 'á'), ("acirc", 'â'), ("auml", 'ä'),
+    ("egrave", 'è'), ("eacute", 'é'), ("ecirc", 'ê'), ("euml", 'ë'),
+    ("igrave", 'ì'), ("iacute", 'í'), ("icirc", 'î'), ("iuml", 'ï'),
+    ("ograve", 'ò'), ("oacute", 'ó'), ("ocirc", 'ô'), ("ouml", 'ö'),
+    ("ugrave", 'ù'), ("uacute", 'ú'), ("ucirc", 'û'), ("uuml", 'ü'),
+    ("Agrave", 'À'), ("Aacute", 'Á'), ("Egrave", 'È'), ("Eacute", 'É'),
+    ("Igrave", 'Ì'), ("Ograve", 'Ò'), ("Ugrave", 'Ù'), ("Auml", 'Ä'), ("Ouml", 'Ö'), ("Uuml", 'Ü'),
+    ("ccedil", 'ç'), ("Ccedil", 'Ç'), ("ntilde", 'ñ'), ("szlig", 'ß'), ("oelig", 'œ'),
+    ("laquo", '«'), ("raquo", '»'), ("lsquo", '‘'), ("rsquo", '’'), ("ldquo", '“'), ("rdquo", '”'),
+    ("hellip", '…'), ("ndash", '–'), ("mdash", '—'),
+];
+
+/// Windows-1252 characters in the 0x80–0x9F range, for undoing mojibake.
+const CP1252_HIGH: &[(char, u8)] = &[
+    ('€', 0x80), ('‚', 0x82), ('ƒ', 0x83), ('„', 0x84), ('…', 0x85), ('†', 0x86), ('‡', 0x87),
+    ('ˆ', 0x88), ('‰', 0x89), ('Š', 0x8a), ('‹', 0x8b), ('Œ', 0x8c), ('Ž', 0x8e), ('‘', 0x91),
+    ('’', 0x92), ('“', 0x93), ('”', 0x94), ('•', 0x95), ('–', 0x96), ('—', 0x97), ('˜', 0x98),
+    ('™', 0x99), ('š', 0x9a), ('›', 0x9b), ('œ', 0x9c), ('ž', 0x9e), ('Ÿ', 0x9f),
+];
+
+/// Find every artifact in `text`, in order of appearance.
+pub fn find_artifacts(text: &str) -> Vec<TextArtifact> {
+    let mut found = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '&' {
+            if let Some(len) = entity_len(&chars[i..]) {
+                found.push(TextArtifact::HtmlEntity(chars[i..i + len].iter().collect()));
+                i += len;
+                continue;
+            }
+        }
+        if let Some(&next) = chars.get(i + 1) {
+            let latin1_lead = matches!(c, 'Ã' | 'Â') && ('\u{80}'..='\u{bf}').contains(&next);
+            let cp1252_lead = c == 'â' && next == '€';
+            if latin1_lead || cp1252_lead {
+                let len = if cp1252_lead { 3 } else { 2 }.min(chars.len() - i);
+                found.push(TextArtifact::Mojibake(chars[i..i + len].iter().collect()));
+                i += len;
+                continue;
+            }
+        }
+        if c == '\u{fffd}' {
+            found.push(TextArtifact::ReplacementCharacter);
+        } else if c.is_control() && !matches!(c, '\n' | '\t' | '\r') {
+            found.push(TextArtifact::ControlCharacter(c));
+        }
+        i += 1;
+    }
+    found
+}
+
+/// Length of a well-formed entity (`&name;`, `&#123;`, `&#x7b;`) at the start of `chars`.
+fn entity_len(chars: &[char]) -> Option<usize> {
+    let end = chars.iter().take(12).position(|&c| c == ';')?;
+    let body = &chars[1..end];
+    let valid = match body {
+        ['#', 'x' | 'X', hex @ ..] => !hex.is_empty() && hex.iter().all(char::is_ascii_hexdigit),
+        ['#', dec @ ..] => !dec.is_empty() && dec.iter().all(char::is_ascii_digit),
+        name => name.len() >= 2 && name.iter().all(char::is_ascii_alphabetic),
+    };
+    valid.then_some(end + 1)
+}
+
+/// Decode one entity (including `&` and `;`), if it is known.
+fn decode_entity(entity: &str) -> Option<char> {
+    let body = entity.strip_prefix('&')?.strip_suffix(';')?;
+    if let Some(hex) = body.strip_prefix("#x").or_else(|| body.strip_prefix("#X")) {
+        char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+    } else if let Some(dec) = body.strip_prefix('#') {
+        char::from_u32(dec.parse().ok()?)
+    } else {
+        NAMED_ENTITIES.iter().find(|(name, _)| *name == body).map(|&(_, c)| c)
+    }
+}
+
+/// Re-decode a word whose UTF-8 bytes were read as Latin-1/Windows-1252.
+///
+/// Returns `None` unless every character maps back to a single byte and
+/// those bytes form valid UTF-8 that differs from the input, so correctly
+/// decoded accented words are left alone.
+fn redecode_word(word: &str) -> Option<String> {
+    if word.is_ascii() {
+        return None;
+    }
+    let bytes: Option<Vec<u8>> = word.chars()
+        .map(|c| match u32::from(c) {
+            b @ 0..=0xff => Some(b as u8),
+            _ => CP1252_HIGH.iter().find(|(h, _)| *h == c).map(|&(_, b)| b),
+        })
+        .collect();
+    let decoded = String::from_utf8(bytes?).ok()?;
+    (decoded != word).then_some(decoded)
+}
+
+/// Repair what can be repaired safely: decode known HTML entities,
+/// re-decode mis-decoded UTF-8 word by word, and drop control characters.
+///
+/// Replacement characters and unknown entities are left for a human.
+pub fn repair_artifacts(text: &str) -> String {
+    // Entities first, so "&amp;agrave;" style double-encoding is not made worse
+    let chars: Vec<char> = text.chars().collect();
+    let mut decoded = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '&' {
+            if let Some(len) = entity_len(&chars[i..]) {
+                let entity: String = chars[i..i + len].iter().collect();
+                if let Some(c) = decode_entity(&entity) {
+                    decoded.push(c);
+                    i += len;
+                    continue;
+                }
+            }
+        }
+        decoded.push(chars[i]);
+        i += 1;
+    }
+
+    let mut repaired = String::with_capacity(decoded.len());
+    let mut word = String::new();
+    let flush = |word: &mut String, out: &mut String| {
+        out.push_str(&redecode_word(word).unwrap_or_else(|| word.clone()));
+        word.clear();
+    };
+    // Only ASCII whitespace ends a word: the second byte of "à" read as
+    // Latin-1 is a no-break space
+    for c in decoded.chars() {
+        if c.is_ascii_whitespace() {
+            flush(&mut word, &mut repaired);
+            if !c.is_control() || matches!(c, '\n' | '\t' | '\r') {
+                repaired.push(c);
+            }
+        } else if !c.is_control() {
+            word.push(c);
+        }
+    }
+    flush(&mut word, &mut repaired);
+    repaired
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_artifacts() {
+        assert!(find_artifacts("Perché? Città, Ah! l’amore & più").is_empty());
+        assert_eq!(
+            find_artifacts("perch&eacute; Ã¨ l\u{1}a \u{fffd} lâ€™amore &#233;"),
+            vec![
+                TextArtifact::HtmlEntity("&eacute;".into()),
+                TextArtifact::Mojibake("Ã¨".into()),
+                TextArtifact::ControlCharacter('\u{1}'),
+                TextArtifact::ReplacementCharacter,
+                TextArtifact::Mojibake("â€™".into()),
+                TextArtifact::HtmlEntity("&#233;".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repair_artifacts() {
+        assert_eq!(repair_artifacts("perch&eacute; Ã¨ cos&igrave;"), "perché è così");
+        assert_eq!(repair_artifacts("lâ€™amore Figaro &amp; Susanna"), "l’amore Figaro & Susanna");
+        assert_eq!(repair_artifacts("Città\u{7} e \u{fffd}"), "Città e \u{fffd}");
+        assert_eq!(repair_artifacts("&bogus; &#x41;"), "&bogus; A");
+        assert_eq!(repair_artifacts("Ã\u{a0} Ã\u{a0}ncora pietÃ\u{a0}"), "à àncora pietà");
+        assert_eq!(repair_artifacts("Ah!\u{a0}perché"), "Ah!\u{a0}perché");
+    }
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::rights::Rights;
 
 /// A base libretto: the untimed, structured text of an opera.
 ///
@@ -11,6 +14,9 @@ pub struct BaseLibretto {
     pub opera: OperaMetadata,
     pub cast: Vec<CastMember>,
     pub numbers: Vec<MusicalNumber>,
+    /// Licensing for the text and translation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rights: Option<Rights>,
 }
 
 /// Metadata about the opera itself.
@@ -44,6 +50,67 @@ pub struct CastMember {
     /// Description or role info (e.g., "page to the Count").
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// Other names this character is attributed under (e.g., "ALMAVIVA").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+impl CastMember {
+    /// Whether a segment attribution name refers to this character.
+    ///
+    /// Names are compared after `normalize_character_name`. Besides the
+    /// short name and aliases, an attribution matches if its words occur
+    /// consecutively in the full character name, so "BARTOLO" matches
+    /// "Don Bartolo" and "IL CONTE" matches "Il Conte d'Almaviva".
+    pub fn matches_name(&self, name: &str) -> bool {
+        let name = normalize_character_name(name);
+        if name.is_empty() {
+            return false;
+        }
+        let exact = self.short_name.iter()
+            .chain(&self.aliases)
+            .any(|n| normalize_character_name(n) == name);
+        let full = normalize_character_name(&self.character);
+        let words: Vec<&str> = name.split(' ').collect();
+        let full_words: Vec<&str> = full.split(' ').collect();
+        exact || full_words.windows(words.len()).any(|w| w == words.as_slice())
+    }
+}
+
+/// Normalize a character name for comparison: strip accents, uppercase,
+/// and reduce punctuation and whitespace runs to single spaces.
+pub fn normalize_character_name(name: &str) -> String {
+    let stripped: String = name.nfd()
+        .filter(|c| !unicode_normalization::char::is_combining_mark(*c))
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect();
+    stripped.to_uppercase().split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split a segment attribution into individual character names.
+///
+/// Ensemble attributions list several characters, e.g. "SUSANNA, FIGARO"
+/// or "IL CONTE E LA CONTESSA"; they are split on commas, `&`, `/`, and
+/// the words "E", "ED", "AND", "UND", and "ET".
+pub fn split_character_attribution(attribution: &str) -> Vec<String> {
+    attribution.split([',', '&', '/'])
+        .flat_map(|part| {
+            let part = normalize_character_name(part);
+            let mut names = Vec::new();
+            let mut current: Vec<&str> = Vec::new();
+            for word in part.split(' ') {
+                if matches!(word, "E" | "ED" | "AND" | "UND" | "ET") && !current.is_empty() {
+                    names.push(current.join(" "));
+                    current.clear();
+                } else {
+                    current.push(word);
+                }
+            }
+            names.push(current.join(" "));
+            names
+        })
+        .filter(|n| !n.is_empty())
+        .collect()
 }
 
 /// A musical number within the opera (aria, duet, recitative, finale, etc.).
@@ -68,7 +135,7 @@ pub struct MusicalNumber {
 }
 
 /// Classification of a musical number.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum NumberType {
     Overture,
@@ -117,7 +184,7 @@ pub struct Segment {
 }
 
 /// Type of content in a segment.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum SegmentType {
     Sung,
@@ -138,6 +205,7 @@ impl BaseLibretto {
             opera,
             cast: Vec::new(),
             numbers: Vec::new(),
+            rights: None,
         }
     }
 
@@ -157,6 +225,18 @@ impl BaseLibretto {
             .find(|s| s.id == id)
     }
 
+    /// Whether any segment carries translation text.
+    pub fn has_translations(&self) -> bool {
+        self.numbers.iter()
+            .flat_map(|n| n.segments.iter())
+            .any(|s| s.translation.is_some())
+    }
+
+    /// Find the cast member a segment attribution name refers to.
+    pub fn find_cast_member(&self, name: &str) -> Option<&CastMember> {
+        self.cast.iter().find(|c| c.matches_name(name))
+    }
+
     /// Look up a musical number by ID.
     pub fn find_number(&self, id: &str) -> Option<&MusicalNumber> {
         self.numbers.iter().find(|n| n.id == id)
@@ -182,6 +262,7 @@ mod tests {
             short_name: Some("FIGARO".to_string()),
             voice_type: Some("bass-baritone".to_string()),
             description: None,
+            aliases: vec![],
         });
 
         libretto.numbers.push(MusicalNumber {
@@ -246,4 +327,24 @@ mod tests {
         assert_eq!(parsed.numbers.len(), 1);
         assert_eq!(parsed.numbers[0].segments.len(), 2);
     }
+
+    #[test]
+    fn test_character_attribution() {
+        assert_eq!(split_character_attribution("SUSANNA, FIGARO"), vec!["SUSANNA", "FIGARO"]);
+        assert_eq!(split_character_attribution("Il Conte e la Contessa"), vec!["IL CONTE", "LA CONTESSA"]);
+        assert_eq!(normalize_character_name("  Nérone. "), "NERONE");
+
+        let conte = CastMember {
+            character: "Il Conte d'Almaviva".to_string(),
+            short_name: None,
+            voice_type: None,
+            description: None,
+            aliases: vec!["Almaviva".to_string()],
+        };
+        assert!(conte.matches_name("IL CONTE"));
+        assert!(conte.matches_name("ALMAVIVA"));
+        assert!(conte.matches_name("conte"));
+        assert!(!conte.matches_name("LA CONTESSA"));
+        assert!(!conte.matches_name(""));
+    }
 }
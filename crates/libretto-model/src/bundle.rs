@@ -0,0 +1,434 @@
+// Several recordings of one opera in one document.
+//
+// An interchange document carries every segment's text next to its times,
+// so a display client offering a choice of recordings would load the same
+// text once per recording. A bundle keeps each segment's text once, keyed
+// by segment ID, and gives each recording only its tracks and the times of
+// the segments on them. When the listener switches recording, `recording`
+// puts that one back together as an ordinary interchange document.
+//
+// Bundles are built from interchange documents merged from the same base
+// libretto, so whatever the merge added (annotations, pronunciations,
+// album times, untimed segments) comes along.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use crate::annotation_overlay::Annotation;
+use crate::interchange::{
+    check_untimed, default_type, is_default_type, is_false, start_or_untimed, InterchangeLibretto, InterchangeOpera,
+    InterchangeSegment, InterchangeTrack,
+};
+use crate::pronunciation_overlay::PronunciationNotation;
+use crate::rights::Rights;
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("a bundle needs at least one recording")]
+    NoRecordings,
+    #[error("recording '{0}' is in the bundle twice")]
+    DuplicateRecording(String),
+    #[error("recording '{recording}' is of {title}, not {expected}")]
+    DifferentOpera {
+        recording: String,
+        title: String,
+        expected: String,
+    },
+    #[error("recording '{recording}', track '{track}': segment #{position} has no segment_id to share its text by")]
+    MissingSegmentId {
+        recording: String,
+        track: String,
+        position: usize,
+    },
+    #[error("recording '{recording}': segment '{segment_id}' has other text than in an earlier recording (merged from another revision of the base libretto?)")]
+    ConflictingText {
+        recording: String,
+        segment_id: String,
+    },
+}
+
+/// One opera's text with the timings of several recordings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterchangeBundle {
+    pub version: String,
+    pub opera: InterchangeOpera,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_rights: Option<Rights>,
+    /// The text of every segment any recording performs, once each, in
+    /// order of first appearance.
+    pub segments: Vec<BundleText>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation_notation: Option<PronunciationNotation>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation_rights: Option<Rights>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synopsis_language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synopsis_rights: Option<Rights>,
+    pub recordings: Vec<BundleRecording>,
+}
+
+/// A segment's text, shared by every recording that performs it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleText {
+    pub segment_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_id: Option<String>,
+    #[serde(default = "default_type", skip_serializing_if = "is_default_type")]
+    #[serde(rename = "type")]
+    pub segment_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub character: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// One recording's tracks and times.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleRecording {
+    /// Name of the recording, e.g. `giulini-1959`.
+    pub id: String,
+    /// Album title and artist of the first track that has them, for a
+    /// client's list of recordings.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing_rights: Option<Rights>,
+    pub tracks: Vec<BundleTrack>,
+}
+
+/// A track of a recording, as in the interchange format, with its segments
+/// referring to the bundle's text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleTrack {
+    pub track_id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disc_number: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_number: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_seconds: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album_offset: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub act: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scene: Option<String>,
+    pub segments: Vec<BundleTime>,
+}
+
+/// When a recording performs a segment of the bundle's text.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
+pub struct BundleTime {
+    pub segment_id: String,
+    /// `null` in JSON (NaN here) for an untimed segment, and only for one.
+    #[serde(deserialize_with = "start_or_untimed")]
+    pub start: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album_start: Option<f64>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub untimed: bool,
+}
+
+impl Serialize for BundleTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BundleTime::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BundleTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let time = BundleTime::deserialize(deserializer)?;
+        check_untimed(time.start, time.untimed)?;
+        Ok(time)
+    }
+}
+
+/// Bundle interchange documents of one opera, each named by its recording.
+///
+/// Every segment must have a `segment_id`, and a segment performed in
+/// several recordings must have the same text in each: the documents must
+/// be merged from the same base libretto. The opera metadata and text
+/// rights are the first document's; annotations are gathered from all.
+pub fn bundle(recordings: Vec<(String, InterchangeLibretto)>) -> Result<InterchangeBundle, BundleError> {
+    let mut recordings = recordings.into_iter();
+    let Some((first_id, first)) = recordings.next() else {
+        return Err(BundleError::NoRecordings);
+    };
+    let mut result = InterchangeBundle {
+        version: "1.0".to_string(),
+        opera: first.opera.clone(),
+        text_rights: first.text_rights.clone(),
+        segments: Vec::new(),
+        annotations: Vec::new(),
+        pronunciation_notation: first.pronunciation_notation,
+        pronunciation_rights: first.pronunciation_rights.clone(),
+        synopsis_language: first.synopsis_language.clone(),
+        synopsis_rights: first.synopsis_rights.clone(),
+        recordings: Vec::new(),
+    };
+    let mut texts: HashMap<String, usize> = HashMap::new();
+
+    for (id, doc) in std::iter::once((first_id, first)).chain(recordings) {
+        if result.recordings.iter().any(|r| r.id == id) {
+            return Err(BundleError::DuplicateRecording(id));
+        }
+        if doc.opera.title != result.opera.title || doc.opera.composer != result.opera.composer {
+            return Err(BundleError::DifferentOpera {
+                recording: id,
+                title: doc.opera.title,
+                expected: result.opera.title,
+            });
+        }
+        for annotation in doc.annotations {
+            if !result.annotations.contains(&annotation) {
+                result.annotations.push(annotation);
+            }
+        }
+
+        let mut tracks = Vec::new();
+        for track in doc.tracks {
+            let mut times = Vec::new();
+            for (i, segment) in track.segments.into_iter().enumerate() {
+                let (text, time) = split_segment(segment).ok_or_else(|| BundleError::MissingSegmentId {
+                    recording: id.clone(),
+                    track: track.track_id.clone(),
+                    position: i + 1,
+                })?;
+                match texts.get(&text.segment_id) {
+                    Some(&k) if result.segments[k] != text => {
+                        return Err(BundleError::ConflictingText { recording: id, segment_id: text.segment_id });
+                    }
+                    Some(_) => {}
+                    None => {
+                        texts.insert(text.segment_id.clone(), result.segments.len());
+                        result.segments.push(text);
+                    }
+                }
+                times.push(time);
+            }
+            tracks.push(BundleTrack {
+                track_id: track.track_id,
+                title: track.title,
+                album: track.album,
+                artist: track.artist,
+                disc_number: track.disc_number,
+                track_number: track.track_number,
+                duration_seconds: track.duration_seconds,
+                album_offset: track.album_offset,
+                act: track.act,
+                scene: track.scene,
+                segments: times,
+            });
+        }
+        result.recordings.push(BundleRecording {
+            id,
+            album: tracks.iter().find_map(|t| t.album.clone()),
+            artist: tracks.iter().find_map(|t| t.artist.clone()),
+            timing_rights: doc.timing_rights,
+            tracks,
+        });
+    }
+    Ok(result)
+}
+
+/// A segment's shared text and this recording's times for it, or `None`
+/// without a segment ID.
+fn split_segment(segment: InterchangeSegment) -> Option<(BundleText, BundleTime)> {
+    let segment_id = segment.segment_id?;
+    let time = BundleTime {
+        segment_id: segment_id.clone(),
+        start: segment.start,
+        end: segment.end,
+        album_start: segment.album_start,
+        untimed: segment.untimed,
+    };
+    let text = BundleText {
+        segment_id,
+        number_id: segment.number_id,
+        segment_type: segment.segment_type,
+        character: segment.character,
+        text: segment.text,
+        translation: segment.translation,
+        pronunciation: segment.pronunciation,
+        direction: segment.direction,
+        act: segment.act,
+        scene: segment.scene,
+        group: segment.group,
+    };
+    Some((text, time))
+}
+
+impl InterchangeBundle {
+    /// The IDs of the bundled recordings, in order.
+    pub fn recording_ids(&self) -> impl Iterator<Item = &str> {
+        self.recordings.iter().map(|r| r.id.as_str())
+    }
+
+    /// Recording `id` as an interchange document of its own. Times whose
+    /// segment is missing from the bundle's text are left out.
+    pub fn recording(&self, id: &str) -> Option<InterchangeLibretto> {
+        let recording = self.recordings.iter().find(|r| r.id == id)?;
+        let texts: HashMap<&str, &BundleText> = self.segments.iter()
+            .map(|t| (t.segment_id.as_str(), t))
+            .collect();
+        let tracks = recording.tracks.iter()
+            .map(|track| InterchangeTrack {
+                track_id: track.track_id.clone(),
+                title: track.title.clone(),
+                album: track.album.clone(),
+                artist: track.artist.clone(),
+                disc_number: track.disc_number,
+                track_number: track.track_number,
+                duration_seconds: track.duration_seconds,
+                album_offset: track.album_offset,
+                act: track.act.clone(),
+                scene: track.scene.clone(),
+                segments: track.segments.iter()
+                    .filter_map(|time| {
+                        let text = texts.get(time.segment_id.as_str())?;
+                        Some(InterchangeSegment {
+                            start: time.start,
+                            end: time.end,
+                            segment_type: text.segment_type.clone(),
+                            character: text.character.clone(),
+                            text: text.text.clone(),
+                            translation: text.translation.clone(),
+                            pronunciation: text.pronunciation.clone(),
+                            direction: text.direction.clone(),
+                            act: text.act.clone(),
+                            scene: text.scene.clone(),
+                            group: text.group.clone(),
+                            segment_id: Some(time.segment_id.clone()),
+                            number_id: text.number_id.clone(),
+                            album_start: time.album_start,
+                            untimed: time.untimed,
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+        Some(InterchangeLibretto {
+            version: self.version.clone(),
+            opera: self.opera.clone(),
+            text_rights: self.text_rights.clone(),
+            timing_rights: recording.timing_rights.clone(),
+            tracks,
+            annotations: self.annotations.clone(),
+            pronunciation_notation: self.pronunciation_notation,
+            pronunciation_rights: self.pronunciation_rights.clone(),
+            synopsis_language: self.synopsis_language.clone(),
+            synopsis_rights: self.synopsis_rights.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(id: &str, start: f64, text: &str) -> InterchangeSegment {
+        InterchangeSegment {
+            start,
+            segment_type: "sung".to_string(),
+            character: Some("FIGARO".to_string()),
+            text: Some(text.to_string()),
+            act: Some("1".to_string()),
+            segment_id: Some(id.to_string()),
+            number_id: Some("no-1".to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn recording(album: &str, segments: Vec<InterchangeSegment>) -> InterchangeLibretto {
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "Mozart".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks: vec![InterchangeTrack {
+                track_id: "d1-t2".to_string(),
+                title: "Cinque... dieci...".to_string(),
+                album: Some(album.to_string()),
+                disc_number: Some(1),
+                track_number: Some(2),
+                duration_seconds: Some(180.0),
+                act: Some("1".to_string()),
+                segments,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bundle_shares_text() {
+        let giulini = recording("Giulini 1959", vec![segment("a", 1.0, "Cinque..."), segment("b", 4.0, "dieci...")]);
+        let kleiber = recording("Kleiber 1955", vec![segment("a", 2.5, "Cinque..."), segment("c", 9.0, "venti...")]);
+        let result = bundle(vec![("giulini".to_string(), giulini.clone()), ("kleiber".to_string(), kleiber)]).unwrap();
+
+        let ids: Vec<_> = result.segments.iter().map(|t| t.segment_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(result.recording_ids().collect::<Vec<_>>(), vec!["giulini", "kleiber"]);
+        assert_eq!(result.recordings[1].album.as_deref(), Some("Kleiber 1955"));
+
+        // Each recording comes back as it went in
+        let back = result.recording("giulini").unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), serde_json::to_value(&giulini).unwrap());
+        let kleiber = result.recording("kleiber").unwrap();
+        assert_eq!(kleiber.tracks[0].segments[0].start, 2.5);
+        assert_eq!(kleiber.tracks[0].segments[1].text.as_deref(), Some("venti..."));
+        assert!(result.recording("karajan").is_none());
+
+        // And through JSON
+        let json = serde_json::to_string(&result).unwrap();
+        let read: InterchangeBundle = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.recording("kleiber").unwrap().tracks[0].segments.len(), 2);
+    }
+
+    #[test]
+    fn test_bundle_errors() {
+        let first = recording("A", vec![segment("a", 1.0, "Cinque...")]);
+        let revised = recording("B", vec![segment("a", 1.0, "Cinque, dieci...")]);
+        let err = bundle(vec![("a".to_string(), first.clone()), ("b".to_string(), revised)]).unwrap_err();
+        assert!(matches!(err, BundleError::ConflictingText { ref segment_id, .. } if segment_id == "a"));
+
+        let mut anonymous = first.clone();
+        anonymous.tracks[0].segments[0].segment_id = None;
+        let err = bundle(vec![("a".to_string(), anonymous)]).unwrap_err();
+        assert!(matches!(err, BundleError::MissingSegmentId { position: 1, .. }));
+
+        let err = bundle(vec![("a".to_string(), first.clone()), ("a".to_string(), first)]).unwrap_err();
+        assert!(matches!(err, BundleError::DuplicateRecording(_)));
+        assert!(matches!(bundle(vec![]).unwrap_err(), BundleError::NoRecordings));
+    }
+}
@@ -0,0 +1,299 @@
+// Chapter markers for recordings kept as one long file per act.
+//
+// Some listeners rip each act to a single file rather than keeping the
+// label's track layout. A chapter file lets their player jump between
+// numbers all the same. Chapters are laid out on the album timeline (see
+// `timeline`), split into one list per act with times counted from the
+// start of the act's first track, and written as an FFmpeg FFMETADATA
+// file or a Matroska chapter XML file.
+
+use crate::export::slug;
+use crate::interchange::{InterchangeLibretto, InterchangeSegment};
+use crate::timeline::track_offsets;
+
+/// Where a new chapter begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChapterSplit {
+    /// At the first segment of each musical number.
+    #[default]
+    Number,
+    /// At each ensemble group, and at every segment outside one.
+    Group,
+}
+
+/// One chapter, in seconds from the start of its act.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub start: f64,
+    pub end: f64,
+    pub title: String,
+}
+
+/// The chapters of one act.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActChapters {
+    /// The act, as given on its segments; `None` when they carry none.
+    pub act: Option<String>,
+    pub chapters: Vec<Chapter>,
+}
+
+/// Result of laying out chapters.
+#[derive(Debug)]
+pub struct ChapterResult {
+    pub acts: Vec<ActChapters>,
+    pub warnings: Vec<String>,
+}
+
+impl ActChapters {
+    /// File name for this act's chapters with `extension` (`act-1.ffmetadata`),
+    /// the act being `index`, counted from zero. An act whose name has no
+    /// letters or digits is numbered instead.
+    pub fn file_name(&self, index: usize, extension: &str) -> String {
+        match self.act.as_deref().map(slug) {
+            Some(act) if act.is_empty() => format!("act-{}.{}", index + 1, extension),
+            Some(act) => format!("act-{act}.{extension}"),
+            None => format!("chapters.{extension}"),
+        }
+    }
+}
+
+/// Lay out the chapters of an interchange libretto, act by act.
+///
+/// Track offsets come from track durations, so tracks after one without
+/// a duration cannot be placed and are left out with a warning. A chapter
+/// that begins a track takes the track's title; any other is named by its
+/// first line of text (an opera number's incipit) or its direction. Each
+/// chapter runs to the next one, and the last of an act to the end of its
+/// last track.
+pub fn act_chapters(doc: &InterchangeLibretto, split: ChapterSplit) -> ChapterResult {
+    let mut acts: Vec<ActChapters> = Vec::new();
+    let mut warnings = Vec::new();
+    // Album time at which the current act's file starts, and ends.
+    let mut origin = 0.0;
+    let mut act_end: Option<f64> = None;
+    let mut previous: Option<&InterchangeSegment> = None;
+
+    let offsets = track_offsets(doc.tracks.iter().map(|t| t.duration_seconds));
+    for (track, offset) in doc.tracks.iter().zip(offsets) {
+        let Some(offset) = offset else {
+            warnings.push(format!("Track '{}' cannot be placed on the album timeline (an earlier track has no duration)", track.track_id));
+            continue;
+        };
+        for (i, seg) in track.segments.iter().enumerate() {
+            let act = seg.act.clone().or_else(|| track.act.clone());
+            if acts.last().is_none_or(|a| a.act != act) {
+                // An act that ends mid-track ends where the next begins.
+                let end = if i == 0 { act_end } else { Some(offset + seg.start) };
+                close_act(&mut acts, end, origin);
+                acts.push(ActChapters { act, chapters: vec![] });
+                origin = offset;
+                previous = None;
+            }
+            let starts_chapter = match (previous, split) {
+                (None, _) => true,
+                (Some(prev), ChapterSplit::Number) => prev.number_id != seg.number_id,
+                (Some(prev), ChapterSplit::Group) => seg.group.is_none() || prev.group != seg.group,
+            };
+            if starts_chapter {
+                let title = if i == 0 { track.title.clone() } else { chapter_title(seg) };
+                let start = offset + seg.start - origin;
+                let chapters = &mut acts.last_mut().expect("act pushed above").chapters;
+                if let Some(last) = chapters.last_mut() {
+                    last.end = start;
+                }
+                chapters.push(Chapter { start, end: start, title });
+            }
+            previous = Some(seg);
+        }
+        act_end = track.duration_seconds.map(|d| offset + d);
+    }
+    close_act(&mut acts, act_end, origin);
+
+    ChapterResult { acts, warnings }
+}
+
+/// Run the last chapter of the last act to the act's end.
+fn close_act(acts: &mut [ActChapters], act_end: Option<f64>, origin: f64) {
+    if let (Some(last), Some(end)) = (acts.last_mut().and_then(|a| a.chapters.last_mut()), act_end) {
+        last.end = (end - origin).max(last.start);
+    }
+}
+
+/// The first line of a segment's text, or of its direction.
+fn chapter_title(seg: &InterchangeSegment) -> String {
+    seg.text.as_deref()
+        .or(seg.direction.as_deref())
+        .and_then(|t| t.lines().map(str::trim).find(|l| !l.is_empty()))
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Write an act's chapters as an FFmpeg FFMETADATA file, for
+/// `ffmpeg -i act.flac -i act-1.ffmetadata -map_metadata 1 -map_chapters 1 ...`.
+pub fn ffmetadata(doc: &InterchangeLibretto, act: &ActChapters) -> String {
+    let title = match &act.act {
+        Some(a) => format!("{} — Act {}", doc.opera.title, a),
+        None => doc.opera.title.clone(),
+    };
+    let mut out = String::from(";FFMETADATA1\n");
+    out.push_str(&format!("title={}\n", ffmetadata_escape(&title)));
+    out.push_str(&format!("artist={}\n", ffmetadata_escape(&doc.opera.composer)));
+    for ch in &act.chapters {
+        out.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+        out.push_str(&format!("START={}\n", (ch.start * 1000.0).round() as u64));
+        out.push_str(&format!("END={}\n", (ch.end * 1000.0).round() as u64));
+        out.push_str(&format!("title={}\n", ffmetadata_escape(&ch.title)));
+    }
+    out
+}
+
+/// Backslash-escape the characters FFMETADATA values reserve.
+fn ffmetadata_escape(value: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Write an act's chapters as a Matroska chapter XML file, for
+/// `mkvmerge --chapters act-1.xml ...`.
+pub fn mkv_chapters(act: &ActChapters) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<!DOCTYPE Chapters SYSTEM \"matroskachapters.dtd\">\n");
+    out.push_str("<Chapters>\n  <EditionEntry>\n");
+    for ch in &act.chapters {
+        out.push_str("    <ChapterAtom>\n");
+        out.push_str(&format!("      <ChapterTimeStart>{}</ChapterTimeStart>\n", mkv_time(ch.start)));
+        out.push_str(&format!("      <ChapterTimeEnd>{}</ChapterTimeEnd>\n", mkv_time(ch.end)));
+        out.push_str("      <ChapterDisplay>\n");
+        out.push_str(&format!(
+            "        <ChapterString>{}</ChapterString>\n",
+            ch.title.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+        ));
+        out.push_str("      </ChapterDisplay>\n");
+        out.push_str("    </ChapterAtom>\n");
+    }
+    out.push_str("  </EditionEntry>\n</Chapters>\n");
+    out
+}
+
+/// `hh:mm:ss.nnnnnnnnn`, as Matroska chapter files give times.
+fn mkv_time(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02}.{:03}000000", ms / 3_600_000, (ms / 60_000) % 60, (ms / 1000) % 60, ms % 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interchange::*;
+
+    fn segment(start: f64, number_id: &str, act: &str, text: &str, group: Option<&str>) -> InterchangeSegment {
+        InterchangeSegment {
+            start,
+            segment_type: "sung".to_string(),
+            text: Some(text.to_string()),
+            act: Some(act.to_string()),
+            group: group.map(str::to_string),
+            number_id: Some(number_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn track(id: &str, title: &str, duration: Option<f64>, segments: Vec<InterchangeSegment>) -> InterchangeTrack {
+        InterchangeTrack {
+            track_id: id.to_string(),
+            title: title.to_string(),
+            duration_seconds: duration,
+            segments,
+            ..Default::default()
+        }
+    }
+
+    fn doc(tracks: Vec<InterchangeTrack>) -> InterchangeLibretto {
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "W. A. Mozart".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks,
+            ..Default::default()
+        }
+    }
+
+    fn figaro() -> InterchangeLibretto {
+        doc(vec![
+            track("t1", "Cinque... dieci...", Some(100.0), vec![
+                segment(0.0, "no-1", "1", "Cinque... dieci...", None),
+                segment(40.0, "no-1", "1", "Ora sì ch'io son contenta", Some("a")),
+                segment(50.0, "no-1", "1", "Ora sì ch'io son contenta", Some("a")),
+                segment(70.0, "rec-1", "1", "Cosa stai misurando,\ncaro il mio Figaretto?", None),
+            ]),
+            track("t2", "Se vuol ballare", Some(120.0), vec![
+                segment(0.0, "no-3", "1", "Se vuol ballare", None),
+            ]),
+            track("t3", "Porgi, amor", Some(200.0), vec![
+                segment(5.0, "no-10", "2", "Porgi, amor", None),
+            ]),
+        ])
+    }
+
+    #[test]
+    fn test_act_chapters_by_number() {
+        let result = act_chapters(&figaro(), ChapterSplit::Number);
+        assert!(result.warnings.is_empty());
+        assert_eq!(result.acts.len(), 2);
+
+        let act1 = &result.acts[0];
+        assert_eq!(act1.file_name(0, "ffmetadata"), "act-1.ffmetadata");
+        let unnamed = ActChapters { act: Some("*".to_string()), chapters: Vec::new() };
+        assert_eq!(unnamed.file_name(2, "xml"), "act-3.xml");
+        let chapters: Vec<_> = act1.chapters.iter().map(|c| (c.start, c.end, c.title.as_str())).collect();
+        assert_eq!(chapters, vec![
+            (0.0, 70.0, "Cinque... dieci..."),
+            (70.0, 100.0, "Cosa stai misurando,"),
+            (100.0, 220.0, "Se vuol ballare"),
+        ]);
+
+        // Act 2 is counted from the start of its own first track.
+        assert_eq!(result.acts[1].chapters, vec![Chapter { start: 5.0, end: 200.0, title: "Porgi, amor".to_string() }]);
+    }
+
+    #[test]
+    fn test_act_chapters_by_group() {
+        let result = act_chapters(&figaro(), ChapterSplit::Group);
+        let starts: Vec<f64> = result.acts[0].chapters.iter().map(|c| c.start).collect();
+        assert_eq!(starts, vec![0.0, 40.0, 70.0, 100.0]);
+    }
+
+    #[test]
+    fn test_missing_duration_drops_later_tracks() {
+        let mut lib = figaro();
+        lib.tracks[0].duration_seconds = None;
+        let result = act_chapters(&lib, ChapterSplit::Number);
+        assert_eq!(result.warnings.len(), 2);
+        assert_eq!(result.acts.len(), 1);
+        // The last chapter has no known end, so it ends where it starts.
+        assert_eq!(result.acts[0].chapters.last().unwrap().end, 70.0);
+    }
+
+    #[test]
+    fn test_chapter_files() {
+        let result = act_chapters(&figaro(), ChapterSplit::Number);
+        let meta = ffmetadata(&figaro(), &result.acts[0]);
+        assert!(meta.starts_with(";FFMETADATA1\ntitle=Le nozze di Figaro — Act 1\nartist=W. A. Mozart\n"));
+        assert!(meta.contains("[CHAPTER]\nTIMEBASE=1/1000\nSTART=70000\nEND=100000\ntitle=Cosa stai misurando,\n"));
+
+        let xml = mkv_chapters(&result.acts[1]);
+        assert!(xml.contains("<ChapterTimeStart>00:00:05.000000000</ChapterTimeStart>"));
+        assert!(xml.contains("<ChapterTimeEnd>00:03:20.000000000</ChapterTimeEnd>"));
+        assert!(xml.contains("<ChapterString>Porgi, amor</ChapterString>"));
+    }
+}
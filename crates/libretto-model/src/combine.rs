@@ -0,0 +1,209 @@
+// Combining timing overlays made by different people for one recording.
+//
+// Timing a whole opera is shared work: one contributor taps Act I, another
+// Act II, and gaps get filled with estimates. `combine_overlays` merges two
+// such overlays segment by segment. Where both time the same segment, the
+// time with the more trustworthy source wins (see `TimingSource`), and the
+// first overlay wins ties; times that differ noticeably are reported as
+// conflicts for someone to check against the recording.
+
+use thiserror::Error;
+
+use crate::timing_overlay::{SegmentTime, TimingOverlay};
+
+/// Default difference between two times for the same segment, in seconds,
+/// above which they are reported as a conflict.
+pub const DEFAULT_CONFLICT_TOLERANCE_SECONDS: f64 = 0.25;
+
+#[derive(Debug, Error)]
+pub enum CombineError {
+    #[error("overlays reference different base librettos: '{first}' and '{second}'")]
+    DifferentBase { first: String, second: String },
+}
+
+/// Two differing times for the same segment.
+#[derive(Debug, Clone)]
+pub struct TimingConflict {
+    pub track_id: String,
+    /// The time kept in the combined overlay.
+    pub kept: SegmentTime,
+    /// The time dropped in its favour.
+    pub dropped: SegmentTime,
+}
+
+/// Result of combining two overlays.
+#[derive(Debug)]
+pub struct CombineResult {
+    pub overlay: TimingOverlay,
+    /// Segment times only the second overlay had.
+    pub added: usize,
+    /// Segment times of the first overlay replaced by better-sourced ones from the second.
+    pub replaced: usize,
+    pub conflicts: Vec<TimingConflict>,
+    pub warnings: Vec<String>,
+}
+
+/// Combine two overlays of the same base libretto and recording.
+///
+/// Tracks are matched by `track_id`; tracks only in `second` are appended.
+/// Track fields missing from the first overlay's track are taken from the
+/// second's. Contributors and omitted numbers of both are kept, without
+/// duplicates. Times for the same segment further apart than `tolerance`
+/// seconds (start or explicit end) are reported as conflicts.
+pub fn combine_overlays(
+    first: &TimingOverlay,
+    second: &TimingOverlay,
+    tolerance: f64,
+) -> Result<CombineResult, CombineError> {
+    if first.base_libretto != second.base_libretto {
+        return Err(CombineError::DifferentBase {
+            first: first.base_libretto.clone(),
+            second: second.base_libretto.clone(),
+        });
+    }
+
+    let mut overlay = first.clone();
+    let mut warnings = Vec::new();
+    if let (Some(a), Some(b)) = (&first.base_sha256, &second.base_sha256) {
+        if a != b {
+            warnings.push("Overlays were made against different versions of the base libretto".to_string());
+        }
+    }
+    let (ra, rb) = (&first.recording, &second.recording);
+    let differ = |a: Option<&str>, b: Option<&str>| matches!((a, b), (Some(a), Some(b)) if a != b);
+    if differ(ra.conductor.as_deref(), rb.conductor.as_deref())
+        || differ(ra.label.as_deref(), rb.label.as_deref())
+        || matches!((ra.year, rb.year), (Some(a), Some(b)) if a != b)
+    {
+        warnings.push("Overlays' recording metadata differ; they may not time the same recording".to_string());
+    }
+
+    for contributor in &second.contributors {
+        if !overlay.contributors.contains(contributor) {
+            overlay.contributors.push(contributor.clone());
+        }
+    }
+    for omitted in &second.omitted_numbers {
+        if !overlay.omitted_numbers.iter().any(|o| o.number_id == omitted.number_id) {
+            overlay.omitted_numbers.push(omitted.clone());
+        }
+    }
+    if overlay.rights.is_none() {
+        overlay.rights = second.rights.clone();
+    }
+    if overlay.padding.is_none() {
+        overlay.padding = second.padding;
+    }
+
+    let (mut added, mut replaced) = (0, 0);
+    let mut conflicts = Vec::new();
+    for (j, theirs) in second.track_timings.iter().enumerate() {
+        let track_id = theirs.track_id(j);
+        let Some(i) = overlay.find_track(&track_id) else {
+            added += theirs.segment_times.len();
+            overlay.track_timings.push(theirs.clone());
+            continue;
+        };
+        let ours = &mut overlay.track_timings[i];
+        if ours.duration_seconds.is_none() {
+            ours.duration_seconds = theirs.duration_seconds;
+        }
+        if ours.number_ids.is_empty() {
+            ours.number_ids = theirs.number_ids.clone();
+        }
+        if ours.start_segment_id.is_none() {
+            ours.start_segment_id = theirs.start_segment_id.clone();
+        }
+        if ours.padding.is_none() {
+            ours.padding = theirs.padding;
+        }
+
+        for time in &theirs.segment_times {
+            let Some(existing) = ours.segment_times.iter_mut().find(|t| t.segment_id == time.segment_id) else {
+                ours.segment_times.push(time.clone());
+                added += 1;
+                continue;
+            };
+            let ends_differ = matches!((existing.end, time.end), (Some(a), Some(b)) if (a - b).abs() > tolerance);
+            let differs = (existing.start - time.start).abs() > tolerance || ends_differ;
+            let better = time.trust() > existing.trust();
+            if differs {
+                let (kept, dropped) = if better { (time, &*existing) } else { (&*existing, time) };
+                conflicts.push(TimingConflict {
+                    track_id: track_id.clone(),
+                    kept: kept.clone(),
+                    dropped: dropped.clone(),
+                });
+            }
+            if better {
+                *existing = time.clone();
+                replaced += 1;
+            }
+        }
+        ours.sort_segment_times();
+    }
+
+    Ok(CombineResult { overlay, added, replaced, conflicts, warnings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing_overlay::TimingSource;
+
+    fn overlay(json: &str) -> TimingOverlay {
+        serde_json::from_str(&format!(r#"{{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {{ "conductor": "Giulini" }},
+            {json}
+        }}"#)).unwrap()
+    }
+
+    #[test]
+    fn test_combine_overlays() {
+        let first = overlay(r#""contributors": [{ "name": "Ada" }], "track_timings": [
+            { "track_title": "One", "disc_number": 1, "track_number": 1, "number_ids": ["no-1"], "segment_times": [
+                { "segment_id": "a", "start": 0.0 },
+                { "segment_id": "b", "start": 10.0, "source": "estimated" },
+                { "segment_id": "c", "start": 20.0 }
+            ] }
+        ]"#);
+        let second = overlay(r#""contributors": [{ "name": "Ada" }, { "name": "Bea" }], "track_timings": [
+            { "track_title": "One", "disc_number": 1, "track_number": 1, "number_ids": ["no-1"],
+              "duration_seconds": 40.0, "segment_times": [
+                { "segment_id": "b", "start": 12.5, "source": "verified" },
+                { "segment_id": "c", "start": 21.0, "source": "estimated" },
+                { "segment_id": "d", "start": 30.0 }
+            ] },
+            { "track_title": "Two", "disc_number": 1, "track_number": 2, "number_ids": ["no-2"], "segment_times": [
+                { "segment_id": "e", "start": 0.0 }
+            ] }
+        ]"#);
+
+        let result = combine_overlays(&first, &second, DEFAULT_CONFLICT_TOLERANCE_SECONDS).unwrap();
+        let track = &result.overlay.track_timings[0];
+        let starts: Vec<(&str, f64)> = track.segment_times.iter().map(|t| (t.segment_id.as_str(), t.start)).collect();
+        // The verified time replaces the estimate; the tapped time beats the estimate
+        assert_eq!(starts, vec![("a", 0.0), ("b", 12.5), ("c", 20.0), ("d", 30.0)]);
+        assert_eq!(track.segment_times[1].source, Some(TimingSource::Verified));
+        assert_eq!(track.duration_seconds, Some(40.0));
+        assert_eq!(result.overlay.track_timings.len(), 2);
+        assert_eq!((result.added, result.replaced), (2, 1));
+        assert_eq!(result.conflicts.len(), 2);
+        assert_eq!(result.conflicts[1].dropped.start, 21.0);
+        assert_eq!(result.overlay.contributors.len(), 2);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_combine_different_bases() {
+        let first = overlay(r#""track_timings": []"#);
+        let mut second = first.clone();
+        second.base_libretto = "other.libretto.json".to_string();
+        assert!(matches!(
+            combine_overlays(&first, &second, DEFAULT_CONFLICT_TOLERANCE_SECONDS),
+            Err(CombineError::DifferentBase { .. })
+        ));
+    }
+}
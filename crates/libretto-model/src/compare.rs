@@ -0,0 +1,187 @@
+// Comparing timing overlays of two recordings of the same opera.
+//
+// Two overlays of one base libretto say how long each recording takes over
+// each number. Side by side, the durations check an overlay (a number
+// twice as long as in every other recording is probably mistimed) and show
+// how conductors differ: the ratio of the durations is the relative tempo.
+// Numbers one recording performs and the other cuts are flagged.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::base_libretto::BaseLibretto;
+use crate::timing_overlay::{round_to_ms, TimingOverlay};
+
+/// Which of the two recordings perform a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Presence {
+    Both,
+    /// Cut (omitted, or on no track) in the second recording.
+    FirstOnly,
+    /// Cut in the first recording.
+    SecondOnly,
+    Neither,
+}
+
+/// One number's durations in the two recordings.
+#[derive(Debug, Clone, Serialize)]
+pub struct NumberComparison {
+    pub number_id: String,
+    pub label: String,
+    pub presence: Presence,
+    /// Seconds the first recording takes over the number, when known.
+    pub first: Option<f64>,
+    pub second: Option<f64>,
+    /// `first / second`: above 1 the first recording is slower.
+    pub ratio: Option<f64>,
+}
+
+/// Compare two overlays number by number, in libretto order.
+pub fn compare_overlays(base: &BaseLibretto, first: &TimingOverlay, second: &TimingOverlay) -> Vec<NumberComparison> {
+    let (durations_a, durations_b) = (number_durations(base, first), number_durations(base, second));
+    base.numbers.iter()
+        .map(|number| {
+            let id = number.id.as_str();
+            let presence = match (performs(first, id), performs(second, id)) {
+                (true, true) => Presence::Both,
+                (true, false) => Presence::FirstOnly,
+                (false, true) => Presence::SecondOnly,
+                (false, false) => Presence::Neither,
+            };
+            let (a, b) = (durations_a.get(id).copied(), durations_b.get(id).copied());
+            NumberComparison {
+                number_id: number.id.clone(),
+                label: number.label.clone(),
+                presence,
+                first: a,
+                second: b,
+                ratio: a.zip(b).filter(|&(_, b)| b > 0.0).map(|(a, b)| (a / b * 1000.0).round() / 1000.0),
+            }
+        })
+        .collect()
+}
+
+/// Whether `overlay`'s recording performs the number `number_id`.
+fn performs(overlay: &TimingOverlay, number_id: &str) -> bool {
+    overlay.track_timings.iter().any(|t| t.number_ids.iter().any(|n| n == number_id))
+        && !overlay.omitted_number_ids().contains(&number_id)
+}
+
+/// Seconds each number takes in `overlay`'s recording.
+///
+/// A timed segment lasts until its `end`, when it has one, else until the
+/// next segment in its track starts, or the track ends; a number lasts as long as its segments together. A
+/// track without segment times holding a single number gives that number
+/// the track's duration. Numbers with a segment whose length is unknown
+/// are left out.
+pub fn number_durations(base: &BaseLibretto, overlay: &TimingOverlay) -> HashMap<String, f64> {
+    let number_of: HashMap<&str, &str> = base.numbers.iter()
+        .flat_map(|n| n.segments.iter().map(move |s| (s.id.as_str(), n.id.as_str())))
+        .collect();
+
+    let mut durations: HashMap<&str, f64> = HashMap::new();
+    let mut unknown: Vec<&str> = Vec::new();
+    for track in &overlay.track_timings {
+        if track.segment_times.is_empty() {
+            if let ([number_id], Some(duration)) = (track.number_ids.as_slice(), track.duration_seconds) {
+                *durations.entry(number_id.as_str()).or_default() += duration;
+            }
+            continue;
+        }
+        for (i, time) in track.segment_times.iter().enumerate() {
+            let Some(&number_id) = number_of.get(time.segment_id.as_str()) else {
+                continue;
+            };
+            let until = time.end
+                .or_else(|| track.segment_times.get(i + 1).map(|next| next.start))
+                .or(track.duration_seconds);
+            match until {
+                Some(until) => *durations.entry(number_id).or_default() += (until - time.start).max(0.0),
+                None => unknown.push(number_id),
+            }
+        }
+    }
+
+    durations.into_iter()
+        .filter(|(id, _)| !unknown.contains(id))
+        .map(|(id, d)| (id.to_string(), round_to_ms(d)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base() -> BaseLibretto {
+        serde_json::from_str(r#"{
+            "version": "1.0",
+            "opera": { "title": "T", "composer": "C", "language": "it" },
+            "cast": [],
+            "numbers": [
+                { "id": "no-1", "label": "No. 1", "number_type": "duet", "act": "1", "segments": [
+                    { "id": "no-1-001", "text": "Cinque" }, { "id": "no-1-002", "text": "dieci" }
+                ] },
+                { "id": "no-2", "label": "No. 2", "number_type": "aria", "act": "1", "segments": [
+                    { "id": "no-2-001", "text": "Se vuol ballare" }
+                ] },
+                { "id": "no-3", "label": "No. 3", "number_type": "aria", "act": "1", "segments": [
+                    { "id": "no-3-001", "text": "La vendetta" }
+                ] }
+            ]
+        }"#).unwrap()
+    }
+
+    fn overlay(json: &str) -> TimingOverlay {
+        serde_json::from_str(&format!(
+            r#"{{ "version": "1.0", "base_libretto": "base.libretto.json", "recording": {{}}, {json} }}"#
+        )).unwrap()
+    }
+
+    #[test]
+    fn test_compare_overlays() {
+        let first = overlay(r#""track_timings": [
+            { "track_title": "Duet", "number_ids": ["no-1"], "duration_seconds": 180.0, "segment_times": [
+                { "segment_id": "no-1-001", "start": 0.0 }, { "segment_id": "no-1-002", "start": 90.0 }
+            ] },
+            { "track_title": "Aria", "number_ids": ["no-2"], "duration_seconds": 200.0 },
+            { "track_title": "Vendetta", "number_ids": ["no-3"] }
+        ]"#);
+        // One track holding both numbers, and the third number cut
+        let second = overlay(r#""omitted_numbers": [{ "number_id": "no-3" }], "track_timings": [
+            { "track_title": "Duet and aria", "number_ids": ["no-1", "no-2"], "duration_seconds": 400.0,
+              "segment_times": [
+                { "segment_id": "no-1-001", "start": 0.0 },
+                { "segment_id": "no-1-002", "start": 100.0 },
+                { "segment_id": "no-2-001", "start": 200.0 }
+            ] }
+        ]"#);
+
+        let rows = compare_overlays(&base(), &first, &second);
+        let summary: Vec<_> = rows.iter()
+            .map(|r| (r.number_id.as_str(), r.presence, r.first, r.second, r.ratio))
+            .collect();
+        assert_eq!(summary, vec![
+            ("no-1", Presence::Both, Some(180.0), Some(200.0), Some(0.9)),
+            ("no-2", Presence::Both, Some(200.0), Some(200.0), Some(1.0)),
+            // Performed in the first, but its length is unknown without a track duration
+            ("no-3", Presence::FirstOnly, None, None, None),
+        ]);
+    }
+
+    #[test]
+    fn test_end_wins() {
+        let timed = overlay(r#""track_timings": [
+            { "track_title": "Duet and aria", "number_ids": ["no-1", "no-2"], "duration_seconds": 400.0,
+              "segment_times": [
+                { "segment_id": "no-1-001", "start": 0.0 },
+                { "segment_id": "no-1-002", "start": 100.0, "end": 150.0 },
+                { "segment_id": "no-2-001", "start": 200.0, "end": 380.0 }
+            ] }
+        ]"#);
+        let durations = number_durations(&base(), &timed);
+        assert_eq!(durations["no-1"], 150.0);
+        assert_eq!(durations["no-2"], 180.0);
+    }
+}
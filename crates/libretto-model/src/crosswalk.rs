@@ -0,0 +1,555 @@
+// Cross-walk between two base librettos of the same opera.
+//
+// Different sources (murashev.com, opera-arias.com, hand-prepared texts)
+// produce bases with different segment IDs, so a timing overlay made against
+// one cannot be used with the other. A `SegmentMap` records which segment
+// in the source base corresponds to which segment in the target base, and
+// `retarget_overlay` uses it to rewrite an overlay against the target.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::annotation_overlay::AnnotationOverlay;
+use crate::base_libretto::{BaseLibretto, Segment};
+use crate::pronunciation_overlay::PronunciationOverlay;
+use crate::resolve::normalize_for_match;
+use crate::timing_overlay::{OmittedNumber, TimingOverlay};
+
+/// A mapping document between the segment/number IDs of two base librettos.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentMap {
+    pub version: String,
+    /// Path to the base libretto whose IDs are mapped from.
+    pub source_base: String,
+    /// Path to the base libretto whose IDs are mapped to.
+    pub target_base: String,
+    pub segments: Vec<IdMapping>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub numbers: Vec<IdMapping>,
+}
+
+/// One source ID ↔ target ID correspondence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdMapping {
+    pub source: String,
+    pub target: String,
+    /// Match confidence in `[0, 1]`; 1.0 means identical normalized text.
+    pub confidence: f64,
+}
+
+/// Result of building a segment map.
+#[derive(Debug)]
+pub struct MapResult {
+    pub map: SegmentMap,
+    /// Source segment IDs with no acceptable counterpart in the target.
+    pub unmapped: Vec<String>,
+    /// Unmapped segments whose counterpart lies beyond the look-ahead
+    /// window, where the two bases have drifted too far apart to match.
+    pub warnings: Vec<String>,
+}
+
+/// Result of retargeting an overlay.
+#[derive(Debug)]
+pub struct RetargetResult {
+    pub overlay: TimingOverlay,
+    pub warnings: Vec<String>,
+}
+
+/// Minimum similarity for two segments to be considered the same passage.
+const MIN_CONFIDENCE: f64 = 0.5;
+
+/// How far ahead of the last match to look for the next one in the target.
+const LOOKAHEAD: usize = 25;
+
+impl SegmentMap {
+    /// Look up the target segment ID for a source segment ID.
+    pub fn target_segment(&self, source: &str) -> Option<&str> {
+        self.segments.iter().find(|m| m.source == source).map(|m| m.target.as_str())
+    }
+
+    /// Look up the target number ID for a source number ID.
+    pub fn target_number(&self, source: &str) -> Option<&str> {
+        self.numbers.iter().find(|m| m.source == source).map(|m| m.target.as_str())
+    }
+
+    /// The mappings indexed by source ID, for looking up every ID of an
+    /// overlay.
+    fn index(&self) -> MapIndex<'_> {
+        fn index(mappings: &[IdMapping]) -> HashMap<&str, &str> {
+            let mut index = HashMap::new();
+            for m in mappings {
+                // The first mapping wins, as with `target_segment`
+                index.entry(m.source.as_str()).or_insert(m.target.as_str());
+            }
+            index
+        }
+        MapIndex { segments: index(&self.segments), numbers: index(&self.numbers) }
+    }
+}
+
+/// A `SegmentMap`'s source → target IDs.
+struct MapIndex<'a> {
+    segments: HashMap<&'a str, &'a str>,
+    numbers: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> MapIndex<'a> {
+    fn target_segment(&self, source: &str) -> Option<&'a str> {
+        self.segments.get(source).copied()
+    }
+
+    fn target_number(&self, source: &str) -> Option<&'a str> {
+        self.numbers.get(source).copied()
+    }
+}
+
+/// Text used to compare segments: sung text, or the direction for
+/// direction/interlude segments, normalized down to bare words.
+pub(crate) fn comparable_text(seg: &Segment) -> String {
+    let raw = seg.text.as_deref().or(seg.direction.as_deref()).unwrap_or("");
+    normalize_for_match(raw)
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Dice coefficient over the word sets of two normalized strings.
+fn similarity(a: &str, b: &str) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+    let wa: Vec<&str> = a.split_whitespace().collect();
+    let wb: Vec<&str> = b.split_whitespace().collect();
+    let common = wa.iter().filter(|w| wb.contains(w)).count();
+    (2 * common) as f64 / (wa.len() + wb.len()) as f64
+}
+
+/// Build a segment map from `source` to `target` by text matching.
+///
+/// Walks the source segments in order and, for each, looks for the most
+/// similar target segment within a window after the previous match. Keeping
+/// the walk monotonic stops repeated text (refrains, "Susanna, or via
+/// sortite") from mapping to the wrong occurrence. A segment left unmapped
+/// although it matches a target segment past the window gets a warning.
+pub fn build_segment_map(
+    source: &BaseLibretto,
+    target: &BaseLibretto,
+    source_path: &str,
+    target_path: &str,
+) -> MapResult {
+    let target_segments: Vec<(&str, &Segment)> = target.numbers.iter()
+        .flat_map(|n| n.segments.iter().map(move |s| (n.id.as_str(), s)))
+        .collect();
+    let target_texts: Vec<String> = target_segments.iter().map(|(_, s)| comparable_text(s)).collect();
+
+    let mut segments = Vec::new();
+    let mut unmapped = Vec::new();
+    let mut warnings = Vec::new();
+    // source number → (target number → matched segment count)
+    let mut number_votes: Vec<(&str, usize, HashMap<&str, usize>)> = Vec::new();
+    let mut cursor = 0;
+
+    for number in &source.numbers {
+        let mut votes: HashMap<&str, usize> = HashMap::new();
+        for seg in &number.segments {
+            let text = comparable_text(seg);
+            let window_end = (cursor + LOOKAHEAD).min(target_segments.len());
+            let best = (cursor..window_end)
+                .map(|j| (j, similarity(&text, &target_texts[j])))
+                .fold(None, |best: Option<(usize, f64)>, (j, score)| match best {
+                    Some((_, b)) if b >= score => best,
+                    _ => Some((j, score)),
+                });
+
+            match best {
+                Some((j, score)) if score >= MIN_CONFIDENCE => {
+                    let (target_number, target_seg) = target_segments[j];
+                    segments.push(IdMapping {
+                        source: seg.id.clone(),
+                        target: target_seg.id.clone(),
+                        confidence: (score * 1000.0).round() / 1000.0,
+                    });
+                    *votes.entry(target_number).or_default() += 1;
+                    cursor = j + 1;
+                }
+                _ => {
+                    let beyond = (window_end..target_segments.len())
+                        .find(|&j| similarity(&text, &target_texts[j]) >= MIN_CONFIDENCE);
+                    if let Some(j) = beyond {
+                        warnings.push(format!(
+                            "Segment '{}' matches '{}', {} segments past the look-ahead window; left unmapped",
+                            seg.id, target_segments[j].1.id, j - cursor
+                        ));
+                    }
+                    unmapped.push(seg.id.clone());
+                }
+            }
+        }
+        number_votes.push((number.id.as_str(), number.segments.len(), votes));
+    }
+
+    let numbers = number_votes.into_iter()
+        .filter_map(|(source_number, total, votes)| {
+            let (target_number, count) = votes.into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+            Some(IdMapping {
+                source: source_number.to_string(),
+                target: target_number.to_string(),
+                confidence: ((count as f64 / total as f64) * 1000.0).round() / 1000.0,
+            })
+        })
+        .collect();
+
+    MapResult {
+        map: SegmentMap {
+            version: "1.0".to_string(),
+            source_base: source_path.to_string(),
+            target_base: target_path.to_string(),
+            segments,
+            numbers,
+        },
+        unmapped,
+        warnings,
+    }
+}
+
+/// Rewrite an overlay made against the map's source base so that it
+/// references the target base instead.
+///
+/// Segment times and skipped segments whose segment has no mapping are
+/// dropped with a warning.
+/// Track `number_ids` and omitted numbers are translated through the number
+/// mapping; unmapped numbers are dropped with a warning. The source base's
+/// `base_sha256` no longer applies and is cleared.
+pub fn retarget_overlay(overlay: &TimingOverlay, map: &SegmentMap, target_path: &str) -> RetargetResult {
+    let map = map.index();
+    let mut result = overlay.clone();
+    let mut warnings = Vec::new();
+    result.base_libretto = target_path.to_string();
+    result.base_sha256 = None;
+
+    for track in &mut result.track_timings {
+        let mut number_ids: Vec<String> = Vec::new();
+        for nid in &track.number_ids {
+            match map.target_number(nid) {
+                Some(t) => {
+                    if !number_ids.iter().any(|n| n == t) {
+                        number_ids.push(t.to_string());
+                    }
+                }
+                None => warnings.push(format!(
+                    "Track '{}': number '{}' has no mapping, dropped", track.track_title, nid
+                )),
+            }
+        }
+        track.number_ids = number_ids;
+
+        if let Some(sid) = track.start_segment_id.take() {
+            track.start_segment_id = map.target_segment(&sid).map(str::to_string);
+            if track.start_segment_id.is_none() {
+                warnings.push(format!(
+                    "Track '{}': start segment '{}' has no mapping, cleared", track.track_title, sid
+                ));
+            }
+        }
+
+        let title = track.track_title.clone();
+        track.skip_segment_ids.retain_mut(|sid| match map.target_segment(sid) {
+            Some(t) => {
+                *sid = t.to_string();
+                true
+            }
+            None => {
+                warnings.push(format!("Track '{}': skipped segment '{}' has no mapping, dropped", title, sid));
+                false
+            }
+        });
+        track.section_marks.retain_mut(|mark| match map.target_segment(&mark.segment_id) {
+            Some(t) => {
+                mark.segment_id = t.to_string();
+                true
+            }
+            None => {
+                warnings.push(format!("Track '{}': section mark '{}' has no mapping, dropped", title, mark.segment_id));
+                false
+            }
+        });
+        track.segment_times.retain_mut(|st| match map.target_segment(&st.segment_id) {
+            Some(t) => {
+                st.segment_id = t.to_string();
+                true
+            }
+            None => {
+                warnings.push(format!(
+                    "Track '{}': segment '{}' has no mapping, dropped", title, st.segment_id
+                ));
+                false
+            }
+        });
+    }
+
+    result.omitted_numbers = overlay.omitted_numbers.iter()
+        .filter_map(|o| match map.target_number(&o.number_id) {
+            Some(t) => Some(OmittedNumber { number_id: t.to_string(), reason: o.reason.clone() }),
+            None => {
+                warnings.push(format!("Omitted number '{}' has no mapping, dropped", o.number_id));
+                None
+            }
+        })
+        .collect();
+
+    RetargetResult { overlay: result, warnings }
+}
+
+/// Rewrite an annotation overlay's segment and number IDs through `map`,
+/// as `retarget_overlay` does for a timing overlay. Annotations on an
+/// unmapped segment or number are dropped with a warning.
+pub fn retarget_annotations(overlay: &AnnotationOverlay, map: &SegmentMap, target_path: &str) -> (AnnotationOverlay, Vec<String>) {
+    let map = map.index();
+    let mut result = overlay.clone();
+    let mut warnings = Vec::new();
+    result.base_libretto = target_path.to_string();
+    result.annotations.retain_mut(|annotation| {
+        if let Some(sid) = &mut annotation.segment_id {
+            match map.target_segment(sid) {
+                Some(t) => *sid = t.to_string(),
+                None => {
+                    warnings.push(format!("Annotation on segment '{sid}' has no mapping, dropped"));
+                    return false;
+                }
+            }
+        }
+        if let Some(nid) = &mut annotation.number_id {
+            match map.target_number(nid) {
+                Some(t) => *nid = t.to_string(),
+                None => {
+                    warnings.push(format!("Annotation on number '{nid}' has no mapping, dropped"));
+                    return false;
+                }
+            }
+        }
+        true
+    });
+    (result, warnings)
+}
+
+/// Rewrite a pronunciation overlay's segment IDs through `map`, as
+/// `retarget_overlay` does for a timing overlay. Pronunciations of an
+/// unmapped segment are dropped with a warning.
+pub fn retarget_pronunciations(overlay: &PronunciationOverlay, map: &SegmentMap, target_path: &str) -> (PronunciationOverlay, Vec<String>) {
+    let map = map.index();
+    let mut result = overlay.clone();
+    let mut warnings = Vec::new();
+    result.base_libretto = target_path.to_string();
+    result.pronunciations.retain_mut(|pronunciation| match map.target_segment(&pronunciation.segment_id) {
+        Some(t) => {
+            pronunciation.segment_id = t.to_string();
+            true
+        }
+        None => {
+            warnings.push(format!("Pronunciation of segment '{}' has no mapping, dropped", pronunciation.segment_id));
+            false
+        }
+    });
+    (result, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_libretto::*;
+    use crate::timing_overlay::*;
+
+    fn base(ids: &[(&str, &str, &str)]) -> BaseLibretto {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Test".to_string(),
+            composer: "Test".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: None,
+            year: None,
+        });
+        for (number_id, seg_id, text) in ids {
+            if lib.find_number(number_id).is_none() {
+                lib.numbers.push(MusicalNumber {
+                    id: number_id.to_string(),
+                    label: number_id.to_string(),
+                    number_type: NumberType::Aria,
+                    act: "1".to_string(),
+                    scene: None,
+                    segments: vec![],
+                });
+            }
+            let number = lib.numbers.iter_mut().find(|n| n.id == *number_id).unwrap();
+            number.segments.push(Segment {
+                id: seg_id.to_string(),
+                segment_type: SegmentType::Sung,
+                character: None,
+                text: Some(text.to_string()),
+                translation: None,
+                direction: None,
+                group: None,
+            });
+        }
+        lib
+    }
+
+    #[test]
+    fn test_build_segment_map() {
+        let source = base(&[
+            ("no-1-duettino", "no-1-duettino-001", "Cinque... dieci... venti..."),
+            ("no-1-duettino", "no-1-duettino-002", "Ora sì ch'io son contenta"),
+            ("no-2-aria", "no-2-aria-001", "Se vuol ballare, signor contino"),
+        ]);
+        let target = base(&[
+            ("n1", "n1-001", "Cinque, dieci, venti"),
+            ("n1", "n1-002", "Ora sì, ch'io son contenta!"),
+            ("n2", "n2-001", "Se vuol ballare signor Contino"),
+        ]);
+
+        let result = build_segment_map(&source, &target, "a.json", "b.json");
+        assert!(result.unmapped.is_empty(), "unmapped: {:?}", result.unmapped);
+        assert_eq!(result.map.target_segment("no-1-duettino-002"), Some("n1-002"));
+        assert_eq!(result.map.target_segment("no-2-aria-001"), Some("n2-001"));
+        assert_eq!(result.map.target_number("no-2-aria"), Some("n2"));
+        assert_eq!(result.map.segments[0].confidence, 1.0);
+    }
+
+    #[test]
+    fn test_repeated_text_maps_monotonically() {
+        let source = base(&[
+            ("a", "a-001", "Susanna, or via sortite"),
+            ("a", "a-002", "Something else entirely here"),
+            ("a", "a-003", "Susanna, or via sortite"),
+        ]);
+        let target = base(&[
+            ("x", "x-001", "Susanna or via sortite"),
+            ("x", "x-002", "Something else entirely here"),
+            ("x", "x-003", "Susanna or via sortite"),
+        ]);
+        let result = build_segment_map(&source, &target, "a.json", "b.json");
+        assert_eq!(result.map.target_segment("a-003"), Some("x-003"));
+    }
+
+    #[test]
+    fn test_match_beyond_window_warns() {
+        // The target has a long passage the source lacks
+        let mut target_ids = vec![("x".to_string(), "x-001".to_string(), "Cinque dieci venti trenta".to_string())];
+        for i in 0..LOOKAHEAD {
+            target_ids.push(("x".to_string(), format!("x-{:03}", i + 2), format!("Interpolated line number {i}")));
+        }
+        target_ids.push(("x".to_string(), "x-099".to_string(), "Se vuol ballare signor contino".to_string()));
+        let target_ids: Vec<(&str, &str, &str)> = target_ids.iter().map(|(n, s, t)| (n.as_str(), s.as_str(), t.as_str())).collect();
+        let source = base(&[
+            ("a", "a-001", "Cinque dieci venti trenta"),
+            ("a", "a-002", "Se vuol ballare signor contino"),
+        ]);
+        let result = build_segment_map(&source, &base(&target_ids), "a.json", "b.json");
+        assert_eq!(result.unmapped, vec!["a-002"]);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("'x-099', 25 segments past"), "{}", result.warnings[0]);
+    }
+
+    #[test]
+    fn test_retarget_overlay() {
+        let map = SegmentMap {
+            version: "1.0".to_string(),
+            source_base: "a.json".to_string(),
+            target_base: "b.json".to_string(),
+            segments: vec![IdMapping { source: "a-001".to_string(), target: "x-001".to_string(), confidence: 1.0 }],
+            numbers: vec![IdMapping { source: "a".to_string(), target: "x".to_string(), confidence: 1.0 }],
+        };
+        let overlay = TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "a.json".to_string(),
+            recording: RecordingMetadata {
+                conductor: None, orchestra: None, year: None, label: None, album_title: None,
+            },
+            omitted_numbers: vec![OmittedNumber { number_id: "gone".to_string(), reason: None }],
+            track_timings: vec![TrackTiming {
+                track_title: "Track 1".to_string(),
+                number_ids: vec!["a".to_string()],
+                start_segment_id: Some("a-001".to_string()),
+                skip_segment_ids: vec!["a-001".to_string(), "a-003".to_string()],
+                segment_times: vec![
+                    SegmentTime { segment_id: "a-001".to_string(), start: 0.0, end: None, source: None },
+                    SegmentTime { segment_id: "a-002".to_string(), start: 5.0, end: None, source: None },
+                ],
+                ..Default::default()
+            }],
+            base_sha256: Some("0".repeat(64)),
+            ..Default::default()
+        };
+
+        let result = retarget_overlay(&overlay, &map, "b.json");
+        assert_eq!(result.overlay.base_sha256, None);
+        let track = &result.overlay.track_timings[0];
+        assert_eq!(result.overlay.base_libretto, "b.json");
+        assert_eq!(track.number_ids, vec!["x"]);
+        assert_eq!(track.start_segment_id.as_deref(), Some("x-001"));
+        assert_eq!(track.segment_times.len(), 1);
+        assert_eq!(track.segment_times[0].segment_id, "x-001");
+        assert_eq!(track.skip_segment_ids, vec!["x-001"]);
+        assert!(result.overlay.omitted_numbers.is_empty());
+        assert_eq!(result.warnings.len(), 3);
+    }
+
+    #[test]
+    fn test_retarget_annotations() {
+        let map = SegmentMap {
+            version: "1.0".to_string(),
+            source_base: "a.json".to_string(),
+            target_base: "b.json".to_string(),
+            segments: vec![IdMapping { source: "a-001".to_string(), target: "x-001".to_string(), confidence: 1.0 }],
+            numbers: vec![IdMapping { source: "a".to_string(), target: "x".to_string(), confidence: 1.0 }],
+        };
+        let overlay: AnnotationOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "a.json",
+            "annotations": [
+                { "segment_id": "a-001", "text": "Figaro measures the room." },
+                { "number_id": "a", "kind": "synopsis", "text": "The wedding bed." },
+                { "segment_id": "a-009", "text": "Gone." }
+            ]
+        }"#).unwrap();
+
+        let (result, warnings) = retarget_annotations(&overlay, &map, "b.json");
+        assert_eq!(result.base_libretto, "b.json");
+        assert_eq!(result.annotations.len(), 2);
+        assert_eq!(result.annotations[0].segment_id.as_deref(), Some("x-001"));
+        assert_eq!(result.annotations[1].number_id.as_deref(), Some("x"));
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_retarget_pronunciations() {
+        let map = SegmentMap {
+            version: "1.0".to_string(),
+            source_base: "a.json".to_string(),
+            target_base: "b.json".to_string(),
+            segments: vec![IdMapping { source: "a-001".to_string(), target: "x-001".to_string(), confidence: 1.0 }],
+            numbers: vec![],
+        };
+        let overlay: PronunciationOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "a.json",
+            "pronunciations": [
+                { "segment_id": "a-001", "line": 2, "text": "ˈdjɛ.tʃi" },
+                { "segment_id": "a-009", "text": "Gone." }
+            ]
+        }"#).unwrap();
+
+        let (result, warnings) = retarget_pronunciations(&overlay, &map, "b.json");
+        assert_eq!(result.base_libretto, "b.json");
+        assert_eq!(result.pronunciations.len(), 1);
+        assert_eq!((result.pronunciations[0].segment_id.as_str(), result.pronunciations[0].line), ("x-001", Some(2)));
+        assert_eq!(warnings.len(), 1);
+    }
+}
@@ -0,0 +1,444 @@
+// Scaffolding timing overlays from CD cue sheets.
+//
+// Archival rips often come with a `.cue` file listing each track's title
+// and where it starts, to the CD frame (1/75 s). A whole disc is usually
+// one audio file with `INDEX 01` marking each track's start within it;
+// some rips have one file per track instead. Either way the start of the
+// next track in the same file gives a track's exact duration, so a
+// scaffold built from cue sheets is ready for `timing resolve` once its
+// tracks' `number_ids` are filled in.
+//
+// Going the other way, an overlay's track list and durations are written
+// out as one cue sheet per disc, so a single-file rip can be split again
+// to match the track layout the overlay was timed against.
+
+use thiserror::Error;
+
+use crate::base_libretto::BaseLibretto;
+use crate::timeline::track_offsets;
+use crate::timing_overlay::{round_to_ms, TimingOverlay, TrackTiming};
+
+/// CD frames per second, the unit of cue sheet times.
+const FRAMES_PER_SECOND: f64 = 75.0;
+
+#[derive(Debug, Error)]
+pub enum CueError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    #[error("cue sheet has no tracks")]
+    NoTracks,
+}
+
+/// The parts of a cue sheet a timing overlay needs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CueSheet {
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    /// From `REM DATE`.
+    pub year: Option<u16>,
+    /// From `REM DISCNUMBER`.
+    pub disc_number: Option<u32>,
+    pub tracks: Vec<CueTrack>,
+}
+
+/// One `TRACK` of a cue sheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CueTrack {
+    pub number: u32,
+    pub title: Option<String>,
+    pub performer: Option<String>,
+    /// The `FILE` the track is in.
+    pub file: String,
+    /// Start of the track (`INDEX 01`) within its file, in seconds.
+    pub start: f64,
+}
+
+/// Decode a cue sheet's bytes. Rippers write UTF-8 (sometimes with a byte
+/// order mark) or, older ones, Latin-1.
+pub fn decode_cue_sheet(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => bytes.iter().map(|&b| char::from(b)).collect(),
+    }
+}
+
+/// Parse a cue sheet. Commands other than those kept in `CueSheet` and
+/// `CueTrack` (`FLAGS`, `ISRC`, `PREGAP`, other `REM`s, …) are skipped.
+///
+/// A `TITLE` or `PERFORMER` runs to the end of its line, so quotes inside
+/// it (the anchors in opera track titles) are kept.
+pub fn parse_cue_sheet(contents: &str) -> Result<CueSheet, CueError> {
+    let mut sheet = CueSheet::default();
+    let mut file: Option<String> = None;
+    let mut pending: Option<PendingTrack> = None;
+
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let err = |message: String| CueError::Parse { line: line_number, message };
+        let line = line.trim();
+        let words = split_cue_line(line);
+        let Some(command) = words.first() else {
+            continue;
+        };
+        let arg = |n: usize| words.get(n).map(String::as_str).unwrap_or_default();
+
+        match command.to_ascii_uppercase().as_str() {
+            "FILE" => {
+                sheet.tracks.extend(finish_track(pending.take(), &file)?);
+                file = Some(arg(1).to_string());
+            }
+            "TRACK" => {
+                sheet.tracks.extend(finish_track(pending.take(), &file)?);
+                let number = arg(1).parse().map_err(|_| err(format!("invalid track number '{}'", arg(1))))?;
+                pending = Some(PendingTrack { line: line_number, number, title: None, performer: None, start: None });
+            }
+            upper @ ("TITLE" | "PERFORMER") => {
+                let value = line[command.len()..].trim();
+                let value = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')).unwrap_or(value);
+                let value = Some(value.to_string());
+                match (&mut pending, upper) {
+                    (Some(track), "TITLE") => track.title = value,
+                    (Some(track), _) => track.performer = value,
+                    (None, "TITLE") => sheet.title = value,
+                    (None, _) => sheet.performer = value,
+                }
+            }
+            "INDEX" if arg(1) == "01" => {
+                let start = parse_cue_time(arg(2)).ok_or_else(|| err(format!("invalid time '{}'", arg(2))))?;
+                match &mut pending {
+                    Some(track) => track.start = Some(start),
+                    None => return Err(err("INDEX outside a TRACK".to_string())),
+                }
+            }
+            "REM" => match arg(1).to_ascii_uppercase().as_str() {
+                "DATE" => sheet.year = arg(2).get(..4).and_then(|y| y.parse().ok()),
+                "DISCNUMBER" => sheet.disc_number = arg(2).parse().ok(),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    sheet.tracks.extend(finish_track(pending, &file)?);
+
+    if sheet.tracks.is_empty() {
+        return Err(CueError::NoTracks);
+    }
+    Ok(sheet)
+}
+
+/// A `TRACK` being read, complete once its `INDEX 01` is.
+struct PendingTrack {
+    line: usize,
+    number: u32,
+    title: Option<String>,
+    performer: Option<String>,
+    start: Option<f64>,
+}
+
+fn finish_track(pending: Option<PendingTrack>, file: &Option<String>) -> Result<Option<CueTrack>, CueError> {
+    let Some(track) = pending else {
+        return Ok(None);
+    };
+    let start = track.start.ok_or_else(|| CueError::Parse {
+        line: track.line,
+        message: format!("track {} has no INDEX 01", track.number),
+    })?;
+    Ok(Some(CueTrack {
+        number: track.number,
+        title: track.title,
+        performer: track.performer,
+        file: file.clone().unwrap_or_default(),
+        start,
+    }))
+}
+
+/// Split a cue sheet line into words, keeping double-quoted strings whole.
+fn split_cue_line(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(|c: char| !c.is_whitespace()) {
+        rest = &rest[start..];
+        let (word, remainder) = match rest.strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                None => (quoted, ""),
+            },
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (&rest[..end], &rest[end..])
+            }
+        };
+        words.push(word.to_string());
+        rest = remainder;
+    }
+    words
+}
+
+/// Parse a cue sheet time, `mm:ss:ff` with 75 frames per second.
+fn parse_cue_time(time: &str) -> Option<f64> {
+    let mut parts = time.split(':').map(|p| p.parse::<u32>().ok());
+    let (minutes, seconds, frames) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || seconds >= 60 || f64::from(frames) >= FRAMES_PER_SECOND {
+        return None;
+    }
+    let seconds = minutes.checked_mul(60)?.checked_add(seconds)?;
+    Some(f64::from(seconds) + f64::from(frames) / FRAMES_PER_SECOND)
+}
+
+/// Build a timing overlay scaffold with one track per cue sheet track.
+///
+/// `sheets` are the recording's discs in order; a sheet without `REM
+/// DISCNUMBER` is numbered by its position. A track's duration runs to the
+/// next track in the same file; the last track of each file gets none
+/// (`timing durations` can read it from the audio). Album title and year
+/// come from the first sheet that has them. Tracks have no `number_ids`.
+pub fn scaffold_from_cue(sheets: &[CueSheet], base_path: &str) -> TimingOverlay {
+    let mut track_timings = Vec::new();
+    for (i, sheet) in sheets.iter().enumerate() {
+        let disc = sheet.disc_number.unwrap_or(i as u32 + 1);
+        for (j, track) in sheet.tracks.iter().enumerate() {
+            let duration = sheet.tracks.get(j + 1)
+                .filter(|next| next.file == track.file && next.start > track.start)
+                .map(|next| round_to_ms(next.start - track.start));
+            track_timings.push(TrackTiming {
+                track_title: track.title.clone().unwrap_or_else(|| format!("Track {}", track.number)),
+                disc_number: Some(disc),
+                track_number: Some(track.number),
+                duration_seconds: duration,
+                ..Default::default()
+            });
+        }
+    }
+
+    let mut overlay = TimingOverlay::new(base_path);
+    overlay.recording.year = sheets.iter().find_map(|s| s.year);
+    overlay.recording.album_title = sheets.iter().find_map(|s| s.title.clone());
+    overlay.track_timings = track_timings;
+    overlay
+}
+
+/// One disc's cue sheet, written by `export_cue_sheets`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscCueSheet {
+    pub disc_number: Option<u32>,
+    pub contents: String,
+}
+
+impl DiscCueSheet {
+    /// File name for the sheet: `d1.cue`, or `album.cue` without a disc number.
+    pub fn file_name(&self) -> String {
+        match self.disc_number {
+            Some(d) => format!("d{d}.cue"),
+            None => "album.cue".to_string(),
+        }
+    }
+}
+
+/// Result of writing cue sheets.
+#[derive(Debug)]
+pub struct CueExport {
+    pub sheets: Vec<DiscCueSheet>,
+    pub warnings: Vec<String>,
+}
+
+/// Write an overlay's tracks as cue sheets, one per disc.
+///
+/// Each disc is taken to be one audio file, named by `file_name` with
+/// `{disc}` replaced by the disc number, and each track starts where the
+/// previous one's duration ends. Tracks after one without a duration
+/// cannot be placed and are left out with a warning. With a base
+/// libretto, each track also gets a `REM COMMENT` listing the labels of
+/// its numbers. Parsing a sheet back with `parse_cue_sheet` gives the
+/// overlay's tracks and durations to the CD frame.
+pub fn export_cue_sheets(overlay: &TimingOverlay, base: Option<&BaseLibretto>, file_name: &str) -> CueExport {
+    let mut discs: Vec<(Option<u32>, Vec<_>)> = Vec::new();
+    for (i, track) in overlay.track_timings.iter().enumerate() {
+        match discs.iter_mut().find(|(d, _)| *d == track.disc_number) {
+            Some((_, tracks)) => tracks.push((i, track)),
+            None => discs.push((track.disc_number, vec![(i, track)])),
+        }
+    }
+
+    let recording = &overlay.recording;
+    let performer = match (&recording.conductor, &recording.orchestra) {
+        (Some(c), Some(o)) => Some(format!("{c} / {o}")),
+        (c, o) => c.clone().or_else(|| o.clone()),
+    };
+    let mut warnings = Vec::new();
+    let mut sheets = Vec::new();
+    for (disc, tracks) in discs {
+        let mut out = String::from("REM GENRE Opera\n");
+        if let Some(year) = recording.year {
+            out.push_str(&format!("REM DATE {year}\n"));
+        }
+        if let Some(d) = disc {
+            out.push_str(&format!("REM DISCNUMBER {d}\n"));
+        }
+        if let Some(p) = &performer {
+            out.push_str(&format!("PERFORMER \"{}\"\n", cue_text(p)));
+        }
+        if let Some(title) = &recording.album_title {
+            out.push_str(&format!("TITLE \"{}\"\n", cue_text(title)));
+        }
+        let file = file_name.replace("{disc}", &disc.unwrap_or(1).to_string());
+        out.push_str(&format!("FILE \"{file}\" WAVE\n"));
+
+        let offsets = track_offsets(tracks.iter().map(|(_, t)| t.duration_seconds));
+        for (position, ((index, track), offset)) in tracks.iter().zip(offsets).enumerate() {
+            let Some(offset) = offset else {
+                warnings.push(format!(
+                    "Track '{}' cannot be placed in its disc's cue sheet (an earlier track has no duration)",
+                    track.track_id(*index)
+                ));
+                continue;
+            };
+            let number = track.track_number.unwrap_or(position as u32 + 1);
+            out.push_str(&format!("  TRACK {number:02} AUDIO\n"));
+            out.push_str(&format!("    TITLE \"{}\"\n", cue_text(&track.track_title)));
+            let labels: Vec<&str> = track.number_ids.iter()
+                .filter_map(|nid| base?.find_number(nid))
+                .map(|n| n.label.as_str())
+                .collect();
+            if !labels.is_empty() {
+                out.push_str(&format!("    REM COMMENT \"{}\"\n", cue_text(&labels.join(" / "))));
+            }
+            out.push_str(&format!("    INDEX 01 {}\n", format_cue_time(offset)));
+        }
+        sheets.push(DiscCueSheet { disc_number: disc, contents: out });
+    }
+    CueExport { sheets, warnings }
+}
+
+/// A value for a quoted cue sheet field. Cue sheets have no escape for a
+/// quote, and most readers end the value at the second one, so inner
+/// double quotes are written as single quotes.
+fn cue_text(value: &str) -> String {
+    value.replace('"', "'")
+}
+
+/// Format seconds as a cue sheet time, `mm:ss:ff`.
+fn format_cue_time(seconds: f64) -> String {
+    let frames = (seconds.max(0.0) * FRAMES_PER_SECOND).round() as u64;
+    let whole = frames / FRAMES_PER_SECOND as u64;
+    format!("{:02}:{:02}:{:02}", whole / 60, whole % 60, frames % FRAMES_PER_SECOND as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DISC_IMAGE: &str = r#"REM GENRE Opera
+REM DATE 1959
+PERFORMER "Carlo Maria Giulini"
+TITLE "Le nozze di Figaro (Giulini)"
+FILE "CD1.flac" WAVE
+  TRACK 01 AUDIO
+    TITLE "Sinfonia"
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    TITLE "Atto I: "Cinque... dieci...""
+    PERFORMER "Taddei, Moffo"
+    INDEX 00 04:10:00
+    INDEX 01 04:12:37
+  TRACK 03 AUDIO
+    TITLE "Recitativo"
+    INDEX 01 07:27:37
+"#;
+
+    #[test]
+    fn test_parse_cue_sheet() {
+        let sheet = parse_cue_sheet(DISC_IMAGE).unwrap();
+        assert_eq!(sheet.title.as_deref(), Some("Le nozze di Figaro (Giulini)"));
+        assert_eq!((sheet.year, sheet.disc_number), (Some(1959), None));
+        assert_eq!(sheet.tracks.len(), 3);
+        let track = &sheet.tracks[1];
+        assert_eq!(track.title.as_deref(), Some("Atto I: \"Cinque... dieci...\""));
+        assert_eq!(track.performer.as_deref(), Some("Taddei, Moffo"));
+        assert_eq!((track.file.as_str(), track.start), ("CD1.flac", 252.0 + 37.0 / 75.0));
+
+        assert!(matches!(parse_cue_sheet("TRACK 01 AUDIO\n  TITLE \"x\"\n"), Err(CueError::Parse { line: 1, .. })));
+        assert!(matches!(parse_cue_sheet("INDEX 01 99:99:99"), Err(CueError::Parse { line: 1, .. })));
+        assert!(matches!(parse_cue_sheet("INDEX 01 4294967295:00:00"), Err(CueError::Parse { line: 1, .. })));
+        assert!(matches!(parse_cue_sheet("TITLE \"Empty\""), Err(CueError::NoTracks)));
+    }
+
+    #[test]
+    fn test_scaffold_from_cue() {
+        let first = parse_cue_sheet(DISC_IMAGE).unwrap();
+        // A second disc ripped one file per track
+        let second = parse_cue_sheet(
+            "REM DISCNUMBER 2\nFILE \"01.flac\" WAVE\n TRACK 01 AUDIO\n  INDEX 01 00:00:00\n\
+             FILE \"02.flac\" WAVE\n TRACK 02 AUDIO\n  INDEX 01 00:00:00\n",
+        ).unwrap();
+        let overlay = scaffold_from_cue(&[first, second], "base.libretto.json");
+
+        assert_eq!(overlay.recording.year, Some(1959));
+        let tracks: Vec<(Option<u32>, Option<u32>, Option<f64>)> = overlay.track_timings.iter()
+            .map(|t| (t.disc_number, t.track_number, t.duration_seconds))
+            .collect();
+        assert_eq!(tracks, vec![
+            (Some(1), Some(1), Some(252.493)),
+            (Some(1), Some(2), Some(195.0)),
+            (Some(1), Some(3), None),
+            (Some(2), Some(1), None),
+            (Some(2), Some(2), None),
+        ]);
+        assert_eq!(overlay.track_timings[3].track_title, "Track 1");
+    }
+
+    #[test]
+    fn test_decode_cue_sheet() {
+        assert_eq!(decode_cue_sheet(b"\xEF\xBB\xBFTITLE \"Tosca\""), "TITLE \"Tosca\"");
+        assert_eq!(decode_cue_sheet(b"TITLE \"Recondita armonia \xE8\""), "TITLE \"Recondita armonia è\"");
+    }
+
+    #[test]
+    fn test_export_cue_sheets() {
+        let first = parse_cue_sheet(DISC_IMAGE).unwrap();
+        let mut overlay = scaffold_from_cue(&[first], "base.libretto.json");
+        overlay.track_timings[2].duration_seconds = Some(300.0);
+        overlay.track_timings[1].number_ids = vec!["no-1-duettino".to_string()];
+        // A second disc whose first track has no duration
+        for n in 1..=2 {
+            let mut track = overlay.track_timings[0].clone();
+            track.disc_number = Some(2);
+            track.track_number = Some(n);
+            track.duration_seconds = None;
+            overlay.track_timings.push(track);
+        }
+
+        let mut base = BaseLibretto::new(crate::base_libretto::OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "Mozart".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: None,
+            year: None,
+        });
+        base.numbers.push(crate::base_libretto::MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "N° 1: Duettino".to_string(),
+            number_type: crate::base_libretto::NumberType::Duet,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![],
+        });
+
+        let export = export_cue_sheets(&overlay, Some(&base), "CD{disc}.flac");
+        assert_eq!(export.sheets.len(), 2);
+        assert_eq!(export.sheets[0].file_name(), "d1.cue");
+        assert!(export.sheets[0].contents.contains("    TITLE \"Atto I: 'Cinque... dieci...'\"\n    REM COMMENT \"N° 1: Duettino\"\n"));
+        assert_eq!(export.warnings.len(), 1);
+
+        // Round-trips through the cue sheet reader.
+        let sheet = parse_cue_sheet(&export.sheets[0].contents).unwrap();
+        assert_eq!((sheet.year, sheet.disc_number), (Some(1959), Some(1)));
+        assert_eq!(sheet.title.as_deref(), Some("Le nozze di Figaro (Giulini)"));
+        assert_eq!(sheet.tracks[1].title.as_deref(), Some("Atto I: 'Cinque... dieci...'"));
+        let starts: Vec<(&str, f64)> = sheet.tracks.iter().map(|t| (t.file.as_str(), t.start)).collect();
+        assert_eq!(starts, vec![("CD1.flac", 0.0), ("CD1.flac", 252.0 + 37.0 / 75.0), ("CD1.flac", 447.0 + 37.0 / 75.0)]);
+        assert_eq!(parse_cue_sheet(&export.sheets[1].contents).unwrap().tracks.len(), 1);
+    }
+}
@@ -0,0 +1,443 @@
+// Decompose an InterchangeLibretto back into a BaseLibretto + TimingOverlay.
+//
+// This is the inverse of merge. Numbers are rebuilt from the `number_id`
+// carried on each interchange segment (or derived from its `segment_id`),
+// so edits made by downstream tools in interchange form can be brought
+// back into the two-layer base + overlay representation.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::base_libretto::{BaseLibretto, MusicalNumber, NumberType, OperaMetadata, Segment, SegmentType};
+use crate::interchange::{InterchangeLibretto, InterchangeSegment, InterchangeTrack};
+use crate::timing_overlay::{RecordingMetadata, SegmentTime, TimingOverlay, TrackTiming};
+
+/// Result of decompiling an interchange document.
+#[derive(Debug)]
+pub struct DecompileResult {
+    /// The reconstructed base libretto.
+    pub base: BaseLibretto,
+    /// The reconstructed timing overlay, referencing `base` by segment ID.
+    pub overlay: TimingOverlay,
+    /// Warnings encountered during reconstruction.
+    pub warnings: Vec<String>,
+}
+
+/// Split an interchange libretto into a base libretto and a timing overlay.
+///
+/// Segments are grouped into musical numbers by `number_id` when present,
+/// otherwise by the prefix of `segment_id` (everything before the trailing
+/// `-NNN`). Segments carrying neither are assigned synthetic IDs under a
+/// number named after their track. A segment referenced by several tracks
+/// (a crossover) appears once in the base and once per track in the overlay.
+/// A track holding only the textless interlude merge makes of an
+/// instrumental track is marked `instrumental` again, with no segments.
+/// A segment's `end` is kept only where merge would not work it out again
+/// from the next start or the track's duration.
+///
+/// `base_path` is recorded as the overlay's `base_libretto` reference.
+pub fn decompile(doc: &InterchangeLibretto, base_path: &str) -> DecompileResult {
+    let mut warnings: Vec<String> = Vec::new();
+
+    let mut base = BaseLibretto::new(OperaMetadata {
+        title: doc.opera.title.clone(),
+        composer: doc.opera.composer.clone(),
+        librettist: doc.opera.librettist.clone(),
+        language: doc.opera.language.clone(),
+        translation_language: doc.opera.translation_language.clone(),
+        year: doc.opera.year,
+    });
+    base.rights = doc.text_rights.clone();
+    if !doc.annotations.is_empty() {
+        warnings.push(format!(
+            "{} annotations are not carried into the base libretto or timing overlay",
+            doc.annotations.len()
+        ));
+    }
+
+    let mut number_index: HashMap<String, usize> = HashMap::new();
+    let mut seen_segments: HashSet<String> = HashSet::new();
+    let mut track_timings = Vec::with_capacity(doc.tracks.len());
+    let mut synopses = 0;
+
+    for track in &doc.tracks {
+        // An instrumental track, merged as one interlude over its length
+        if let [seg] = track.segments.as_slice() {
+            if seg.segment_type == "interlude" && seg.segment_id.is_none() && seg.text.is_none() {
+                if let Some(number_id) = &seg.number_id {
+                    add_number(&mut base, &mut number_index, number_id, seg, track);
+                }
+                track_timings.push(TrackTiming {
+                    track_title: track.title.clone(),
+                    disc_number: track.disc_number,
+                    track_number: track.track_number,
+                    duration_seconds: track.duration_seconds,
+                    number_ids: seg.number_id.iter().cloned().collect(),
+                    instrumental: true,
+                    ..Default::default()
+                });
+                continue;
+            }
+        }
+
+        let mut number_ids: Vec<String> = Vec::new();
+        let mut segment_times = Vec::with_capacity(track.segments.len());
+        // Where merge would end each timed segment without an explicit end
+        let timed: Vec<f64> = track.segments.iter()
+            .filter(|s| s.segment_type != "synopsis" && !s.untimed)
+            .map(|s| s.start)
+            .collect();
+
+        for (i, seg) in track.segments.iter().enumerate() {
+            // Merged from a synopsis overlay, not part of the libretto
+            if seg.segment_type == "synopsis" {
+                synopses += 1;
+                continue;
+            }
+            let (number_id, segment_id) = match (&seg.number_id, &seg.segment_id) {
+                (Some(nid), Some(sid)) => (nid.clone(), sid.clone()),
+                (None, Some(sid)) => (number_id_from_segment_id(sid).to_string(), sid.clone()),
+                (nid, None) => {
+                    let nid = nid.clone().unwrap_or_else(|| track.track_id.clone());
+                    let seq = number_index.get(&nid)
+                        .map(|&i| base.numbers[i].segments.len())
+                        .unwrap_or(0) + 1;
+                    let sid = format!("{nid}-{seq:03}");
+                    (nid, sid)
+                }
+            };
+
+            let idx = add_number(&mut base, &mut number_index, &number_id, seg, track);
+
+            if seen_segments.insert(segment_id.clone()) {
+                let segment_type = parse_segment_type(&seg.segment_type).unwrap_or_else(|| {
+                    warnings.push(format!(
+                        "Track '{}': segment '{}' has unknown type '{}', treating as sung",
+                        track.track_id, segment_id, seg.segment_type
+                    ));
+                    SegmentType::Sung
+                });
+                base.numbers[idx].segments.push(to_base_segment(seg, &segment_id, segment_type));
+            } else if let Some(existing) = base.find_segment(&segment_id) {
+                if existing.text != seg.text || existing.translation != seg.translation {
+                    warnings.push(format!(
+                        "Track '{}': segment '{}' repeats with different content, keeping first occurrence",
+                        track.track_id, segment_id
+                    ));
+                }
+            }
+
+            if !number_ids.contains(&number_id) {
+                number_ids.push(number_id);
+            }
+            if !seg.untimed {
+                // or where a merged synopsis after it cut it short
+                let implied = timed.get(segment_times.len() + 1).copied().or(track.duration_seconds);
+                let synopsis = track.segments[i + 1..].iter()
+                    .find(|s| !s.untimed)
+                    .filter(|s| s.segment_type == "synopsis")
+                    .map(|s| s.start);
+                let end = seg.end.filter(|&end| Some(end) != implied && Some(end) != synopsis);
+                segment_times.push(SegmentTime { segment_id, start: seg.start, end, source: None });
+            }
+        }
+
+        track_timings.push(TrackTiming {
+            track_title: track.title.clone(),
+            disc_number: track.disc_number,
+            track_number: track.track_number,
+            duration_seconds: track.duration_seconds,
+            number_ids,
+            segment_times,
+            ..Default::default()
+        });
+    }
+    if synopses > 0 {
+        warnings.push(format!("{synopses} synopsis segments are not carried into the base libretto or timing overlay"));
+    }
+
+    let overlay = TimingOverlay {
+        version: "1.0".to_string(),
+        base_libretto: base_path.to_string(),
+        rights: doc.timing_rights.clone(),
+        recording: recording_from_tracks(doc),
+        track_timings,
+        ..Default::default()
+    };
+
+    DecompileResult { base, overlay, warnings }
+}
+
+/// Index in `base` of the number `number_id`, added (empty, with the act
+/// and scene of `seg` or its track) the first time it is seen.
+fn add_number(
+    base: &mut BaseLibretto,
+    number_index: &mut HashMap<String, usize>,
+    number_id: &str,
+    seg: &InterchangeSegment,
+    track: &InterchangeTrack,
+) -> usize {
+    *number_index.entry(number_id.to_string()).or_insert_with(|| {
+        base.numbers.push(MusicalNumber {
+            id: number_id.to_string(),
+            label: number_id.to_string(),
+            number_type: NumberType::Other,
+            act: seg.act.clone().or_else(|| track.act.clone()).unwrap_or_default(),
+            scene: seg.scene.clone().or_else(|| track.scene.clone()),
+            segments: Vec::new(),
+        });
+        base.numbers.len() - 1
+    })
+}
+
+/// Derive a number ID from a segment ID by stripping the trailing `-NNN`.
+///
+/// `"no-1-duettino-003"` → `"no-1-duettino"`. IDs without a numeric suffix
+/// are returned unchanged.
+fn number_id_from_segment_id(segment_id: &str) -> &str {
+    match segment_id.rsplit_once('-') {
+        Some((prefix, suffix))
+            if !prefix.is_empty() && !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            prefix
+        }
+        _ => segment_id,
+    }
+}
+
+/// Map an interchange `type` string back to a base `SegmentType`.
+///
+/// `"recitative"` is a merge-time refinement of sung text, so it maps to `Sung`.
+fn parse_segment_type(s: &str) -> Option<SegmentType> {
+    match s {
+        "sung" | "recitative" => Some(SegmentType::Sung),
+        "spoken" => Some(SegmentType::Spoken),
+        "direction" => Some(SegmentType::Direction),
+        "interlude" => Some(SegmentType::Interlude),
+        _ => None,
+    }
+}
+
+fn to_base_segment(seg: &InterchangeSegment, id: &str, segment_type: SegmentType) -> Segment {
+    Segment {
+        id: id.to_string(),
+        segment_type,
+        character: seg.character.clone(),
+        text: seg.text.clone(),
+        translation: seg.translation.clone(),
+        direction: seg.direction.clone(),
+        group: seg.group.clone(),
+    }
+}
+
+/// Recover recording metadata from track album/artist fields.
+///
+/// Merge writes `artist` as "Conductor / Orchestra", so split it back apart.
+fn recording_from_tracks(doc: &InterchangeLibretto) -> RecordingMetadata {
+    let album_title = doc.tracks.iter().find_map(|t| t.album.clone());
+    let (conductor, orchestra) = match doc.tracks.iter().find_map(|t| t.artist.as_deref()) {
+        Some(artist) => match artist.split_once(" / ") {
+            Some((c, o)) => (Some(c.to_string()), Some(o.to_string())),
+            None => (Some(artist.to_string()), None),
+        },
+        None => (None, None),
+    };
+
+    RecordingMetadata {
+        conductor,
+        orchestra,
+        year: None,
+        label: None,
+        album_title,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merge::merge;
+
+    fn sample_base() -> BaseLibretto {
+        let mut libretto = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "Mozart".to_string(),
+            librettist: Some("Da Ponte".to_string()),
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: Some(1786),
+        });
+        libretto.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "N° 1: Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: Some("1".to_string()),
+            segments: vec![
+                Segment {
+                    id: "no-1-duettino-001".to_string(),
+                    segment_type: SegmentType::Sung,
+                    character: Some("FIGARO".to_string()),
+                    text: Some("Cinque... dieci...".to_string()),
+                    translation: Some("Five... ten...".to_string()),
+                    direction: None,
+                    group: None,
+                },
+                Segment {
+                    id: "no-1-duettino-002".to_string(),
+                    segment_type: SegmentType::Direction,
+                    character: None,
+                    text: None,
+                    translation: None,
+                    direction: Some("Susanna enters.".to_string()),
+                    group: None,
+                },
+            ],
+        });
+        libretto
+    }
+
+    fn sample_overlay() -> TimingOverlay {
+        TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            recording: RecordingMetadata {
+                conductor: Some("Giulini".to_string()),
+                orchestra: Some("Philharmonia".to_string()),
+                year: None,
+                label: None,
+                album_title: Some("Le nozze di Figaro".to_string()),
+            },
+            track_timings: vec![TrackTiming {
+                track_title: "Cinque... dieci...".to_string(),
+                disc_number: Some(1),
+                track_number: Some(2),
+                duration_seconds: Some(195.0),
+                number_ids: vec!["no-1-duettino".to_string()],
+                segment_times: vec![
+                    SegmentTime { segment_id: "no-1-duettino-001".to_string(), start: 0.0, end: None, source: None },
+                    SegmentTime { segment_id: "no-1-duettino-002".to_string(), start: 12.5, end: None, source: None },
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_through_merge() {
+        let merged = merge(&sample_base(), &sample_overlay()).libretto;
+        let result = decompile(&merged, "base.libretto.json");
+        assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+
+        assert_eq!(result.base.opera.title, "Le nozze di Figaro");
+        assert_eq!(result.base.segment_ids(), vec!["no-1-duettino-001", "no-1-duettino-002"]);
+        let number = result.base.find_number("no-1-duettino").unwrap();
+        assert_eq!(number.act, "1");
+        assert_eq!(number.segments[1].segment_type, SegmentType::Direction);
+
+        let track = &result.overlay.track_timings[0];
+        assert_eq!(track.number_ids, vec!["no-1-duettino"]);
+        assert_eq!(track.segment_times[1].segment_id, "no-1-duettino-002");
+        assert_eq!(track.segment_times[1].start, 12.5);
+        // Ends merge computed from the next start and the duration are left to it
+        assert!(track.segment_times.iter().all(|t| t.end.is_none()));
+        assert_eq!(result.overlay.recording.conductor.as_deref(), Some("Giulini"));
+        assert_eq!(result.overlay.recording.orchestra.as_deref(), Some("Philharmonia"));
+    }
+
+    #[test]
+    fn test_explicit_end_kept() {
+        // A cue end imported from subtitles, before the next segment starts
+        let mut overlay = sample_overlay();
+        overlay.track_timings[0].segment_times[0].end = Some(10.0);
+        let merged = merge(&sample_base(), &overlay).libretto;
+        let result = decompile(&merged, "base.libretto.json");
+        let ends: Vec<_> = result.overlay.track_timings[0].segment_times.iter().map(|t| t.end).collect();
+        assert_eq!(ends, vec![Some(10.0), None]);
+        assert_eq!(merge(&result.base, &result.overlay).libretto.tracks[0].segments[0].end, Some(10.0));
+
+        // An end a merged synopsis made, by cutting the segment short, is not
+        let mut merged = merge(&sample_base(), &sample_overlay()).libretto;
+        let mut synopsis = merged.tracks[0].segments[1].clone();
+        synopsis.segment_type = "synopsis".to_string();
+        (synopsis.start, synopsis.end) = (7.5, Some(12.5));
+        merged.tracks[0].segments[0].end = Some(7.5);
+        merged.tracks[0].segments.insert(1, synopsis);
+        let result = decompile(&merged, "base.libretto.json");
+        assert!(result.overlay.track_timings[0].segment_times.iter().all(|t| t.end.is_none()));
+    }
+
+    #[test]
+    fn test_instrumental_track() {
+        let mut overlay = sample_overlay();
+        overlay.track_timings.insert(0, TrackTiming {
+            track_title: "Sinfonia".to_string(),
+            disc_number: Some(1),
+            track_number: Some(1),
+            duration_seconds: Some(250.0),
+            number_ids: vec!["sinfonia".to_string()],
+            instrumental: true,
+            ..Default::default()
+        });
+        let merged = merge(&sample_base(), &overlay).libretto;
+        let result = decompile(&merged, "base.libretto.json");
+        assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+
+        let track = &result.overlay.track_timings[0];
+        assert!(track.instrumental);
+        assert_eq!(track.number_ids, vec!["sinfonia"]);
+        assert!(track.segment_times.is_empty());
+        assert!(result.base.find_number("sinfonia").unwrap().segments.is_empty());
+        assert_eq!(result.base.segment_ids(), vec!["no-1-duettino-001", "no-1-duettino-002"]);
+    }
+
+    #[test]
+    fn test_synopses_left_out() {
+        let mut merged = merge(&sample_base(), &sample_overlay()).libretto;
+        let mut synopsis = merged.tracks[0].segments[0].clone();
+        synopsis.segment_type = "synopsis".to_string();
+        synopsis.segment_id = Some("no-1-duettino-synopsis".to_string());
+        merged.tracks[0].segments.insert(0, synopsis);
+        let result = decompile(&merged, "base.libretto.json");
+
+        assert_eq!(result.base.segment_ids(), vec!["no-1-duettino-001", "no-1-duettino-002"]);
+        assert_eq!(result.overlay.track_timings[0].segment_times.len(), 2);
+        assert_eq!(result.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_synthesizes_ids_when_absent() {
+        let mut merged = merge(&sample_base(), &sample_overlay()).libretto;
+        for seg in &mut merged.tracks[0].segments {
+            seg.segment_id = None;
+            seg.number_id = None;
+        }
+        let result = decompile(&merged, "base.libretto.json");
+
+        assert_eq!(result.base.numbers.len(), 1);
+        assert_eq!(result.base.numbers[0].id, "d1-t2");
+        assert_eq!(result.base.segment_ids(), vec!["d1-t2-001", "d1-t2-002"]);
+        assert_eq!(result.overlay.track_timings[0].segment_times[0].segment_id, "d1-t2-001");
+    }
+
+    #[test]
+    fn test_crossover_segment_kept_once() {
+        let mut overlay = sample_overlay();
+        let mut second = overlay.track_timings[0].clone();
+        second.track_number = Some(3);
+        second.segment_times.truncate(1);
+        overlay.track_timings.push(second);
+
+        let merged = merge(&sample_base(), &overlay).libretto;
+        let result = decompile(&merged, "base.libretto.json");
+
+        assert_eq!(result.base.segment_ids().len(), 2);
+        assert_eq!(result.overlay.segment_ids().len(), 3);
+    }
+
+    #[test]
+    fn test_number_id_from_segment_id() {
+        assert_eq!(number_id_from_segment_id("no-1-duettino-003"), "no-1-duettino");
+        assert_eq!(number_id_from_segment_id("overture"), "overture");
+        assert_eq!(number_id_from_segment_id("rec-1a-012"), "rec-1a");
+    }
+}
@@ -0,0 +1,208 @@
+// On-disk encodings for interchange documents.
+//
+// Full-opera interchange files are multi-megabyte JSON. Embedded display
+// clients can instead load a binary encoding (CBOR or MessagePack), each
+// behind its own cargo feature. The encoding is chosen by file extension.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::interchange::InterchangeLibretto;
+
+/// Serialization format of an interchange file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    /// CBOR (RFC 8949); requires the `cbor` feature.
+    Cbor,
+    /// MessagePack; requires the `msgpack` feature.
+    MessagePack,
+}
+
+#[derive(Debug, Error)]
+pub enum EncodingError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Load(#[from] crate::io::LoadError),
+
+    #[error("{0}: {1}")]
+    Binary(Encoding, String),
+
+    #[error("{0} support not compiled in (enable the `{1}` feature)")]
+    Unsupported(Encoding, &'static str),
+
+    #[error("{0} documents cannot be streamed; read them whole")]
+    NotStreamable(Encoding),
+}
+
+impl std::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Json => write!(f, "JSON"),
+            Encoding::Cbor => write!(f, "CBOR"),
+            Encoding::MessagePack => write!(f, "MessagePack"),
+        }
+    }
+}
+
+impl Encoding {
+    /// Detect the encoding from a path's extension.
+    ///
+    /// `.cbor` → CBOR, `.msgpack` / `.mpk` → MessagePack, anything else → JSON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("cbor") => Encoding::Cbor,
+            Some("msgpack") | Some("mpk") => Encoding::MessagePack,
+            _ => Encoding::Json,
+        }
+    }
+}
+
+/// Serialize an interchange document. JSON output is pretty-printed.
+pub fn to_bytes(doc: &InterchangeLibretto, encoding: Encoding) -> Result<Vec<u8>, EncodingError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::to_vec_pretty(doc)?),
+        Encoding::Cbor => {
+            #[cfg(feature = "cbor")]
+            {
+                let mut buf = Vec::new();
+                ciborium::into_writer(doc, &mut buf)
+                    .map_err(|e| EncodingError::Binary(encoding, e.to_string()))?;
+                Ok(buf)
+            }
+            #[cfg(not(feature = "cbor"))]
+            Err(EncodingError::Unsupported(encoding, "cbor"))
+        }
+        Encoding::MessagePack => {
+            #[cfg(feature = "msgpack")]
+            {
+                rmp_serde::to_vec_named(doc).map_err(|e| EncodingError::Binary(encoding, e.to_string()))
+            }
+            #[cfg(not(feature = "msgpack"))]
+            Err(EncodingError::Unsupported(encoding, "msgpack"))
+        }
+    }
+}
+
+/// Deserialize an interchange document.
+pub fn from_slice(bytes: &[u8], encoding: Encoding) -> Result<InterchangeLibretto, EncodingError> {
+    match encoding {
+        Encoding::Json => Ok(serde_json::from_slice(bytes)?),
+        Encoding::Cbor => {
+            #[cfg(feature = "cbor")]
+            {
+                ciborium::from_reader(bytes).map_err(|e| EncodingError::Binary(encoding, e.to_string()))
+            }
+            #[cfg(not(feature = "cbor"))]
+            Err(EncodingError::Unsupported(encoding, "cbor"))
+        }
+        Encoding::MessagePack => {
+            #[cfg(feature = "msgpack")]
+            {
+                rmp_serde::from_slice(bytes).map_err(|e| EncodingError::Binary(encoding, e.to_string()))
+            }
+            #[cfg(not(feature = "msgpack"))]
+            Err(EncodingError::Unsupported(encoding, "msgpack"))
+        }
+    }
+}
+
+/// Read an interchange file, detecting the encoding from its extension.
+///
+/// JSON errors are reported with the file name and JSON path (see `io`).
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read_interchange(path: &Path) -> Result<InterchangeLibretto, EncodingError> {
+    match Encoding::from_path(path) {
+        Encoding::Json => Ok(crate::io::load_json(path)?),
+        encoding => from_slice(&fs::read(path)?, encoding),
+    }
+}
+
+/// Write an interchange file, choosing the encoding from its extension.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write_interchange(path: &Path, doc: &InterchangeLibretto) -> Result<(), EncodingError> {
+    fs::write(path, to_bytes(doc, Encoding::from_path(path))?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interchange::{InterchangeOpera, InterchangeSegment, InterchangeTrack};
+
+    fn sample() -> InterchangeLibretto {
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Tosca".to_string(),
+                composer: "Puccini".to_string(),
+                language: "it".to_string(),
+                translation_language: Some("en".to_string()),
+                year: Some(1900),
+                ..Default::default()
+            },
+            tracks: vec![InterchangeTrack {
+                track_id: "d1-t1".to_string(),
+                title: "Act I".to_string(),
+                disc_number: Some(1),
+                track_number: Some(1),
+                duration_seconds: Some(60.0),
+                act: Some("1".to_string()),
+                segments: vec![InterchangeSegment {
+                    start: 1.5,
+                    segment_type: "direction".to_string(),
+                    direction: Some("Una chiesa.".to_string()),
+                    segment_id: Some("act-1-001".to_string()),
+                    number_id: Some("act-1".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Encoding::from_path(Path::new("a.timed.libretto.json")), Encoding::Json);
+        assert_eq!(Encoding::from_path(Path::new("a.timed.libretto.CBOR")), Encoding::Cbor);
+        assert_eq!(Encoding::from_path(Path::new("a.mpk")), Encoding::MessagePack);
+        assert_eq!(Encoding::from_path(Path::new("noext")), Encoding::Json);
+    }
+
+    fn roundtrip(encoding: Encoding) {
+        let bytes = to_bytes(&sample(), encoding).unwrap();
+        let doc = from_slice(&bytes, encoding).unwrap();
+        assert_eq!(doc.tracks[0].segments[0].segment_type, "direction");
+        assert_eq!(doc.tracks[0].segments[0].direction.as_deref(), Some("Una chiesa."));
+        assert_eq!(doc.opera.year, Some(1900));
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        roundtrip(Encoding::Json);
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_roundtrip() {
+        roundtrip(Encoding::Cbor);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_roundtrip() {
+        roundtrip(Encoding::MessagePack);
+        let json = to_bytes(&sample(), Encoding::Json).unwrap();
+        let packed = to_bytes(&sample(), Encoding::MessagePack).unwrap();
+        assert!(packed.len() < json.len());
+    }
+}
@@ -1,14 +1,32 @@
 // Estimate segment timings from track durations and word counts.
 //
-// Given a BaseLibretto and a TimingOverlay with track durations but empty
-// segment_times, this module fills in estimated start times by distributing
-// each track's duration proportionally across its segments' word counts.
+// Given a BaseLibretto and a TimingOverlay with track durations, this
+// module fills in estimated start times by distributing each track's
+// duration proportionally across its segments' word (or syllable) counts,
+// paced by the kind of number and segment (see `Pacing`).
+//
+// Times already in a track that are not themselves estimates (tapped,
+// imported, aligned) are kept as anchors: the duration is distributed
+// only between consecutive anchors, so a handful of hand-tapped entries
+// per track pins the estimates around them. A track title can give anchors
+// too: a time after a quoted anchor (`"Se vuol ballare" (1:42)`) pins the
+// segment the quote matches.
+//
+// Segments the recording does not perform take no time: those of numbers
+// in `omitted_numbers`, and those a track lists in `skip_segment_ids`
+// (a cut verse or repeat) are left out rather than stretching the rest.
+// Likewise a track's `Padding` (an orchestral introduction, applause) is
+// kept free of text at its ends.
+
+use std::collections::{HashMap, HashSet};
 
-use std::collections::HashMap;
+use serde::{de, Deserialize, Deserializer};
 
-use crate::base_libretto::{BaseLibretto, MusicalNumber, SegmentType};
-use crate::resolve;
-use crate::timing_overlay::{SegmentTime, TimingOverlay, TrackTiming};
+use crate::base_libretto::{BaseLibretto, MusicalNumber, NumberType, Segment, SegmentType};
+use crate::parallel;
+use crate::resolve::{self, SegmentIndex};
+use crate::syllable;
+use crate::timing_overlay::{round_to_ms, Padding, SegmentTime, TimingOverlay, TimingSource, TrackTiming};
 
 /// Result of an estimation pass.
 #[derive(Debug)]
@@ -17,6 +35,8 @@ pub struct EstimateResult {
     pub overlay: TimingOverlay,
     /// Per-track statistics.
     pub stats: Vec<TrackEstimateStats>,
+    /// Weight given to each segment of the estimated tracks, by segment ID.
+    pub weights: HashMap<String, f64>,
     /// Warnings encountered during estimation.
     pub warnings: Vec<String>,
 }
@@ -29,60 +49,224 @@ pub struct TrackEstimateStats {
     pub track_number: Option<u32>,
     pub duration: f64,
     pub segments_estimated: usize,
+    /// Existing times kept as anchors.
+    pub anchors: usize,
+    /// Segments left out as omitted or cut.
+    pub segments_skipped: usize,
+    /// Total weight of the track's segments, in words or syllables.
     pub total_word_weight: f64,
 }
 
 /// Minimum weight for segments with no text (directions, interludes).
 const MIN_SEGMENT_WEIGHT: f64 = 0.5;
 
-/// Recitative segments are spoken-sung at roughly 2× the pace of sung text,
-/// so their word weight is discounted by this factor.
-const RECITATIVE_DISCOUNT: f64 = 0.5;
-
-/// Calculate word weight for a segment's text.
-fn word_weight(text: &Option<String>, seg_type: &SegmentType) -> f64 {
-    match seg_type {
-        SegmentType::Direction | SegmentType::Interlude => MIN_SEGMENT_WEIGHT,
-        _ => {
-            let count = text.as_deref()
-                .map(|t| t.split_whitespace().count())
-                .unwrap_or(0);
-            if count == 0 { MIN_SEGMENT_WEIGHT } else { count as f64 }
+/// How long a unit of text (a word or syllable) takes to deliver in each
+/// kind of number and segment, relative to ordinary sung text (1.0).
+///
+/// Within a track the estimate is proportional, so these only matter
+/// between kinds: a track holding a recitative and the aria after it gives
+/// the recitative's quick parlando less of the time than its word count
+/// alone would. Kinds a table leaves out take the defaults below; a table
+/// can be loaded from a file, e.g.
+///
+/// ```toml
+/// [number_types]
+/// aria = 1.6
+/// finale = 0.9
+///
+/// [segment_types]
+/// spoken = 0.5
+/// ```
+///
+/// Every value must be positive and finite.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Pacing {
+    #[serde(deserialize_with = "positive_factors")]
+    pub number_types: HashMap<NumberType, f64>,
+    #[serde(deserialize_with = "positive_factors")]
+    pub segment_types: HashMap<SegmentType, f64>,
+}
+
+/// A table of pacing factors, rejecting any that is zero, negative or not
+/// a number: a kind of text taking no time, or less than none, would
+/// collapse or reverse its segments' estimates.
+fn positive_factors<'de, D, K>(deserializer: D) -> Result<HashMap<K, f64>, D::Error>
+where
+    D: Deserializer<'de>,
+    K: Deserialize<'de> + Eq + std::hash::Hash + std::fmt::Debug,
+{
+    let factors = HashMap::<K, f64>::deserialize(deserializer)?;
+    if let Some((kind, factor)) = factors.iter().find(|(_, f)| !(f.is_finite() && **f > 0.0)) {
+        return Err(de::Error::custom(format!("pacing for {kind:?} must be a positive number, not {factor}")));
+    }
+    Ok(factors)
+}
+
+impl Pacing {
+    /// Relative time per unit of text in a number of type `number_type`.
+    pub fn number_type(&self, number_type: &NumberType) -> f64 {
+        self.number_types.get(number_type).copied().unwrap_or(match number_type {
+            // Sustained lines and repeated text
+            NumberType::Aria | NumberType::Cavatina => 1.4,
+            NumberType::Chorus => 1.3,
+            NumberType::Canzone | NumberType::Duet => 1.2,
+            // Voices overlap, getting through more text at once
+            NumberType::Terzetto | NumberType::Quartet | NumberType::Quintet | NumberType::Sextet => 0.9,
+            // Spoken-sung at roughly twice the pace of sung text
+            NumberType::Recitative => 0.5,
+            // Finales mix fast exchanges and set pieces
+            NumberType::Duettino | NumberType::Finale | NumberType::Overture | NumberType::Other => 1.0,
+        })
+    }
+
+    /// Relative time per unit of text in a segment of type `segment_type`.
+    pub fn segment_type(&self, segment_type: &SegmentType) -> f64 {
+        self.segment_types.get(segment_type).copied().unwrap_or(match segment_type {
+            SegmentType::Spoken => 0.6,
+            SegmentType::Sung | SegmentType::Direction | SegmentType::Interlude => 1.0,
+        })
+    }
+}
+
+/// Minimum weight for segments with no text when weighting by syllables:
+/// about as long as `MIN_SEGMENT_WEIGHT` words.
+const MIN_SEGMENT_SYLLABLES: f64 = 1.0;
+
+/// What a segment's share of its track's time is proportional to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Weighting {
+    /// Words in the text.
+    #[default]
+    Words,
+    /// Syllables in the text (see `syllable::count_syllables`), which
+    /// follows melismatic and long-worded text, notably Italian, more closely.
+    Syllables,
+}
+
+/// Options for an estimation pass.
+#[derive(Debug, Clone, Default)]
+pub struct EstimateOptions {
+    pub weighting: Weighting,
+    pub pacing: Pacing,
+}
+
+/// Weighs segments for one base libretto.
+struct Weigher<'a> {
+    weighting: Weighting,
+    pacing: &'a Pacing,
+    /// ISO 639-1 code of the text, for counting syllables.
+    language: &'a str,
+}
+
+impl Weigher<'_> {
+    /// Weight of a segment in a number of type `number_type`: its words or
+    /// syllables, paced by number and segment type.
+    fn weight(&self, segment: &Segment, number_type: &NumberType) -> f64 {
+        self.text_weight(&segment.text, &segment.segment_type)
+            * self.pacing.segment_type(&segment.segment_type)
+            * self.pacing.number_type(number_type)
+    }
+
+    /// Weight of a segment's text: its words or syllables.
+    fn text_weight(&self, text: &Option<String>, seg_type: &SegmentType) -> f64 {
+        let minimum = match self.weighting {
+            Weighting::Words => MIN_SEGMENT_WEIGHT,
+            Weighting::Syllables => MIN_SEGMENT_SYLLABLES,
+        };
+        match seg_type {
+            SegmentType::Direction | SegmentType::Interlude => minimum,
+            _ => {
+                let count = text.as_deref()
+                    .map(|t| match self.weighting {
+                        Weighting::Words => t.split_whitespace().count(),
+                        Weighting::Syllables => syllable::count_syllables(t, self.language),
+                    })
+                    .unwrap_or(0);
+                if count == 0 { minimum } else { count as f64 }
+            }
         }
     }
 }
 
-/// Estimate segment timings for all tracks in the overlay.
+/// Estimate segment timings for all tracks in the overlay, weighting by words.
 ///
 /// If tracks have `start_segment_id` set (from anchor resolution), uses
 /// those boundaries to precisely partition segments across tracks.
 /// Otherwise, falls back to number-based assignment using `number_ids`.
 pub fn estimate_timings(base: &BaseLibretto, overlay: &TimingOverlay) -> EstimateResult {
+    estimate_timings_with(base, overlay, &EstimateOptions::default())
+}
+
+/// Estimate segment timings as `estimate_timings` does, with `options`.
+pub fn estimate_timings_with(base: &BaseLibretto, overlay: &TimingOverlay, options: &EstimateOptions) -> EstimateResult {
+    estimate_timings_indexed(&SegmentIndex::new(base), overlay, options)
+}
+
+/// Estimate segment timings as `estimate_timings_with` does, matching
+/// title anchors in `index`, for callers that also resolve against the
+/// same base libretto. Tracks are estimated in parallel.
+pub fn estimate_timings_indexed(index: &SegmentIndex<'_>, overlay: &TimingOverlay, options: &EstimateOptions) -> EstimateResult {
+    let weigher = Weigher {
+        weighting: options.weighting,
+        pacing: &options.pacing,
+        language: &index.base().opera.language,
+    };
     let has_boundaries = overlay.track_timings.iter()
         .any(|t| t.start_segment_id.is_some());
 
     if has_boundaries {
-        estimate_with_boundaries(base, overlay)
+        estimate_with_boundaries(index, overlay, &weigher)
     } else {
-        estimate_by_numbers(base, overlay)
+        estimate_by_numbers(index, overlay, &weigher)
+    }
+}
+
+/// What estimating one track, or the tracks sharing a number, came to.
+#[derive(Default)]
+struct Estimated {
+    /// The times and stats of each track estimated, by track index.
+    tracks: Vec<(usize, Vec<SegmentTime>, TrackEstimateStats)>,
+    weights: Vec<(String, f64)>,
+    warnings: Vec<String>,
+}
+
+impl Estimated {
+    fn warning(warning: String) -> Self {
+        Estimated { warnings: vec![warning], ..Default::default() }
     }
 }
 
+/// Put `estimates` into a copy of `overlay`, in order.
+fn assemble(overlay: &TimingOverlay, estimates: Vec<Estimated>) -> EstimateResult {
+    let mut result_overlay = overlay.clone();
+    let mut stats = Vec::new();
+    let mut weights = HashMap::new();
+    let mut warnings = Vec::new();
+    for estimated in estimates {
+        for (i, segment_times, stat) in estimated.tracks {
+            result_overlay.track_timings[i].segment_times = segment_times;
+            stats.push(stat);
+        }
+        weights.extend(estimated.weights);
+        warnings.extend(estimated.warnings);
+    }
+    EstimateResult { overlay: result_overlay, stats, weights, warnings }
+}
+
 /// Boundary-based estimation: uses `start_segment_id` to determine which
 /// segments belong to each track, regardless of number boundaries.
 ///
 /// Builds a global ordered segment list from all numbers covered by the
 /// overlay, then partitions it using the start_segment_id markers.
-fn estimate_with_boundaries(base: &BaseLibretto, overlay: &TimingOverlay) -> EstimateResult {
-    let mut result_overlay = overlay.clone();
-    let mut stats = Vec::new();
-    let mut warnings: Vec<String> = Vec::new();
+fn estimate_with_boundaries(index: &SegmentIndex<'_>, overlay: &TimingOverlay, weigher: &Weigher<'_>) -> EstimateResult {
+    let base = index.base();
 
     // Build global ordered segment list from all covered numbers (in libretto order)
     let covered: Vec<&str> = overlay.covered_number_ids();
     let all_segments: Vec<WeightedSegment> = base.numbers.iter()
         .filter(|n| covered.contains(&n.id.as_str()))
-        .flat_map(|n| collect_number_segments(n))
+        .flat_map(|n| collect_number_segments(n, weigher))
         .collect();
 
     // Build segment_id → position index
@@ -91,18 +275,13 @@ fn estimate_with_boundaries(base: &BaseLibretto, overlay: &TimingOverlay) -> Est
         .map(|(i, s)| (s.id.as_str(), i))
         .collect();
 
-    // Build resolve infrastructure once for recitative classification
-    let resolve_candidates = resolve::build_segment_index(base);
     let all_nids: Vec<String> = covered.iter().map(|s| s.to_string()).collect();
+    let omitted = omitted_segments(base, overlay);
 
-    for (i, track) in overlay.track_timings.iter().enumerate() {
-        // Skip tracks that already have segment_times
-        if !track.segment_times.is_empty() {
-            continue;
-        }
+    let estimates = parallel::map_indexed(&overlay.track_timings, |i, track| {
         let duration = match track.duration_seconds {
-            Some(d) => d,
-            None => continue,
+            Some(d) if !track.instrumental => d,
+            _ => return Estimated::default(),
         };
 
         // Find start position from start_segment_id or first segment of first number
@@ -110,13 +289,12 @@ fn estimate_with_boundaries(base: &BaseLibretto, overlay: &TimingOverlay) -> Est
             Some(sid) => match seg_index.get(sid.as_str()) {
                 Some(&pos) => pos,
                 None => {
-                    warnings.push(format!(
+                    return Estimated::warning(format!(
                         "D{}T{} '{}': start_segment_id '{}' not found in segment index",
                         track.disc_number.unwrap_or(0),
                         track.track_number.unwrap_or(0),
                         track.track_title, sid,
                     ));
-                    continue;
                 }
             },
             None => {
@@ -128,7 +306,7 @@ fn estimate_with_boundaries(base: &BaseLibretto, overlay: &TimingOverlay) -> Est
                     .copied()
                 {
                     Some(pos) => pos,
-                    None => continue,
+                    None => return Estimated::default(),
                 }
             }
         };
@@ -143,19 +321,18 @@ fn estimate_with_boundaries(base: &BaseLibretto, overlay: &TimingOverlay) -> Est
             .unwrap_or(all_segments.len());
 
         if start_pos >= end_pos {
-            warnings.push(format!(
+            return Estimated::warning(format!(
                 "D{}T{} '{}': empty segment range (start={}, end={})",
                 track.disc_number.unwrap_or(0),
                 track.track_number.unwrap_or(0),
                 track.track_title, start_pos, end_pos,
             ));
-            continue;
         }
 
         // Classify title sections and resolve sub-boundaries for recitative discount
         let section_marks = resolve_section_marks(
-            &track.track_title, start_pos, end_pos,
-            &seg_index, &resolve_candidates, &all_nids,
+            track, start_pos, end_pos,
+            &seg_index, index.text(), &all_nids,
         );
 
         // Build adjusted weights: recitative segments get discounted
@@ -169,82 +346,117 @@ fn estimate_with_boundaries(base: &BaseLibretto, overlay: &TimingOverlay) -> Est
                     .find(|(pos, _)| *pos <= global_pos)
                     .map(|(_, recit)| *recit)
                     .unwrap_or(false);
-                WeightedSegment {
-                    id: seg.id.clone(),
-                    weight: if is_recit { seg.weight * RECITATIVE_DISCOUNT } else { seg.weight },
-                }
+                // A recitative section of a number typed otherwise is paced as recitative
+                let weight = if is_recit {
+                    seg.weight / weigher.pacing.number_type(&seg.number_type)
+                        * weigher.pacing.number_type(&NumberType::Recitative)
+                } else {
+                    seg.weight
+                };
+                WeightedSegment { id: seg.id.clone(), weight, number_type: seg.number_type.clone() }
             })
             .collect();
+        let (track_segments, skipped) = without_cuts(track_segments, &omitted, &[track]);
 
-        let segment_times = distribute_segments(&track_segments, duration);
+        let mut warnings = Vec::new();
+        let mut anchors = track_anchors(track);
+        anchors.extend(title_cue_anchors(track, &track_segments, index.text(), &anchors));
+        let segment_times = distribute_segments(
+            &track_segments, duration, track_padding(overlay, track), &anchors, &track.track_title, &mut warnings,
+        );
 
         let stat = TrackEstimateStats {
             track_title: track.track_title.clone(),
             disc_number: track.disc_number,
             track_number: track.track_number,
             duration,
-            segments_estimated: segment_times.len(),
+            segments_estimated: segment_times.iter().filter(|t| t.source == Some(TimingSource::Estimated)).count(),
+            anchors: anchors.len(),
+            segments_skipped: skipped,
             total_word_weight: track_segments.iter().map(|s| s.weight).sum(),
         };
-        stats.push(stat);
-        result_overlay.track_timings[i].segment_times = segment_times;
-    }
+        Estimated {
+            tracks: vec![(i, segment_times, stat)],
+            weights: track_segments.into_iter().map(|s| (s.id, s.weight)).collect(),
+            warnings,
+        }
+    });
 
-    EstimateResult { overlay: result_overlay, stats, warnings }
+    assemble(overlay, estimates)
 }
 
-/// Resolve title section anchors to global segment positions, returning
-/// (position, is_recitative) pairs sorted by position.
+/// Resolve a track's sections to global segment positions, returning
+/// (position, is_recitative) pairs sorted by position. The track's
+/// `section_marks` are used when it has any; otherwise the title's anchors
+/// are matched here.
 fn resolve_section_marks(
-    title: &str,
+    track: &TrackTiming,
     start_pos: usize,
     end_pos: usize,
     seg_index: &HashMap<&str, usize>,
     candidates: &[resolve::SegCandidate<'_>],
     all_nids: &[String],
 ) -> Vec<(usize, bool)> {
-    let title_anchors = resolve::classify_title_anchors(title);
-    let mut marks: Vec<(usize, bool)> = Vec::new();
-
-    for ta in &title_anchors {
-        if let Some((seg_id, _)) = resolve::match_anchor(&ta.anchor, all_nids, candidates) {
-            if let Some(&pos) = seg_index.get(seg_id.as_str()) {
-                if pos >= start_pos && pos < end_pos {
-                    marks.push((pos, ta.is_recitative));
-                }
-            }
-        }
-    }
+    let sections: Vec<(String, bool)> = if track.section_marks.is_empty() {
+        resolve::classify_title_anchors(&track.track_title).iter()
+            .filter_map(|ta| {
+                let (seg_id, _) = resolve::match_anchor(&ta.anchor, all_nids, candidates)?;
+                Some((seg_id, ta.is_recitative))
+            })
+            .collect()
+    } else {
+        track.section_marks.iter().map(|m| (m.segment_id.clone(), m.is_recitative)).collect()
+    };
+
+    let mut marks: Vec<(usize, bool)> = sections.iter()
+        .filter_map(|(seg_id, recit)| seg_index.get(seg_id.as_str()).map(|&pos| (pos, *recit)))
+        .filter(|(pos, _)| *pos >= start_pos && *pos < end_pos)
+        .collect();
 
     marks.sort_by_key(|(pos, _)| *pos);
     marks
 }
 
+/// Tracks estimated together by number-based estimation.
+enum NumberJob<'a> {
+    /// A track on its own, by index, with its duration.
+    Track(usize, f64),
+    /// A number spread over several tracks, by index, with their durations.
+    Pooled(&'a MusicalNumber, Vec<(usize, f64)>),
+}
+
 /// Number-based estimation (legacy): uses `number_ids` to assign segments
 /// to tracks. Multi-track numbers are handled by pooling duration.
-fn estimate_by_numbers(base: &BaseLibretto, overlay: &TimingOverlay) -> EstimateResult {
-    let mut result_overlay = overlay.clone();
-    let mut stats = Vec::new();
-    let mut warnings: Vec<String> = Vec::new();
-
-    // Build a map of number_id → list of track indices that reference it.
+fn estimate_by_numbers(index: &SegmentIndex<'_>, overlay: &TimingOverlay, weigher: &Weigher<'_>) -> EstimateResult {
+    let base = index.base();
+    let omitted = omitted_segments(base, overlay);
+    let mut planning = Estimated::default();
+
+    // Build a map of number_id → list of track indices that reference it,
+    // keeping the numbers in the order the tracks first reference them.
+    let mut number_ids: Vec<&str> = Vec::new();
     let mut number_to_tracks: HashMap<&str, Vec<usize>> = HashMap::new();
-    for (i, track) in overlay.track_timings.iter().enumerate() {
+    for (i, track) in overlay.track_timings.iter().enumerate().filter(|(_, t)| !t.instrumental) {
         for nid in &track.number_ids {
-            number_to_tracks.entry(nid.as_str()).or_default().push(i);
+            let tracks = number_to_tracks.entry(nid.as_str()).or_default();
+            if tracks.is_empty() {
+                number_ids.push(nid.as_str());
+            }
+            tracks.push(i);
         }
     }
 
-    // Track which tracks we've already estimated (avoid double-processing
+    // Track which tracks we've already planned (avoid double-processing
     // multi-track numbers from different number_ids on the same track).
-    let mut estimated_tracks: std::collections::HashSet<usize> = std::collections::HashSet::new();
+    let mut estimated_tracks: HashSet<usize> = HashSet::new();
+    let mut jobs = Vec::new();
 
-    // Process each unique number_id
-    for (number_id, track_indices) in &number_to_tracks {
+    // Plan each unique number_id
+    for number_id in number_ids {
         let number = match base.find_number(number_id) {
             Some(n) => n,
             None => {
-                warnings.push(format!(
+                planning.warnings.push(format!(
                     "Number '{}' referenced by overlay but not found in base libretto",
                     number_id
                 ));
@@ -258,15 +470,8 @@ fn estimate_by_numbers(base: &BaseLibretto, overlay: &TimingOverlay) -> Estimate
         }
 
         // Collect track durations; skip if any track is missing duration
-        let track_durations: Vec<(usize, f64)> = track_indices.iter()
-            .filter_map(|&i| {
-                let track = &overlay.track_timings[i];
-                // Skip tracks that already have segment_times filled in
-                if !track.segment_times.is_empty() {
-                    return None;
-                }
-                track.duration_seconds.map(|d| (i, d))
-            })
+        let track_durations: Vec<(usize, f64)> = number_to_tracks[number_id].iter()
+            .filter_map(|&i| overlay.track_timings[i].duration_seconds.map(|d| (i, d)))
             .collect();
 
         if track_durations.is_empty() {
@@ -275,45 +480,81 @@ fn estimate_by_numbers(base: &BaseLibretto, overlay: &TimingOverlay) -> Estimate
 
         if track_durations.len() == 1 {
             let (track_idx, duration) = track_durations[0];
-            if estimated_tracks.contains(&track_idx) {
+            if estimated_tracks.insert(track_idx) {
+                jobs.push(NumberJob::Track(track_idx, duration));
+            }
+        } else {
+            // Multi-track number: pool duration and distribute
+            if track_durations.iter().any(|(i, _)| estimated_tracks.contains(i)) {
+                continue;
+            }
+            // Which segments fall in which track is only known from the
+            // pooled estimate, so anchors cannot be placed
+            if track_durations.iter().any(|&(i, _)| !track_anchors(&overlay.track_timings[i]).is_empty()) {
+                planning.warnings.push(format!(
+                    "Number '{number_id}' spans several tracks with existing times; \
+                     run `timing resolve` first to estimate around them"
+                ));
+                continue;
+            }
+            let tracks: Vec<&TrackTiming> = track_durations.iter().map(|&(i, _)| &overlay.track_timings[i]).collect();
+            if number.segments.iter().all(|s| is_cut(&s.id, &omitted, &tracks)) {
                 continue;
             }
 
-            let track = &overlay.track_timings[track_idx];
-            let all_segments = collect_track_segments(base, track, &mut warnings);
-            let segment_times = distribute_segments(&all_segments, duration);
+            estimated_tracks.extend(track_durations.iter().map(|(i, _)| *i));
+            jobs.push(NumberJob::Pooled(number, track_durations));
+        }
+    }
+
+    let estimates = parallel::map_indexed(&jobs, |_, job| match job {
+        NumberJob::Track(track_idx, duration) => {
+            let (track, duration) = (&overlay.track_timings[*track_idx], *duration);
+            let mut warnings = Vec::new();
+            let all_segments = collect_track_segments(base, track, weigher, &mut warnings);
+            let (all_segments, skipped) = without_cuts(all_segments, &omitted, &[track]);
+            let mut anchors = track_anchors(track);
+            anchors.extend(title_cue_anchors(track, &all_segments, index.text(), &anchors));
+            let segment_times = distribute_segments(
+                &all_segments, duration, track_padding(overlay, track), &anchors, &track.track_title, &mut warnings,
+            );
 
             let stat = TrackEstimateStats {
                 track_title: track.track_title.clone(),
                 disc_number: track.disc_number,
                 track_number: track.track_number,
                 duration,
-                segments_estimated: segment_times.len(),
+                segments_estimated: segment_times.iter().filter(|t| t.source == Some(TimingSource::Estimated)).count(),
+                anchors: anchors.len(),
+                segments_skipped: skipped,
                 total_word_weight: all_segments.iter().map(|s| s.weight).sum(),
             };
-            stats.push(stat);
-
-            result_overlay.track_timings[track_idx].segment_times = segment_times;
-            estimated_tracks.insert(track_idx);
-        } else {
-            // Multi-track number: pool duration and distribute
-            if track_durations.iter().any(|(i, _)| estimated_tracks.contains(i)) {
-                continue;
+            Estimated {
+                tracks: vec![(*track_idx, segment_times, stat)],
+                weights: all_segments.into_iter().map(|s| (s.id, s.weight)).collect(),
+                warnings,
             }
-
+        }
+        NumberJob::Pooled(number, track_durations) => {
+            let mut warnings = Vec::new();
             let total_duration: f64 = track_durations.iter().map(|(_, d)| *d).sum();
-            let segments = collect_number_segments(number);
-
-            if segments.is_empty() {
-                continue;
-            }
-
-            let all_times = distribute_segments(&segments, total_duration);
+            let tracks: Vec<&TrackTiming> = track_durations.iter().map(|&(i, _)| &overlay.track_timings[i]).collect();
+            let (segments, mut skipped) = without_cuts(collect_number_segments(number, weigher), &omitted, &tracks);
+
+            // The number starts after its first track's introduction and
+            // ends before its last track's applause
+            let padding = Padding {
+                head_seconds: track_padding(overlay, tracks[0]).head_seconds,
+                tail_seconds: track_padding(overlay, tracks[tracks.len() - 1]).tail_seconds,
+            };
+            let all_times = distribute_segments(&segments, total_duration, padding, &[], "", &mut warnings);
+            let total_word_weight = segments.iter().map(|s| s.weight).sum::<f64>() / track_durations.len() as f64;
 
             let mut cumulative = 0.0;
             let mut time_iter = all_times.into_iter().peekable();
+            let mut estimated = Vec::new();
 
-            for (track_idx, track_duration) in &track_durations {
+            for (track_idx, track_duration) in track_durations {
                 let track_end = cumulative + track_duration;
                 let mut track_segments = Vec::new();
 
@@ -334,32 +575,40 @@ fn estimate_by_numbers(base: &BaseLibretto, overlay: &TimingOverlay) -> Estimate
                     track_number: track.track_number,
                     duration: *track_duration,
                     segments_estimated: track_segments.len(),
-                    total_word_weight: segments.iter().map(|s| s.weight).sum::<f64>() / track_durations.len() as f64,
+                    anchors: 0,
+                    // Counted once, against the number's first track
+                    segments_skipped: std::mem::take(&mut skipped),
+                    total_word_weight,
                 };
-                stats.push(stat);
-
-                result_overlay.track_timings[*track_idx].segment_times = track_segments;
-                estimated_tracks.insert(*track_idx);
+                estimated.push((*track_idx, track_segments, stat));
                 cumulative = track_end;
             }
+            Estimated {
+                tracks: estimated,
+                weights: segments.into_iter().map(|s| (s.id, s.weight)).collect(),
+                warnings,
+            }
         }
-    }
+    });
 
-    EstimateResult { overlay: result_overlay, stats, warnings }
+    assemble(overlay, std::iter::once(planning).chain(estimates).collect())
 }
 
 /// A weighted segment for distribution.
 struct WeightedSegment {
     id: String,
     weight: f64,
+    /// Type of the number the segment belongs to.
+    number_type: NumberType,
 }
 
 /// Collect all segments for a single musical number, with word weights.
-fn collect_number_segments(number: &MusicalNumber) -> Vec<WeightedSegment> {
+fn collect_number_segments(number: &MusicalNumber, weigher: &Weigher<'_>) -> Vec<WeightedSegment> {
     number.segments.iter()
         .map(|s| WeightedSegment {
             id: s.id.clone(),
-            weight: word_weight(&s.text, &s.segment_type),
+            weight: weigher.weight(s, &number.number_type),
+            number_type: number.number_type.clone(),
         })
         .collect()
 }
@@ -368,13 +617,14 @@ fn collect_number_segments(number: &MusicalNumber) -> Vec<WeightedSegment> {
 fn collect_track_segments(
     base: &BaseLibretto,
     track: &TrackTiming,
+    weigher: &Weigher<'_>,
     warnings: &mut Vec<String>,
 ) -> Vec<WeightedSegment> {
     let mut segments = Vec::new();
     for nid in &track.number_ids {
         match base.find_number(nid) {
             Some(number) => {
-                segments.extend(collect_number_segments(number));
+                segments.extend(collect_number_segments(number, weigher));
             }
             None => {
                 warnings.push(format!(
@@ -387,35 +637,284 @@ fn collect_track_segments(
     segments
 }
 
+/// IDs of all segments of the overlay's omitted numbers.
+fn omitted_segments<'a>(base: &'a BaseLibretto, overlay: &TimingOverlay) -> HashSet<&'a str> {
+    let omitted = overlay.omitted_number_ids();
+    base.numbers.iter()
+        .filter(|n| omitted.contains(&n.id.as_str()))
+        .flat_map(|n| n.segments.iter().map(|s| s.id.as_str()))
+        .collect()
+}
+
+/// Whether `tracks` do not perform segment `id`: it is `omitted`, or one
+/// of them skips it.
+fn is_cut(id: &str, omitted: &HashSet<&str>, tracks: &[&TrackTiming]) -> bool {
+    omitted.contains(id) || tracks.iter().any(|t| t.skip_segment_ids.iter().any(|s| s == id))
+}
+
+/// `segments` without those `tracks` do not perform, and how many were
+/// left out.
+fn without_cuts(
+    segments: Vec<WeightedSegment>,
+    omitted: &HashSet<&str>,
+    tracks: &[&TrackTiming],
+) -> (Vec<WeightedSegment>, usize) {
+    let total = segments.len();
+    let kept: Vec<WeightedSegment> = segments.into_iter()
+        .filter(|s| !is_cut(&s.id, omitted, tracks))
+        .collect();
+    let skipped = total - kept.len();
+    (kept, skipped)
+}
+
+/// Padding of `track`: its own, or else the overlay's.
+fn track_padding(overlay: &TimingOverlay, track: &TrackTiming) -> Padding {
+    track.padding.or(overlay.padding).unwrap_or_default()
+}
+
+/// Existing times in a track to keep as anchors: all but earlier estimates,
+/// and none of a scaffold's placeholders.
+fn track_anchors(track: &TrackTiming) -> Vec<SegmentTime> {
+    if track.is_scaffold() {
+        return Vec::new();
+    }
+    track.segment_times.iter()
+        .filter(|t| t.trust() > TimingSource::Estimated)
+        .cloned()
+        .collect()
+}
+
+/// Anchors from timing cues in a track's title (`"Se vuol ballare" (1:42)`):
+/// each anchor with a time, matched to one of `segments`, unless `existing`
+/// already pins that segment.
+fn title_cue_anchors(
+    track: &TrackTiming,
+    segments: &[WeightedSegment],
+    candidates: &[resolve::SegCandidate<'_>],
+    existing: &[SegmentTime],
+) -> Vec<SegmentTime> {
+    resolve::classify_title_anchors(&track.track_title).into_iter()
+        .filter_map(|ta| {
+            let time = ta.time?;
+            let (segment_id, _) = resolve::match_anchor(&ta.anchor, &track.number_ids, candidates)?;
+            let in_track = segments.iter().any(|s| s.id == segment_id);
+            let pinned = existing.iter().any(|a| a.segment_id == segment_id);
+            (in_track && !pinned).then_some(SegmentTime {
+                segment_id,
+                start: time,
+                end: None,
+                source: Some(TimingSource::Imported),
+            })
+        })
+        .collect()
+}
+
 /// Distribute weighted segments across a duration, returning estimated start times.
-fn distribute_segments(segments: &[WeightedSegment], duration: f64) -> Vec<SegmentTime> {
+///
+/// The first segment starts after `padding`'s head and the last ends
+/// before its tail, unless an anchor falls within the padding; padding
+/// that leaves no time for the text is ignored with a warning. `anchors`
+/// are times already known for some segments; they are returned
+/// unchanged, and the segments between two anchors share the time between
+/// them by weight. An anchor naming a segment not in `segments`, or a
+/// segment already pinned by an earlier anchor, or out of order with the
+/// anchors before it, or past the end of the track, is kept as it is but
+/// does not pin the estimates around it.
+fn distribute_segments(
+    segments: &[WeightedSegment],
+    duration: f64,
+    padding: Padding,
+    anchors: &[SegmentTime],
+    track_title: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<SegmentTime> {
     if segments.is_empty() || duration <= 0.0 {
         return Vec::new();
     }
 
-    let total_weight: f64 = segments.iter().map(|s| s.weight).sum();
-    if total_weight == 0.0 {
-        return Vec::new();
+    let position: HashMap<&str, usize> = segments.iter().enumerate().map(|(i, s)| (s.id.as_str(), i)).collect();
+    let mut pinned: Vec<(usize, &SegmentTime)> = Vec::new();
+    let mut unpinned: Vec<&SegmentTime> = Vec::new();
+    let mut by_position: Vec<(usize, &SegmentTime)> = Vec::new();
+    for anchor in anchors {
+        match position.get(anchor.segment_id.as_str()) {
+            Some(&pos) => by_position.push((pos, anchor)),
+            None => {
+                warnings.push(format!(
+                    "Track '{track_title}': anchor '{}' is not one of the track's segments",
+                    anchor.segment_id
+                ));
+                unpinned.push(anchor);
+            }
+        }
+    }
+    by_position.sort_by_key(|(pos, _)| *pos);
+    for (pos, anchor) in by_position {
+        if pinned.last().is_some_and(|(p, _)| *p == pos) {
+            warnings.push(format!(
+                "Track '{track_title}': anchor '{}' at {}s names a segment already anchored; estimates ignore it",
+                anchor.segment_id, anchor.start
+            ));
+            unpinned.push(anchor);
+            continue;
+        }
+        let in_order = anchor.start >= 0.0 && anchor.start < duration
+            && pinned.last().map_or(pos == 0 || anchor.start > 0.0, |(_, a)| anchor.start > a.start);
+        if in_order {
+            pinned.push((pos, anchor));
+        } else {
+            warnings.push(format!(
+                "Track '{track_title}': anchor '{}' at {}s is out of order; estimates ignore it",
+                anchor.segment_id, anchor.start
+            ));
+            unpinned.push(anchor);
+        }
     }
 
-    let mut result = Vec::with_capacity(segments.len());
-    let mut cumulative = 0.0;
+    // Where the text starts and ends, inside any padding
+    let mut head = padding.head_seconds.max(0.0);
+    let mut end = duration - padding.tail_seconds.max(0.0);
+    if pinned.first().is_some_and(|(_, a)| a.start <= head) {
+        head = 0.0;
+    }
+    if pinned.last().is_some_and(|(_, a)| a.start >= end) {
+        end = duration;
+    }
+    if head >= end {
+        warnings.push(format!(
+            "Track '{track_title}': padding of {}s and {}s leaves no time for the text; ignored",
+            padding.head_seconds, padding.tail_seconds
+        ));
+        (head, end) = (0.0, duration);
+    }
 
-    for seg in segments {
-        let start = (cumulative / total_weight) * duration;
-        result.push(SegmentTime {
-            segment_id: seg.id.clone(),
-            start: round_to_ms(start),
-        });
-        cumulative += seg.weight;
+    // Fixed points bounding each stretch of estimates: the start of the
+    // text, the pinned anchors, and the end of the text
+    let mut fixed: Vec<(usize, f64)> = vec![(0, head)];
+    for &(pos, anchor) in &pinned {
+        if pos == 0 {
+            fixed[0].1 = anchor.start;
+        } else {
+            fixed.push((pos, anchor.start));
+        }
+    }
+    fixed.push((segments.len(), end));
+
+    let pinned: HashMap<usize, &SegmentTime> = pinned.into_iter().collect();
+    let mut result = Vec::with_capacity(segments.len() + unpinned.len());
+    for window in fixed.windows(2) {
+        let ((from, t0), (to, t1)) = (window[0], window[1]);
+        // Segments without weight (no words) share the time evenly
+        let total_weight: f64 = segments[from..to].iter().map(|s| s.weight).sum();
+        let weight = |seg: &WeightedSegment| if total_weight > 0.0 { seg.weight } else { 1.0 };
+        let total_weight = if total_weight > 0.0 { total_weight } else { (to - from) as f64 };
+        let mut cumulative = 0.0;
+        for (pos, seg) in segments.iter().enumerate().take(to).skip(from) {
+            let start = t0 + (cumulative / total_weight) * (t1 - t0);
+            cumulative += weight(seg);
+            if let Some(anchor) = pinned.get(&pos) {
+                result.push((*anchor).clone());
+            } else if !unpinned.iter().any(|a| a.segment_id == seg.id) {
+                result.push(SegmentTime {
+                    segment_id: seg.id.clone(),
+                    start: round_to_ms(start),
+                    end: None,
+                    source: Some(TimingSource::Estimated),
+                });
+            }
+        }
+    }
+    if !unpinned.is_empty() {
+        result.extend(unpinned.into_iter().cloned());
+        result.sort_by(|a, b| a.start.total_cmp(&b.start));
     }
 
     result
 }
 
-/// Round to millisecond precision.
-fn round_to_ms(seconds: f64) -> f64 {
-    (seconds * 1000.0).round() / 1000.0
+/// One row of the per-segment estimation report.
+#[derive(Debug, Clone)]
+pub struct SegmentDiagnostic {
+    pub track_id: String,
+    pub track_title: String,
+    pub segment_id: String,
+    pub source: Option<TimingSource>,
+    /// Weight the estimate gave the segment; `None` for times it did not place.
+    pub weight: Option<f64>,
+    pub start: f64,
+    /// Time until the segment's end: its explicit end, the next segment's
+    /// start, or the end of the track.
+    pub duration: Option<f64>,
+    pub words: usize,
+    pub words_per_second: Option<f64>,
+}
+
+/// Per-segment rows for every track of `overlay` with segments in
+/// `weights` (an estimation pass's `EstimateResult::weights`), for spotting
+/// where the weighting goes wrong (implausible words per second).
+pub fn diagnostics(
+    base: &BaseLibretto,
+    overlay: &TimingOverlay,
+    weights: &HashMap<String, f64>,
+) -> Vec<SegmentDiagnostic> {
+    let mut rows = Vec::new();
+    for (i, track) in overlay.track_timings.iter().enumerate() {
+        let times = &track.segment_times;
+        if !times.iter().any(|t| weights.contains_key(&t.segment_id)) {
+            continue;
+        }
+        for (j, time) in times.iter().enumerate() {
+            let end = time.end
+                .or_else(|| times.get(j + 1).map(|next| next.start))
+                .or(track.duration_seconds);
+            let duration = end.map(|e| round_to_ms(e - time.start));
+            let words = base.find_segment(&time.segment_id)
+                .and_then(|s| s.text.as_deref())
+                .map_or(0, |t| t.split_whitespace().count());
+            rows.push(SegmentDiagnostic {
+                track_id: track.track_id(i),
+                track_title: track.track_title.clone(),
+                segment_id: time.segment_id.clone(),
+                source: time.source,
+                weight: weights.get(&time.segment_id).copied(),
+                start: time.start,
+                duration,
+                words,
+                words_per_second: duration.filter(|d| *d > 0.0).map(|d| words as f64 / d),
+            });
+        }
+    }
+    rows
+}
+
+/// Write diagnostic rows as CSV with a header line.
+pub fn diagnostics_csv(rows: &[SegmentDiagnostic]) -> String {
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+    let number = |value: Option<f64>, decimals: usize| value.map(|v| format!("{v:.decimals$}")).unwrap_or_default();
+
+    let mut csv = String::from("track,track_title,segment_id,source,weight,start,duration,words,words_per_second\n");
+    for row in rows {
+        let source = row.source.map(|s| format!("{s:?}").to_lowercase()).unwrap_or_default();
+        csv.push_str(&format!(
+            "{},{},{},{},{},{:.3},{},{},{}\n",
+            field(&row.track_id),
+            field(&row.track_title),
+            field(&row.segment_id),
+            source,
+            number(row.weight, 2),
+            row.start,
+            number(row.duration, 3),
+            row.words,
+            number(row.words_per_second, 2),
+        ));
+    }
+    csv
 }
 
 #[cfg(test)]
@@ -479,17 +978,15 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![TrackTiming {
                 track_title: "Track 1".to_string(),
                 disc_number: Some(1),
                 track_number: Some(1),
                 duration_seconds: Some(duration),
                 number_ids: vec!["no-1".to_string()],
-                start_segment_id: None,
-                segment_times: vec![],
+                ..Default::default()
             }],
+            ..Default::default()
         }
     }
 
@@ -519,17 +1016,162 @@ mod tests {
     }
 
     #[test]
-    fn test_estimate_skips_existing_times() {
+    fn test_estimate_around_anchors() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        // A tapped start for the second segment pins it; an earlier
+        // estimate for the third is recomputed
+        overlay.track_timings[0].segment_times = vec![
+            SegmentTime { segment_id: "no-1-002".to_string(), start: 20.0, end: None, source: None },
+            SegmentTime {
+                segment_id: "no-1-003".to_string(),
+                start: 1.0,
+                end: None,
+                source: Some(TimingSource::Estimated),
+            },
+        ];
+
+        let result = estimate_timings(&base, &overlay);
+        assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+        let times = &result.overlay.track_timings[0].segment_times;
+        let starts: Vec<f64> = times.iter().map(|t| t.start).collect();
+        // Weights 9 and 0.5 share the 105s after the anchor
+        assert_eq!(starts, vec![0.0, 20.0, 119.474]);
+        assert_eq!(times[1].source, None);
+        assert_eq!(times[2].source, Some(TimingSource::Estimated));
+        assert_eq!((result.stats[0].segments_estimated, result.stats[0].anchors), (2, 1));
+    }
+
+    #[test]
+    fn test_estimate_scaffold() {
+        // `timing init` then `timing estimate`: the scaffold's placeholder
+        // times are not anchors
+        let base = test_base();
+        let mut overlay = crate::merge::scaffold_overlay(&base, "test");
+        overlay.track_timings[0].duration_seconds = Some(125.0);
+        assert!(overlay.track_timings[0].is_scaffold());
+
+        let result = estimate_timings(&base, &overlay);
+        assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+        let starts: Vec<f64> = result.overlay.track_timings[0].segment_times.iter().map(|t| t.start).collect();
+        assert_eq!(starts, vec![0.0, 30.0, 120.0]);
+        assert_eq!((result.stats[0].segments_estimated, result.stats[0].anchors), (3, 0));
+    }
+
+    #[test]
+    fn test_estimate_keeps_tapped_zero() {
+        // A tapped 0.0 from before `source` existed is an anchor like any other
         let base = test_base();
         let mut overlay = test_overlay(125.0);
-        // Pre-fill segment_times — should be left alone
+        overlay.track_timings[0].padding = Some(Padding { head_seconds: 5.0, tail_seconds: 0.0 });
         overlay.track_timings[0].segment_times = vec![
-            SegmentTime { segment_id: "no-1-001".to_string(), start: 0.0 },
+            SegmentTime { segment_id: "no-1-001".to_string(), start: 0.0, end: None, source: None },
+            SegmentTime { segment_id: "no-1-002".to_string(), start: 20.0, end: None, source: None },
         ];
+        assert!(!overlay.track_timings[0].is_scaffold());
 
         let result = estimate_timings(&base, &overlay);
-        // Should still have the original single segment_time
-        assert_eq!(result.overlay.track_timings[0].segment_times.len(), 1);
+        let times = &result.overlay.track_timings[0].segment_times;
+        assert_eq!((times[0].start, times[0].source), (0.0, None));
+        assert_eq!((result.stats[0].segments_estimated, result.stats[0].anchors), (1, 2));
+    }
+
+    #[test]
+    fn test_distribute_without_weight() {
+        let segments: Vec<WeightedSegment> = ["a", "b", "c", "d"].iter()
+            .map(|id| WeightedSegment { id: id.to_string(), weight: 0.0, number_type: NumberType::Aria })
+            .collect();
+        let mut warnings = Vec::new();
+        let times = distribute_segments(&segments, 40.0, Padding::default(), &[], "", &mut warnings);
+        let starts: Vec<f64> = times.iter().map(|t| t.start).collect();
+        assert_eq!(starts, vec![0.0, 10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn test_estimate_out_of_order_anchor() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        overlay.track_timings[0].segment_times = vec![
+            SegmentTime { segment_id: "no-1-002".to_string(), start: 50.0, end: None, source: None },
+            SegmentTime { segment_id: "no-1-003".to_string(), start: 40.0, end: None, source: None },
+        ];
+
+        let result = estimate_timings(&base, &overlay);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("no-1-003"));
+        // The out-of-order time is kept as it is for someone to fix
+        let times: Vec<(&str, f64)> = result.overlay.track_timings[0].segment_times.iter()
+            .map(|t| (t.segment_id.as_str(), t.start))
+            .collect();
+        assert_eq!(times, vec![("no-1-001", 0.0), ("no-1-003", 40.0), ("no-1-002", 50.0)]);
+    }
+
+    #[test]
+    fn test_estimate_duplicate_anchor() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        // Two taps for one segment: the first pins it, the second is kept
+        // as it is for someone to fix
+        overlay.track_timings[0].segment_times = vec![
+            SegmentTime { segment_id: "no-1-002".to_string(), start: 20.0, end: None, source: None },
+            SegmentTime { segment_id: "no-1-002".to_string(), start: 25.0, end: None, source: None },
+        ];
+
+        let result = estimate_timings(&base, &overlay);
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("already anchored"));
+        let times: Vec<(&str, f64)> = result.overlay.track_timings[0].segment_times.iter()
+            .map(|t| (t.segment_id.as_str(), t.start))
+            .collect();
+        assert_eq!(times, vec![("no-1-001", 0.0), ("no-1-002", 20.0), ("no-1-002", 25.0), ("no-1-003", 119.474)]);
+        assert_eq!((result.stats[0].segments_estimated, result.stats[0].anchors), (2, 2));
+    }
+
+    #[test]
+    fn test_estimate_skips_cut_segments() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        overlay.track_timings[0].skip_segment_ids = vec!["no-1-002".to_string()];
+
+        let result = estimate_timings(&base, &overlay);
+        let times: Vec<(&str, f64)> = result.overlay.track_timings[0].segment_times.iter()
+            .map(|t| (t.segment_id.as_str(), t.start))
+            .collect();
+        // Weights 3 and 0.5 share the whole track
+        assert_eq!(times, vec![("no-1-001", 0.0), ("no-1-003", 107.143)]);
+        assert_eq!(result.stats[0].segments_skipped, 1);
+
+        // An omitted number takes no time at all
+        overlay.omitted_numbers = vec![OmittedNumber { number_id: "no-1".to_string(), reason: None }];
+        let result = estimate_timings(&base, &overlay);
+        assert!(result.overlay.track_timings[0].segment_times.is_empty());
+        assert_eq!(result.stats[0].segments_skipped, 3);
+    }
+
+    #[test]
+    fn test_estimate_with_padding() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        overlay.padding = Some(Padding { head_seconds: 20.0, tail_seconds: 5.0 });
+        let starts = |overlay: &TimingOverlay| -> Vec<f64> {
+            let result = estimate_timings(&base, overlay);
+            assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+            result.overlay.track_timings[0].segment_times.iter().map(|t| t.start).collect()
+        };
+
+        // Weights 3, 9, 0.5 share the 100s between the introduction and the applause
+        assert_eq!(starts(&overlay), vec![20.0, 44.0, 116.0]);
+
+        // A track's own padding overrides the overlay's
+        overlay.track_timings[0].padding = Some(Padding { head_seconds: 0.0, tail_seconds: 25.0 });
+        assert_eq!(starts(&overlay), vec![0.0, 24.0, 96.0]);
+
+        // An anchor inside the introduction shows there is none
+        overlay.track_timings[0].padding = None;
+        overlay.track_timings[0].segment_times = vec![
+            SegmentTime { segment_id: "no-1-002".to_string(), start: 10.0, end: None, source: None },
+        ];
+        assert_eq!(starts(&overlay), vec![0.0, 10.0, 114.211]);
     }
 
     #[test]
@@ -542,6 +1184,27 @@ mod tests {
         assert!(result.overlay.track_timings[0].segment_times.is_empty());
     }
 
+    #[test]
+    fn test_estimate_skips_instrumental_tracks() {
+        let base = test_base();
+        let mut overlay = test_overlay(100.0);
+        overlay.track_timings[0].instrumental = true;
+        let result = estimate_timings(&base, &overlay);
+        assert!(result.overlay.track_timings[0].segment_times.is_empty());
+        assert!(result.stats.is_empty());
+
+        // Nor does it take the segments of the track after it
+        overlay.track_timings[0].number_ids.clear();
+        let mut sung = overlay.track_timings[0].clone();
+        sung.instrumental = false;
+        sung.number_ids = vec!["no-1".to_string()];
+        sung.start_segment_id = Some("no-1-001".to_string());
+        overlay.track_timings.push(sung);
+        let result = estimate_timings(&base, &overlay);
+        assert!(result.overlay.track_timings[0].segment_times.is_empty());
+        assert_eq!(result.overlay.track_timings[1].segment_times.len(), 3);
+    }
+
     #[test]
     fn test_estimate_multi_track_number() {
         let mut base = test_base();
@@ -598,8 +1261,6 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![
                 TrackTiming {
                     track_title: "Finale Part 1".to_string(),
@@ -607,8 +1268,7 @@ mod tests {
                     track_number: Some(1),
                     duration_seconds: Some(50.0), // half the time
                     number_ids: vec!["no-2".to_string()],
-                    start_segment_id: None,
-                    segment_times: vec![],
+                    ..Default::default()
                 },
                 TrackTiming {
                     track_title: "Finale Part 2".to_string(),
@@ -616,10 +1276,10 @@ mod tests {
                     track_number: Some(2),
                     duration_seconds: Some(50.0), // half the time
                     number_ids: vec!["no-2".to_string()],
-                    start_segment_id: None,
-                    segment_times: vec![],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         let result = estimate_timings(&base, &overlay);
@@ -673,8 +1333,6 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![
                 TrackTiming {
                     track_title: "Track 1".to_string(),
@@ -684,7 +1342,7 @@ mod tests {
                     number_ids: vec!["no-1".to_string()],
                     // Track 1 starts at seg 001
                     start_segment_id: Some("no-1-001".to_string()),
-                    segment_times: vec![],
+                    ..Default::default()
                 },
                 TrackTiming {
                     track_title: "Track 2".to_string(),
@@ -694,9 +1352,10 @@ mod tests {
                     number_ids: vec!["no-2".to_string()],
                     // Track 2 starts at seg 003 (crossover from no-1!)
                     start_segment_id: Some("no-1-003".to_string()),
-                    segment_times: vec![],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         let result = estimate_timings(&base, &overlay);
@@ -719,4 +1378,192 @@ mod tests {
         assert_eq!(t1[0].start, 0.0);
         assert_eq!(t2[0].start, 0.0);
     }
+
+    #[test]
+    fn test_estimate_uses_section_marks() {
+        let base = test_base();
+        let mut overlay = test_overlay(120.0);
+        overlay.track_timings[0].start_segment_id = Some("no-1-001".to_string());
+        let plain = estimate_timings(&base, &overlay);
+
+        // Marks from `timing resolve` (or set by hand) stand in for the title's anchors
+        overlay.track_timings[0].section_marks = vec![
+            SectionMark { segment_id: "no-1-001".to_string(), is_recitative: true },
+            SectionMark { segment_id: "no-1-002".to_string(), is_recitative: false },
+        ];
+        let marked = estimate_timings(&base, &overlay);
+        let second_start = |r: &EstimateResult| r.overlay.track_timings[0].segment_times[1].start;
+        // The recitative opening is paced faster, so the aria starts sooner
+        assert!(second_start(&marked) < second_start(&plain));
+    }
+
+    #[test]
+    fn test_syllable_weighting_accuracy() {
+        // "Non so più": short lines of long Italian words, set about one
+        // note per syllable
+        let lines = [
+            "Non so più cosa son, cosa faccio",
+            "or di foco, ora sono di ghiaccio",
+            "ogni donna cangiar di colore",
+            "ogni donna mi fa palpitar",
+            "Solo ai nomi d'amor, di diletto",
+        ];
+        // Counted by hand: non so più co-sa son co-sa fac-cio, ...,
+        // so-lo ai no-mi d'a-mor di di-let-to
+        let syllables = [10, 11, 10, 9, 11];
+        for (line, count) in lines.iter().zip(syllables) {
+            assert_eq!(syllable::count_syllables(line, "it"), count, "{line}");
+        }
+        // Reference starts at 0.8s per syllable
+        let reference = [0.0, 8.0, 16.8, 24.8, 32.0];
+        let duration = 40.8;
+
+        let mut base = test_base();
+        base.numbers[0].segments = lines.iter()
+            .enumerate()
+            .map(|(i, text)| Segment {
+                id: format!("no-1-{:03}", i + 1),
+                segment_type: SegmentType::Sung,
+                character: Some("CHERUBINO".to_string()),
+                text: Some(text.to_string()),
+                translation: None,
+                direction: None,
+                group: None,
+            })
+            .collect();
+        let overlay = test_overlay(duration);
+
+        let mean_error = |weighting| {
+            let result = estimate_timings_with(&base, &overlay, &EstimateOptions { weighting, ..Default::default() });
+            let times = &result.overlay.track_timings[0].segment_times;
+            times.iter().zip(reference).map(|(t, r)| (t.start - r).abs()).sum::<f64>() / reference.len() as f64
+        };
+        let (words, syllables) = (mean_error(Weighting::Words), mean_error(Weighting::Syllables));
+        assert!(syllables < words / 2.0, "syllables {syllables:.3}s vs words {words:.3}s");
+    }
+
+    #[test]
+    fn test_pacing_by_number_type() {
+        let mut base = test_base();
+        // A recitative and the aria after it, 6 words each, in one track
+        let segment = |id: &str, text: &str| Segment {
+            id: id.to_string(),
+            segment_type: SegmentType::Sung,
+            character: Some("A".to_string()),
+            text: Some(text.to_string()),
+            translation: None,
+            direction: None,
+            group: None,
+        };
+        base.numbers = vec![
+            MusicalNumber {
+                id: "rec-1".to_string(),
+                label: "Recitativo".to_string(),
+                number_type: NumberType::Recitative,
+                act: "1".to_string(),
+                scene: None,
+                segments: vec![segment("rec-1-001", "one two three four five six")],
+            },
+            MusicalNumber {
+                id: "no-1".to_string(),
+                label: "No. 1".to_string(),
+                number_type: NumberType::Aria,
+                act: "1".to_string(),
+                scene: None,
+                segments: vec![segment("no-1-001", "one two three four five six")],
+            },
+        ];
+        let mut overlay = test_overlay(100.0);
+        overlay.track_timings[0].number_ids = vec!["rec-1".to_string(), "no-1".to_string()];
+
+        // Default pacing: 6 × 0.5 against 6 × 1.4
+        let result = estimate_timings(&base, &overlay);
+        assert_eq!(result.overlay.track_timings[0].segment_times[1].start, 26.316);
+
+        // A table overriding the aria's pace; the recitative keeps its default
+        let pacing: Pacing = serde_json::from_str(r#"{ "number_types": { "aria": 0.5 } }"#).unwrap();
+        let result = estimate_timings_with(&base, &overlay, &EstimateOptions { pacing, ..Default::default() });
+        assert_eq!(result.overlay.track_timings[0].segment_times[1].start, 50.0);
+
+        // A kind of text taking no time, or less, is refused
+        for invalid in [r#"{ "number_types": { "aria": 0.0 } }"#, r#"{ "segment_types": { "spoken": -1.0 } }"#] {
+            assert!(serde_json::from_str::<Pacing>(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_estimate_with_title_timing_cue() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        overlay.track_timings[0].track_title = "Aria: \"one two three\" – \"four five six\" (0:50)".to_string();
+
+        let result = estimate_timings(&base, &overlay);
+        assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+        let times = &result.overlay.track_timings[0].segment_times;
+        let starts: Vec<f64> = times.iter().map(|t| t.start).collect();
+        // The cue pins the second segment; the direction takes 0.5/9.5 of the rest
+        assert_eq!(starts, vec![0.0, 50.0, 121.053]);
+        assert_eq!(times[1].source, Some(TimingSource::Imported));
+        assert_eq!(result.stats[0].anchors, 1);
+    }
+
+    #[test]
+    fn test_estimate_diagnostics() {
+        let base = test_base();
+        let mut overlay = test_overlay(125.0);
+        overlay.track_timings[0].track_title = "Aria, \"one two three\"".to_string();
+        let result = estimate_timings(&base, &overlay);
+
+        let rows = diagnostics(&base, &result.overlay, &result.weights);
+        assert_eq!(rows.len(), 3);
+        // 9 words between 30s and 120s
+        assert_eq!(rows[1].duration, Some(90.0));
+        assert_eq!(rows[1].words_per_second, Some(0.1));
+        assert_eq!(rows[2].duration, Some(5.0));
+
+        let csv = diagnostics_csv(&rows);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1], "d1-t1,\"Aria, \"\"one two three\"\"\",no-1-001,estimated,4.20,0.000,30.000,3,0.10");
+    }
+
+    #[test]
+    fn test_estimate_tracks_in_order_with_shared_index() {
+        // One number per track, estimated in parallel and reported in order
+        let mut base = test_base();
+        let mut overlay = test_overlay(125.0);
+        for n in 2..=8 {
+            let mut number = base.numbers[0].clone();
+            number.id = format!("no-{n}");
+            for (i, seg) in number.segments.iter_mut().enumerate() {
+                seg.id = format!("no-{n}-{:03}", i + 1);
+            }
+            base.numbers.push(number);
+            let mut track = overlay.track_timings[0].clone();
+            track.track_number = Some(n);
+            track.track_title = format!("Track {n}");
+            track.number_ids = vec![format!("no-{n}")];
+            overlay.track_timings.push(track);
+        }
+
+        let result = estimate_timings(&base, &overlay);
+        let titles: Vec<&str> = result.stats.iter().map(|s| s.track_title.as_str()).collect();
+        let expected: Vec<String> = (1..=8).map(|n| format!("Track {n}")).collect();
+        assert_eq!(titles, expected);
+        assert_eq!(result.overlay.track_timings[7].segment_times[2].segment_id, "no-8-003");
+
+        // Resolving first, against the same index, partitions by start segment
+        let index = SegmentIndex::new(&base);
+        let resolved = resolve::resolve_anchors_indexed(&index, &overlay, &Default::default()).overlay;
+        assert_eq!(resolved.track_timings[4].start_segment_id.as_deref(), Some("no-5-001"));
+        let shared = estimate_timings_indexed(&index, &resolved, &EstimateOptions::default());
+        let starts = |overlay: &TimingOverlay| -> Vec<Vec<(String, f64)>> {
+            overlay.track_timings.iter()
+                .map(|t| t.segment_times.iter().map(|st| (st.segment_id.clone(), st.start)).collect())
+                .collect()
+        };
+        assert_eq!(shared.stats.len(), 8);
+        assert_eq!(starts(&shared.overlay), starts(&estimate_timings(&base, &resolved).overlay));
+        assert_eq!(starts(&shared.overlay), starts(&result.overlay));
+    }
 }
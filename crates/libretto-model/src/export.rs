@@ -0,0 +1,696 @@
+// Exporting a timed libretto to lyric and subtitle formats.
+//
+// Most music players can show an `.lrc` file next to an audio file as
+// scrolling lyrics, so writing one per track turns any of them into a
+// libretto display. Each segment becomes one `[mm:ss.xx]` line prefixed
+// by the singing character. With both languages, the translated line
+// follows under the same timestamp, which players that understand
+// bilingual LRC show beneath the original and the rest show as the next
+// line. Per-line timing instead gives every line of a segment its own
+// timestamp, spreading the segment's time over its lines by their word
+// counts, so long segments scroll line by line.
+//
+// SRT subtitles carry an end time as well, so a timed libretto can be
+// laid over concert video or a screen recording of playback. Each segment
+// becomes one cue, keeping the line breaks of its text.
+//
+// WebVTT is what browsers play through a `<track>` element. Cues are
+// identified by segment ID and the singer is given as a `<v>` voice span
+// rather than a text prefix; optionally each character also gets a CSS
+// class and a color in a `STYLE` block, so a page can tell voices apart.
+// Per-line timing there becomes karaoke-style timestamp tags inside the
+// cue, which players use to highlight the line being sung.
+//
+// Broadcast and streaming toolchains take TTML instead, in its IMSC 1
+// text profile. A bilingual export puts the original in a region at the
+// top of the picture and the translation in one at the bottom.
+//
+// Advanced SubStation Alpha (`.ass`), the format Aegisub edits, gives
+// each character a style of their own, so timing can be reviewed by eye
+// against video and overlays look finished. Colors are derived from the
+// character's name, so they agree across tracks, unless a config names
+// them.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::base_libretto::normalize_character_name;
+use crate::interchange::{InterchangeLibretto, InterchangeSegment, InterchangeTrack};
+
+/// Which language(s) of each segment an export shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// The libretto's original text.
+    #[default]
+    Original,
+    /// The translation, falling back to the original where a segment has none.
+    Translation,
+    /// The original followed by its translation.
+    Both,
+}
+
+/// How long a cue with no known end stays up, in seconds.
+const DEFAULT_CUE_SECONDS: f64 = 5.0;
+
+/// File name for a track's export: its track ID with `extension` (`d1-t2.lrc`).
+pub fn track_file_name(track: &InterchangeTrack, extension: &str) -> String {
+    format!("{}.{}", track.track_id, extension)
+}
+
+/// The blocks of text a segment shows under `layout`, each a list of lines.
+///
+/// With `prefix`, sung text gets the character's name before its first
+/// line. A segment with no text shows its stage direction in parentheses,
+/// whatever the layout. Segments with neither give no blocks.
+fn segment_blocks(seg: &InterchangeSegment, layout: Layout, prefix: bool) -> Vec<Vec<String>> {
+    let with_character = |text: &str| {
+        let mut lines = text_lines(text);
+        if let (true, Some(c), Some(first)) = (prefix, &seg.character, lines.first_mut()) {
+            *first = format!("{c}: {first}");
+        }
+        lines
+    };
+    let translation = seg.translation.as_deref().filter(|t| !t.trim().is_empty());
+
+    match (&seg.text, &seg.direction) {
+        (Some(text), _) => match (layout, translation) {
+            (Layout::Original, _) | (_, None) => vec![with_character(text)],
+            (Layout::Translation, Some(t)) => vec![with_character(t)],
+            (Layout::Both, Some(t)) => vec![with_character(text), text_lines(t)],
+        },
+        (None, Some(direction)) => vec![vec![format!("({})", text_lines(direction).join(" "))]],
+        (None, None) => vec![],
+    }
+}
+
+/// The non-blank lines of a text, trimmed.
+fn text_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// End of segment `i`: its own end, the next segment's start, the end of
+/// the track, or a few seconds after it starts.
+fn segment_end(track: &InterchangeTrack, i: usize) -> f64 {
+    let seg = &track.segments[i];
+    seg.end
+        .or_else(|| track.segments.get(i + 1).map(|next| next.start))
+        .or(track.duration_seconds)
+        .filter(|&end| end > seg.start)
+        .unwrap_or(seg.start + DEFAULT_CUE_SECONDS)
+}
+
+/// When each line of a block starts, spreading `start..end` over the
+/// lines in proportion to their word counts.
+fn line_starts(lines: &[String], start: f64, end: f64) -> Vec<f64> {
+    let weights: Vec<usize> = lines.iter().map(|l| l.split_whitespace().count().max(1)).collect();
+    let total: usize = weights.iter().sum();
+    let mut before = 0;
+    weights.iter()
+        .map(|w| {
+            let at = start + (end - start) * before as f64 / total as f64;
+            before += w;
+            at
+        })
+        .collect()
+}
+
+/// Write one track of an interchange libretto as an LRC file.
+///
+/// The header carries the track title, composer, and album (the opera
+/// title when the track has no album) as ID tags. Each block of text
+/// under `layout` becomes one line at the segment's start; multi-line
+/// text is joined with " / ", since an LRC line cannot wrap.
+///
+/// With `per_line`, every line gets its own timestamp instead, from
+/// `line_starts` over the segment's interval. A translation with as many
+/// lines as the original shares the original's timestamps, line by line,
+/// so bilingual players still pair them.
+pub fn export_lrc(libretto: &InterchangeLibretto, track: &InterchangeTrack, layout: Layout, per_line: bool) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("[ti:{}]\n", text_lines(&track.title).join(" ")));
+    out.push_str(&format!("[ar:{}]\n", libretto.opera.composer));
+    out.push_str(&format!("[al:{}]\n", track.album.as_deref().unwrap_or(&libretto.opera.title)));
+    if let Some(d) = track.duration_seconds {
+        let secs = d.max(0.0).round() as u64;
+        out.push_str(&format!("[length:{}:{:02}]\n", secs / 60, secs % 60));
+    }
+
+    for (i, seg) in track.segments.iter().enumerate() {
+        let blocks = segment_blocks(seg, layout, true);
+        if !per_line {
+            let stamp = lrc_timestamp(seg.start);
+            for block in blocks {
+                out.push_str(&format!("[{stamp}]{}\n", block.join(" / ")));
+            }
+            continue;
+        }
+        // Word counts without the character prefix.
+        let unprefixed = segment_blocks(seg, layout, false);
+        let end = segment_end(track, i);
+        let first = unprefixed.first().map(|lines| line_starts(lines, seg.start, end)).unwrap_or_default();
+        let mut lines: Vec<(f64, &String)> = Vec::new();
+        for (block, weighed) in blocks.iter().zip(&unprefixed) {
+            let starts = match weighed.len() == first.len() {
+                true => first.clone(),
+                false => line_starts(weighed, seg.start, end),
+            };
+            lines.extend(starts.into_iter().zip(block));
+        }
+        lines.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (at, line) in lines {
+            out.push_str(&format!("[{}]{line}\n", lrc_timestamp(at)));
+        }
+    }
+    out
+}
+
+/// `mm:ss.xx`, with minutes running past 59 for long tracks.
+fn lrc_timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{:02}:{:02}.{:02}", centis / 6000, (centis / 100) % 60, centis % 100)
+}
+
+/// Write one track of an interchange libretto as an SRT subtitle file.
+///
+/// Each segment with text becomes a numbered cue running from its start
+/// to its end (see `segment_end`), with the lines of every block under
+/// `layout` one after another.
+pub fn export_srt(track: &InterchangeTrack, layout: Layout) -> String {
+    let mut out = String::new();
+    let mut cue = 0;
+    for (i, seg) in track.segments.iter().enumerate() {
+        let lines: Vec<String> = segment_blocks(seg, layout, true).into_iter().flatten().collect();
+        if lines.is_empty() {
+            continue;
+        }
+        cue += 1;
+        out.push_str(&format!(
+            "{cue}\n{} --> {}\n{}\n\n",
+            srt_timestamp(seg.start),
+            srt_timestamp(segment_end(track, i)),
+            lines.join("\n")
+        ));
+    }
+    out
+}
+
+/// `hh:mm:ss,mmm`.
+fn srt_timestamp(seconds: f64) -> String {
+    let ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!("{:02}:{:02}:{:02},{:03}", ms / 3_600_000, (ms / 60_000) % 60, (ms / 1000) % 60, ms % 1000)
+}
+
+/// Colors given to characters' cue classes, in order of first appearance.
+const VOICE_COLORS: &[&str] = &["#ffd75e", "#8fd3ff", "#ff9f9f", "#a8e6a1", "#d7b4ff", "#ffbf80"];
+
+/// Write one track of an interchange libretto as a WebVTT file.
+///
+/// Each segment with text becomes a cue identified by its segment ID
+/// (its position when it has none), timed like SRT. Sung text is wrapped
+/// in a `<v Character>` voice span. With `classes`, the voice span also
+/// carries a class named after the character (`<v.figaro FIGARO>`, or
+/// `voice-2` for the second character when the name has no letters or
+/// digits), the
+/// translation of a bilingual cue is wrapped in `<c.translation>`, and a
+/// `STYLE` block gives each character its own color.
+///
+/// With `per_line`, each line of the cue's first block after the first is
+/// preceded by a `<hh:mm:ss.mmm>` timestamp tag from `line_starts`, for
+/// karaoke-style highlighting. Lines of a translation that follows are
+/// left untimed, since the tags in a cue must run forward.
+pub fn export_vtt(track: &InterchangeTrack, layout: Layout, classes: bool, per_line: bool) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+
+    // Each character's class, in order of first appearance
+    let mut voices: Vec<(&str, String)> = Vec::new();
+    for c in track.segments.iter().filter(|s| s.text.is_some()).filter_map(|s| s.character.as_deref()) {
+        if !voices.iter().any(|(name, _)| *name == c) {
+            let class = match slug(c) {
+                class if class.is_empty() => format!("voice-{}", voices.len() + 1),
+                class => class,
+            };
+            voices.push((c, class));
+        }
+    }
+    if classes {
+        out.push_str("STYLE\n::cue(.translation) { font-style: italic; }\n");
+        for (i, (_, class)) in voices.iter().enumerate() {
+            out.push_str(&format!("::cue(.{class}) {{ color: {}; }}\n", VOICE_COLORS[i % VOICE_COLORS.len()]));
+        }
+        out.push('\n');
+    }
+
+    for (i, seg) in track.segments.iter().enumerate() {
+        let blocks = segment_blocks(seg, layout, false);
+        if blocks.is_empty() {
+            continue;
+        }
+        let end = segment_end(track, i);
+        let mut text: Vec<String> = blocks.iter()
+            .enumerate()
+            .map(|(b, lines)| {
+                let starts = line_starts(lines, seg.start, end);
+                let block = lines.iter()
+                    .zip(starts)
+                    .enumerate()
+                    .map(|(n, (l, at))| match per_line && b == 0 && n > 0 {
+                        true => format!("<{}>{}", vtt_timestamp(at), vtt_escape(l)),
+                        false => vtt_escape(l),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if classes && b > 0 { format!("<c.translation>{block}</c>") } else { block }
+            })
+            .collect();
+        if let (Some(c), Some(_)) = (&seg.character, &seg.text) {
+            let name = vtt_escape(c);
+            let first = &mut text[0];
+            *first = match classes {
+                true => {
+                    let class = voices.iter().find(|(voice, _)| *voice == c.as_str()).map(|(_, class)| class.as_str()).unwrap_or_default();
+                    format!("<v.{class} {name}>{first}</v>")
+                }
+                false => format!("<v {name}>{first}</v>"),
+            };
+        }
+        let id = seg.segment_id.clone().unwrap_or_else(|| (i + 1).to_string());
+        out.push_str(&format!(
+            "{id}\n{} --> {}\n{}\n\n",
+            vtt_timestamp(seg.start),
+            vtt_timestamp(end),
+            text.join("\n")
+        ));
+    }
+    out
+}
+
+/// `hh:mm:ss.mmm`.
+fn vtt_timestamp(seconds: f64) -> String {
+    srt_timestamp(seconds).replace(',', ".")
+}
+
+/// Escape the characters WebVTT cue text reserves.
+fn vtt_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Lowercase letters and digits, other runs of characters becoming a
+/// single hyphen (`IL CONTE` → `il-conte`); used for CSS classes and file names.
+pub fn slug(character: &str) -> String {
+    let mut class = String::new();
+    for ch in character.to_lowercase().chars() {
+        if ch.is_alphanumeric() {
+            class.push(ch);
+        } else if !class.is_empty() && !class.ends_with('-') {
+            class.push('-');
+        }
+    }
+    class.trim_end_matches('-').to_string()
+}
+
+/// Write one track of an interchange libretto as an IMSC 1 (TTML) document.
+///
+/// Each segment with text becomes a `<p>` timed like SRT, character
+/// prefixed, with line breaks kept as `<br/>`. Under `Layout::Both` the
+/// original goes in the `top` region and its translation, tagged with
+/// the translation language, in the `bottom` region; a single language
+/// goes in `bottom`. Paragraphs carry their segment ID as `xml:id`.
+pub fn export_ttml(libretto: &InterchangeLibretto, track: &InterchangeTrack, layout: Layout) -> String {
+    let language = &libretto.opera.language;
+    let translation_language = libretto.opera.translation_language.as_deref();
+    let mut out = String::new();
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!(
+        "<tt xmlns=\"http://www.w3.org/ns/ttml\" xmlns:tts=\"http://www.w3.org/ns/ttml#styling\" \
+         xmlns:ttp=\"http://www.w3.org/ns/ttml#parameter\" xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" \
+         ttp:profile=\"http://www.w3.org/ns/ttml/profile/imsc1/text\" xml:lang=\"{}\">\n",
+        xml_escape(language)
+    ));
+    out.push_str("  <head>\n");
+    out.push_str(&format!("    <metadata><ttm:title>{}</ttm:title></metadata>\n", xml_escape(&track.title)));
+    out.push_str("    <styling>\n");
+    out.push_str("      <style xml:id=\"cue\" tts:textAlign=\"center\" tts:color=\"white\" tts:backgroundColor=\"rgba(0,0,0,0.6)\"/>\n");
+    out.push_str("    </styling>\n");
+    out.push_str("    <layout>\n");
+    out.push_str("      <region xml:id=\"top\" tts:origin=\"10% 5%\" tts:extent=\"80% 20%\" tts:displayAlign=\"before\"/>\n");
+    out.push_str("      <region xml:id=\"bottom\" tts:origin=\"10% 75%\" tts:extent=\"80% 20%\" tts:displayAlign=\"after\"/>\n");
+    out.push_str("    </layout>\n");
+    out.push_str("  </head>\n");
+    out.push_str("  <body style=\"cue\">\n    <div>\n");
+
+    for (i, seg) in track.segments.iter().enumerate() {
+        let has_translation = seg.text.is_some() && seg.translation.as_deref().is_some_and(|t| !t.trim().is_empty());
+        let timing = format!("begin=\"{}\" end=\"{}\"", vtt_timestamp(seg.start), vtt_timestamp(segment_end(track, i)));
+        let id = seg.segment_id.as_deref().filter(|id| id.starts_with(|c: char| c.is_alphabetic() || c == '_'));
+        for (b, lines) in segment_blocks(seg, layout, true).iter().enumerate() {
+            let translated = b > 0 || (layout == Layout::Translation && has_translation);
+            let region = if layout == Layout::Both && b == 0 { "top" } else { "bottom" };
+            let mut attrs = String::new();
+            if let Some(id) = id {
+                attrs.push_str(&format!(" xml:id=\"{}{}\"", xml_escape(id), if b > 0 { "-translation" } else { "" }));
+            }
+            attrs.push_str(&format!(" {timing} region=\"{region}\""));
+            if let (true, Some(lang)) = (translated, translation_language) {
+                attrs.push_str(&format!(" xml:lang=\"{}\"", xml_escape(lang)));
+            }
+            let text = lines.iter().map(|l| xml_escape(l)).collect::<Vec<_>>().join("<br/>");
+            out.push_str(&format!("      <p{attrs}>{text}</p>\n"));
+        }
+    }
+    out.push_str("    </div>\n  </body>\n</tt>\n");
+    out
+}
+
+/// Escape text for an XML (or HTML) element or attribute value.
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Styling for ASS export, read from TOML:
+///
+/// ```toml
+/// [colors]
+/// FIGARO = "#ffd75e"
+/// "LA CONTESSA" = "#d7b4ff"
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AssConfig {
+    /// `#rrggbb` per character name (compared after `normalize_character_name`).
+    #[serde(default)]
+    pub colors: HashMap<String, String>,
+}
+
+impl AssConfig {
+    /// The configured color for a character, or one derived from its name.
+    /// Colors that are not `#rrggbb` are ignored.
+    fn color(&self, character: &str) -> (u8, u8, u8) {
+        let name = normalize_character_name(character);
+        self.colors.iter()
+            .find(|(c, _)| normalize_character_name(c) == name)
+            .and_then(|(_, color)| parse_hex_color(color))
+            .unwrap_or_else(|| {
+                // FNV-1a, so a character keeps its color from track to track and run to run
+                let hash = name.bytes().fold(0x811c9dc5u32, |h, b| (h ^ u32::from(b)).wrapping_mul(0x01000193));
+                parse_hex_color(VOICE_COLORS[hash as usize % VOICE_COLORS.len()]).expect("palette colors are valid")
+            })
+    }
+}
+
+fn parse_hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Write one track of an interchange libretto as an ASS subtitle script.
+///
+/// Each character gets a style in their color; the primary text of a
+/// segment (original, or translation under `Layout::Translation`) is a
+/// layer 0 event at the bottom of the picture in that style, and under
+/// `Layout::Both` the translation is a layer 1 event at the top in the
+/// `Translation` style. Directions use the `Direction` style. A comment
+/// event marks each change of act or scene.
+pub fn export_ass(libretto: &InterchangeLibretto, track: &InterchangeTrack, layout: Layout, config: &AssConfig) -> String {
+    let mut characters: Vec<&str> = Vec::new();
+    for c in track.segments.iter().filter(|s| s.text.is_some()).filter_map(|s| s.character.as_deref()) {
+        if !characters.contains(&c) {
+            characters.push(c);
+        }
+    }
+
+    let mut out = String::from("[Script Info]\n");
+    out.push_str(&format!("; {} — {}\n", libretto.opera.title, libretto.opera.composer));
+    out.push_str(&format!("Title: {}\n", ass_text(&track.title)));
+    out.push_str("ScriptType: v4.00+\nPlayResX: 1920\nPlayResY: 1080\nWrapStyle: 0\nScaledBorderAndShadow: yes\n\n");
+
+    out.push_str("[V4+ Styles]\n");
+    out.push_str("Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, \
+                  Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, \
+                  MarginL, MarginR, MarginV, Encoding\n");
+    let style = |name: &str, color: (u8, u8, u8), italic: bool, alignment: u8| {
+        format!(
+            "Style: {name},Arial,56,{},&H000000FF,&H00000000,&H80000000,0,{},0,0,100,100,0,0,1,2.5,1,{alignment},80,80,50,1\n",
+            ass_color(color),
+            if italic { -1 } else { 0 }
+        )
+    };
+    out.push_str(&style("Default", (255, 255, 255), false, 2));
+    out.push_str(&style("Translation", (200, 220, 235), true, 8));
+    out.push_str(&style("Direction", (170, 170, 170), true, 2));
+    for c in &characters {
+        out.push_str(&style(&ass_name(c), config.color(c), false, 2));
+    }
+
+    out.push_str("\n[Events]\nFormat: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text\n");
+    let mut place: Option<(Option<&str>, Option<&str>)> = None;
+    for (i, seg) in track.segments.iter().enumerate() {
+        let (start, end) = (ass_time(seg.start), ass_time(segment_end(track, i)));
+        let here = (seg.act.as_deref().or(track.act.as_deref()), seg.scene.as_deref().or(track.scene.as_deref()));
+        if place != Some(here) && (here.0.is_some() || here.1.is_some()) {
+            let mut label = Vec::new();
+            if let Some(act) = here.0 {
+                label.push(format!("Act {act}"));
+            }
+            if let Some(scene) = here.1 {
+                label.push(format!("Scene {scene}"));
+            }
+            out.push_str(&format!("Comment: 0,{start},{start},Default,,0,0,0,,{}\n", ass_text(&label.join(", "))));
+        }
+        place = Some(here);
+
+        let name = seg.character.as_deref().map(ass_name).unwrap_or_default();
+        for (b, lines) in segment_blocks(seg, layout, false).iter().enumerate() {
+            let text = lines.iter().map(|l| ass_text(l)).collect::<Vec<_>>().join("\\N");
+            let style = match (b, &seg.text, &seg.character) {
+                (0, None, _) => "Direction".to_string(),
+                (0, Some(_), Some(_)) => name.clone(),
+                (0, Some(_), None) => "Default".to_string(),
+                _ => "Translation".to_string(),
+            };
+            out.push_str(&format!("Dialogue: {b},{start},{end},{style},{name},0,0,0,,{text}\n"));
+        }
+    }
+    out
+}
+
+/// `&HAABBGGRR`, ASS's byte order, fully opaque.
+fn ass_color((r, g, b): (u8, u8, u8)) -> String {
+    format!("&H00{b:02X}{g:02X}{r:02X}")
+}
+
+/// `h:mm:ss.cc`.
+fn ass_time(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{}:{:02}:{:02}.{:02}", centis / 360_000, (centis / 6000) % 60, (centis / 100) % 60, centis % 100)
+}
+
+/// A style or actor name: commas would split the line's fields.
+fn ass_name(name: &str) -> String {
+    name.replace(',', " /")
+}
+
+/// Event text: braces would open override blocks.
+fn ass_text(text: &str) -> String {
+    text.replace('{', "(").replace('}', ")")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::{parse_lrc, parse_srt, parse_vtt};
+    use crate::interchange::*;
+
+    fn segment(start: f64, character: Option<&str>, text: Option<&str>, translation: Option<&str>, direction: Option<&str>) -> InterchangeSegment {
+        InterchangeSegment {
+            start,
+            segment_type: if text.is_some() { "sung" } else { "direction" }.to_string(),
+            character: character.map(str::to_string),
+            text: text.map(str::to_string),
+            translation: translation.map(str::to_string),
+            direction: direction.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn libretto() -> InterchangeLibretto {
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "W. A. Mozart".to_string(),
+                language: "it".to_string(),
+                translation_language: Some("en".to_string()),
+                ..Default::default()
+            },
+            tracks: vec![InterchangeTrack {
+                track_id: "d1-t2".to_string(),
+                title: "Cinque... dieci...".to_string(),
+                disc_number: Some(1),
+                track_number: Some(2),
+                duration_seconds: Some(185.4),
+                segments: vec![
+                    segment(0.0, None, None, None, Some("Figaro is measuring the room.")),
+                    segment(12.5, Some("FIGARO"), Some("Cinque...\ndieci..."), Some("Five...\nten..."), None),
+                    segment(65.25, Some("SUSANNA"), Some("Ora sì ch'io son contenta"), None, None),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_lrc() {
+        let lib = libretto();
+        let track = &lib.tracks[0];
+        assert_eq!(track_file_name(track, "lrc"), "d1-t2.lrc");
+
+        let lrc = export_lrc(&lib, track, Layout::Original, false);
+        assert_eq!(lrc, "[ti:Cinque... dieci...]\n\
+                         [ar:W. A. Mozart]\n\
+                         [al:Le nozze di Figaro]\n\
+                         [length:3:05]\n\
+                         [00:00.00](Figaro is measuring the room.)\n\
+                         [00:12.50]FIGARO: Cinque... / dieci...\n\
+                         [01:05.25]SUSANNA: Ora sì ch'io son contenta\n");
+    }
+
+    #[test]
+    fn test_export_lrc_with_translation() {
+        let lib = libretto();
+        let lrc = export_lrc(&lib, &lib.tracks[0], Layout::Both, false);
+        assert!(lrc.contains("[00:12.50]FIGARO: Cinque... / dieci...\n[00:12.50]Five... / ten...\n"));
+
+        // Round-trips through the LRC reader used for imports.
+        let lines = parse_lrc(&lrc).unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3].start, 65.25);
+    }
+
+    #[test]
+    fn test_export_lrc_per_line() {
+        let mut lib = libretto();
+        lib.tracks[0].segments[1].text = Some("Cinque... dieci...\nventi...".to_string());
+        lib.tracks[0].segments[1].translation = Some("Five... ten... twenty...".to_string());
+        let lrc = export_lrc(&lib, &lib.tracks[0], Layout::Both, true);
+        // 12.5 to 65.25 over three words, two on the first line: the
+        // second line starts two thirds of the way through.
+        assert!(lrc.contains(
+            "[00:12.50]FIGARO: Cinque... dieci...\n\
+             [00:12.50]Five... ten... twenty...\n\
+             [00:47.67]venti...\n\
+             [01:05.25]SUSANNA: Ora sì ch'io son contenta\n"
+        ));
+    }
+
+    #[test]
+    fn test_export_srt() {
+        let lib = libretto();
+        let srt = export_srt(&lib.tracks[0], Layout::Translation);
+        assert_eq!(srt, "1\n00:00:00,000 --> 00:00:12,500\n(Figaro is measuring the room.)\n\n\
+                         2\n00:00:12,500 --> 00:01:05,250\nFIGARO: Five...\nten...\n\n\
+                         3\n00:01:05,250 --> 00:03:05,400\nSUSANNA: Ora sì ch'io son contenta\n\n");
+
+        let cues = parse_srt(&export_srt(&lib.tracks[0], Layout::Both)).unwrap();
+        assert_eq!(cues.len(), 3);
+        assert_eq!(cues[1].end, Some(65.25));
+        assert!(cues[1].text.contains("FIGARO: Cinque..."));
+        assert!(cues[1].text.contains("ten..."));
+    }
+
+    #[test]
+    fn test_export_vtt() {
+        let mut lib = libretto();
+        lib.tracks[0].segments[1].segment_id = Some("no-1-duettino-001".to_string());
+        let vtt = export_vtt(&lib.tracks[0], Layout::Original, false, false);
+        assert!(vtt.starts_with("WEBVTT\n\n1\n00:00:00.000 --> 00:00:12.500\n(Figaro is measuring the room.)\n\n"));
+        assert!(vtt.contains("no-1-duettino-001\n00:00:12.500 --> 00:01:05.250\n<v FIGARO>Cinque...\ndieci...</v>\n\n"));
+        assert!(!vtt.contains("STYLE"));
+
+        let cues = parse_vtt(&vtt).unwrap();
+        assert_eq!(cues.len(), 3);
+        assert_eq!(cues[1].text, "Cinque... dieci...");
+    }
+
+    #[test]
+    fn test_export_vtt_per_line() {
+        let lib = libretto();
+        let vtt = export_vtt(&lib.tracks[0], Layout::Both, false, true);
+        assert!(vtt.contains("00:00:12.500 --> 00:01:05.250\n<v FIGARO>Cinque...\n<00:00:38.875>dieci...</v>\nFive...\nten...\n\n"));
+        assert_eq!(parse_vtt(&vtt).unwrap()[1].text, "Cinque... dieci... Five... ten...");
+    }
+
+    #[test]
+    fn test_export_vtt_classes() {
+        let mut lib = libretto();
+        lib.tracks[0].segments[2].character = Some("IL CONTE".to_string());
+        let vtt = export_vtt(&lib.tracks[0], Layout::Both, true, false);
+        assert!(vtt.contains("STYLE\n::cue(.translation) { font-style: italic; }\n::cue(.figaro) { color: #ffd75e; }\n::cue(.il-conte) { color: #8fd3ff; }\n\n"));
+        assert!(vtt.contains("<v.figaro FIGARO>Cinque...\ndieci...</v>\n<c.translation>Five...\nten...</c>\n"));
+        assert!(vtt.contains("<v.il-conte IL CONTE>Ora sì"));
+        assert_eq!(parse_vtt(&vtt).unwrap().len(), 3);
+
+        // A name with no letters or digits falls back to its place in the order.
+        lib.tracks[0].segments[2].character = Some("???".to_string());
+        let vtt = export_vtt(&lib.tracks[0], Layout::Both, true, false);
+        assert!(vtt.contains("::cue(.voice-2) { color: #8fd3ff; }\n"));
+        assert!(vtt.contains("<v.voice-2 ???>Ora sì"));
+        assert!(!vtt.contains("::cue(.)"));
+    }
+
+    #[test]
+    fn test_export_ttml() {
+        let mut lib = libretto();
+        lib.tracks[0].segments[1].segment_id = Some("no-1-duettino-001".to_string());
+        let ttml = export_ttml(&lib, &lib.tracks[0], Layout::Both);
+        assert!(ttml.contains("ttp:profile=\"http://www.w3.org/ns/ttml/profile/imsc1/text\" xml:lang=\"it\""));
+        assert!(ttml.contains(
+            "<p xml:id=\"no-1-duettino-001\" begin=\"00:00:12.500\" end=\"00:01:05.250\" region=\"top\">FIGARO: Cinque...<br/>dieci...</p>\n      \
+             <p xml:id=\"no-1-duettino-001-translation\" begin=\"00:00:12.500\" end=\"00:01:05.250\" region=\"bottom\" xml:lang=\"en\">Five...<br/>ten...</p>\n"
+        ));
+        // No translation: the original alone, still at the top.
+        assert!(ttml.contains("region=\"top\">SUSANNA: Ora sì ch'io son contenta</p>"));
+        assert_eq!(ttml.matches("<p ").count(), 4);
+
+        let ttml = export_ttml(&lib, &lib.tracks[0], Layout::Translation);
+        assert!(ttml.contains("region=\"bottom\" xml:lang=\"en\">FIGARO: Five...<br/>ten...</p>"));
+        assert!(!ttml.contains("region=\"top\">"));
+    }
+
+    #[test]
+    fn test_export_ass() {
+        let mut lib = libretto();
+        lib.tracks[0].segments[1].act = Some("1".to_string());
+        lib.tracks[0].segments[2].act = Some("1".to_string());
+        lib.tracks[0].segments[2].character = Some("SUSANNA, FIGARO".to_string());
+        let config: AssConfig = serde_json::from_str(r##"{"colors": {"figaro": "#ff8000", "SUSANNA": "nope"}}"##).unwrap();
+        let ass = export_ass(&lib, &lib.tracks[0], Layout::Both, &config);
+
+        assert!(ass.contains("Title: Cinque... dieci...\n"));
+        assert!(ass.contains("Style: FIGARO,Arial,56,&H000080FF,"));
+        assert!(ass.contains("Style: SUSANNA / FIGARO,Arial,56,&H00"));
+        assert!(ass.contains("Dialogue: 0,0:00:00.00,0:00:12.50,Direction,,0,0,0,,(Figaro is measuring the room.)\n"));
+        assert!(ass.contains("Comment: 0,0:00:12.50,0:00:12.50,Default,,0,0,0,,Act 1\n"));
+        assert_eq!(ass.matches("Comment:").count(), 1);
+        assert!(ass.contains("Dialogue: 0,0:00:12.50,0:01:05.25,FIGARO,FIGARO,0,0,0,,Cinque...\\Ndieci...\n"));
+        assert!(ass.contains("Dialogue: 1,0:00:12.50,0:01:05.25,Translation,FIGARO,0,0,0,,Five...\\Nten...\n"));
+        assert!(ass.contains("Dialogue: 0,0:01:05.25,0:03:05.40,SUSANNA / FIGARO,SUSANNA / FIGARO,0,0,0,,Ora sì"));
+    }
+
+    #[test]
+    fn test_ass_colors_are_stable() {
+        let config = AssConfig::default();
+        assert_eq!(config.color("Figaro"), config.color("FIGARO"));
+        assert_eq!(parse_hex_color("#0a0B0c"), Some((10, 11, 12)));
+        assert_eq!(parse_hex_color("0a0b0c"), None);
+        assert_eq!(ass_color((0x12, 0x34, 0x56)), "&H00563412");
+    }
+}
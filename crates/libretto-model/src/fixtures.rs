@@ -0,0 +1,17 @@
+//! Builders shared by the unit tests of the format writers.
+
+use crate::base_libretto::{Segment, SegmentType};
+
+/// A base libretto segment: sung when it has text, a stage direction
+/// otherwise.
+pub(crate) fn segment(id: &str, character: Option<&str>, text: Option<&str>, translation: Option<&str>, direction: Option<&str>) -> Segment {
+    Segment {
+        id: id.to_string(),
+        segment_type: if text.is_some() { SegmentType::Sung } else { SegmentType::Direction },
+        character: character.map(str::to_string),
+        text: text.map(str::to_string),
+        translation: translation.map(str::to_string),
+        direction: direction.map(str::to_string),
+        group: None,
+    }
+}
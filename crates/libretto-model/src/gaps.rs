@@ -0,0 +1,252 @@
+// Gaps, overlaps, and coverage of interchange tracks.
+//
+// A display client shows a segment from its `start` until its `end`, or
+// until the next segment starts when it has no `end`. Wherever no segment
+// is showing, the screen is blank: between one segment's `end` and the
+// next `start`, and before the first or after the last. `analyze_track`
+// measures those moments, and the overlaps where two segments would be
+// showing at once, so a document can be checked before it reaches a
+// listener.
+
+use serde::Serialize;
+
+use crate::interchange::{InterchangeLibretto, InterchangeTrack};
+
+/// How much of one track has a segment showing.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackGaps {
+    pub track_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// Seconds during which some segment is showing, up to the track's
+    /// duration (or the last `end`, without one).
+    pub covered: f64,
+    /// `covered` as a percentage of the duration, for tracks with one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<f64>,
+    /// Blank moments between segments, in order.
+    pub gaps: Vec<Gap>,
+    /// Segments still showing when the next starts, outside ensemble groups.
+    pub overlaps: Vec<Overlap>,
+}
+
+/// A stretch between segments with nothing showing.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gap {
+    /// The segment whose `end` starts the gap, by segment ID or `#<position>`.
+    pub after: String,
+    /// The segment whose start ends it.
+    pub before: String,
+    pub start: f64,
+    pub end: f64,
+    /// The segment after the gap starts another musical number than the
+    /// one before it, so the gap holds that number's orchestral
+    /// introduction, or an instrumental number between the two.
+    #[serde(skip_serializing_if = "crate::interchange::is_false")]
+    pub between_numbers: bool,
+}
+
+/// A segment ending after the next one starts.
+#[derive(Debug, Clone, Serialize)]
+pub struct Overlap {
+    pub segment: String,
+    pub next: String,
+    /// The next segment's start.
+    pub start: f64,
+    /// The segment's `end`.
+    pub end: f64,
+}
+
+impl Gap {
+    pub fn seconds(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+impl Overlap {
+    pub fn seconds(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+impl TrackGaps {
+    /// Total seconds of the gaps between segments.
+    pub fn gap_seconds(&self) -> f64 {
+        self.gaps.iter().map(Gap::seconds).sum()
+    }
+
+    /// The longest gap between segments, if there is one.
+    pub fn longest_gap(&self) -> Option<&Gap> {
+        self.gaps.iter().max_by(|a, b| a.seconds().total_cmp(&b.seconds()))
+    }
+}
+
+/// Analyze every track of `doc`.
+pub fn analyze(doc: &InterchangeLibretto) -> Vec<TrackGaps> {
+    doc.tracks.iter().map(analyze_track).collect()
+}
+
+/// Measure the gaps, overlaps, and coverage of one track.
+///
+/// A segment without an `end` shows until the next segment to start after
+/// it, or until the end of the track; segments of one ensemble `group`
+/// starting together all show until then. Untimed segments are passed
+/// over.
+pub fn analyze_track(track: &InterchangeTrack) -> TrackGaps {
+    let label = |i: usize| match &track.segments[i].segment_id {
+        Some(id) => id.clone(),
+        None => format!("#{}", i + 1),
+    };
+    let timed: Vec<usize> = (0..track.segments.len())
+        .filter(|&i| !track.segments[i].untimed)
+        .collect();
+
+    // When each segment is showing, clipped to the track
+    let clip = |t: f64| match track.duration_seconds {
+        Some(duration) => t.max(0.0).min(duration),
+        None => t.max(0.0),
+    };
+    let mut shown: Vec<(usize, f64, f64)> = timed.iter().enumerate()
+        .map(|(k, &i)| {
+            let seg = &track.segments[i];
+            let end = seg.end
+                .or_else(|| timed[k + 1..].iter().map(|&n| track.segments[n].start).find(|&s| s > seg.start))
+                .or(track.duration_seconds)
+                .unwrap_or(seg.start);
+            (i, clip(seg.start), clip(end.max(seg.start)))
+        })
+        .collect();
+    shown.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    // Join the overlapping stretches; the holes between them are the gaps
+    let mut covered = 0.0;
+    let mut gaps = Vec::new();
+    let mut run: Option<(f64, f64, usize)> = None;
+    for (i, start, end) in shown {
+        match &mut run {
+            Some((_, run_end, last)) if start <= *run_end => {
+                if end > *run_end {
+                    *run_end = end;
+                    *last = i;
+                }
+            }
+            _ => {
+                if let Some((run_start, run_end, last)) = run {
+                    covered += run_end - run_start;
+                    let (number, next_number) = (&track.segments[last].number_id, &track.segments[i].number_id);
+                    gaps.push(Gap {
+                        after: label(last),
+                        before: label(i),
+                        start: run_end,
+                        end: start,
+                        between_numbers: number.is_some() && next_number.is_some() && number != next_number,
+                    });
+                }
+                run = Some((start, end, i));
+            }
+        }
+    }
+    if let Some((run_start, run_end, _)) = run {
+        covered += run_end - run_start;
+    }
+
+    let overlaps = timed.windows(2)
+        .filter_map(|pair| {
+            let (seg, next) = (&track.segments[pair[0]], &track.segments[pair[1]]);
+            let same_group = seg.group.is_some() && seg.group == next.group;
+            match seg.end {
+                Some(end) if end > next.start && !same_group => Some(Overlap {
+                    segment: label(pair[0]),
+                    next: label(pair[1]),
+                    start: next.start,
+                    end,
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+
+    TrackGaps {
+        track_id: track.track_id.clone(),
+        duration: track.duration_seconds,
+        covered,
+        coverage: track.duration_seconds.filter(|&d| d > 0.0).map(|d| covered * 100.0 / d),
+        gaps,
+        overlaps,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interchange::InterchangeSegment;
+
+    fn segment(id: &str, start: f64, end: Option<f64>, group: Option<&str>) -> InterchangeSegment {
+        InterchangeSegment {
+            start,
+            end,
+            segment_type: "sung".to_string(),
+            text: Some(id.to_string()),
+            group: group.map(String::from),
+            segment_id: Some(id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn track(duration: Option<f64>, segments: Vec<InterchangeSegment>) -> InterchangeTrack {
+        InterchangeTrack {
+            track_id: "act-1".to_string(),
+            title: "Act I".to_string(),
+            duration_seconds: duration,
+            segments,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_gaps_and_coverage() {
+        let mut untimed = segment("u", f64::NAN, None, None);
+        untimed.untimed = true;
+        let t = track(Some(100.0), vec![
+            segment("a", 10.0, Some(20.0), None),
+            untimed,
+            // Shows until "c" starts
+            segment("b", 30.0, None, None),
+            segment("c", 40.0, Some(50.0), None),
+            segment("d", 80.0, None, None),
+        ]);
+        let gaps = analyze_track(&t);
+        let found: Vec<_> = gaps.gaps.iter().map(|g| (g.after.as_str(), g.before.as_str(), g.start, g.end)).collect();
+        assert_eq!(found, vec![("a", "b", 20.0, 30.0), ("c", "d", 50.0, 80.0)]);
+        // a 10, b and c 20, d to the end of the track 20
+        assert_eq!(gaps.covered, 50.0);
+        assert_eq!(gaps.coverage, Some(50.0));
+        assert_eq!(gaps.gap_seconds(), 40.0);
+        assert_eq!(gaps.longest_gap().unwrap().before, "d");
+        assert!(gaps.overlaps.is_empty());
+        assert!(!gaps.gaps[1].between_numbers);
+
+        let mut t = t;
+        t.segments[3].number_id = Some("no-1".to_string());
+        t.segments[4].number_id = Some("no-2".to_string());
+        assert!(analyze_track(&t).gaps[1].between_numbers);
+    }
+
+    #[test]
+    fn test_overlaps_and_groups() {
+        let t = track(None, vec![
+            segment("a", 0.0, Some(12.0), None),
+            segment("b", 10.0, Some(20.0), Some("trio")),
+            // The ensemble shows until its last member ends
+            segment("c", 10.0, Some(25.0), Some("trio")),
+            segment("d", 30.0, Some(35.0), None),
+        ]);
+        let gaps = analyze_track(&t);
+        assert_eq!(gaps.overlaps.len(), 1);
+        assert_eq!((gaps.overlaps[0].segment.as_str(), gaps.overlaps[0].seconds()), ("a", 2.0));
+        assert_eq!(gaps.gaps.len(), 1);
+        assert_eq!((gaps.gaps[0].after.as_str(), gaps.gaps[0].start, gaps.gaps[0].end), ("c", 25.0, 30.0));
+        assert_eq!(gaps.covered, 30.0);
+        assert_eq!(gaps.coverage, None);
+    }
+}
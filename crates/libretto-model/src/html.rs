@@ -0,0 +1,241 @@
+// Printable HTML rendering of a base libretto.
+//
+// A standalone page, styles included, for printing or for reading on a
+// tablet while listening: the cast list, then each act and musical number
+// with character, original text and translation side by side. Given a
+// timing overlay, the page also marks where each track of the recording
+// begins and when each timed segment starts.
+
+use std::collections::HashMap;
+
+use crate::base_libretto::{BaseLibretto, Segment};
+use crate::export::xml_escape;
+use crate::import::track_sequence;
+use crate::timing_overlay::TimingOverlay;
+
+const STYLE: &str = "\
+body { font-family: Georgia, 'Times New Roman', serif; max-width: 60em; margin: 2em auto; padding: 0 1em; line-height: 1.4; color: #222; }
+header { text-align: center; margin-bottom: 2em; }
+h1 { margin-bottom: 0.2em; }
+.byline { font-style: italic; color: #555; }
+.cast { margin: 0 auto 2em; border-collapse: collapse; }
+.cast td { padding: 0.1em 1em; }
+h2 { text-align: center; margin-top: 2em; border-bottom: 1px solid #999; }
+h3 { margin: 1.5em 0 0.5em; font-variant: small-caps; }
+table.text { width: 100%; border-collapse: collapse; }
+table.text td { vertical-align: top; padding: 0.15em 0.5em; }
+td.time { width: 3.5em; color: #888; font-family: monospace; font-size: 0.85em; }
+td.character { width: 8em; font-variant: small-caps; font-weight: bold; }
+td.translation { color: #444; font-style: italic; }
+tr.direction td { font-style: italic; color: #666; }
+tr.track td { padding-top: 0.8em; color: #a33; font-family: sans-serif; font-size: 0.8em; font-weight: bold; }
+@media print {
+  body { margin: 0; max-width: none; }
+  section.act { break-before: page; }
+  tr { break-inside: avoid; }
+}
+";
+
+/// Render a base libretto as a standalone HTML page.
+///
+/// Acts become `<h2>` sections and numbers `<h3>` headings over a table
+/// of time, character, text and translation. A character is named only
+/// when the singer changes. The translation column is left out when the
+/// libretto has no translations. With `timing`, a marker row precedes the
+/// first segment of each track, and each segment with a time gets it in
+/// the first column (`m:ss` into its track).
+pub fn export_html(base: &BaseLibretto, timing: Option<&TimingOverlay>) -> String {
+    let opera = &base.opera;
+    let has_translation = base.numbers.iter()
+        .flat_map(|n| &n.segments)
+        .any(|s| s.translation.as_deref().is_some_and(|t| !t.trim().is_empty()));
+
+    // segment ID → track markers beginning there, and → start time
+    let mut track_starts: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut times: HashMap<&str, f64> = HashMap::new();
+    if let Some(overlay) = timing {
+        for (i, track) in overlay.track_timings.iter().enumerate() {
+            if let Some(first) = track_sequence(base, track).first() {
+                track_starts.entry(first).or_default().push(format!("{} · {}", track.track_id(i), track.track_title));
+            }
+            for st in &track.segment_times {
+                times.entry(st.segment_id.as_str()).or_insert(st.start);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    out.push_str(&format!("<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n", xml_escape(&opera.language)));
+    out.push_str(&format!("<title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n", xml_escape(&opera.title)));
+
+    out.push_str(&format!("<header>\n<h1>{}</h1>\n", xml_escape(&opera.title)));
+    let mut byline = vec![xml_escape(&opera.composer)];
+    if let Some(librettist) = &opera.librettist {
+        byline.push(format!("libretto by {}", xml_escape(librettist)));
+    }
+    if let Some(year) = opera.year {
+        byline.push(year.to_string());
+    }
+    out.push_str(&format!("<p class=\"byline\">{}</p>\n</header>\n", byline.join(" · ")));
+
+    if !base.cast.is_empty() {
+        out.push_str("<table class=\"cast\">\n");
+        for member in &base.cast {
+            let voice = member.voice_type.as_deref().map(xml_escape).unwrap_or_default();
+            out.push_str(&format!("<tr><td>{}</td><td>{}</td></tr>\n", xml_escape(&member.character), voice));
+        }
+        out.push_str("</table>\n");
+    }
+
+    let columns = if has_translation { 4 } else { 3 };
+    let mut act: Option<&str> = None;
+    for number in &base.numbers {
+        if act != Some(number.act.as_str()) {
+            if act.is_some() {
+                out.push_str("</section>\n");
+            }
+            out.push_str(&format!("<section class=\"act\">\n<h2>Act {}</h2>\n", xml_escape(&number.act)));
+            act = Some(number.act.as_str());
+        }
+        out.push_str(&format!("<h3 id=\"{}\">{}</h3>\n<table class=\"text\">\n", xml_escape(&number.id), xml_escape(&number.label)));
+        let mut singer: Option<&str> = None;
+        for seg in &number.segments {
+            for marker in track_starts.get(seg.id.as_str()).into_iter().flatten() {
+                out.push_str(&format!("<tr class=\"track\"><td colspan=\"{columns}\">▶ {}</td></tr>\n", xml_escape(marker)));
+            }
+            out.push_str(&segment_row(seg, times.get(seg.id.as_str()).copied(), &mut singer, has_translation));
+        }
+        out.push_str("</table>\n");
+    }
+    if act.is_some() {
+        out.push_str("</section>\n");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// One table row for a segment. `singer` tracks the last character named.
+fn segment_row<'a>(seg: &'a Segment, time: Option<f64>, singer: &mut Option<&'a str>, has_translation: bool) -> String {
+    let time = time.map(format_time).unwrap_or_default();
+    let Some(text) = &seg.text else {
+        let direction = seg.direction.as_deref().map(lines).unwrap_or_default();
+        *singer = None;
+        let span = if has_translation { 3 } else { 2 };
+        return format!(
+            "<tr class=\"direction\" id=\"{}\"><td class=\"time\">{time}</td><td colspan=\"{span}\">{direction}</td></tr>\n",
+            xml_escape(&seg.id)
+        );
+    };
+    let character = match seg.character.as_deref() {
+        Some(c) if *singer != Some(c) => {
+            *singer = Some(c);
+            xml_escape(c)
+        }
+        _ => String::new(),
+    };
+    let mut row = format!(
+        "<tr class=\"sung\" id=\"{}\"><td class=\"time\">{time}</td><td class=\"character\">{character}</td><td class=\"original\">{}</td>",
+        xml_escape(&seg.id),
+        lines(text)
+    );
+    if has_translation {
+        row.push_str(&format!("<td class=\"translation\">{}</td>", seg.translation.as_deref().map(lines).unwrap_or_default()));
+    }
+    row.push_str("</tr>\n");
+    row
+}
+
+/// Escape text and keep its line breaks.
+fn lines(text: &str) -> String {
+    text.lines().map(str::trim).map(xml_escape).collect::<Vec<_>>().join("<br>")
+}
+
+/// `m:ss` (`h:mm:ss` from an hour).
+fn format_time(seconds: f64) -> String {
+    let secs = seconds.max(0.0).floor() as u64;
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        h => format!("{}:{:02}:{:02}", h, (secs / 60) % 60, secs % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::segment;
+    use crate::base_libretto::*;
+    use crate::timing_overlay::*;
+
+    fn base() -> BaseLibretto {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "W. A. Mozart".to_string(),
+            librettist: Some("Lorenzo Da Ponte".to_string()),
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: Some(1786),
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "No. 1 - Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![
+                segment("no-1-duettino-001", None, None, None, Some("Figaro measures the room.")),
+                segment("no-1-duettino-002", Some("FIGARO"), Some("Cinque...\ndieci..."), Some("Five...\nten..."), None),
+                segment("no-1-duettino-003", Some("FIGARO"), Some("venti..."), Some("twenty..."), None),
+                segment("no-1-duettino-004", Some("SUSANNA"), Some("Ora sì ch'io son contenta"), None, None),
+            ],
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-10-cavatina".to_string(),
+            label: "No. 10 - Cavatina".to_string(),
+            number_type: NumberType::Cavatina,
+            act: "2".to_string(),
+            scene: None,
+            segments: vec![segment("no-10-cavatina-001", Some("LA CONTESSA"), Some("Porgi, amor"), Some("Grant, love"), None)],
+        });
+        lib
+    }
+
+    #[test]
+    fn test_export_html() {
+        let html = export_html(&base(), None);
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"it\">"));
+        assert!(html.contains("<p class=\"byline\">W. A. Mozart · libretto by Lorenzo Da Ponte · 1786</p>"));
+        assert_eq!(html.matches("<section class=\"act\">").count(), 2);
+        assert!(html.contains("<h3 id=\"no-1-duettino\">No. 1 - Duettino</h3>"));
+        assert!(html.contains("<td class=\"character\">FIGARO</td><td class=\"original\">Cinque...<br>dieci...</td><td class=\"translation\">Five...<br>ten...</td>"));
+        // FIGARO's second line is not named again.
+        assert!(html.contains("<td class=\"character\"></td><td class=\"original\">venti...</td>"));
+        assert!(html.contains("<td class=\"original\">Ora sì ch'io son contenta</td><td class=\"translation\"></td>"));
+        assert!(html.contains("<td colspan=\"3\">Figaro measures the room.</td>"));
+        assert!(!html.contains("tr class=\"track\""));
+    }
+
+    #[test]
+    fn test_export_html_with_timing() {
+        let mut overlay = TimingOverlay::new("base.libretto.json");
+        overlay.track_timings.push(TrackTiming {
+            track_title: "Cinque... dieci...".to_string(),
+            disc_number: Some(1),
+            track_number: Some(2),
+            number_ids: vec!["no-1-duettino".to_string()],
+            start_segment_id: Some("no-1-duettino-002".to_string()),
+            segment_times: vec![
+                SegmentTime { segment_id: "no-1-duettino-002".to_string(), start: 3.2, end: None, source: None },
+                SegmentTime { segment_id: "no-1-duettino-004".to_string(), start: 75.0, end: None, source: None },
+            ],
+            ..Default::default()
+        });
+
+        let html = export_html(&base(), Some(&overlay));
+        assert!(html.contains(
+            "<tr class=\"track\"><td colspan=\"4\">▶ d1-t2 · Cinque... dieci...</td></tr>\n\
+             <tr class=\"sung\" id=\"no-1-duettino-002\"><td class=\"time\">0:03</td>"
+        ));
+        assert!(html.contains("id=\"no-1-duettino-004\"><td class=\"time\">1:15</td>"));
+        assert_eq!(html.matches("tr class=\"track\"").count(), 1);
+    }
+}
@@ -0,0 +1,740 @@
+// Importing segment timings made in other tools, and exporting them back.
+//
+// The quickest way to time a track is to play it in an audio editor and
+// drop a label at each entry. Audacity exports a label track as
+// tab-separated `start<TAB>end<TAB>label` lines; each label becomes one
+// segment start in the matching overlay track. Labels naming a segment ID
+// are matched to it, and anything else (blank labels, sung words) is
+// matched to the track's segments in libretto order.
+//
+// Community LRC karaoke files and subtitle files (SRT, WebVTT) from disc
+// rips time lines of text instead. Their text is matched to the track's
+// segments, original or translation, with the same normalized matching
+// used for track title anchors, moving forward through the track.
+//
+// Exported Audacity labels carry segment IDs, so a track can be loaded
+// over its audio, nudged by eye, and imported again.
+//
+// A forced aligner is given the track's segment texts one per line and
+// returns a sync map with one timed fragment per line, in the JSON format
+// aeneas writes. Fragments are matched to segments by position.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::base_libretto::BaseLibretto;
+use crate::resolve;
+use crate::timing_overlay::{round_to_ms, SegmentTime, TimingOverlay, TimingSource, TrackTiming};
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    #[error("no track '{0}' in the timing overlay")]
+    UnknownTrack(String),
+
+    #[error("aligner returned {found} fragment(s) for {expected} line(s) of text")]
+    FragmentCount { expected: usize, found: usize },
+}
+
+/// One label from an Audacity label track. Point labels have `end == start`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Parse an exported Audacity label file.
+///
+/// Blank lines and the `\t<low freq>\t<high freq>` lines Audacity adds
+/// for spectral selections are skipped. Labels are returned sorted by start.
+pub fn parse_audacity_labels(contents: &str) -> Result<Vec<Label>, ImportError> {
+    let mut labels = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('\\') {
+            continue;
+        }
+        let mut fields = line.splitn(3, '\t');
+        let mut time = |what: &str| -> Result<f64, ImportError> {
+            let field = fields.next().unwrap_or_default().trim();
+            field.parse().map_err(|_| ImportError::Parse {
+                line: i + 1,
+                message: format!("invalid {what} time '{field}'"),
+            })
+        };
+        let start = time("start")?;
+        let end = time("end")?;
+        if start < 0.0 || end < start {
+            return Err(ImportError::Parse {
+                line: i + 1,
+                message: format!("label spans {start}s to {end}s"),
+            });
+        }
+        let text = fields.next().unwrap_or_default().trim().to_string();
+        labels.push(Label { start, end, text });
+    }
+    labels.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(labels)
+}
+
+/// Result of importing labels into one track.
+#[derive(Debug)]
+pub struct ImportResult {
+    /// The overlay with the track's segment_times replaced.
+    pub overlay: TimingOverlay,
+    /// Entries that became segment times.
+    pub matched: usize,
+    /// Entries that matched no segment and were dropped.
+    pub unmatched: usize,
+    /// Warnings encountered during import, including each unmatched entry.
+    pub warnings: Vec<String>,
+}
+
+/// Find a track by ID, returning its index.
+pub(crate) fn find_track<'a>(overlay: &'a TimingOverlay, track_id: &str) -> Result<(usize, &'a TrackTiming), ImportError> {
+    overlay.find_track(track_id)
+        .map(|i| (i, &overlay.track_timings[i]))
+        .ok_or_else(|| ImportError::UnknownTrack(track_id.to_string()))
+}
+
+/// The segment IDs of a track in libretto order: those of its `number_ids`,
+/// beginning at `start_segment_id` when it is set.
+pub(crate) fn track_sequence<'a>(base: &'a BaseLibretto, track: &TrackTiming) -> Vec<&'a str> {
+    let mut sequence: Vec<&str> = base.numbers.iter()
+        .filter(|n| track.number_ids.contains(&n.id))
+        .flat_map(|n| n.segments.iter().map(|s| s.id.as_str()))
+        .collect();
+    if let Some(first) = &track.start_segment_id {
+        if let Some(pos) = sequence.iter().position(|id| id == first) {
+            sequence.drain(..pos);
+        }
+    }
+    sequence
+}
+
+/// Copy of `overlay` with the segment times of track `index` replaced.
+pub(crate) fn with_segment_times(overlay: &TimingOverlay, index: usize, segment_times: Vec<SegmentTime>) -> TimingOverlay {
+    let mut result = overlay.clone();
+    result.track_timings[index].segment_times = segment_times;
+    result
+}
+
+/// Replace the segment times of track `track_id` with the label starts.
+///
+/// The track's segments are those of its `number_ids` in base libretto
+/// order, beginning at `start_segment_id` when it is set. A label whose
+/// text is a segment ID of the base libretto is matched to that segment
+/// and the order matching carries on after it; other labels take the next
+/// segment in order. Labels left over once the segments run out are
+/// reported and dropped.
+pub fn import_labels(
+    base: &BaseLibretto,
+    overlay: &TimingOverlay,
+    track_id: &str,
+    labels: &[Label],
+) -> Result<ImportResult, ImportError> {
+    let (index, track) = find_track(overlay, track_id)?;
+    let sequence = track_sequence(base, track);
+
+    let mut warnings = Vec::new();
+    let mut segment_times = Vec::new();
+    let mut next = 0;
+    for label in labels {
+        let segment_id = if base.find_segment(&label.text).is_some() {
+            match sequence.iter().position(|id| *id == label.text) {
+                Some(pos) => next = pos + 1,
+                None => warnings.push(format!(
+                    "Label '{}' at {}s names a segment outside track '{track_id}'",
+                    label.text, label.start
+                )),
+            }
+            label.text.clone()
+        } else if let Some(id) = sequence.get(next) {
+            next += 1;
+            id.to_string()
+        } else {
+            warnings.push(format!(
+                "Label at {}s ('{}') has no segment left to match in track '{track_id}'",
+                label.start, label.text
+            ));
+            continue;
+        };
+        segment_times.push(SegmentTime {
+            segment_id,
+            start: round_to_ms(label.start),
+            end: None,
+            source: Some(TimingSource::Tapped),
+        });
+    }
+    if next < sequence.len() {
+        warnings.push(format!(
+            "Track '{track_id}': {} segment(s) after '{}' have no label",
+            sequence.len() - next,
+            next.checked_sub(1).map_or("the start", |i| sequence[i]),
+        ));
+    }
+
+    Ok(ImportResult {
+        matched: segment_times.len(),
+        unmatched: labels.len() - segment_times.len(),
+        overlay: with_segment_times(overlay, index, segment_times),
+        warnings,
+    })
+}
+
+/// Write the segment times of track `track_id` as an Audacity label file.
+///
+/// Each segment becomes a region label named by its segment ID, running
+/// to its explicit end, the next segment's start, or the end of the track
+/// (a point label when none is known). Importing the file again matches
+/// every label by ID, so boundaries nudged in Audacity round-trip.
+pub fn export_audacity_labels(overlay: &TimingOverlay, track_id: &str) -> Result<String, ImportError> {
+    let (_, track) = find_track(overlay, track_id)?;
+    let mut out = String::new();
+    for (i, st) in track.segment_times.iter().enumerate() {
+        let end = st.end
+            .or_else(|| track.segment_times.get(i + 1).map(|next| next.start))
+            .or(track.duration_seconds)
+            .unwrap_or(st.start);
+        out.push_str(&format!("{:.6}\t{:.6}\t{}\n", st.start, end, st.segment_id));
+    }
+    Ok(out)
+}
+
+/// A timed line of text: an LRC lyric line or a subtitle cue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimedText {
+    pub start: f64,
+    /// End of a subtitle cue; LRC lines have none.
+    pub end: Option<f64>,
+    pub text: String,
+}
+
+/// Parse an LRC file into timed lines, sorted by start.
+///
+/// A line may carry several `[mm:ss.xx]` timestamps (a repeated chorus),
+/// each producing its own entry. ID tags such as `[ar:...]` are skipped,
+/// except `[offset:ms]`, which is applied to every timestamp (positive
+/// offsets make lyrics appear earlier). Enhanced LRC word timestamps
+/// (`<mm:ss.xx>`) are stripped from the text.
+pub fn parse_lrc(contents: &str) -> Result<Vec<TimedText>, ImportError> {
+    let mut offset = 0.0;
+    let mut lines = Vec::new();
+    for (i, raw) in contents.lines().enumerate() {
+        let mut rest = raw.trim();
+        let mut starts = Vec::new();
+        while let Some(tag) = rest.strip_prefix('[') {
+            let Some(close) = tag.find(']') else { break };
+            let (body, after) = (&tag[..close], &tag[close + 1..]);
+            match parse_timestamp(body) {
+                Some(t) => starts.push(t),
+                None if starts.is_empty() => {
+                    if let Some(ms) = body.strip_prefix("offset:") {
+                        offset = ms.trim().parse::<f64>().map_err(|_| ImportError::Parse {
+                            line: i + 1,
+                            message: format!("invalid offset '{}'", ms.trim()),
+                        })? / 1000.0;
+                    }
+                }
+                None => break,
+            }
+            rest = after;
+        }
+        let text = strip_word_timestamps(rest);
+        for start in starts {
+            lines.push(TimedText { start, end: None, text: text.clone() });
+        }
+    }
+    for line in &mut lines {
+        line.start = round_to_ms((line.start - offset).max(0.0));
+    }
+    lines.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(lines)
+}
+
+/// Parse `mm:ss`, `mm:ss.xx`, or `mm:ss.xxx` into seconds.
+pub(crate) fn parse_timestamp(body: &str) -> Option<f64> {
+    let (minutes, seconds) = body.split_once(':')?;
+    if minutes.is_empty() || !minutes.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds: f64 = seconds.parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    Some(minutes.parse::<f64>().ok()? * 60.0 + seconds)
+}
+
+fn strip_word_timestamps(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('<') {
+        match rest[open..].find('>') {
+            Some(close) if parse_timestamp(&rest[open + 1..open + close]).is_some() => {
+                out.push_str(&rest[..open]);
+                rest = &rest[open + close + 1..];
+            }
+            _ => {
+                out.push_str(&rest[..=open]);
+                rest = &rest[open + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Parse an SRT subtitle file into cues, sorted by start.
+pub fn parse_srt(contents: &str) -> Result<Vec<TimedText>, ImportError> {
+    parse_cues(contents, false)
+}
+
+/// Parse a WebVTT subtitle file into cues, sorted by start.
+///
+/// The `WEBVTT` header and `NOTE`, `STYLE`, and `REGION` blocks are
+/// skipped, as are cue settings after the timestamps.
+pub fn parse_vtt(contents: &str) -> Result<Vec<TimedText>, ImportError> {
+    parse_cues(contents, true)
+}
+
+/// Parse blank-line-separated cue blocks. Each block's first line holding
+/// `-->` carries the timing; anything before it is an identifier and
+/// everything after it is cue text.
+fn parse_cues(contents: &str, vtt: bool) -> Result<Vec<TimedText>, ImportError> {
+    let mut cues = Vec::new();
+    let mut block: Vec<(usize, &str)> = Vec::new();
+    let lines = contents.trim_start_matches('\u{feff}').lines().enumerate();
+    for (i, line) in lines.chain(std::iter::once((usize::MAX, ""))) {
+        if !line.trim().is_empty() {
+            block.push((i + 1, line));
+            continue;
+        }
+        let skip = vtt && block.first().is_some_and(|(_, first)| {
+            ["WEBVTT", "NOTE", "STYLE", "REGION"].iter().any(|kw| first.starts_with(kw))
+        });
+        if let (false, Some(timing)) = (skip, block.iter().position(|(_, l)| l.contains("-->"))) {
+            let (line, timing_line) = block[timing];
+            let (start, rest) = timing_line.split_once("-->").unwrap_or_default();
+            let end = rest.split_whitespace().next().unwrap_or_default();
+            let (Some(start), Some(end)) = (parse_cue_time(start.trim()), parse_cue_time(end)) else {
+                return Err(ImportError::Parse { line, message: format!("invalid cue timing '{timing_line}'") });
+            };
+            if end < start {
+                return Err(ImportError::Parse { line, message: format!("cue ends at {end}s, before its start at {start}s") });
+            }
+            let text: Vec<String> = block[timing + 1..].iter().map(|(_, l)| clean_cue_line(l)).collect();
+            cues.push(TimedText { start, end: Some(end), text: text.join(" ").trim().to_string() });
+        }
+        block.clear();
+    }
+    cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+    Ok(cues)
+}
+
+/// Parse `hh:mm:ss,mmm` (SRT) or `[hh:]mm:ss.mmm` (WebVTT) into seconds.
+fn parse_cue_time(time: &str) -> Option<f64> {
+    let time = time.replace(',', ".");
+    let parts: Vec<&str> = time.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [h, m, s] => (h.parse::<f64>().ok()?, *m, *s),
+        [m, s] => (0.0, *m, *s),
+        _ => return None,
+    };
+    let minutes: f64 = minutes.parse().ok()?;
+    let seconds: f64 = seconds.parse().ok()?;
+    Some(round_to_ms(hours * 3600.0 + minutes * 60.0 + seconds))
+}
+
+/// Strip markup (`<i>`, `{\an8}`) and dialogue dashes from a cue line.
+fn clean_cue_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut skip_until = None;
+    for c in line.chars() {
+        match (skip_until, c) {
+            (Some(close), _) if c == close => skip_until = None,
+            (Some(_), _) => {}
+            (None, '<') => skip_until = Some('>'),
+            (None, '{') => skip_until = Some('}'),
+            (None, _) => out.push(c),
+        }
+    }
+    out.trim().trim_start_matches(['-', '–']).trim().to_string()
+}
+
+/// Replace the segment times of track `track_id` from timed text.
+///
+/// Each line or cue is matched to one of the track's segments (see
+/// `import_labels`) after the last one matched, comparing it with both the
+/// original text and the translation using the normalized matching used
+/// for track title anchors; the nearest match wins, so matches follow the
+/// order of the input. A segment spanning several lines or cues takes the
+/// start of the first and the end of the last; the rest are recognized as
+/// continuations. Entries without words are skipped, and entries that
+/// match nothing are reported and dropped.
+pub fn import_timed_text(
+    base: &BaseLibretto,
+    overlay: &TimingOverlay,
+    track_id: &str,
+    entries: &[TimedText],
+) -> Result<ImportResult, ImportError> {
+    let (index, track) = find_track(overlay, track_id)?;
+    let sequence = track_sequence(base, track);
+    let position: HashMap<&str, usize> = sequence.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let in_track = |c: &resolve::SegCandidate<'_>| position.contains_key(c.segment_id);
+    let indexes = [
+        resolve::build_segment_index(base).into_iter().filter(in_track).collect::<Vec<_>>(),
+        resolve::build_translation_index(base).into_iter().filter(in_track).collect(),
+    ];
+
+    let mut warnings = Vec::new();
+    let mut segment_times: Vec<SegmentTime> = Vec::new();
+    let (mut worded, mut continuations) = (0, 0);
+    // Position in `sequence` of the segment currently being sung
+    let mut current: Option<usize> = None;
+    for entry in entries {
+        let norm = resolve::normalize_for_match(&entry.text);
+        if !norm.chars().any(char::is_alphanumeric) {
+            continue;
+        }
+        worded += 1;
+
+        let continues = current.is_some_and(|cur| indexes.iter().flatten()
+            .any(|c| position[c.segment_id] == cur && c.full_text_norm.contains(&norm)));
+        if continues {
+            continuations += 1;
+            if let (Some(last), Some(end)) = (segment_times.last_mut(), entry.end) {
+                last.end = Some(last.end.map_or(end, |e| e.max(end)));
+            }
+            continue;
+        }
+
+        let from = current.map_or(0, |cur| cur + 1);
+        let matched = indexes.iter()
+            .filter_map(|idx| {
+                let later = idx.partition_point(|c| position[c.segment_id] < from);
+                resolve::match_anchor(&entry.text, &track.number_ids, &idx[later..])
+            })
+            .map(|(id, _)| position[id.as_str()])
+            .min();
+        match matched {
+            Some(pos) => {
+                current = Some(pos);
+                segment_times.push(SegmentTime {
+                    segment_id: sequence[pos].to_string(),
+                    start: entry.start,
+                    end: entry.end,
+                    source: Some(TimingSource::Imported),
+                });
+            }
+            None => warnings.push(format!(
+                "Unmatched text at {}s in track '{track_id}': '{}'",
+                entry.start, entry.text
+            )),
+        }
+    }
+    if let Some(cur) = current.filter(|&cur| cur + 1 < sequence.len()) {
+        warnings.push(format!(
+            "Track '{track_id}': {} segment(s) after '{}' have no timed text",
+            sequence.len() - cur - 1,
+            sequence[cur]
+        ));
+    }
+
+    Ok(ImportResult {
+        matched: segment_times.len(),
+        unmatched: worded - continuations - segment_times.len(),
+        overlay: with_segment_times(overlay, index, segment_times),
+        warnings,
+    })
+}
+
+/// A track's segments as aligner input: each segment with text, in
+/// libretto order (see `import_labels`), and its text on one line.
+pub fn alignment_text(
+    base: &BaseLibretto,
+    overlay: &TimingOverlay,
+    track_id: &str,
+) -> Result<Vec<(String, String)>, ImportError> {
+    let (_, track) = find_track(overlay, track_id)?;
+    Ok(track_sequence(base, track).into_iter()
+        .filter_map(|id| {
+            let text = base.find_segment(id)?.text.as_deref()?;
+            let line = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            (!line.is_empty()).then(|| (id.to_string(), line))
+        })
+        .collect())
+}
+
+/// One fragment of a forced aligner's sync map, in seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignedFragment {
+    pub start: f64,
+    pub end: f64,
+    /// The aligner's confidence in the fragment, from 0 to 1, if it reports one.
+    pub confidence: Option<f64>,
+}
+
+/// Parse an aligner's JSON sync map.
+///
+/// The format is aeneas's: a `fragments` array whose entries have `begin`
+/// and `end` times, as strings or numbers. Aligners that score their
+/// output add a `confidence` to each fragment.
+pub fn parse_sync_map(contents: &str) -> Result<Vec<AlignedFragment>, ImportError> {
+    let invalid = |message: String| ImportError::Parse { line: 1, message };
+    let map: serde_json::Value = serde_json::from_str(contents).map_err(|e| ImportError::Parse {
+        line: e.line(),
+        message: e.to_string(),
+    })?;
+    let fragments = map.get("fragments")
+        .and_then(|f| f.as_array())
+        .ok_or_else(|| invalid("no 'fragments' array".to_string()))?;
+
+    let seconds = |value: Option<&serde_json::Value>| match value {
+        Some(serde_json::Value::String(s)) => s.trim().parse::<f64>().ok(),
+        Some(v) => v.as_f64(),
+        None => None,
+    };
+    fragments.iter()
+        .enumerate()
+        .map(|(i, fragment)| {
+            let time = |key: &str| seconds(fragment.get(key))
+                .filter(|t| t.is_finite() && *t >= 0.0)
+                .ok_or_else(|| invalid(format!("fragment {}: invalid '{key}' time", i + 1)));
+            Ok(AlignedFragment {
+                start: time("begin")?,
+                end: time("end")?,
+                confidence: seconds(fragment.get("confidence")),
+            })
+        })
+        .collect()
+}
+
+/// Replace the segment times of track `track_id` with aligned fragments.
+///
+/// `segment_ids` are the segments whose text was given to the aligner, in
+/// the same order (see `alignment_text`). Fragments the aligner scored
+/// below `min_confidence` are kept but reported for review.
+pub fn import_alignment(
+    overlay: &TimingOverlay,
+    track_id: &str,
+    segment_ids: &[String],
+    fragments: &[AlignedFragment],
+    min_confidence: f64,
+) -> Result<ImportResult, ImportError> {
+    let (index, _) = find_track(overlay, track_id)?;
+    if fragments.len() != segment_ids.len() {
+        return Err(ImportError::FragmentCount { expected: segment_ids.len(), found: fragments.len() });
+    }
+
+    let mut warnings = Vec::new();
+    let segment_times = segment_ids.iter()
+        .zip(fragments)
+        .map(|(segment_id, fragment)| {
+            if let Some(confidence) = fragment.confidence.filter(|&c| c < min_confidence) {
+                warnings.push(format!(
+                    "Low alignment confidence {confidence:.2} for '{segment_id}' at {}s in track '{track_id}'",
+                    round_to_ms(fragment.start)
+                ));
+            }
+            SegmentTime {
+                segment_id: segment_id.clone(),
+                start: round_to_ms(fragment.start),
+                end: Some(round_to_ms(fragment.end)),
+                source: Some(TimingSource::Aligned),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(ImportResult {
+        matched: segment_times.len(),
+        unmatched: 0,
+        overlay: with_segment_times(overlay, index, segment_times),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> (BaseLibretto, TimingOverlay) {
+        let base: BaseLibretto = serde_json::from_str(r#"{
+            "version": "1.0",
+            "opera": { "title": "T", "composer": "C", "language": "it" },
+            "cast": [],
+            "numbers": [
+                { "id": "no-1", "label": "1", "number_type": "aria", "act": "1", "segments": [
+                    { "id": "no-1-001", "text": "Cinque, dieci, venti" },
+                    { "id": "no-1-002", "text": "Ora sì ch'io son contenta\nsembra fatto inver per me" }
+                ] },
+                { "id": "no-2", "label": "2", "number_type": "aria", "act": "1", "segments": [
+                    { "id": "no-2-001", "text": "Cosa stai misurando" },
+                    { "id": "no-2-002", "text": "Io guardo se quel letto" }
+                ] }
+            ]
+        }"#).unwrap();
+        let overlay: TimingOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [
+                { "track_title": "One", "disc_number": 1, "track_number": 1, "number_ids": ["no-1"] },
+                { "track_title": "Two", "disc_number": 1, "track_number": 2, "number_ids": ["no-1", "no-2"],
+                  "start_segment_id": "no-1-002" }
+            ]
+        }"#).unwrap();
+        (base, overlay)
+    }
+
+    #[test]
+    fn test_parse_audacity_labels() {
+        let labels = parse_audacity_labels(
+            "12.5\t12.5\tno-1-002\n\\\t200.0\t4000.0\n3.000000\t4.250000\t\n\n",
+        ).unwrap();
+        assert_eq!(labels, vec![
+            Label { start: 3.0, end: 4.25, text: String::new() },
+            Label { start: 12.5, end: 12.5, text: "no-1-002".to_string() },
+        ]);
+
+        let err = parse_audacity_labels("1.0\t1.0\ta\nx\t2.0\tb\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: invalid start time 'x'");
+        assert!(parse_audacity_labels("5.0\t4.0\tc\n").is_err());
+    }
+
+    #[test]
+    fn test_import_labels() {
+        let (base, overlay) = fixtures();
+        let labels = parse_audacity_labels("0.0\t0.0\t\n4.0\t4.0\tno-2-002\n9.0\t9.0\tspare\n").unwrap();
+        let result = import_labels(&base, &overlay, "d1-t2", &labels).unwrap();
+        let times: Vec<_> = result.overlay.track_timings[1].segment_times.iter()
+            .map(|st| (st.segment_id.as_str(), st.start))
+            .collect();
+        // Order matching starts at the track's start segment and resumes after an ID label
+        assert_eq!(times, vec![("no-1-002", 0.0), ("no-2-002", 4.0)]);
+        assert_eq!((result.matched, result.unmatched), (2, 1));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("spare"));
+
+        assert!(matches!(
+            import_labels(&base, &overlay, "d2-t1", &labels),
+            Err(ImportError::UnknownTrack(_))
+        ));
+    }
+
+    #[test]
+    fn test_export_audacity_labels_round_trip() {
+        let (base, overlay) = fixtures();
+        let labels = parse_audacity_labels("0.0\t0.0\t\n4.0\t4.0\t\n7.5\t7.5\t\n").unwrap();
+        let mut overlay = import_labels(&base, &overlay, "d1-t2", &labels).unwrap().overlay;
+        overlay.track_timings[1].duration_seconds = Some(10.0);
+
+        let exported = export_audacity_labels(&overlay, "d1-t2").unwrap();
+        assert_eq!(
+            exported,
+            "0.000000\t4.000000\tno-1-002\n4.000000\t7.500000\tno-2-001\n7.500000\t10.000000\tno-2-002\n"
+        );
+        let reimported = import_labels(&base, &overlay, "d1-t2", &parse_audacity_labels(&exported).unwrap()).unwrap();
+        assert_eq!(reimported.overlay.track_timings[1].segment_times.len(), 3);
+        assert!(reimported.warnings.is_empty());
+        assert!(export_audacity_labels(&overlay, "d9-t9").is_err());
+    }
+
+    #[test]
+    fn test_parse_lrc() {
+        let lines = parse_lrc(
+            "[ti:Le nozze di Figaro]\n[offset:500]\n[00:10.50][01:00.00]Ora <00:11.00>sì\n[00:02.00]\nno tag\n",
+        ).unwrap();
+        assert_eq!(lines, vec![
+            TimedText { start: 1.5, end: None, text: String::new() },
+            TimedText { start: 10.0, end: None, text: "Ora sì".to_string() },
+            TimedText { start: 59.5, end: None, text: "Ora sì".to_string() },
+        ]);
+        assert!(parse_lrc("[offset:soon]\n").is_err());
+    }
+
+    #[test]
+    fn test_import_lrc() {
+        let (base, overlay) = fixtures();
+        let lines = parse_lrc(
+            "[00:00.00]\n[00:01.00]Ora si ch'io son contenta\n[00:04.00]Sembra fatto inver per me\n\
+             [00:09.00]La la la\n[00:12.00]Cosa stai misurando?\n",
+        ).unwrap();
+        let result = import_timed_text(&base, &overlay, "d1-t2", &lines).unwrap();
+        let times: Vec<_> = result.overlay.track_timings[1].segment_times.iter()
+            .map(|st| (st.segment_id.as_str(), st.start))
+            .collect();
+        // The second line continues no-1-002; unaccented text still matches
+        assert_eq!(times, vec![("no-1-002", 1.0), ("no-2-001", 12.0)]);
+        assert_eq!((result.matched, result.unmatched), (2, 1));
+        assert!(result.warnings.iter().any(|w| w.contains("La la la")));
+        assert!(result.warnings.iter().any(|w| w.contains("1 segment(s) after 'no-2-001'")));
+    }
+
+    #[test]
+    fn test_parse_subtitles() {
+        let srt = "1\n00:00:01,000 --> 00:00:03,500\n<i>Ora sì</i>\n- ch'io son contenta\n\n\
+                   2\n00:01:02,250 --> 00:01:04,000\n{\\an8}Cosa stai misurando?\n";
+        let cues = parse_srt(srt).unwrap();
+        assert_eq!(cues, vec![
+            TimedText { start: 1.0, end: Some(3.5), text: "Ora sì ch'io son contenta".to_string() },
+            TimedText { start: 62.25, end: Some(64.0), text: "Cosa stai misurando?".to_string() },
+        ]);
+        assert!(parse_srt("1\n00:00:05,000 --> 00:00:04,000\nx\n").is_err());
+
+        let vtt = "WEBVTT\n\nNOTE made by hand\n--> not a cue\n\n\
+                   intro\n00:01.000 --> 00:02.500 align:start\nOra sì\n";
+        let cues = parse_vtt(vtt).unwrap();
+        assert_eq!(cues, vec![TimedText { start: 1.0, end: Some(2.5), text: "Ora sì".to_string() }]);
+    }
+
+    #[test]
+    fn test_import_subtitles_by_translation() {
+        let (mut base, overlay) = fixtures();
+        base.numbers[1].segments[0].translation = Some("What are you measuring?".to_string());
+        let cues = parse_srt(
+            "1\n00:00:01,000 --> 00:00:02,000\nOra sì ch'io son contenta\n\n\
+             2\n00:00:02,500 --> 00:00:04,000\nsembra fatto inver per me\n\n\
+             3\n00:00:05,000 --> 00:00:06,000\n♪\n\n\
+             4\n00:00:08,000 --> 00:00:09,000\nWhat are you measuring?\n",
+        ).unwrap();
+        let result = import_timed_text(&base, &overlay, "d1-t2", &cues).unwrap();
+        let times: Vec<_> = result.overlay.track_timings[1].segment_times.iter()
+            .map(|st| (st.segment_id.as_str(), st.start, st.end))
+            .collect();
+        // The continuation cue extends no-1-002's end; the last cue matches the translation
+        assert_eq!(times, vec![("no-1-002", 1.0, Some(4.0)), ("no-2-001", 8.0, Some(9.0))]);
+        assert_eq!((result.matched, result.unmatched), (2, 0));
+    }
+
+    #[test]
+    fn test_import_alignment() {
+        let (base, overlay) = fixtures();
+        let lines = alignment_text(&base, &overlay, "d1-t2").unwrap();
+        assert_eq!(lines[0], ("no-1-002".to_string(), "Ora sì ch'io son contenta sembra fatto inver per me".to_string()));
+        assert_eq!(lines.len(), 3);
+
+        let fragments = parse_sync_map(r#"{ "fragments": [
+            { "begin": "0.000", "end": "4.120", "id": "f000001", "lines": ["Ora sì"] },
+            { "begin": "4.120", "end": "6.5", "confidence": 0.31 },
+            { "begin": 6.5, "end": 9.0, "confidence": 0.93 }
+        ] }"#).unwrap();
+        assert_eq!(fragments[1], AlignedFragment { start: 4.12, end: 6.5, confidence: Some(0.31) });
+
+        let ids: Vec<String> = lines.into_iter().map(|(id, _)| id).collect();
+        let result = import_alignment(&overlay, "d1-t2", &ids, &fragments, 0.5).unwrap();
+        let times = &result.overlay.track_timings[1].segment_times;
+        assert_eq!(times[2].segment_id, "no-2-002");
+        assert_eq!((times[2].start, times[2].end), (6.5, Some(9.0)));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("no-2-001"));
+
+        assert!(matches!(
+            import_alignment(&overlay, "d1-t2", &ids, &fragments[..2], 0.5),
+            Err(ImportError::FragmentCount { expected: 3, found: 2 })
+        ));
+        assert!(parse_sync_map(r#"{ "fragments": [{ "begin": "x", "end": "1" }] }"#).is_err());
+    }
+}
@@ -1,4 +1,8 @@
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::annotation_overlay::Annotation;
+use crate::pronunciation_overlay::PronunciationNotation;
+use crate::rights::Rights;
 
 /// The full interchange format: a timed libretto for a complete opera recording.
 ///
@@ -7,15 +11,40 @@ use serde::{Deserialize, Serialize};
 /// into a single self-contained document.
 ///
 /// See INTERCHANGE_FORMAT.md for the full specification.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InterchangeLibretto {
     pub version: String,
     pub opera: InterchangeOpera,
+    /// Licensing for the libretto text, carried from the base libretto.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_rights: Option<Rights>,
+    /// Licensing for the timing data, carried from the timing overlay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timing_rights: Option<Rights>,
     pub tracks: Vec<InterchangeTrack>,
+    /// Commentary merged from an annotation overlay, keyed by the
+    /// `segment_id` / `number_id` carried on segments.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+    /// How segments' `pronunciation` is written, carried from the
+    /// pronunciation overlay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation_notation: Option<PronunciationNotation>,
+    /// Licensing for the pronunciations, carried from the pronunciation
+    /// overlay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation_rights: Option<Rights>,
+    /// Language of the `"synopsis"` segments (ISO 639-1), carried from the
+    /// synopsis overlay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synopsis_language: Option<String>,
+    /// Licensing for the synopsis text, carried from the synopsis overlay.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub synopsis_rights: Option<Rights>,
 }
 
 /// Opera metadata in the interchange format.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InterchangeOpera {
     pub title: String,
     pub composer: String,
@@ -29,7 +58,7 @@ pub struct InterchangeOpera {
 }
 
 /// A track in the interchange format, containing timed segments.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct InterchangeTrack {
     pub track_id: String,
     pub title: String,
@@ -43,6 +72,10 @@ pub struct InterchangeTrack {
     pub track_number: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration_seconds: Option<f64>,
+    /// Seconds from the start of the album (the whole set, across discs)
+    /// at which this track begins. Set by `timeline::apply_absolute_times`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album_offset: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub act: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -52,7 +85,11 @@ pub struct InterchangeTrack {
 
 /// A timed text segment in the interchange format.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct InterchangeSegment {
+    /// Start time in seconds from the start of the track; `null` in JSON
+    /// (NaN here) for an untimed segment, and only for one.
+    #[serde(deserialize_with = "start_or_untimed")]
     pub start: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end: Option<f64>,
@@ -65,6 +102,10 @@ pub struct InterchangeSegment {
     pub text: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub translation: Option<String>,
+    /// How `text` is pronounced, merged from a pronunciation overlay: one
+    /// line per line of `text` when given line by line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pronunciation: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub direction: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -75,17 +116,180 @@ pub struct InterchangeSegment {
     /// sung simultaneously and should be displayed together.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub group: Option<String>,
+    /// Segment ID in the base libretto this segment was merged from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub segment_id: Option<String>,
+    /// Musical number ID in the base libretto this segment belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number_id: Option<String>,
+    /// Start time in seconds from the start of the album, i.e. `start`
+    /// plus the track's `album_offset`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub album_start: Option<f64>,
+    /// A base segment the recording performs but the overlay has no time
+    /// for yet, placed among the timed segments for context (`timing merge
+    /// --include-untimed`). It has no start or end.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub untimed: bool,
 }
 
-fn default_type() -> String {
+/// A sung segment at the start of its track, with nothing else set.
+impl Default for InterchangeSegment {
+    fn default() -> Self {
+        InterchangeSegment {
+            start: 0.0,
+            end: None,
+            segment_type: default_type(),
+            character: None,
+            text: None,
+            translation: None,
+            pronunciation: None,
+            direction: None,
+            act: None,
+            scene: None,
+            group: None,
+            segment_id: None,
+            number_id: None,
+            album_start: None,
+            untimed: false,
+        }
+    }
+}
+
+impl Serialize for InterchangeSegment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        InterchangeSegment::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InterchangeSegment {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let segment = InterchangeSegment::deserialize(deserializer)?;
+        check_untimed(segment.start, segment.untimed)?;
+        Ok(segment)
+    }
+}
+
+pub(crate) fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+/// A start time, or NaN for the `null` start of an untimed segment.
+pub(crate) fn start_or_untimed<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+/// Reject a `null` start on a segment not marked `untimed`, which would
+/// otherwise pass for a time and sort nowhere.
+pub(crate) fn check_untimed<E: de::Error>(start: f64, untimed: bool) -> Result<(), E> {
+    if start.is_nan() && !untimed {
+        return Err(E::custom("segment has a null `start` but is not marked `untimed`"));
+    }
+    Ok(())
+}
+
+pub(crate) fn default_type() -> String {
     "sung".to_string()
 }
 
-fn is_default_type(s: &str) -> bool {
+pub(crate) fn is_default_type(s: &str) -> bool {
     s == "sung"
 }
 
+impl InterchangeLibretto {
+    /// Remove the untimed segments `timing merge --include-untimed` adds,
+    /// for outputs that need a time for every segment. Returns how many
+    /// were removed.
+    pub fn drop_untimed(&mut self) -> usize {
+        let before: usize = self.tracks.iter().map(|t| t.segments.len()).sum();
+        for track in &mut self.tracks {
+            track.segments.retain(|s| !s.untimed);
+        }
+        before - self.tracks.iter().map(|t| t.segments.len()).sum::<usize>()
+    }
+
+    /// A document holding only the track at `index`, with the annotations
+    /// on its segments and numbers, for players that load the libretto of
+    /// the track being played rather than of the whole opera.
+    pub fn single_track(&self, index: usize) -> InterchangeLibretto {
+        self.with_tracks(&[index])
+    }
+
+    /// One document per act, in the order the acts first appear, for
+    /// clients and reviewers that want act-sized documents. A track with no
+    /// act of its own (an instrumental interlude) goes with the act before
+    /// it, or with the first act when it opens the recording.
+    pub fn split_by_act(&self) -> Vec<(String, InterchangeLibretto)> {
+        let mut acts: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut leading = Vec::new();
+        let mut current: Option<usize> = None;
+        for (i, track) in self.tracks.iter().enumerate() {
+            if let Some(act) = &track.act {
+                let index = match acts.iter().position(|(a, _)| a == act) {
+                    Some(index) => index,
+                    None => {
+                        acts.push((act.clone(), std::mem::take(&mut leading)));
+                        acts.len() - 1
+                    }
+                };
+                current = Some(index);
+            }
+            match current {
+                Some(index) => acts[index].1.push(i),
+                None => leading.push(i),
+            }
+        }
+        if acts.is_empty() && !leading.is_empty() {
+            return vec![(String::new(), self.with_tracks(&leading))];
+        }
+        acts.into_iter()
+            .map(|(act, indices)| (act, self.with_tracks(&indices)))
+            .collect()
+    }
+
+    /// A document holding the tracks at `indices` and the annotations on
+    /// their segments and numbers.
+    fn with_tracks(&self, indices: &[usize]) -> InterchangeLibretto {
+        let tracks: Vec<InterchangeTrack> = indices.iter().map(|&i| self.tracks[i].clone()).collect();
+        let annotations = self.annotations.iter()
+            .filter(|a| {
+                tracks.iter().flat_map(|t| &t.segments).any(|s| {
+                    (a.segment_id.is_some() && a.segment_id == s.segment_id)
+                        || (a.segment_id.is_none() && a.number_id.is_some() && a.number_id == s.number_id)
+                })
+            })
+            .cloned()
+            .collect();
+        InterchangeLibretto {
+            version: self.version.clone(),
+            opera: self.opera.clone(),
+            text_rights: self.text_rights.clone(),
+            timing_rights: self.timing_rights.clone(),
+            tracks,
+            annotations,
+            pronunciation_notation: self.pronunciation_notation,
+            pronunciation_rights: self.pronunciation_rights.clone(),
+            synopsis_language: self.synopsis_language.clone(),
+            synopsis_rights: self.synopsis_rights.clone(),
+        }
+    }
+}
+
 impl InterchangeTrack {
+    /// File name for this track's own interchange document, from its disc
+    /// and track numbers (`d1-t02.segments.json`, or `t02.segments.json`
+    /// without a disc), falling back to the track ID made into a slug.
+    pub fn segments_file_name(&self) -> String {
+        match (self.disc_number, self.track_number) {
+            (Some(d), Some(t)) => format!("d{d}-t{t:02}.segments.json"),
+            (None, Some(t)) => format!("t{t:02}.segments.json"),
+            _ => match crate::export::slug(&self.track_id) {
+                id if id.is_empty() => "track.segments.json".to_string(),
+                id => format!("{id}.segments.json"),
+            },
+        }
+    }
+
     /// Find the active segment at the given playback time (seconds).
     ///
     /// Returns the last segment whose `start` is <= the given time.
@@ -93,7 +297,90 @@ impl InterchangeTrack {
         self.segments
             .iter()
             .rev()
-            .find(|s| s.start <= time)
+            .find(|s| !s.untimed && s.start <= time)
+    }
+
+    /// Find the segment that follows the one active at the given time:
+    /// the first whose `start` is after it.
+    pub fn next_segment(&self, time: f64) -> Option<&InterchangeSegment> {
+        self.segments.iter().find(|s| !s.untimed && s.start > time)
+    }
+
+    /// A cursor for following playback of this track.
+    pub fn cursor(&self) -> SegmentCursor<'_> {
+        SegmentCursor::new(self)
+    }
+}
+
+/// Follows playback through a track's segments, for clients that ask for
+/// the segment playing many times a second.
+///
+/// `segment_at` scans the track on every call. A cursor remembers where it
+/// was: asked for a time a little after the last, it steps to the next
+/// segment at most, and only a jump (a seek, or scrubbing backwards) costs
+/// a binary search. Unlike `segment_at` it honours `end`, so in a gap
+/// between one segment's end and the next's start nothing is playing.
+/// Untimed segments are passed over. Segments must be in `start` order, as
+/// validation requires.
+#[derive(Debug, Clone)]
+pub struct SegmentCursor<'a> {
+    track: &'a InterchangeTrack,
+    /// Indices of the timed segments.
+    timed: Vec<usize>,
+    /// Position in `timed` of the last segment starting at or before
+    /// `time`, if any does.
+    position: Option<usize>,
+    time: f64,
+}
+
+impl<'a> SegmentCursor<'a> {
+    pub fn new(track: &'a InterchangeTrack) -> Self {
+        let timed = (0..track.segments.len()).filter(|&i| !track.segments[i].untimed).collect();
+        SegmentCursor { track, timed, position: None, time: f64::NEG_INFINITY }
+    }
+
+    /// The segment playing at `time` (seconds from the start of the track).
+    pub fn at(&mut self, time: f64) -> Option<&'a InterchangeSegment> {
+        let next = self.position.map_or(0, |p| p + 1);
+        if time >= self.time && self.start(next).is_none_or(|start| start > time) {
+            // Still within the same segment
+        } else if time >= self.time && self.start(next + 1).is_none_or(|start| start > time) {
+            self.position = Some(next);
+        } else {
+            return self.seek(time);
+        }
+        self.time = time;
+        self.current()
+    }
+
+    /// The segment playing at `time`, found by binary search wherever the
+    /// cursor was.
+    pub fn seek(&mut self, time: f64) -> Option<&'a InterchangeSegment> {
+        let starting = self.timed.partition_point(|&i| self.track.segments[i].start <= time);
+        self.position = starting.checked_sub(1);
+        self.time = time;
+        self.current()
+    }
+
+    /// The segment playing at the time last asked for: none before the
+    /// first segment starts, or after the latest one's `end`.
+    pub fn current(&self) -> Option<&'a InterchangeSegment> {
+        let segment = &self.track.segments[self.timed[self.position?]];
+        match segment.end {
+            Some(end) if self.time >= end => None,
+            _ => Some(segment),
+        }
+    }
+
+    /// The next segment to start after the time last asked for, to load
+    /// (or lay out its translation) before it is needed.
+    pub fn peek_next(&self) -> Option<&'a InterchangeSegment> {
+        let next = self.position.map_or(0, |p| p + 1);
+        self.timed.get(next).map(|&i| &self.track.segments[i])
+    }
+
+    fn start(&self, position: usize) -> Option<f64> {
+        self.timed.get(position).map(|&i| self.track.segments[i].start)
     }
 }
 
@@ -106,39 +393,24 @@ mod tests {
         let track = InterchangeTrack {
             track_id: "act-1".to_string(),
             title: "Act I".to_string(),
-            album: None,
-            artist: None,
-            disc_number: None,
-            track_number: None,
             duration_seconds: Some(100.0),
-            act: None,
-            scene: None,
             segments: vec![
                 InterchangeSegment {
-                    start: 0.0,
                     end: Some(10.0),
                     segment_type: "interlude".to_string(),
-                    character: None,
-                    text: None,
-                    translation: None,
                     direction: Some("Overture begins.".to_string()),
-                    act: None,
-                    scene: None,
-                    group: None,
+                    ..Default::default()
                 },
                 InterchangeSegment {
                     start: 10.0,
                     end: Some(25.0),
-                    segment_type: "sung".to_string(),
                     character: Some("FIGARO".to_string()),
                     text: Some("Cinque... dieci...".to_string()),
                     translation: Some("Five... ten...".to_string()),
-                    direction: None,
-                    act: None,
-                    scene: None,
-                    group: None,
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         assert!(track.segment_at(-1.0).is_none());
@@ -148,6 +420,151 @@ mod tests {
 
         let seg = track.segment_at(15.0).unwrap();
         assert_eq!(seg.character.as_deref(), Some("FIGARO"));
+
+        assert_eq!(track.next_segment(5.0).unwrap().start, 10.0);
+        assert!(track.next_segment(15.0).is_none());
+    }
+
+    fn cursor_track() -> InterchangeTrack {
+        let segment = |start: f64, end: Option<f64>, text: &str| InterchangeSegment {
+            start,
+            end,
+            text: Some(text.to_string()),
+            ..Default::default()
+        };
+        let mut untimed = segment(f64::NAN, None, "untimed");
+        untimed.untimed = true;
+        InterchangeTrack {
+            track_id: "d1-t2".to_string(),
+            title: "Cinque... dieci...".to_string(),
+            disc_number: Some(1),
+            track_number: Some(2),
+            duration_seconds: Some(60.0),
+            segments: vec![
+                segment(2.0, Some(10.0), "a"),
+                untimed,
+                segment(10.0, Some(15.0), "b"),
+                // A gap from 15 to 20
+                segment(20.0, None, "c"),
+                segment(30.0, Some(60.0), "d"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn text(segment: Option<&InterchangeSegment>) -> Option<&str> {
+        segment.and_then(|s| s.text.as_deref())
+    }
+
+    #[test]
+    fn test_cursor_polling() {
+        let track = cursor_track();
+        let mut cursor = track.cursor();
+        let playing: Vec<Option<&str>> = (0..=650)
+            .map(|tenths| text(cursor.at(tenths as f64 / 10.0)))
+            .collect();
+        for (tenths, playing) in playing.iter().enumerate() {
+            let time = tenths as f64 / 10.0;
+            let expected = match time {
+                t if t < 2.0 => None,
+                t if t < 10.0 => Some("a"),
+                t if t < 15.0 => Some("b"),
+                t if t < 20.0 => None,
+                t if t < 30.0 => Some("c"),
+                t if t < 60.0 => Some("d"),
+                _ => None,
+            };
+            assert_eq!(*playing, expected, "at {time}s");
+        }
+    }
+
+    #[test]
+    fn test_cursor_seek_and_scrub() {
+        let track = cursor_track();
+        let mut cursor = track.cursor();
+        assert_eq!(text(cursor.peek_next()), Some("a"));
+        assert_eq!(text(cursor.at(35.0)), Some("d"));
+        assert!(cursor.peek_next().is_none());
+        // Scrubbing back into a gap, then into an earlier segment
+        assert_eq!(text(cursor.at(17.0)), None);
+        assert_eq!(text(cursor.peek_next()), Some("c"));
+        assert_eq!(text(cursor.at(3.0)), Some("a"));
+        assert_eq!(text(cursor.peek_next()), Some("b"));
+        assert_eq!(text(cursor.at(0.0)), None);
+        // A jump forward over several segments
+        assert_eq!(text(cursor.at(25.0)), Some("c"));
+        assert_eq!(text(cursor.seek(12.0)), Some("b"));
+        assert_eq!(text(cursor.current()), Some("b"));
+        // Agrees with segment_at wherever a segment has no end
+        assert_eq!(text(cursor.at(29.9)), text(track.segment_at(29.9)));
+    }
+
+    #[test]
+    fn test_single_track() {
+        let track = |disc: Option<u32>, number: u32, segment_id: &str, number_id: &str| InterchangeTrack {
+            track_id: format!("t{number}"),
+            disc_number: disc,
+            track_number: Some(number),
+            segments: vec![InterchangeSegment {
+                text: Some("Cinque...".to_string()),
+                segment_id: Some(segment_id.to_string()),
+                number_id: Some(number_id.to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let annotation = |segment_id: Option<&str>, number_id: Option<&str>| Annotation {
+            segment_id: segment_id.map(str::to_string),
+            number_id: number_id.map(str::to_string),
+            kind: Default::default(),
+            title: None,
+            text: "Note".to_string(),
+            author: None,
+        };
+        let libretto = InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "W. A. Mozart".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks: vec![track(Some(1), 2, "no-1-001", "no-1"), track(None, 3, "no-2-001", "no-2")],
+            annotations: vec![
+                annotation(Some("no-1-001"), Some("no-1")),
+                annotation(None, Some("no-1")),
+                annotation(None, Some("no-2")),
+            ],
+            ..Default::default()
+        };
+
+        let first = libretto.single_track(0);
+        assert_eq!(first.tracks.len(), 1);
+        assert_eq!(first.tracks[0].segments_file_name(), "d1-t02.segments.json");
+        assert_eq!(first.annotations.len(), 2);
+        let second = libretto.single_track(1);
+        assert_eq!(second.tracks[0].segments_file_name(), "t03.segments.json");
+        assert_eq!(second.annotations.len(), 1);
+
+        let mut untagged = libretto.tracks[0].clone();
+        untagged.track_number = None;
+        untagged.track_id = "../Act 1/Sinfonia".to_string();
+        assert_eq!(untagged.segments_file_name(), "act-1-sinfonia.segments.json");
+
+        // Tracks without an act go with the act before them, or the first
+        let mut libretto = libretto;
+        let mut tracks = vec![track(Some(1), 1, "overture-001", "overture")];
+        for (number, act) in [(2, Some("1")), (3, None), (4, Some("2")), (5, Some("1"))] {
+            let mut t = track(Some(1), number, &format!("no-{number}-001"), &format!("no-{number}"));
+            t.act = act.map(str::to_string);
+            tracks.push(t);
+        }
+        libretto.tracks = tracks;
+        let acts = libretto.split_by_act();
+        let numbers: Vec<(&str, Vec<u32>)> = acts.iter()
+            .map(|(act, doc)| (act.as_str(), doc.tracks.iter().filter_map(|t| t.track_number).collect()))
+            .collect();
+        assert_eq!(numbers, vec![("1", vec![1, 2, 3, 5]), ("2", vec![4])]);
     }
 
     #[test]
@@ -157,15 +574,22 @@ mod tests {
             opera: InterchangeOpera {
                 title: "Tosca".to_string(),
                 composer: "Giacomo Puccini".to_string(),
-                librettist: None,
                 language: "it".to_string(),
                 translation_language: Some("en".to_string()),
-                year: None,
+                ..Default::default()
             },
-            tracks: vec![],
+            ..Default::default()
         };
         let json = serde_json::to_string_pretty(&libretto).unwrap();
         let parsed: InterchangeLibretto = serde_json::from_str(&json).unwrap();
         assert_eq!(parsed.opera.title, "Tosca");
     }
+
+    #[test]
+    fn test_null_start_requires_untimed() {
+        let untimed: InterchangeSegment = serde_json::from_str(r#"{ "start": null, "untimed": true }"#).unwrap();
+        assert!(untimed.start.is_nan());
+        let err = serde_json::from_str::<InterchangeSegment>(r#"{ "start": null, "text": "Cinque" }"#).unwrap_err();
+        assert!(err.to_string().contains("not marked `untimed`"), "{err}");
+    }
 }
@@ -0,0 +1,319 @@
+// Loading model documents from JSON with located error messages.
+//
+// Overlays are frequently edited by hand, and a bare serde error ("invalid
+// type: string, expected f64 at line 812 column 19") is hard to act on.
+// Errors from these helpers name the file and the JSON path of the field,
+// e.g. `ricci.timing.json: track_timings[12].segment_times[3].start: ...`.
+//
+// Loading is all or nothing: one malformed track and the overlay is not
+// loaded at all. To triage a large hand-edited file, the `_lenient`
+// variants leave out each list entry that fails to load instead (the
+// innermost object: a bad segment time, not its whole track, but the
+// whole track for a bad number ID) and return the rest of the document
+// with the reason each entry was left out.
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{fs, path::Path};
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use serde_path_to_error::Segment;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("{file}: {source}")]
+    Io {
+        file: String,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("{file}: {}{message}", located(path))]
+    Parse {
+        file: String,
+        /// JSON path of the failing field (`.` for the document root).
+        path: String,
+        message: String,
+    },
+}
+
+fn located(path: &str) -> String {
+    if path == "." { String::new() } else { format!("{path}: ") }
+}
+
+/// Deserialize a JSON string, reporting the JSON path of any failure.
+///
+/// `file` is only used to label the error.
+pub fn from_json_str<T: DeserializeOwned>(contents: &str, file: &str) -> Result<T, LoadError> {
+    let de = &mut serde_json::Deserializer::from_str(contents);
+    serde_path_to_error::deserialize(de).map_err(|e| LoadError::Parse {
+        file: file.to_string(),
+        path: e.path().to_string(),
+        message: e.into_inner().to_string(),
+    })
+}
+
+/// Read and deserialize a JSON file, reporting the file name and JSON path of any failure.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_json<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, LoadError> {
+    let path = path.as_ref();
+    let file = path.display().to_string();
+    let contents = fs::read_to_string(path).map_err(|source| LoadError::Io { file: file.clone(), source })?;
+    from_json_str(&contents, &file)
+}
+
+/// A document loaded leniently, without the entries that failed to load.
+#[derive(Debug, Clone)]
+pub struct Lenient<T> {
+    pub document: T,
+    /// Each entry left out, in the order found.
+    pub skipped: Vec<SkippedEntry>,
+}
+
+/// A list entry left out of a leniently loaded document.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkippedEntry {
+    /// JSON path of the entry in the file, e.g. `track_timings[12]`.
+    pub entry: String,
+    /// JSON path of the failing field, within the entry.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SkippedEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Most entries a lenient load leaves out before giving up on the file.
+/// Each one costs another pass over the whole document.
+pub const MAX_SKIPPED: usize = 500;
+
+/// One step of a JSON path.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Step {
+    Index(usize),
+    Key(String),
+}
+
+fn format_path(steps: &[Step]) -> String {
+    if steps.is_empty() {
+        return ".".to_string();
+    }
+    let mut path = String::new();
+    for step in steps {
+        match step {
+            Step::Index(i) => path.push_str(&format!("[{i}]")),
+            Step::Key(key) if path.is_empty() => path.push_str(key),
+            Step::Key(key) => path.push_str(&format!(".{key}")),
+        }
+    }
+    path
+}
+
+/// Deserialize a JSON string as `from_json_str` does, leaving out each list
+/// entry that fails to deserialize: the innermost object in a list that
+/// holds the failing field, or the failing item of a list of plain values
+/// outside any such object.
+///
+/// Fails, as `from_json_str` does, when the JSON is malformed or a field
+/// outside any list is invalid. The document is deserialized again after
+/// each entry is left out, so the time taken grows with the entries times
+/// the size of the file; past [`MAX_SKIPPED`] entries, the load fails.
+pub fn from_json_str_lenient<T: DeserializeOwned>(contents: &str, file: &str) -> Result<Lenient<T>, LoadError> {
+    let parse_error = |path: String, message: String| LoadError::Parse { file: file.to_string(), path, message };
+    let mut value: Value = serde_json::from_str(contents).map_err(|e| parse_error(".".to_string(), e.to_string()))?;
+    let mut skipped = Vec::new();
+    // Indices in the file of the entries removed so far, by the file path of their list
+    let mut removed: HashMap<Vec<Step>, Vec<usize>> = HashMap::new();
+
+    loop {
+        let error = match serde_path_to_error::deserialize::<_, T>(&value) {
+            Ok(document) => return Ok(Lenient { document, skipped }),
+            Err(error) => error,
+        };
+        let message = error.inner().to_string();
+        let Some(steps) = error.path().iter()
+            .map(|segment| match segment {
+                Segment::Seq { index } => Some(Step::Index(*index)),
+                Segment::Map { key } | Segment::Enum { variant: key } => Some(Step::Key(key.clone())),
+                Segment::Unknown => None,
+            })
+            .collect::<Option<Vec<Step>>>()
+        else {
+            return Err(parse_error(error.path().to_string(), message));
+        };
+
+        // The same path in the file, before earlier entries were removed
+        let mut in_file: Vec<Step> = Vec::with_capacity(steps.len());
+        for step in &steps {
+            let step = match step {
+                Step::Index(i) => {
+                    let mut index = *i;
+                    for &gone in removed.get(&in_file).map(Vec::as_slice).unwrap_or_default() {
+                        if gone <= index {
+                            index += 1;
+                        }
+                    }
+                    Step::Index(index)
+                }
+                key => key.clone(),
+            };
+            in_file.push(step);
+        }
+
+        if skipped.len() == MAX_SKIPPED {
+            return Err(parse_error(format_path(&in_file), format!("{message} (after {MAX_SKIPPED} entries left out, giving up)")));
+        }
+
+        // The entry to leave out: the innermost list item that is an
+        // object, else the innermost list item
+        let mut entry = steps.iter().rposition(|s| matches!(s, Step::Index(_)));
+        let mut at = Some(&value);
+        for (position, step) in steps.iter().enumerate() {
+            at = at.and_then(|v| match step {
+                Step::Index(i) => v.get(*i),
+                Step::Key(key) => v.get(key.as_str()),
+            });
+            if matches!(step, Step::Index(_)) && matches!(at, Some(Value::Object(_))) {
+                entry = Some(position);
+            }
+        }
+        let list = entry.and_then(|entry| {
+            steps[..entry].iter().try_fold(&mut value, |v, step| match step {
+                Step::Index(i) => v.get_mut(*i),
+                Step::Key(key) => v.get_mut(key.as_str()),
+            })
+        });
+        let (Some(entry), Some(Value::Array(items))) = (entry, list) else {
+            return Err(parse_error(format_path(&in_file), message));
+        };
+        let Step::Index(index) = steps[entry] else { unreachable!("entry is the position of an index") };
+        if index >= items.len() {
+            return Err(parse_error(format_path(&in_file), message));
+        }
+        items.remove(index);
+
+        let Step::Index(index_in_file) = in_file[entry] else { unreachable!("in_file has the same steps") };
+        let gone = removed.entry(in_file[..entry].to_vec()).or_default();
+        let at = gone.partition_point(|&g| g < index_in_file);
+        gone.insert(at, index_in_file);
+        skipped.push(SkippedEntry {
+            entry: format_path(&in_file[..=entry]),
+            path: format_path(&in_file),
+            message,
+        });
+    }
+}
+
+/// Read and deserialize a JSON file as `load_json` does, leaving out each
+/// list entry that fails to deserialize.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_json_lenient<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<Lenient<T>, LoadError> {
+    let path = path.as_ref();
+    let file = path.display().to_string();
+    let contents = fs::read_to_string(path).map_err(|source| LoadError::Io { file: file.clone(), source })?;
+    from_json_str_lenient(&contents, &file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing_overlay::TimingOverlay;
+
+    #[test]
+    fn test_error_names_field_path() {
+        let json = r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [
+                { "track_title": "One", "number_ids": [], "segment_times": [] },
+                { "track_title": "Two", "number_ids": [], "segment_times": [
+                    { "segment_id": "a-001", "start": 0.0 },
+                    { "segment_id": "a-002", "start": "12.5" }
+                ] }
+            ]
+        }"#;
+        let err = from_json_str::<TimingOverlay>(json, "ricci.timing.json").unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.starts_with("ricci.timing.json: track_timings[1].segment_times[1].start: "), "{msg}");
+        assert!(msg.contains("expected f64"), "{msg}");
+    }
+
+    #[test]
+    fn test_root_error_has_no_path() {
+        let err = from_json_str::<TimingOverlay>("42", "x.json").unwrap_err();
+        assert!(err.to_string().starts_with("x.json: invalid type"), "{err}");
+    }
+
+    #[test]
+    fn test_lenient_skips_bad_entries() {
+        let json = r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [
+                { "number_ids": [], "segment_times": [] },
+                { "track_title": "Two", "number_ids": ["a"], "segment_times": [
+                    { "segment_id": "a-001", "start": 0.0 },
+                    { "segment_id": "a-002", "start": "12.5" },
+                    { "segment_id": "a-003", "start": 20.0 },
+                    { "segment_id": "a-004" },
+                    { "segment_id": "a-005", "start": 31.0 }
+                ] },
+                { "track_title": "Three", "number_ids": [7], "segment_times": [] }
+            ]
+        }"#;
+        assert!(from_json_str::<TimingOverlay>(json, "x.timing.json").is_err());
+
+        let loaded = from_json_str_lenient::<TimingOverlay>(json, "x.timing.json").unwrap();
+        let overlay = loaded.document;
+        // A bad number ID takes its track with it
+        assert_eq!(overlay.track_timings.len(), 1);
+        let starts: Vec<&str> = overlay.track_timings[0].segment_times.iter().map(|t| t.segment_id.as_str()).collect();
+        assert_eq!(starts, vec!["a-001", "a-003", "a-005"]);
+
+        // Paths are as in the file, not as shifted by earlier removals
+        let found: Vec<(&str, &str)> = loaded.skipped.iter().map(|s| (s.entry.as_str(), s.path.as_str())).collect();
+        assert_eq!(found, vec![
+            ("track_timings[0]", "track_timings[0]"),
+            ("track_timings[1].segment_times[1]", "track_timings[1].segment_times[1].start"),
+            ("track_timings[1].segment_times[3]", "track_timings[1].segment_times[3]"),
+            ("track_timings[2]", "track_timings[2].number_ids[0]"),
+        ]);
+        assert!(loaded.skipped[0].message.contains("track_title"), "{}", loaded.skipped[0]);
+    }
+
+    #[test]
+    fn test_lenient_gives_up() {
+        let times = vec![r#"{ "segment_id": "a", "start": "0" }"#; MAX_SKIPPED + 1].join(",");
+        let json = format!(
+            r#"{{ "version": "1.0", "base_libretto": "b.json", "recording": {{}}, "track_timings": [
+                {{ "track_title": "One", "number_ids": [], "segment_times": [{times}] }}
+            ] }}"#
+        );
+        let err = from_json_str_lenient::<TimingOverlay>(&json, "x.json").unwrap_err();
+        assert!(err.to_string().contains("giving up"), "{err}");
+    }
+
+    #[test]
+    fn test_lenient_fails_outside_lists() {
+        let json = r#"{ "version": 1, "base_libretto": "b.json", "recording": {}, "track_timings": [] }"#;
+        let err = from_json_str_lenient::<TimingOverlay>(json, "x.json").unwrap_err();
+        assert!(err.to_string().starts_with("x.json: version: invalid type"), "{err}");
+        assert!(from_json_str_lenient::<TimingOverlay>("{", "x.json").is_err());
+    }
+
+    #[test]
+    fn test_missing_file() {
+        let err = load_json::<TimingOverlay>("/nonexistent/x.timing.json").unwrap_err();
+        assert!(matches!(err, LoadError::Io { .. }));
+    }
+}
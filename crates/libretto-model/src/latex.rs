@@ -0,0 +1,222 @@
+// LaTeX rendering of a base libretto, for program-book quality PDFs.
+//
+// The document comes from a template with `{{placeholder}}`s so a house
+// style (paper, fonts, title page) can be kept outside the code. The
+// built-in template is a plain article for pdflatex. Placeholders:
+//
+// - `{{title}}`, `{{composer}}`, `{{librettist}}`, `{{year}}`, `{{language}}`
+// - `{{author}}`: the composer, and "libretto by" the librettist when known
+// - `{{cast}}`: the cast list as a table
+// - `{{body}}`: a section per act, opening with a table of its numbers, and
+//   a subsection per number with character, text and translation side by side
+//
+// Everything substituted is escaped for LaTeX; `{{cast}}` and `{{body}}`
+// are LaTeX markup needing the `longtable` and `array` packages.
+
+use crate::base_libretto::{BaseLibretto, MusicalNumber};
+
+/// The built-in template.
+pub const DEFAULT_TEMPLATE: &str = include_str!("latex/default.tex");
+
+/// Render a base libretto through `template`.
+pub fn export_latex(base: &BaseLibretto, template: &str) -> String {
+    let opera = &base.opera;
+    let mut author = escape(&opera.composer);
+    if let Some(librettist) = &opera.librettist {
+        author.push_str(&format!(" \\\\ \\small libretto by {}", escape(librettist)));
+    }
+    let placeholders = [
+        ("title", escape(&opera.title)),
+        ("composer", escape(&opera.composer)),
+        ("librettist", opera.librettist.as_deref().map(escape).unwrap_or_default()),
+        ("year", opera.year.map(|y| y.to_string()).unwrap_or_default()),
+        ("language", escape(&opera.language)),
+        ("author", author),
+        ("cast", cast_table(base)),
+        ("body", body(base)),
+    ];
+    placeholders.iter().fold(template.to_string(), |doc, (name, value)| {
+        doc.replace(&format!("{{{{{name}}}}}"), value)
+    })
+}
+
+fn cast_table(base: &BaseLibretto) -> String {
+    let mut out = String::from("\\begin{longtable}{>{\\scshape}l l}\n");
+    for member in &base.cast {
+        let voice = member.voice_type.as_deref().map(escape).unwrap_or_default();
+        out.push_str(&format!("{} & \\textit{{{}}} \\\\\n", escape(&member.character), voice));
+    }
+    out.push_str("\\end{longtable}\n");
+    out
+}
+
+fn body(base: &BaseLibretto) -> String {
+    let has_translation = base.numbers.iter()
+        .flat_map(|n| &n.segments)
+        .any(|s| s.translation.as_deref().is_some_and(|t| !t.trim().is_empty()));
+
+    let mut acts: Vec<(&str, Vec<&MusicalNumber>)> = Vec::new();
+    for number in &base.numbers {
+        match acts.last_mut() {
+            Some((act, numbers)) if *act == number.act => numbers.push(number),
+            _ => acts.push((number.act.as_str(), vec![number])),
+        }
+    }
+
+    let mut out = String::new();
+    for (act, numbers) in acts {
+        out.push_str(&format!("\\section*{{Act {}}}\n\\addcontentsline{{toc}}{{section}}{{Act {}}}\n\n", escape(act), escape(act)));
+        out.push_str("\\begin{longtable}{p{0.35\\textwidth} p{0.5\\textwidth} r}\n");
+        for number in &numbers {
+            out.push_str(&format!(
+                "{} & \\textit{{{}}} & \\pageref{{num:{}}} \\\\\n",
+                escape(&number.label),
+                escape(&incipit(number)),
+                number.id
+            ));
+        }
+        out.push_str("\\end{longtable}\n\n");
+
+        for number in numbers {
+            out.push_str(&format!("\\subsection*{{{}}}\\label{{num:{}}}\n", escape(&number.label), number.id));
+            out.push_str(&number_table(number, has_translation));
+        }
+        out.push_str("\\clearpage\n");
+    }
+    out
+}
+
+/// A number's text: character, original and (when the libretto has
+/// translations) translation columns, stage directions across the page.
+fn number_table(number: &MusicalNumber, has_translation: bool) -> String {
+    let (columns, span) = match has_translation {
+        true => ("p{0.16\\textwidth} p{0.38\\textwidth} p{0.38\\textwidth}", 3),
+        false => ("p{0.2\\textwidth} p{0.7\\textwidth}", 2),
+    };
+    let mut out = format!("\\begin{{longtable}}{{{columns}}}\n");
+    let mut singer: Option<&str> = None;
+    for seg in &number.segments {
+        let Some(text) = &seg.text else {
+            if let Some(direction) = &seg.direction {
+                out.push_str(&format!("\\multicolumn{{{span}}}{{p{{0.95\\textwidth}}}}{{\\textit{{{}}}}} \\\\\n", lines(direction)));
+            }
+            singer = None;
+            continue;
+        };
+        let character = match seg.character.as_deref() {
+            Some(c) if singer != Some(c) => {
+                singer = Some(c);
+                format!("\\textsc{{{}}}", escape(c))
+            }
+            _ => String::new(),
+        };
+        out.push_str(&format!("{character} & {}", lines(text)));
+        if has_translation {
+            out.push_str(&format!(" & \\textit{{{}}}", seg.translation.as_deref().map(lines).unwrap_or_default()));
+        }
+        out.push_str(" \\\\\n");
+    }
+    out.push_str("\\end{longtable}\n\n");
+    out
+}
+
+/// The first line of a number's sung text.
+fn incipit(number: &MusicalNumber) -> String {
+    number.segments.iter()
+        .filter_map(|s| s.text.as_deref())
+        .find_map(|t| t.lines().map(str::trim).find(|l| !l.is_empty()))
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Escape text and keep its line breaks as `\newline`.
+fn lines(text: &str) -> String {
+    text.lines().map(str::trim).filter(|l| !l.is_empty()).map(escape).collect::<Vec<_>>().join(" \\newline ")
+}
+
+/// Escape LaTeX's special characters.
+fn escape(text: &str) -> String {
+    let mut out = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::segment;
+    use crate::base_libretto::*;
+
+    fn base() -> BaseLibretto {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "W. A. Mozart".to_string(),
+            librettist: Some("Lorenzo Da Ponte".to_string()),
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: Some(1786),
+        });
+        lib.cast.push(CastMember {
+            character: "Figaro".to_string(),
+            short_name: Some("FIGARO".to_string()),
+            voice_type: Some("bass".to_string()),
+            description: None,
+            aliases: vec![],
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "No. 1 - Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![
+                segment("no-1-duettino-001", None, None, None, Some("Figaro measures the room & bed.")),
+                segment("no-1-duettino-002", Some("FIGARO"), Some("Cinque...\ndieci..."), Some("Five...\nten..."), None),
+                segment("no-1-duettino-003", Some("FIGARO"), Some("venti..."), Some("twenty..."), None),
+            ],
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-10-cavatina".to_string(),
+            label: "No. 10 - Cavatina".to_string(),
+            number_type: NumberType::Cavatina,
+            act: "2".to_string(),
+            scene: None,
+            segments: vec![segment("no-10-cavatina-001", Some("LA CONTESSA"), Some("Porgi, amor"), Some("Grant, love"), None)],
+        });
+        lib
+    }
+
+    #[test]
+    fn test_export_latex() {
+        let tex = export_latex(&base(), DEFAULT_TEMPLATE);
+        assert!(tex.contains("\\title{Le nozze di Figaro}"));
+        assert!(tex.contains("\\author{W. A. Mozart \\\\ \\small libretto by Lorenzo Da Ponte}"));
+        assert!(tex.contains("Figaro & \\textit{bass} \\\\"));
+        assert_eq!(tex.matches("\\section*{Act").count(), 2);
+        // Per-act table of numbers, linked to the numbers' pages.
+        assert!(tex.contains("No. 1 - Duettino & \\textit{Cinque...} & \\pageref{num:no-1-duettino} \\\\"));
+        assert!(tex.contains("\\subsection*{No. 1 - Duettino}\\label{num:no-1-duettino}"));
+        assert!(tex.contains("\\multicolumn{3}{p{0.95\\textwidth}}{\\textit{Figaro measures the room \\& bed.}} \\\\"));
+        assert!(tex.contains("\\textsc{FIGARO} & Cinque... \\newline dieci... & \\textit{Five... \\newline ten...} \\\\"));
+        assert!(tex.contains("\n & venti... & \\textit{twenty...} \\\\"));
+        assert!(!tex.contains("{{"));
+    }
+
+    #[test]
+    fn test_custom_template() {
+        let tex = export_latex(&base(), "{{title}} ({{year}}, {{language}}) {{unknown}}");
+        assert_eq!(tex, "Le nozze di Figaro (1786, it) {{unknown}}");
+        assert_eq!(escape("50% off_{x}^2 ~ #1 $ \\"), "50\\% off\\_\\{x\\}\\textasciicircum{}2 \\textasciitilde{} \\#1 \\$ \\textbackslash{}");
+    }
+}
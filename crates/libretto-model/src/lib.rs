@@ -1,11 +1,52 @@
 pub mod base_libretto;
+pub mod rights;
 pub mod timing_overlay;
+pub mod annotation_overlay;
+pub mod pronunciation_overlay;
+pub mod synopsis_overlay;
 pub mod interchange;
+pub mod encoding;
+pub mod io;
 pub mod merge;
 pub mod estimate;
+pub mod syllable;
 pub mod resolve;
+pub mod decompile;
+pub mod library;
+pub mod crosswalk;
+pub mod combine;
+pub mod compare;
+pub mod cue;
+pub mod tracklist;
+pub mod timeline;
+pub mod artifacts;
+pub mod import;
+pub mod export;
+pub mod chapters;
+pub mod html;
+pub mod segment_csv;
+pub mod latex;
+pub mod webapp;
+pub mod surtitles;
+pub mod tei;
+pub mod markdown;
+pub mod roon;
+pub mod track_edit;
+pub mod reid;
+pub mod stream;
+pub mod gaps;
+pub mod bundle;
+mod parallel;
+#[cfg(test)]
+mod fixtures;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 pub use base_libretto::*;
+pub use rights::*;
 pub use timing_overlay::*;
+pub use annotation_overlay::*;
+pub use pronunciation_overlay::*;
+pub use synopsis_overlay::*;
 pub use interchange::*;
 pub use merge::*;
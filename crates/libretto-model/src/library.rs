@@ -0,0 +1,525 @@
+// Library catalog: a single manifest of every base libretto, timing overlay,
+// and interchange document under a library root.
+//
+// Display systems read `library.json` to discover which timed librettos
+// exist without walking the tree themselves. Paths are relative to the
+// library root and use `/` separators; each file carries a SHA-256 hash so
+// consumers can detect stale copies.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Scanning reads the file system, which a WebAssembly build has none of.
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::base_libretto::BaseLibretto,
+    crate::interchange::InterchangeLibretto,
+    crate::timing_overlay::TimingOverlay,
+    std::collections::HashMap,
+    std::path::PathBuf,
+    std::{fs, io},
+};
+
+/// Conventional file name of the library index at the library root.
+pub const LIBRARY_INDEX_FILE: &str = "library.json";
+
+/// The library catalog: operas → base librettos → recordings → interchange outputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    pub version: String,
+    /// RFC 3339 timestamp of when the index was generated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub generated_at: Option<String>,
+    pub operas: Vec<LibraryOpera>,
+}
+
+/// An opera in the library, identified by title and composer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryOpera {
+    pub title: String,
+    pub composer: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bases: Vec<LibraryBase>,
+    /// Interchange documents for this opera that could not be attributed
+    /// to a specific recording overlay.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interchange: Vec<LibraryFile>,
+}
+
+/// A base libretto file and the recordings timed against it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryBase {
+    pub path: String,
+    pub sha256: String,
+    pub language: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation_language: Option<String>,
+    pub numbers: usize,
+    pub segments: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recordings: Vec<LibraryRecording>,
+}
+
+/// A timing overlay for one recording, plus interchange files produced from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryRecording {
+    pub path: String,
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conductor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orchestra: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album_title: Option<String>,
+    pub tracks: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub interchange: Vec<LibraryFile>,
+}
+
+/// A generic file reference with its content hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryFile {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Result of scanning a library root.
+#[derive(Debug)]
+pub struct LibraryScan {
+    pub index: LibraryIndex,
+    /// Files that could not be placed in the catalog.
+    pub warnings: Vec<String>,
+}
+
+impl LibraryIndex {
+    /// Look up a base libretto entry by its library-relative path.
+    pub fn find_base(&self, path: &str) -> Option<&LibraryBase> {
+        self.operas.iter()
+            .flat_map(|o| o.bases.iter())
+            .find(|b| b.path == path)
+    }
+
+    /// Iterate over every recording in the library with its base entry.
+    pub fn recordings(&self) -> impl Iterator<Item = (&LibraryBase, &LibraryRecording)> {
+        self.operas.iter()
+            .flat_map(|o| o.bases.iter())
+            .flat_map(|b| b.recordings.iter().map(move |r| (b, r)))
+    }
+
+    /// Iterate over every interchange document in the library, those of
+    /// recordings and those filed under their opera alone.
+    pub fn interchange_files(&self) -> impl Iterator<Item = &LibraryFile> {
+        self.recordings()
+            .flat_map(|(_, r)| r.interchange.iter())
+            .chain(self.operas.iter().flat_map(|o| o.interchange.iter()))
+    }
+}
+
+/// Compute the lowercase hex SHA-256 of a byte slice.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// A parsed document found while scanning, with its relative path and hash.
+#[cfg(not(target_arch = "wasm32"))]
+struct Scanned<T> {
+    path: String,
+    sha256: String,
+    doc: T,
+}
+
+/// Scan a directory tree and build a library index.
+///
+/// Every `*.json` file is classified by shape as a base libretto, timing
+/// overlay, or interchange document; other JSON (acquisition output,
+/// the index itself) is ignored. Overlays are attached to the base their
+/// `base_libretto` path resolves to, relative to the library root or to
+/// the overlay's own directory. Interchange documents are attached to an
+/// overlay in the same directory with a matching album title (or to the
+/// sole overlay there), otherwise to their opera by title and composer.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn scan_library(root: &Path) -> io::Result<LibraryScan> {
+    let mut warnings = Vec::new();
+    let mut files = Vec::new();
+    collect_json_files(root, &mut files)?;
+
+    let mut bases: Vec<Scanned<BaseLibretto>> = Vec::new();
+    let mut overlays: Vec<Scanned<TimingOverlay>> = Vec::new();
+    let mut interchanges: Vec<Scanned<InterchangeLibretto>> = Vec::new();
+
+    for file in &files {
+        let rel = relative_path(root, file);
+        if rel == LIBRARY_INDEX_FILE {
+            continue;
+        }
+        let bytes = fs::read(file)?;
+        let sha256 = sha256_hex(&bytes);
+        if let Ok(doc) = serde_json::from_slice::<BaseLibretto>(&bytes) {
+            bases.push(Scanned { path: rel, sha256, doc });
+        } else if let Ok(doc) = serde_json::from_slice::<TimingOverlay>(&bytes) {
+            overlays.push(Scanned { path: rel, sha256, doc });
+        } else if let Ok(doc) = serde_json::from_slice::<InterchangeLibretto>(&bytes) {
+            interchanges.push(Scanned { path: rel, sha256, doc });
+        }
+    }
+
+    // Operas keyed by (title, composer), in first-seen order
+    let mut operas: Vec<LibraryOpera> = Vec::new();
+    let mut opera_index: HashMap<(String, String), usize> = HashMap::new();
+    let mut opera_for = |title: &str, composer: &str, operas: &mut Vec<LibraryOpera>| -> usize {
+        *opera_index
+            .entry((title.to_string(), composer.to_string()))
+            .or_insert_with(|| {
+                operas.push(LibraryOpera {
+                    title: title.to_string(),
+                    composer: composer.to_string(),
+                    bases: Vec::new(),
+                    interchange: Vec::new(),
+                });
+                operas.len() - 1
+            })
+    };
+
+    // base path → (opera index, base index)
+    let mut base_location: HashMap<String, (usize, usize)> = HashMap::new();
+    for base in &bases {
+        let oi = opera_for(&base.doc.opera.title, &base.doc.opera.composer, &mut operas);
+        operas[oi].bases.push(LibraryBase {
+            path: base.path.clone(),
+            sha256: base.sha256.clone(),
+            language: base.doc.opera.language.clone(),
+            translation_language: base.doc.opera.translation_language.clone(),
+            numbers: base.doc.numbers.len(),
+            segments: base.doc.segment_ids().len(),
+            recordings: Vec::new(),
+        });
+        base_location.insert(base.path.clone(), (oi, operas[oi].bases.len() - 1));
+    }
+
+    // overlay path → (opera index, base index, recording index)
+    let mut overlay_location: HashMap<String, (usize, usize, usize)> = HashMap::new();
+    for overlay in &overlays {
+        let located = resolve_reference(&overlay.path, &overlay.doc.base_libretto)
+            .into_iter()
+            .find_map(|p| base_location.get(&p).copied());
+        let Some((oi, bi)) = located else {
+            warnings.push(format!(
+                "{}: base libretto '{}' not found in library",
+                overlay.path, overlay.doc.base_libretto
+            ));
+            continue;
+        };
+        let rec = &overlay.doc.recording;
+        let recordings = &mut operas[oi].bases[bi].recordings;
+        recordings.push(LibraryRecording {
+            path: overlay.path.clone(),
+            sha256: overlay.sha256.clone(),
+            conductor: rec.conductor.clone(),
+            orchestra: rec.orchestra.clone(),
+            year: rec.year,
+            album_title: rec.album_title.clone(),
+            tracks: overlay.doc.track_timings.len(),
+            interchange: Vec::new(),
+        });
+        overlay_location.insert(overlay.path.clone(), (oi, bi, recordings.len() - 1));
+    }
+
+    for doc in &interchanges {
+        let entry = LibraryFile { path: doc.path.clone(), sha256: doc.sha256.clone() };
+        let dir = parent_dir(&doc.path);
+        let album = doc.doc.tracks.iter().find_map(|t| t.album.as_deref());
+
+        let same_dir: Vec<&Scanned<TimingOverlay>> = overlays.iter()
+            .filter(|o| parent_dir(&o.path) == dir && overlay_location.contains_key(&o.path))
+            .collect();
+        let matched = same_dir.iter()
+            .find(|o| album.is_some() && o.doc.recording.album_title.as_deref() == album)
+            .or(if same_dir.len() == 1 { same_dir.first() } else { None });
+
+        match matched.and_then(|o| overlay_location.get(&o.path)) {
+            Some(&(oi, bi, ri)) => operas[oi].bases[bi].recordings[ri].interchange.push(entry),
+            None => {
+                let oi = opera_for(&doc.doc.opera.title, &doc.doc.opera.composer, &mut operas);
+                operas[oi].interchange.push(entry);
+            }
+        }
+    }
+
+    Ok(LibraryScan {
+        index: LibraryIndex {
+            version: "1.0".to_string(),
+            generated_at: Some(chrono::Utc::now().to_rfc3339()),
+            operas,
+        },
+        warnings,
+    })
+}
+
+/// Recursively collect `*.json` files, skipping hidden directories and
+/// `target/`, in sorted order so the index is deterministic.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn collect_json_files(dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "target" {
+                collect_json_files(&path, out)?;
+            }
+        } else if name.ends_with(".json") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Path of `file` relative to `root`, with `/` separators.
+pub fn relative_path(root: &Path, file: &Path) -> String {
+    let rel = file.strip_prefix(root).unwrap_or(file);
+    rel.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn parent_dir(rel: &str) -> &str {
+    rel.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+/// Candidate library-relative paths for a reference made from `from_path`:
+/// first as written (relative to the library root), then relative to the
+/// referencing file's directory.
+pub fn resolve_reference(from_path: &str, reference: &str) -> Vec<String> {
+    let mut candidates = Vec::new();
+    if let Some(p) = normalize_path(reference) {
+        candidates.push(p);
+    }
+    let dir = parent_dir(from_path);
+    if !dir.is_empty() {
+        if let Some(p) = normalize_path(&format!("{dir}/{reference}")) {
+            if !candidates.contains(&p) {
+                candidates.push(p);
+            }
+        }
+    }
+    candidates
+}
+
+/// Find the file a document's reference (e.g. an overlay's `base_libretto`) points to.
+///
+/// References are relative to the library root, which is not known from
+/// the referencing file alone, so the reference is tried against the
+/// file's own directory and then each ancestor in turn, up past the working
+/// directory for a relative `from_file`.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn locate_reference(from_file: &Path, reference: &str) -> Option<PathBuf> {
+    std::path::absolute(from_file).ok()?
+        .parent()?
+        .ancestors()
+        .map(|dir| dir.join(reference))
+        .find(|p| p.is_file())
+}
+
+/// The reference to `target` to write in `from_file`: the path from
+/// `from_file`'s directory, e.g. `../base.libretto.json` for an overlay in
+/// `timings/`, which resolves wherever the two files are moved together.
+/// `target` as given when the two share no directory.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn reference_from(from_file: &Path, target: &Path) -> String {
+    let absolute = |path: &Path| -> Option<Vec<String>> {
+        let mut parts: Vec<String> = Vec::new();
+        for component in std::path::absolute(path).ok()?.components() {
+            match component {
+                std::path::Component::CurDir => {}
+                std::path::Component::ParentDir => {
+                    parts.pop();
+                }
+                c => parts.push(c.as_os_str().to_string_lossy().into_owned()),
+            }
+        }
+        Some(parts)
+    };
+    let (Some(mut from), Some(to)) = (absolute(from_file), absolute(target)) else {
+        return target.to_string_lossy().into_owned();
+    };
+    from.pop();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return target.to_string_lossy().into_owned();
+    }
+    std::iter::repeat_n("..", from.len() - common)
+        .chain(to[common..].iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Collapse `.` and `..` components. Returns `None` if the path escapes the root.
+fn normalize_path(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            p => parts.push(p),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_libretto::OperaMetadata;
+    use crate::interchange::{InterchangeOpera, InterchangeTrack};
+    use crate::timing_overlay::RecordingMetadata;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("libretto-library-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_json<T: Serialize>(path: &Path, value: &T) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, serde_json::to_string_pretty(value).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("a/./b/../c.json").as_deref(), Some("a/c.json"));
+        assert_eq!(normalize_path("../outside.json"), None);
+    }
+
+    #[test]
+    fn test_resolve_reference() {
+        let candidates = resolve_reference("figaro/timings/giulini.timing.json", "../base.libretto.json");
+        assert_eq!(candidates, vec!["figaro/base.libretto.json"]);
+        let candidates = resolve_reference("figaro/timings/giulini.timing.json", "figaro/base.libretto.json");
+        assert_eq!(candidates[0], "figaro/base.libretto.json");
+    }
+
+    #[test]
+    fn test_reference_from() {
+        let reference = reference_from(Path::new("figaro/timings/giulini.timing.json"), Path::new("figaro/base.libretto.json"));
+        assert_eq!(reference, "../base.libretto.json");
+        let reference = reference_from(Path::new("./giulini.timing.json"), Path::new("base.libretto.json"));
+        assert_eq!(reference, "base.libretto.json");
+        let reference = reference_from(Path::new("a/b/../x.timing.json"), Path::new("a/c/base.libretto.json"));
+        assert_eq!(reference, "c/base.libretto.json");
+    }
+
+    #[test]
+    fn test_scan_library() {
+        let root = temp_root("scan");
+        let base = BaseLibretto::new(OperaMetadata {
+            title: "Tosca".to_string(),
+            composer: "Puccini".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: None,
+            year: None,
+        });
+        write_json(&root.join("puccini/tosca/base.libretto.json"), &base);
+
+        let overlay = TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "puccini/tosca/base.libretto.json".to_string(),
+            recording: RecordingMetadata {
+                conductor: Some("De Sabata".to_string()),
+                orchestra: None,
+                year: Some(1953),
+                label: None,
+                album_title: Some("Tosca (1953)".to_string()),
+            },
+            ..Default::default()
+        };
+        write_json(&root.join("puccini/tosca/timings/desabata.timing.json"), &overlay);
+
+        let interchange = InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Tosca".to_string(),
+                composer: "Puccini".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks: vec![InterchangeTrack {
+                track_id: "t1".to_string(),
+                title: "Act I".to_string(),
+                album: Some("Tosca (1953)".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        write_json(&root.join("puccini/tosca/timings/desabata.timed.libretto.json"), &interchange);
+        fs::write(root.join("puccini/tosca/bilingual.json"), "{\"rows\": []}").unwrap();
+
+        let scan = scan_library(&root).unwrap();
+        assert!(scan.warnings.is_empty(), "warnings: {:?}", scan.warnings);
+        assert_eq!(scan.index.operas.len(), 1);
+
+        let base_entry = scan.index.find_base("puccini/tosca/base.libretto.json").unwrap();
+        assert_eq!(base_entry.sha256.len(), 64);
+        assert_eq!(base_entry.recordings.len(), 1);
+        let rec = &base_entry.recordings[0];
+        assert_eq!(rec.path, "puccini/tosca/timings/desabata.timing.json");
+        assert_eq!(rec.interchange.len(), 1);
+        assert_eq!(rec.interchange[0].path, "puccini/tosca/timings/desabata.timed.libretto.json");
+        assert_eq!(scan.index.interchange_files().count(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_scan_reports_orphan_overlay() {
+        let root = temp_root("orphan");
+        let overlay = TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "missing.libretto.json".to_string(),
+            recording: RecordingMetadata {
+                conductor: None, orchestra: None, year: None, label: None, album_title: None,
+            },
+            ..Default::default()
+        };
+        write_json(&root.join("x.timing.json"), &overlay);
+
+        let scan = scan_library(&root).unwrap();
+        assert_eq!(scan.warnings.len(), 1);
+        assert!(scan.warnings[0].contains("missing.libretto.json"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_locate_reference() {
+        let root = std::env::temp_dir().join(format!("libretto-locate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("puccini/tosca/timings")).unwrap();
+        fs::write(root.join("puccini/tosca/base.libretto.json"), "{}").unwrap();
+        let overlay = root.join("puccini/tosca/timings/x.timing.json");
+
+        let root_relative = locate_reference(&overlay, "puccini/tosca/base.libretto.json").unwrap();
+        assert_eq!(root_relative, root.join("puccini/tosca/base.libretto.json"));
+        assert!(locate_reference(&overlay, "../base.libretto.json").is_some());
+        assert!(locate_reference(&overlay, "verdi/aida/base.libretto.json").is_none());
+
+        // Relative to the working directory (the crate's, under test), a
+        // reference from the directory above is still found
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR")).file_name().unwrap().to_string_lossy();
+        let above = format!("{crate_dir}/Cargo.toml");
+        assert!(locate_reference(Path::new("x.timing.json"), &above).is_some());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
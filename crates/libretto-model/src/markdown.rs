@@ -0,0 +1,173 @@
+// Markdown rendering of a base libretto.
+//
+// Reading the JSON is slow going when reviewing a parse, and the JSON
+// diffs poorly. The Markdown page reads like the printed libretto: a
+// heading per act and per number, each sung segment a paragraph under
+// its character's name in bold, directions in italics, and translations
+// as block quotes under the text they translate. One segment per
+// paragraph keeps a diff of two exports to the segments that changed,
+// and any static site generator will publish the page as it is.
+
+use crate::base_libretto::{BaseLibretto, Segment};
+
+/// Render a base libretto as Markdown.
+pub fn export_markdown(base: &BaseLibretto) -> String {
+    let opera = &base.opera;
+    let mut out = format!("# {}\n\n", escape(&opera.title));
+    let mut byline = vec![escape(&opera.composer)];
+    if let Some(librettist) = &opera.librettist {
+        byline.push(format!("libretto by {}", escape(librettist)));
+    }
+    if let Some(year) = opera.year {
+        byline.push(year.to_string());
+    }
+    out.push_str(&format!("*{}*\n", byline.join(" · ")));
+
+    if !base.cast.is_empty() {
+        out.push_str("\n## Cast\n\n");
+        for member in &base.cast {
+            out.push_str(&format!("- **{}**", escape(&member.character)));
+            if let Some(voice) = &member.voice_type {
+                out.push_str(&format!(", {}", escape(voice)));
+            }
+            if let Some(description) = &member.description {
+                out.push_str(&format!(" — {}", escape(description)));
+            }
+            out.push('\n');
+        }
+    }
+
+    let mut act: Option<&str> = None;
+    for number in &base.numbers {
+        if act != Some(number.act.as_str()) {
+            out.push_str(&format!("\n## Act {}\n", escape(&number.act)));
+            act = Some(number.act.as_str());
+        }
+        out.push_str(&format!("\n### {}\n", escape(&number.label)));
+        for seg in &number.segments {
+            out.push('\n');
+            out.push_str(&segment_markdown(seg));
+        }
+    }
+    out
+}
+
+/// A paragraph for a segment, and a block quote for its translation.
+fn segment_markdown(seg: &Segment) -> String {
+    let Some(text) = &seg.text else {
+        let direction = seg.direction.as_deref().map(|d| lines(d).join(" ")).unwrap_or_default();
+        return format!("*{direction}*\n");
+    };
+    let mut lines_out = Vec::new();
+    if let Some(character) = &seg.character {
+        lines_out.push(format!("**{}**", escape(character)));
+    }
+    lines_out.extend(lines(text));
+    let mut out = lines_out.join("\\\n");
+    out.push('\n');
+    if let Some(translation) = seg.translation.as_deref().filter(|t| !t.trim().is_empty()) {
+        let quoted: Vec<String> = lines(translation).iter().map(|l| format!("> {l}")).collect();
+        out.push('\n');
+        out.push_str(&quoted.join("\\\n"));
+        out.push('\n');
+    }
+    out
+}
+
+/// The non-empty lines of `text`, escaped.
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(str::trim).filter(|l| !l.is_empty()).map(escape).collect()
+}
+
+/// Backslash-escape the characters Markdown would read as markup, and
+/// whatever at the start of the text would make it a heading, quote or
+/// list item: a `#`, `>`, `-` or `+`, or the `.` or `)` after a leading
+/// number ("1. Atto primo").
+fn escape(text: &str) -> String {
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    let mut out = String::with_capacity(text.len());
+    for (i, ch) in text.chars().enumerate() {
+        let starts_block = if i == 0 {
+            matches!(ch, '#' | '>' | '-' | '+')
+        } else {
+            i == digits && matches!(ch, '.' | ')')
+        };
+        if matches!(ch, '\\' | '*' | '_' | '`' | '[' | ']' | '<') || starts_block {
+            out.push('\\');
+        }
+        out.push(ch);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::segment;
+    use crate::base_libretto::*;
+
+    #[test]
+    fn test_export_markdown() {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "W. A. Mozart".to_string(),
+            librettist: Some("Lorenzo Da Ponte".to_string()),
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: None,
+        });
+        lib.cast.push(CastMember {
+            character: "Figaro".to_string(),
+            short_name: None,
+            voice_type: Some("bass".to_string()),
+            description: Some("valet to the Count".to_string()),
+            aliases: vec![],
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "No. 1 - Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![
+                segment("no-1-duettino-001", None, None, None, Some("Figaro measures\nthe room.")),
+                segment("no-1-duettino-002", Some("FIGARO"), Some("Cinque... *dieci*...\nventi..."), Some("Five... ten...\ntwenty..."), None),
+            ],
+        });
+
+        assert_eq!(export_markdown(&lib), "\
+# Le nozze di Figaro
+
+*W. A. Mozart · libretto by Lorenzo Da Ponte*
+
+## Cast
+
+- **Figaro**, bass — valet to the Count
+
+## Act 1
+
+### No. 1 - Duettino
+
+*Figaro measures the room.*
+
+**FIGARO**\\
+Cinque... \\*dieci\\*...\\
+venti...
+
+> Five... ten...\\
+> twenty...
+");
+    }
+
+    #[test]
+    fn test_escape_line_starts() {
+        assert_eq!(escape("# Coro"), "\\# Coro");
+        assert_eq!(escape("- Ah!"), "\\- Ah!");
+        assert_eq!(escape("+ più"), "\\+ più");
+        assert_eq!(escape("> no"), "\\> no");
+        assert_eq!(escape("1. Atto primo"), "1\\. Atto primo");
+        assert_eq!(escape("12) dodici"), "12\\) dodici");
+        assert_eq!(escape("Sì - no. 3."), "Sì - no. 3.");
+        assert_eq!(escape("1 2. tre"), "1 2. tre");
+    }
+}
@@ -3,12 +3,19 @@
 // The merge resolves segment IDs from the overlay against the base libretto,
 // producing a self-contained timed document ready for display systems.
 
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 
-use crate::base_libretto::{BaseLibretto, Segment};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::annotation_overlay::AnnotationOverlay;
+use crate::pronunciation_overlay::PronunciationOverlay;
+use crate::synopsis_overlay::SynopsisOverlay;
+use crate::base_libretto::{BaseLibretto, MusicalNumber, NumberType, Segment};
 use crate::interchange::{InterchangeLibretto, InterchangeOpera, InterchangeSegment, InterchangeTrack};
 use crate::resolve;
-use crate::timing_overlay::{TimingOverlay, TrackTiming};
+use crate::timing_overlay::{SegmentTime, TimingOverlay, TrackTiming};
 
 /// Merge a base libretto with a timing overlay to produce an interchange libretto.
 ///
@@ -18,21 +25,78 @@ use crate::timing_overlay::{TimingOverlay, TrackTiming};
 ///
 /// Segments referenced in the overlay but missing from the base libretto
 /// are included with a warning (empty text fields). Segments in the base
-/// libretto but not referenced in the overlay are silently skipped.
+/// libretto but not referenced in the overlay are silently skipped. A
+/// segment referenced more than once is included each time, with a warning.
 pub fn merge(base: &BaseLibretto, overlay: &TimingOverlay) -> MergeResult {
+    merge_with(base, overlay, &MergeOptions::default())
+        .expect("merging with the default options does not fail")
+}
+
+/// What a merge does with a segment the overlay references more than once.
+///
+/// Crossover tracks, where a number runs from one track into the next, can
+/// rightly list the same segment twice; otherwise it is usually a slip when
+/// copying times between tracks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateStrategy {
+    /// Include every reference, silently.
+    Allow,
+    /// Include every reference, with a warning for each repeat.
+    #[default]
+    Warn,
+    /// Fail the merge, naming every repeat.
+    Error,
+    /// Include only the first reference, with a warning for each repeat
+    /// left out.
+    KeepFirst,
+}
+
+#[derive(Debug, Error)]
+pub enum MergeError {
+    #[error("{} segment reference(s) repeated: {}", .0.len(), .0.join("; "))]
+    DuplicateReferences(Vec<String>),
+}
+
+/// Options for a merge.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeOptions {
+    /// Keep base segments the overlay has no time for, marked `untimed`,
+    /// in their place on the track of their number, instead of skipping
+    /// them.
+    pub include_untimed: bool,
+    /// What to do with segments referenced more than once.
+    pub duplicates: DuplicateStrategy,
+}
+
+/// Merge as `merge` does, with `options`. Fails only with
+/// `DuplicateStrategy::Error`.
+pub fn merge_with(base: &BaseLibretto, overlay: &TimingOverlay, options: &MergeOptions) -> Result<MergeResult, MergeError> {
     let mut warnings: Vec<String> = Vec::new();
 
+    let (track_timings, duplicates) = resolve_duplicates(overlay, options.duplicates);
+    match options.duplicates {
+        DuplicateStrategy::Allow => {}
+        DuplicateStrategy::Error if !duplicates.is_empty() => {
+            return Err(MergeError::DuplicateReferences(duplicates));
+        }
+        _ => warnings.extend(duplicates),
+    }
+
     // Index all base libretto segments by ID for O(1) lookup
     let segment_map: HashMap<&str, &Segment> = base.numbers.iter()
         .flat_map(|n| n.segments.iter())
         .map(|s| (s.id.as_str(), s))
         .collect();
 
-    // Also index number metadata by segment ID → (act, scene)
-    let mut segment_context: HashMap<&str, (&str, Option<&str>)> = HashMap::new();
+    // Also index number metadata by segment ID → (number_id, act, scene)
+    let mut segment_context: HashMap<&str, (&str, &str, Option<&str>)> = HashMap::new();
     for number in &base.numbers {
         for seg in &number.segments {
-            segment_context.insert(seg.id.as_str(), (number.act.as_str(), number.scene.as_deref()));
+            segment_context.insert(
+                seg.id.as_str(),
+                (number.id.as_str(), number.act.as_str(), number.scene.as_deref()),
+            );
         }
     }
 
@@ -49,13 +113,17 @@ pub fn merge(base: &BaseLibretto, overlay: &TimingOverlay) -> MergeResult {
     let resolve_candidates = resolve::build_segment_index(base);
     let all_nids: Vec<String> = overlay.covered_number_ids().iter().map(|s| s.to_string()).collect();
 
-    let tracks: Vec<InterchangeTrack> = overlay.track_timings.iter()
+    let mut tracks: Vec<InterchangeTrack> = track_timings.iter()
         .enumerate()
         .map(|(i, track)| merge_track(
             track, i, &segment_map, &segment_context,
             &overlay.recording, &resolve_candidates, &all_nids, &mut warnings,
         ))
         .collect();
+    let untimed_segments = match options.include_untimed {
+        true => insert_untimed(base, overlay, &mut tracks),
+        false => 0,
+    };
 
     let total_segments: usize = tracks.iter().map(|t| t.segments.len()).sum();
     let total_base_segments: usize = base.numbers.iter().map(|n| n.segments.len()).sum();
@@ -63,27 +131,73 @@ pub fn merge(base: &BaseLibretto, overlay: &TimingOverlay) -> MergeResult {
         .map(|t| t.segment_times.len())
         .sum();
 
-    MergeResult {
+    Ok(MergeResult {
         libretto: InterchangeLibretto {
             version: "1.0".to_string(),
             opera,
+            text_rights: base.rights.clone(),
+            timing_rights: overlay.rights.clone(),
             tracks,
+            ..Default::default()
         },
         stats: MergeStats {
             base_segments: total_base_segments,
             overlay_references: referenced_ids,
             merged_segments: total_segments,
+            untimed_segments,
             tracks: overlay.track_timings.len(),
         },
         warnings,
-    }
+    })
+}
+
+/// The overlay's tracks to merge, without repeated segment references under
+/// `DuplicateStrategy::KeepFirst`, and a description of each repeat with
+/// the tracks and positions (1-based) of it and the first reference.
+fn resolve_duplicates(overlay: &TimingOverlay, strategy: DuplicateStrategy) -> (Vec<Cow<'_, TrackTiming>>, Vec<String>) {
+    let mut first: HashMap<&str, (&str, usize)> = HashMap::new();
+    let mut duplicates = Vec::new();
+    let tracks = overlay.track_timings.iter()
+        .map(|track| {
+            let mut repeated = HashSet::new();
+            for (i, st) in track.segment_times.iter().enumerate() {
+                match first.get(st.segment_id.as_str()) {
+                    Some(&(first_track, first_position)) => {
+                        duplicates.push(format!(
+                            "Track '{}': segment '{}' at position {} is already on track '{first_track}' at position {first_position}{}",
+                            track.track_title,
+                            st.segment_id,
+                            i + 1,
+                            if strategy == DuplicateStrategy::KeepFirst { ", leaving it out" } else { "" },
+                        ));
+                        repeated.insert(i);
+                    }
+                    None => {
+                        first.insert(&st.segment_id, (&track.track_title, i + 1));
+                    }
+                }
+            }
+            if strategy != DuplicateStrategy::KeepFirst || repeated.is_empty() {
+                return Cow::Borrowed(track);
+            }
+            let mut kept = track.clone();
+            kept.segment_times = track.segment_times.iter()
+                .enumerate()
+                .filter(|(i, _)| !repeated.contains(i))
+                .map(|(_, st)| st.clone())
+                .collect();
+            Cow::Owned(kept)
+        })
+        .collect();
+    (tracks, duplicates)
 }
 
+#[allow(clippy::too_many_arguments)]
 fn merge_track(
     track: &TrackTiming,
     index: usize,
     segment_map: &HashMap<&str, &Segment>,
-    segment_context: &HashMap<&str, (&str, Option<&str>)>,
+    segment_context: &HashMap<&str, (&str, &str, Option<&str>)>,
     recording: &crate::timing_overlay::RecordingMetadata,
     resolve_candidates: &[resolve::SegCandidate<'_>],
     all_nids: &[String],
@@ -99,7 +213,7 @@ fn merge_track(
     }
 
     let mut current_is_recitative = false;
-    let segments: Vec<InterchangeSegment> = track.segment_times.iter()
+    let mut segments: Vec<InterchangeSegment> = track.segment_times.iter()
         .enumerate()
         .map(|(j, st)| {
             // Update recitative state at section boundaries
@@ -117,8 +231,10 @@ fn merge_track(
 
             let ctx = segment_context.get(st.segment_id.as_str());
 
-            // Compute end time: next segment's start, or track duration
-            let end = if j + 1 < track.segment_times.len() {
+            // Compute end time: explicit end, next segment's start, or track duration
+            let end = if st.end.is_some() {
+                st.end
+            } else if j + 1 < track.segment_times.len() {
                 Some(track.segment_times[j + 1].start)
             } else {
                 track.duration_seconds
@@ -141,22 +257,30 @@ fn merge_track(
                 text: base_seg.and_then(|s| s.text.clone()),
                 translation: base_seg.and_then(|s| s.translation.clone()),
                 direction: base_seg.and_then(|s| s.direction.clone()),
-                act: ctx.map(|(act, _)| act.to_string()),
-                scene: ctx.and_then(|(_, scene)| scene.map(|s| s.to_string())),
+                act: ctx.map(|(_, act, _)| act.to_string()),
+                scene: ctx.and_then(|(_, _, scene)| scene.map(|s| s.to_string())),
                 group: base_seg.and_then(|s| s.group.clone()),
+                segment_id: Some(st.segment_id.clone()),
+                number_id: ctx.map(|(nid, _, _)| nid.to_string()),
+                ..Default::default()
             }
         })
         .collect();
 
+    // An instrumental track without times is one interlude over its length
+    if track.instrumental && segments.is_empty() {
+        segments.push(InterchangeSegment {
+            end: track.duration_seconds,
+            segment_type: "interlude".to_string(),
+            number_id: track.number_ids.first().cloned(),
+            ..Default::default()
+        });
+    }
+
     // Derive act from the first segment's context, if available
     let act = segments.first().and_then(|s| s.act.clone());
 
-    // Build track ID from disc/track number or index
-    let track_id = match (track.disc_number, track.track_number) {
-        (Some(d), Some(t)) => format!("d{d}-t{t}"),
-        (None, Some(t)) => format!("t{t}"),
-        _ => format!("track-{}", index + 1),
-    };
+    let track_id = track.track_id(index);
 
     // Artist from recording metadata
     let artist = recording.conductor.as_ref().map(|c| {
@@ -176,12 +300,101 @@ fn merge_track(
         track_number: track.track_number,
         duration_seconds: track.duration_seconds,
         act,
-        scene: None,
         segments,
+        ..Default::default()
+    }
+}
+
+/// Put the base segments no track times into `tracks`, marked untimed,
+/// returning how many were added.
+///
+/// Segments of omitted numbers, or skipped on a track, are left out, as are
+/// those of numbers no track lists. A segment goes on the track listing its
+/// number; where several do, the track holding the nearest timed segment
+/// before it in the base libretto (or else after it). Within the track it
+/// goes before the first segment that follows it in the base libretto.
+fn insert_untimed(base: &BaseLibretto, overlay: &TimingOverlay, tracks: &mut [InterchangeTrack]) -> usize {
+    let position: HashMap<&str, usize> = base.segment_ids().into_iter()
+        .enumerate()
+        .map(|(i, id)| (id, i))
+        .collect();
+    let left_out: HashSet<&str> = overlay.track_timings.iter()
+        .flat_map(|t| {
+            let timed = t.segment_times.iter().map(|st| st.segment_id.as_str());
+            timed.chain(t.skip_segment_ids.iter().map(|id| id.as_str()))
+        })
+        .collect();
+    let omitted: HashSet<&str> = overlay.omitted_number_ids().into_iter().collect();
+    let timed_positions: Vec<Vec<usize>> = overlay.track_timings.iter()
+        .map(|t| t.segment_times.iter().filter_map(|st| position.get(st.segment_id.as_str()).copied()).collect())
+        .collect();
+
+    let mut pending: Vec<Vec<(usize, InterchangeSegment)>> = vec![Vec::new(); tracks.len()];
+    let mut added = 0;
+    for number in base.numbers.iter().filter(|n| !omitted.contains(n.id.as_str())) {
+        let listing: Vec<usize> = overlay.track_timings.iter()
+            .enumerate()
+            .filter(|(_, t)| !t.instrumental && t.number_ids.contains(&number.id))
+            .map(|(i, _)| i)
+            .collect();
+        let Some(&first) = listing.first() else {
+            continue;
+        };
+        for seg in number.segments.iter().filter(|s| !left_out.contains(s.id.as_str())) {
+            let pos = position[seg.id.as_str()];
+            let before = listing.iter()
+                .filter_map(|&t| timed_positions[t].iter().filter(|&&p| p < pos).max().map(|&p| (p, t)))
+                .max();
+            let after = listing.iter()
+                .filter_map(|&t| timed_positions[t].iter().filter(|&&p| p > pos).min().map(|&p| (p, t)))
+                .min();
+            let track = before.or(after).map_or(first, |(_, t)| t);
+            pending[track].push((pos, untimed_segment(number, seg)));
+            added += 1;
+        }
+    }
+
+    for (track, untimed) in tracks.iter_mut().zip(pending) {
+        if untimed.is_empty() {
+            continue;
+        }
+        let mut untimed = untimed.into_iter().peekable();
+        let mut segments = Vec::with_capacity(track.segments.len() + untimed.len());
+        for seg in std::mem::take(&mut track.segments) {
+            if let Some(&p) = seg.segment_id.as_deref().and_then(|id| position.get(id)) {
+                while let Some((_, u)) = untimed.next_if(|(u, _)| *u < p) {
+                    segments.push(u);
+                }
+            }
+            segments.push(seg);
+        }
+        segments.extend(untimed.map(|(_, u)| u));
+        track.segments = segments;
+    }
+    added
+}
+
+/// A segment of `number` with no time, for `insert_untimed`.
+fn untimed_segment(number: &MusicalNumber, seg: &Segment) -> InterchangeSegment {
+    InterchangeSegment {
+        start: f64::NAN,
+        segment_type: format!("{:?}", seg.segment_type).to_lowercase(),
+        character: seg.character.clone(),
+        text: seg.text.clone(),
+        translation: seg.translation.clone(),
+        direction: seg.direction.clone(),
+        act: Some(number.act.clone()),
+        scene: number.scene.clone(),
+        group: seg.group.clone(),
+        segment_id: Some(seg.id.clone()),
+        number_id: Some(number.id.clone()),
+        untimed: true,
+        ..Default::default()
     }
 }
 
 /// Result of a merge operation.
+#[derive(Debug)]
 pub struct MergeResult {
     pub libretto: InterchangeLibretto,
     pub stats: MergeStats,
@@ -189,54 +402,224 @@ pub struct MergeResult {
 }
 
 /// Statistics about the merge.
+#[derive(Debug)]
 pub struct MergeStats {
     pub base_segments: usize,
     pub overlay_references: usize,
     pub merged_segments: usize,
+    /// Untimed segments included (with `include_untimed`), counted in
+    /// `merged_segments`.
+    pub untimed_segments: usize,
     pub tracks: usize,
 }
 
+/// Merge an annotation overlay into an interchange libretto for display.
+///
+/// Annotations are kept only when their target segment or number appears
+/// in the document (matched via the `segment_id` / `number_id` carried on
+/// interchange segments), so notes on numbers a recording omits are dropped.
+/// Returns a warning for each annotation that was dropped.
+pub fn merge_annotations(doc: &mut InterchangeLibretto, annotations: &AnnotationOverlay) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let segments = doc.tracks.iter().flat_map(|t| t.segments.iter());
+    let segment_ids: HashSet<&str> = segments.clone()
+        .filter_map(|s| s.segment_id.as_deref())
+        .collect();
+    let number_ids: HashSet<&str> = segments
+        .filter_map(|s| s.number_id.as_deref())
+        .collect();
+
+    let mut kept = Vec::new();
+    for annotation in &annotations.annotations {
+        let present = match (&annotation.segment_id, &annotation.number_id) {
+            (Some(sid), _) => segment_ids.contains(sid.as_str()),
+            (None, Some(nid)) => number_ids.contains(nid.as_str()),
+            (None, None) => false,
+        };
+        if present {
+            kept.push(annotation.clone());
+        } else {
+            let target = annotation.segment_id.as_deref()
+                .or(annotation.number_id.as_deref())
+                .unwrap_or("<none>");
+            warnings.push(format!("Annotation target '{target}' not present in interchange, skipping"));
+        }
+    }
+
+    doc.annotations.extend(kept);
+    warnings
+}
+
+/// Merge a pronunciation overlay into an interchange libretto for display.
+///
+/// Each segment the overlay has a pronunciation for gets it, line by line
+/// under its text, and the overlay's notation and rights go on the
+/// document. Returns a warning for each segment the overlay names that is
+/// not in the document.
+pub fn merge_pronunciations(doc: &mut InterchangeLibretto, pronunciations: &PronunciationOverlay) -> Vec<String> {
+    let mut present = HashSet::new();
+    let mut merged = false;
+    for seg in doc.tracks.iter_mut().flat_map(|t| t.segments.iter_mut()) {
+        let Some(sid) = seg.segment_id.as_deref() else { continue };
+        present.insert(sid.to_string());
+        let lines = seg.text.as_deref().map_or(0, |t| t.lines().count());
+        if let Some(text) = pronunciations.text_for(sid, lines) {
+            seg.pronunciation = Some(text);
+            merged = true;
+        }
+    }
+    if merged {
+        doc.pronunciation_notation = Some(pronunciations.notation);
+        doc.pronunciation_rights = pronunciations.rights.clone();
+    }
+
+    let mut missing: Vec<&str> = pronunciations.pronunciations.iter()
+        .map(|p| p.segment_id.as_str())
+        .filter(|sid| !present.contains(*sid))
+        .collect();
+    missing.dedup();
+    missing.into_iter()
+        .map(|sid| format!("Pronunciation for segment '{sid}' not present in interchange, skipping"))
+        .collect()
+}
+
+/// The least time a merged synopsis is on screen for.
+pub const MIN_SYNOPSIS_SECONDS: f64 = 5.0;
+
+/// Merge a synopsis overlay into an interchange libretto, as a `"synopsis"`
+/// segment where each number starts.
+///
+/// A number's synopsis goes before its first timed segment in the document.
+/// Like an interlude, it covers the time before the number's first line:
+/// from the start of the track, or from the explicit `end` of the segment
+/// before. Where that leaves it less than `MIN_SYNOPSIS_SECONDS`, as when
+/// the number follows straight on, it takes the last of the time of the
+/// segment before instead, ending that segment early, so a display has
+/// time to show it. The synopsis is the segment's `direction`, which
+/// displays show for a segment without text, and its segment ID is the
+/// number's with `-synopsis`. The overlay's language and rights go on the
+/// document.
+///
+/// Returns a warning for each synopsis whose number is not in the document.
+pub fn merge_synopses(doc: &mut InterchangeLibretto, synopses: &SynopsisOverlay) -> Vec<String> {
+    let mut placed = HashSet::new();
+    for track in &mut doc.tracks {
+        // The timed segment before, if any
+        let mut previous: Option<usize> = None;
+        let mut i = 0;
+        while i < track.segments.len() {
+            let seg = &track.segments[i];
+            if seg.untimed {
+                i += 1;
+                continue;
+            }
+            let synopsis = seg.number_id.clone()
+                .and_then(|nid| synopses.for_number(&nid).map(|text| (nid, text)))
+                .filter(|(nid, _)| !placed.contains(nid));
+            if let Some((nid, text)) = synopsis {
+                placed.insert(nid.clone());
+                let (floor, gap_start) = match previous.map(|p| &track.segments[p]) {
+                    None => (0.0, 0.0),
+                    Some(prev) => (prev.start, prev.end.unwrap_or(seg.start)),
+                };
+                let gap_start = gap_start.clamp(floor, seg.start.max(floor));
+                let start = if seg.start - gap_start >= MIN_SYNOPSIS_SECONDS {
+                    gap_start
+                } else {
+                    (seg.start - MIN_SYNOPSIS_SECONDS).clamp(floor, gap_start)
+                };
+                if let Some(prev) = previous.map(|p| &mut track.segments[p]) {
+                    if prev.end.is_some_and(|end| end > start) {
+                        prev.end = Some(start);
+                    }
+                }
+                let seg = &track.segments[i];
+                let segment = InterchangeSegment {
+                    start,
+                    end: Some(seg.start),
+                    segment_type: "synopsis".to_string(),
+                    direction: Some(text.to_string()),
+                    act: seg.act.clone(),
+                    scene: seg.scene.clone(),
+                    segment_id: Some(format!("{nid}-synopsis")),
+                    number_id: Some(nid),
+                    ..Default::default()
+                };
+                track.segments.insert(i, segment);
+                i += 1;
+            }
+            previous = Some(i);
+            i += 1;
+        }
+    }
+    if !placed.is_empty() {
+        doc.synopsis_language = synopses.language.clone();
+        doc.synopsis_rights = synopses.rights.clone();
+    }
+
+    synopses.synopses.keys()
+        .filter(|nid| !placed.contains(nid.as_str()))
+        .map(|nid| format!("Synopsis for number '{nid}' not present in interchange, skipping"))
+        .collect()
+}
+
+/// Options for generating a scaffold overlay.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScaffoldOptions {
+    /// Put each recitative on the track of the number after it (within the
+    /// same act), as recordings almost always do, instead of on its own.
+    pub group_recitatives: bool,
+}
+
 /// Generate a scaffold TimingOverlay from a BaseLibretto.
 ///
 /// Creates one TrackTiming per musical number, with all segment IDs
 /// listed but start times set to 0.0. This gives a template to fill
 /// in with actual timing data.
 pub fn scaffold_overlay(base: &BaseLibretto, base_path: &str) -> TimingOverlay {
-    let track_timings: Vec<TrackTiming> = base.numbers.iter()
-        .map(|number| {
-            let segment_times: Vec<crate::timing_overlay::SegmentTime> = number.segments.iter()
-                .map(|seg| crate::timing_overlay::SegmentTime {
+    scaffold_overlay_with(base, base_path, &ScaffoldOptions::default())
+}
+
+/// Generate a scaffold as `scaffold_overlay` does, with `options`.
+///
+/// A track holding several numbers lists them all in `number_ids`, and its
+/// title joins their labels.
+pub fn scaffold_overlay_with(base: &BaseLibretto, base_path: &str, options: &ScaffoldOptions) -> TimingOverlay {
+    let mut groups: Vec<Vec<&MusicalNumber>> = Vec::new();
+    let mut current: Vec<&MusicalNumber> = Vec::new();
+    for (i, number) in base.numbers.iter().enumerate() {
+        current.push(number);
+        let leads_into_next = options.group_recitatives
+            && number.number_type == NumberType::Recitative
+            && base.numbers.get(i + 1).is_some_and(|next| next.act == number.act);
+        if !leads_into_next {
+            groups.push(std::mem::take(&mut current));
+        }
+    }
+
+    let mut overlay = TimingOverlay::new(base_path);
+    overlay.track_timings = groups.into_iter()
+        .map(|numbers| {
+            let segment_times: Vec<SegmentTime> = numbers.iter()
+                .flat_map(|number| &number.segments)
+                .map(|seg| SegmentTime {
                     segment_id: seg.id.clone(),
                     start: 0.0,
+                    end: None,
+                    source: None,
                 })
                 .collect();
 
             TrackTiming {
-                track_title: number.label.clone(),
-                disc_number: None,
-                track_number: None,
-                duration_seconds: None,
-                number_ids: vec![number.id.clone()],
-                start_segment_id: None,
+                track_title: numbers.iter().map(|n| n.label.as_str()).collect::<Vec<_>>().join("; "),
+                number_ids: numbers.iter().map(|n| n.id.clone()).collect(),
                 segment_times,
+                ..Default::default()
             }
         })
         .collect();
-
-    TimingOverlay {
-        version: "1.0".to_string(),
-        base_libretto: base_path.to_string(),
-        recording: crate::timing_overlay::RecordingMetadata {
-            conductor: None,
-            orchestra: None,
-            year: None,
-            label: None,
-            album_title: None,
-        },
-        contributors: Vec::new(),
-        track_timings,
-        omitted_numbers: Vec::new(),
-    }
+    overlay
 }
 
 #[cfg(test)]
@@ -295,20 +678,19 @@ mod tests {
                 label: Some("EMI".to_string()),
                 album_title: Some("Le nozze di Figaro".to_string()),
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![TrackTiming {
                 track_title: "Cinque... dieci...".to_string(),
                 disc_number: Some(1),
                 track_number: Some(2),
                 duration_seconds: Some(195.0),
                 number_ids: vec!["no-1-duettino".to_string()],
-                start_segment_id: None,
                 segment_times: vec![
-                    SegmentTime { segment_id: "no-1-duettino-001".to_string(), start: 0.0 },
-                    SegmentTime { segment_id: "no-1-duettino-002".to_string(), start: 12.5 },
+                    SegmentTime { segment_id: "no-1-duettino-001".to_string(), start: 0.0, end: None, source: None },
+                    SegmentTime { segment_id: "no-1-duettino-002".to_string(), start: 12.5, end: None, source: None },
                 ],
+                ..Default::default()
             }],
+            ..Default::default()
         }
     }
 
@@ -336,6 +718,8 @@ mod tests {
         assert_eq!(seg0.translation.as_deref(), Some("Five... ten..."));
         assert_eq!(seg0.act.as_deref(), Some("1"));
         assert_eq!(seg0.scene.as_deref(), Some("1"));
+        assert_eq!(seg0.segment_id.as_deref(), Some("no-1-duettino-001"));
+        assert_eq!(seg0.number_id.as_deref(), Some("no-1-duettino"));
 
         let seg1 = &track.segments[1];
         assert_eq!(seg1.start, 12.5);
@@ -343,12 +727,29 @@ mod tests {
         assert_eq!(seg1.character.as_deref(), Some("SUSANNA"));
     }
 
+    #[test]
+    fn test_merge_instrumental_track() {
+        let base = sample_base();
+        let mut overlay = sample_overlay();
+        let track = &mut overlay.track_timings[0];
+        track.track_title = "Sinfonia".to_string();
+        track.instrumental = true;
+        track.number_ids.clear();
+        track.segment_times.clear();
+
+        let result = merge(&base, &overlay);
+        let segments = &result.libretto.tracks[0].segments;
+        assert_eq!(segments.len(), 1);
+        assert_eq!((segments[0].start, segments[0].end), (0.0, Some(195.0)));
+        assert_eq!(segments[0].segment_type, "interlude");
+    }
+
     #[test]
     fn test_merge_unknown_segment() {
         let base = sample_base();
         let mut overlay = sample_overlay();
         overlay.track_timings[0].segment_times.push(
-            SegmentTime { segment_id: "no-1-duettino-999".to_string(), start: 50.0 }
+            SegmentTime { segment_id: "no-1-duettino-999".to_string(), start: 50.0, end: None, source: None }
         );
 
         let result = merge(&base, &overlay);
@@ -368,6 +769,40 @@ mod tests {
         assert_eq!(overlay.track_timings[0].segment_times[0].start, 0.0);
     }
 
+    #[test]
+    fn test_scaffold_grouping_recitatives() {
+        let mut base = sample_base();
+        let template = base.numbers[0].clone();
+        let number = |id: &str, number_type: NumberType, act: &str| MusicalNumber {
+            id: id.to_string(),
+            label: id.to_string(),
+            number_type,
+            act: act.to_string(),
+            segments: vec![],
+            ..template.clone()
+        };
+        base.numbers = vec![
+            number("recit-1", NumberType::Recitative, "1"),
+            number("recit-2", NumberType::Recitative, "1"),
+            number("aria", NumberType::Aria, "1"),
+            // The act ends before anything follows this recitative
+            number("recit-3", NumberType::Recitative, "1"),
+            number("chorus", NumberType::Chorus, "2"),
+        ];
+
+        let options = ScaffoldOptions { group_recitatives: true };
+        let overlay = scaffold_overlay_with(&base, "base.libretto.json", &options);
+        let tracks: Vec<(&str, Vec<&str>)> = overlay.track_timings.iter()
+            .map(|t| (t.track_title.as_str(), t.number_ids.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(tracks, vec![
+            ("recit-1; recit-2; aria", vec!["recit-1", "recit-2", "aria"]),
+            ("recit-3", vec!["recit-3"]),
+            ("chorus", vec!["chorus"]),
+        ]);
+        assert_eq!(scaffold_overlay(&base, "base.libretto.json").track_timings.len(), 5);
+    }
+
     #[test]
     fn test_merge_stats() {
         let base = sample_base();
@@ -379,4 +814,184 @@ mod tests {
         assert_eq!(result.stats.merged_segments, 2);
         assert_eq!(result.stats.tracks, 1);
     }
+
+    #[test]
+    fn test_merge_duplicate_strategies() {
+        let base = sample_base();
+        let mut overlay = sample_overlay();
+        let mut second = overlay.track_timings[0].clone();
+        second.track_title = "Ora sì".to_string();
+        second.track_number = Some(3);
+        second.segment_times.remove(0);
+        second.segment_times[0].start = 0.0;
+        overlay.track_timings.push(second);
+        let options = |duplicates| MergeOptions { duplicates, ..Default::default() };
+
+        let result = merge(&base, &overlay);
+        assert_eq!(result.warnings, vec![
+            "Track 'Ora sì': segment 'no-1-duettino-002' at position 1 is already on track 'Cinque... dieci...' at position 2",
+        ]);
+        assert_eq!(result.libretto.tracks[1].segments.len(), 1);
+
+        let result = merge_with(&base, &overlay, &options(DuplicateStrategy::Allow)).unwrap();
+        assert!(result.warnings.is_empty());
+
+        let result = merge_with(&base, &overlay, &options(DuplicateStrategy::KeepFirst)).unwrap();
+        assert!(result.warnings[0].ends_with("leaving it out"));
+        assert!(result.libretto.tracks[1].segments.is_empty());
+        assert_eq!(result.libretto.tracks[0].segments.len(), 2);
+
+        let error = merge_with(&base, &overlay, &options(DuplicateStrategy::Error)).unwrap_err();
+        assert!(matches!(error, MergeError::DuplicateReferences(ref d) if d.len() == 1));
+    }
+
+    #[test]
+    fn test_merge_include_untimed() {
+        let base = sample_base();
+        let mut overlay = sample_overlay();
+        overlay.track_timings[0].segment_times.remove(0);
+        let options = MergeOptions { include_untimed: true, ..Default::default() };
+        let result = merge_with(&base, &overlay, &options).unwrap();
+
+        assert_eq!(result.stats.untimed_segments, 1);
+        let segments = &result.libretto.tracks[0].segments;
+        assert_eq!(segments.len(), 2);
+        assert!(segments[0].untimed && segments[0].start.is_nan() && segments[0].end.is_none());
+        assert_eq!(segments[0].segment_id.as_deref(), Some("no-1-duettino-001"));
+        assert_eq!(segments[0].character.as_deref(), Some("FIGARO"));
+        assert!(!segments[1].untimed);
+        assert!(result.libretto.tracks[0].segment_at(1.0).is_none());
+
+        // The start is written as null and read back as untimed
+        let json = serde_json::to_value(&result.libretto).unwrap();
+        assert!(json["tracks"][0]["segments"][0]["start"].is_null());
+        assert!(json["tracks"][0]["segments"][1].get("untimed").is_none());
+        let read: InterchangeLibretto = serde_json::from_value(json).unwrap();
+        assert!(read.tracks[0].segments[0].untimed && read.tracks[0].segments[0].start.is_nan());
+
+        // Skipped segments stay out
+        overlay.track_timings[0].skip_segment_ids.push("no-1-duettino-001".to_string());
+        assert_eq!(merge_with(&base, &overlay, &options).unwrap().stats.untimed_segments, 0);
+    }
+
+    #[test]
+    fn test_merge_annotations() {
+        let mut doc = merge(&sample_base(), &sample_overlay()).libretto;
+        let note = |segment_id: Option<&str>, number_id: Option<&str>| crate::annotation_overlay::Annotation {
+            segment_id: segment_id.map(String::from),
+            number_id: number_id.map(String::from),
+            kind: crate::annotation_overlay::AnnotationKind::Note,
+            title: None,
+            text: "A note.".to_string(),
+            author: None,
+        };
+        let annotations = AnnotationOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            contributors: vec![],
+            rights: None,
+            annotations: vec![
+                note(Some("no-1-duettino-001"), None),
+                note(None, Some("no-1-duettino")),
+                note(None, Some("no-24-aria")),
+            ],
+        };
+
+        let warnings = merge_annotations(&mut doc, &annotations);
+        assert_eq!(doc.annotations.len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no-24-aria"));
+    }
+
+    #[test]
+    fn test_merge_synopses() {
+        let mut overlay = sample_overlay();
+        overlay.track_timings[0].segment_times[0].start = 8.0;
+        let mut doc = merge(&sample_base(), &overlay).libretto;
+        let synopses = SynopsisOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            contributors: vec![],
+            rights: None,
+            language: Some("en".to_string()),
+            synopses: [
+                ("no-1-duettino", "Figaro measures the room."),
+                ("no-24-aria", "Figaro rails against women."),
+            ]
+            .into_iter()
+            .map(|(nid, text)| (nid.to_string(), text.to_string()))
+            .collect(),
+        };
+
+        let warnings = merge_synopses(&mut doc, &synopses);
+        let segments = &doc.tracks[0].segments;
+        assert_eq!(segments.len(), 3);
+        // Over the introduction, before the number's first line
+        let synopsis = &segments[0];
+        assert_eq!((synopsis.segment_type.as_str(), synopsis.start, synopsis.end), ("synopsis", 0.0, Some(8.0)));
+        assert_eq!(synopsis.direction.as_deref(), Some("Figaro measures the room."));
+        assert_eq!(synopsis.segment_id.as_deref(), Some("no-1-duettino-synopsis"));
+        assert_eq!(segments[1].segment_id.as_deref(), Some("no-1-duettino-001"));
+        assert_eq!(doc.synopsis_language.as_deref(), Some("en"));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no-24-aria"));
+    }
+
+    #[test]
+    fn test_merge_synopses_without_ends() {
+        // A number following straight on from segments without an `end`
+        let mut doc = merge(&sample_base(), &sample_overlay()).libretto;
+        for seg in &mut doc.tracks[0].segments {
+            seg.end = None;
+        }
+        doc.tracks[0].segments[1].number_id = Some("no-2-cavatina".to_string());
+        let synopses = SynopsisOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            contributors: vec![],
+            rights: None,
+            language: None,
+            synopses: [("no-2-cavatina".to_string(), "Figaro sees through the Count.".to_string())].into(),
+        };
+
+        assert!(merge_synopses(&mut doc, &synopses).is_empty());
+        let track = &doc.tracks[0];
+        let synopsis = &track.segments[1];
+        assert_eq!((synopsis.start, synopsis.end), (12.5 - MIN_SYNOPSIS_SECONDS, Some(12.5)));
+        assert_eq!(track.segments[0].end, None);
+        let shown = track.segment_at(10.0).and_then(|s| s.segment_id.as_deref());
+        assert_eq!(shown, Some("no-2-cavatina-synopsis"));
+        let shown = track.cursor().at(10.0).and_then(|s| s.segment_id.as_deref());
+        assert_eq!(shown, Some("no-2-cavatina-synopsis"));
+    }
+
+    #[test]
+    fn test_merge_pronunciations() {
+        let mut doc = merge(&sample_base(), &sample_overlay()).libretto;
+        let pronounce = |segment_id: &str, line: Option<usize>, text: &str| crate::pronunciation_overlay::Pronunciation {
+            segment_id: segment_id.to_string(),
+            line,
+            text: text.to_string(),
+        };
+        let pronunciations = PronunciationOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            contributors: vec![],
+            rights: None,
+            notation: crate::pronunciation_overlay::PronunciationNotation::Ipa,
+            pronunciations: vec![
+                pronounce("no-1-duettino-002", Some(1), "ˈo.ra ˈsi kˈki.o son konˈtɛn.ta"),
+                pronounce("no-24-aria-001", None, "ˈa.pri.te un pɔ"),
+                pronounce("no-24-aria-001", Some(2), "kwɛʎˈʎɔk.ki"),
+            ],
+        };
+
+        let warnings = merge_pronunciations(&mut doc, &pronunciations);
+        let segments = &doc.tracks[0].segments;
+        assert_eq!(segments[0].pronunciation, None);
+        assert_eq!(segments[1].pronunciation.as_deref(), Some("ˈo.ra ˈsi kˈki.o son konˈtɛn.ta"));
+        assert_eq!(doc.pronunciation_notation, Some(crate::pronunciation_overlay::PronunciationNotation::Ipa));
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("no-24-aria-001"));
+    }
 }
@@ -0,0 +1,18 @@
+// Per-track work spread over threads.
+//
+// Estimating or resolving one track reads the base libretto and the
+// overlay but no other track's result, so the hundreds of tracks of a box
+// set can be worked on at once and their results put back in order. The
+// WebAssembly build has no threads and works through them in turn.
+
+/// `f` applied to each item with its index, results in item order.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn map_indexed<T: Sync, R: Send>(items: &[T], f: impl Fn(usize, &T) -> R + Sync + Send) -> Vec<R> {
+    use rayon::prelude::*;
+    items.par_iter().enumerate().map(|(i, item)| f(i, item)).collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn map_indexed<T, R>(items: &[T], f: impl Fn(usize, &T) -> R) -> Vec<R> {
+    items.iter().enumerate().map(|(i, item)| f(i, item)).collect()
+}
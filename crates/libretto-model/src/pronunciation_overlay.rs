@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+use crate::rights::Rights;
+use crate::timing_overlay::Contributor;
+
+/// A pronunciation overlay: how a base libretto's original text sounds,
+/// in IPA or a simplified phonetic spelling, keyed by segment ID.
+///
+/// Like an annotation overlay, this is a separate layer over the base text,
+/// authored and licensed on its own, so a language-learner display can show
+/// the pronunciation under each line as it is sung.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PronunciationOverlay {
+    pub version: String,
+    /// Path to the base libretto this overlay references (relative to library root).
+    pub base_libretto: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+    /// Licensing for the pronunciation text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rights: Option<Rights>,
+    #[serde(default)]
+    pub notation: PronunciationNotation,
+    pub pronunciations: Vec<Pronunciation>,
+}
+
+/// The pronunciation of a segment's text, or of one line of it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Pronunciation {
+    pub segment_id: String,
+    /// Line of the segment's text, counting from 1; the whole text when
+    /// absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub text: String,
+}
+
+/// How pronunciations are written.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PronunciationNotation {
+    /// The International Phonetic Alphabet.
+    #[default]
+    Ipa,
+    /// A respelling for readers of the translation language
+    /// ("KAH-ro MEE-oh").
+    Simplified,
+}
+
+impl PronunciationOverlay {
+    /// Pronunciations attached to the given segment ID.
+    pub fn for_segment<'a>(&'a self, segment_id: &'a str) -> impl Iterator<Item = &'a Pronunciation> {
+        self.pronunciations.iter().filter(move |p| p.segment_id == segment_id)
+    }
+
+    /// The pronunciation to show under a segment whose text has `lines`
+    /// lines: a whole-segment pronunciation as written, or the line
+    /// pronunciations one per line, blank for lines without one, so the
+    /// two line up. `None` when the segment has no pronunciation.
+    pub fn text_for(&self, segment_id: &str, lines: usize) -> Option<String> {
+        let mut by_line = vec![""; lines];
+        let mut found = false;
+        for pronunciation in self.for_segment(segment_id) {
+            match pronunciation.line {
+                None => return Some(pronunciation.text.clone()),
+                Some(line) if (1..=lines).contains(&line) => {
+                    by_line[line - 1] = &pronunciation.text;
+                    found = true;
+                }
+                Some(_) => {}
+            }
+        }
+        found.then(|| by_line.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_for_lines() {
+        let json = r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "pronunciations": [
+                { "segment_id": "no-1-001", "text": "ˈtʃin.kwe ˈdjɛ.tʃi" },
+                { "segment_id": "no-1-002", "line": 1, "text": "ˈven.ti" },
+                { "segment_id": "no-1-002", "line": 3, "text": "ˈtren.ta" },
+                { "segment_id": "no-1-003", "line": 4, "text": "out of range" }
+            ]
+        }"#;
+        let overlay: PronunciationOverlay = serde_json::from_str(json).unwrap();
+        assert_eq!(overlay.notation, PronunciationNotation::Ipa);
+        assert_eq!(overlay.text_for("no-1-001", 2).as_deref(), Some("ˈtʃin.kwe ˈdjɛ.tʃi"));
+        assert_eq!(overlay.text_for("no-1-002", 3).as_deref(), Some("ˈven.ti\n\nˈtren.ta"));
+        assert_eq!(overlay.text_for("no-1-003", 2), None);
+        assert_eq!(overlay.text_for("no-1-004", 1), None);
+    }
+}
@@ -0,0 +1,153 @@
+// Regenerating the segment IDs of a base libretto.
+//
+// `libretto parse` numbers each number's segments in order
+// (`no-9-aria-001`, `-002`, ...). Splitting or inserting a segment by hand
+// leaves a gap or an out-of-place ID, and renumbering shifts every ID after
+// it, orphaning the times overlays hold for them. `reid` gives every
+// segment a new ID under a scheme and returns a `SegmentMap` from the old
+// IDs to the new, so overlays can be rewritten in the same step (see
+// `crosswalk::retarget_overlay`) and any left behind later with
+// `timing retarget`.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::base_libretto::BaseLibretto;
+use crate::crosswalk::{comparable_text, IdMapping, SegmentMap};
+
+/// How `reid` names segments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdScheme {
+    /// `<number>-001`, `<number>-002`, ... in order, as `libretto parse`
+    /// names them.
+    Sequential,
+    /// `<number>-<hash>`, from a hash of the segment's text, so adding or
+    /// removing a segment leaves the other IDs as they were. Text repeated
+    /// within a number gets `-2`, `-3`, ... after the first.
+    Content,
+}
+
+/// Hex digits of the text hash in content IDs.
+const HASH_DIGITS: usize = 8;
+
+#[derive(Debug)]
+pub struct ReidResult {
+    /// The base libretto with its new segment IDs.
+    pub base: BaseLibretto,
+    /// Every segment's old ID and new, and every number's (unchanged), for
+    /// `retarget_overlay`.
+    pub map: SegmentMap,
+    /// Segments whose ID changed.
+    pub changed: usize,
+}
+
+/// Give every segment of `base`, found at `base_path`, an ID under `scheme`.
+pub fn reid(base: &BaseLibretto, scheme: IdScheme, base_path: &str) -> ReidResult {
+    let mut result = base.clone();
+    let mut segments = Vec::new();
+    let mut changed = 0;
+
+    for number in &mut result.numbers {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for (i, segment) in number.segments.iter_mut().enumerate() {
+            let id = match scheme {
+                IdScheme::Sequential => format!("{}-{:03}", number.id, i + 1),
+                IdScheme::Content => {
+                    let digest = format!("{:x}", Sha256::digest(comparable_text(segment).as_bytes()));
+                    let id = format!("{}-{}", number.id, &digest[..HASH_DIGITS]);
+                    let count = seen.entry(id.clone()).or_default();
+                    *count += 1;
+                    match *count {
+                        1 => id,
+                        n => format!("{id}-{n}"),
+                    }
+                }
+            };
+            if id != segment.id {
+                changed += 1;
+            }
+            segments.push(IdMapping {
+                source: std::mem::replace(&mut segment.id, id.clone()),
+                target: id,
+                confidence: 1.0,
+            });
+        }
+    }
+
+    let numbers = result.numbers.iter()
+        .map(|n| IdMapping { source: n.id.clone(), target: n.id.clone(), confidence: 1.0 })
+        .collect();
+    let map = SegmentMap {
+        version: "1.0".to_string(),
+        source_base: base_path.to_string(),
+        target_base: base_path.to_string(),
+        segments,
+        numbers,
+    };
+    ReidResult { base: result, map, changed }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_libretto::{MusicalNumber, NumberType, OperaMetadata, Segment, SegmentType};
+
+    fn base(segments: &[(&str, &str)]) -> BaseLibretto {
+        BaseLibretto {
+            version: "1.0".to_string(),
+            opera: OperaMetadata {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "Mozart".to_string(),
+                librettist: None,
+                language: "it".to_string(),
+                translation_language: None,
+                year: None,
+            },
+            cast: vec![],
+            numbers: vec![MusicalNumber {
+                id: "no-1-duettino".to_string(),
+                label: "No. 1 - Duettino".to_string(),
+                number_type: NumberType::Duet,
+                act: "1".to_string(),
+                scene: None,
+                segments: segments.iter()
+                    .map(|(id, text)| Segment {
+                        id: id.to_string(),
+                        segment_type: SegmentType::Sung,
+                        character: None,
+                        text: Some(text.to_string()),
+                        translation: None,
+                        direction: None,
+                        group: None,
+                    })
+                    .collect(),
+            }],
+            rights: None,
+        }
+    }
+
+    #[test]
+    fn test_sequential_renumbers() {
+        let libretto = base(&[("no-1-duettino-001", "Cinque..."), ("no-1-duettino-001a", "dieci..."), ("no-1-duettino-002", "venti...")]);
+        let result = reid(&libretto, IdScheme::Sequential, "base.libretto.json");
+        assert_eq!(result.base.segment_ids(), vec!["no-1-duettino-001", "no-1-duettino-002", "no-1-duettino-003"]);
+        assert_eq!(result.changed, 2);
+        assert_eq!(result.map.target_segment("no-1-duettino-001a"), Some("no-1-duettino-002"));
+        assert_eq!(result.map.target_segment("no-1-duettino-001"), Some("no-1-duettino-001"));
+        assert_eq!(result.map.target_number("no-1-duettino"), Some("no-1-duettino"));
+    }
+
+    #[test]
+    fn test_content_ids_are_stable() {
+        let before = reid(&base(&[("a", "Cinque... dieci..."), ("b", "Ora sì"), ("c", "Cinque, dieci!")]), IdScheme::Content, "b.json");
+        let after = reid(&base(&[("x", "Venti..."), ("a", "Cinque... dieci..."), ("b", "Ora sì")]), IdScheme::Content, "b.json");
+        let ids = before.base.segment_ids();
+        assert!(ids[0].starts_with("no-1-duettino-") && ids[0].len() == "no-1-duettino-".len() + HASH_DIGITS);
+        // The same words, whatever the punctuation, repeat with a suffix
+        assert_eq!(ids[2], format!("{}-2", ids[0]));
+        // Inserting a segment leaves the others' IDs alone
+        assert_eq!(after.map.target_segment("a"), before.map.target_segment("a"));
+        assert_eq!(after.map.target_segment("b"), before.map.target_segment("b"));
+    }
+}
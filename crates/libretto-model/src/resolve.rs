@@ -9,10 +9,14 @@
 // This module extracts those anchors, matches them to segments, and
 // populates `start_segment_id` on each TrackTiming.
 
+use std::sync::OnceLock;
+
+use serde::Serialize;
 use unicode_normalization::UnicodeNormalization;
 
-use crate::base_libretto::BaseLibretto;
-use crate::timing_overlay::TimingOverlay;
+use crate::base_libretto::{BaseLibretto, Segment};
+use crate::parallel;
+use crate::timing_overlay::{ResolvedBy, SectionMark, TimingOverlay, TrackTiming};
 
 /// Result of anchor resolution.
 #[derive(Debug)]
@@ -26,7 +30,7 @@ pub struct ResolveResult {
 }
 
 /// Resolution details for a single track.
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct TrackResolution {
     pub track_title: String,
     pub disc_number: Option<u32>,
@@ -37,10 +41,20 @@ pub struct TrackResolution {
     pub resolved_segment_id: Option<String>,
     /// How the match was made.
     pub match_method: Option<MatchMethod>,
+    /// How sure the match is, from 0 to 1: lower for looser match methods,
+    /// for segments outside the track's numbers, and split between
+    /// segments the anchor matches equally well. `None` when unmatched.
+    pub confidence: Option<f64>,
+    /// Other segments the first anchor matches as well as the resolved one.
+    pub alternatives: Vec<String>,
+    /// Anchors in the title that match no segment.
+    pub unmatched_anchors: Vec<String>,
+    /// The track was taken for instrumental and given no start segment.
+    pub instrumental: bool,
 }
 
 /// How an anchor was matched to a segment.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum MatchMethod {
     /// Exact prefix match on first line of segment text.
     PrefixMatch,
@@ -48,47 +62,83 @@ pub enum MatchMethod {
     NormalizedMatch,
     /// Match found via substring search within segment text.
     SubstringMatch,
+    /// Near miss: the anchor's words are close (by edit distance) to the
+    /// opening words of the segment's first line.
+    FuzzyMatch,
+    /// Matched (by any of the above) against the segment's translation,
+    /// for titles quoting the translated incipit.
+    TranslationMatch,
     /// Anchor was already set manually (preserved).
     Manual,
 }
 
 /// Extract quoted strings from a track title.
-/// Handles both straight quotes and typographic quotes.
+///
+/// Handles straight and typographic double quotes, German „…“ and »…«,
+/// French « … », and single quotes. A single quote only opens a quotation
+/// at the start of a word and closes it at the end of one, so apostrophes
+/// ("L'amour", "ch'io") are left alone.
 pub(crate) fn extract_anchors(title: &str) -> Vec<String> {
     let mut anchors = Vec::new();
-    let mut chars = title.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c == '"' || c == '\u{201c}' {
-            // Collect until closing quote
-            let close = if c == '\u{201c}' { '\u{201d}' } else { '"' };
-            let mut quoted = String::new();
-            for ch in chars.by_ref() {
-                if ch == close || ch == '"' {
-                    break;
+    let chars: Vec<char> = title.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let end = if let Some(closers) = closing_quotes(c) {
+            // Collect until closing quote, or the end of the title
+            chars[i + 1..].iter().position(|ch| closers.contains(ch)).map_or(chars.len(), |p| i + 1 + p)
+        } else if (c == '\'' || c == '\u{2018}') && !chars.get(i.wrapping_sub(1)).is_some_and(|p| p.is_alphanumeric()) {
+            let closing = (i + 1..chars.len()).find(|&j| {
+                matches!(chars[j], '\'' | '\u{2019}') && !chars.get(j + 1).is_some_and(|n| n.is_alphanumeric())
+            });
+            match closing {
+                Some(j) => j,
+                None => {
+                    i += 1;
+                    continue;
                 }
-                quoted.push(ch);
-            }
-            let trimmed = quoted.trim().to_string();
-            if !trimmed.is_empty() {
-                anchors.push(trimmed);
             }
+        } else {
+            i += 1;
+            continue;
+        };
+
+        let quoted: String = chars[i + 1..end].iter().collect();
+        let trimmed = quoted.trim().to_string();
+        if !trimmed.is_empty() {
+            anchors.push(trimmed);
         }
+        i = end + 1;
     }
 
     anchors
 }
 
+/// The characters that close a quotation opened by `open`, if it opens one.
+fn closing_quotes(open: char) -> Option<&'static [char]> {
+    match open {
+        '"' => Some(&['"']),
+        '\u{201c}' => Some(&['\u{201d}', '"']),
+        // German „…“
+        '\u{201e}' => Some(&['\u{201c}', '\u{201d}', '"']),
+        '«' => Some(&['»']),
+        // German »…«
+        '»' => Some(&['«']),
+        '‹' => Some(&['›']),
+        '›' => Some(&['‹']),
+        _ => None,
+    }
+}
+
 /// Normalize text for fuzzy matching: lowercase, strip accents, normalize punctuation.
-fn normalize_for_match(text: &str) -> String {
+pub(crate) fn normalize_for_match(text: &str) -> String {
     text.nfd()
         .filter(|c| !unicode_normalization::char::is_combining_mark(*c))
         .collect::<String>()
         .to_lowercase()
-        .replace('\u{2018}', "'")
-        .replace('\u{2019}', "'")
-        .replace('\u{201c}', "\"")
-        .replace('\u{201d}', "\"")
+        .replace(['\u{2018}', '\u{2019}'], "'")
+        .replace(['\u{201c}', '\u{201d}'], "\"")
         .replace("...", "…")
         .replace([',', ';', ':', '!', '?'], "")
         .replace("  ", " ")
@@ -103,6 +153,9 @@ pub struct TitleAnchor {
     pub is_recitative: bool,
     /// The quoted anchor text.
     pub anchor: String,
+    /// Time into the track at which the anchor's text begins, when the
+    /// title gives one as `(m:ss)` or `[m:ss]` right after the quotes.
+    pub time: Option<f64>,
 }
 
 /// Parse a track title and classify each quoted anchor as recitative or not.
@@ -119,31 +172,78 @@ pub fn classify_title_anchors(title: &str) -> Vec<TitleAnchor> {
         if let Some(pos) = title[search_from..].find(anchor.as_str()) {
             let abs_pos = search_from + pos;
             let context = title[search_from..abs_pos].to_lowercase();
+            search_from = abs_pos + anchor.len();
             result.push(TitleAnchor {
                 is_recitative: is_recitative_context(&context),
                 anchor: anchor.clone(),
+                time: timing_cue(&title[search_from..]),
             });
-            search_from = abs_pos + anchor.len();
         }
     }
 
     result
 }
 
+/// Parse a `(m:ss)` or `[m:ss]` timing cue at the start of the title text
+/// following an anchor, after its closing quote.
+fn timing_cue(after_anchor: &str) -> Option<f64> {
+    let rest = after_anchor.trim_start_matches(|c: char| "\"\u{201c}\u{201d}'\u{2019}«»‹›".contains(c) || c.is_whitespace());
+    let close = match rest.chars().next()? {
+        '(' => ')',
+        '[' => ']',
+        _ => return None,
+    };
+    let (cue, _) = rest[1..].split_once(close)?;
+    crate::import::parse_timestamp(cue.trim())
+}
+
+/// Labels of recitatives in track titles, matched at the start of words.
+const RECITATIVE_KEYWORDS: &[&str] = &["recit", "récit", "rezitativ"];
+
+/// Labels of sung numbers, in Italian, German, French and English.
+const VOCAL_KEYWORDS: &[&str] = &[
+    "aria", "arie", "arioso", "air", "duet", "duo", "cavatin", "canzon",
+    "lied", "romanz", "romance", "terzet", "trio", "quartet", "quatuor",
+    "quintet", "sestet", "sextet", "finale", "coro", "chor", "chœur", "choeur",
+];
+
+/// Labels of pieces without text.
+const INSTRUMENTAL_KEYWORDS: &[&str] = &[
+    "sinfonia", "ouvert", "overture", "marcia", "marsch", "marche", "march",
+    "entr'acte", "entracte", "zwischenspiel", "intermezzo", "interlude",
+    "preludio", "prelude", "prélude", "vorspiel", "ballet", "ballabile",
+];
+
+/// Position of the last of `keywords` in lowercase `text` that starts a word.
+fn last_keyword_at(text: &str, keywords: &[&str]) -> Option<usize> {
+    keywords.iter()
+        .filter_map(|kw| {
+            text.match_indices(kw)
+                .map(|(pos, _)| pos)
+                .filter(|&pos| !text[..pos].chars().next_back().is_some_and(char::is_alphabetic))
+                .last()
+        })
+        .max()
+}
+
+/// Whether a track title without anchors names a piece without text
+/// ("Sinfonia", "Marcia", "Entr'acte") and no sung one.
+pub fn is_instrumental_title(title: &str) -> bool {
+    let title = title.to_lowercase().replace('’', "'");
+    last_keyword_at(&title, INSTRUMENTAL_KEYWORDS).is_some()
+        && last_keyword_at(&title, VOCAL_KEYWORDS).is_none()
+        && last_keyword_at(&title, RECITATIVE_KEYWORDS).is_none()
+}
+
 /// Check whether the context text preceding a quoted anchor indicates recitative.
 ///
-/// Returns true if "recitativ" appears and is the last type-indicating keyword
-/// (i.e., no aria/duet/etc. keyword appears after it).
+/// Returns true if a recitative label ("Recitativo", "Rezitativ", "Récit")
+/// appears and is the last type-indicating keyword (i.e., no aria/duet/etc.
+/// keyword appears after it). Keywords are matched at the start of words,
+/// in Italian, German, French and English.
 fn is_recitative_context(context: &str) -> bool {
-    let recit_pos = context.rfind("recitativ");
-    let sung_keywords = [
-        "aria", "duett", "cavatina", "canzon", "terzett",
-        "quartett", "quintett", "sestett", "finale", "coro",
-        "sinfonia", "marcia",
-    ];
-    let last_sung_pos = sung_keywords.iter()
-        .filter_map(|kw| context.rfind(kw))
-        .max();
+    let recit_pos = last_keyword_at(context, RECITATIVE_KEYWORDS);
+    let last_sung_pos = last_keyword_at(context, VOCAL_KEYWORDS).max(last_keyword_at(context, INSTRUMENTAL_KEYWORDS));
 
     match (recit_pos, last_sung_pos) {
         (Some(rp), Some(sp)) => rp > sp,
@@ -154,29 +254,67 @@ fn is_recitative_context(context: &str) -> bool {
 
 /// A candidate segment for matching.
 pub(crate) struct SegCandidate<'a> {
-    segment_id: &'a str,
+    pub(crate) segment_id: &'a str,
     number_id: &'a str,
-    first_line: String,
-    full_text: String,
     first_line_norm: String,
-    full_text_norm: String,
+    pub(crate) full_text_norm: String,
+}
+
+/// The segments of a base libretto, searchable by their text and by their
+/// translations.
+///
+/// Each half is built the first time it is searched, and then shared by
+/// every track (and thread) searching it, so resolving and estimating an
+/// overlay against the same base can build it once between them.
+pub struct SegmentIndex<'a> {
+    base: &'a BaseLibretto,
+    text: OnceLock<Vec<SegCandidate<'a>>>,
+    translations: OnceLock<Vec<SegCandidate<'a>>>,
+}
+
+impl<'a> SegmentIndex<'a> {
+    pub fn new(base: &'a BaseLibretto) -> Self {
+        SegmentIndex { base, text: OnceLock::new(), translations: OnceLock::new() }
+    }
+
+    /// The base libretto indexed.
+    pub fn base(&self) -> &'a BaseLibretto {
+        self.base
+    }
+
+    pub(crate) fn text(&self) -> &[SegCandidate<'a>] {
+        self.text.get_or_init(|| build_segment_index(self.base))
+    }
+
+    pub(crate) fn translations(&self) -> &[SegCandidate<'a>] {
+        self.translations.get_or_init(|| build_translation_index(self.base))
+    }
 }
 
 /// Build a searchable index of all segments with text.
 pub(crate) fn build_segment_index(base: &BaseLibretto) -> Vec<SegCandidate<'_>> {
+    build_index_by(base, |seg| seg.text.as_deref())
+}
+
+/// Build a searchable index of all segments with a translation.
+pub(crate) fn build_translation_index(base: &BaseLibretto) -> Vec<SegCandidate<'_>> {
+    build_index_by(base, |seg| seg.translation.as_deref())
+}
+
+fn build_index_by<'a>(
+    base: &'a BaseLibretto,
+    text_of: impl Fn(&'a Segment) -> Option<&'a str>,
+) -> Vec<SegCandidate<'a>> {
     let mut candidates = Vec::new();
     for number in &base.numbers {
         for seg in &number.segments {
-            if let Some(text) = &seg.text {
-                let first_line = text.split('\n').next().unwrap_or("").to_string();
-                let full_text = text.clone();
-                let first_line_norm = normalize_for_match(&first_line);
-                let full_text_norm = normalize_for_match(&full_text);
+            if let Some(text) = text_of(seg) {
+                let first_line = text.split('\n').next().unwrap_or("");
+                let first_line_norm = normalize_for_match(first_line);
+                let full_text_norm = normalize_for_match(text);
                 candidates.push(SegCandidate {
                     segment_id: &seg.id,
                     number_id: &number.id,
-                    first_line,
-                    full_text,
                     first_line_norm,
                     full_text_norm,
                 });
@@ -194,165 +332,456 @@ fn char_prefix(s: &str, n: usize) -> &str {
     }
 }
 
-/// Try to match an anchor to a segment, preferring matches within the given number_ids.
-pub(crate) fn match_anchor(
+/// A segment an anchor matches, and how.
+#[derive(Debug, Clone)]
+pub(crate) struct AnchorMatch<'a> {
+    pub(crate) segment_id: &'a str,
+    pub(crate) method: MatchMethod,
+    /// Whether the segment is in one of the numbers searched first.
+    pub(crate) in_numbers: bool,
+    /// For fuzzy matches, how close the words are, from 0 to 1; 1 otherwise.
+    pub(crate) similarity: f64,
+}
+
+impl AnchorMatch<'_> {
+    /// Sort key, best first: match method, whether in the numbers, then
+    /// similarity (in thousandths, so near-equal fuzzy matches tie).
+    fn rank(&self) -> (u8, bool, std::cmp::Reverse<u32>) {
+        let method = match self.method {
+            MatchMethod::PrefixMatch | MatchMethod::Manual => 0,
+            MatchMethod::NormalizedMatch => 1,
+            MatchMethod::SubstringMatch => 2,
+            MatchMethod::FuzzyMatch => 3,
+            MatchMethod::TranslationMatch => 4,
+        };
+        (method, !self.in_numbers, std::cmp::Reverse((self.similarity * 1000.0).round() as u32))
+    }
+}
+
+/// Options for resolving anchors.
+#[derive(Debug, Clone, Copy)]
+pub struct ResolveOptions {
+    /// Minimum similarity (0 to 1) for the fuzzy fallback, tried when no
+    /// segment matches an anchor outright; `None` turns it off.
+    pub fuzzy_threshold: Option<f64>,
+    /// Resolve again every track not resolved by hand (`resolved_by:
+    /// manual`), instead of keeping their start segments.
+    pub force: bool,
+}
+
+impl Default for ResolveOptions {
+    fn default() -> Self {
+        Self { fuzzy_threshold: Some(0.8), force: false }
+    }
+}
+
+/// Every segment an anchor matches, best first, preferring matches within
+/// the given number_ids. Each segment appears once, with its best method;
+/// equally good matches stay in libretto order.
+pub(crate) fn score_anchor<'a>(
     anchor: &str,
     number_ids: &[String],
-    candidates: &[SegCandidate<'_>],
-) -> Option<(String, MatchMethod)> {
+    candidates: &[SegCandidate<'a>],
+) -> Vec<AnchorMatch<'a>> {
+    score_anchor_with(anchor, number_ids, candidates, None)
+}
+
+/// `score_anchor`, falling back to fuzzy matches at least `fuzzy_threshold`
+/// similar when nothing else matches.
+fn score_anchor_with<'a>(
+    anchor: &str,
+    number_ids: &[String],
+    candidates: &[SegCandidate<'a>],
+    fuzzy_threshold: Option<f64>,
+) -> Vec<AnchorMatch<'a>> {
     let anchor_norm = normalize_for_match(anchor);
     let anchor_prefix = char_prefix(&anchor_norm, 15);
 
-    // Strategy 1: Prefix match on first line (exact, within number_ids first)
-    for pass in &[true, false] {
-        let filter_nids = *pass;
-        for cand in candidates {
-            if filter_nids && !number_ids.contains(&cand.number_id.to_string()) {
-                continue;
-            }
+    let mut matches: Vec<AnchorMatch<'a>> = candidates.iter()
+        .filter_map(|cand| {
             let cand_prefix = char_prefix(&cand.first_line_norm, 15);
-            if cand.first_line_norm.starts_with(anchor_prefix)
-                || anchor_norm.starts_with(cand_prefix)
-            {
-                return Some((cand.segment_id.to_string(), MatchMethod::PrefixMatch));
-            }
+            let method = if cand.first_line_norm.starts_with(anchor_prefix) || anchor_norm.starts_with(cand_prefix) {
+                // Strategy 1: Prefix match on first line
+                MatchMethod::PrefixMatch
+            } else if cand.first_line_norm.contains(&anchor_norm) {
+                // Strategy 2: Normalized match on first line (after accent stripping)
+                MatchMethod::NormalizedMatch
+            } else if cand.full_text_norm.contains(&anchor_norm) {
+                // Strategy 3: Substring match anywhere in full text
+                MatchMethod::SubstringMatch
+            } else {
+                return None;
+            };
+            Some(AnchorMatch {
+                segment_id: cand.segment_id,
+                method,
+                in_numbers: number_ids.iter().any(|n| n == cand.number_id),
+                similarity: 1.0,
+            })
+        })
+        .collect();
+
+    // Strategy 4: Fuzzy match on the opening words of the first line
+    if let (true, Some(threshold)) = (matches.is_empty(), fuzzy_threshold) {
+        let anchor_words = match_words(&anchor_norm);
+        if !anchor_words.is_empty() {
+            matches = candidates.iter()
+                .filter_map(|cand| {
+                    let opening: Vec<&str> = match_words(&cand.first_line_norm).into_iter().take(anchor_words.len()).collect();
+                    let similarity = similarity(&anchor_words.join(" "), &opening.join(" "));
+                    (similarity >= threshold).then(|| AnchorMatch {
+                        segment_id: cand.segment_id,
+                        method: MatchMethod::FuzzyMatch,
+                        in_numbers: number_ids.iter().any(|n| n == cand.number_id),
+                        similarity,
+                    })
+                })
+                .collect();
         }
     }
 
-    // Strategy 2: Normalized match on first line (after accent stripping)
-    for pass in &[true, false] {
-        let filter_nids = *pass;
-        for cand in candidates {
-            if filter_nids && !number_ids.contains(&cand.number_id.to_string()) {
-                continue;
-            }
-            if cand.first_line_norm.contains(&anchor_norm) {
-                return Some((cand.segment_id.to_string(), MatchMethod::NormalizedMatch));
-            }
+    matches.sort_by_key(AnchorMatch::rank);
+    matches
+}
+
+/// Words of normalized text, without the punctuation between them.
+fn match_words(text: &str) -> Vec<&str> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect()
+}
+
+/// Similarity of two strings from 0 to 1: one less their Levenshtein
+/// distance over the longer one's length.
+fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    // One row of the edit-distance table at a time
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (above + 1).min(row[j] + 1).min(diagonal + usize::from(ca != cb));
+            diagonal = above;
         }
     }
+    1.0 - row[b.len()] as f64 / longest as f64
+}
 
-    // Strategy 3: Substring match anywhere in full text
-    for pass in &[true, false] {
-        let filter_nids = *pass;
-        for cand in candidates {
-            if filter_nids && !number_ids.contains(&cand.number_id.to_string()) {
-                continue;
-            }
-            if cand.full_text_norm.contains(&anchor_norm) {
-                return Some((cand.segment_id.to_string(), MatchMethod::SubstringMatch));
+/// Try to match an anchor to a segment, preferring matches within the given number_ids.
+pub(crate) fn match_anchor(
+    anchor: &str,
+    number_ids: &[String],
+    candidates: &[SegCandidate<'_>],
+) -> Option<(String, MatchMethod)> {
+    score_anchor(anchor, number_ids, candidates).into_iter()
+        .next()
+        .map(|m| (m.segment_id.to_string(), m.method))
+}
+
+/// `score_anchor_with` against the original text, then against the
+/// translations if nothing matches; true when the matches are translations.
+fn score_with_translation<'a>(
+    anchor: &str,
+    number_ids: &[String],
+    index: &SegmentIndex<'a>,
+    fuzzy_threshold: Option<f64>,
+) -> (Vec<AnchorMatch<'a>>, bool) {
+    let matches = score_anchor_with(anchor, number_ids, index.text(), fuzzy_threshold);
+    if !matches.is_empty() {
+        return (matches, false);
+    }
+    (score_anchor_with(anchor, number_ids, index.translations(), fuzzy_threshold), true)
+}
+
+/// The matches tied with the best one, and the confidence in the best.
+fn best_matches<'m, 'a>(matches: &'m [AnchorMatch<'a>]) -> (&'m [AnchorMatch<'a>], Option<f64>) {
+    let Some(best) = matches.first() else {
+        return (&[], None);
+    };
+    let ties = matches.iter().take_while(|m| m.rank() == best.rank()).count();
+    let method = match best.method {
+        MatchMethod::PrefixMatch | MatchMethod::Manual => 1.0,
+        MatchMethod::NormalizedMatch => 0.85,
+        MatchMethod::SubstringMatch | MatchMethod::TranslationMatch => 0.6,
+        MatchMethod::FuzzyMatch => 0.5 * best.similarity,
+    };
+    let scope = if best.in_numbers { 1.0 } else { 0.8 };
+    let confidence = method * scope / ties as f64;
+    (&matches[..ties], Some((confidence * 100.0).round() / 100.0))
+}
+
+/// Segments an anchor that matches none outright might start at, most
+/// similar first (segments in `number_ids` ahead of the rest), for a
+/// person to choose from.
+pub fn suggest_segments(base: &BaseLibretto, anchor: &str, number_ids: &[String], limit: usize) -> Vec<String> {
+    let candidates = build_segment_index(base);
+    score_anchor_with(anchor, number_ids, &candidates, Some(0.0)).into_iter()
+        .take(limit)
+        .map(|m| m.segment_id.to_string())
+        .collect()
+}
+
+impl ResolveResult {
+    /// Record `segment_id`, chosen by hand (e.g. from the alternatives of
+    /// an ambiguous anchor), as the start of the track at `index`. The
+    /// track's first section mark moves with it.
+    pub fn choose_segment(&mut self, index: usize, segment_id: &str) {
+        let track = &mut self.overlay.track_timings[index];
+        let resolution = &mut self.resolutions[index];
+
+        match (&resolution.resolved_segment_id, track.section_marks.first_mut()) {
+            (Some(old), Some(mark)) if mark.segment_id == *old => mark.segment_id = segment_id.to_string(),
+            (None, _) => {
+                // The first anchor matched nothing, so it has no mark yet
+                let first = classify_title_anchors(&track.track_title).into_iter().next();
+                if let Some(first) = first.filter(|a| resolution.unmatched_anchors.first() == Some(&a.anchor)) {
+                    resolution.unmatched_anchors.remove(0);
+                    track.section_marks.insert(0, SectionMark {
+                        segment_id: segment_id.to_string(),
+                        is_recitative: first.is_recitative,
+                    });
+                }
             }
+            _ => {}
         }
-    }
 
-    None
+        track.start_segment_id = Some(segment_id.to_string());
+        track.resolved_by = Some(ResolvedBy::Manual);
+        resolution.resolved_segment_id = Some(segment_id.to_string());
+        resolution.match_method = Some(MatchMethod::Manual);
+        resolution.confidence = Some(1.0);
+        resolution.alternatives.clear();
+    }
 }
 
 /// Resolve track title anchors to segment IDs.
 ///
 /// For each track in the overlay:
-/// 1. If `start_segment_id` is already set, preserve it (manual override).
-/// 2. Extract quoted text from the track title.
-/// 3. Match the first anchor to a segment in the base libretto.
+/// 1. If `start_segment_id` is already set, preserve it (manual override),
+///    unless `options.force` is set and it was not set by hand.
+/// 2. Extract quoted text from the track title. A title without any that
+///    names an instrumental piece marks the track `instrumental`.
+/// 3. Match the first anchor to a segment in the base libretto, trying
+///    segment translations when the original text has no match.
 /// 4. Set `start_segment_id` to the matched segment ID.
+/// 5. Unless the track has `section_marks` already, record every anchor
+///    that matches as a section mark.
 ///
 /// The first anchor in the track title is used as the start segment because
 /// it typically corresponds to the opening text of that track.
 pub fn resolve_anchors(base: &BaseLibretto, overlay: &TimingOverlay) -> ResolveResult {
+    resolve_anchors_with(base, overlay, &ResolveOptions::default())
+}
+
+/// Resolve anchors as `resolve_anchors` does, with `options`.
+pub fn resolve_anchors_with(base: &BaseLibretto, overlay: &TimingOverlay, options: &ResolveOptions) -> ResolveResult {
+    resolve_anchors_indexed(&SegmentIndex::new(base), overlay, options)
+}
+
+/// Resolve anchors as `resolve_anchors_with` does, searching `index`, for
+/// callers that also estimate against the same base libretto. Tracks are
+/// resolved in parallel.
+pub fn resolve_anchors_indexed(index: &SegmentIndex<'_>, overlay: &TimingOverlay, options: &ResolveOptions) -> ResolveResult {
+    let resolved = parallel::map_indexed(&overlay.track_timings, |i, _| resolve_track(index, overlay, i, options));
+
     let mut result_overlay = overlay.clone();
-    let mut resolutions = Vec::new();
+    let mut resolutions = Vec::with_capacity(resolved.len());
     let mut warnings = Vec::new();
-    let candidates = build_segment_index(base);
+    for (i, (track, resolution, track_warnings)) in resolved.into_iter().enumerate() {
+        result_overlay.track_timings[i] = track;
+        resolutions.push(resolution);
+        warnings.extend(track_warnings);
+    }
+    ResolveResult {
+        overlay: result_overlay,
+        resolutions,
+        warnings,
+    }
+}
 
-    for (i, track) in overlay.track_timings.iter().enumerate() {
-        let anchors = extract_anchors(&track.track_title);
-
-        // Preserve manual overrides
-        if track.start_segment_id.is_some() {
-            resolutions.push(TrackResolution {
-                track_title: track.track_title.clone(),
-                disc_number: track.disc_number,
-                track_number: track.track_number,
-                anchors,
-                resolved_segment_id: track.start_segment_id.clone(),
-                match_method: Some(MatchMethod::Manual),
-            });
-            continue;
+/// Resolve the track at `i` of `overlay`, returning it updated, with its
+/// resolution and warnings.
+fn resolve_track(
+    index: &SegmentIndex<'_>,
+    overlay: &TimingOverlay,
+    i: usize,
+    options: &ResolveOptions,
+) -> (TrackTiming, TrackResolution, Vec<String>) {
+    let mut warnings = Vec::new();
+    let track = &overlay.track_timings[i];
+    let track = if options.force && track.resolved_by != Some(ResolvedBy::Manual) {
+        TrackTiming {
+            start_segment_id: None,
+            resolved_by: None,
+            section_marks: vec![],
+            instrumental: false,
+            ..track.clone()
         }
+    } else {
+        track.clone()
+    };
+    let mut result = track.clone();
+    let anchors = extract_anchors(&track.track_title);
+    let resolution = |anchors: Vec<String>, unmatched_anchors: Vec<String>| TrackResolution {
+        track_title: track.track_title.clone(),
+        disc_number: track.disc_number,
+        track_number: track.track_number,
+        anchors,
+        resolved_segment_id: None,
+        match_method: None,
+        confidence: None,
+        alternatives: vec![],
+        unmatched_anchors,
+        instrumental: false,
+    };
 
-        if anchors.is_empty() {
-            // No quoted text — use first segment of the first referenced number
-            let fallback = track.number_ids.first()
-                .and_then(|nid| base.find_number(nid))
-                .and_then(|n| n.segments.first())
-                .map(|s| s.id.clone());
-
-            if let Some(seg_id) = &fallback {
-                result_overlay.track_timings[i].start_segment_id = Some(seg_id.clone());
+    // Also collect number_ids from this track AND adjacent tracks for broader search
+    let mut search_nids = track.number_ids.clone();
+    // Include number_ids from the previous track (anchor might be tail of prev number)
+    if i > 0 {
+        for nid in &overlay.track_timings[i - 1].number_ids {
+            if !search_nids.contains(nid) {
+                search_nids.push(nid.clone());
             }
+        }
+    }
 
-            resolutions.push(TrackResolution {
-                track_title: track.track_title.clone(),
-                disc_number: track.disc_number,
-                track_number: track.track_number,
-                anchors: vec![],
-                resolved_segment_id: fallback,
-                match_method: None,
-            });
-            continue;
+    let mut marks = Vec::new();
+    let mut unmatched_anchors = Vec::new();
+    for ta in classify_title_anchors(&track.track_title) {
+        let (matches, _) = score_with_translation(&ta.anchor, &search_nids, index, options.fuzzy_threshold);
+        match matches.first() {
+            Some(m) => marks.push(SectionMark {
+                segment_id: m.segment_id.to_string(),
+                is_recitative: ta.is_recitative,
+            }),
+            None => unmatched_anchors.push(ta.anchor),
         }
+    }
+    if track.section_marks.is_empty() {
+        result.section_marks = marks;
+    }
 
-        // Try to match the first anchor — it determines the track's start segment
-        // Also collect number_ids from this track AND adjacent tracks for broader search
-        let mut search_nids = track.number_ids.clone();
-        // Include number_ids from the previous track (anchor might be tail of prev number)
-        if i > 0 {
-            for nid in &overlay.track_timings[i - 1].number_ids {
-                if !search_nids.contains(nid) {
-                    search_nids.push(nid.clone());
-                }
-            }
+    // Preserve manual overrides
+    if track.start_segment_id.is_some() {
+        let resolution = TrackResolution {
+            resolved_segment_id: track.start_segment_id.clone(),
+            match_method: Some(MatchMethod::Manual),
+            confidence: Some(1.0),
+            instrumental: track.instrumental,
+            ..resolution(anchors, unmatched_anchors)
+        };
+        return (result, resolution, warnings);
+    }
+
+    if anchors.is_empty() && (track.instrumental || is_instrumental_title(&track.track_title)) {
+        result.instrumental = true;
+        result.resolved_by = Some(ResolvedBy::Resolve);
+        let resolution = TrackResolution { instrumental: true, ..resolution(vec![], unmatched_anchors) };
+        return (result, resolution, warnings);
+    }
+
+    if anchors.is_empty() {
+        // No quoted text — use first segment of the first referenced number
+        let fallback = track.number_ids.first()
+            .and_then(|nid| index.base.find_number(nid))
+            .and_then(|n| n.segments.first())
+            .map(|s| s.id.clone());
+
+        if let Some(seg_id) = &fallback {
+            result.start_segment_id = Some(seg_id.clone());
+            result.resolved_by = Some(ResolvedBy::Resolve);
         }
+        let resolution = TrackResolution { resolved_segment_id: fallback, ..resolution(vec![], unmatched_anchors) };
+        return (result, resolution, warnings);
+    }
 
-        let first_anchor = &anchors[0];
-        let matched = match_anchor(first_anchor, &search_nids, &candidates);
-
-        match &matched {
-            Some((seg_id, method)) => {
-                result_overlay.track_timings[i].start_segment_id = Some(seg_id.clone());
-                resolutions.push(TrackResolution {
-                    track_title: track.track_title.clone(),
-                    disc_number: track.disc_number,
-                    track_number: track.track_number,
-                    anchors,
-                    resolved_segment_id: Some(seg_id.clone()),
-                    match_method: Some(method.clone()),
-                });
-            }
-            None => {
+    // Try to match the first anchor — it determines the track's start segment
+    let first_anchor = &anchors[0];
+    let (matches, in_translation) = score_with_translation(first_anchor, &search_nids, index, options.fuzzy_threshold);
+    let (best, mut confidence) = best_matches(&matches);
+    if in_translation {
+        confidence = confidence.map(|c| (c * 90.0).round() / 100.0);
+    }
+
+    let resolution = match best.first() {
+        Some(matched) => {
+            let alternatives: Vec<String> = best[1..].iter().map(|m| m.segment_id.to_string()).collect();
+            if !alternatives.is_empty() {
                 warnings.push(format!(
-                    "D{}T{}: anchor \"{}\" — no match found in base libretto",
+                    "D{}T{}: anchor \"{}\" is ambiguous — matches {} and {}; using {}",
                     track.disc_number.unwrap_or(0),
                     track.track_number.unwrap_or(0),
                     first_anchor,
+                    matched.segment_id,
+                    alternatives.join(", "),
+                    matched.segment_id,
                 ));
-                resolutions.push(TrackResolution {
-                    track_title: track.track_title.clone(),
-                    disc_number: track.disc_number,
-                    track_number: track.track_number,
-                    anchors,
-                    resolved_segment_id: None,
-                    match_method: None,
-                });
+            }
+            result.start_segment_id = Some(matched.segment_id.to_string());
+            result.resolved_by = Some(ResolvedBy::Resolve);
+            TrackResolution {
+                resolved_segment_id: Some(matched.segment_id.to_string()),
+                match_method: Some(if in_translation { MatchMethod::TranslationMatch } else { matched.method.clone() }),
+                confidence,
+                alternatives,
+                ..resolution(anchors.clone(), unmatched_anchors)
             }
         }
-    }
+        None => {
+            warnings.push(format!(
+                "D{}T{}: anchor \"{}\" — no match found in base libretto",
+                track.disc_number.unwrap_or(0),
+                track.track_number.unwrap_or(0),
+                first_anchor,
+            ));
+            resolution(anchors.clone(), unmatched_anchors)
+        }
+    };
+    (result, resolution, warnings)
+}
 
-    ResolveResult {
-        overlay: result_overlay,
-        resolutions,
-        warnings,
+/// A Markdown report of a resolve run: one table row per track with its
+/// anchors, the segment it starts at, how that was matched, and the
+/// anchors that matched nothing, for auditing resolution in one pass.
+pub fn resolve_report_markdown(resolutions: &[TrackResolution]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|");
+    let quoted = |anchors: &[String]| anchors.iter().map(|a| format!("“{}”", cell(a))).collect::<Vec<_>>().join(", ");
+
+    let mut md = String::from("# Resolve report\n\n");
+    let resolved = resolutions.iter().filter(|r| r.resolved_segment_id.is_some()).count();
+    let ambiguous = resolutions.iter().filter(|r| !r.alternatives.is_empty()).count();
+    let with_unmatched = resolutions.iter().filter(|r| !r.unmatched_anchors.is_empty()).count();
+    md.push_str(&format!(
+        "{resolved}/{} tracks resolved, {ambiguous} ambiguous, {with_unmatched} with unmatched anchors.\n\n",
+        resolutions.len()
+    ));
+    md.push_str("| Track | Title | Anchors | Segment | Method | Confidence | Unmatched |\n");
+    md.push_str("|---|---|---|---|---|---|---|\n");
+    for r in resolutions {
+        let segment = match &r.resolved_segment_id {
+            None if r.instrumental => "instrumental".to_string(),
+            Some(id) if r.alternatives.is_empty() => format!("`{id}`"),
+            Some(id) => format!("`{id}` (also {})", r.alternatives.iter().map(|a| format!("`{a}`")).collect::<Vec<_>>().join(", ")),
+            None => "**unresolved**".to_string(),
+        };
+        md.push_str(&format!(
+            "| D{}T{} | {} | {} | {} | {} | {} | {} |\n",
+            r.disc_number.unwrap_or(0),
+            r.track_number.unwrap_or(0),
+            cell(&r.track_title),
+            quoted(&r.anchors),
+            segment,
+            r.match_method.as_ref().map(|m| format!("{m:?}")).unwrap_or_default(),
+            r.confidence.map(|c| format!("{c:.2}")).unwrap_or_default(),
+            quoted(&r.unmatched_anchors),
+        ));
     }
+    md
 }
 
 #[cfg(test)]
@@ -449,8 +878,6 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![
                 TrackTiming {
                     track_title: r#"No. 1 Duetto "Se a caso madama"; recitativo "Or bene, ascolta""#.to_string(),
@@ -458,8 +885,7 @@ mod tests {
                     track_number: Some(1),
                     duration_seconds: Some(200.0),
                     number_ids: vec!["no-1".to_string()],
-                    start_segment_id: None,
-                    segment_times: vec![],
+                    ..Default::default()
                 },
                 TrackTiming {
                     track_title: r#"Recitativo "Bravo, signor padrone"; No. 2 Cavatina "Se vuol ballare""#.to_string(),
@@ -467,10 +893,10 @@ mod tests {
                     track_number: Some(2),
                     duration_seconds: Some(250.0),
                     number_ids: vec!["no-2".to_string()],
-                    start_segment_id: None,
-                    segment_times: vec![],
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         let result = resolve_anchors(&base, &overlay);
@@ -488,6 +914,223 @@ mod tests {
             result.overlay.track_timings[1].start_segment_id.as_deref(),
             Some("no-1-003")
         );
+        assert_eq!(result.resolutions[0].confidence, Some(1.0));
+        let marks: Vec<(&str, bool)> = result.overlay.track_timings[1].section_marks.iter()
+            .map(|m| (m.segment_id.as_str(), m.is_recitative))
+            .collect();
+        assert_eq!(marks, vec![("no-1-003", true), ("no-2-001", false)]);
+        // In the previous track's number, which is searched with the track's own
+        assert_eq!(result.resolutions[1].confidence, Some(1.0));
+    }
+
+    #[test]
+    fn test_resolve_reports_ambiguity() {
+        let mut base = test_base();
+        let line = |id: &str| Segment {
+            id: id.to_string(),
+            segment_type: SegmentType::Sung,
+            character: Some("C".to_string()),
+            text: Some("Susanna, or via sortite".to_string()),
+            translation: None,
+            direction: None,
+            group: None,
+        };
+        base.numbers.push(MusicalNumber {
+            id: "no-13".to_string(),
+            label: "No. 13".to_string(),
+            number_type: NumberType::Terzetto,
+            act: "2".to_string(),
+            scene: None,
+            segments: vec![line("no-13-001"), line("no-13-004")],
+        });
+        let mut overlay = TimingOverlay::new("test");
+        overlay.track_timings.push(TrackTiming {
+            track_title: r#"No. 13 Terzetto "Susanna, or via sortite""#.to_string(),
+            disc_number: Some(2),
+            track_number: Some(4),
+            number_ids: vec!["no-13".to_string()],
+            ..Default::default()
+        });
+
+        let result = resolve_anchors(&base, &overlay);
+        let resolution = &result.resolutions[0];
+        assert_eq!(resolution.resolved_segment_id.as_deref(), Some("no-13-001"));
+        assert_eq!(resolution.alternatives, vec!["no-13-004"]);
+        assert_eq!(resolution.confidence, Some(0.5));
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("no-13-001 and no-13-004"), "{}", result.warnings[0]);
+    }
+
+    #[test]
+    fn test_resolve_fuzzy_fallback() {
+        let base = test_base();
+        let mut overlay = TimingOverlay::new("test");
+        for title in [r#"Recitativo "Brava, signor padrone""#, r#"Cavatina "Se vuol balare""#, r#"Aria "Non più andrai""#] {
+            overlay.track_timings.push(TrackTiming {
+                track_title: title.to_string(),
+                disc_number: Some(1),
+                track_number: Some(overlay.track_timings.len() as u32 + 1),
+                ..Default::default()
+            });
+        }
+
+        let result = resolve_anchors(&base, &overlay);
+        let resolved: Vec<(Option<&str>, Option<MatchMethod>)> = result.resolutions.iter()
+            .map(|r| (r.resolved_segment_id.as_deref(), r.match_method.clone()))
+            .collect();
+        assert_eq!(resolved, vec![
+            (Some("no-1-003"), Some(MatchMethod::FuzzyMatch)),
+            (Some("no-2-001"), Some(MatchMethod::FuzzyMatch)),
+            (None, None),
+        ]);
+        assert!(result.resolutions[1].confidence.unwrap() < 0.5);
+        assert_eq!(result.warnings.len(), 1);
+
+        // A strict threshold leaves near misses unresolved
+        let strict = ResolveOptions { fuzzy_threshold: Some(0.97), ..Default::default() };
+        let result = resolve_anchors_with(&base, &overlay, &strict);
+        assert!(result.resolutions.iter().all(|r| r.resolved_segment_id.is_none()));
+    }
+
+    #[test]
+    fn test_resolve_translation_anchor() {
+        let mut base = test_base();
+        base.numbers[1].segments[0].translation = Some("If you want to dance, my little count".to_string());
+        let mut overlay = TimingOverlay::new("test");
+        overlay.track_timings.push(TrackTiming {
+            track_title: r#"No. 3 Cavatina: "If you want to dance""#.to_string(),
+            disc_number: Some(1),
+            track_number: Some(3),
+            number_ids: vec!["no-2".to_string()],
+            ..Default::default()
+        });
+
+        let result = resolve_anchors(&base, &overlay);
+        assert!(result.warnings.is_empty(), "warnings: {:?}", result.warnings);
+        let resolution = &result.resolutions[0];
+        assert_eq!(resolution.resolved_segment_id.as_deref(), Some("no-2-001"));
+        assert_eq!(resolution.match_method, Some(MatchMethod::TranslationMatch));
+        assert_eq!(resolution.confidence, Some(0.9));
+    }
+
+    #[test]
+    fn test_similarity() {
+        assert_eq!(similarity("se vuol ballare", "se vuol ballare"), 1.0);
+        assert!((similarity("se vuol balare", "se vuol ballare") - 14.0 / 15.0).abs() < 1e-9);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("abc", ""), 0.0);
+    }
+
+    #[test]
+    fn test_choose_segment() {
+        let base = test_base();
+        let mut overlay = TimingOverlay::new("test");
+        for title in [r#"Recitativo "Zitti, zitti"; Cavatina "Se vuol ballare""#, r#"Duettino "Se a caso madama""#] {
+            overlay.track_timings.push(TrackTiming {
+                track_title: title.to_string(),
+                disc_number: Some(1),
+                track_number: Some(overlay.track_timings.len() as u32 + 1),
+                number_ids: vec!["no-1".to_string()],
+                ..Default::default()
+            });
+        }
+        let mut result = resolve_anchors(&base, &overlay);
+        assert_eq!(result.resolutions[0].resolved_segment_id, None);
+        assert_eq!(suggest_segments(&base, "Zitti, zitti", &["no-1".to_string()], 2).len(), 2);
+
+        // An unmatched first anchor gets its section mark
+        result.choose_segment(0, "no-1-002");
+        let track = &result.overlay.track_timings[0];
+        assert_eq!(track.start_segment_id.as_deref(), Some("no-1-002"));
+        let marks: Vec<(&str, bool)> = track.section_marks.iter().map(|m| (m.segment_id.as_str(), m.is_recitative)).collect();
+        assert_eq!(marks, vec![("no-1-002", true), ("no-2-001", false)]);
+        assert!(result.resolutions[0].unmatched_anchors.is_empty());
+        assert_eq!(result.resolutions[0].match_method, Some(MatchMethod::Manual));
+
+        // A matched one has its mark moved
+        result.choose_segment(1, "no-1-003");
+        assert_eq!(result.overlay.track_timings[1].section_marks[0].segment_id, "no-1-003");
+        assert_eq!(result.resolutions[1].confidence, Some(1.0));
+    }
+
+    #[test]
+    fn test_score_anchor_order() {
+        let base = test_base();
+        let candidates = build_segment_index(&base);
+        // A prefix match outside the numbers beats a substring match inside
+        let matches = score_anchor("Se vuol ballare", &["no-1".to_string()], &candidates);
+        assert_eq!(matches[0].segment_id, "no-2-001");
+        assert!(!matches[0].in_numbers);
+        let (best, confidence) = best_matches(&matches);
+        assert_eq!((best.len(), confidence), (1, Some(0.8)));
+
+        let matches = score_anchor("signor", &["no-2".to_string()], &candidates);
+        let found: Vec<(&str, MatchMethod)> = matches.iter().map(|m| (m.segment_id, m.method.clone())).collect();
+        assert_eq!(found, vec![
+            ("no-2-001", MatchMethod::NormalizedMatch),
+            ("no-1-003", MatchMethod::NormalizedMatch),
+        ]);
+    }
+
+    #[test]
+    fn test_resolve_report_markdown() {
+        let base = test_base();
+        let mut overlay = TimingOverlay::new("test");
+        overlay.track_timings.push(TrackTiming {
+            track_title: r#"No. 1 "Se a caso madama" | "Zitti, zitti""#.to_string(),
+            disc_number: Some(1),
+            track_number: Some(1),
+            number_ids: vec!["no-1".to_string()],
+            ..Default::default()
+        });
+
+        let result = resolve_anchors(&base, &overlay);
+        assert_eq!(result.resolutions[0].unmatched_anchors, vec!["Zitti, zitti"]);
+        let md = resolve_report_markdown(&result.resolutions);
+        assert!(md.contains("1/1 tracks resolved, 0 ambiguous, 1 with unmatched anchors."), "{md}");
+        assert!(md.contains(
+            r#"| D1T1 | No. 1 "Se a caso madama" \| "Zitti, zitti" | “Se a caso madama”, “Zitti, zitti” | `no-1-001` | PrefixMatch | 1.00 | “Zitti, zitti” |"#
+        ), "{md}");
+    }
+
+    #[test]
+    fn test_resolve_force() {
+        let base = test_base();
+        let mut overlay = TimingOverlay::new("test");
+        for (title, start, by) in [
+            (r#""Se a caso madama""#, "no-1-003", Some(ResolvedBy::Resolve)),
+            (r#""Or bene, ascolta""#, "no-1-001", Some(ResolvedBy::Manual)),
+            (r#""Or bene, ascolta""#, "no-1-003", None),
+        ] {
+            overlay.track_timings.push(TrackTiming {
+                track_title: title.to_string(),
+                disc_number: Some(1),
+                track_number: Some(overlay.track_timings.len() as u32 + 1),
+                start_segment_id: Some(start.to_string()),
+                resolved_by: by,
+                section_marks: vec![SectionMark { segment_id: start.to_string(), is_recitative: false }],
+                ..Default::default()
+            });
+        }
+
+        let starts = |result: &ResolveResult| -> Vec<String> {
+            result.overlay.track_timings.iter().map(|t| t.start_segment_id.clone().unwrap()).collect()
+        };
+        // Without force, every start is kept
+        let kept = resolve_anchors(&base, &overlay);
+        assert_eq!(starts(&kept), vec!["no-1-003", "no-1-001", "no-1-003"]);
+
+        // With it, all but the choice made by hand are redone, those from
+        // before resolved_by was recorded included
+        let options = ResolveOptions { force: true, ..Default::default() };
+        let result = resolve_anchors_with(&base, &overlay, &options);
+        assert_eq!(starts(&result), vec!["no-1-001", "no-1-001", "no-1-002"]);
+        assert_eq!(result.overlay.track_timings[2].resolved_by, Some(ResolvedBy::Resolve));
+        let track = &result.overlay.track_timings[0];
+        assert_eq!(track.resolved_by, Some(ResolvedBy::Resolve));
+        assert_eq!(track.section_marks[0].segment_id, "no-1-001");
+        assert_eq!(result.resolutions[0].match_method, Some(MatchMethod::PrefixMatch));
+        assert_eq!(result.resolutions[1].match_method, Some(MatchMethod::Manual));
     }
 
     #[test]
@@ -499,8 +1142,6 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![TrackTiming {
                 track_title: r#"No. 1 Duetto "Se a caso madama""#.to_string(),
                 disc_number: Some(1),
@@ -508,8 +1149,9 @@ mod tests {
                 duration_seconds: Some(200.0),
                 number_ids: vec!["no-1".to_string()],
                 start_segment_id: Some("no-1-002".to_string()), // manual override
-                segment_times: vec![],
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let result = resolve_anchors(&base, &overlay);
@@ -530,17 +1172,15 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![TrackTiming {
-                track_title: "Sinfonia".to_string(),
+                track_title: "No. 1 Duettino".to_string(),
                 disc_number: Some(1),
                 track_number: Some(1),
                 duration_seconds: Some(200.0),
                 number_ids: vec!["no-1".to_string()],
-                start_segment_id: None,
-                segment_times: vec![],
+                ..Default::default()
             }],
+            ..Default::default()
         };
 
         let result = resolve_anchors(&base, &overlay);
@@ -551,6 +1191,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_instrumental_track() {
+        let base = test_base();
+        let mut overlay = TimingOverlay::new("test");
+        overlay.track_timings.push(TrackTiming {
+            track_title: "Atto III: Marcia".to_string(),
+            disc_number: Some(2),
+            track_number: Some(9),
+            duration_seconds: Some(120.0),
+            number_ids: vec!["no-2".to_string()],
+            ..Default::default()
+        });
+
+        let result = resolve_anchors(&base, &overlay);
+        let track = &result.overlay.track_timings[0];
+        assert!(track.instrumental);
+        assert_eq!(track.start_segment_id, None);
+        assert!(result.resolutions[0].instrumental);
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_is_instrumental_title() {
+        assert!(is_instrumental_title("Sinfonia"));
+        assert!(is_instrumental_title("Act III: Entr’acte"));
+        assert!(is_instrumental_title("Ouvertüre"));
+        assert!(!is_instrumental_title("Marcia e coro"));
+        assert!(!is_instrumental_title("Preludio e recitativo"));
+        assert!(!is_instrumental_title("No. 1 Duettino"));
+        // Only at the start of a word
+        assert!(!is_instrumental_title("Rosmarch"));
+    }
+
     #[test]
     fn test_normalize_for_match() {
         // Accented vs unaccented
@@ -588,6 +1261,44 @@ mod tests {
         assert!(!anchors[1].is_recitative);
     }
 
+    #[test]
+    fn test_classify_title_anchors_timing_cues() {
+        let title = "Recitativo: \"Cosa stai misurando\" – Aria: \u{201c}Se vuol ballare\u{201d} [1:42.5] (Figaro)";
+        let anchors = classify_title_anchors(title);
+        assert_eq!(anchors.len(), 2);
+        assert_eq!(anchors[0].time, None);
+        assert_eq!(anchors[1].time, Some(102.5));
+        assert_eq!(classify_title_anchors("Aria: \"Porgi, amor\" (Contessa)")[0].time, None);
+    }
+
+    #[test]
+    fn test_extract_anchors_other_quotes() {
+        assert_eq!(extract_anchors("Récit et air « Je veux vivre »"), vec!["Je veux vivre"]);
+        assert_eq!(extract_anchors("Nr. 3 Arie „Dies Bildnis ist bezaubernd schön“"), vec!["Dies Bildnis ist bezaubernd schön"]);
+        assert_eq!(extract_anchors("Nr. 17 Arie »Ach, ich fühl's«"), vec!["Ach, ich fühl's"]);
+        assert_eq!(
+            extract_anchors("Aria 'Dove sono'; recitativo \u{2018}Ch'io mi scordi di te?\u{2019}"),
+            vec!["Dove sono", "Ch'io mi scordi di te?"]
+        );
+        // Apostrophes are not quotes
+        assert!(extract_anchors("L'amour est un oiseau rebelle").is_empty());
+    }
+
+    #[test]
+    fn test_classify_title_anchors_german_french() {
+        let anchors = classify_title_anchors("Nr. 8 Rezitativ „Endlich naht sich die Stunde“ und Arie „Deh vieni“");
+        assert_eq!(anchors.len(), 2);
+        assert!(anchors[0].is_recitative);
+        assert!(!anchors[1].is_recitative);
+
+        assert!(!classify_title_anchors("Récit et air « Je veux vivre » (0:45)")[0].is_recitative);
+        assert_eq!(classify_title_anchors("Récit et air « Je veux vivre » (0:45)")[0].time, Some(45.0));
+        assert!(classify_title_anchors("Acte II: Récit « Voici l'heure »")[0].is_recitative);
+        assert!(!classify_title_anchors("Chœur « Voici l'heure »")[0].is_recitative);
+        // "air" inside a word is not a keyword
+        assert!(classify_title_anchors("Recitativo del Cairo \"Ecco\"")[0].is_recitative);
+    }
+
     #[test]
     fn test_classify_title_anchors_no_quotes() {
         let anchors = classify_title_anchors("Sinfonia");
@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+
+/// Licensing and attribution for a libretto document.
+///
+/// Original libretto texts are mostly public domain, but translations and
+/// timing work usually are not, so bundles published from the library need
+/// to say under what terms each layer may be redistributed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Rights {
+    /// License of the document (SPDX identifier where possible, e.g., "CC-BY-SA-4.0").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Attribution text to display wherever the content is shown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribution: Option<String>,
+    /// Terms of the source the content was acquired from (e.g., site terms of use).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_terms: Option<String>,
+    /// License of the translation text, when it differs from the original's.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation_license: Option<String>,
+}
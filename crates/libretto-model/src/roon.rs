@@ -0,0 +1,180 @@
+// Hand-off to the roon-rd display extension.
+//
+// roon-rd reads timed librettos from a data directory and decides which
+// one to show from `config/track-map.json`, which maps now-playing
+// metadata (album, title, disc and track number) to a file under `data/`
+// and a track within it. This module lays a merged libretto out that way:
+// the document goes to `data/<name>/<name>.libretto.json` and one mapping
+// per track is added to the track map, replacing the mappings an earlier
+// export of the same recording left there and keeping all others.
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::slug;
+use crate::interchange::InterchangeLibretto;
+
+/// Where roon-rd looks for its track map, relative to its root.
+pub const TRACK_MAP_PATH: &str = "config/track-map.json";
+
+/// Directory under roon-rd's root that `libretto_file` paths are relative to.
+pub const DATA_DIR: &str = "data";
+
+/// The track map roon-rd matches now-playing tracks against.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackMap {
+    #[serde(default)]
+    pub mappings: Vec<TrackMapping>,
+    /// Settings roon-rd keeps alongside the mappings, written back as read.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// One audio track's libretto: the file, relative to the data directory,
+/// and the track within it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackMapping {
+    #[serde(rename = "match")]
+    pub matches: TrackMatch,
+    pub libretto_file: String,
+    pub track_id: String,
+    /// Fields of the mapping this crate doesn't know, written back as read.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Now-playing metadata a mapping applies to; a field left out matches
+/// anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrackMatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disc_number: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track_number: Option<u32>,
+}
+
+/// Files to write under roon-rd's root, and what changed in the track map.
+#[derive(Debug)]
+pub struct RoonExport {
+    /// Paths relative to the root, and their contents.
+    pub files: Vec<(String, String)>,
+    /// Mappings added for this recording.
+    pub mappings: usize,
+    /// Earlier mappings to the same file that they replace.
+    pub replaced: usize,
+}
+
+/// Map every track of `doc` to `libretto_file`, matching on the album,
+/// track title, and disc and track numbers. Artist tags vary too much
+/// between rips to match on.
+pub fn track_mappings(doc: &InterchangeLibretto, libretto_file: &str) -> Vec<TrackMapping> {
+    doc.tracks.iter()
+        .map(|track| TrackMapping {
+            matches: TrackMatch {
+                album: track.album.clone(),
+                title: Some(track.title.clone()),
+                artist: None,
+                disc_number: track.disc_number,
+                track_number: track.track_number,
+            },
+            libretto_file: libretto_file.to_string(),
+            track_id: track.track_id.clone(),
+            extra: Default::default(),
+        })
+        .collect()
+}
+
+/// Lay `doc` out for roon-rd under the recording name `name` (made into
+/// a slug; the opera's title, or `recording`, when that leaves nothing),
+/// updating `track_map`, the map already in place, if any.
+pub fn roon_files(doc: &InterchangeLibretto, name: &str, mut track_map: TrackMap) -> Result<RoonExport, serde_json::Error> {
+    let name = [slug(name), slug(&doc.opera.title)]
+        .into_iter()
+        .find(|s| !s.is_empty())
+        .unwrap_or_else(|| "recording".to_string());
+    let libretto_file = format!("{name}/{name}.libretto.json");
+    let mappings = track_mappings(doc, &libretto_file);
+
+    let before = track_map.mappings.len();
+    track_map.mappings.retain(|m| m.libretto_file != libretto_file);
+    let replaced = before - track_map.mappings.len();
+    let added = mappings.len();
+    track_map.mappings.extend(mappings);
+
+    Ok(RoonExport {
+        files: vec![
+            (format!("{DATA_DIR}/{libretto_file}"), serde_json::to_string_pretty(doc)?),
+            (TRACK_MAP_PATH.to_string(), serde_json::to_string_pretty(&track_map)? + "\n"),
+        ],
+        mappings: added,
+        replaced,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interchange::{InterchangeOpera, InterchangeTrack};
+
+    fn doc() -> InterchangeLibretto {
+        let track = |number: u32, title: &str| InterchangeTrack {
+            track_id: format!("d1-t{number}"),
+            title: title.to_string(),
+            album: Some("Le nozze di Figaro".to_string()),
+            artist: Some("Giulini".to_string()),
+            disc_number: Some(1),
+            track_number: Some(number),
+            ..Default::default()
+        };
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "W. A. Mozart".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks: vec![track(1, "Sinfonia"), track(2, "Cinque... dieci...")],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_roon_files() {
+        let other = TrackMapping {
+            matches: TrackMatch { album: Some("Tosca".to_string()), ..Default::default() },
+            libretto_file: "tosca-callas/tosca-callas.libretto.json".to_string(),
+            track_id: "act-1".to_string(),
+            extra: serde_json::from_str(r#"{ "offset_seconds": 2.5 }"#).unwrap(),
+        };
+        let stale = TrackMapping {
+            libretto_file: "giulini-1959-emi/giulini-1959-emi.libretto.json".to_string(),
+            ..other.clone()
+        };
+        let map = TrackMap {
+            mappings: vec![other.clone(), stale],
+            extra: serde_json::from_str(r#"{ "default_font_size": 18 }"#).unwrap(),
+        };
+
+        let export = roon_files(&doc(), "Giulini 1959 EMI", map).unwrap();
+        assert_eq!((export.mappings, export.replaced), (2, 1));
+        assert_eq!(export.files[0].0, "data/giulini-1959-emi/giulini-1959-emi.libretto.json");
+        assert_eq!(export.files[1].0, "config/track-map.json");
+
+        let map: TrackMap = serde_json::from_str(&export.files[1].1).unwrap();
+        assert_eq!(map.mappings.len(), 3);
+        assert_eq!(map.mappings[0], other);
+        assert_eq!(map.extra["default_font_size"], 18);
+        assert_eq!(map.mappings[2].track_id, "d1-t2");
+        assert_eq!(map.mappings[2].matches.title.as_deref(), Some("Cinque... dieci..."));
+        assert!(export.files[1].1.contains("\"match\": {\n        \"album\": \"Le nozze di Figaro\""));
+
+        let export = roon_files(&doc(), "???", TrackMap::default()).unwrap();
+        assert_eq!(export.files[0].0, "data/le-nozze-di-figaro/le-nozze-di-figaro.libretto.json");
+    }
+}
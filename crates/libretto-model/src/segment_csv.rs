@@ -0,0 +1,367 @@
+// Segments as CSV, for collaborators who edit in a spreadsheet.
+//
+// One row per base libretto segment: its ID, the label of its number, the
+// character, text and translation, and with a timing overlay the time the
+// segment starts in its track. The edited sheet is read back by column
+// name, so columns may be reordered or dropped; text edits go to the base
+// libretto and start times to the overlay.
+
+use thiserror::Error;
+
+use crate::base_libretto::BaseLibretto;
+use crate::import::track_sequence;
+use crate::timing_overlay::{round_to_ms, SegmentTime, TimingOverlay, TimingSource};
+
+#[derive(Debug, Error)]
+pub enum CsvError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    #[error("no segment_id column in the header")]
+    NoSegmentIdColumn,
+}
+
+/// One row of an edited sheet. A field is `None` when the sheet has no
+/// such column, and `Some("")` when the cell is empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CsvRow {
+    /// Line on which the row starts.
+    pub line: usize,
+    pub segment_id: String,
+    pub character: Option<String>,
+    pub text: Option<String>,
+    pub translation: Option<String>,
+    pub start: Option<String>,
+}
+
+/// Result of applying an edited sheet.
+#[derive(Debug, Default)]
+pub struct CsvApplyResult {
+    /// Segments whose character, text, or translation changed.
+    pub base_changes: usize,
+    /// Segment times added, moved, or removed.
+    pub timing_changes: usize,
+    pub warnings: Vec<String>,
+}
+
+const HEADER: &str = "segment_id,number,character,text,translation,start";
+
+/// Write every segment of `base` as a CSV row.
+///
+/// With `timing`, the start column holds the segment's time in the first
+/// track that times it. Multi-line text stays in one quoted cell.
+pub fn export_segments_csv(base: &BaseLibretto, timing: Option<&TimingOverlay>) -> String {
+    let mut csv = format!("{HEADER}\n");
+    for number in &base.numbers {
+        for seg in &number.segments {
+            let start = timing
+                .and_then(|o| o.track_timings.iter().flat_map(|t| &t.segment_times).find(|st| st.segment_id == seg.id))
+                .map(|st| format!("{:.3}", st.start))
+                .unwrap_or_default();
+            let row = [
+                seg.id.as_str(),
+                number.label.as_str(),
+                seg.character.as_deref().unwrap_or(""),
+                seg.text.as_deref().or(seg.direction.as_deref()).unwrap_or(""),
+                seg.translation.as_deref().unwrap_or(""),
+                start.as_str(),
+            ];
+            csv.push_str(&row.map(field).join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+pub(crate) fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Split CSV into records of fields, with the line each record starts on.
+/// Quoted fields may hold commas, doubled quotes, and line breaks.
+fn parse_records(contents: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut records = Vec::new();
+    let mut record: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = contents.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.is_empty()) {
+                    records.push((record_line, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                record_line = line;
+            }
+            (_, c) => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if quoted {
+        return Err(CsvError::Parse { line: record_line, message: "unterminated quoted field".to_string() });
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.is_empty()) {
+        records.push((record_line, record));
+    }
+    Ok(records)
+}
+
+/// Parse an edited sheet. The header names the columns; `segment_id` is
+/// required, and `number` and unknown columns are ignored.
+pub fn parse_segments_csv(contents: &str) -> Result<Vec<CsvRow>, CsvError> {
+    let mut records = parse_records(contents)?.into_iter();
+    let Some((_, header)) = records.next() else {
+        return Err(CsvError::NoSegmentIdColumn);
+    };
+    let column = |name: &str| header.iter().position(|h| h.trim().eq_ignore_ascii_case(name));
+    let id_column = column("segment_id").ok_or(CsvError::NoSegmentIdColumn)?;
+    let (character, text, translation, start) = (column("character"), column("text"), column("translation"), column("start"));
+
+    records
+        .map(|(line, fields)| {
+            let get = |c: Option<usize>| c.map(|c| fields.get(c).cloned().unwrap_or_default());
+            let segment_id = fields.get(id_column).map(|s| s.trim().to_string()).unwrap_or_default();
+            if segment_id.is_empty() {
+                return Err(CsvError::Parse { line, message: "row has no segment_id".to_string() });
+            }
+            Ok(CsvRow {
+                line,
+                segment_id,
+                character: get(character),
+                text: get(text),
+                translation: get(translation),
+                start: get(start),
+            })
+        })
+        .collect()
+}
+
+/// Apply edited rows to a base libretto and, when given, its timing overlay.
+///
+/// An empty character, text, or translation cell clears that field; for
+/// a segment without sung text the text column holds its direction. A
+/// start time replaces the segment's time in the track that already
+/// times it, or is added to the first track whose numbers include the
+/// segment; edited times are marked as tapped. An empty start removes
+/// the segment's time. Rows naming unknown segments are skipped with a
+/// warning, as are start times when no overlay is given.
+pub fn apply_segments_csv(
+    base: &mut BaseLibretto,
+    mut timing: Option<&mut TimingOverlay>,
+    rows: &[CsvRow],
+) -> CsvApplyResult {
+    let mut result = CsvApplyResult::default();
+    let set = |field: &mut Option<String>, value: &Option<String>| -> bool {
+        let Some(value) = value else { return false };
+        let value = (!value.trim().is_empty()).then(|| value.clone());
+        let changed = *field != value;
+        *field = value;
+        changed
+    };
+
+    for row in rows {
+        let Some(seg) = base.numbers.iter_mut().flat_map(|n| &mut n.segments).find(|s| s.id == row.segment_id) else {
+            result.warnings.push(format!("line {}: no segment '{}' in the base libretto", row.line, row.segment_id));
+            continue;
+        };
+        let text_field = if seg.text.is_none() && seg.direction.is_some() { &mut seg.direction } else { &mut seg.text };
+        let mut changed = set(text_field, &row.text);
+        changed |= set(&mut seg.character, &row.character);
+        changed |= set(&mut seg.translation, &row.translation);
+        if changed {
+            result.base_changes += 1;
+        }
+
+        let Some(start) = &row.start else { continue };
+        let start = start.trim();
+        let start = match start.parse::<f64>() {
+            _ if start.is_empty() => None,
+            Ok(s) if s.is_finite() && s >= 0.0 => Some(round_to_ms(s)),
+            _ => {
+                result.warnings.push(format!("line {}: invalid start '{}'", row.line, start));
+                continue;
+            }
+        };
+        let Some(overlay) = timing.as_deref_mut() else {
+            if start.is_some() {
+                result.warnings.push(format!("line {}: start time given but no timing overlay to apply it to", row.line));
+            }
+            continue;
+        };
+        if apply_start(base, overlay, &row.segment_id, start) {
+            result.timing_changes += 1;
+        } else if start.is_some() && !overlay.track_timings.iter().flat_map(|t| &t.segment_times).any(|st| st.segment_id == row.segment_id) {
+            result.warnings.push(format!("line {}: segment '{}' is in no track of the timing overlay", row.line, row.segment_id));
+        }
+    }
+    if let Some(overlay) = timing {
+        overlay.sort_segment_times();
+    }
+    result
+}
+
+/// Set (or with `None`, remove) a segment's start. Returns whether anything changed.
+fn apply_start(base: &BaseLibretto, overlay: &mut TimingOverlay, segment_id: &str, start: Option<f64>) -> bool {
+    let existing = overlay.track_timings.iter().enumerate().find_map(|(t, track)| {
+        track.segment_times.iter().position(|st| st.segment_id == segment_id).map(|i| (t, i))
+    });
+    match (existing, start) {
+        (Some((t, i)), None) => {
+            overlay.track_timings[t].segment_times.remove(i);
+            true
+        }
+        (Some((t, i)), Some(start)) => {
+            let st = &mut overlay.track_timings[t].segment_times[i];
+            if st.start == start {
+                return false;
+            }
+            st.start = start;
+            st.source = Some(TimingSource::Tapped);
+            true
+        }
+        (None, Some(start)) => {
+            let Some(track) = overlay.track_timings.iter_mut().find(|t| track_sequence(base, t).contains(&segment_id)) else {
+                return false;
+            };
+            track.segment_times.push(SegmentTime {
+                segment_id: segment_id.to_string(),
+                start,
+                end: None,
+                source: Some(TimingSource::Tapped),
+            });
+            true
+        }
+        (None, None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::segment;
+    use crate::base_libretto::*;
+    use crate::timing_overlay::*;
+
+    fn base() -> BaseLibretto {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "W. A. Mozart".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: None,
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "No. 1, Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![
+                segment("no-1-duettino-001", Some("FIGARO"), Some("Cinque...\ndieci..."), Some("Five, \"ten\""), None),
+                segment("no-1-duettino-002", Some("SUSANNA"), Some("Ora sì ch'io son contenta"), None, None),
+            ],
+        });
+        lib
+    }
+
+    fn overlay() -> TimingOverlay {
+        let mut overlay = TimingOverlay::new("base.libretto.json");
+        overlay.track_timings.push(TrackTiming {
+            track_title: "Cinque... dieci...".to_string(),
+            disc_number: Some(1),
+            track_number: Some(2),
+            number_ids: vec!["no-1-duettino".to_string()],
+            segment_times: vec![SegmentTime {
+                segment_id: "no-1-duettino-001".to_string(),
+                start: 1.5,
+                end: None,
+                source: Some(TimingSource::Estimated),
+            }],
+            ..Default::default()
+        });
+        overlay
+    }
+
+    #[test]
+    fn test_export_segments_csv() {
+        let csv = export_segments_csv(&base(), Some(&overlay()));
+        assert_eq!(csv, "segment_id,number,character,text,translation,start\n\
+                         no-1-duettino-001,\"No. 1, Duettino\",FIGARO,\"Cinque...\ndieci...\",\"Five, \"\"ten\"\"\",1.500\n\
+                         no-1-duettino-002,\"No. 1, Duettino\",SUSANNA,Ora sì ch'io son contenta,,\n");
+
+        // Round-trips through the reader.
+        let rows = parse_segments_csv(&csv).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].text.as_deref(), Some("Cinque...\ndieci..."));
+        assert_eq!(rows[0].translation.as_deref(), Some("Five, \"ten\""));
+        assert_eq!(rows[1].line, 4);
+        let mut lib = base();
+        let mut timing = overlay();
+        let result = apply_segments_csv(&mut lib, Some(&mut timing), &rows);
+        assert_eq!((result.base_changes, result.timing_changes), (0, 0));
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_apply_segments_csv() {
+        // Columns reordered, number column dropped, a spreadsheet's CRLF line endings
+        let csv = "start,segment_id,translation,text\r\n\
+                   2.25,no-1-duettino-001,\"Five, ten\",\"Cinque...\ndieci...\"\r\n\
+                   80,no-1-duettino-002,Now I am happy,Ora sì ch'io son contenta\r\n\
+                   1,no-9-missing,,\r\n";
+        let rows = parse_segments_csv(csv).unwrap();
+        let mut lib = base();
+        let mut timing = overlay();
+        let result = apply_segments_csv(&mut lib, Some(&mut timing), &rows);
+
+        assert_eq!((result.base_changes, result.timing_changes), (2, 2));
+        assert_eq!(result.warnings.len(), 1);
+        let seg = lib.find_segment("no-1-duettino-002").unwrap();
+        assert_eq!(seg.translation.as_deref(), Some("Now I am happy"));
+        assert_eq!(seg.character.as_deref(), Some("SUSANNA"));
+
+        let times: Vec<_> = timing.track_timings[0].segment_times.iter().map(|st| (st.segment_id.as_str(), st.start, st.source)).collect();
+        assert_eq!(times, vec![
+            ("no-1-duettino-001", 2.25, Some(TimingSource::Tapped)),
+            ("no-1-duettino-002", 80.0, Some(TimingSource::Tapped)),
+        ]);
+
+        // An empty start removes the time; without an overlay starts are reported.
+        let rows = parse_segments_csv("segment_id,start\nno-1-duettino-001,\n").unwrap();
+        assert_eq!(apply_segments_csv(&mut lib, Some(&mut timing), &rows).timing_changes, 1);
+        assert_eq!(timing.track_timings[0].segment_times.len(), 1);
+        let rows = parse_segments_csv("segment_id,start\nno-1-duettino-001,3\n").unwrap();
+        assert_eq!(apply_segments_csv(&mut lib, None, &rows).warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(parse_segments_csv("id,text\nx,y\n"), Err(CsvError::NoSegmentIdColumn)));
+        assert!(matches!(parse_segments_csv("segment_id,text\n,y\n"), Err(CsvError::Parse { line: 2, .. })));
+        assert!(matches!(parse_segments_csv("segment_id,text\na,\"open\n"), Err(CsvError::Parse { line: 2, .. })));
+    }
+}
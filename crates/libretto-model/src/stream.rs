@@ -0,0 +1,544 @@
+// Reading an interchange document a segment at a time.
+//
+// `encoding::from_slice` builds the whole document in memory: every track,
+// segment and annotation, on top of the file's bytes. For a full Ring cycle
+// box set that is tens of megabytes, more than a small display device has
+// to spare for the one track it is playing. `stream_interchange` reads from
+// any `io::Read` and hands each track, segment and annotation to an
+// `InterchangeVisitor` as it is read, keeping none of them; the visitor
+// keeps what it needs, and can skip the rest of a track or stop reading.
+//
+// JSON and MessagePack documents can be streamed. The CBOR library only
+// reads whole values, so CBOR documents are refused (`NotStreamable`).
+
+use std::fmt;
+use std::io::Read;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+
+use crate::annotation_overlay::Annotation;
+use crate::encoding::{Encoding, EncodingError};
+use crate::interchange::{InterchangeOpera, InterchangeSegment, InterchangeTrack};
+use crate::pronunciation_overlay::PronunciationNotation;
+use crate::rights::Rights;
+
+/// What a visitor wants after each item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Visit {
+    Continue,
+    /// Pass over the rest of the current list unread: the tracks after the
+    /// header, the segments of a track, or the annotations.
+    Skip,
+    /// Stop reading the document.
+    Stop,
+}
+
+/// A document's details other than its tracks and annotations.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentHeader {
+    pub version: String,
+    pub opera: Option<InterchangeOpera>,
+    pub text_rights: Option<Rights>,
+    pub timing_rights: Option<Rights>,
+    pub pronunciation_notation: Option<PronunciationNotation>,
+    pub pronunciation_rights: Option<Rights>,
+    pub synopsis_language: Option<String>,
+    pub synopsis_rights: Option<Rights>,
+}
+
+/// Receives a document's parts in the order they are read.
+///
+/// Documents written by this crate list a track's fields before its
+/// segments, and the document's before its tracks, so `header` and `track`
+/// see them all. A document listing them after has them in the
+/// `StreamSummary`, but not in what the visitor was given.
+pub trait InterchangeVisitor {
+    /// The document's details, once its tracks begin.
+    fn header(&mut self, _header: &DocumentHeader) -> Visit {
+        Visit::Continue
+    }
+
+    /// Track `index` (from 0), with `segments` empty, once its segments
+    /// begin.
+    fn track(&mut self, _index: usize, _track: &InterchangeTrack) -> Visit {
+        Visit::Continue
+    }
+
+    /// The next segment of track `track`.
+    fn segment(&mut self, _track: usize, _segment: InterchangeSegment) -> Visit {
+        Visit::Continue
+    }
+
+    fn annotation(&mut self, _annotation: Annotation) -> Visit {
+        Visit::Continue
+    }
+}
+
+/// What a streamed read found.
+#[derive(Debug, Clone, Default)]
+pub struct StreamSummary {
+    pub header: DocumentHeader,
+    /// Tracks and segments handed to the visitor. Those passed over
+    /// unread after a `Skip` are not counted.
+    pub tracks: usize,
+    pub segments: usize,
+    /// Whether the visitor stopped the read before the end.
+    pub stopped: bool,
+}
+
+/// Read an interchange document in `encoding` from `reader`, handing its
+/// parts to `visitor`. Wrap a file in a `BufReader`.
+pub fn stream_interchange<R: Read>(
+    reader: R,
+    encoding: Encoding,
+    visitor: &mut dyn InterchangeVisitor,
+) -> Result<StreamSummary, EncodingError> {
+    let mut state = State { visitor, summary: StreamSummary::default(), header_sent: false };
+    let result = match encoding {
+        Encoding::Json => {
+            let mut de = serde_json::Deserializer::from_reader(reader);
+            DocumentSeed(&mut state).deserialize(&mut de)
+                .and_then(|()| de.end())
+                .map_err(EncodingError::from)
+        }
+        Encoding::Cbor => return Err(EncodingError::NotStreamable(encoding)),
+        Encoding::MessagePack => {
+            #[cfg(feature = "msgpack")]
+            {
+                let mut de = rmp_serde::Deserializer::new(reader);
+                DocumentSeed(&mut state).deserialize(&mut de)
+                    .map_err(|e| EncodingError::Binary(encoding, e.to_string()))
+            }
+            #[cfg(not(feature = "msgpack"))]
+            return Err(EncodingError::Unsupported(encoding, "msgpack"));
+        }
+    };
+    match result {
+        Err(_) if state.summary.stopped => Ok(state.summary),
+        Err(e) => Err(e),
+        Ok(()) => Ok(state.summary),
+    }
+}
+
+/// Stream an interchange file, detecting the encoding from its extension.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn stream_interchange_file(path: &Path, visitor: &mut dyn InterchangeVisitor) -> Result<StreamSummary, EncodingError> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    stream_interchange(file, Encoding::from_path(path), visitor)
+}
+
+struct State<'v> {
+    visitor: &'v mut dyn InterchangeVisitor,
+    summary: StreamSummary,
+    header_sent: bool,
+}
+
+impl State<'_> {
+    /// The error that unwinds the read when the visitor stops it.
+    fn stop<E: de::Error>(&mut self) -> E {
+        self.summary.stopped = true;
+        E::custom("read stopped by the visitor")
+    }
+
+    /// Give the visitor the header, once, returning its answer.
+    fn send_header(&mut self) -> Visit {
+        if std::mem::replace(&mut self.header_sent, true) {
+            return Visit::Continue;
+        }
+        self.visitor.header(&self.summary.header)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum DocumentField {
+    Version,
+    Opera,
+    TextRights,
+    TimingRights,
+    PronunciationNotation,
+    PronunciationRights,
+    SynopsisLanguage,
+    SynopsisRights,
+    Tracks,
+    Annotations,
+    #[serde(other)]
+    Other,
+}
+
+// Mirrors the fields of `InterchangeTrack`.
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum TrackField {
+    TrackId,
+    Title,
+    Album,
+    Artist,
+    DiscNumber,
+    TrackNumber,
+    DurationSeconds,
+    AlbumOffset,
+    Act,
+    Scene,
+    Segments,
+    #[serde(other)]
+    Other,
+}
+
+struct DocumentSeed<'s, 'v>(&'s mut State<'v>);
+
+impl<'de> DeserializeSeed<'de> for DocumentSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for DocumentSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an interchange libretto")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let state = self.0;
+        let mut version = false;
+        while let Some(field) = map.next_key()? {
+            match field {
+                DocumentField::Version => {
+                    state.summary.header.version = map.next_value()?;
+                    version = true;
+                }
+                DocumentField::Opera => state.summary.header.opera = Some(map.next_value()?),
+                DocumentField::TextRights => state.summary.header.text_rights = map.next_value()?,
+                DocumentField::TimingRights => state.summary.header.timing_rights = map.next_value()?,
+                DocumentField::PronunciationNotation => state.summary.header.pronunciation_notation = map.next_value()?,
+                DocumentField::PronunciationRights => state.summary.header.pronunciation_rights = map.next_value()?,
+                DocumentField::SynopsisLanguage => state.summary.header.synopsis_language = map.next_value()?,
+                DocumentField::SynopsisRights => state.summary.header.synopsis_rights = map.next_value()?,
+                DocumentField::Tracks => match state.send_header() {
+                    Visit::Continue => map.next_value_seed(ListSeed(TracksSeed(&mut *state)))?,
+                    Visit::Skip => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                    Visit::Stop => return Err(state.stop()),
+                },
+                DocumentField::Annotations => map.next_value_seed(ListSeed(AnnotationsSeed(&mut *state)))?,
+                DocumentField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !version {
+            return Err(de::Error::missing_field("version"));
+        }
+        if state.summary.header.opera.is_none() {
+            return Err(de::Error::missing_field("opera"));
+        }
+        if state.send_header() == Visit::Stop {
+            return Err(state.stop());
+        }
+        Ok(())
+    }
+}
+
+/// Reads a list one element at a time through `Element`.
+struct ListSeed<E>(E);
+
+/// What to do with each element of a list.
+trait Element {
+    /// Read the next element, returning `None` at the end of the list and
+    /// the visitor's answer otherwise.
+    fn next<'de, A: SeqAccess<'de>>(&mut self, seq: &mut A) -> Result<Option<Visit>, A::Error>;
+
+    fn stop<E: de::Error>(&mut self) -> E;
+}
+
+impl<'de, E: Element> DeserializeSeed<'de> for ListSeed<E> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, E: Element> Visitor<'de> for ListSeed<E> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(visit) = self.0.next(&mut seq)? {
+            match visit {
+                Visit::Continue => {}
+                Visit::Skip => {
+                    while seq.next_element::<IgnoredAny>()?.is_some() {}
+                    break;
+                }
+                Visit::Stop => return Err(self.0.stop()),
+            }
+        }
+        Ok(())
+    }
+}
+
+struct TracksSeed<'s, 'v>(&'s mut State<'v>);
+
+impl Element for TracksSeed<'_, '_> {
+    fn next<'de, A: SeqAccess<'de>>(&mut self, seq: &mut A) -> Result<Option<Visit>, A::Error> {
+        let index = self.0.summary.tracks;
+        let read = seq.next_element_seed(TrackSeed { state: &mut *self.0, index })?;
+        if read.is_some() {
+            self.0.summary.tracks += 1;
+        }
+        // A track's own Skip and Stop are handled as it is read
+        Ok(read.map(|()| Visit::Continue))
+    }
+
+    fn stop<E: de::Error>(&mut self) -> E {
+        self.0.stop()
+    }
+}
+
+struct SegmentsSeed<'s, 'v> {
+    state: &'s mut State<'v>,
+    track: usize,
+}
+
+impl Element for SegmentsSeed<'_, '_> {
+    fn next<'de, A: SeqAccess<'de>>(&mut self, seq: &mut A) -> Result<Option<Visit>, A::Error> {
+        let Some(segment) = seq.next_element::<InterchangeSegment>()? else {
+            return Ok(None);
+        };
+        self.state.summary.segments += 1;
+        Ok(Some(self.state.visitor.segment(self.track, segment)))
+    }
+
+    fn stop<E: de::Error>(&mut self) -> E {
+        self.state.stop()
+    }
+}
+
+struct AnnotationsSeed<'s, 'v>(&'s mut State<'v>);
+
+impl Element for AnnotationsSeed<'_, '_> {
+    fn next<'de, A: SeqAccess<'de>>(&mut self, seq: &mut A) -> Result<Option<Visit>, A::Error> {
+        Ok(seq.next_element::<Annotation>()?.map(|annotation| self.0.visitor.annotation(annotation)))
+    }
+
+    fn stop<E: de::Error>(&mut self) -> E {
+        self.0.stop()
+    }
+}
+
+struct TrackSeed<'s, 'v> {
+    state: &'s mut State<'v>,
+    index: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for TrackSeed<'_, '_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for TrackSeed<'_, '_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an interchange track")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let TrackSeed { state, index } = self;
+        let mut track = InterchangeTrack {
+            ..Default::default()
+        };
+        let (mut track_id, mut title, mut sent) = (false, false, false);
+        // Segments listed before the track's ID wait for it
+        let mut held: Option<Vec<InterchangeSegment>> = None;
+
+        while let Some(field) = map.next_key()? {
+            match field {
+                TrackField::TrackId => {
+                    track.track_id = map.next_value()?;
+                    track_id = true;
+                }
+                TrackField::Title => {
+                    track.title = map.next_value()?;
+                    title = true;
+                }
+                TrackField::Album => track.album = map.next_value()?,
+                TrackField::Artist => track.artist = map.next_value()?,
+                TrackField::DiscNumber => track.disc_number = map.next_value()?,
+                TrackField::TrackNumber => track.track_number = map.next_value()?,
+                TrackField::DurationSeconds => track.duration_seconds = map.next_value()?,
+                TrackField::AlbumOffset => track.album_offset = map.next_value()?,
+                TrackField::Act => track.act = map.next_value()?,
+                TrackField::Scene => track.scene = map.next_value()?,
+                TrackField::Segments if track_id => {
+                    sent = true;
+                    match state.visitor.track(index, &track) {
+                        Visit::Continue => map.next_value_seed(ListSeed(SegmentsSeed { state: &mut *state, track: index }))?,
+                        Visit::Skip => {
+                            map.next_value::<IgnoredAny>()?;
+                        }
+                        Visit::Stop => return Err(state.stop()),
+                    }
+                }
+                TrackField::Segments => held = Some(map.next_value()?),
+                TrackField::Other => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !track_id {
+            return Err(de::Error::missing_field("track_id"));
+        }
+        if !title {
+            return Err(de::Error::missing_field("title"));
+        }
+        if !sent && held.is_none() {
+            return Err(de::Error::missing_field("segments"));
+        }
+
+        if let Some(segments) = held {
+            match state.visitor.track(index, &track) {
+                Visit::Continue => {}
+                Visit::Skip => return Ok(()),
+                Visit::Stop => return Err(state.stop()),
+            }
+            for segment in segments {
+                state.summary.segments += 1;
+                match state.visitor.segment(index, segment) {
+                    Visit::Continue => {}
+                    Visit::Skip => break,
+                    Visit::Stop => return Err(state.stop()),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::to_bytes;
+    use crate::interchange::InterchangeLibretto;
+
+    fn sample() -> InterchangeLibretto {
+        let segment = |start: f64, text: &str| InterchangeSegment {
+            start,
+            character: Some("FIGARO".to_string()),
+            text: Some(text.to_string()),
+            act: Some("1".to_string()),
+            ..Default::default()
+        };
+        let track = |n: u32, segments| InterchangeTrack {
+            track_id: format!("d1-t{n}"),
+            title: format!("Track {n}"),
+            album: Some("Le nozze di Figaro".to_string()),
+            disc_number: Some(1),
+            track_number: Some(n),
+            duration_seconds: Some(120.0),
+            act: Some("1".to_string()),
+            segments,
+            ..Default::default()
+        };
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Le nozze di Figaro".to_string(),
+                composer: "Mozart".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks: vec![
+                track(1, vec![segment(0.0, "Cinque..."), segment(5.0, "dieci...")]),
+                track(2, vec![segment(0.0, "Se a caso madama"), segment(9.0, "Din din")]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    /// Keeps the texts of one track, and every track's ID.
+    #[derive(Default)]
+    struct Collect {
+        track_ids: Vec<String>,
+        wanted: usize,
+        texts: Vec<String>,
+        stop_after: Option<usize>,
+    }
+
+    impl InterchangeVisitor for Collect {
+        fn track(&mut self, index: usize, track: &InterchangeTrack) -> Visit {
+            self.track_ids.push(track.track_id.clone());
+            match index == self.wanted {
+                true => Visit::Continue,
+                false => Visit::Skip,
+            }
+        }
+
+        fn segment(&mut self, _track: usize, segment: InterchangeSegment) -> Visit {
+            self.texts.extend(segment.text);
+            match self.stop_after {
+                Some(n) if self.texts.len() == n => Visit::Stop,
+                _ => Visit::Continue,
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_json() {
+        let bytes = to_bytes(&sample(), Encoding::Json).unwrap();
+        let mut visitor = Collect { wanted: 1, ..Default::default() };
+        let summary = stream_interchange(bytes.as_slice(), Encoding::Json, &mut visitor).unwrap();
+        assert_eq!(visitor.track_ids, vec!["d1-t1", "d1-t2"]);
+        assert_eq!(visitor.texts, vec!["Se a caso madama", "Din din"]);
+        assert_eq!(summary.header.opera.unwrap().composer, "Mozart");
+        // The first track's segments were skipped, unread
+        assert_eq!((summary.tracks, summary.segments, summary.stopped), (2, 2, false));
+
+        let mut visitor = Collect { stop_after: Some(1), ..Default::default() };
+        let summary = stream_interchange(bytes.as_slice(), Encoding::Json, &mut visitor).unwrap();
+        assert_eq!(visitor.texts, vec!["Cinque..."]);
+        assert_eq!(visitor.track_ids, vec!["d1-t1"]);
+        assert!(summary.stopped);
+    }
+
+    #[test]
+    fn test_stream_segments_before_track_id() {
+        let json = r#"{"version": "1.0", "tracks": [{"segments": [{"start": 0, "text": "Cinque..."}], "track_id": "d1-t1", "title": "Cinque"}],
+                       "opera": {"title": "Le nozze di Figaro", "composer": "Mozart", "language": "it"}}"#;
+        let mut visitor = Collect::default();
+        let summary = stream_interchange(json.as_bytes(), Encoding::Json, &mut visitor).unwrap();
+        assert_eq!(visitor.track_ids, vec!["d1-t1"]);
+        assert_eq!(visitor.texts, vec!["Cinque..."]);
+        assert_eq!((summary.tracks, summary.segments), (1, 1));
+        assert!(summary.header.opera.is_some());
+
+        let missing = r#"{"version": "1.0", "opera": {"title": "T", "composer": "C", "language": "it"}, "tracks": [{"title": "x", "segments": []}]}"#;
+        let error = stream_interchange(missing.as_bytes(), Encoding::Json, &mut Collect::default()).unwrap_err();
+        assert!(error.to_string().contains("track_id"));
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_stream_msgpack() {
+        let bytes = to_bytes(&sample(), Encoding::MessagePack).unwrap();
+        let mut visitor = Collect::default();
+        let summary = stream_interchange(bytes.as_slice(), Encoding::MessagePack, &mut visitor).unwrap();
+        assert_eq!(visitor.texts, vec!["Cinque...", "dieci..."]);
+        assert_eq!(summary.tracks, 2);
+    }
+}
@@ -0,0 +1,380 @@
+// Surtitle cue decks for live performance.
+//
+// Companies that project surtitles advance them by hand from a numbered
+// list of slides. A deck is built from the base libretto: each sung
+// segment, or each run of one character's segments, is word-wrapped to
+// the width of the screen and split over as many slides as it needs, and
+// an empty slide closes every musical number so the screen clears during
+// the orchestral passages between them. With a timing overlay, each slide
+// notes where in the recording it starts, for rehearsing against it.
+//
+// The deck is written as plain text for the surtitle operator's score, or
+// as a CSV cue list that QLab imports as Text cues.
+
+use crate::base_libretto::{BaseLibretto, Segment};
+use crate::export::Layout;
+use crate::segment_csv::field;
+use crate::timing_overlay::{format_time, TimingOverlay};
+
+/// What a slide is made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SlideSplit {
+    /// Each sung segment starts a new slide.
+    #[default]
+    Segment,
+    /// Consecutive segments of one character share slides, until a
+    /// direction or another character interrupts them.
+    Stanza,
+}
+
+/// How a deck is laid out.
+#[derive(Debug, Clone)]
+pub struct SurtitleOptions {
+    pub layout: Layout,
+    pub split: SlideSplit,
+    /// Longest line, in characters, before a line is wrapped.
+    pub max_line_chars: usize,
+    /// Most lines per language on one slide.
+    pub max_lines: usize,
+}
+
+impl Default for SurtitleOptions {
+    fn default() -> Self {
+        Self { layout: Layout::Translation, split: SlideSplit::Segment, max_line_chars: 40, max_lines: 2 }
+    }
+}
+
+/// One numbered slide of a deck.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Slide {
+    /// Cue number, counting from 1.
+    pub cue: usize,
+    pub act: String,
+    /// Label of the musical number the slide belongs to.
+    pub number: String,
+    /// Segments shown on the slide; empty for a blank slide.
+    pub segment_ids: Vec<String>,
+    /// Lines to project; with [`Layout::Both`], the original lines then
+    /// the translation lines. Empty on a blank slide, or when the slide's
+    /// segments have no translation to show.
+    pub lines: Vec<String>,
+    /// Where the slide's first segment starts in the recording, e.g.
+    /// `d1-t2 0:03.2`.
+    pub time: Option<String>,
+}
+
+impl Slide {
+    pub fn is_blank(&self) -> bool {
+        self.segment_ids.is_empty()
+    }
+}
+
+/// Build the slides of a deck from `base`.
+///
+/// Directions are not projected. A number with no sung text gets no
+/// slides, not even a blank one.
+pub fn surtitle_slides(base: &BaseLibretto, timing: Option<&TimingOverlay>, options: &SurtitleOptions) -> Vec<Slide> {
+    let mut slides: Vec<Slide> = Vec::new();
+    for number in &base.numbers {
+        let mut runs: Vec<Vec<&Segment>> = Vec::new();
+        let mut joinable = false;
+        for seg in &number.segments {
+            if seg.text.as_deref().is_none_or(|t| t.trim().is_empty()) {
+                joinable = false;
+                continue;
+            }
+            match runs.last_mut() {
+                Some(run) if joinable && options.split == SlideSplit::Stanza && run[0].character == seg.character => run.push(seg),
+                _ => runs.push(vec![seg]),
+            }
+            joinable = true;
+        }
+        if runs.is_empty() {
+            continue;
+        }
+
+        let mut push = |segment_ids: Vec<String>, lines: Vec<String>, time: Option<String>| {
+            slides.push(Slide {
+                cue: slides.len() + 1,
+                act: number.act.clone(),
+                number: number.label.clone(),
+                segment_ids,
+                lines,
+                time,
+            });
+        };
+        for run in runs {
+            let ids: Vec<String> = run.iter().map(|s| s.id.clone()).collect();
+            let time = timing.and_then(|overlay| start_time(overlay, &ids[0]));
+            for (page, languages) in paginate(&run, options).into_iter().enumerate() {
+                push(ids.clone(), languages.concat(), if page == 0 { time.clone() } else { None });
+            }
+        }
+        push(Vec::new(), Vec::new(), None);
+    }
+    slides
+}
+
+/// Split a run of segments over slides, each slide holding the lines of
+/// every shown language. A segment's original and translation are kept
+/// on the same slide, so the two stay side by side even when they wrap
+/// to different line counts; a segment too long for one slide is split
+/// into pages of both languages at once. A segment with no translation
+/// leaves the translation blank rather than repeating the original.
+fn paginate(run: &[&Segment], options: &SurtitleOptions) -> Vec<Vec<Vec<String>>> {
+    let max_lines = options.max_lines.max(1);
+    let width = options.max_line_chars;
+    let shown = if options.layout == Layout::Both { 2 } else { 1 };
+    let mut pages = Vec::new();
+    let mut page: Vec<Vec<String>> = vec![Vec::new(); shown];
+    for seg in run {
+        let original = wrap(seg.text.as_deref().into_iter(), width);
+        let translation = wrap(seg.translation.as_deref().into_iter(), width);
+        let block = match options.layout {
+            Layout::Original => vec![original],
+            Layout::Translation => vec![translation],
+            Layout::Both => vec![original, translation],
+        };
+        let fits = page.iter().zip(&block).all(|(lines, more)| lines.len() + more.len() <= max_lines);
+        if !fits && page.iter().any(|lines| !lines.is_empty()) {
+            pages.push(std::mem::replace(&mut page, vec![Vec::new(); shown]));
+        }
+        let height = block.iter().map(Vec::len).max().unwrap_or(0);
+        for start in (0..height).step_by(max_lines) {
+            if start > 0 {
+                pages.push(std::mem::replace(&mut page, vec![Vec::new(); shown]));
+            }
+            for (lines, more) in page.iter_mut().zip(&block) {
+                lines.extend(more.iter().skip(start).take(max_lines).cloned());
+            }
+        }
+    }
+    pages.push(page);
+    pages
+}
+
+/// Start of `segment_id` in the first track that times it.
+fn start_time(overlay: &TimingOverlay, segment_id: &str) -> Option<String> {
+    overlay.track_timings.iter().enumerate().find_map(|(i, track)| {
+        track.segment_times.iter()
+            .find(|st| st.segment_id == segment_id)
+            .map(|st| format!("{} {}", track.track_id(i), format_time(st.start)))
+    })
+}
+
+/// Word-wrap every line of `texts` to `width` characters. A word longer
+/// than the width gets a line of its own.
+fn wrap<'a>(texts: impl Iterator<Item = &'a str>, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in texts.flat_map(str::lines).map(str::trim).filter(|l| !l.is_empty()) {
+        let mut current = String::new();
+        for word in line.split_whitespace() {
+            if !current.is_empty() && current.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        lines.push(current);
+    }
+    lines
+}
+
+/// Write a deck as plain text: a heading per act and number, then each
+/// cue number with its recording time and segments, and the slide's
+/// lines. Blank slides read `(blank)`.
+pub fn deck_text(base: &BaseLibretto, slides: &[Slide]) -> String {
+    let mut out = format!("{} — surtitles, {} cues\n", base.opera.title, slides.len());
+    let mut act: Option<&str> = None;
+    let mut number: Option<&str> = None;
+    for slide in slides {
+        if act != Some(slide.act.as_str()) {
+            out.push_str(&format!("\n=== Act {} ===\n", slide.act));
+            act = Some(slide.act.as_str());
+            number = None;
+        }
+        if number != Some(slide.number.as_str()) {
+            out.push_str(&format!("\n--- {} ---\n", slide.number));
+            number = Some(slide.number.as_str());
+        }
+        let mut heading = format!("\n[{}]", slide.cue);
+        if let Some(time) = &slide.time {
+            heading.push_str(&format!(" {time}"));
+        }
+        if let Some(first) = slide.segment_ids.first() {
+            heading.push_str(&format!("  {first}"));
+            if let Some(last) = slide.segment_ids.last().filter(|&last| last != first) {
+                heading.push_str(&format!(" … {last}"));
+            }
+        }
+        out.push_str(&heading);
+        out.push('\n');
+        if slide.is_blank() {
+            out.push_str("(blank)\n");
+        }
+        for line in &slide.lines {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Write a deck as a CSV cue list for QLab's cue import: one Text cue per
+/// slide, numbered by cue, named by its first line, with the number label
+/// and recording time in the notes.
+pub fn deck_qlab(slides: &[Slide]) -> String {
+    let mut csv = String::from("Number,Type,Name,Text,Notes\n");
+    for slide in slides {
+        let cue = slide.cue.to_string();
+        let name = slide.lines.first().map(String::as_str).unwrap_or("(blank)");
+        let notes = match &slide.time {
+            Some(time) => format!("{} · {time}", slide.number),
+            None => slide.number.clone(),
+        };
+        let text = slide.lines.join("\n");
+        let row = [cue.as_str(), "Text", name, text.as_str(), notes.as_str()];
+        csv.push_str(&row.map(field).join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::segment;
+    use crate::base_libretto::*;
+    use crate::timing_overlay::*;
+
+    fn base() -> BaseLibretto {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "W. A. Mozart".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: None,
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "No. 1 - Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![
+                segment("no-1-duettino-001", Some("FIGARO"), Some("Cinque... dieci..."), Some("Five... ten..."), None),
+                segment("no-1-duettino-002", Some("FIGARO"), Some("Venti... trenta..."), Some("Twenty... thirty..."), None),
+                segment("no-1-duettino-003", None, None, None, Some("Exit.")),
+                segment(
+                    "no-1-duettino-004",
+                    Some("SUSANNA"),
+                    Some("Ora sì ch'io son contenta"),
+                    Some("How pleased I am now, it seems made for me"),
+                    None,
+                ),
+            ],
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-2-recitativo".to_string(),
+            label: "No. 2 - Recitativo".to_string(),
+            number_type: NumberType::Recitative,
+            act: "1".to_string(),
+            scene: None,
+            segments: vec![segment("no-2-recitativo-001", None, None, None, Some("Exit."))],
+        });
+        lib
+    }
+
+    #[test]
+    fn test_slides_per_segment() {
+        let options = SurtitleOptions { max_line_chars: 20, ..Default::default() };
+        let slides = surtitle_slides(&base(), None, &options);
+        let lines: Vec<_> = slides.iter().map(|s| s.lines.join(" / ")).collect();
+        assert_eq!(lines, [
+            "Five... ten...",
+            "Twenty... thirty...",
+            "How pleased I am / now, it seems made",
+            "for me",
+            "",
+        ]);
+        assert_eq!(slides[3].segment_ids, ["no-1-duettino-004"]);
+        assert!(slides[4].is_blank());
+        assert_eq!(slides.iter().map(|s| s.cue).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_slides_per_stanza() {
+        let options = SurtitleOptions { layout: Layout::Both, split: SlideSplit::Stanza, max_line_chars: 40, max_lines: 2 };
+        let slides = surtitle_slides(&base(), None, &options);
+        assert_eq!(slides[0].segment_ids, ["no-1-duettino-001", "no-1-duettino-002"]);
+        assert_eq!(slides[0].lines, [
+            "Cinque... dieci...",
+            "Venti... trenta...",
+            "Five... ten...",
+            "Twenty... thirty...",
+        ]);
+        // The direction ends the stanza.
+        assert_eq!(slides[1].segment_ids, ["no-1-duettino-004"]);
+        assert_eq!(slides.len(), 3);
+    }
+
+    #[test]
+    fn test_both_pages_in_pairs() {
+        let mut lib = base();
+        lib.numbers[0].segments = vec![
+            segment("no-1-duettino-001", Some("FIGARO"), Some("Cinque"), Some("Five\nand again five"), None),
+            segment("no-1-duettino-002", Some("FIGARO"), Some("Venti\ntrenta"), Some("Twenty, thirty"), None),
+        ];
+        let options = SurtitleOptions { layout: Layout::Both, split: SlideSplit::Stanza, max_line_chars: 40, max_lines: 2 };
+        let slides = surtitle_slides(&lib, None, &options);
+        assert_eq!(slides[0].lines, ["Cinque", "Five", "and again five"]);
+        assert_eq!(slides[1].lines, ["Venti", "trenta", "Twenty, thirty"]);
+        assert!(slides[2].is_blank());
+    }
+
+    #[test]
+    fn test_missing_translation_left_blank() {
+        let mut lib = base();
+        lib.numbers[0].segments[1].translation = None;
+        let slides = surtitle_slides(&lib, None, &SurtitleOptions::default());
+        assert_eq!(slides[1].segment_ids, ["no-1-duettino-002"]);
+        assert!(slides[1].lines.is_empty());
+        assert!(!slides[1].is_blank());
+
+        let options = SurtitleOptions { layout: Layout::Both, ..Default::default() };
+        let slides = surtitle_slides(&lib, None, &options);
+        assert_eq!(slides[1].lines, ["Venti... trenta..."]);
+    }
+
+    #[test]
+    fn test_decks() {
+        let mut overlay = TimingOverlay::new("base.libretto.json");
+        overlay.track_timings.push(TrackTiming {
+            track_title: "Cinque... dieci...".to_string(),
+            disc_number: Some(1),
+            track_number: Some(2),
+            number_ids: vec!["no-1-duettino".to_string()],
+            segment_times: vec![SegmentTime { segment_id: "no-1-duettino-002".to_string(), start: 63.25, end: None, source: None }],
+            ..Default::default()
+        });
+        let lib = base();
+        let slides = surtitle_slides(&lib, Some(&overlay), &SurtitleOptions::default());
+        assert_eq!(slides[1].time.as_deref(), Some("d1-t2 1:03.3"));
+
+        let text = deck_text(&lib, &slides);
+        assert!(text.starts_with("Le nozze di Figaro — surtitles, 4 cues\n\n=== Act 1 ===\n\n--- No. 1 - Duettino ---\n"));
+        assert!(text.contains("\n[2] d1-t2 1:03.3  no-1-duettino-002\nTwenty... thirty...\n"));
+        assert!(text.contains("\n[4]\n(blank)\n"));
+
+        let csv = deck_qlab(&slides);
+        let rows: Vec<_> = csv.lines().collect();
+        assert_eq!(rows[0], "Number,Type,Name,Text,Notes");
+        assert_eq!(rows[2], "2,Text,Twenty... thirty...,Twenty... thirty...,No. 1 - Duettino · d1-t2 1:03.3");
+        assert_eq!(rows[3], "3,Text,\"How pleased I am now, it seems made for\",\"How pleased I am now, it seems made for");
+        assert_eq!(rows[4], "me\",No. 1 - Duettino");
+        assert_eq!(rows[5], "4,Text,(blank),,No. 1 - Duettino");
+    }
+}
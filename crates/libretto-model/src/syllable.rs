@@ -0,0 +1,96 @@
+// Counting syllables in libretto text.
+//
+// Sung time follows syllables more closely than words: "Cinque" and
+// "a" take very different lengths of time, and Italian text is full of
+// long words set one note per syllable. Counts here are orthographic
+// heuristics, good enough to weight estimates, not a prosodic analysis.
+//
+// Italian counts groups of adjacent vowels, where `i` and `u` glide into
+// a neighbouring vowel (diphthongs like "pie", "guar", "chia") but two of
+// `a`, `e`, `o` side by side are separate syllables ("pa-e-se"). Falling
+// diphthongs of two strong vowels are counted as two as well, so "ciao"
+// is "cia-o", one more than is sung. Other
+// languages count vowel groups, dropping a silent final `e` in French
+// and English.
+
+/// Count the syllables in `text`, written in the language with ISO 639-1
+/// code `language`. Elided fragments without vowels ("ch'", "l'") add nothing.
+pub fn count_syllables(text: &str, language: &str) -> usize {
+    text.split(|c: char| c.is_whitespace() || c == '\'' || c == '’' || c == '-')
+        .map(|word| word.chars().filter(|c| c.is_alphabetic()).flat_map(char::to_lowercase).collect::<String>())
+        .filter(|word| !word.is_empty())
+        .map(|word| match language {
+            "it" => italian_word(&word),
+            _ => generic_word(&word, language),
+        })
+        .sum()
+}
+
+fn is_vowel(c: char) -> bool {
+    "aeiouyàáâäèéêëìíîïòóôöùúûüæœå".contains(c)
+}
+
+/// Vowels that form their own syllable next to another vowel.
+fn is_strong_italian(c: char) -> bool {
+    "aeoàáèéìíòóùú".contains(c)
+}
+
+fn italian_word(word: &str) -> usize {
+    let chars: Vec<char> = word.chars().collect();
+    let mut count = 0;
+    for (i, &c) in chars.iter().enumerate() {
+        if !is_vowel(c) {
+            continue;
+        }
+        match i.checked_sub(1).map(|p| chars[p]) {
+            // A new vowel group
+            Some(prev) if !is_vowel(prev) => count += 1,
+            None => count += 1,
+            // Hiatus: two strong vowels are separate syllables
+            Some(prev) if is_strong_italian(prev) && is_strong_italian(c) => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+fn generic_word(word: &str, language: &str) -> usize {
+    let chars: Vec<char> = word.chars().collect();
+    let mut count = chars.iter()
+        .enumerate()
+        .filter(|&(i, &c)| is_vowel(c) && (i == 0 || !is_vowel(chars[i - 1])))
+        .count();
+    let silent_final_e = matches!(language, "fr" | "en")
+        && chars.len() > 2
+        && chars.ends_with(&['e'])
+        && !is_vowel(chars[chars.len() - 2]);
+    if silent_final_e && count > 1 {
+        count -= 1;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_italian_syllables() {
+        assert_eq!(count_syllables("Cinque, dieci, venti", "it"), 6);
+        assert_eq!(count_syllables("paese", "it"), 3);
+        assert_eq!(count_syllables("chiaro", "it"), 2);
+        // Over-counted: "ao" is sung as one syllable
+        assert_eq!(count_syllables("ciao", "it"), 2);
+        assert_eq!(count_syllables("Voi che sapete che cosa è amor", "it"), 11);
+        // Elided words count with the word they attach to
+        assert_eq!(count_syllables("ch'io", "it"), 1);
+        assert_eq!(count_syllables("Quello ch'io provo vi ridirò", "it"), 9);
+    }
+
+    #[test]
+    fn test_other_languages() {
+        assert_eq!(count_syllables("Der Hölle Rache kocht in meinem Herzen", "de"), 11);
+        assert_eq!(count_syllables("L'amour est un oiseau rebelle", "fr"), 8);
+        assert_eq!(count_syllables("— !", "de"), 0);
+    }
+}
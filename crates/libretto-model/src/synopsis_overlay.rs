@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rights::Rights;
+use crate::timing_overlay::Contributor;
+
+/// A synopsis overlay: a line or two of plot for each musical number of a
+/// base libretto, keyed by number ID.
+///
+/// Lighter than an annotation overlay, it holds nothing but the synopses,
+/// so one can be written in a single sitting with the libretto's number
+/// list at hand:
+///
+/// ```json
+/// { "version": "1.0", "base_libretto": "base.libretto.json", "language": "en",
+///   "synopses": { "no-2-duettino": "The Count, Figaro warns, has designs on Susanna." } }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SynopsisOverlay {
+    pub version: String,
+    /// Path to the base libretto this overlay references (relative to library root).
+    pub base_libretto: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contributors: Vec<Contributor>,
+    /// Licensing for the synopsis text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rights: Option<Rights>,
+    /// Language the synopses are written in (ISO 639-1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Number ID → synopsis.
+    pub synopses: BTreeMap<String, String>,
+}
+
+impl SynopsisOverlay {
+    /// The synopsis of the given musical number ID.
+    pub fn for_number(&self, number_id: &str) -> Option<&str> {
+        self.synopses.get(number_id).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_number() {
+        let json = r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "synopses": {
+                "no-1-duettino": "Figaro measures the room the Count has given them.",
+                "no-2-duettino": "Susanna tells him why the Count chose it."
+            }
+        }"#;
+        let overlay: SynopsisOverlay = serde_json::from_str(json).unwrap();
+        assert_eq!(overlay.language, None);
+        assert_eq!(overlay.for_number("no-2-duettino"), Some("Susanna tells him why the Count chose it."));
+        assert_eq!(overlay.for_number("no-3-cavatina"), None);
+    }
+}
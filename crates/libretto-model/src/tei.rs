@@ -0,0 +1,208 @@
+// TEI P5 export of a base libretto.
+//
+// Digital-humanities projects read drama through the Text Encoding
+// Initiative's performance-text module, so a parsed libretto written as
+// TEI can go straight into their corpora, collation tools and XSLT.
+// The cast becomes a `<castList>` of roles, each act a `<div>` holding a
+// `<div>` per musical number, sung text `<sp>` blocks of verse lines
+// whose `who` points at the roles singing, and directions `<stage>`.
+// Translations sit with the original as a line group in the translation
+// language. Every segment keeps its ID as `xml:id`, so annotations made
+// against the TEI can be mapped back onto the libretto.
+
+use crate::base_libretto::{split_character_attribution, BaseLibretto, Segment};
+use crate::export::{slug, xml_escape};
+
+/// Write a base libretto as a TEI P5 document.
+pub fn export_tei(base: &BaseLibretto) -> String {
+    let opera = &base.opera;
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str(&format!("<TEI xmlns=\"http://www.tei-c.org/ns/1.0\" xml:lang=\"{}\">\n", xml_escape(&opera.language)));
+
+    out.push_str("  <teiHeader>\n    <fileDesc>\n      <titleStmt>\n");
+    out.push_str(&format!("        <title>{}</title>\n", xml_escape(&opera.title)));
+    out.push_str(&format!("        <author role=\"composer\">{}</author>\n", xml_escape(&opera.composer)));
+    if let Some(librettist) = &opera.librettist {
+        out.push_str(&format!("        <author role=\"librettist\">{}</author>\n", xml_escape(librettist)));
+    }
+    out.push_str("      </titleStmt>\n");
+    out.push_str("      <publicationStmt>\n        <p>Exported from a libretto base file.</p>\n      </publicationStmt>\n");
+    out.push_str("      <sourceDesc>\n");
+    match opera.year {
+        Some(year) => out.push_str(&format!("        <p>Libretto of <title>{}</title>, <date when=\"{year:04}\">{year}</date>.</p>\n", xml_escape(&opera.title))),
+        None => out.push_str(&format!("        <p>Libretto of <title>{}</title>.</p>\n", xml_escape(&opera.title))),
+    }
+    out.push_str("      </sourceDesc>\n    </fileDesc>\n");
+    out.push_str("    <profileDesc>\n      <langUsage>\n");
+    out.push_str(&format!("        <language ident=\"{}\"/>\n", xml_escape(&opera.language)));
+    if let Some(translation) = &opera.translation_language {
+        out.push_str(&format!("        <language ident=\"{}\">translation</language>\n", xml_escape(translation)));
+    }
+    out.push_str("      </langUsage>\n    </profileDesc>\n  </teiHeader>\n");
+
+    out.push_str("  <text>\n");
+    if !base.cast.is_empty() {
+        out.push_str("    <front>\n      <castList>\n");
+        for member in &base.cast {
+            let name = member.short_name.as_deref().unwrap_or(&member.character);
+            out.push_str(&format!(
+                "        <castItem><role xml:id=\"{}\">{}</role>",
+                role_id(name),
+                xml_escape(&member.character)
+            ));
+            let desc: Vec<&str> = [member.description.as_deref(), member.voice_type.as_deref()].into_iter().flatten().collect();
+            if !desc.is_empty() {
+                out.push_str(&format!("<roleDesc>{}</roleDesc>", xml_escape(&desc.join(", "))));
+            }
+            out.push_str("</castItem>\n");
+        }
+        out.push_str("      </castList>\n    </front>\n");
+    }
+
+    out.push_str("    <body>\n");
+    let mut act: Option<&str> = None;
+    for number in &base.numbers {
+        if act != Some(number.act.as_str()) {
+            if act.is_some() {
+                out.push_str("      </div>\n");
+            }
+            out.push_str(&format!("      <div type=\"act\" n=\"{}\">\n", xml_escape(&number.act)));
+            out.push_str(&format!("        <head>Act {}</head>\n", xml_escape(&number.act)));
+            act = Some(number.act.as_str());
+        }
+        let subtype = serde_json::to_value(&number.number_type).ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        out.push_str(&format!("        <div type=\"number\" subtype=\"{subtype}\" xml:id=\"{}\"", xml_escape(&number.id)));
+        if let Some(scene) = &number.scene {
+            out.push_str(&format!(" n=\"scene {}\"", xml_escape(scene)));
+        }
+        out.push_str(&format!(">\n          <head>{}</head>\n", xml_escape(&number.label)));
+        for seg in &number.segments {
+            out.push_str(&segment_xml(base, seg));
+        }
+        out.push_str("        </div>\n");
+    }
+    if act.is_some() {
+        out.push_str("      </div>\n");
+    }
+    out.push_str("    </body>\n  </text>\n</TEI>\n");
+    out
+}
+
+/// An `<sp>` for sung text, a `<stage>` for a direction.
+fn segment_xml(base: &BaseLibretto, seg: &Segment) -> String {
+    let indent = "          ";
+    let Some(text) = &seg.text else {
+        let direction = seg.direction.as_deref().map(|d| lines(d).join(" ")).unwrap_or_default();
+        return format!("{indent}<stage xml:id=\"{}\">{direction}</stage>\n", xml_escape(&seg.id));
+    };
+    let mut sp = format!("{indent}<sp xml:id=\"{}\"", xml_escape(&seg.id));
+    if let Some(character) = &seg.character {
+        let who: Vec<String> = split_character_attribution(character).iter()
+            .map(|name| {
+                let name = base.find_cast_member(name)
+                    .map(|m| m.short_name.as_deref().unwrap_or(&m.character))
+                    .unwrap_or(name);
+                format!("#{}", role_id(name))
+            })
+            .collect();
+        sp.push_str(&format!(" who=\"{}\">\n{indent}  <speaker>{}</speaker>\n", who.join(" "), xml_escape(character)));
+    } else {
+        sp.push_str(">\n");
+    }
+    for line in lines(text) {
+        sp.push_str(&format!("{indent}  <l>{line}</l>\n"));
+    }
+    if let Some(translation) = seg.translation.as_deref().filter(|t| !t.trim().is_empty()) {
+        match &base.opera.translation_language {
+            Some(lang) => sp.push_str(&format!("{indent}  <lg type=\"translation\" xml:lang=\"{}\">\n", xml_escape(lang))),
+            None => sp.push_str(&format!("{indent}  <lg type=\"translation\">\n")),
+        }
+        for line in lines(translation) {
+            sp.push_str(&format!("{indent}    <l>{line}</l>\n"));
+        }
+        sp.push_str(&format!("{indent}  </lg>\n"));
+    }
+    sp.push_str(&format!("{indent}</sp>\n"));
+    sp
+}
+
+/// `role-` and the character's name as a slug, e.g. `role-il-conte`.
+fn role_id(name: &str) -> String {
+    format!("role-{}", slug(name))
+}
+
+/// The non-empty lines of `text`, escaped.
+fn lines(text: &str) -> Vec<String> {
+    text.lines().map(str::trim).filter(|l| !l.is_empty()).map(xml_escape).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::segment;
+    use crate::base_libretto::*;
+
+    fn base() -> BaseLibretto {
+        let mut lib = BaseLibretto::new(OperaMetadata {
+            title: "Le nozze di Figaro".to_string(),
+            composer: "W. A. Mozart".to_string(),
+            librettist: Some("Lorenzo Da Ponte".to_string()),
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: Some(1786),
+        });
+        lib.cast.push(CastMember {
+            character: "Figaro".to_string(),
+            short_name: Some("FIGARO".to_string()),
+            voice_type: Some("bass".to_string()),
+            description: Some("valet to the Count".to_string()),
+            aliases: vec![],
+        });
+        lib.cast.push(CastMember {
+            character: "Susanna".to_string(),
+            short_name: Some("SUSANNA".to_string()),
+            voice_type: Some("soprano".to_string()),
+            description: None,
+            aliases: vec![],
+        });
+        lib.numbers.push(MusicalNumber {
+            id: "no-1-duettino".to_string(),
+            label: "No. 1 - Duettino".to_string(),
+            number_type: NumberType::Duettino,
+            act: "1".to_string(),
+            scene: Some("1".to_string()),
+            segments: vec![
+                segment("no-1-duettino-001", None, None, None, Some("Figaro measures the room & Susanna\ntries on a hat.")),
+                segment("no-1-duettino-002", Some("SUSANNA, FIGARO"), Some("Cinque...\ndieci..."), Some("Five...\nten..."), None),
+            ],
+        });
+        lib
+    }
+
+    #[test]
+    fn test_export_tei() {
+        let tei = export_tei(&base());
+        assert!(tei.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<TEI xmlns=\"http://www.tei-c.org/ns/1.0\" xml:lang=\"it\">"));
+        assert!(tei.contains("<author role=\"librettist\">Lorenzo Da Ponte</author>"));
+        assert!(tei.contains("<date when=\"1786\">1786</date>"));
+        assert!(tei.contains(
+            "<castItem><role xml:id=\"role-figaro\">Figaro</role><roleDesc>valet to the Count, bass</roleDesc></castItem>"
+        ));
+        assert!(tei.contains("<div type=\"act\" n=\"1\">\n        <head>Act 1</head>"));
+        assert!(tei.contains("<div type=\"number\" subtype=\"duettino\" xml:id=\"no-1-duettino\" n=\"scene 1\">"));
+        assert!(tei.contains(
+            "<stage xml:id=\"no-1-duettino-001\">Figaro measures the room &amp; Susanna tries on a hat.</stage>"
+        ));
+        assert!(tei.contains(
+            "<sp xml:id=\"no-1-duettino-002\" who=\"#role-susanna #role-figaro\">\n            \
+             <speaker>SUSANNA, FIGARO</speaker>\n            \
+             <l>Cinque...</l>\n            \
+             <l>dieci...</l>\n            \
+             <lg type=\"translation\" xml:lang=\"en\">\n              \
+             <l>Five...</l>\n"
+        ));
+        assert!(tei.ends_with("      </div>\n    </body>\n  </text>\n</TEI>\n"));
+    }
+}
@@ -0,0 +1,143 @@
+// Absolute album timeline: cumulative start times across all tracks.
+//
+// Interchange segment times are relative to their own track. Clients that
+// play a whole recording as one file (or gaplessly) need album-level times
+// instead, which requires every preceding track's duration.
+
+use crate::interchange::{InterchangeLibretto, InterchangeSegment, InterchangeTrack};
+
+/// Compute each track's offset from the start of the album.
+///
+/// The first track starts at 0. Each following offset is the previous
+/// offset plus the previous track's duration; once a duration is missing,
+/// no later track can be placed and its offset is `None`.
+pub fn track_offsets<I>(durations: I) -> Vec<Option<f64>>
+where
+    I: IntoIterator<Item = Option<f64>>,
+{
+    let mut offsets = Vec::new();
+    let mut next = Some(0.0);
+    for duration in durations {
+        offsets.push(next);
+        next = next.zip(duration).map(|(offset, d)| offset + d);
+    }
+    offsets
+}
+
+/// Fill in `album_offset` on every track and `album_start` on every segment.
+///
+/// Tracks are taken in document order, which `merge` produces in disc and
+/// track order. Returns a warning if a missing track duration left later
+/// tracks without absolute times.
+pub fn apply_absolute_times(doc: &mut InterchangeLibretto) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let offsets = track_offsets(doc.tracks.iter().map(|t| t.duration_seconds));
+
+    for (track, offset) in doc.tracks.iter_mut().zip(offsets) {
+        track.album_offset = offset;
+        for seg in &mut track.segments {
+            seg.album_start = offset.filter(|_| !seg.untimed).map(|o| o + seg.start);
+        }
+    }
+
+    if let Some(i) = doc.tracks.iter().position(|t| t.duration_seconds.is_none()) {
+        let unplaced = doc.tracks.len() - i - 1;
+        if unplaced > 0 {
+            warnings.push(format!(
+                "Track '{}' has no duration; {} later tracks have no absolute times",
+                doc.tracks[i].track_id, unplaced
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Find the track and active segment at an album-level playback time.
+///
+/// Requires `apply_absolute_times` to have been run. Returns `None` before
+/// the first segment or when the time falls in a track without an offset.
+pub fn segment_at_album_time(
+    doc: &InterchangeLibretto,
+    time: f64,
+) -> Option<(&InterchangeTrack, &InterchangeSegment)> {
+    let track = doc.tracks.iter()
+        .rev()
+        .find(|t| t.album_offset.is_some_and(|o| o <= time))?;
+    let seg = track.segment_at(time - track.album_offset?)?;
+    Some((track, seg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interchange::InterchangeOpera;
+
+    fn track(id: &str, duration: Option<f64>, starts: &[f64]) -> InterchangeTrack {
+        InterchangeTrack {
+            track_id: id.to_string(),
+            title: id.to_string(),
+            duration_seconds: duration,
+            segments: starts.iter().map(|&start| InterchangeSegment {
+                start,
+                text: Some(format!("{id}@{start}")),
+                ..Default::default()
+            }).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn doc(tracks: Vec<InterchangeTrack>) -> InterchangeLibretto {
+        InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Test".to_string(),
+                composer: "Test".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            tracks,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_track_offsets() {
+        assert_eq!(
+            track_offsets([Some(100.0), Some(50.0), None, Some(10.0)]),
+            vec![Some(0.0), Some(100.0), Some(150.0), None]
+        );
+        assert!(track_offsets([]).is_empty());
+    }
+
+    #[test]
+    fn test_apply_absolute_times() {
+        let mut doc = doc(vec![
+            track("t1", Some(100.0), &[0.0, 40.0]),
+            track("t2", Some(60.0), &[0.0, 30.0]),
+        ]);
+        let warnings = apply_absolute_times(&mut doc);
+        assert!(warnings.is_empty());
+        assert_eq!(doc.tracks[1].album_offset, Some(100.0));
+        assert_eq!(doc.tracks[1].segments[1].album_start, Some(130.0));
+
+        let (t, seg) = segment_at_album_time(&doc, 135.0).unwrap();
+        assert_eq!(t.track_id, "t2");
+        assert_eq!(seg.text.as_deref(), Some("t2@30"));
+        let (t, _) = segment_at_album_time(&doc, 99.0).unwrap();
+        assert_eq!(t.track_id, "t1");
+    }
+
+    #[test]
+    fn test_missing_duration_warns() {
+        let mut doc = doc(vec![
+            track("t1", None, &[0.0]),
+            track("t2", Some(60.0), &[0.0]),
+        ]);
+        let warnings = apply_absolute_times(&mut doc);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(doc.tracks[0].segments[0].album_start, Some(0.0));
+        assert_eq!(doc.tracks[1].album_offset, None);
+        assert_eq!(doc.tracks[1].segments[0].album_start, None);
+    }
+}
@@ -1,26 +1,38 @@
 use serde::{Deserialize, Serialize};
 
+use crate::rights::Rights;
+
 /// A timing overlay: recording-specific timing data that references
 /// a base libretto's segment IDs.
 ///
 /// This is the output of the timing tool — it maps segment IDs to
 /// start times within specific audio tracks for a particular recording.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TimingOverlay {
     pub version: String,
     /// Path to the base libretto this overlay references (relative to library root).
     pub base_libretto: String,
+    /// SHA-256 of the base libretto file the overlay was made against, so
+    /// later edits to the base can be detected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_sha256: Option<String>,
     pub recording: RecordingMetadata,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub contributors: Vec<Contributor>,
+    /// Licensing for the timing data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rights: Option<Rights>,
     pub track_timings: Vec<TrackTiming>,
     /// Numbers from the base libretto that this recording does not perform.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub omitted_numbers: Vec<OmittedNumber>,
+    /// Time without text at the ends of tracks, for tracks that set none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<Padding>,
 }
 
 /// Metadata about the specific recording this timing is for.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct RecordingMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub conductor: Option<String>,
@@ -35,7 +47,7 @@ pub struct RecordingMetadata {
 }
 
 /// A person who contributed timing data.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Contributor {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -45,7 +57,7 @@ pub struct Contributor {
 }
 
 /// Timing data for a single audio track.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TrackTiming {
     /// Track title as it appears in the album metadata.
     pub track_title: String,
@@ -62,11 +74,68 @@ pub struct TrackTiming {
     /// at the first segment of the referenced number_ids.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub start_segment_id: Option<String>,
+    /// Who set `start_segment_id` (and `section_marks`, `instrumental`).
+    /// Absent in overlays from before it was recorded; `timing resolve
+    /// --force` resolves such tracks again, so a start chosen by hand must
+    /// say so.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<ResolvedBy>,
+    /// Where each anchor quoted in the track title starts, in title order
+    /// (from anchor matching): the sections of a track holding several
+    /// numbers or a recitative and aria.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub section_marks: Vec<SectionMark>,
+    /// The track has no text (an overture, march or entr'acte): it starts
+    /// at no segment, and is shown as a single interlude.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub instrumental: bool,
+    /// Segments of this track's numbers that the recording cuts (a verse,
+    /// a repeat), left out when estimating the track's segment times.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skip_segment_ids: Vec<String>,
+    /// Time without text at the ends of this track, overriding the overlay's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub padding: Option<Padding>,
     /// Timed segment references, ordered by start time.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub segment_times: Vec<SegmentTime>,
 }
 
+/// Who resolved a track's start segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResolvedBy {
+    /// Anchor matching in `timing resolve`, which `--force` may redo.
+    Resolve,
+    /// A person, whose choice re-resolution keeps.
+    Manual,
+}
+
+fn is_false(b: &bool) -> bool {
+    !b
+}
+
+/// Time at the ends of a track with no text sung: an orchestral
+/// introduction before the first line, applause after the last. Estimates
+/// leave it out of the time they distribute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Padding {
+    #[serde(default)]
+    pub head_seconds: f64,
+    #[serde(default)]
+    pub tail_seconds: f64,
+}
+
+/// A section of a track, starting at a segment an anchor in the track
+/// title matched.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionMark {
+    pub segment_id: String,
+    /// Whether the title labels the section a recitative.
+    #[serde(default)]
+    pub is_recitative: bool,
+}
+
 /// A musical number explicitly declared as not performed in this recording.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OmittedNumber {
@@ -78,15 +147,49 @@ pub struct OmittedNumber {
 }
 
 /// A single segment's timing within a track.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SegmentTime {
     /// References a segment ID in the base libretto.
     pub segment_id: String,
     /// Start time in seconds from the beginning of the track.
     pub start: f64,
+    /// End time, when the segment ends before the next one starts (e.g.
+    /// imported from subtitle cues). Usually omitted: the end is then the
+    /// next segment's start or the track's end.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<f64>,
+    /// How the time was made. Omitted for times entered by hand before
+    /// sources were recorded, which count as tapped (see `trust`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<TimingSource>,
+}
+
+/// Where a segment time came from, from least to most trustworthy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimingSource {
+    /// Spread over the track by word count (`timing estimate`).
+    Estimated,
+    /// Found by a forced aligner (`timing align-audio`).
+    Aligned,
+    /// Taken from lyrics or subtitle files made for another purpose.
+    Imported,
+    /// Marked by someone listening to the recording.
+    Tapped,
+    /// Checked by someone against the recording.
+    Verified,
 }
 
 impl TimingOverlay {
+    /// Create an empty overlay referencing the base libretto at `base_libretto`.
+    pub fn new(base_libretto: &str) -> Self {
+        Self {
+            version: "1.0".to_string(),
+            base_libretto: base_libretto.to_string(),
+            ..Default::default()
+        }
+    }
+
     /// Get all segment IDs referenced in this overlay, in order.
     pub fn segment_ids(&self) -> Vec<&str> {
         self.track_timings
@@ -110,6 +213,135 @@ impl TimingOverlay {
     pub fn omitted_number_ids(&self) -> Vec<&str> {
         self.omitted_numbers.iter().map(|o| o.number_id.as_str()).collect()
     }
+
+    /// Credit `name` with `role` (the operation, e.g. "estimate") on
+    /// `date`: the date of their entry for that role is updated, or an
+    /// entry added.
+    pub fn stamp_contributor(&mut self, name: &str, role: &str, date: &str) {
+        let existing = self.contributors.iter_mut()
+            .find(|c| c.name == name && c.role.as_deref() == Some(role));
+        match existing {
+            Some(contributor) => contributor.date = Some(date.to_string()),
+            None => self.contributors.push(Contributor {
+                name: name.to_string(),
+                role: Some(role.to_string()),
+                date: Some(date.to_string()),
+            }),
+        }
+    }
+
+    /// Index of the track whose `track_id` is `id`.
+    pub fn find_track(&self, id: &str) -> Option<usize> {
+        self.track_timings.iter().enumerate().position(|(i, t)| t.track_id(i) == id)
+    }
+
+    /// Shift every start time in the track at `index` by `delta` seconds.
+    ///
+    /// Returns `false` if there is no such track.
+    pub fn shift_track(&mut self, index: usize, delta: f64) -> bool {
+        self.track_timings.get_mut(index).map(|t| t.shift(delta)).is_some()
+    }
+
+    /// Scale every start time in the track at `index` by `factor`.
+    ///
+    /// Returns `false` if there is no such track.
+    pub fn scale_track(&mut self, index: usize, factor: f64) -> bool {
+        self.track_timings.get_mut(index).map(|t| t.scale(factor)).is_some()
+    }
+
+    /// Sort the segment times of every track by start time.
+    pub fn sort_segment_times(&mut self) {
+        self.track_timings.iter_mut().for_each(TrackTiming::sort_segment_times);
+    }
+
+    /// Round every start time and track duration to millisecond precision.
+    pub fn normalize_ms(&mut self) {
+        self.track_timings.iter_mut().for_each(TrackTiming::normalize_ms);
+    }
+}
+
+impl SegmentTime {
+    /// The time's source, counting an unrecorded source as tapped.
+    pub fn trust(&self) -> TimingSource {
+        self.source.unwrap_or(TimingSource::Tapped)
+    }
+}
+
+impl TrackTiming {
+    /// Stable identifier for the track at `index`: `d1-t2` from disc and
+    /// track number, `t2` without a disc, or `track-3` by position.
+    pub fn track_id(&self, index: usize) -> String {
+        match (self.disc_number, self.track_number) {
+            (Some(d), Some(t)) => format!("d{d}-t{t}"),
+            (None, Some(t)) => format!("t{t}"),
+            _ => format!("track-{}", index + 1),
+        }
+    }
+
+    /// Shift every start (and explicit end) time by `delta` seconds (negative moves earlier).
+    ///
+    /// Results are rounded to milliseconds. Starts are not clamped: check
+    /// `shifts_below_zero` first.
+    pub fn shift(&mut self, delta: f64) {
+        for st in &mut self.segment_times {
+            st.start = round_to_ms(st.start + delta);
+            st.end = st.end.map(|e| round_to_ms(e + delta));
+        }
+    }
+
+    /// The first segment time that a shift by `delta` would move before
+    /// zero, start or end, if any.
+    pub fn shifts_below_zero(&self, delta: f64) -> Option<&SegmentTime> {
+        self.segment_times.iter()
+            .find(|st| round_to_ms(st.start + delta) < 0.0 || st.end.is_some_and(|e| round_to_ms(e + delta) < 0.0))
+    }
+
+    /// Multiply every start time by `factor`, e.g. to retime a track onto
+    /// a recording with a different tempo. Results are rounded to milliseconds.
+    pub fn scale(&mut self, factor: f64) {
+        for st in &mut self.segment_times {
+            st.start = round_to_ms(st.start * factor);
+            st.end = st.end.map(|e| round_to_ms(e * factor));
+        }
+    }
+
+    /// Whether the track's times are a scaffold's stand-ins rather than
+    /// times: `timing init` lists every segment at 0.0 with no source. A
+    /// single 0.0 with no source among other times is a tapped start, as
+    /// overlays written before `source` existed have them.
+    pub fn is_scaffold(&self) -> bool {
+        !self.segment_times.is_empty()
+            && self.segment_times.iter().all(|t| t.start == 0.0 && t.source.is_none())
+    }
+
+    /// Stable-sort segment times by start time.
+    pub fn sort_segment_times(&mut self) {
+        self.segment_times.sort_by(|a, b| a.start.total_cmp(&b.start));
+    }
+
+    /// Round every start time and the duration to millisecond precision.
+    pub fn normalize_ms(&mut self) {
+        for st in &mut self.segment_times {
+            st.start = round_to_ms(st.start);
+            st.end = st.end.map(round_to_ms);
+        }
+        self.duration_seconds = self.duration_seconds.map(round_to_ms);
+    }
+}
+
+/// Round a time in seconds to millisecond precision.
+///
+/// Timing files store times with at most three decimals; rounding after
+/// arithmetic keeps values like `12.499999999` out of them.
+pub fn round_to_ms(seconds: f64) -> f64 {
+    (seconds * 1000.0).round() / 1000.0
+}
+
+/// Format seconds as `m:ss.s`, the way times are shown to people (and
+/// typed back to `timing edit`).
+pub fn format_time(seconds: f64) -> String {
+    let tenths = (seconds.max(0.0) * 10.0).round() as u64;
+    format!("{}:{:02}.{}", tenths / 600, (tenths / 10) % 60, tenths % 10)
 }
 
 #[cfg(test)]
@@ -138,22 +370,27 @@ mod tests {
                 track_number: Some(2),
                 duration_seconds: Some(195.0),
                 number_ids: vec!["no-1-duettino".to_string()],
-                start_segment_id: None,
                 segment_times: vec![
                     SegmentTime {
                         segment_id: "no-1-001".to_string(),
                         start: 0.0,
+                        end: None,
+                        source: None,
                     },
                     SegmentTime {
                         segment_id: "no-1-002".to_string(),
                         start: 12.5,
+                        end: None,
+                        source: None,
                     },
                 ],
+                ..Default::default()
             }],
             omitted_numbers: vec![OmittedNumber {
                 number_id: "no-24-aria".to_string(),
                 reason: Some("Traditional cut".to_string()),
             }],
+            ..Default::default()
         }
     }
 
@@ -172,4 +409,59 @@ mod tests {
         assert_eq!(parsed.recording.conductor.as_deref(), Some("Carlo Maria Giulini"));
         assert_eq!(parsed.track_timings[0].segment_times.len(), 2);
     }
+
+    #[test]
+    fn test_stamp_contributor() {
+        let mut overlay = sample_overlay();
+        overlay.stamp_contributor("Test User", "timing", "2026-03-01");
+        overlay.stamp_contributor("Test User", "estimate", "2026-03-01");
+        overlay.stamp_contributor("Test User", "estimate", "2026-03-02");
+        let entries: Vec<(&str, &str)> = overlay.contributors.iter()
+            .map(|c| (c.role.as_deref().unwrap(), c.date.as_deref().unwrap()))
+            .collect();
+        assert_eq!(entries, vec![("timing", "2026-03-01"), ("estimate", "2026-03-02")]);
+    }
+
+    #[test]
+    fn test_shift_and_scale() {
+        let mut overlay = sample_overlay();
+        assert!(overlay.shift_track(0, 0.1));
+        assert_eq!(overlay.track_timings[0].segment_times[1].start, 12.6);
+        assert!(overlay.scale_track(0, 2.0));
+        assert_eq!(overlay.track_timings[0].segment_times[0].start, 0.2);
+        assert_eq!(overlay.track_timings[0].segment_times[1].start, 25.2);
+        assert!(!overlay.shift_track(5, 1.0));
+    }
+
+    #[test]
+    fn test_shifts_below_zero() {
+        let overlay = sample_overlay();
+        let track = &overlay.track_timings[0];
+        assert_eq!(track.shifts_below_zero(-3.2).map(|st| st.segment_id.as_str()), Some("no-1-001"));
+        assert!(track.shifts_below_zero(-0.0004).is_none());
+        assert!(track.shifts_below_zero(5.0).is_none());
+    }
+
+    #[test]
+    fn test_sort_and_normalize() {
+        let mut overlay = sample_overlay();
+        let track = &mut overlay.track_timings[0];
+        track.segment_times[0].start = 20.123456;
+        track.duration_seconds = Some(195.0004);
+        overlay.sort_segment_times();
+        overlay.normalize_ms();
+        let track = &overlay.track_timings[0];
+        assert_eq!(track.segment_times[0].segment_id, "no-1-002");
+        assert_eq!(track.segment_times[1].start, 20.123);
+        assert_eq!(track.duration_seconds, Some(195.0));
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0.0), "0:00.0");
+        assert_eq!(format_time(83.46), "1:23.5");
+        assert_eq!(format_time(59.96), "1:00.0");
+        assert_eq!(format_time(3725.0), "62:05.0");
+        assert_eq!(format_time(-1.0), "0:00.0");
+    }
 }
@@ -0,0 +1,255 @@
+// Hand corrections to one track's segment times, for `timing edit`.
+//
+// Fixing a handful of bad times by editing the overlay's JSON means
+// finding the segment among hundreds and retyping seconds. The editor
+// works on one track's rows in order: set a row's start, nudge it, or
+// mark it verified, and undo any of these. An edit that would move a
+// start before the previous row's or after the next row's is refused,
+// so the track stays in order however the times are changed; moving a
+// segment past its neighbours is a job for the base libretto, not its
+// times.
+
+use thiserror::Error;
+
+use crate::import::parse_timestamp;
+use crate::timing_overlay::{round_to_ms, SegmentTime, TimingSource, TrackTiming};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EditError {
+    #[error("no row {0}")]
+    NoRow(usize),
+
+    #[error("start must not be negative")]
+    Negative,
+
+    #[error("{start}s is before {previous}'s start at {previous_start}s")]
+    BeforePrevious { start: f64, previous: String, previous_start: f64 },
+
+    #[error("{start}s is after {next}'s start at {next_start}s")]
+    AfterNext { start: f64, next: String, next_start: f64 },
+
+    #[error("{start}s is not before the segment's end at {end}s")]
+    PastEnd { start: f64, end: f64 },
+
+    #[error("{start}s is beyond the end of the track at {duration}s")]
+    BeyondTrack { start: f64, duration: f64 },
+
+    #[error("nothing to undo")]
+    NothingToUndo,
+}
+
+/// Edits to one track's segment times, in row order (row 1 is the first
+/// segment time).
+pub struct TrackEditor<'a> {
+    track: &'a mut TrackTiming,
+    /// Rows changed, and their times before the change, latest last.
+    history: Vec<(usize, SegmentTime)>,
+    /// Length of `history` when the rows were last saved, or `None` once
+    /// undoing past that point and editing again has lost it.
+    saved: Option<usize>,
+}
+
+impl<'a> TrackEditor<'a> {
+    pub fn new(track: &'a mut TrackTiming) -> Self {
+        TrackEditor { track, history: Vec::new(), saved: Some(0) }
+    }
+
+    pub fn rows(&self) -> &[SegmentTime] {
+        &self.track.segment_times
+    }
+
+    /// Whether the rows differ from when they were last saved (or from
+    /// the start, before any save), after undoing.
+    pub fn is_changed(&self) -> bool {
+        self.saved != Some(self.history.len())
+    }
+
+    /// Record that the rows as they are now have been saved.
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.history.len());
+    }
+
+    /// Set row `row`'s start. A segment with an end keeps it, so the
+    /// start must stay before it. The time becomes tapped.
+    pub fn set_start(&mut self, row: usize, start: f64) -> Result<(), EditError> {
+        self.change(row, round_to_ms(start), |time, start| {
+            time.start = start;
+        })
+    }
+
+    /// Move row `row`, and its end if it has one, by `delta` seconds. The
+    /// time becomes tapped.
+    pub fn nudge(&mut self, row: usize, delta: f64) -> Result<(), EditError> {
+        let index = self.index(row)?;
+        let start = round_to_ms(self.track.segment_times[index].start + delta);
+        self.change(row, start, |time, start| {
+            time.end = time.end.map(|end| round_to_ms(end + delta));
+            time.start = start;
+        })
+    }
+
+    /// Mark row `row`'s time as checked against the recording.
+    pub fn verify(&mut self, row: usize) -> Result<(), EditError> {
+        let index = self.index(row)?;
+        let before = self.track.segment_times[index].clone();
+        self.track.segment_times[index].source = Some(TimingSource::Verified);
+        self.record(index, before);
+        Ok(())
+    }
+
+    /// Undo the latest edit, returning its row.
+    pub fn undo(&mut self) -> Result<usize, EditError> {
+        let (index, before) = self.history.pop().ok_or(EditError::NothingToUndo)?;
+        self.track.segment_times[index] = before;
+        Ok(index + 1)
+    }
+
+    fn index(&self, row: usize) -> Result<usize, EditError> {
+        (1..=self.track.segment_times.len())
+            .contains(&row)
+            .then(|| row - 1)
+            .ok_or(EditError::NoRow(row))
+    }
+
+    fn change(&mut self, row: usize, start: f64, apply: impl FnOnce(&mut SegmentTime, f64)) -> Result<(), EditError> {
+        let index = self.index(row)?;
+        let times = &self.track.segment_times;
+        if start < 0.0 {
+            return Err(EditError::Negative);
+        }
+        if let Some(previous) = index.checked_sub(1).map(|i| &times[i]).filter(|p| start < p.start) {
+            return Err(EditError::BeforePrevious {
+                start,
+                previous: previous.segment_id.clone(),
+                previous_start: previous.start,
+            });
+        }
+        if let Some(next) = times.get(index + 1).filter(|n| start > n.start) {
+            return Err(EditError::AfterNext { start, next: next.segment_id.clone(), next_start: next.start });
+        }
+        if let Some(duration) = self.track.duration_seconds.filter(|&d| start >= d) {
+            return Err(EditError::BeyondTrack { start, duration });
+        }
+
+        let before = times[index].clone();
+        let mut after = before.clone();
+        apply(&mut after, start);
+        if let Some(end) = after.end.filter(|&end| start >= end) {
+            return Err(EditError::PastEnd { start, end });
+        }
+        after.source = Some(TimingSource::Tapped);
+        self.track.segment_times[index] = after;
+        self.record(index, before);
+        Ok(())
+    }
+
+    fn record(&mut self, index: usize, before: SegmentTime) {
+        // An edit made after undoing past the save replaces what was saved
+        if self.saved.is_some_and(|saved| self.history.len() < saved) {
+            self.saved = None;
+        }
+        self.history.push((index, before));
+    }
+}
+
+/// Parse a start time typed by hand: seconds (`83.5`) or `m:ss.s`
+/// (`1:23.5`).
+pub fn parse_start(text: &str) -> Option<f64> {
+    let text = text.trim();
+    if text.contains(':') {
+        parse_timestamp(text)
+    } else {
+        text.parse().ok().filter(|s: &f64| s.is_finite())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track() -> TrackTiming {
+        let time = |id: &str, start: f64| SegmentTime {
+            segment_id: id.to_string(),
+            start,
+            end: None,
+            source: Some(TimingSource::Estimated),
+        };
+        TrackTiming {
+            track_title: "Non più andrai".to_string(),
+            disc_number: Some(1),
+            track_number: Some(7),
+            duration_seconds: Some(200.0),
+            number_ids: vec!["no-9-aria".to_string()],
+            start_segment_id: None,
+            resolved_by: None,
+            section_marks: vec![],
+            instrumental: false,
+            skip_segment_ids: vec![],
+            padding: None,
+            segment_times: vec![time("no-9-aria-001", 3.0), time("no-9-aria-002", 10.0), time("no-9-aria-003", 20.0)],
+        }
+    }
+
+    #[test]
+    fn test_edit_and_undo() {
+        let mut track = track();
+        let mut editor = TrackEditor::new(&mut track);
+        editor.nudge(2, 0.5).unwrap();
+        editor.set_start(3, 19.25).unwrap();
+        editor.verify(1).unwrap();
+        assert_eq!(editor.rows()[1].start, 10.5);
+        assert_eq!(editor.rows()[1].source, Some(TimingSource::Tapped));
+        assert_eq!(editor.rows()[0].source, Some(TimingSource::Verified));
+
+        assert_eq!(editor.undo(), Ok(1));
+        assert_eq!(editor.undo(), Ok(3));
+        assert_eq!(editor.rows()[2].start, 20.0);
+        assert_eq!(editor.rows()[2].source, Some(TimingSource::Estimated));
+        assert!(editor.is_changed());
+        editor.undo().unwrap();
+        assert!(!editor.is_changed());
+        assert_eq!(editor.undo(), Err(EditError::NothingToUndo));
+    }
+
+    #[test]
+    fn test_changed_since_save() {
+        let mut track = track();
+        let mut editor = TrackEditor::new(&mut track);
+        editor.nudge(1, 0.5).unwrap();
+        editor.mark_saved();
+        assert!(!editor.is_changed());
+        editor.verify(2).unwrap();
+        assert!(editor.is_changed());
+        editor.undo().unwrap();
+        assert!(!editor.is_changed());
+        // Undoing what was saved is a change, and stays one after a new edit
+        editor.undo().unwrap();
+        assert!(editor.is_changed());
+        editor.verify(3).unwrap();
+        assert!(editor.is_changed());
+        editor.mark_saved();
+        assert!(!editor.is_changed());
+    }
+
+    #[test]
+    fn test_edits_keep_order() {
+        let mut track = track();
+        let mut editor = TrackEditor::new(&mut track);
+        assert!(matches!(editor.set_start(2, 2.0), Err(EditError::BeforePrevious { .. })));
+        assert!(matches!(editor.nudge(2, 10.5), Err(EditError::AfterNext { .. })));
+        assert!(matches!(editor.set_start(3, 250.0), Err(EditError::BeyondTrack { .. })));
+        assert_eq!(editor.nudge(1, -3.5), Err(EditError::Negative));
+        assert_eq!(editor.verify(4), Err(EditError::NoRow(4)));
+        assert!(!editor.is_changed());
+        // Meeting a neighbour's start is allowed, as ensembles do.
+        editor.set_start(2, 20.0).unwrap();
+    }
+
+    #[test]
+    fn test_parse_start() {
+        assert_eq!(parse_start("83.5"), Some(83.5));
+        assert_eq!(parse_start(" 1:23.5 "), Some(83.5));
+        assert_eq!(parse_start("1:75"), None);
+        assert_eq!(parse_start("soon"), None);
+    }
+}
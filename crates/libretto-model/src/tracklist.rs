@@ -0,0 +1,190 @@
+// Scaffolding timing overlays from pasted track listings.
+//
+// Without a cue sheet, the quickest way to a recording's track structure
+// is to copy the listing from a streaming service or the booklet into a
+// text file, one track per line:
+//
+//   CD 1
+//   1 Sinfonia 4:12
+//   2. Cinque... dieci... venti... 2:58
+//   1-03 Recitativo "Bravo, signor padrone" 1:05
+//
+// A line starts with its track number, or disc and track number, and ends
+// with its duration. Lines without a number continue the numbering, and a
+// `CD 2` or `Disc 2` line starts the next disc. A time in parentheses is
+// not taken as the duration: it stays in the title as a timing cue.
+
+use thiserror::Error;
+
+use crate::import::parse_timestamp;
+use crate::timing_overlay::{round_to_ms, TimingOverlay, TrackTiming};
+
+#[derive(Debug, Error)]
+pub enum TrackListError {
+    #[error("line {line}: {message}")]
+    Parse { line: usize, message: String },
+
+    #[error("track listing has no tracks")]
+    NoTracks,
+}
+
+/// One line of a track listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedTrack {
+    pub disc_number: u32,
+    pub track_number: u32,
+    pub title: String,
+    pub duration: Option<f64>,
+}
+
+/// Parse a track listing. Blank lines and lines starting with `#` are
+/// skipped; tracks before any disc number are on disc 1.
+pub fn parse_track_listing(contents: &str) -> Result<Vec<ListedTrack>, TrackListError> {
+    let mut tracks: Vec<ListedTrack> = Vec::new();
+    let mut disc = 1;
+    let mut last_number = 0;
+
+    for (i, line) in contents.lines().enumerate() {
+        let err = |message: String| TrackListError::Parse { line: i + 1, message };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(number) = disc_heading(line) {
+            disc = number;
+            last_number = 0;
+            continue;
+        }
+
+        let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let (disc_number, track_number, rest) = match leading_number(first) {
+            Some((Some(d), t)) => (d, t, rest),
+            Some((None, t)) => (disc, t, rest),
+            None => (disc, last_number + 1, line),
+        };
+        let rest = rest.trim();
+        let (before, last) = rest.rsplit_once(char::is_whitespace).unwrap_or(("", rest));
+        let (title, duration) = match parse_duration(last) {
+            Some(d) => (before, Some(d)),
+            None => (rest, None),
+        };
+        let title = title.trim().trim_matches(|c: char| "-–—".contains(c)).trim();
+        if title.is_empty() {
+            return Err(err(format!("no title for track {disc_number}-{track_number:02}")));
+        }
+        if tracks.iter().any(|t| (t.disc_number, t.track_number) == (disc_number, track_number)) {
+            return Err(err(format!("track {disc_number}-{track_number:02} listed twice")));
+        }
+
+        disc = disc_number;
+        last_number = track_number;
+        tracks.push(ListedTrack {
+            disc_number,
+            track_number,
+            title: title.to_string(),
+            duration,
+        });
+    }
+
+    if tracks.is_empty() {
+        return Err(TrackListError::NoTracks);
+    }
+    Ok(tracks)
+}
+
+/// The disc number of a heading line such as `CD 2`, `Disc 2:` or `DISK 2`.
+fn disc_heading(line: &str) -> Option<u32> {
+    let lower = line.to_lowercase();
+    let rest = ["disc", "disk", "cd"].iter().find_map(|word| lower.strip_prefix(word))?;
+    rest.trim().trim_end_matches(':').trim().parse().ok()
+}
+
+/// A leading `5`, `5.`, `05)` (track) or `1-05` (disc and track).
+fn leading_number(word: &str) -> Option<(Option<u32>, u32)> {
+    let word = word.trim_end_matches(['.', ')']);
+    match word.split_once('-') {
+        Some((disc, track)) => Some((Some(disc.parse().ok()?), track.parse().ok()?)),
+        None => Some((None, word.parse().ok()?)),
+    }
+}
+
+/// Parse a track duration: `m:ss` or `h:mm:ss`.
+fn parse_duration(word: &str) -> Option<f64> {
+    let (hours, rest) = match word.matches(':').count() {
+        1 => (0.0, word),
+        2 => {
+            let (hours, rest) = word.split_once(':')?;
+            (hours.parse::<u32>().ok()?.into(), rest)
+        }
+        _ => return None,
+    };
+    let seconds = parse_timestamp(rest)?;
+    (hours == 0.0 || seconds < 3600.0).then_some(hours * 3600.0 + seconds)
+}
+
+/// Build a timing overlay scaffold with one track per listed track.
+/// Tracks have no `number_ids`.
+pub fn scaffold_from_listing(tracks: &[ListedTrack], base_path: &str) -> TimingOverlay {
+    let mut overlay = TimingOverlay::new(base_path);
+    overlay.track_timings = tracks.iter()
+        .map(|track| TrackTiming {
+            track_title: track.title.clone(),
+            disc_number: Some(track.disc_number),
+            track_number: Some(track.track_number),
+            duration_seconds: track.duration.map(round_to_ms),
+            ..Default::default()
+        })
+        .collect();
+    overlay
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_track_listing() {
+        let tracks = parse_track_listing(r#"
+# Giulini, EMI 1959
+CD 1
+1 Sinfonia 4:12
+2. Cinque... dieci... venti... - 2:58
+Recitativo "Bravo, signor padrone" (0:41)	1:05
+1-04 Finale   1:02:30
+Disc 2:
+Porgi, amor
+"#).unwrap();
+
+        let summary: Vec<(u32, u32, &str, Option<f64>)> = tracks.iter()
+            .map(|t| (t.disc_number, t.track_number, t.title.as_str(), t.duration))
+            .collect();
+        assert_eq!(summary, vec![
+            (1, 1, "Sinfonia", Some(252.0)),
+            (1, 2, "Cinque... dieci... venti...", Some(178.0)),
+            // The parenthesized time is a cue, left in the title
+            (1, 3, r#"Recitativo "Bravo, signor padrone" (0:41)"#, Some(65.0)),
+            (1, 4, "Finale", Some(3750.0)),
+            (2, 1, "Porgi, amor", None),
+        ]);
+    }
+
+    #[test]
+    fn test_parse_track_listing_errors() {
+        assert!(matches!(
+            parse_track_listing("1 Sinfonia 4:12\n1 Sinfonia 4:12"),
+            Err(TrackListError::Parse { line: 2, .. })
+        ));
+        assert!(matches!(parse_track_listing("3. 4:12"), Err(TrackListError::Parse { line: 1, .. })));
+        assert!(matches!(parse_track_listing("# nothing\n\n"), Err(TrackListError::NoTracks)));
+    }
+
+    #[test]
+    fn test_scaffold_from_listing() {
+        let tracks = parse_track_listing("1-01 Sinfonia 4:12\n1-02 Cinque 2:58").unwrap();
+        let overlay = scaffold_from_listing(&tracks, "base.libretto.json");
+        assert_eq!(overlay.track_timings.len(), 2);
+        assert_eq!(overlay.track_timings[1].duration_seconds, Some(178.0));
+        assert_eq!(overlay.track_timings[1].disc_number, Some(1));
+        assert!(overlay.track_timings[0].number_ids.is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+// JavaScript bindings for reading interchange files in the browser.
+//
+// A web player that already has the audio can fetch a merged libretto and
+// follow along without a server: build this crate as a cdylib with the
+// `wasm` feature for `wasm32-unknown-unknown` (`cargo rustc --crate-type
+// cdylib`, so native builds stay an rlib), run `wasm-bindgen` over it, and
+// load the document with `new Libretto(json)`. Segment lookup is the same code the CLI and server
+// use, so every client agrees on what is playing when.
+//
+// Values handed to JavaScript are plain objects shaped like the JSON: an
+// absent field is `null`, not `undefined`.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::interchange::{InterchangeLibretto, InterchangeTrack};
+use crate::io;
+
+/// A loaded interchange document.
+#[wasm_bindgen]
+pub struct Libretto {
+    doc: InterchangeLibretto,
+}
+
+/// A track as listed by [`Libretto::tracks`]: its metadata without the
+/// segments.
+#[derive(Serialize)]
+struct TrackSummary<'a> {
+    track_id: &'a str,
+    title: &'a str,
+    disc_number: Option<u32>,
+    track_number: Option<u32>,
+    duration_seconds: Option<f64>,
+    act: Option<&'a str>,
+    /// How many segments the track has.
+    segments: usize,
+}
+
+#[wasm_bindgen]
+impl Libretto {
+    /// Parse an interchange document from its JSON text. A malformed
+    /// document throws, naming the JSON path of the field at fault.
+    #[wasm_bindgen(constructor)]
+    pub fn new(json: &str) -> Result<Libretto, JsError> {
+        let doc = io::from_json_str(json, "interchange document")?;
+        Ok(Libretto { doc })
+    }
+
+    /// The opera's title, composer, and languages.
+    #[wasm_bindgen(getter)]
+    pub fn opera(&self) -> Result<JsValue, JsError> {
+        to_js(&self.doc.opera)
+    }
+
+    /// Every track, in order, without its segments.
+    pub fn tracks(&self) -> Result<JsValue, JsError> {
+        let summaries: Vec<TrackSummary> = self.doc.tracks.iter()
+            .map(|t| TrackSummary {
+                track_id: &t.track_id,
+                title: &t.title,
+                disc_number: t.disc_number,
+                track_number: t.track_number,
+                duration_seconds: t.duration_seconds,
+                act: t.act.as_deref(),
+                segments: t.segments.len(),
+            })
+            .collect();
+        to_js(&summaries)
+    }
+
+    /// The segment playing `time` seconds into track `trackId`, or `null`
+    /// before its first segment.
+    #[wasm_bindgen(js_name = segmentAt)]
+    pub fn segment_at(&self, track_id: &str, time: f64) -> Result<JsValue, JsError> {
+        to_js(&self.track(track_id)?.segment_at(time))
+    }
+
+    /// The first segment of track `trackId` starting after `time`, or
+    /// `null` after its last.
+    #[wasm_bindgen(js_name = nextSegment)]
+    pub fn next_segment(&self, track_id: &str, time: f64) -> Result<JsValue, JsError> {
+        to_js(&self.track(track_id)?.next_segment(time))
+    }
+}
+
+impl Libretto {
+    fn track(&self, track_id: &str) -> Result<&InterchangeTrack, JsError> {
+        self.doc.tracks.iter()
+            .find(|t| t.track_id == track_id)
+            .ok_or_else(|| JsError::new(&format!("no track '{track_id}' in the document")))
+    }
+}
+
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    value
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsError::new(&e.to_string()))
+}
@@ -0,0 +1,53 @@
+// A static web player for a timed libretto.
+//
+// The bundle is a folder that opens straight from disk: `index.html`,
+// `player.js`, the interchange document as `libretto.json`, and the same
+// document as `libretto.js`. Browsers refuse to fetch local files from a
+// page opened with `file://`, so the page loads the script copy; the JSON
+// copy is there for other tools and for serving the folder over HTTP.
+// The listener picks the recording's audio files in the page, and the
+// segment being sung is highlighted and scrolled into view as it plays.
+
+use crate::interchange::InterchangeLibretto;
+
+const INDEX_HTML: &str = include_str!("webapp/index.html");
+const PLAYER_JS: &str = include_str!("webapp/player.js");
+
+/// The files of a web player bundle for `doc`, as (file name, contents).
+pub fn webapp_files(doc: &InterchangeLibretto) -> Result<Vec<(String, String)>, serde_json::Error> {
+    let json = serde_json::to_string_pretty(doc)?;
+    Ok(vec![
+        ("index.html".to_string(), INDEX_HTML.to_string()),
+        ("player.js".to_string(), PLAYER_JS.to_string()),
+        ("libretto.js".to_string(), format!("window.LIBRETTO = {};\n", serde_json::to_string(doc)?)),
+        ("libretto.json".to_string(), json),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interchange::*;
+
+    #[test]
+    fn test_webapp_files() {
+        let doc = InterchangeLibretto {
+            version: "1.0".to_string(),
+            opera: InterchangeOpera {
+                title: "Tosca".to_string(),
+                composer: "Giacomo Puccini".to_string(),
+                language: "it".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let files = webapp_files(&doc).unwrap();
+        let names: Vec<&str> = files.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["index.html", "player.js", "libretto.js", "libretto.json"]);
+        assert!(files[0].1.contains("<script src=\"libretto.js\"></script>"));
+
+        let script = files[2].1.strip_prefix("window.LIBRETTO = ").unwrap().trim_end().strip_suffix(';').unwrap();
+        let parsed: InterchangeLibretto = serde_json::from_str(script).unwrap();
+        assert_eq!(parsed.opera.title, "Tosca");
+    }
+}
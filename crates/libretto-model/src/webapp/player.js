@@ -0,0 +1,93 @@
+// Scrolling libretto display for a timed libretto (see libretto.js).
+//
+// Audio comes from files the listener picks, so the page needs no server:
+// files are sorted by name and assigned to tracks in order, and the
+// segment whose start has passed is highlighted and scrolled into view.
+(function () {
+  "use strict";
+
+  const doc = window.LIBRETTO;
+  const audio = document.getElementById("audio");
+  const trackSelect = document.getElementById("track");
+  const files = document.getElementById("files");
+  const container = document.getElementById("segments");
+  let trackIndex = 0;
+  let urls = [];
+  let rows = [];
+  let active = -1;
+
+  document.getElementById("title").textContent = doc.opera.title + " — " + doc.opera.composer;
+  document.title = doc.opera.title;
+
+  doc.tracks.forEach(function (track, i) {
+    const option = document.createElement("option");
+    option.value = i;
+    option.textContent = track.track_id + " · " + track.title;
+    trackSelect.appendChild(option);
+  });
+
+  function element(className, text) {
+    const el = document.createElement("div");
+    el.className = className;
+    el.textContent = text;
+    return el;
+  }
+
+  function showTrack(i, autoplay) {
+    trackIndex = i;
+    trackSelect.value = i;
+    container.replaceChildren();
+    rows = doc.tracks[i].segments.map(function (seg) {
+      const row = document.createElement("div");
+      row.className = "segment";
+      if (seg.character && seg.text) row.appendChild(element("character", seg.character));
+      if (seg.text) row.appendChild(element("text", seg.text));
+      if (seg.text && seg.pronunciation) row.appendChild(element("pronunciation", seg.pronunciation));
+      if (seg.text && seg.translation) row.appendChild(element("translation", seg.translation));
+      if (!seg.text && seg.direction) row.appendChild(element("direction", seg.direction));
+      row.addEventListener("click", function () {
+        audio.currentTime = seg.start;
+        if (audio.paused && audio.src) audio.play();
+      });
+      container.appendChild(row);
+      return row;
+    });
+    active = -1;
+    if (urls[i]) {
+      audio.src = urls[i];
+      if (autoplay) audio.play();
+    } else {
+      audio.removeAttribute("src");
+    }
+    window.scrollTo(0, 0);
+  }
+
+  function update() {
+    const segments = doc.tracks[trackIndex].segments;
+    let current = -1;
+    for (let i = 0; i < segments.length && segments[i].start <= audio.currentTime; i++) current = i;
+    if (current === active) return;
+    rows.forEach(function (row, i) {
+      row.classList.toggle("active", i === current);
+      row.classList.toggle("past", i < current);
+    });
+    active = current;
+    if (current >= 0) rows[current].scrollIntoView({ behavior: "smooth", block: "center" });
+  }
+
+  files.addEventListener("change", function () {
+    urls.forEach(function (url) { URL.revokeObjectURL(url); });
+    urls = Array.from(files.files)
+      .sort(function (a, b) { return a.name.localeCompare(b.name, undefined, { numeric: true }); })
+      .map(function (file) { return URL.createObjectURL(file); });
+    showTrack(trackIndex, false);
+  });
+  trackSelect.addEventListener("change", function () { showTrack(Number(trackSelect.value), true); });
+  audio.addEventListener("timeupdate", update);
+  audio.addEventListener("seeked", update);
+  audio.addEventListener("ended", function () {
+    if (trackIndex + 1 < doc.tracks.length && urls[trackIndex + 1]) showTrack(trackIndex + 1, true);
+  });
+
+  showTrack(0, false);
+})();
@@ -17,7 +17,7 @@ use crate::segments;
 /// translation, match them by number ID and sequence position,
 /// then copy translation text into the original segments.
 pub fn align_segments(
-    original: &mut Vec<Segment>,
+    original: &mut [Segment],
     translation: &[Segment],
 ) {
     // Build a lookup: (number_id_prefix, seq) → translation text
@@ -175,7 +175,7 @@ mod tests {
         assert_eq!(result.cast[0].character, "Figaro");
 
         // overture (empty, retained) + duettino
-        assert!(result.numbers.len() >= 1);
+        assert!(!result.numbers.is_empty());
 
         // Find the duettino segments
         let duettino_segs: Vec<_> = result.segments.iter()
@@ -112,6 +112,7 @@ fn parse_character_entry(text: &str) -> Option<CastMember> {
             short_name: Some(name),
             voice_type: Some(voice),
             description: None,
+            aliases: vec![],
         })
     } else {
         // No parenthetical — just a name (e.g., "CHORUS")
@@ -120,6 +121,7 @@ fn parse_character_entry(text: &str) -> Option<CastMember> {
             short_name: Some(text.to_string()),
             voice_type: None,
             description: None,
+            aliases: vec![],
         })
     }
 }
@@ -149,6 +151,7 @@ fn parse_text_entry(text: &str) -> Option<CastMember> {
             short_name: None,
             voice_type: Some(voice),
             description,
+            aliases: vec![],
         })
     } else {
         // No dash — could be "Due Donne" or "Coro di Contadini, ..."
@@ -165,6 +168,7 @@ fn parse_text_entry(text: &str) -> Option<CastMember> {
             short_name: None,
             voice_type: None,
             description,
+            aliases: vec![],
         })
     }
 }
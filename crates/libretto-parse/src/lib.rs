@@ -1,5 +1,5 @@
-use anyhow::{Context, Result};
-use std::fs;
+use anyhow::Result;
+use serde::Deserialize;
 use std::path::Path;
 
 use libretto_acquire::types::{AcquiredLibretto, AcquiredMonolingual};
@@ -10,23 +10,63 @@ pub mod structure;
 pub mod segments;
 pub mod align;
 
-/// Parse acquired libretto files into a structured base libretto JSON.
+/// Opera details to set on a parsed libretto in place of what the source
+/// gave: sources carry the title as their site spells it and never the
+/// composer, and a project knows better. Fields left out keep the parsed
+/// value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParseOverrides {
+    pub title: Option<String>,
+    pub composer: Option<String>,
+    pub librettist: Option<String>,
+    pub year: Option<u16>,
+    pub language: Option<String>,
+    pub translation_language: Option<String>,
+}
+
+impl ParseOverrides {
+    /// Overwrite the fields of `metadata` this sets.
+    pub fn apply(&self, metadata: &mut OperaMetadata) {
+        let set = |field: &mut String, value: &Option<String>| {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        };
+        set(&mut metadata.title, &self.title);
+        set(&mut metadata.composer, &self.composer);
+        set(&mut metadata.language, &self.language);
+        if self.librettist.is_some() {
+            metadata.librettist = self.librettist.clone();
+        }
+        if self.year.is_some() {
+            metadata.year = self.year;
+        }
+        if self.translation_language.is_some() {
+            metadata.translation_language = self.translation_language.clone();
+        }
+    }
+}
+
+/// Parse acquired libretto files into a structured base libretto.
 ///
-/// Reads structured JSON from the input directory (bilingual.json or {lang}.json),
-/// runs the parse pipeline, and writes a `BaseLibretto` JSON file.
+/// Reads structured JSON from the input directory (bilingual.json or {lang}.json)
+/// and runs the parse pipeline.
 ///
 /// Supported input configurations:
 /// - `bilingual.json` — bilingual acquisition (produces aligned original + translation)
 /// - `italian.json` + `english.json` — two monolingual files (aligned by structure)
 /// - `italian.json` or `english.json` — single language (no translation)
-pub fn parse(input_dir: &str, output_file: &str) -> Result<()> {
+///
+/// `overrides` then replaces the opera details the source gave.
+pub fn parse(input_dir: &str, overrides: &ParseOverrides) -> Result<BaseLibretto> {
     let dir = Path::new(input_dir);
 
     let bilingual_path = dir.join("bilingual.json");
     let italian_json = dir.join("italian.json");
     let english_json = dir.join("english.json");
 
-    let libretto = if bilingual_path.exists() {
+    let mut libretto = if bilingual_path.exists() {
         tracing::info!("Found bilingual.json — using bilingual mode");
         parse_bilingual(&bilingual_path)?
     } else if italian_json.exists() && english_json.exists() {
@@ -44,24 +84,13 @@ pub fn parse(input_dir: &str, output_file: &str) -> Result<()> {
              Expected bilingual.json, italian.json, or english.json."
         );
     };
-
-    let json = serde_json::to_string_pretty(&libretto)?;
-    fs::write(output_file, &json)?;
-    tracing::info!(
-        path = %output_file,
-        numbers = libretto.numbers.len(),
-        segments = libretto.segment_ids().len(),
-        "Wrote base libretto JSON"
-    );
-
-    Ok(())
+    overrides.apply(&mut libretto.opera);
+    Ok(libretto)
 }
 
 /// Parse from a bilingual.json file.
 fn parse_bilingual(path: &Path) -> Result<BaseLibretto> {
-    let text = fs::read_to_string(path).context("Failed to read bilingual.json")?;
-    let acquired: AcquiredLibretto = serde_json::from_str(&text)
-        .context("Failed to parse bilingual.json")?;
+    let acquired: AcquiredLibretto = libretto_model::io::load_json(path)?;
 
     let (lang1_elements, lang2_elements) = align::parse_bilingual(&acquired);
 
@@ -113,43 +142,41 @@ fn parse_bilingual(path: &Path) -> Result<BaseLibretto> {
 
 /// Parse from two separate monolingual JSON files.
 fn parse_dual_monolingual(italian_path: &Path, english_path: &Path) -> Result<BaseLibretto> {
-    let it_text = fs::read_to_string(italian_path).context("Failed to read italian.json")?;
-    let it_acquired: AcquiredMonolingual = serde_json::from_str(&it_text)
-        .context("Failed to parse italian.json")?;
-
-    let en_text = fs::read_to_string(english_path).context("Failed to read english.json")?;
-    let en_acquired: AcquiredMonolingual = serde_json::from_str(&en_text)
-        .context("Failed to parse english.json")?;
+    let it_acquired: AcquiredMonolingual = libretto_model::io::load_json(italian_path)?;
+    let en_acquired: AcquiredMonolingual = libretto_model::io::load_json(english_path)?;
+    parse_monolingual_pair(&it_acquired, &en_acquired)
+}
 
-    let it_result = align::pipeline(&it_acquired.elements);
-    let en_result = align::pipeline(&en_acquired.elements);
+/// Parse an acquired original text, aligning an acquired translation into
+/// it by structure, as `parse` does for `italian.json` + `english.json`.
+pub fn parse_monolingual_pair(original: &AcquiredMonolingual, translation: &AcquiredMonolingual) -> Result<BaseLibretto> {
+    let orig_result = align::pipeline(&original.elements);
+    let trans_result = align::pipeline(&translation.elements);
 
     tracing::info!(
-        it_segments = it_result.segments.len(),
-        en_segments = en_result.segments.len(),
+        orig_segments = orig_result.segments.len(),
+        trans_segments = trans_result.segments.len(),
         "Parsed both languages"
     );
 
-    let mut segments = it_result.segments;
-    align::align_segments(&mut segments, &en_result.segments);
+    let mut segments = orig_result.segments;
+    align::align_segments(&mut segments, &trans_result.segments);
 
     let metadata = OperaMetadata {
-        title: it_acquired.source.opera.clone(),
+        title: original.source.opera.clone(),
         composer: String::new(),
         librettist: None,
-        language: "it".to_string(),
-        translation_language: Some("en".to_string()),
+        language: original.lang.clone(),
+        translation_language: Some(translation.lang.clone()),
         year: None,
     };
 
-    assemble(metadata, &it_result.cast, &it_result.numbers, segments)
+    assemble(metadata, &orig_result.cast, &orig_result.numbers, segments)
 }
 
 /// Parse from a single monolingual JSON file.
 fn parse_single_monolingual(path: &Path) -> Result<BaseLibretto> {
-    let text = fs::read_to_string(path).context("Failed to read monolingual JSON")?;
-    let acquired: AcquiredMonolingual = serde_json::from_str(&text)
-        .context("Failed to parse monolingual JSON")?;
+    let acquired: AcquiredMonolingual = libretto_model::io::load_json(path)?;
 
     let result = align::pipeline(&acquired.elements);
 
@@ -197,3 +224,32 @@ fn assemble(
 
     Ok(libretto)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_overrides() {
+        let mut metadata = OperaMetadata {
+            title: "Le Nozze Di Figaro".to_string(),
+            composer: String::new(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: None,
+        };
+        let overrides = ParseOverrides {
+            title: Some("Le nozze di Figaro".to_string()),
+            composer: Some("W. A. Mozart".to_string()),
+            year: Some(1786),
+            ..Default::default()
+        };
+        overrides.apply(&mut metadata);
+        assert_eq!(metadata.title, "Le nozze di Figaro");
+        assert_eq!(metadata.composer, "W. A. Mozart");
+        assert_eq!(metadata.year, Some(1786));
+        assert_eq!(metadata.language, "it");
+        assert_eq!(metadata.translation_language.as_deref(), Some("en"));
+    }
+}
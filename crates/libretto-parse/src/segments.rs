@@ -15,6 +15,7 @@ use crate::structure::RawNumber;
 /// `Direction` elements become either:
 /// - A standalone direction segment (if no character context), or
 /// - Attached to the current segment's `direction` field.
+///
 /// `BlankLine` elements are ignored (they were stanza separators).
 pub fn split_segments(number: &RawNumber) -> Vec<Segment> {
     let mut segments: Vec<Segment> = Vec::new();
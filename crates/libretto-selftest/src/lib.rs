@@ -0,0 +1,188 @@
+// The whole pipeline, run against a miniature opera bundled with the crate.
+//
+// Each crate tests its own steps; this runs them one after another as the
+// CLI does, from the opera-arias.com pages `libretto acquire` would have
+// fetched (`fixtures/raw_it.html`, `raw_en.html`) through parse, the
+// scaffold overlay `timing init` makes of it, anchor resolution and
+// estimation for a recording (`fixtures/recording.timing.json`, that
+// scaffold with the recording's titles and durations filled in, quoting
+// the text each track starts with) to the merged interchange document,
+// and compares what each step produces with its golden file in
+// `fixtures/golden/`. A change in one crate that alters what a later step
+// makes of its output shows up here as a difference.
+//
+// When a difference is intended, `libretto selftest --write <dir>` writes
+// what each step produced under its golden file's name; review the
+// changes and copy them over `fixtures/golden/`.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use libretto_acquire::opera_arias;
+use libretto_acquire::types::{AcquiredMonolingual, SourceInfo};
+use libretto_model::estimate::{self, EstimateOptions};
+use libretto_model::merge::{self, ScaffoldOptions};
+use libretto_model::resolve::{self, ResolveOptions, SegmentIndex};
+use libretto_model::TimingOverlay;
+use libretto_parse::ParseOverrides;
+
+const OPERA: &str = "mozart/le-nozze-di-figaro";
+const RAW_IT: &str = include_str!("../fixtures/raw_it.html");
+const RAW_EN: &str = include_str!("../fixtures/raw_en.html");
+const RECORDING: &str = include_str!("../fixtures/recording.timing.json");
+
+/// The golden output of each step, by file name.
+const GOLDEN: &[(&str, &str)] = &[
+    ("italian.json", include_str!("../fixtures/golden/italian.json")),
+    ("english.json", include_str!("../fixtures/golden/english.json")),
+    ("base.libretto.json", include_str!("../fixtures/golden/base.libretto.json")),
+    ("scaffold.timing.json", include_str!("../fixtures/golden/scaffold.timing.json")),
+    ("resolved.timing.json", include_str!("../fixtures/golden/resolved.timing.json")),
+    ("estimated.timing.json", include_str!("../fixtures/golden/estimated.timing.json")),
+    ("timed.libretto.json", include_str!("../fixtures/golden/timed.libretto.json")),
+];
+
+/// What one step of the pipeline produced, against its golden file.
+#[derive(Debug, Clone, Serialize)]
+pub struct StageResult {
+    pub stage: &'static str,
+    /// Name of the golden file, and of the output under `--write`.
+    pub file: &'static str,
+    pub passed: bool,
+    /// Where the output first departs from the golden file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difference: Option<Difference>,
+    /// Warnings the step gave.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip)]
+    pub output: String,
+}
+
+/// The first line at which an output and its golden file differ.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Difference {
+    /// 1-based line number.
+    pub line: usize,
+    /// The golden file's line, or `None` past its end.
+    pub expected: Option<String>,
+    /// The output's line, or `None` past its end.
+    pub actual: Option<String>,
+}
+
+/// Run every step on the bundled fixtures, comparing each output with its
+/// golden file. Fails only when a step does; differences are in the
+/// results.
+pub fn run() -> Result<Vec<StageResult>> {
+    let mut results = Vec::new();
+    let mut stage = |stage, file, output: String, warnings: Vec<String>| {
+        let golden = GOLDEN.iter().find(|(name, _)| *name == file).map(|(_, golden)| *golden).unwrap_or_default();
+        let difference = first_difference(golden, &output);
+        results.push(StageResult { stage, file, passed: difference.is_none(), difference, warnings, output });
+    };
+
+    // Acquire, from the pages as fetched
+    let acquired = |html: &str, lang: &str| -> Result<AcquiredMonolingual> {
+        Ok(AcquiredMonolingual {
+            source: SourceInfo {
+                url: format!("fixtures/raw_{lang}.html"),
+                site: "opera-arias.com".to_string(),
+                fetched_at: "2026-01-01T00:00:00+00:00".to_string(),
+                opera: OPERA.to_string(),
+            },
+            lang: lang.to_string(),
+            elements: opera_arias::parse_page(html, lang).with_context(|| format!("raw_{lang}.html"))?,
+        })
+    };
+    let italian = acquired(RAW_IT, "it")?;
+    let english = acquired(RAW_EN, "en")?;
+    stage("acquire", "italian.json", to_json(&italian)?, vec![]);
+    stage("acquire", "english.json", to_json(&english)?, vec![]);
+
+    // Parse, with the details a project's libretto.toml would give
+    let mut base = libretto_parse::parse_monolingual_pair(&italian, &english)?;
+    let overrides = ParseOverrides {
+        title: Some("Le nozze di Figaro".to_string()),
+        composer: Some("Wolfgang Amadeus Mozart".to_string()),
+        librettist: Some("Lorenzo Da Ponte".to_string()),
+        year: Some(1786),
+        ..Default::default()
+    };
+    overrides.apply(&mut base.opera);
+    stage("parse", "base.libretto.json", to_json(&base)?, vec![]);
+
+    // Scaffold an overlay, as `timing init --group-recits` does; the
+    // recording's is this one filled in by hand
+    let scaffold = merge::scaffold_overlay_with(&base, "base.libretto.json", &ScaffoldOptions { group_recitatives: true });
+    stage("init", "scaffold.timing.json", to_json(&scaffold)?, vec![]);
+    let overlay: TimingOverlay = libretto_model::io::from_json_str(RECORDING, "recording.timing.json")?;
+    let layout = |overlay: &TimingOverlay| -> Vec<(Vec<String>, Vec<String>)> {
+        overlay.track_timings.iter()
+            .map(|t| (t.number_ids.clone(), t.segment_times.iter().map(|st| st.segment_id.clone()).collect()))
+            .collect()
+    };
+    anyhow::ensure!(
+        layout(&overlay) == layout(&scaffold),
+        "fixtures/recording.timing.json no longer fills in the scaffold; update its tracks from scaffold.timing.json"
+    );
+
+    // Resolve and estimate the recording's tracks against one index
+    let index = SegmentIndex::new(&base);
+    let resolved = resolve::resolve_anchors_indexed(&index, &overlay, &ResolveOptions::default());
+    stage("resolve", "resolved.timing.json", to_json(&resolved.overlay)?, resolved.warnings);
+    let estimated = estimate::estimate_timings_indexed(&index, &resolved.overlay, &EstimateOptions::default());
+    stage("estimate", "estimated.timing.json", to_json(&estimated.overlay)?, estimated.warnings);
+
+    let merged = merge::merge(&base, &estimated.overlay);
+    stage("merge", "timed.libretto.json", to_json(&merged.libretto)?, merged.warnings);
+
+    Ok(results)
+}
+
+fn to_json(value: &impl Serialize) -> Result<String> {
+    Ok(serde_json::to_string_pretty(value)? + "\n")
+}
+
+/// Where `actual` first departs from `expected`, line by line, ignoring
+/// line endings; `None` if they agree.
+pub fn first_difference(expected: &str, actual: &str) -> Option<Difference> {
+    let mut expected_lines = expected.lines().map(|l| l.trim_end_matches('\r'));
+    let mut actual_lines = actual.lines().map(|l| l.trim_end_matches('\r'));
+    for line in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (e, a) if e == a => continue,
+            (e, a) => {
+                return Some(Difference {
+                    line,
+                    expected: e.map(String::from),
+                    actual: a.map(String::from),
+                })
+            }
+        }
+    }
+    unreachable!("the loop returns once both files end")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_matches_golden_files() {
+        let results = run().unwrap();
+        assert_eq!(results.len(), GOLDEN.len());
+        for result in &results {
+            assert!(result.passed, "{} ({}) differs: {:?}", result.stage, result.file, result.difference);
+        }
+    }
+
+    #[test]
+    fn test_first_difference() {
+        assert_eq!(first_difference("a\nb\n", "a\r\nb\r\n"), None);
+        let diff = first_difference("a\nb\nc\n", "a\nB\nc\n").unwrap();
+        assert_eq!((diff.line, diff.expected.as_deref(), diff.actual.as_deref()), (2, Some("b"), Some("B")));
+        let diff = first_difference("a\n", "a\nb\n").unwrap();
+        assert_eq!((diff.line, diff.expected, diff.actual.as_deref()), (2, None, Some("b")));
+    }
+}
@@ -0,0 +1,265 @@
+// Timing tracks with an external forced aligner.
+//
+// A forced aligner takes a recording and the text sung in it and finds
+// where each line starts. Aligners are large speech toolkits in their own
+// right, so rather than linking one in, `timing align-audio` runs one as a
+// command: each track's segment texts are written one per line to a
+// temporary file, the aligner is run on the track's audio file, and its
+// JSON sync map (aeneas's format) becomes the track's segment times.
+//
+// The default command runs aeneas. Any other aligner can be used through
+// a wrapper script that takes the same arguments and writes the same JSON;
+// one that scores its fragments adds a `confidence` to each.
+
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{bail, Context, Result};
+use libretto_model::import;
+use libretto_model::{BaseLibretto, TimingOverlay};
+use serde::Serialize;
+
+use crate::audio::{match_track, AudioFile};
+
+/// Command run for each track. `{audio}`, `{text}`, `{output}` and
+/// `{language}` are replaced with the audio file, the text file, the sync
+/// map to write, and the language code.
+pub const DEFAULT_ALIGNER: &str = "python3 -m aeneas.tools.execute_task {audio} {text} \
+    \"task_language={language}|is_text_type=plain|os_task_file_format=json\" {output}";
+
+/// Default confidence below which an aligned segment is reported for review.
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.5;
+
+/// How to run the aligner.
+#[derive(Debug, Clone)]
+pub struct AlignerOptions {
+    /// Command template (see `DEFAULT_ALIGNER`), run with `sh -c`.
+    pub command: String,
+    /// Language code substituted for `{language}`.
+    pub language: String,
+    /// Confidence below which an aligned segment is reported.
+    pub min_confidence: f64,
+}
+
+/// One aligned segment, as written to the alignment report.
+#[derive(Debug, Clone, Serialize)]
+pub struct AlignedSegment {
+    pub segment_id: String,
+    pub start: f64,
+    pub end: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+/// The aligned segments of one track.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackAlignment {
+    pub track_id: String,
+    pub audio_file: String,
+    pub segments: Vec<AlignedSegment>,
+}
+
+/// Outcome of aligning an overlay's tracks.
+#[derive(Debug, Default, Serialize)]
+pub struct AlignSummary {
+    pub tracks: Vec<TrackAlignment>,
+    /// IDs of tracks with no text to align or no matching audio file.
+    pub skipped: Vec<String>,
+    /// Warnings from the import, including low-confidence segments.
+    pub warnings: Vec<String>,
+}
+
+/// Three-letter code for an ISO 639-1 language code, as aeneas expects.
+/// Codes it does not know are passed through.
+pub fn aligner_language(code: &str) -> String {
+    let three = match code {
+        "it" => "ita",
+        "de" => "deu",
+        "fr" => "fra",
+        "en" => "eng",
+        "ru" => "rus",
+        "cs" => "ces",
+        "es" => "spa",
+        "la" => "lat",
+        "pl" => "pol",
+        "hu" => "hun",
+        other => other,
+    };
+    three.to_string()
+}
+
+/// Align the segments of `track_id`, or of every track with numbers when
+/// it is `None`, replacing their segment times in `overlay`.
+///
+/// Tracks are matched to files as in `match_track`. A failing aligner
+/// command, or a sync map that does not fit the text, is an error.
+pub fn align_overlay(
+    base: &BaseLibretto,
+    overlay: &mut TimingOverlay,
+    track_id: Option<&str>,
+    files: &[AudioFile],
+    options: &AlignerOptions,
+) -> Result<AlignSummary> {
+    let track_ids: Vec<String> = match track_id {
+        Some(id) => vec![id.to_string()],
+        None => overlay.track_timings.iter()
+            .enumerate()
+            .filter(|(_, t)| !t.number_ids.is_empty())
+            .map(|(i, t)| t.track_id(i))
+            .collect(),
+    };
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+    let work_dir = std::env::temp_dir().join(format!(
+        "libretto-align-{}-{}",
+        std::process::id(),
+        RUNS.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed to create {}", work_dir.display()))?;
+
+    let mut summary = AlignSummary::default();
+    let result = (|| -> Result<()> {
+        for id in &track_ids {
+            let lines = import::alignment_text(base, overlay, id)?;
+            let track = &overlay.track_timings[overlay.find_track(id).expect("track found above")];
+            let file = match_track(&track.track_title, track.disc_number, track.track_number, files);
+            let Some(file) = file.filter(|_| !lines.is_empty()) else {
+                summary.skipped.push(id.clone());
+                continue;
+            };
+
+            let text_path = work_dir.join(format!("{id}.txt"));
+            let map_path = work_dir.join(format!("{id}.json"));
+            let text: String = lines.iter().map(|(_, line)| format!("{line}\n")).collect();
+            std::fs::write(&text_path, text)?;
+            run_aligner(&options.command, &file.path, &text_path, &map_path, &options.language)
+                .with_context(|| format!("Aligning track '{id}'"))?;
+            let contents = std::fs::read_to_string(&map_path)
+                .with_context(|| format!("Aligner wrote no sync map for track '{id}'"))?;
+            let fragments = import::parse_sync_map(&contents)
+                .with_context(|| format!("Invalid sync map for track '{id}'"))?;
+
+            let segment_ids: Vec<String> = lines.into_iter().map(|(segment_id, _)| segment_id).collect();
+            let result = import::import_alignment(overlay, id, &segment_ids, &fragments, options.min_confidence)?;
+            *overlay = result.overlay;
+            summary.warnings.extend(result.warnings);
+            summary.tracks.push(TrackAlignment {
+                track_id: id.clone(),
+                audio_file: file.path.display().to_string(),
+                segments: segment_ids.into_iter()
+                    .zip(&fragments)
+                    .map(|(segment_id, f)| AlignedSegment {
+                        segment_id,
+                        start: f.start,
+                        end: f.end,
+                        confidence: f.confidence,
+                    })
+                    .collect(),
+            });
+        }
+        Ok(())
+    })();
+    let _ = std::fs::remove_dir_all(&work_dir);
+    result.map(|()| summary)
+}
+
+/// Run the aligner command template on one track.
+fn run_aligner(template: &str, audio: &Path, text: &Path, output: &Path, language: &str) -> Result<()> {
+    let command = template
+        .replace("{audio}", &shell_quote(audio))
+        .replace("{text}", &shell_quote(text))
+        .replace("{output}", &shell_quote(output))
+        .replace("{language}", language);
+    tracing::debug!(command = %command, "Running aligner");
+    let out = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .output()
+        .context("Failed to run sh")?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        bail!("aligner exited with {}: {}", out.status, stderr.trim());
+    }
+    Ok(())
+}
+
+/// Quote a path for `sh`.
+fn shell_quote(path: &Path) -> String {
+    format!("'{}'", path.display().to_string().replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> (BaseLibretto, TimingOverlay) {
+        let base: BaseLibretto = serde_json::from_str(r#"{
+            "version": "1.0",
+            "opera": { "title": "T", "composer": "C", "language": "it" },
+            "cast": [],
+            "numbers": [
+                { "id": "no-1", "label": "1", "number_type": "aria", "act": "1", "segments": [
+                    { "id": "no-1-001", "text": "Voi che sapete" },
+                    { "id": "no-1-002", "segment_type": "direction", "direction": "Cherubino sighs" },
+                    { "id": "no-1-003", "text": "che cosa è amor" }
+                ] }
+            ]
+        }"#).unwrap();
+        let overlay: TimingOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [
+                { "track_title": "Aria", "disc_number": 1, "track_number": 1, "number_ids": ["no-1"] },
+                { "track_title": "Finale", "disc_number": 1, "track_number": 2, "number_ids": ["no-1"] }
+            ]
+        }"#).unwrap();
+        (base, overlay)
+    }
+
+    fn files() -> Vec<AudioFile> {
+        vec![AudioFile {
+            path: "/music/1-01 Aria.flac".into(),
+            disc_number: Some(1),
+            track_number: Some(1),
+            title: Some("Aria".to_string()),
+            duration_seconds: 20.0,
+        }]
+    }
+
+    #[test]
+    fn test_align_overlay() {
+        let (base, mut overlay) = fixtures();
+        // A stand-in aligner that checks its input and writes a fixed sync map
+        let options = AlignerOptions {
+            command: r#"test "$(wc -l < {text})" -eq 2 && test {language} = ita && printf '%s' '{"fragments": [
+                {"begin": "1.000", "end": "5.250", "confidence": 0.9},
+                {"begin": "5.250", "end": "9.000", "confidence": 0.2}]}' > {output}"#.to_string(),
+            language: aligner_language("it"),
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+        };
+        let summary = align_overlay(&base, &mut overlay, None, &files(), &options).unwrap();
+
+        assert_eq!(summary.skipped, vec!["d1-t2"]);
+        assert_eq!(summary.warnings.len(), 1);
+        assert_eq!(summary.tracks[0].segments[1].confidence, Some(0.2));
+        let times = &overlay.track_timings[0].segment_times;
+        assert_eq!(times.len(), 2);
+        assert_eq!((times[1].segment_id.as_str(), times[1].start), ("no-1-003", 5.25));
+    }
+
+    #[test]
+    fn test_align_overlay_aligner_failure() {
+        let (base, mut overlay) = fixtures();
+        let options = AlignerOptions {
+            command: "echo 'no such language' >&2; exit 3".to_string(),
+            language: "it".to_string(),
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+        };
+        let err = align_overlay(&base, &mut overlay, Some("d1-t1"), &files(), &options)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("no such language"));
+        assert!(overlay.track_timings[0].segment_times.is_empty());
+    }
+}
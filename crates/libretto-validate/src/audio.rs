@@ -0,0 +1,290 @@
+// Checking timing overlays against the audio files they describe.
+//
+// Overlays carry each track's `duration_seconds`, normally copied from the
+// album the timings were made against. Comparing those with the real
+// files catches overlays built for a different edition or remaster, where
+// track lengths (and so every segment time) are off. The same matching
+// fills in durations for new overlays, which the estimator needs.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use libretto_model::timing_overlay::round_to_ms;
+use libretto_model::TimingOverlay;
+
+use crate::{decode, ValidationError};
+
+/// Default allowed difference between an overlay duration and the audio file, in seconds.
+pub const DEFAULT_AUDIO_TOLERANCE_SECONDS: f64 = 2.0;
+
+/// File extensions probed as audio.
+pub const AUDIO_EXTENSIONS: &[&str] = &["flac", "mp3", "m4a", "mp4", "aac", "ogg", "oga", "wav", "aif", "aiff"];
+
+/// An audio file with the metadata used to match it to an overlay track.
+#[derive(Debug, Clone)]
+pub struct AudioFile {
+    pub path: PathBuf,
+    pub disc_number: Option<u32>,
+    pub track_number: Option<u32>,
+    pub title: Option<String>,
+    pub duration_seconds: f64,
+}
+
+/// Probe every audio file under `dir`, recursively.
+///
+/// Files that cannot be decoded are skipped with a warning.
+pub fn scan_audio_dir(dir: &Path) -> Result<Vec<AudioFile>> {
+    let mut paths = Vec::new();
+    collect_audio_paths(dir, &mut paths)?;
+    paths.sort();
+
+    let mut files = Vec::new();
+    for path in paths {
+        match probe(&path) {
+            Ok(file) => files.push(file),
+            Err(e) => tracing::warn!(path = %path.display(), "Skipping unreadable audio file: {e:#}"),
+        }
+    }
+    Ok(files)
+}
+
+fn collect_audio_paths(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_audio_paths(&path, paths)?;
+        } else if path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Read an audio file's duration and disc/track/title tags.
+///
+/// Tags missing from the file are taken from its name where possible
+/// (`1-03 Title.flac`, `03 - Title.mp3`). Requires the `audio` feature.
+pub fn probe(path: &Path) -> Result<AudioFile> {
+    let probed = decode::probe(path)?;
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let (name_disc, name_track, name_title) = parse_file_name(stem);
+    Ok(AudioFile {
+        path: path.to_path_buf(),
+        disc_number: probed.disc_number.or(name_disc),
+        track_number: probed.track_number.or(name_track),
+        title: probed.title.or(name_title),
+        duration_seconds: probed.duration_seconds,
+    })
+}
+
+/// Parse the leading number of a tag value such as `3` or `3/12`.
+pub(crate) fn leading_number(value: &str) -> Option<u32> {
+    let digits: String = value.trim().chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Split a file stem like `1-03 Title` or `03 - Title` into disc, track, and title.
+fn parse_file_name(stem: &str) -> (Option<u32>, Option<u32>, Option<String>) {
+    let first = leading_number(stem);
+    let mut rest = stem.trim_start_matches(|c: char| c.is_ascii_digit());
+    let (mut disc, mut track) = (None, first);
+    if first.is_some() {
+        if let Some(after) = rest.strip_prefix(['-', '.', '_']) {
+            if let Some(second) = leading_number(after) {
+                disc = first;
+                track = Some(second);
+                rest = after.trim_start_matches(|c: char| c.is_ascii_digit());
+            }
+        }
+    }
+    let title = rest.trim_start_matches([' ', '-', '.', '_']).trim();
+    (disc, track, (!title.is_empty()).then(|| title.to_string()))
+}
+
+/// Lowercased alphanumerics only, for comparing titles across tag conventions.
+fn normalize_title(title: &str) -> String {
+    title.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Find the audio file for an overlay track.
+///
+/// Tracks with a track number match on disc and track number (a missing
+/// disc number on either side matches any disc, as long as only one file
+/// does); otherwise, or if that finds nothing, on title.
+pub fn match_track<'a>(
+    track_title: &str,
+    disc_number: Option<u32>,
+    track_number: Option<u32>,
+    files: &'a [AudioFile],
+) -> Option<&'a AudioFile> {
+    if let Some(number) = track_number {
+        let candidates: Vec<&AudioFile> = files.iter()
+            .filter(|f| f.track_number == Some(number))
+            .filter(|f| disc_number.is_none() || f.disc_number.is_none() || f.disc_number == disc_number)
+            .collect();
+        let exact = candidates.iter().find(|f| disc_number.is_some() && f.disc_number == disc_number);
+        match (exact, candidates.as_slice()) {
+            (Some(f), _) => return Some(f),
+            (None, [only]) => return Some(only),
+            _ => {}
+        }
+    }
+    let wanted = normalize_title(track_title);
+    files.iter().find(|f| f.title.as_deref().is_some_and(|t| normalize_title(t) == wanted))
+}
+
+/// Compare each overlay track's `duration_seconds` with its audio file.
+///
+/// Tracks without a declared duration are not checked.
+pub fn check_audio_durations(
+    overlay: &TimingOverlay,
+    files: &[AudioFile],
+    tolerance: f64,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for track in &overlay.track_timings {
+        let Some(expected) = track.duration_seconds else { continue };
+        let Some(file) = match_track(&track.track_title, track.disc_number, track.track_number, files) else {
+            errors.push(ValidationError::NoAudioForTrack(track.track_title.clone()));
+            continue;
+        };
+        if (file.duration_seconds - expected).abs() > tolerance {
+            errors.push(ValidationError::AudioDurationMismatch {
+                track: track.track_title.clone(),
+                file: file.path.display().to_string(),
+                expected,
+                actual: file.duration_seconds,
+            });
+        }
+    }
+    errors
+}
+
+/// Tracks given durations by `fill_track_durations`, and those with no audio file.
+#[derive(Debug, Default)]
+pub struct DurationFill {
+    /// Tracks whose `duration_seconds` was set or changed.
+    pub updated: usize,
+    /// Tracks already carrying the file's duration.
+    pub unchanged: usize,
+    /// Titles of tracks no audio file matched; their durations are left alone.
+    pub unmatched: Vec<String>,
+}
+
+/// Set each overlay track's `duration_seconds` from its audio file,
+/// rounded to milliseconds. Tracks are matched as in `match_track`.
+pub fn fill_track_durations(overlay: &mut TimingOverlay, files: &[AudioFile]) -> DurationFill {
+    let mut fill = DurationFill::default();
+    for track in &mut overlay.track_timings {
+        match match_track(&track.track_title, track.disc_number, track.track_number, files) {
+            Some(file) => {
+                let duration = round_to_ms(file.duration_seconds);
+                if track.duration_seconds == Some(duration) {
+                    fill.unchanged += 1;
+                } else {
+                    track.duration_seconds = Some(duration);
+                    fill.updated += 1;
+                }
+            }
+            None => fill.unmatched.push(track.track_title.clone()),
+        }
+    }
+    fill
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "audio")]
+    use libretto_model::{RecordingMetadata, TrackTiming};
+
+    #[cfg(feature = "audio")]
+    fn write_wav(path: &Path, seconds: u32) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for _ in 0..spec.sample_rate * seconds {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[cfg(feature = "audio")]
+    fn track(title: &str, disc: Option<u32>, number: Option<u32>, duration: f64) -> TrackTiming {
+        TrackTiming {
+            track_title: title.to_string(),
+            disc_number: disc,
+            track_number: number,
+            duration_seconds: Some(duration),
+            number_ids: vec![],
+            start_segment_id: None,
+            resolved_by: None,
+            section_marks: vec![],
+            instrumental: false,
+            skip_segment_ids: vec![],
+            padding: None,
+            segment_times: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_file_name() {
+        assert_eq!(parse_file_name("1-03 Recondita armonia"), (Some(1), Some(3), Some("Recondita armonia".into())));
+        assert_eq!(parse_file_name("03 - Te Deum"), (None, Some(3), Some("Te Deum".into())));
+        assert_eq!(parse_file_name("Vissi d'arte"), (None, None, Some("Vissi d'arte".into())));
+    }
+
+    #[cfg(feature = "audio")]
+    #[test]
+    fn test_check_audio_durations() {
+        let dir = std::env::temp_dir().join(format!("libretto-audio-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("CD2")).unwrap();
+        write_wav(&dir.join("1-01 Act I.wav"), 3);
+        write_wav(&dir.join("CD2/2-01 Act II.wav"), 2);
+
+        let files = scan_audio_dir(&dir).unwrap();
+        assert_eq!(files.len(), 2);
+        assert!((files[0].duration_seconds - 3.0).abs() < 1e-6);
+
+        let overlay = TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            recording: RecordingMetadata {
+                conductor: None,
+                orchestra: None,
+                year: None,
+                label: None,
+                album_title: None,
+            },
+            track_timings: vec![
+                track("Act I", Some(1), Some(1), 3.5),
+                // Matched by title, but the recording is longer
+                track("Act II", None, None, 12.0),
+                track("Act III", Some(3), Some(1), 5.0),
+            ],
+            ..Default::default()
+        };
+        let errors = check_audio_durations(&overlay, &files, DEFAULT_AUDIO_TOLERANCE_SECONDS);
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(&errors[0], ValidationError::AudioDurationMismatch { track, .. } if track == "Act II"));
+        assert!(matches!(&errors[1], ValidationError::NoAudioForTrack(t) if t == "Act III"));
+
+        let mut overlay = overlay;
+        let fill = fill_track_durations(&mut overlay, &files);
+        assert_eq!((fill.updated, fill.unchanged), (2, 0));
+        assert_eq!(fill.unmatched, vec!["Act III"]);
+        assert_eq!(overlay.track_timings[1].duration_seconds, Some(2.0));
+        assert!(check_audio_durations(&overlay, &files, 0.0).iter().all(|e| e.rule() == "no-audio-for-track"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+// Batch validation over a library directory tree.
+//
+// Every model document under the root is validated; overlays are checked
+// against the base libretto they declare, resolved the same way the
+// library index resolves it. Files are validated on several threads at
+// once, as a library of a few dozen operas has hundreds of documents.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Result;
+use libretto_model::library::{self, LIBRARY_INDEX_FILE};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::lint::LintConfig;
+use crate::report::ValidationReport;
+use crate::DocumentKind;
+
+/// Conventional base libretto file name, used when an overlay's declared
+/// base cannot be resolved.
+const DEFAULT_BASE_FILE: &str = "base.libretto.json";
+
+/// Results of validating every document under a directory.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub root: String,
+    /// Whether every file passed and loaded.
+    pub passed: bool,
+    pub files: usize,
+    pub failed: usize,
+    pub reports: Vec<ValidationReport>,
+    /// Files that are not valid JSON, or are recognized as model documents
+    /// but could not be loaded.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub load_errors: Vec<LoadFailure>,
+}
+
+/// A document that failed to read or parse.
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadFailure {
+    pub file: String,
+    pub message: String,
+}
+
+/// Called as each file is done with the number done, the number in all,
+/// and the file's path relative to the root.
+pub type ProgressFn<'a> = dyn Fn(usize, usize, &str) + Sync + 'a;
+
+/// How to run a batch validation.
+#[derive(Clone, Copy, Default)]
+pub struct BatchOptions<'a> {
+    /// Files validated at once; 0 for one per available CPU.
+    pub jobs: usize,
+    pub progress: Option<&'a ProgressFn<'a>>,
+}
+
+/// Validate every base libretto, overlay, and interchange document under `root`.
+///
+/// Overlays are validated against their declared `base_libretto`, resolved
+/// relative to the root or to the overlay's directory; failing that,
+/// against the nearest `base.libretto.json` in the overlay's directory or
+/// an ancestor within the root. Overlays with no base found are validated
+/// standalone. JSON files that are not model documents are skipped, but a
+/// `.json` file that does not parse is a load error. Report paths are
+/// relative to `root`.
+pub fn validate_tree(root: &Path, config: &LintConfig, strict: bool) -> Result<BatchReport> {
+    validate_tree_with(root, config, strict, &BatchOptions::default())
+}
+
+/// Validate a tree as `validate_tree` does, with `options`. Reports are in
+/// path order however many files are validated at once.
+pub fn validate_tree_with(root: &Path, config: &LintConfig, strict: bool, options: &BatchOptions) -> Result<BatchReport> {
+    let mut files = Vec::new();
+    library::collect_json_files(root, &mut files)?;
+    files.retain(|file| library::relative_path(root, file) != LIBRARY_INDEX_FILE);
+
+    let done = AtomicUsize::new(0);
+    let validate_all = || -> Vec<Option<Outcome>> {
+        files.par_iter()
+            .map(|file| {
+                let outcome = validate_one(root, file, config, strict);
+                let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if let Some(progress) = options.progress {
+                    progress(done, files.len(), &library::relative_path(root, file));
+                }
+                outcome
+            })
+            .collect()
+    };
+    let outcomes = match options.jobs {
+        0 => validate_all(),
+        jobs => rayon::ThreadPoolBuilder::new().num_threads(jobs).build()?.install(validate_all),
+    };
+
+    let mut reports = Vec::new();
+    let mut load_errors = Vec::new();
+    for outcome in outcomes.into_iter().flatten() {
+        match outcome {
+            Outcome::Report(report) => reports.push(*report),
+            Outcome::LoadFailure(failure) => load_errors.push(failure),
+        }
+    }
+
+    let failed = reports.iter().filter(|r| !r.passed).count() + load_errors.len();
+    Ok(BatchReport {
+        root: root.display().to_string(),
+        passed: failed == 0,
+        files: reports.len() + load_errors.len(),
+        failed,
+        reports,
+        load_errors,
+    })
+}
+
+enum Outcome {
+    Report(Box<ValidationReport>),
+    LoadFailure(LoadFailure),
+}
+
+/// Validate one file of the tree; `None` if it is JSON but not a model
+/// document.
+fn validate_one(root: &Path, file: &Path, config: &LintConfig, strict: bool) -> Option<Outcome> {
+    let rel = library::relative_path(root, file);
+    let Ok(contents) = std::fs::read_to_string(file) else {
+        return Some(Outcome::LoadFailure(LoadFailure { file: rel, message: "unreadable".to_string() }));
+    };
+    let kind = match DocumentKind::detect(&contents) {
+        Ok(kind) => kind?,
+        Err(e) => return Some(Outcome::LoadFailure(LoadFailure { file: rel, message: format!("invalid JSON: {e}") })),
+    };
+
+    let base = match kind {
+        DocumentKind::TimingOverlay
+        | DocumentKind::AnnotationOverlay
+        | DocumentKind::PronunciationOverlay
+        | DocumentKind::SynopsisOverlay => {
+            declared_base(&contents).and_then(|reference| find_base(root, &rel, &reference))
+        }
+        _ => None,
+    };
+    let base_path = base.as_ref().map(|b| root.join(b).to_string_lossy().into_owned());
+
+    Some(match crate::validate_file(&file.to_string_lossy(), base_path.as_deref(), config, strict) {
+        Ok(mut report) => {
+            report.file = rel;
+            report.base = base;
+            Outcome::Report(Box::new(report))
+        }
+        Err(e) => Outcome::LoadFailure(LoadFailure { file: rel, message: format!("{e:#}") }),
+    })
+}
+
+/// Read the `base_libretto` reference from an overlay without a full parse.
+fn declared_base(contents: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(contents).ok()?;
+    value.get("base_libretto")?.as_str().map(String::from)
+}
+
+/// Resolve an overlay's base libretto to a root-relative path that exists.
+fn find_base(root: &Path, overlay_rel: &str, reference: &str) -> Option<String> {
+    let declared = library::resolve_reference(overlay_rel, reference)
+        .into_iter()
+        .find(|p| root.join(p).is_file());
+    declared.or_else(|| {
+        // Fall back to the naming convention: nearest base.libretto.json above the overlay
+        let overlay_dir = Path::new(overlay_rel).parent().unwrap_or(Path::new(""));
+        overlay_dir.ancestors()
+            .map(|dir| library::relative_path(Path::new(""), &dir.join(DEFAULT_BASE_FILE)))
+            .find(|p| root.join(p).is_file())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_root(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("libretto-batch-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(root: &Path, rel: &str, contents: &str) {
+        let path = root.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    const BASE: &str = r#"{
+        "version": "1.0",
+        "opera": { "title": "Tosca", "composer": "Puccini", "language": "it" },
+        "cast": [],
+        "numbers": [{ "id": "act-1", "label": "Act I", "number_type": "other", "act": "1",
+            "segments": [{ "id": "act-1-001", "type": "sung", "text": "Ah!" }] }]
+    }"#;
+
+    fn overlay(base_ref: &str, segment_id: &str) -> String {
+        format!(r#"{{
+            "version": "1.0",
+            "base_libretto": "{base_ref}",
+            "recording": {{}},
+            "track_timings": [{{ "track_title": "Act I", "number_ids": ["act-1"],
+                "segment_times": [{{ "segment_id": "{segment_id}", "start": 0.0 }}] }}]
+        }}"#)
+    }
+
+    #[test]
+    fn test_validate_tree() {
+        let root = temp_root("tree");
+        write(&root, "puccini/tosca/base.libretto.json", BASE);
+        write(&root, "puccini/tosca/timings/good.timing.json", &overlay("puccini/tosca/base.libretto.json", "act-1-001"));
+        write(&root, "puccini/tosca/timings/bad.timing.json", &overlay("missing.json", "act-1-999"));
+        write(&root, "puccini/tosca/timings/broken.timing.json", r#"{"track_timings": 5}"#);
+        write(&root, "puccini/tosca/bilingual.json", r#"{"rows": []}"#);
+        write(&root, "puccini/tosca/notes.json", r#"{"rows": [}"#);
+
+        let seen = std::sync::Mutex::new(Vec::new());
+        let progress = |done: usize, total: usize, _: &str| seen.lock().unwrap().push((done, total));
+        let options = BatchOptions { jobs: 3, progress: Some(&progress) };
+        let batch = validate_tree_with(&root, &LintConfig::default(), false, &options).unwrap();
+        assert_eq!(seen.into_inner().unwrap().len(), 6);
+        assert_eq!(batch.files, 5);
+        assert_eq!(batch.failed, 3);
+        assert!(!batch.passed);
+
+        let bad = batch.reports.iter().find(|r| r.file.ends_with("bad.timing.json")).unwrap();
+        // Declared base is missing, so the conventional base.libretto.json is used
+        assert_eq!(bad.base.as_deref(), Some("puccini/tosca/base.libretto.json"));
+        assert!(bad.rule_counts.contains_key("unknown-segment-id"));
+        let good = batch.reports.iter().find(|r| r.file.ends_with("good.timing.json")).unwrap();
+        assert!(good.passed);
+        // Not JSON at all: reported, not passed over as some other kind of file
+        assert_eq!(batch.load_errors[0].file, "puccini/tosca/notes.json");
+        assert!(batch.load_errors[0].message.starts_with("invalid JSON: "), "{}", batch.load_errors[0].message);
+        assert_eq!(batch.load_errors[1].file, "puccini/tosca/timings/broken.timing.json");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,177 @@
+// Audio decoding with symphonia.
+//
+// Decoding is behind the `audio` feature so library users who only
+// validate JSON do not pull in the codecs. Without it these functions
+// return an error naming the feature, like the binary interchange
+// encodings in libretto-model.
+
+use std::path::Path;
+
+use anyhow::Result;
+
+/// Tags and duration read from an audio file.
+#[derive(Debug, Default)]
+pub(crate) struct Probed {
+    pub disc_number: Option<u32>,
+    pub track_number: Option<u32>,
+    pub title: Option<String>,
+    pub duration_seconds: f64,
+}
+
+/// Read an audio file's duration and disc/track/title tags.
+pub(crate) fn probe(path: &Path) -> Result<Probed> {
+    #[cfg(feature = "audio")]
+    {
+        symphonia_impl::probe(path)
+    }
+    #[cfg(not(feature = "audio"))]
+    unsupported(path)
+}
+
+/// RMS level of the file's mono mixdown over consecutive windows of
+/// `window_seconds`, as linear amplitude (full scale = 1.0).
+pub(crate) fn rms_envelope(path: &Path, window_seconds: f64) -> Result<Vec<f32>> {
+    #[cfg(feature = "audio")]
+    {
+        symphonia_impl::rms_envelope(path, window_seconds)
+    }
+    #[cfg(not(feature = "audio"))]
+    {
+        let _ = window_seconds;
+        unsupported(path)
+    }
+}
+
+#[cfg(not(feature = "audio"))]
+fn unsupported<T>(path: &Path) -> Result<T> {
+    anyhow::bail!("{}: audio decoding not compiled in (enable the `audio` feature)", path.display())
+}
+
+#[cfg(feature = "audio")]
+mod symphonia_impl {
+    use std::fs::File;
+    use std::path::Path;
+
+    use anyhow::{Context, Result};
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::DecoderOptions;
+    use symphonia::core::errors::Error as SymphoniaError;
+    use symphonia::core::formats::FormatOptions;
+    use symphonia::core::io::MediaSourceStream;
+    use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey};
+    use symphonia::core::probe::{Hint, ProbeResult};
+
+    use super::Probed;
+    use crate::audio::leading_number;
+
+    fn open(path: &Path) -> Result<ProbeResult> {
+        let source = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+        Ok(symphonia::default::get_probe()
+            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())?)
+    }
+
+    pub(super) fn probe(path: &Path) -> Result<Probed> {
+        let mut probed = open(path)?;
+        let mut tags = Probed::default();
+        if let Some(metadata) = probed.metadata.get() {
+            read_tags(&mut tags, metadata.current());
+        }
+        read_tags(&mut tags, probed.format.metadata().current());
+
+        let track = probed.format.default_track()
+            .with_context(|| format!("{}: no audio track", path.display()))?;
+        let track_id = track.id;
+        let params = track.codec_params.clone();
+        tags.duration_seconds = match (params.n_frames, params.time_base) {
+            (Some(frames), Some(tb)) => {
+                let t = tb.calc_time(frames);
+                t.seconds as f64 + t.frac
+            }
+            _ => {
+                // No frame count in the header (e.g. MP3 without a Xing frame):
+                // sum packet durations instead
+                let tb = params.time_base
+                    .with_context(|| format!("{}: unknown time base", path.display()))?;
+                let mut ts = 0;
+                while let Ok(packet) = probed.format.next_packet() {
+                    if packet.track_id() == track_id {
+                        ts += packet.dur();
+                    }
+                }
+                let t = tb.calc_time(ts);
+                t.seconds as f64 + t.frac
+            }
+        };
+        Ok(tags)
+    }
+
+    /// Take tags from a metadata revision, keeping any already found.
+    fn read_tags(tags: &mut Probed, revision: Option<&MetadataRevision>) {
+        for tag in revision.map(|r| r.tags()).unwrap_or_default() {
+            let value = tag.value.to_string();
+            match tag.std_key {
+                Some(StandardTagKey::DiscNumber) => tags.disc_number = tags.disc_number.or(leading_number(&value)),
+                Some(StandardTagKey::TrackNumber) => tags.track_number = tags.track_number.or(leading_number(&value)),
+                Some(StandardTagKey::TrackTitle) => tags.title = tags.title.take().or(Some(value)),
+                _ => {}
+            }
+        }
+    }
+
+    pub(super) fn rms_envelope(path: &Path, window_seconds: f64) -> Result<Vec<f32>> {
+        let mut probed = open(path)?;
+        let track = probed.format.default_track()
+            .with_context(|| format!("{}: no audio track", path.display()))?;
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate
+            .with_context(|| format!("{}: unknown sample rate", path.display()))?;
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+        let window_len = ((sample_rate as f64 * window_seconds).round() as usize).max(1);
+
+        let mut envelope = Vec::new();
+        let (mut sum, mut count) = (0.0_f64, 0_usize);
+        let mut buffer: Option<SampleBuffer<f32>> = None;
+        loop {
+            let packet = match probed.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(e.into()),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // A corrupt packet is skipped rather than failing the whole track
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let spec = *decoded.spec();
+            let channels = spec.channels.count().max(1);
+            if buffer.as_ref().is_none_or(|b| b.capacity() < decoded.capacity()) {
+                buffer = Some(SampleBuffer::new(decoded.capacity() as u64, spec));
+            }
+            let buffer = buffer.as_mut().expect("buffer allocated above");
+            buffer.copy_interleaved_ref(decoded);
+            for frame in buffer.samples().chunks(channels) {
+                let mono = frame.iter().sum::<f32>() / channels as f32;
+                sum += f64::from(mono * mono);
+                count += 1;
+                if count == window_len {
+                    envelope.push((sum / count as f64).sqrt() as f32);
+                    (sum, count) = (0.0, 0);
+                }
+            }
+        }
+        if count > 0 {
+            envelope.push((sum / count as f64).sqrt() as f32);
+        }
+        Ok(envelope)
+    }
+}
@@ -0,0 +1,242 @@
+// Automatic repair of mechanical timing overlay problems.
+//
+// `libretto validate --fix` applies only corrections that cannot change
+// what an overlay means: ordering, exact duplicates, and float noise.
+// Anything needing judgement (unknown IDs, overlaps, bad durations) is
+// left for the validation report.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+use libretto_model::timing_overlay::round_to_ms;
+use libretto_model::{io, library, BaseLibretto, SegmentTime, TimingOverlay};
+
+/// Counts of each kind of fix applied to an overlay.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FixSummary {
+    /// Start, end, and duration times rounded to millisecond precision.
+    pub times_rounded: usize,
+    /// Segment time entries removed as exact duplicates of an earlier one in the same track.
+    pub duplicates_removed: usize,
+    /// Tracks whose segment times were re-sorted by start.
+    pub tracks_sorted: usize,
+    /// Tracks whose number IDs were deduplicated or reordered to base libretto order,
+    /// plus one if the omitted numbers were.
+    pub number_lists_normalized: usize,
+}
+
+impl FixSummary {
+    /// Whether nothing was changed.
+    pub fn is_empty(&self) -> bool {
+        *self == FixSummary::default()
+    }
+
+    /// One human-readable line per kind of fix applied.
+    pub fn lines(&self) -> Vec<String> {
+        [
+            (self.times_rounded, "times rounded to milliseconds"),
+            (self.duplicates_removed, "duplicate segment times removed"),
+            (self.tracks_sorted, "tracks re-sorted by start time"),
+            (self.number_lists_normalized, "number ID lists normalized"),
+        ]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{count} {what}"))
+        .collect()
+    }
+}
+
+/// Apply safe fixes to a timing overlay in place.
+///
+/// Times are rounded to milliseconds first, so entries differing only by
+/// float noise count as duplicates. Entries for one segment at one start
+/// that differ in end or source are all kept, for the report to flag. Number IDs are deduplicated and, when
+/// a base libretto is available, put in its order; IDs it does not know
+/// keep their relative order at the end.
+pub fn fix_timing_overlay(overlay: &mut TimingOverlay, base: Option<&BaseLibretto>) -> FixSummary {
+    let mut summary = FixSummary::default();
+    let order: HashMap<&str, usize> = base
+        .map(|b| b.numbers.iter().enumerate().map(|(i, n)| (n.id.as_str(), i)).collect())
+        .unwrap_or_default();
+    let rank = |id: &str| order.get(id).copied().unwrap_or(usize::MAX);
+
+    for track in &mut overlay.track_timings {
+        for st in &mut track.segment_times {
+            let rounded = round_to_ms(st.start);
+            if rounded != st.start {
+                st.start = rounded;
+                summary.times_rounded += 1;
+            }
+            if let Some(end) = st.end.filter(|&e| round_to_ms(e) != e) {
+                st.end = Some(round_to_ms(end));
+                summary.times_rounded += 1;
+            }
+        }
+        if let Some(d) = track.duration_seconds {
+            if round_to_ms(d) != d {
+                track.duration_seconds = Some(round_to_ms(d));
+                summary.times_rounded += 1;
+            }
+        }
+
+        let before = track.segment_times.len();
+        let mut kept: Vec<SegmentTime> = Vec::new();
+        track.segment_times.retain(|st| {
+            let duplicate = kept.contains(st);
+            if !duplicate {
+                kept.push(st.clone());
+            }
+            !duplicate
+        });
+        summary.duplicates_removed += before - track.segment_times.len();
+
+        if track.segment_times.windows(2).any(|w| w[1].start < w[0].start) {
+            track.sort_segment_times();
+            summary.tracks_sorted += 1;
+        }
+
+        let mut ids = track.number_ids.clone();
+        dedup_preserving_order(&mut ids);
+        ids.sort_by_key(|id| rank(id));
+        if ids != track.number_ids {
+            track.number_ids = ids;
+            summary.number_lists_normalized += 1;
+        }
+    }
+
+    if base.is_some() && overlay.omitted_numbers.windows(2).any(|w| rank(&w[1].number_id) < rank(&w[0].number_id)) {
+        overlay.omitted_numbers.sort_by_key(|o| rank(&o.number_id));
+        summary.number_lists_normalized += 1;
+    }
+
+    summary
+}
+
+fn dedup_preserving_order(ids: &mut Vec<String>) {
+    let mut seen = Vec::new();
+    ids.retain(|id| {
+        let new = !seen.contains(id);
+        if new {
+            seen.push(id.clone());
+        }
+        new
+    });
+}
+
+/// Load a timing overlay file and fix it, returning the fixed overlay to
+/// write back if the summary is not empty.
+///
+/// Number ordering uses `base_path` if given, otherwise the overlay's own
+/// `base_libretto` reference when it can be found.
+pub fn fix_file(file_path: &Path, base_path: Option<&Path>) -> Result<(TimingOverlay, FixSummary)> {
+    let mut overlay: TimingOverlay = io::load_json(file_path)?;
+    let base_path = base_path
+        .map(Path::to_path_buf)
+        .or_else(|| library::locate_reference(file_path, &overlay.base_libretto));
+    let base: Option<BaseLibretto> = base_path.map(io::load_json).transpose()?;
+
+    let summary = fix_timing_overlay(&mut overlay, base.as_ref());
+    Ok((overlay, summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libretto_model::{OmittedNumber, RecordingMetadata, TimingSource, TrackTiming};
+
+    fn st(id: &str, start: f64) -> SegmentTime {
+        SegmentTime { segment_id: id.to_string(), start, end: None, source: None }
+    }
+
+    #[test]
+    fn test_fix_timing_overlay() {
+        let mut overlay = TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            recording: RecordingMetadata {
+                conductor: None,
+                orchestra: None,
+                year: None,
+                label: None,
+                album_title: None,
+            },
+            track_timings: vec![TrackTiming {
+                track_title: "Track 1".to_string(),
+                duration_seconds: Some(60.000_000_1),
+                number_ids: vec!["no-2".to_string(), "no-1".to_string(), "no-2".to_string()],
+                segment_times: vec![
+                    st("a", 0.0),
+                    st("c", 20.0),
+                    st("b", 10.499_999_999),
+                    st("c", 20.0),
+                ],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let summary = fix_timing_overlay(&mut overlay, None);
+        assert_eq!(summary, FixSummary {
+            times_rounded: 2,
+            duplicates_removed: 1,
+            tracks_sorted: 1,
+            number_lists_normalized: 1,
+        });
+        let track = &overlay.track_timings[0];
+        let ids: Vec<_> = track.segment_times.iter().map(|s| s.segment_id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "c"]);
+        assert_eq!(track.segment_times[1].start, 10.5);
+        // Without a base libretto, number IDs are only deduplicated
+        assert_eq!(track.number_ids, vec!["no-2", "no-1"]);
+
+        // A second pass finds nothing to do
+        assert!(fix_timing_overlay(&mut overlay, None).is_empty());
+    }
+
+    #[test]
+    fn test_fix_keeps_near_duplicates() {
+        // One start, estimated then verified: not the same entry
+        let verified = SegmentTime { source: Some(TimingSource::Verified), ..st("a", 5.0) };
+        let estimated = SegmentTime { source: Some(TimingSource::Estimated), ..st("a", 5.0) };
+        let mut overlay = TimingOverlay {
+            track_timings: vec![TrackTiming {
+                segment_times: vec![estimated.clone(), verified.clone(), SegmentTime { end: Some(9.0), ..st("a", 5.0) }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let summary = fix_timing_overlay(&mut overlay, None);
+        assert_eq!(summary.duplicates_removed, 0);
+        assert_eq!(overlay.track_timings[0].segment_times[..2], [estimated, verified]);
+    }
+
+    #[test]
+    fn test_number_order_from_base() {
+        let base: BaseLibretto = serde_json::from_str(r#"{
+            "version": "1.0",
+            "opera": { "title": "T", "composer": "C", "language": "it" },
+            "cast": [],
+            "numbers": [
+                { "id": "no-1", "label": "1", "number_type": "aria", "act": "1", "segments": [] },
+                { "id": "no-2", "label": "2", "number_type": "aria", "act": "1", "segments": [] },
+                { "id": "no-3", "label": "3", "number_type": "aria", "act": "1", "segments": [] }
+            ]
+        }"#).unwrap();
+        let mut overlay: TimingOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [{ "track_title": "T", "number_ids": ["x", "no-2", "no-1"] }],
+            "omitted_numbers": [{ "number_id": "no-3" }, { "number_id": "no-1" }]
+        }"#).unwrap();
+        overlay.omitted_numbers.push(OmittedNumber { number_id: "no-2".to_string(), reason: None });
+
+        let summary = fix_timing_overlay(&mut overlay, Some(&base));
+        assert_eq!(summary.number_lists_normalized, 2);
+        assert_eq!(overlay.track_timings[0].number_ids, vec!["no-1", "no-2", "x"]);
+        let omitted: Vec<_> = overlay.omitted_number_ids();
+        assert_eq!(omitted, vec!["no-1", "no-2", "no-3"]);
+        assert_eq!(summary.lines(), vec!["2 number ID lists normalized"]);
+    }
+}
@@ -1,8 +1,52 @@
-use anyhow::Result;
-use libretto_model::{BaseLibretto, TimingOverlay};
-use std::collections::HashSet;
+use anyhow::{Context, Result};
+use libretto_model::encoding::{self, Encoding};
+use libretto_model::base_libretto::split_character_attribution;
+use libretto_model::{
+    artifacts, gaps, io, library, AnnotationOverlay, BaseLibretto, InterchangeLibretto, PronunciationOverlay,
+    SynopsisOverlay, TimingOverlay,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use thiserror::Error;
 
+pub mod align;
+pub mod audio;
+pub mod batch;
+mod decode;
+pub mod fix;
+pub mod lint;
+pub mod report;
+pub mod silence;
+pub mod stats;
+
+pub use lint::{Finding, LintConfig, Severity, TimingBounds};
+pub use report::{ReportFinding, ValidationReport};
+
+/// Shortest plausible duration of a track's final segment, in seconds.
+///
+/// A last segment starting closer than this to the end of the track is
+/// almost always a mistimed start rather than a real sub-second line.
+pub const MIN_FINAL_SEGMENT_SECONDS: f64 = 1.0;
+
+/// Longest plausible single character name, in words ("IL CONTE D'ALMAVIVA" is 4).
+pub const MAX_CHARACTER_NAME_WORDS: usize = 5;
+
+/// Heading prefixes that are never character names, in the languages sources use.
+const HEADING_PREFIXES: &[&str] = &[
+    "ACT ", "ATTO ", "ACTE ", "AKT ", "SCENE", "SCENA", "SCÈNE", "SZENE",
+    "OVERTURE", "SINFONIA", "OUVERTURE", "PRELUDE", "PRELUDIO", "END OF", "FINE ", "FIN ",
+];
+
+/// Lowercase words that may join or qualify names ("SUSANNA e FIGARO", "DON ALFONSO di").
+const NAME_PARTICLES: &[&str] = &[
+    "e", "ed", "and", "und", "et", "di", "de", "del", "della", "da", "von", "van", "la", "le", "il", "lo", "a",
+];
+
+/// Generic ensemble attributions that need no cast entry.
+pub const ENSEMBLE_ATTRIBUTIONS: &[&str] = &["TUTTI", "ENSEMBLE", "CORO", "CHORUS", "CHOR", "CHOEUR", "ALL", "ALLE", "TOUS"];
+
 #[derive(Debug, Error)]
 pub enum ValidationError {
     #[error("missing required field: {0}")]
@@ -20,6 +64,73 @@ pub enum ValidationError {
     #[error("segment time {0}s is negative")]
     NegativeTime(f64),
 
+    #[error("track '{track}': segment '{segment_id}' starts at {start}s, beyond the track duration of {duration}s")]
+    SegmentBeyondDuration {
+        track: String,
+        segment_id: String,
+        start: f64,
+        duration: f64,
+    },
+
+    #[error("track '{track}': last segment '{segment_id}' starts {tail:.3}s before the end of the track (minimum {MIN_FINAL_SEGMENT_SECONDS}s)")]
+    ShortFinalSegment {
+        track: String,
+        segment_id: String,
+        tail: f64,
+    },
+
+    #[error("segment '{segment_id}' referenced more than once: track '{first_track}' position {first_position}, track '{track}' position {position}")]
+    DuplicateSegmentReference {
+        segment_id: String,
+        first_track: String,
+        first_position: usize,
+        track: String,
+        position: usize,
+    },
+
+    #[error("base libretto '{0}' not found relative to the overlay or any parent directory")]
+    MissingBaseLibretto(String),
+
+    #[error("base libretto '{reference}' has changed since the overlay was made (sha256 {actual}, recorded {expected})")]
+    BaseHashMismatch {
+        reference: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("overlay declares base '{reference}' ({declared}), but is being checked against {checked}")]
+    BaseOperaMismatch {
+        reference: String,
+        declared: String,
+        checked: String,
+    },
+
+    #[error("none of the overlay's numbers exist in its declared base '{0}'; it may belong to a different opera")]
+    ForeignBaseReference(String),
+
+    #[error("number '{number_id}' is in act {act}, after a number in act {previous_act}")]
+    ActOutOfOrder {
+        number_id: String,
+        act: String,
+        previous_act: String,
+    },
+
+    #[error("act {act} resumes at number '{number_id}' after other acts")]
+    ActNotContiguous {
+        act: String,
+        number_id: String,
+    },
+
+    #[error("track '{track}' (disc {disc}, track {number}) does not follow track '{previous_track}' (disc {previous_disc}, track {previous_number})")]
+    TrackOutOfOrder {
+        track: String,
+        disc: u32,
+        number: u32,
+        previous_track: String,
+        previous_disc: u32,
+        previous_number: u32,
+    },
+
     #[error("number '{0}' is neither covered by any track nor declared as omitted")]
     UnaccountedNumber(String),
 
@@ -29,40 +140,448 @@ pub enum ValidationError {
     #[error("number '{0}' is both covered by a track and declared as omitted")]
     ConflictingCoverage(String),
 
+    #[error("number '{number_id}' has only {timed} of {segments} segments timed")]
+    PartiallyTimedNumber {
+        number_id: String,
+        timed: usize,
+        segments: usize,
+    },
+
+    #[error("track '{track}': segment '{segment_id}' implies {rate:.2} words/s ({words} words in {interval:.1}s)")]
+    ImplausibleSpeechRate {
+        track: String,
+        segment_id: String,
+        words: usize,
+        interval: f64,
+        rate: f64,
+    },
+
+    #[error("track '{track}': duration {expected}s differs from the audio file {file} ({actual:.3}s)")]
+    AudioDurationMismatch {
+        track: String,
+        file: String,
+        expected: f64,
+        actual: f64,
+    },
+
+    #[error("no audio file found for track '{0}'")]
+    NoAudioForTrack(String),
+
+    #[error("annotation references unknown {0} ID: {1}")]
+    UnknownAnnotationTarget(&'static str, String),
+
+    #[error("pronunciation references unknown segment ID: {0}")]
+    UnknownPronunciationSegment(String),
+
+    #[error("segment '{segment_id}': pronunciation for line {line} of a {lines}-line text")]
+    PronunciationLineOutOfRange {
+        segment_id: String,
+        line: usize,
+        lines: usize,
+    },
+
+    #[error("segment '{0}' has a pronunciation but no text")]
+    PronunciationWithoutText(String),
+
+    #[error("segment '{0}' is pronounced more than once (a line twice, or the whole text and a line)")]
+    DuplicatePronunciation(String),
+
+    #[error("synopsis for unknown number ID: {0}")]
+    UnknownSynopsisNumber(String),
+
+    #[error("character '{name}' ({occurrences} segments, first '{first_segment}') is not in the cast list")]
+    UnknownCharacter {
+        name: String,
+        first_segment: String,
+        occurrences: usize,
+    },
+
+    #[error("segment '{segment_id}': character '{name}' looks misparsed ({reason})")]
+    SuspiciousCharacterName {
+        segment_id: String,
+        name: String,
+        reason: &'static str,
+    },
+
+    #[error("cast member '{0}' is never attributed a segment")]
+    SilentCastMember(String),
+
+    #[error("segment '{segment_id}' {field}: {artifact}")]
+    SuspiciousText {
+        segment_id: String,
+        field: &'static str,
+        artifact: String,
+    },
+
+    #[error("number '{0}' has no translated segments")]
+    UntranslatedNumber(String),
+
+    #[error("translation text present but no license declared in rights")]
+    UnlicensedTranslation,
+
+    #[error("duplicate track ID: {0}")]
+    DuplicateTrackId(String),
+
+    #[error("track '{0}' has no segments")]
+    EmptyTrack(String),
+
+    #[error("track '{track}': segment {segment} has no usable start time ({start})")]
+    NonFiniteTime {
+        track: String,
+        segment: String,
+        start: f64,
+    },
+
+    #[error("track '{track}': segment {segment} ends at {end}s, before its start at {start}s")]
+    EndBeforeStart {
+        track: String,
+        segment: String,
+        start: f64,
+        end: f64,
+    },
+
+    #[error("track '{track}': segment {segment} ends at {end}s, after the next segment starts at {next_start}s")]
+    OverlappingSegments {
+        track: String,
+        segment: String,
+        end: f64,
+        next_start: f64,
+    },
+
+    #[error("track '{track}': nothing showing for {seconds:.1}s between segment '{after}' (ends {start}s) and '{before}' ({end}s)")]
+    LongGap {
+        track: String,
+        after: String,
+        before: String,
+        start: f64,
+        end: f64,
+        seconds: f64,
+    },
+
+    #[error("entry {entry} left out: {0}", entry = .0.entry)]
+    SkippedEntry(io::SkippedEntry),
+
     #[error("{0}")]
     Other(String),
 }
 
-/// Validate a base libretto or timing overlay file.
-///
-/// If `base_path` is provided, the file is treated as a timing overlay
-/// and segment ID references are checked against the base libretto.
-pub fn validate(file_path: &str, base_path: Option<&str>) -> Result<()> {
-    let contents = std::fs::read_to_string(file_path)?;
-
-    if let Some(base) = base_path {
-        // Validate as timing overlay
-        let overlay: TimingOverlay = serde_json::from_str(&contents)?;
-        let base_contents = std::fs::read_to_string(base)?;
-        let base_libretto: BaseLibretto = serde_json::from_str(&base_contents)?;
-        validate_timing_overlay(&overlay, &base_libretto)?;
-        tracing::info!("Timing overlay is valid");
-    } else {
-        // Try as base libretto first, then as timing overlay
-        if let Ok(libretto) = serde_json::from_str::<BaseLibretto>(&contents) {
-            validate_base_libretto(&libretto)?;
-            tracing::info!("Base libretto is valid");
-        } else if let Ok(overlay) = serde_json::from_str::<TimingOverlay>(&contents) {
-            validate_timing_overlay_standalone(&overlay)?;
-            tracing::info!("Timing overlay is valid (standalone, no base libretto cross-check)");
+/// Which kind of model document a JSON file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    BaseLibretto,
+    TimingOverlay,
+    AnnotationOverlay,
+    PronunciationOverlay,
+    SynopsisOverlay,
+    Interchange,
+}
+
+impl DocumentKind {
+    /// Classify a JSON document by its distinguishing top-level keys.
+    ///
+    /// Classification is by shape rather than by a full parse, so a file
+    /// with a type error deep inside is still recognized and the error can
+    /// be reported with its JSON path. `Ok(None)` for JSON that is no model
+    /// document; an error, with its line and column, for text that is not
+    /// JSON at all.
+    pub fn detect(contents: &str) -> Result<Option<Self>, serde_json::Error> {
+        let value: serde_json::Value = serde_json::from_str(contents)?;
+        let Some(obj) = value.as_object() else {
+            return Ok(None);
+        };
+        Ok(if obj.contains_key("track_timings") {
+            Some(DocumentKind::TimingOverlay)
+        } else if obj.contains_key("annotations") && obj.contains_key("base_libretto") {
+            Some(DocumentKind::AnnotationOverlay)
+        } else if obj.contains_key("pronunciations") && obj.contains_key("base_libretto") {
+            Some(DocumentKind::PronunciationOverlay)
+        } else if obj.contains_key("synopses") && obj.contains_key("base_libretto") {
+            Some(DocumentKind::SynopsisOverlay)
+        } else if obj.contains_key("tracks") {
+            Some(DocumentKind::Interchange)
+        } else if obj.contains_key("numbers") {
+            Some(DocumentKind::BaseLibretto)
         } else {
-            anyhow::bail!("File does not parse as a base libretto or timing overlay");
+            None
+        })
+    }
+}
+
+/// An already-parsed document, for validating without touching the filesystem.
+#[derive(Debug, Clone, Copy)]
+pub enum Document<'a> {
+    BaseLibretto(&'a BaseLibretto),
+    TimingOverlay(&'a TimingOverlay),
+    AnnotationOverlay(&'a AnnotationOverlay),
+    PronunciationOverlay(&'a PronunciationOverlay),
+    SynopsisOverlay(&'a SynopsisOverlay),
+    Interchange(&'a InterchangeLibretto),
+}
+
+impl Document<'_> {
+    pub fn kind(&self) -> DocumentKind {
+        match self {
+            Document::BaseLibretto(_) => DocumentKind::BaseLibretto,
+            Document::TimingOverlay(_) => DocumentKind::TimingOverlay,
+            Document::AnnotationOverlay(_) => DocumentKind::AnnotationOverlay,
+            Document::PronunciationOverlay(_) => DocumentKind::PronunciationOverlay,
+            Document::SynopsisOverlay(_) => DocumentKind::SynopsisOverlay,
+            Document::Interchange(_) => DocumentKind::Interchange,
         }
     }
+}
 
+/// Validate an already-parsed document and summarize the result.
+///
+/// This is the entry point for library consumers: it does no I/O and no
+/// logging. Overlays are cross-checked against `base` when it is given,
+/// and checked standalone otherwise. Checks that depend on where the
+/// document lives on disk (its base libretto reference) are left to
+/// `validate_file`. The report's `file` is empty.
+pub fn check_document(
+    document: Document<'_>,
+    base: Option<&BaseLibretto>,
+    config: &LintConfig,
+    strict: bool,
+) -> Result<ValidationReport> {
+    let mut coverage = None;
+    let mut tracks = Vec::new();
+    let errors = match (document, base) {
+        (Document::BaseLibretto(libretto), _) => validate_base_libretto(libretto)?,
+        (Document::Interchange(doc), _) => {
+            tracks = gaps::analyze(doc);
+            let mut errors = validate_interchange(doc)?;
+            errors.extend(check_gaps(&tracks, &config.timing));
+            errors
+        }
+        (Document::TimingOverlay(overlay), Some(base)) => {
+            coverage = Some(CoverageReport::compute(overlay, base));
+            let mut errors = validate_timing_overlay(overlay, base)?;
+            errors.extend(check_speech_rate(overlay, base, &config.timing));
+            errors
+        }
+        (Document::TimingOverlay(overlay), None) => validate_timing_overlay_standalone(overlay)?,
+        (Document::AnnotationOverlay(annotations), Some(base)) => validate_annotation_overlay(annotations, base)?,
+        (Document::AnnotationOverlay(_), None) => Vec::new(),
+        (Document::PronunciationOverlay(pronunciations), Some(base)) => {
+            validate_pronunciation_overlay(pronunciations, base)?
+        }
+        (Document::PronunciationOverlay(_), None) => Vec::new(),
+        (Document::SynopsisOverlay(synopses), Some(base)) => validate_synopsis_overlay(synopses, base)?,
+        (Document::SynopsisOverlay(_), None) => Vec::new(),
+    };
+
+    let findings = config.apply(errors);
+    let mut report = ValidationReport::new("", document.kind(), &findings, strict);
+    report.coverage = coverage;
+    report.tracks = tracks;
+    report.stats = stats::DocumentStats::compute(document);
+    Ok(report)
+}
+
+/// Options for validating a file.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidateOptions {
+    /// Leave out list entries that fail to load (a malformed track or
+    /// segment time), reporting each as a `skipped-entry` finding, and
+    /// validate the rest of the document.
+    pub lenient: bool,
+}
+
+/// Validate a base libretto, timing overlay, annotation, pronunciation or synopsis overlay, or interchange file.
+///
+/// If `base_path` is provided and the file is an overlay, segment ID
+/// references are checked against the base libretto. Findings are
+/// assigned severities from `config`; the report fails if any finding is
+/// an error, or any finding at all when `strict` is set.
+pub fn validate_file(
+    file_path: &str,
+    base_path: Option<&str>,
+    config: &LintConfig,
+    strict: bool,
+) -> Result<ValidationReport> {
+    validate_file_with(file_path, base_path, config, strict, &ValidateOptions::default())
+}
+
+/// Validate a file as `validate_file` does, with `options`.
+pub fn validate_file_with(
+    file_path: &str,
+    base_path: Option<&str>,
+    config: &LintConfig,
+    strict: bool,
+    options: &ValidateOptions,
+) -> Result<ValidationReport> {
+    // Binary-encoded files can only be interchange documents
+    if Encoding::from_path(Path::new(file_path)) != Encoding::Json {
+        let doc = encoding::read_interchange(Path::new(file_path))?;
+        let mut report = check_document(Document::Interchange(&doc), None, config, strict)?;
+        report.file = file_path.to_string();
+        return Ok(report);
+    }
+
+    let contents = std::fs::read_to_string(file_path)
+        .with_context(|| format!("Failed to read {file_path}"))?;
+    let kind = DocumentKind::detect(&contents)
+        .with_context(|| format!("{file_path} is not valid JSON"))?
+        .ok_or_else(|| anyhow::anyhow!(
+        "{file_path}: not a base libretto, timing overlay, annotation, pronunciation or synopsis overlay, or interchange document"
+    ))?;
+    let base_libretto: Option<BaseLibretto> = base_path.map(io::load_json).transpose()?;
+    let base = base_libretto.as_ref();
+
+    let mut skipped = Vec::new();
+    let mut report = match kind {
+        DocumentKind::Interchange => {
+            let doc: InterchangeLibretto = load_document(&contents, file_path, options, &mut skipped)?;
+            check_document(Document::Interchange(&doc), base, config, strict)?
+        }
+        DocumentKind::BaseLibretto => {
+            let libretto: BaseLibretto = load_document(&contents, file_path, options, &mut skipped)?;
+            check_document(Document::BaseLibretto(&libretto), base, config, strict)?
+        }
+        DocumentKind::TimingOverlay => {
+            let overlay: TimingOverlay = load_document(&contents, file_path, options, &mut skipped)?;
+            if base.is_none() {
+                tracing::info!("Validating timing overlay standalone (no base libretto cross-check)");
+            }
+            let mut report = check_document(Document::TimingOverlay(&overlay), base, config, strict)?;
+            report.add_findings(&config.apply(check_base_reference(&overlay, Path::new(file_path), base)));
+            report
+        }
+        DocumentKind::AnnotationOverlay => {
+            let annotations: AnnotationOverlay = load_document(&contents, file_path, options, &mut skipped)?;
+            if base.is_none() {
+                tracing::info!("Annotation overlay parses (pass --base to check its references)");
+            }
+            check_document(Document::AnnotationOverlay(&annotations), base, config, strict)?
+        }
+        DocumentKind::PronunciationOverlay => {
+            let pronunciations: PronunciationOverlay = load_document(&contents, file_path, options, &mut skipped)?;
+            if base.is_none() {
+                tracing::info!("Pronunciation overlay parses (pass --base to check its references)");
+            }
+            check_document(Document::PronunciationOverlay(&pronunciations), base, config, strict)?
+        }
+        DocumentKind::SynopsisOverlay => {
+            let synopses: SynopsisOverlay = load_document(&contents, file_path, options, &mut skipped)?;
+            if base.is_none() {
+                tracing::info!("Synopsis overlay parses (pass --base to check its references)");
+            }
+            check_document(Document::SynopsisOverlay(&synopses), base, config, strict)?
+        }
+    };
+    report.add_findings(&config.apply(skipped));
+    report.file = file_path.to_string();
+    report.base = base_path.map(String::from);
+    Ok(report)
+}
+
+/// Deserialize `contents`, leniently with `options.lenient`, adding the
+/// entries left out to `skipped`.
+fn load_document<T: DeserializeOwned>(
+    contents: &str,
+    file_path: &str,
+    options: &ValidateOptions,
+    skipped: &mut Vec<ValidationError>,
+) -> Result<T> {
+    if !options.lenient {
+        return Ok(io::from_json_str(contents, file_path)?);
+    }
+    let loaded = io::from_json_str_lenient(contents, file_path)?;
+    skipped.extend(loaded.skipped.into_iter().map(ValidationError::SkippedEntry));
+    Ok(loaded.document)
+}
+
+/// Check a timing overlay's track durations against the audio files in `audio_dir`.
+///
+/// Findings are added to `report`, which must be for a timing overlay.
+pub fn validate_audio(
+    report: &mut ValidationReport,
+    audio_dir: &Path,
+    tolerance: f64,
+    config: &LintConfig,
+) -> Result<()> {
+    if report.kind != DocumentKind::TimingOverlay {
+        anyhow::bail!("{}: audio checks apply only to timing overlays", report.file);
+    }
+    // The report has any entries a lenient validation left out already
+    let overlay: TimingOverlay = io::load_json_lenient(&report.file)?.document;
+    let files = audio::scan_audio_dir(audio_dir)?;
+    tracing::info!(files = files.len(), dir = %audio_dir.display(), "Probed audio files");
+    let findings = config.apply(audio::check_audio_durations(&overlay, &files, tolerance));
+    report.add_findings(&findings);
     Ok(())
 }
 
+/// Validate a file and log the findings, failing if the report fails.
+///
+/// See `validate_file` for a version that returns the report instead.
+pub fn validate(file_path: &str, base_path: Option<&str>, config: &LintConfig, strict: bool) -> Result<()> {
+    let report = validate_file(file_path, base_path, config, strict)?;
+    log_report(&report);
+    if !report.passed {
+        anyhow::bail!("{}", report.summary());
+    }
+    Ok(())
+}
+
+/// Log a report's findings at the tracing level matching their severity.
+pub fn log_report(report: &ValidationReport) {
+    for f in &report.findings {
+        match f.severity {
+            Severity::Error => tracing::error!(rule = f.rule, "{}", f.message),
+            Severity::Warning => tracing::warn!(rule = f.rule, "{}", f.message),
+        }
+    }
+    if let Some(coverage) = &report.coverage {
+        tracing::info!(
+            total = coverage.total,
+            covered = coverage.covered,
+            omitted = coverage.omitted,
+            unaccounted = coverage.unaccounted,
+            "Number coverage"
+        );
+        tracing::info!(
+            total = coverage.segments.total,
+            timed = coverage.segments.timed,
+            untimed = coverage.segments.untimed,
+            omitted = coverage.segments.omitted,
+            "Segment coverage"
+        );
+    }
+    for track in &report.tracks {
+        let longest = track.longest_gap().map_or(0.0, gaps::Gap::seconds);
+        let coverage = track.coverage.map(|c| format!("{c:.1}%")).unwrap_or_else(|| "-".to_string());
+        if track.gaps.is_empty() && track.overlaps.is_empty() {
+            tracing::debug!(track = track.track_id, coverage, "Track coverage");
+        } else {
+            tracing::info!(
+                track = track.track_id,
+                coverage,
+                gaps = track.gaps.len(),
+                gap_seconds = format!("{:.1}", track.gap_seconds()),
+                longest_gap = format!("{longest:.1}"),
+                overlaps = track.overlaps.len(),
+                "Track coverage"
+            );
+        }
+    }
+    if report.passed {
+        tracing::info!(warnings = report.warnings, "{:?} is valid", report.kind);
+    }
+}
+
+/// Log each finding at the tracing level matching its severity.
+pub fn log_findings(findings: &[Finding]) {
+    for f in findings {
+        match f.severity {
+            Severity::Error => tracing::error!(rule = f.error.rule(), "{}", f.error),
+            Severity::Warning => tracing::warn!(rule = f.error.rule(), "{}", f.error),
+        }
+    }
+}
+
 /// Validate a base libretto for internal consistency.
 pub fn validate_base_libretto(libretto: &BaseLibretto) -> Result<Vec<ValidationError>> {
     let mut errors = Vec::new();
@@ -94,15 +613,258 @@ pub fn validate_base_libretto(libretto: &BaseLibretto) -> Result<Vec<ValidationE
         }
     }
 
-    if !errors.is_empty() {
-        for e in &errors {
-            tracing::warn!("{e}");
+    errors.extend(check_act_order(libretto));
+    errors.extend(check_character_names(libretto));
+    errors.extend(check_cast_usage(libretto));
+    errors.extend(check_text_artifacts(libretto));
+
+    // Translations are rarely public domain, so their license must be
+    // stated: as `translation_license`, or as `license` covering both
+    let translation_licensed = libretto.rights.as_ref()
+        .is_some_and(|r| r.translation_license.is_some() || r.license.is_some());
+    if libretto.has_translations() && !translation_licensed {
+        errors.push(ValidationError::UnlicensedTranslation);
+    }
+
+    // In a bilingual libretto, a number with no translation at all usually failed to align
+    if libretto.has_translations() {
+        for number in stats::TranslationCoverage::compute(libretto).untranslated_numbers() {
+            errors.push(ValidationError::UntranslatedNumber(number.id.clone()));
         }
     }
 
     Ok(errors)
 }
 
+/// Parse an act label ("2", "II", "Act 3") to its ordinal, if it has one.
+fn act_ordinal(act: &str) -> Option<u32> {
+    let act = act.trim();
+    let word = act.rsplit(char::is_whitespace).next().unwrap_or(act);
+    if let Ok(n) = word.parse() {
+        return Some(n);
+    }
+    let value = |c: char| match c.to_ascii_uppercase() {
+        'I' => Some(1),
+        'V' => Some(5),
+        'X' => Some(10),
+        _ => None,
+    };
+    let digits: Option<Vec<u32>> = word.chars().map(value).collect();
+    let digits = digits.filter(|d| !d.is_empty())?;
+    let total = digits.iter().enumerate()
+        .map(|(i, &d)| if digits.get(i + 1).is_some_and(|&next| next > d) { -(d as i64) } else { d as i64 })
+        .sum::<i64>();
+    u32::try_from(total).ok()
+}
+
+/// Check that numbers run through the acts in order, one act at a time.
+///
+/// Acts that reappear after another act are flagged; acts with numeric
+/// or Roman-numeral labels must also never decrease. Numbers without an
+/// act are skipped.
+pub fn check_act_order(libretto: &BaseLibretto) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut finished: HashSet<&str> = HashSet::new();
+    let mut previous: Option<&str> = None;
+    for number in &libretto.numbers {
+        let act = number.act.trim();
+        if act.is_empty() {
+            continue;
+        }
+        let Some(prev) = previous.filter(|&p| p != act) else {
+            previous = Some(act);
+            continue;
+        };
+        finished.insert(prev);
+        let decreased = matches!(
+            (act_ordinal(act), act_ordinal(prev)),
+            (Some(a), Some(p)) if a < p
+        );
+        if decreased {
+            errors.push(ValidationError::ActOutOfOrder {
+                number_id: number.id.clone(),
+                act: act.to_string(),
+                previous_act: prev.to_string(),
+            });
+        } else if finished.contains(act) {
+            errors.push(ValidationError::ActNotContiguous {
+                act: act.to_string(),
+                number_id: number.id.clone(),
+            });
+        }
+        previous = Some(act);
+    }
+    errors
+}
+
+/// Flag HTML entities, mojibake, replacement characters, and control
+/// characters in segment text (see `libretto_model::artifacts`).
+///
+/// Each field is reported once, for its first artifact.
+pub fn check_text_artifacts(libretto: &BaseLibretto) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    for segment in libretto.numbers.iter().flat_map(|n| &n.segments) {
+        let fields = [
+            ("character", &segment.character),
+            ("text", &segment.text),
+            ("translation", &segment.translation),
+            ("direction", &segment.direction),
+        ];
+        for (field, value) in fields {
+            let Some(value) = value else { continue };
+            if let Some(artifact) = artifacts::find_artifacts(value).first() {
+                errors.push(ValidationError::SuspiciousText {
+                    segment_id: segment.id.clone(),
+                    field,
+                    artifact: artifact.to_string(),
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Why a segment attribution does not look like a character name, if it doesn't.
+///
+/// Catches the typical false positives of ALL-CAPS name detection during
+/// acquisition: sung lines, headings, and sentences taken for names.
+fn character_name_problem(name: &str) -> Option<&'static str> {
+    let trimmed = name.trim();
+    let upper = trimmed.to_uppercase();
+    if HEADING_PREFIXES.iter().any(|p| upper.starts_with(p)) {
+        return Some("act or scene heading");
+    }
+    if trimmed.ends_with(['.', '!', '?', ';', ':']) {
+        return Some("ends like a sentence");
+    }
+    let lowercase_word = trimmed.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphabetic()))
+        .any(|w| w.chars().next().is_some_and(char::is_lowercase) && !NAME_PARTICLES.contains(&w));
+    if lowercase_word {
+        return Some("contains lowercase words");
+    }
+    if split_character_attribution(trimmed).iter().any(|n| n.split(' ').count() > MAX_CHARACTER_NAME_WORDS) {
+        return Some("too long for a name");
+    }
+    None
+}
+
+/// Flag segment attributions that are probably misclassified text.
+pub fn check_character_names(libretto: &BaseLibretto) -> Vec<ValidationError> {
+    libretto.numbers.iter()
+        .flat_map(|n| &n.segments)
+        .filter_map(|segment| {
+            let name = segment.character.as_deref()?;
+            character_name_problem(name).map(|reason| ValidationError::SuspiciousCharacterName {
+                segment_id: segment.id.clone(),
+                name: name.to_string(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Check segment attributions against the cast list.
+///
+/// Every character named in a segment attribution must resolve to a cast
+/// member (see `CastMember::matches_name`), apart from generic ensemble
+/// names like "TUTTI" or "CORO". Cast members never attributed a segment
+/// are flagged too; both usually mean the parser took a text line for a
+/// character name. Librettos without a cast list are not checked.
+pub fn check_cast_usage(libretto: &BaseLibretto) -> Vec<ValidationError> {
+    if libretto.cast.is_empty() {
+        return Vec::new();
+    }
+
+    let mut errors = Vec::new();
+    let mut used = vec![false; libretto.cast.len()];
+    // Unknown name → (first segment, occurrences), in order of first appearance
+    let mut unknown: Vec<(String, String, usize)> = Vec::new();
+    for segment in libretto.numbers.iter().flat_map(|n| &n.segments) {
+        let Some(attribution) = &segment.character else { continue };
+        for name in split_character_attribution(attribution) {
+            match libretto.cast.iter().position(|c| c.matches_name(&name)) {
+                Some(i) => used[i] = true,
+                None if ENSEMBLE_ATTRIBUTIONS.contains(&name.as_str()) => {}
+                None => match unknown.iter_mut().find(|(n, ..)| *n == name) {
+                    Some((_, _, count)) => *count += 1,
+                    None => unknown.push((name, segment.id.clone(), 1)),
+                },
+            }
+        }
+    }
+
+    for (name, first_segment, occurrences) in unknown {
+        errors.push(ValidationError::UnknownCharacter { name, first_segment, occurrences });
+    }
+    for (member, used) in libretto.cast.iter().zip(used) {
+        if !used {
+            errors.push(ValidationError::SilentCastMember(member.character.clone()));
+        }
+    }
+    errors
+}
+
+/// Check a timing overlay's `base_libretto` reference on disk.
+///
+/// The reference must resolve (see `library::locate_reference`) from the
+/// overlay at `overlay_path`. If it does, the file must match a recorded
+/// `base_sha256`, and at least one of the overlay's numbers must exist in
+/// it. When the overlay is being checked against an explicit `base`, that
+/// base must be the same opera as the declared one.
+pub fn check_base_reference(
+    overlay: &TimingOverlay,
+    overlay_path: &Path,
+    base: Option<&BaseLibretto>,
+) -> Vec<ValidationError> {
+    let reference = &overlay.base_libretto;
+    let Some(path) = library::locate_reference(overlay_path, reference) else {
+        return vec![ValidationError::MissingBaseLibretto(reference.clone())];
+    };
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => return vec![ValidationError::Other(format!("{}: {e}", path.display()))],
+    };
+
+    let mut errors = Vec::new();
+    if let Some(expected) = &overlay.base_sha256 {
+        let actual = library::sha256_hex(&bytes);
+        if !actual.eq_ignore_ascii_case(expected) {
+            errors.push(ValidationError::BaseHashMismatch {
+                reference: reference.clone(),
+                expected: expected.clone(),
+                actual,
+            });
+        }
+    }
+
+    let declared: BaseLibretto = match io::from_json_str(&String::from_utf8_lossy(&bytes), &path.display().to_string()) {
+        Ok(declared) => declared,
+        Err(e) => {
+            errors.push(ValidationError::Other(e.to_string()));
+            return errors;
+        }
+    };
+
+    let number_ids = overlay.covered_number_ids();
+    if !number_ids.is_empty() && !number_ids.iter().any(|id| declared.find_number(id).is_some()) {
+        errors.push(ValidationError::ForeignBaseReference(reference.clone()));
+    }
+
+    let describe = |b: &BaseLibretto| format!("{} ({})", b.opera.title, b.opera.composer);
+    if let Some(base) = base {
+        let same = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+        if !same(&base.opera.title, &declared.opera.title) || !same(&base.opera.composer, &declared.opera.composer) {
+            errors.push(ValidationError::BaseOperaMismatch {
+                reference: reference.clone(),
+                declared: describe(&declared),
+                checked: describe(base),
+            });
+        }
+    }
+    errors
+}
+
 /// Validate a timing overlay against a base libretto.
 pub fn validate_timing_overlay(
     overlay: &TimingOverlay,
@@ -145,37 +907,383 @@ pub fn validate_timing_overlay(
         errors.push(ValidationError::UnaccountedNumber(id.to_string()));
     }
 
-    // Log coverage summary
-    let coverage = CoverageReport {
-        total: base_number_ids.len(),
-        covered: covered.len(),
-        omitted: omitted.len(),
-        unaccounted: unaccounted.len(),
-    };
-    tracing::info!(
-        total = coverage.total,
-        covered = coverage.covered,
-        omitted = coverage.omitted,
-        unaccounted = coverage.unaccounted,
-        "Number coverage"
-    );
+    // Numbers with some but not all segments timed
+    let coverage = CoverageReport::compute(overlay, base);
+    for number in &coverage.by_number {
+        if number.timed > 0 && number.untimed > 0 {
+            errors.push(ValidationError::PartiallyTimedNumber {
+                number_id: number.number_id.clone(),
+                timed: number.timed,
+                segments: number.segments,
+            });
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Flag segments whose implied speech rate falls outside `bounds`.
+///
+/// Each timed segment's words are assumed to be delivered between its own
+/// start and the next segment's start (or the end of the track, for the
+/// last segment when the duration is known). Segments without words, such
+/// as directions and interludes, are not checked, and non-positive
+/// intervals are left to the ordering checks.
+pub fn check_speech_rate(
+    overlay: &TimingOverlay,
+    base: &BaseLibretto,
+    bounds: &TimingBounds,
+) -> Vec<ValidationError> {
+    let words: HashMap<&str, usize> = base.numbers.iter()
+        .flat_map(|n| &n.segments)
+        .map(|s| (s.id.as_str(), s.text.as_deref().map_or(0, |t| t.split_whitespace().count())))
+        .collect();
+
+    let mut errors = Vec::new();
+    for track in &overlay.track_timings {
+        let ends = track.segment_times.iter()
+            .skip(1)
+            .map(|st| Some(st.start))
+            .chain(std::iter::once(track.duration_seconds));
+        for (st, end) in track.segment_times.iter().zip(ends) {
+            let count = words.get(st.segment_id.as_str()).copied().unwrap_or(0);
+            let Some(end) = end else { continue };
+            let interval = end - st.start;
+            if count == 0 || interval <= 0.0 {
+                continue;
+            }
+            let rate = count as f64 / interval;
+            if rate < bounds.min_words_per_second || rate > bounds.max_words_per_second {
+                errors.push(ValidationError::ImplausibleSpeechRate {
+                    track: track.track_title.clone(),
+                    segment_id: st.segment_id.clone(),
+                    words: count,
+                    interval,
+                    rate,
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Validate an interchange document, e.g. one produced by another tool.
+///
+/// Checks required opera fields, unique track IDs, and within each track:
+/// finite, non-negative, ordered, non-overlapping segment times, `end >= start`,
+/// and starts within the track duration. Segments sharing an ensemble
+/// `group` may overlap, since they are sung simultaneously.
+pub fn validate_interchange(doc: &InterchangeLibretto) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    if doc.opera.title.is_empty() {
+        errors.push(ValidationError::MissingField("opera.title".into()));
+    }
+    if doc.opera.composer.is_empty() {
+        errors.push(ValidationError::MissingField("opera.composer".into()));
+    }
+    if doc.opera.language.is_empty() {
+        errors.push(ValidationError::MissingField("opera.language".into()));
+    }
+
+    let mut seen_tracks = HashSet::new();
+    for track in &doc.tracks {
+        if !seen_tracks.insert(track.track_id.as_str()) {
+            errors.push(ValidationError::DuplicateTrackId(track.track_id.clone()));
+        }
+        if track.segments.is_empty() {
+            errors.push(ValidationError::EmptyTrack(track.track_id.clone()));
+            continue;
+        }
+
+        let label = |i: usize| match &track.segments[i].segment_id {
+            Some(id) => format!("'{id}'"),
+            None => format!("#{}", i + 1),
+        };
+
+        // Untimed segments (from `merge --include-untimed`) have no start to
+        // check; the timed ones around them must still be in order.
+        let timed: Vec<usize> = (0..track.segments.len())
+            .filter(|&i| !track.segments[i].untimed)
+            .collect();
+        let mut ordered = true;
+        for (k, &i) in timed.iter().enumerate() {
+            let seg = &track.segments[i];
+            if !seg.start.is_finite() {
+                errors.push(ValidationError::NonFiniteTime {
+                    track: track.track_id.clone(),
+                    segment: label(i),
+                    start: seg.start,
+                });
+                continue;
+            }
+            if seg.start < 0.0 {
+                errors.push(ValidationError::NegativeTime(seg.start));
+            }
+            if let Some(end) = seg.end {
+                if end < seg.start {
+                    errors.push(ValidationError::EndBeforeStart {
+                        track: track.track_id.clone(),
+                        segment: label(i),
+                        start: seg.start,
+                        end,
+                    });
+                }
+            }
+            if let Some(next) = timed.get(k + 1).map(|&n| &track.segments[n]) {
+                if next.start < seg.start {
+                    ordered = false;
+                }
+                let same_group = seg.group.is_some() && seg.group == next.group;
+                if let Some(end) = seg.end {
+                    if end > next.start && !same_group {
+                        errors.push(ValidationError::OverlappingSegments {
+                            track: track.track_id.clone(),
+                            segment: label(i),
+                            end,
+                            next_start: next.start,
+                        });
+                    }
+                }
+            }
+            if let Some(duration) = track.duration_seconds {
+                if seg.start >= duration {
+                    errors.push(ValidationError::SegmentBeyondDuration {
+                        track: track.track_id.clone(),
+                        segment_id: label(i),
+                        start: seg.start,
+                        duration,
+                    });
+                }
+            }
+        }
+        if !ordered {
+            errors.push(ValidationError::SegmentsUnordered(track.track_id.clone()));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Flag gaps between segments longer than `bounds.max_gap_seconds`, where
+/// a display client would show nothing for a noticeable while. A gap
+/// before the first segment of another number is left alone: the music
+/// there is an introduction or an instrumental number, and long ones are
+/// common.
+pub fn check_gaps(tracks: &[gaps::TrackGaps], bounds: &TimingBounds) -> Vec<ValidationError> {
+    tracks.iter()
+        .flat_map(|track| track.gaps.iter().map(move |gap| (track, gap)))
+        .filter(|(_, gap)| !gap.between_numbers && gap.seconds() > bounds.max_gap_seconds)
+        .map(|(track, gap)| ValidationError::LongGap {
+            track: track.track_id.clone(),
+            after: gap.after.clone(),
+            before: gap.before.clone(),
+            start: gap.start,
+            end: gap.end,
+            seconds: gap.seconds(),
+        })
+        .collect()
+}
+
+/// Validate an annotation overlay against a base libretto.
+///
+/// Each annotation must target exactly one existing segment or number
+/// and carry non-empty text.
+pub fn validate_annotation_overlay(
+    overlay: &AnnotationOverlay,
+    base: &BaseLibretto,
+) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    let base_seg_ids: HashSet<&str> = base.segment_ids().into_iter().collect();
+    let base_number_ids: HashSet<&str> = base.numbers.iter().map(|n| n.id.as_str()).collect();
 
-    if !errors.is_empty() {
-        for e in &errors {
-            tracing::warn!("{e}");
+    for (i, annotation) in overlay.annotations.iter().enumerate() {
+        match (&annotation.segment_id, &annotation.number_id) {
+            (Some(sid), None) => {
+                if !base_seg_ids.contains(sid.as_str()) {
+                    errors.push(ValidationError::UnknownAnnotationTarget("segment", sid.clone()));
+                }
+            }
+            (None, Some(nid)) => {
+                if !base_number_ids.contains(nid.as_str()) {
+                    errors.push(ValidationError::UnknownAnnotationTarget("number", nid.clone()));
+                }
+            }
+            (Some(_), Some(_)) => errors.push(ValidationError::Other(format!(
+                "annotations[{i}] sets both segment_id and number_id"
+            ))),
+            (None, None) => errors.push(ValidationError::MissingField(format!(
+                "annotations[{i}].segment_id or number_id"
+            ))),
+        }
+        if annotation.text.trim().is_empty() {
+            errors.push(ValidationError::MissingField(format!("annotations[{i}].text")));
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Validate a pronunciation overlay against a base libretto.
+///
+/// Each pronunciation must name an existing segment with text, and a line
+/// within that text; a segment is pronounced either whole or line by line,
+/// each line once.
+pub fn validate_pronunciation_overlay(
+    overlay: &PronunciationOverlay,
+    base: &BaseLibretto,
+) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+
+    let lines: HashMap<&str, usize> = base.numbers.iter()
+        .flat_map(|n| &n.segments)
+        .map(|s| (s.id.as_str(), s.text.as_deref().map_or(0, |t| t.lines().count())))
+        .collect();
+    let mut pronounced: HashMap<&str, Vec<Option<usize>>> = HashMap::new();
+    let mut duplicated = HashSet::new();
+
+    for (i, pronunciation) in overlay.pronunciations.iter().enumerate() {
+        let sid = pronunciation.segment_id.as_str();
+        if pronunciation.text.trim().is_empty() {
+            errors.push(ValidationError::MissingField(format!("pronunciations[{i}].text")));
+        }
+        let Some(&count) = lines.get(sid) else {
+            errors.push(ValidationError::UnknownPronunciationSegment(sid.to_string()));
+            continue;
+        };
+        match pronunciation.line {
+            _ if count == 0 => errors.push(ValidationError::PronunciationWithoutText(sid.to_string())),
+            Some(line) if !(1..=count).contains(&line) => {
+                errors.push(ValidationError::PronunciationLineOutOfRange {
+                    segment_id: sid.to_string(),
+                    line,
+                    lines: count,
+                });
+            }
+            _ => {}
         }
+        let seen = pronounced.entry(sid).or_default();
+        let clash = seen.iter().any(|&l| l.is_none() || pronunciation.line.is_none() || l == pronunciation.line);
+        if clash && duplicated.insert(sid) {
+            errors.push(ValidationError::DuplicatePronunciation(sid.to_string()));
+        }
+        seen.push(pronunciation.line);
     }
 
     Ok(errors)
 }
 
+/// Validate a synopsis overlay against a base libretto.
+///
+/// Every synopsis must be of an existing number and have text.
+pub fn validate_synopsis_overlay(overlay: &SynopsisOverlay, base: &BaseLibretto) -> Result<Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    for (number_id, synopsis) in &overlay.synopses {
+        if base.find_number(number_id).is_none() {
+            errors.push(ValidationError::UnknownSynopsisNumber(number_id.clone()));
+        }
+        if synopsis.trim().is_empty() {
+            errors.push(ValidationError::MissingField(format!("synopses.{number_id}")));
+        }
+    }
+    Ok(errors)
+}
+
 /// Summary of how well a timing overlay covers the base libretto.
-#[derive(Debug, Clone)]
+///
+/// `total`/`covered`/`omitted`/`unaccounted` count musical numbers;
+/// `segments` and `by_number` break coverage down to individual segments,
+/// since a number listed on a track may still have untimed segments.
+#[derive(Debug, Clone, Serialize)]
 pub struct CoverageReport {
     pub total: usize,
     pub covered: usize,
     pub omitted: usize,
     pub unaccounted: usize,
+    pub segments: SegmentCoverage,
+    pub by_number: Vec<NumberCoverage>,
+}
+
+/// Segment counts across the whole base libretto.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SegmentCoverage {
+    pub total: usize,
+    /// Segments with a start time somewhere in the overlay.
+    pub timed: usize,
+    /// Segments without a start time in numbers that are not omitted.
+    pub untimed: usize,
+    /// Untimed segments in numbers declared omitted, or cut by a track's
+    /// `skip_segment_ids`.
+    pub omitted: usize,
+}
+
+/// Segment coverage for one musical number.
+#[derive(Debug, Clone, Serialize)]
+pub struct NumberCoverage {
+    pub number_id: String,
+    pub segments: usize,
+    pub timed: usize,
+    pub untimed: usize,
+    pub omitted: usize,
+}
+
+impl CoverageReport {
+    /// Count base numbers covered by a track, declared omitted, or neither.
+    ///
+    /// Covered and omitted counts only include numbers that exist in the base.
+    pub fn compute(overlay: &TimingOverlay, base: &BaseLibretto) -> Self {
+        let base_number_ids: HashSet<&str> = base.numbers.iter().map(|n| n.id.as_str()).collect();
+        let covered: HashSet<&str> = overlay.covered_number_ids().into_iter()
+            .filter(|id| base_number_ids.contains(id))
+            .collect();
+        let omitted: HashSet<&str> = overlay.omitted_number_ids().into_iter()
+            .filter(|id| base_number_ids.contains(id))
+            .collect();
+        let accounted = covered.union(&omitted).count();
+
+        let timed_ids: HashSet<&str> = overlay.segment_ids().into_iter().collect();
+        let cut_ids: HashSet<&str> = overlay.track_timings.iter()
+            .flat_map(|t| t.skip_segment_ids.iter().map(String::as_str))
+            .collect();
+        let mut segments = SegmentCoverage::default();
+        let by_number: Vec<NumberCoverage> = base.numbers.iter()
+            .map(|number| {
+                let count = number.segments.len();
+                let timed = number.segments.iter()
+                    .filter(|s| timed_ids.contains(s.id.as_str()))
+                    .count();
+                let (untimed, omitted_count) = if omitted.contains(number.id.as_str()) {
+                    (0, count - timed)
+                } else {
+                    let cut = number.segments.iter()
+                        .filter(|s| cut_ids.contains(s.id.as_str()) && !timed_ids.contains(s.id.as_str()))
+                        .count();
+                    (count - timed - cut, cut)
+                };
+                segments.total += count;
+                segments.timed += timed;
+                segments.untimed += untimed;
+                segments.omitted += omitted_count;
+                NumberCoverage {
+                    number_id: number.id.clone(),
+                    segments: count,
+                    timed,
+                    untimed,
+                    omitted: omitted_count,
+                }
+            })
+            .collect();
+
+        CoverageReport {
+            total: base_number_ids.len(),
+            covered: covered.len(),
+            omitted: omitted.len(),
+            unaccounted: base_number_ids.len() - accounted,
+            segments,
+            by_number,
+        }
+    }
 }
 
 /// Validate a timing overlay for internal consistency (without a base libretto).
@@ -184,7 +1292,49 @@ pub fn validate_timing_overlay_standalone(
 ) -> Result<Vec<ValidationError>> {
     let mut errors = Vec::new();
 
+    // Numbered tracks must be in strictly increasing (disc, track) order
+    let mut previous: Option<(&str, u32, u32)> = None;
+    for track in &overlay.track_timings {
+        let Some(number) = track.track_number else { continue };
+        let disc = track.disc_number.unwrap_or(1);
+        if let Some((previous_track, previous_disc, previous_number)) = previous {
+            if (disc, number) <= (previous_disc, previous_number) {
+                errors.push(ValidationError::TrackOutOfOrder {
+                    track: track.track_title.clone(),
+                    disc,
+                    number,
+                    previous_track: previous_track.to_string(),
+                    previous_disc,
+                    previous_number,
+                });
+            }
+        }
+        previous = Some((&track.track_title, disc, number));
+    }
+
+    // segment ID → (track title, 1-based position) of its first reference
+    let mut first_reference: HashMap<&str, (&str, usize)> = HashMap::new();
+
     for track in &overlay.track_timings {
+        // Check each segment is referenced only once across the overlay
+        for (i, st) in track.segment_times.iter().enumerate() {
+            let position = i + 1;
+            match first_reference.get(st.segment_id.as_str()) {
+                Some(&(first_track, first_position)) => {
+                    errors.push(ValidationError::DuplicateSegmentReference {
+                        segment_id: st.segment_id.clone(),
+                        first_track: first_track.to_string(),
+                        first_position,
+                        track: track.track_title.clone(),
+                        position,
+                    });
+                }
+                None => {
+                    first_reference.insert(&st.segment_id, (&track.track_title, position));
+                }
+            }
+        }
+
         // Check segment times are ordered
         let mut prev_start = -1.0_f64;
         for st in &track.segment_times {
@@ -198,6 +1348,50 @@ pub fn validate_timing_overlay_standalone(
             }
             prev_start = st.start;
         }
+
+        // Explicit ends must follow their start and not run into the next segment
+        for (i, st) in track.segment_times.iter().enumerate() {
+            let Some(end) = st.end else { continue };
+            if end < st.start {
+                errors.push(ValidationError::EndBeforeStart {
+                    track: track.track_title.clone(),
+                    segment: st.segment_id.clone(),
+                    start: st.start,
+                    end,
+                });
+            } else if let Some(next) = track.segment_times.get(i + 1).filter(|n| end > n.start) {
+                errors.push(ValidationError::OverlappingSegments {
+                    track: track.track_title.clone(),
+                    segment: st.segment_id.clone(),
+                    end,
+                    next_start: next.start,
+                });
+            }
+        }
+
+        // Check starts lie within the track, leaving room for the last segment
+        if let Some(duration) = track.duration_seconds {
+            for st in &track.segment_times {
+                if st.start >= duration {
+                    errors.push(ValidationError::SegmentBeyondDuration {
+                        track: track.track_title.clone(),
+                        segment_id: st.segment_id.clone(),
+                        start: st.start,
+                        duration,
+                    });
+                }
+            }
+            if let Some(last) = track.segment_times.last() {
+                let tail = duration - last.start;
+                if tail > 0.0 && tail < MIN_FINAL_SEGMENT_SECONDS {
+                    errors.push(ValidationError::ShortFinalSegment {
+                        track: track.track_title.clone(),
+                        segment_id: last.segment_id.clone(),
+                        tail,
+                    });
+                }
+            }
+        }
     }
 
     Ok(errors)
@@ -247,6 +1441,30 @@ mod tests {
         libretto
     }
 
+    fn single_track_overlay(duration: Option<f64>, times: &[(&str, f64)]) -> TimingOverlay {
+        TimingOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "test".to_string(),
+            recording: RecordingMetadata {
+                conductor: None,
+                orchestra: None,
+                year: None,
+                label: None,
+                album_title: None,
+            },
+            track_timings: vec![TrackTiming {
+                track_title: "Track 1".to_string(),
+                duration_seconds: duration,
+                number_ids: vec!["no-1".to_string()],
+                segment_times: times.iter()
+                    .map(|&(id, start)| SegmentTime { segment_id: id.to_string(), start, end: None, source: None })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn test_valid_libretto() {
         let libretto = sample_libretto();
@@ -270,6 +1488,38 @@ mod tests {
         assert!(errors.iter().any(|e| matches!(e, ValidationError::MissingField(_))));
     }
 
+    #[test]
+    fn test_unlicensed_translation() {
+        let mut libretto = sample_libretto();
+        libretto.numbers[0].segments[0].translation = Some("Test translation".to_string());
+        let errors = validate_base_libretto(&libretto).unwrap();
+        assert!(errors.iter().any(|e| matches!(e, ValidationError::UnlicensedTranslation)));
+
+        // Attribution alone is not a license
+        libretto.rights = Some(Rights {
+            license: None,
+            attribution: Some("Translated by L. Da Ponte Society".to_string()),
+            source_terms: None,
+            translation_license: None,
+        });
+        let errors = validate_base_libretto(&libretto).unwrap();
+        assert!(errors.iter().any(|e| matches!(e, ValidationError::UnlicensedTranslation)));
+
+        // A license for the whole document covers the translation
+        libretto.rights.as_mut().unwrap().license = Some("CC-BY-SA-4.0".to_string());
+        let errors = validate_base_libretto(&libretto).unwrap();
+        assert!(errors.is_empty());
+
+        libretto.rights = Some(Rights {
+            license: None,
+            attribution: None,
+            source_terms: None,
+            translation_license: Some("CC-BY-4.0".to_string()),
+        });
+        let errors = validate_base_libretto(&libretto).unwrap();
+        assert!(errors.is_empty());
+    }
+
     #[test]
     fn test_overlay_unknown_segment() {
         let libretto = sample_libretto();
@@ -283,20 +1533,16 @@ mod tests {
                 label: None,
                 album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![TrackTiming {
                 track_title: "Track 1".to_string(),
-                disc_number: None,
-                track_number: None,
-                duration_seconds: None,
                 number_ids: vec!["no-1".to_string()],
-                start_segment_id: None,
                 segment_times: vec![
-                    SegmentTime { segment_id: "no-1-001".to_string(), start: 0.0 },
-                    SegmentTime { segment_id: "no-1-999".to_string(), start: 5.0 }, // unknown
+                    SegmentTime { segment_id: "no-1-001".to_string(), start: 0.0, end: None, source: None },
+                    SegmentTime { segment_id: "no-1-999".to_string(), start: 5.0, end: None, source: None }, // unknown
                 ],
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
         assert!(errors.iter().any(|e| matches!(e, ValidationError::UnknownSegmentId(_))));
@@ -314,20 +1560,15 @@ mod tests {
                 label: None,
                 album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![TrackTiming {
                 track_title: "Track 1".to_string(),
-                disc_number: None,
-                track_number: None,
-                duration_seconds: None,
-                number_ids: vec![],
-                start_segment_id: None,
                 segment_times: vec![
-                    SegmentTime { segment_id: "a".to_string(), start: 10.0 },
-                    SegmentTime { segment_id: "b".to_string(), start: 5.0 }, // out of order
+                    SegmentTime { segment_id: "a".to_string(), start: 10.0, end: None, source: None },
+                    SegmentTime { segment_id: "b".to_string(), start: 5.0, end: None, source: None }, // out of order
                 ],
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let errors = validate_timing_overlay_standalone(&overlay).unwrap();
         assert!(errors.iter().any(|e| matches!(e, ValidationError::SegmentsUnordered(_))));
@@ -343,9 +1584,8 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
-            omitted_numbers: vec![],
             track_timings: vec![], // no tracks at all
+            ..Default::default()
         };
         let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
         assert!(errors.iter().any(|e| matches!(e, ValidationError::UnaccountedNumber(_))));
@@ -361,12 +1601,11 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
             omitted_numbers: vec![OmittedNumber {
                 number_id: "no-1".to_string(),
                 reason: Some("Traditional cut".to_string()),
             }],
-            track_timings: vec![],
+            ..Default::default()
         };
         let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
         assert!(errors.is_empty(), "Expected no errors, got: {:?}", errors);
@@ -382,20 +1621,16 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
             omitted_numbers: vec![OmittedNumber {
                 number_id: "no-1".to_string(),
                 reason: None,
             }],
             track_timings: vec![TrackTiming {
                 track_title: "Track 1".to_string(),
-                disc_number: None,
-                track_number: None,
-                duration_seconds: None,
                 number_ids: vec!["no-1".to_string()],
-                start_segment_id: None,
-                segment_times: vec![],
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
         assert!(errors.iter().any(|e| matches!(e, ValidationError::ConflictingCoverage(_))));
@@ -411,22 +1646,482 @@ mod tests {
             recording: RecordingMetadata {
                 conductor: None, orchestra: None, year: None, label: None, album_title: None,
             },
-            contributors: vec![],
             omitted_numbers: vec![OmittedNumber {
                 number_id: "no-99-nonexistent".to_string(),
                 reason: None,
             }],
             track_timings: vec![TrackTiming {
                 track_title: "Track 1".to_string(),
-                disc_number: None,
-                track_number: None,
-                duration_seconds: None,
                 number_ids: vec!["no-1".to_string()],
-                start_segment_id: None,
-                segment_times: vec![],
+                ..Default::default()
             }],
+            ..Default::default()
         };
         let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
         assert!(errors.iter().any(|e| matches!(e, ValidationError::UnknownOmittedNumber(_))));
     }
+
+    #[test]
+    fn test_annotation_overlay_targets() {
+        let libretto = sample_libretto();
+        let annotation = |segment_id: Option<&str>, number_id: Option<&str>, text: &str| Annotation {
+            segment_id: segment_id.map(String::from),
+            number_id: number_id.map(String::from),
+            kind: AnnotationKind::Note,
+            title: None,
+            text: text.to_string(),
+            author: None,
+        };
+        let mut overlay = AnnotationOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "test".to_string(),
+            contributors: vec![],
+            rights: None,
+            annotations: vec![
+                annotation(Some("no-1-001"), None, "On the first line."),
+                annotation(None, Some("no-1"), "On the whole number."),
+            ],
+        };
+        assert!(validate_annotation_overlay(&overlay, &libretto).unwrap().is_empty());
+
+        overlay.annotations.push(annotation(Some("no-1-999"), None, "Dangling."));
+        overlay.annotations.push(annotation(None, None, ""));
+        let errors = validate_annotation_overlay(&overlay, &libretto).unwrap();
+        assert!(errors.iter().any(|e| matches!(e, ValidationError::UnknownAnnotationTarget("segment", id) if id == "no-1-999")));
+        assert_eq!(errors.iter().filter(|e| matches!(e, ValidationError::MissingField(_))).count(), 2);
+    }
+
+    #[test]
+    fn test_pronunciation_overlay() {
+        let mut libretto = sample_libretto();
+        libretto.numbers[0].segments[1].text = Some("More text\nand another line".to_string());
+        let pronounce = |segment_id: &str, line: Option<usize>| Pronunciation {
+            segment_id: segment_id.to_string(),
+            line,
+            text: "tɛst".to_string(),
+        };
+        let mut overlay = PronunciationOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            contributors: vec![],
+            rights: None,
+            notation: PronunciationNotation::Ipa,
+            pronunciations: vec![pronounce("no-1-001", None), pronounce("no-1-002", Some(1)), pronounce("no-1-002", Some(2))],
+        };
+        assert!(validate_pronunciation_overlay(&overlay, &libretto).unwrap().is_empty());
+
+        overlay.pronunciations.push(pronounce("no-1-002", Some(3)));
+        overlay.pronunciations.push(pronounce("no-1-001", Some(1)));
+        overlay.pronunciations.push(pronounce("no-1-999", None));
+        let rules: Vec<_> = validate_pronunciation_overlay(&overlay, &libretto).unwrap()
+            .iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, vec!["pronunciation-line-out-of-range", "duplicate-pronunciation", "unknown-pronunciation-segment"]);
+    }
+
+    #[test]
+    fn test_synopsis_overlay() {
+        let libretto = sample_libretto();
+        let mut overlay = SynopsisOverlay {
+            version: "1.0".to_string(),
+            base_libretto: "base.libretto.json".to_string(),
+            contributors: vec![],
+            rights: None,
+            language: None,
+            synopses: [("no-1".to_string(), "Things happen.".to_string())].into_iter().collect(),
+        };
+        assert!(validate_synopsis_overlay(&overlay, &libretto).unwrap().is_empty());
+
+        overlay.synopses.insert("no-9".to_string(), " ".to_string());
+        let errors = validate_synopsis_overlay(&overlay, &libretto).unwrap();
+        assert!(matches!(&errors[..], [ValidationError::UnknownSynopsisNumber(id), ValidationError::MissingField(_)] if id == "no-9"));
+    }
+
+    #[test]
+    fn test_detect_document_kind() {
+        assert_eq!(DocumentKind::detect(r#"{"numbers": []}"#).unwrap(), Some(DocumentKind::BaseLibretto));
+        assert_eq!(
+            DocumentKind::detect(r#"{"base_libretto": "b", "track_timings": "oops"}"#).unwrap(),
+            Some(DocumentKind::TimingOverlay)
+        );
+        assert_eq!(
+            DocumentKind::detect(r#"{"base_libretto": "b", "annotations": []}"#).unwrap(),
+            Some(DocumentKind::AnnotationOverlay)
+        );
+        assert_eq!(
+            DocumentKind::detect(r#"{"base_libretto": "b", "pronunciations": []}"#).unwrap(),
+            Some(DocumentKind::PronunciationOverlay)
+        );
+        assert_eq!(
+            DocumentKind::detect(r#"{"base_libretto": "b", "synopses": {}}"#).unwrap(),
+            Some(DocumentKind::SynopsisOverlay)
+        );
+        assert_eq!(
+            DocumentKind::detect(r#"{"opera": {}, "tracks": []}"#).unwrap(),
+            Some(DocumentKind::Interchange)
+        );
+        assert_eq!(DocumentKind::detect(r#"{"rows": []}"#).unwrap(), None);
+        let err = DocumentKind::detect("{\n  \"numbers\": [,]\n}").unwrap_err();
+        assert_eq!((err.line(), err.column()), (2, 15));
+    }
+
+    #[test]
+    fn test_check_document() {
+        let base = sample_libretto();
+        let overlay = single_track_overlay(Some(30.0), &[("no-1-001", 0.0), ("no-1-003", 10.0)]);
+        let config = LintConfig::default();
+
+        let report = check_document(Document::TimingOverlay(&overlay), Some(&base), &config, false).unwrap();
+        assert_eq!(report.kind, DocumentKind::TimingOverlay);
+        assert!(!report.passed);
+        assert_eq!(report.rule_counts["unknown-segment-id"], 1);
+        assert_eq!(report.coverage.as_ref().unwrap().covered, 1);
+        assert_eq!((report.stats.tracks, report.stats.segments, report.stats.numbers), (1, 2, 1));
+
+        let report = check_document(Document::BaseLibretto(&base), None, &config, false).unwrap();
+        assert!(report.passed);
+        assert_eq!((report.stats.numbers, report.stats.segments), (1, 2));
+        assert!(report.coverage.is_none() && report.stats.translation.is_none());
+        assert!(serde_json::to_value(&report).unwrap().get("file").is_none());
+    }
+
+    #[test]
+    fn test_segment_beyond_duration() {
+        let overlay = single_track_overlay(Some(195.0), &[("no-1-001", 0.0), ("no-1-002", 400.0)]);
+        let errors = validate_timing_overlay_standalone(&overlay).unwrap();
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::SegmentBeyondDuration { segment_id, .. } if segment_id == "no-1-002"
+        )));
+
+        let overlay = single_track_overlay(None, &[("no-1-001", 0.0), ("no-1-002", 400.0)]);
+        assert!(validate_timing_overlay_standalone(&overlay).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_explicit_segment_ends() {
+        let mut overlay = single_track_overlay(None, &[("no-1-001", 0.0), ("no-1-002", 10.0)]);
+        overlay.track_timings[0].segment_times[0].end = Some(8.0);
+        assert!(validate_timing_overlay_standalone(&overlay).unwrap().is_empty());
+
+        overlay.track_timings[0].segment_times[0].end = Some(12.0);
+        overlay.track_timings[0].segment_times[1].end = Some(9.0);
+        let errors = validate_timing_overlay_standalone(&overlay).unwrap();
+        let rules: Vec<_> = errors.iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, vec!["overlapping-segments", "end-before-start"]);
+    }
+
+    #[test]
+    fn test_short_final_segment() {
+        let overlay = single_track_overlay(Some(195.0), &[("no-1-001", 0.0), ("no-1-002", 194.6)]);
+        let errors = validate_timing_overlay_standalone(&overlay).unwrap();
+        assert!(errors.iter().any(|e| matches!(e, ValidationError::ShortFinalSegment { .. })));
+
+        let overlay = single_track_overlay(Some(195.0), &[("no-1-001", 0.0), ("no-1-002", 190.0)]);
+        assert!(validate_timing_overlay_standalone(&overlay).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_segment_reference() {
+        let mut overlay = single_track_overlay(None, &[("no-1-001", 0.0), ("no-1-002", 5.0), ("no-1-001", 9.0)]);
+        let mut second = overlay.track_timings[0].clone();
+        second.track_title = "Track 2".to_string();
+        second.segment_times.truncate(2);
+        second.segment_times.remove(0);
+        overlay.track_timings.push(second);
+
+        let errors = validate_timing_overlay_standalone(&overlay).unwrap();
+        let duplicates: Vec<_> = errors.iter()
+            .filter_map(|e| match e {
+                ValidationError::DuplicateSegmentReference { segment_id, first_position, track, position, .. } =>
+                    Some((segment_id.as_str(), *first_position, track.as_str(), *position)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(duplicates, vec![("no-1-001", 1, "Track 1", 3), ("no-1-002", 2, "Track 2", 1)]);
+    }
+
+    #[test]
+    fn test_interchange_checks() {
+        let overlay = single_track_overlay(Some(30.0), &[("no-1-001", 0.0), ("no-1-002", 10.0)]);
+        let mut doc = merge(&sample_libretto(), &overlay).libretto;
+        assert!(validate_interchange(&doc).unwrap().is_empty());
+
+        let track = &mut doc.tracks[0];
+        track.segments[0].end = Some(12.0);
+        track.segments[1].end = Some(8.0);
+        let mut empty = track.clone();
+        empty.segments.clear();
+        doc.tracks.push(empty);
+        doc.opera.composer = String::new();
+
+        let rules: Vec<&str> = validate_interchange(&doc).unwrap().iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, vec![
+            "missing-field",
+            "overlapping-segments",
+            "end-before-start",
+            "duplicate-track-id",
+            "empty-track",
+        ]);
+    }
+
+    #[test]
+    fn test_interchange_untimed_segments() {
+        let overlay = single_track_overlay(Some(30.0), &[("no-1-002", 10.0)]);
+        let options = MergeOptions { include_untimed: true, ..Default::default() };
+        let mut doc = merge_with(&sample_libretto(), &overlay, &options).unwrap().libretto;
+        assert!(doc.tracks[0].segments[0].untimed);
+        assert!(validate_interchange(&doc).unwrap().is_empty());
+
+        // Timed segments either side of an untimed one must still be in order
+        let mut later = doc.tracks[0].segments[1].clone();
+        later.start = 5.0;
+        doc.tracks[0].segments.push(later);
+        doc.tracks[0].segments[1].end = None;
+        let rules: Vec<&str> = validate_interchange(&doc).unwrap().iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, vec!["segments-unordered"]);
+
+        // A timed segment whose start was lost is flagged, not skipped
+        doc.tracks[0].segments[2].start = f64::NAN;
+        let rules: Vec<&str> = validate_interchange(&doc).unwrap().iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, vec!["non-finite-time"]);
+    }
+
+    #[test]
+    fn test_interchange_group_may_overlap() {
+        let overlay = single_track_overlay(Some(30.0), &[("no-1-001", 5.0), ("no-1-002", 5.0)]);
+        let mut doc = merge(&sample_libretto(), &overlay).libretto;
+        for seg in &mut doc.tracks[0].segments {
+            seg.group = Some("a".to_string());
+            seg.end = Some(20.0);
+        }
+        assert!(validate_interchange(&doc).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_interchange_long_gap() {
+        let overlay = single_track_overlay(Some(100.0), &[("no-1-001", 0.0), ("no-1-002", 60.0)]);
+        let mut doc = merge(&sample_libretto(), &overlay).libretto;
+        doc.tracks[0].segments[0].end = Some(20.0);
+        let config = LintConfig::default();
+        let report = check_document(Document::Interchange(&doc), None, &config, false).unwrap();
+        assert_eq!(report.rule_counts["long-gap"], 1);
+        assert_eq!(report.warnings, 1);
+        assert!(report.passed);
+        assert_eq!(report.tracks[0].coverage, Some(60.0));
+
+        doc.tracks[0].segments[0].end = Some(40.0);
+        let report = check_document(Document::Interchange(&doc), None, &config, false).unwrap();
+        assert_eq!(report.warnings, 0);
+        assert_eq!(report.tracks[0].gaps.len(), 1);
+
+        // Before another number, the gap is its introduction
+        doc.tracks[0].segments[0].end = Some(20.0);
+        doc.tracks[0].segments[1].number_id = Some("no-2".to_string());
+        let report = check_document(Document::Interchange(&doc), None, &config, false).unwrap();
+        assert_eq!(report.warnings, 0);
+    }
+
+    #[test]
+    fn test_segment_coverage() {
+        let libretto = sample_libretto();
+        let overlay = single_track_overlay(None, &[("no-1-001", 0.0)]);
+        let coverage = CoverageReport::compute(&overlay, &libretto);
+        assert_eq!(coverage.covered, 1);
+        assert_eq!((coverage.segments.total, coverage.segments.timed, coverage.segments.untimed), (2, 1, 1));
+        assert_eq!(coverage.by_number[0].untimed, 1);
+
+        let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            ValidationError::PartiallyTimedNumber { timed: 1, segments: 2, .. }
+        )));
+
+        // Numbers listed but not yet timed at all (pre-estimate) are not flagged
+        let overlay = single_track_overlay(None, &[]);
+        let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
+        assert!(!errors.iter().any(|e| matches!(e, ValidationError::PartiallyTimedNumber { .. })));
+
+        // A segment the track cuts is omitted, not missing
+        let mut overlay = single_track_overlay(None, &[("no-1-001", 0.0)]);
+        overlay.track_timings[0].skip_segment_ids = vec!["no-1-002".to_string()];
+        let coverage = CoverageReport::compute(&overlay, &libretto);
+        assert_eq!((coverage.segments.untimed, coverage.segments.omitted), (0, 1));
+        let errors = validate_timing_overlay(&overlay, &libretto).unwrap();
+        assert!(!errors.iter().any(|e| matches!(e, ValidationError::PartiallyTimedNumber { .. })));
+    }
+
+    #[test]
+    fn test_speech_rate() {
+        let libretto = sample_libretto();
+        let bounds = TimingBounds::default();
+
+        // Two words over two seconds, then two words over three: plausible
+        let overlay = single_track_overlay(Some(5.0), &[("no-1-001", 0.0), ("no-1-002", 2.0)]);
+        assert!(check_speech_rate(&overlay, &libretto, &bounds).is_empty());
+
+        // A start typed a minute late leaves the first line stretched over 62s
+        let overlay = single_track_overlay(None, &[("no-1-001", 0.0), ("no-1-002", 62.0)]);
+        let errors = check_speech_rate(&overlay, &libretto, &bounds);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::ImplausibleSpeechRate { segment_id, words: 2, .. } if segment_id == "no-1-001"
+        ));
+
+        // Two words in a tenth of a second is too fast, and the last line
+        // then trails over the rest of the track
+        let overlay = single_track_overlay(Some(60.0), &[("no-1-001", 10.0), ("no-1-002", 10.1)]);
+        let errors = check_speech_rate(&overlay, &libretto, &bounds);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_cast_usage() {
+        let mut libretto = sample_libretto();
+        libretto.cast = ["Test", "Silent Partner"].iter()
+            .map(|&character| CastMember {
+                character: character.to_string(),
+                short_name: None,
+                voice_type: None,
+                description: None,
+                aliases: vec![],
+            })
+            .collect();
+        libretto.numbers[0].segments[1].character = Some("TEST, TUTTI E COME VA".to_string());
+
+        let errors = check_cast_usage(&libretto);
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::UnknownCharacter { name, first_segment, occurrences: 1 }
+                if name == "COME VA" && first_segment == "no-1-002"
+        ));
+        assert!(matches!(&errors[1], ValidationError::SilentCastMember(c) if c == "Silent Partner"));
+    }
+
+    #[test]
+    fn test_base_reference() {
+        let dir = std::env::temp_dir().join(format!("libretto-baseref-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("timings")).unwrap();
+        let libretto = sample_libretto();
+        let json = serde_json::to_string(&libretto).unwrap();
+        std::fs::write(dir.join("base.libretto.json"), &json).unwrap();
+        let overlay_path = dir.join("timings/x.timing.json");
+
+        let mut overlay = single_track_overlay(None, &[]);
+        overlay.base_libretto = "base.libretto.json".to_string();
+        overlay.base_sha256 = Some(library::sha256_hex(json.as_bytes()));
+        assert!(check_base_reference(&overlay, &overlay_path, Some(&libretto)).is_empty());
+
+        overlay.base_sha256 = Some("0".repeat(64));
+        overlay.track_timings[0].number_ids = vec!["act-1".to_string()];
+        let mut other = sample_libretto();
+        other.opera.title = "Another Opera".to_string();
+        let rules: Vec<_> = check_base_reference(&overlay, &overlay_path, Some(&other))
+            .iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, vec!["base-hash-mismatch", "foreign-base-reference", "base-opera-mismatch"]);
+
+        overlay.base_libretto = "elsewhere.libretto.json".to_string();
+        let errors = check_base_reference(&overlay, &overlay_path, None);
+        assert!(matches!(&errors[..], [ValidationError::MissingBaseLibretto(r)] if r == "elsewhere.libretto.json"));
+        // The overlay may be checked away from its library
+        assert_eq!(errors[0].default_severity(), Severity::Warning);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lenient_validation() {
+        let dir = std::env::temp_dir().join(format!("libretto-lenient-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let base_path = dir.join("base.libretto.json").to_string_lossy().into_owned();
+        std::fs::write(&base_path, serde_json::to_string(&sample_libretto()).unwrap()).unwrap();
+        let mut overlay = serde_json::to_value(single_track_overlay(Some(30.0), &[("no-1-001", 0.0), ("no-1-002", 10.0)])).unwrap();
+        overlay["track_timings"][0]["segment_times"][1]["start"] = serde_json::json!("ten");
+        let overlay_path = dir.join("x.timing.json").to_string_lossy().into_owned();
+        std::fs::write(&overlay_path, overlay.to_string()).unwrap();
+        let config = LintConfig::default();
+
+        assert!(validate_file(&overlay_path, Some(&base_path), &config, false).is_err());
+        let options = ValidateOptions { lenient: true };
+        let report = validate_file_with(&overlay_path, Some(&base_path), &config, false, &options).unwrap();
+        assert!(!report.passed);
+        assert_eq!(report.rule_counts["skipped-entry"], 1);
+        assert_eq!(report.stats.segments, 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_text_artifacts() {
+        let mut libretto = sample_libretto();
+        libretto.numbers[0].segments[0].text = Some("Non piÃ¹ andrai &amp; cos&igrave;".to_string());
+        libretto.numbers[0].segments[1].translation = Some("No more\u{fffd}".to_string());
+        let errors = check_text_artifacts(&libretto);
+        let found: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(found, vec![
+            "segment 'no-1-001' text: mis-decoded text 'Ã¹'",
+            "segment 'no-1-002' translation: replacement character U+FFFD",
+        ]);
+    }
+
+    #[test]
+    fn test_act_order() {
+        assert_eq!(act_ordinal("2"), Some(2));
+        assert_eq!(act_ordinal("IV"), Some(4));
+        assert_eq!(act_ordinal("Act III"), Some(3));
+        assert_eq!(act_ordinal("prologue"), None);
+
+        let mut libretto = sample_libretto();
+        let template = libretto.numbers[0].clone();
+        libretto.numbers = [("n1", "I"), ("n2", "II"), ("n3", "I"), ("n4", "prologue"), ("n5", "II")]
+            .iter()
+            .map(|&(id, act)| MusicalNumber { id: id.to_string(), act: act.to_string(), segments: vec![], ..template.clone() })
+            .collect();
+        let rules: Vec<_> = check_act_order(&libretto).iter()
+            .map(|e| (e.rule(), e.to_string()))
+            .collect();
+        assert_eq!(rules, vec![
+            ("act-out-of-order", "number 'n3' is in act I, after a number in act II".to_string()),
+            ("act-not-contiguous", "act II resumes at number 'n5' after other acts".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn test_track_order() {
+        let mut overlay = single_track_overlay(None, &[]);
+        let template = overlay.track_timings[0].clone();
+        overlay.track_timings = [(None, 1), (None, 2), (Some(2), 1), (Some(1), 3)]
+            .iter()
+            .map(|&(disc, number)| TrackTiming { disc_number: disc, track_number: Some(number), ..template.clone() })
+            .collect();
+        let errors = validate_timing_overlay_standalone(&overlay).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            ValidationError::TrackOutOfOrder { disc: 1, number: 3, previous_disc: 2, previous_number: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn test_character_name_problems() {
+        assert_eq!(character_name_problem("SUSANNA, FIGARO"), None);
+        assert_eq!(character_name_problem("IL CONTE D'ALMAVIVA"), None);
+        assert_eq!(character_name_problem("SUSANNA e FIGARO"), None);
+        assert_eq!(character_name_problem("Don Basilio"), None);
+        assert_eq!(character_name_problem("ATTO SECONDO"), Some("act or scene heading"));
+        assert_eq!(character_name_problem("VIVA LA LIBERTÀ!"), Some("ends like a sentence"));
+        assert_eq!(character_name_problem("SE VUOL ballare"), Some("contains lowercase words"));
+        assert_eq!(character_name_problem("NON SO PIÙ COSA SON COSA FACCIO"), Some("too long for a name"));
+
+        let mut libretto = sample_libretto();
+        libretto.numbers[0].segments[1].character = Some("SCENA ULTIMA".to_string());
+        let errors = check_character_names(&libretto);
+        assert!(matches!(
+            &errors[..],
+            [ValidationError::SuspiciousCharacterName { segment_id, .. }] if segment_id == "no-1-002"
+        ));
+    }
 }
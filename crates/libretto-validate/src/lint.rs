@@ -0,0 +1,342 @@
+// Severity layer over validation rules, configurable per project.
+//
+// Every `ValidationError` variant is a named rule with a default severity.
+// A `libretto-lint.toml` at (or above) the working directory can disable
+// rules or change their severity:
+//
+//     [rules]
+//     short-final-segment = "off"
+//     unaccounted-number = "warning"
+//     unlicensed-translation = "error"
+//
+//     [timing]
+//     min_words_per_second = 0.2
+//     max_words_per_second = 8.0
+//     max_gap_seconds = 20.0
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::ValidationError;
+
+/// Conventional file name of the per-project lint configuration.
+pub const LINT_CONFIG_FILE: &str = "libretto-lint.toml";
+
+/// How a validation finding affects the outcome.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Reported, but does not fail validation unless running in strict mode.
+    Warning,
+    /// Fails validation.
+    Error,
+}
+
+/// Configured level for a rule: a severity, or disabled entirely.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Warning,
+    Error,
+}
+
+/// A validation error with the severity it was assigned.
+#[derive(Debug)]
+pub struct Finding {
+    pub error: ValidationError,
+    pub severity: Severity,
+}
+
+/// Per-project lint configuration, usually loaded from `libretto-lint.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LintConfig {
+    /// Rule name → level overrides. Rules not listed keep their default severity.
+    #[serde(default)]
+    pub rules: BTreeMap<String, RuleLevel>,
+    /// Bounds for the speech-rate plausibility check.
+    #[serde(default)]
+    pub timing: TimingBounds,
+}
+
+/// Plausible range of words per second between consecutive segment starts,
+/// and the longest an interchange track may show nothing between segments.
+///
+/// Rates outside the range usually mean a typo in a start time (a minute
+/// off) or segments listed in the wrong order. A long gap is usually an
+/// `end` set far too early.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimingBounds {
+    pub min_words_per_second: f64,
+    pub max_words_per_second: f64,
+    pub max_gap_seconds: f64,
+}
+
+impl Default for TimingBounds {
+    fn default() -> Self {
+        TimingBounds {
+            min_words_per_second: 0.3,
+            max_words_per_second: 6.0,
+            max_gap_seconds: 30.0,
+        }
+    }
+}
+
+impl ValidationError {
+    /// Stable kebab-case rule name, used in lint configuration and reports.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ValidationError::MissingField(_) => "missing-field",
+            ValidationError::DuplicateSegmentId(_) => "duplicate-segment-id",
+            ValidationError::UnknownSegmentId(_) => "unknown-segment-id",
+            ValidationError::SegmentsUnordered(_) => "segments-unordered",
+            ValidationError::NegativeTime(_) => "negative-time",
+            ValidationError::SegmentBeyondDuration { .. } => "segment-beyond-duration",
+            ValidationError::ShortFinalSegment { .. } => "short-final-segment",
+            ValidationError::DuplicateSegmentReference { .. } => "duplicate-segment-reference",
+            ValidationError::MissingBaseLibretto(_) => "missing-base-libretto",
+            ValidationError::BaseHashMismatch { .. } => "base-hash-mismatch",
+            ValidationError::BaseOperaMismatch { .. } => "base-opera-mismatch",
+            ValidationError::ForeignBaseReference(_) => "foreign-base-reference",
+            ValidationError::ActOutOfOrder { .. } => "act-out-of-order",
+            ValidationError::ActNotContiguous { .. } => "act-not-contiguous",
+            ValidationError::TrackOutOfOrder { .. } => "track-out-of-order",
+            ValidationError::UnaccountedNumber(_) => "unaccounted-number",
+            ValidationError::UnknownOmittedNumber(_) => "unknown-omitted-number",
+            ValidationError::ConflictingCoverage(_) => "conflicting-coverage",
+            ValidationError::PartiallyTimedNumber { .. } => "partially-timed-number",
+            ValidationError::ImplausibleSpeechRate { .. } => "implausible-speech-rate",
+            ValidationError::AudioDurationMismatch { .. } => "audio-duration-mismatch",
+            ValidationError::NoAudioForTrack(_) => "no-audio-for-track",
+            ValidationError::UnknownAnnotationTarget(..) => "unknown-annotation-target",
+            ValidationError::UnknownPronunciationSegment(_) => "unknown-pronunciation-segment",
+            ValidationError::PronunciationLineOutOfRange { .. } => "pronunciation-line-out-of-range",
+            ValidationError::PronunciationWithoutText(_) => "pronunciation-without-text",
+            ValidationError::DuplicatePronunciation(_) => "duplicate-pronunciation",
+            ValidationError::UnknownSynopsisNumber(_) => "unknown-synopsis-number",
+            ValidationError::UnknownCharacter { .. } => "unknown-character",
+            ValidationError::SuspiciousCharacterName { .. } => "suspicious-character-name",
+            ValidationError::SilentCastMember(_) => "silent-cast-member",
+            ValidationError::SuspiciousText { .. } => "suspicious-text",
+            ValidationError::UntranslatedNumber(_) => "untranslated-number",
+            ValidationError::UnlicensedTranslation => "unlicensed-translation",
+            ValidationError::DuplicateTrackId(_) => "duplicate-track-id",
+            ValidationError::EmptyTrack(_) => "empty-track",
+            ValidationError::NonFiniteTime { .. } => "non-finite-time",
+            ValidationError::EndBeforeStart { .. } => "end-before-start",
+            ValidationError::OverlappingSegments { .. } => "overlapping-segments",
+            ValidationError::LongGap { .. } => "long-gap",
+            ValidationError::SkippedEntry(_) => "skipped-entry",
+            ValidationError::Other(_) => "other",
+        }
+    }
+
+    /// Severity when no lint configuration overrides it.
+    ///
+    /// Heuristic checks that are sometimes legitimately violated are warnings;
+    /// structural problems that would break a merge are errors.
+    pub fn default_severity(&self) -> Severity {
+        match self {
+            ValidationError::ShortFinalSegment { .. }
+            | ValidationError::UnlicensedTranslation
+            | ValidationError::EmptyTrack(_)
+            | ValidationError::PartiallyTimedNumber { .. }
+            | ValidationError::ImplausibleSpeechRate { .. }
+            | ValidationError::NoAudioForTrack(_)
+            | ValidationError::UnknownCharacter { .. }
+            | ValidationError::SilentCastMember(_)
+            | ValidationError::SuspiciousCharacterName { .. }
+            | ValidationError::MissingBaseLibretto(_)
+            | ValidationError::BaseHashMismatch { .. }
+            | ValidationError::UntranslatedNumber(_)
+            | ValidationError::LongGap { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// Every rule name, for checking configuration files against.
+pub const RULES: &[&str] = &[
+    "missing-field",
+    "duplicate-segment-id",
+    "unknown-segment-id",
+    "segments-unordered",
+    "negative-time",
+    "segment-beyond-duration",
+    "short-final-segment",
+    "duplicate-segment-reference",
+    "missing-base-libretto",
+    "base-hash-mismatch",
+    "base-opera-mismatch",
+    "foreign-base-reference",
+    "act-out-of-order",
+    "act-not-contiguous",
+    "track-out-of-order",
+    "unaccounted-number",
+    "unknown-omitted-number",
+    "conflicting-coverage",
+    "partially-timed-number",
+    "implausible-speech-rate",
+    "audio-duration-mismatch",
+    "no-audio-for-track",
+    "unknown-annotation-target",
+    "unknown-pronunciation-segment",
+    "pronunciation-line-out-of-range",
+    "pronunciation-without-text",
+    "duplicate-pronunciation",
+    "unknown-synopsis-number",
+    "unknown-character",
+    "suspicious-character-name",
+    "silent-cast-member",
+    "suspicious-text",
+    "untranslated-number",
+    "unlicensed-translation",
+    "duplicate-track-id",
+    "empty-track",
+    "non-finite-time",
+    "end-before-start",
+    "overlapping-segments",
+    "long-gap",
+    "skipped-entry",
+    "other",
+];
+
+impl LintConfig {
+    /// Parse a lint configuration, rejecting unknown rule names.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let config: LintConfig = toml::from_str(contents)?;
+        if let Some(unknown) = config.rules.keys().find(|r| !RULES.contains(&r.as_str())) {
+            anyhow::bail!("unknown lint rule '{unknown}' (known rules: {})", RULES.join(", "));
+        }
+        let bounds = config.timing;
+        if !(0.0..bounds.max_words_per_second).contains(&bounds.min_words_per_second) {
+            anyhow::bail!(
+                "timing bounds must satisfy 0 <= min_words_per_second < max_words_per_second (got {} and {})",
+                bounds.min_words_per_second, bounds.max_words_per_second
+            );
+        }
+        if bounds.max_gap_seconds <= 0.0 {
+            anyhow::bail!("timing.max_gap_seconds must be positive (got {})", bounds.max_gap_seconds);
+        }
+        Ok(config)
+    }
+
+    /// Load a lint configuration file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Invalid lint configuration {}", path.display()))
+    }
+
+    /// Find `libretto-lint.toml` in `start` or its nearest ancestor.
+    pub fn find(start: &Path) -> Option<PathBuf> {
+        start.ancestors()
+            .map(|dir| dir.join(LINT_CONFIG_FILE))
+            .find(|p| p.is_file())
+    }
+
+    /// Load the nearest `libretto-lint.toml` above `start`, or the defaults if there is none.
+    pub fn discover(start: &Path) -> Result<Self> {
+        match Self::find(start) {
+            Some(path) => {
+                tracing::debug!(path = %path.display(), "Using lint configuration");
+                Self::load(&path)
+            }
+            None => Ok(Self::default()),
+        }
+    }
+
+    /// Severity for an error under this configuration, or `None` if its rule is disabled.
+    pub fn severity(&self, error: &ValidationError) -> Option<Severity> {
+        match self.rules.get(error.rule()) {
+            Some(RuleLevel::Off) => None,
+            Some(RuleLevel::Warning) => Some(Severity::Warning),
+            Some(RuleLevel::Error) => Some(Severity::Error),
+            None => Some(error.default_severity()),
+        }
+    }
+
+    /// Assign severities to validation errors, dropping disabled rules.
+    pub fn apply(&self, errors: Vec<ValidationError>) -> Vec<Finding> {
+        errors.into_iter()
+            .filter_map(|error| self.severity(&error).map(|severity| Finding { error, severity }))
+            .collect()
+    }
+}
+
+/// Whether a set of findings fails validation.
+///
+/// Errors always fail; warnings fail only in strict mode.
+pub fn is_failure(findings: &[Finding], strict: bool) -> bool {
+    findings.iter().any(|f| f.severity == Severity::Error || strict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_apply() {
+        let config = LintConfig::parse(
+            r#"
+            [rules]
+            short-final-segment = "off"
+            unaccounted-number = "warning"
+            "#,
+        ).unwrap();
+
+        let findings = config.apply(vec![
+            ValidationError::ShortFinalSegment {
+                track: "t".to_string(),
+                segment_id: "s".to_string(),
+                tail: 0.2,
+            },
+            ValidationError::UnaccountedNumber("no-2".to_string()),
+            ValidationError::NegativeTime(-1.0),
+        ]);
+        let severities: Vec<_> = findings.iter().map(|f| (f.error.rule(), f.severity)).collect();
+        assert_eq!(severities, vec![
+            ("unaccounted-number", Severity::Warning),
+            ("negative-time", Severity::Error),
+        ]);
+        assert!(is_failure(&findings, false));
+        assert!(!is_failure(&findings[..1], false));
+        assert!(is_failure(&findings[..1], true));
+    }
+
+    #[test]
+    fn test_unknown_rule_rejected() {
+        let err = LintConfig::parse("[rules]\nno-such-rule = \"off\"\n").unwrap_err();
+        assert!(err.to_string().contains("no-such-rule"));
+        assert!(LintConfig::parse("[rules]\nnegative-time = \"fatal\"\n").is_err());
+    }
+
+    #[test]
+    fn test_timing_bounds() {
+        let config = LintConfig::parse("[timing]\nmax_words_per_second = 8.0\n").unwrap();
+        assert_eq!(config.timing.min_words_per_second, 0.3);
+        assert_eq!(config.timing.max_words_per_second, 8.0);
+        assert!(LintConfig::parse("[timing]\nmin_words_per_second = 9.0\n").is_err());
+        assert!(LintConfig::parse("[timing]\nmax_wps = 9.0\n").is_err());
+        assert_eq!(LintConfig::parse("[timing]\nmax_gap_seconds = 12.5\n").unwrap().timing.max_gap_seconds, 12.5);
+        assert!(LintConfig::parse("[timing]\nmax_gap_seconds = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_rules_list_matches_variants() {
+        let sample = [
+            ValidationError::MissingField(String::new()),
+            ValidationError::UnlicensedTranslation,
+            ValidationError::UnknownAnnotationTarget("segment", String::new()),
+            ValidationError::Other(String::new()),
+        ];
+        for e in &sample {
+            assert!(RULES.contains(&e.rule()));
+        }
+    }
+}
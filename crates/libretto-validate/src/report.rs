@@ -0,0 +1,143 @@
+// Machine-readable validation results.
+//
+// `libretto validate --format json` prints a `ValidationReport` so CI jobs
+// can count findings by rule and annotate pull requests without scraping
+// log output.
+
+use std::collections::BTreeMap;
+
+use libretto_model::gaps::TrackGaps;
+use serde::Serialize;
+
+use crate::lint::{Finding, Severity};
+use crate::stats::DocumentStats;
+use crate::{CoverageReport, DocumentKind};
+
+/// The outcome of validating one file.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationReport {
+    /// Path of the validated file; empty for documents checked in memory.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub file: String,
+    pub kind: DocumentKind,
+    /// Base libretto the file was checked against, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    /// Whether the file passed, taking strict mode into account.
+    pub passed: bool,
+    pub strict: bool,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Finding count per rule name.
+    pub rule_counts: BTreeMap<String, usize>,
+    pub findings: Vec<ReportFinding>,
+    /// Number coverage, for timing overlays checked against a base libretto.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageReport>,
+    /// Gaps, overlaps, and coverage per track, for interchange documents.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tracks: Vec<TrackGaps>,
+    pub stats: DocumentStats,
+}
+
+/// A single finding in serializable form.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportFinding {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl ValidationReport {
+    /// Summarize findings for a file. `base`, `coverage`, `tracks`, and `stats` start empty.
+    pub fn new(file: &str, kind: DocumentKind, findings: &[Finding], strict: bool) -> Self {
+        let mut report = ValidationReport {
+            file: file.to_string(),
+            kind,
+            base: None,
+            passed: true,
+            strict,
+            errors: 0,
+            warnings: 0,
+            rule_counts: BTreeMap::new(),
+            findings: Vec::new(),
+            coverage: None,
+            tracks: Vec::new(),
+            stats: DocumentStats::default(),
+        };
+        report.add_findings(findings);
+        report
+    }
+
+    /// One-line outcome, e.g. `x.timing.json: FAIL (2 errors, 1 warning)`.
+    pub fn summary(&self) -> String {
+        let plural = |n: usize, word: &str| format!("{n} {word}{}", if n == 1 { "" } else { "s" });
+        format!(
+            "{}: {} ({}, {})",
+            self.file,
+            if self.passed { "PASS" } else { "FAIL" },
+            plural(self.errors, "error"),
+            plural(self.warnings, "warning"),
+        )
+    }
+
+    /// Add findings from a further check, updating counts and the outcome.
+    pub fn add_findings(&mut self, findings: &[Finding]) {
+        for f in findings {
+            *self.rule_counts.entry(f.error.rule().to_string()).or_insert(0) += 1;
+            match f.severity {
+                Severity::Error => self.errors += 1,
+                Severity::Warning => self.warnings += 1,
+            }
+            self.findings.push(ReportFinding {
+                rule: f.error.rule(),
+                severity: f.severity,
+                message: f.error.to_string(),
+            });
+        }
+        self.passed = self.errors == 0 && (!self.strict || self.warnings == 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValidationError;
+
+    #[test]
+    fn test_report_counts_and_json() {
+        let findings = vec![
+            Finding { error: ValidationError::UnaccountedNumber("no-2".into()), severity: Severity::Error },
+            Finding { error: ValidationError::UnaccountedNumber("no-3".into()), severity: Severity::Error },
+            Finding { error: ValidationError::UnlicensedTranslation, severity: Severity::Warning },
+        ];
+        let report = ValidationReport::new("x.timing.json", DocumentKind::TimingOverlay, &findings, false);
+        assert!(!report.passed);
+        assert_eq!((report.errors, report.warnings), (2, 1));
+        assert_eq!(report.rule_counts["unaccounted-number"], 2);
+        assert_eq!(report.summary(), "x.timing.json: FAIL (2 errors, 1 warning)");
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["kind"], "timing_overlay");
+        assert_eq!(json["findings"][2]["severity"], "warning");
+        assert_eq!(json["findings"][0]["rule"], "unaccounted-number");
+        assert!(json.get("coverage").is_none());
+    }
+
+    #[test]
+    fn test_warnings_only_pass_unless_strict() {
+        let findings = vec![Finding { error: ValidationError::UnlicensedTranslation, severity: Severity::Warning }];
+        assert!(ValidationReport::new("b.json", DocumentKind::BaseLibretto, &findings, false).passed);
+        assert!(!ValidationReport::new("b.json", DocumentKind::BaseLibretto, &findings, true).passed);
+    }
+
+    #[test]
+    fn test_add_findings() {
+        let mut report = ValidationReport::new("x.timing.json", DocumentKind::TimingOverlay, &[], false);
+        assert!(report.passed);
+        report.add_findings(&[Finding { error: ValidationError::NoAudioForTrack("Act I".into()), severity: Severity::Error }]);
+        assert!(!report.passed);
+        assert_eq!(report.errors, 1);
+        assert_eq!(report.rule_counts["no-audio-for-track"], 1);
+    }
+}
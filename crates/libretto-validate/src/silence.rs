@@ -0,0 +1,248 @@
+// Snapping estimated segment starts to silences in the audio.
+//
+// `timing estimate` spreads a track's duration over its segments by word
+// count, which drifts badly in recitative: the singers race through text
+// and then pause for the continuo. Those pauses show up as stretches of
+// low energy, and a new segment almost always begins where one ends. With
+// `--snap-to-silence`, each estimated start moves to the nearest such
+// boundary within a small window.
+
+use libretto_model::timing_overlay::round_to_ms;
+use libretto_model::{TimingOverlay, TimingSource, TrackTiming};
+
+use crate::audio::{match_track, AudioFile};
+use crate::decode;
+
+/// Length of the windows the audio level is measured over, in seconds.
+pub const ANALYSIS_WINDOW_SECONDS: f64 = 0.02;
+
+/// Default distance an estimated start may move to reach a boundary, in seconds.
+pub const DEFAULT_SNAP_WINDOW_SECONDS: f64 = 1.5;
+
+/// Tuning for silence detection and snapping.
+#[derive(Debug, Clone, Copy)]
+pub struct SilenceOptions {
+    /// Level below which a window counts as silent, in dB relative to the
+    /// track's loudest window. Relative levels cope with quiet masterings.
+    pub threshold_db: f32,
+    /// Shortest quiet stretch treated as a boundary, in seconds.
+    pub min_silence_seconds: f64,
+    /// How far an estimated start may move to reach a boundary, in seconds.
+    pub snap_window_seconds: f64,
+}
+
+impl Default for SilenceOptions {
+    fn default() -> Self {
+        SilenceOptions {
+            threshold_db: -35.0,
+            min_silence_seconds: 0.15,
+            snap_window_seconds: DEFAULT_SNAP_WINDOW_SECONDS,
+        }
+    }
+}
+
+/// A stretch of low energy within a track, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Silence {
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Find silences in an RMS envelope measured over `window_seconds` windows.
+///
+/// A silence still running at the end of the track is not reported: no
+/// segment can start after it.
+pub fn detect_silences(envelope: &[f32], window_seconds: f64, options: &SilenceOptions) -> Vec<Silence> {
+    let peak = envelope.iter().copied().fold(0.0_f32, f32::max);
+    if peak <= 0.0 {
+        return Vec::new();
+    }
+    let threshold = peak * 10_f32.powf(options.threshold_db / 20.0);
+
+    let mut silences = Vec::new();
+    let mut quiet_from: Option<usize> = None;
+    for (i, &level) in envelope.iter().enumerate() {
+        match (level < threshold, quiet_from) {
+            (true, None) => quiet_from = Some(i),
+            (false, Some(from)) => {
+                let silence = Silence {
+                    start: round_to_ms(from as f64 * window_seconds),
+                    end: round_to_ms(i as f64 * window_seconds),
+                };
+                if silence.end - silence.start >= options.min_silence_seconds {
+                    silences.push(silence);
+                }
+                quiet_from = None;
+            }
+            _ => {}
+        }
+    }
+    silences
+}
+
+/// Move each estimated segment start to the nearest boundary within `window` seconds.
+///
+/// Times from any other source are left alone. Boundaries are where sound
+/// resumes after a silence. A start only moves to a boundary after the
+/// previous segment's start and before the next segment's, so the order of
+/// segments never changes. Returns the number of starts moved.
+pub fn snap_to_boundaries(track: &mut TrackTiming, boundaries: &[f64], window: f64) -> usize {
+    let mut snapped = 0;
+    for i in 0..track.segment_times.len() {
+        if track.segment_times[i].trust() != TimingSource::Estimated {
+            continue;
+        }
+        let start = track.segment_times[i].start;
+        let previous = i.checked_sub(1).map_or(f64::NEG_INFINITY, |p| track.segment_times[p].start);
+        let next = track.segment_times.get(i + 1).map_or(f64::INFINITY, |n| n.start);
+        let nearest = boundaries.iter()
+            .copied()
+            .filter(|&b| b > previous && b < next && (b - start).abs() <= window)
+            .min_by(|a, b| (a - start).abs().total_cmp(&(b - start).abs()));
+        if let Some(boundary) = nearest.filter(|&b| b != start) {
+            track.segment_times[i].start = boundary;
+            snapped += 1;
+        }
+    }
+    snapped
+}
+
+/// Outcome of snapping an overlay's segment starts to silences.
+#[derive(Debug, Default)]
+pub struct SnapSummary {
+    /// Segment starts moved to a boundary.
+    pub snapped: usize,
+    /// Segment starts in tracks that were analysed.
+    pub segments: usize,
+    /// Titles of timed tracks no audio file matched, or whose file could not be decoded.
+    pub skipped: Vec<String>,
+}
+
+/// Snap every timed track's segment starts to silences in its audio file.
+///
+/// Tracks are matched to files as in `match_track`. Requires the `audio`
+/// feature to decode the files.
+pub fn snap_overlay_to_silence(
+    overlay: &mut TimingOverlay,
+    files: &[AudioFile],
+    options: &SilenceOptions,
+) -> SnapSummary {
+    let mut summary = SnapSummary::default();
+    for track in overlay.track_timings.iter_mut().filter(|t| !t.segment_times.is_empty()) {
+        let Some(file) = match_track(&track.track_title, track.disc_number, track.track_number, files) else {
+            summary.skipped.push(track.track_title.clone());
+            continue;
+        };
+        let envelope = match decode::rms_envelope(&file.path, ANALYSIS_WINDOW_SECONDS) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                tracing::warn!(path = %file.path.display(), "Skipping undecodable audio file: {e:#}");
+                summary.skipped.push(track.track_title.clone());
+                continue;
+            }
+        };
+        let boundaries: Vec<f64> = detect_silences(&envelope, ANALYSIS_WINDOW_SECONDS, options)
+            .iter()
+            .map(|s| s.end)
+            .collect();
+        summary.segments += track.segment_times.len();
+        summary.snapped += snap_to_boundaries(track, &boundaries, options.snap_window_seconds);
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libretto_model::SegmentTime;
+
+    fn track(starts: &[f64]) -> TrackTiming {
+        TrackTiming {
+            track_title: "T".to_string(),
+            disc_number: Some(1),
+            track_number: Some(1),
+            duration_seconds: None,
+            number_ids: vec![],
+            start_segment_id: None,
+            resolved_by: None,
+            section_marks: vec![],
+            instrumental: false,
+            skip_segment_ids: vec![],
+            padding: None,
+            segment_times: starts.iter()
+                .enumerate()
+                .map(|(i, &start)| SegmentTime {
+                    segment_id: format!("s{i}"),
+                    start,
+                    end: None,
+                    source: Some(TimingSource::Estimated),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_detect_silences() {
+        // 0.1s windows: loud, 3 quiet (0.3s), loud, 1 quiet (too short), loud, trailing quiet
+        let envelope = [0.5, 0.001, 0.001, 0.001, 0.4, 0.001, 0.5, 0.0, 0.0];
+        let silences = detect_silences(&envelope, 0.1, &SilenceOptions::default());
+        assert_eq!(silences, vec![Silence { start: 0.1, end: 0.4 }]);
+        assert!(detect_silences(&[0.0; 4], 0.1, &SilenceOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_snap_to_boundaries() {
+        let mut t = track(&[0.0, 10.0, 12.0, 30.0]);
+        // 10.0 and 12.0 move to their nearest boundaries; 28.0 is outside 30.0's window
+        let snapped = snap_to_boundaries(&mut t, &[9.2, 11.5, 28.0, 40.0], 1.5);
+        let starts: Vec<f64> = t.segment_times.iter().map(|s| s.start).collect();
+        assert_eq!(starts, vec![0.0, 9.2, 11.5, 30.0]);
+        assert_eq!(snapped, 2);
+
+        // A tapped start stays where it was put
+        let mut t = track(&[0.0, 10.0]);
+        t.segment_times[1].source = Some(TimingSource::Tapped);
+        assert_eq!(snap_to_boundaries(&mut t, &[9.2], 1.5), 0);
+    }
+
+    #[cfg(feature = "audio")]
+    #[test]
+    fn test_snap_overlay_to_silence() {
+        let dir = std::env::temp_dir().join(format!("libretto-silence-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("1-01 Recitativo.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        // Tone for 2s, silence for 1s, tone for 2s: sound resumes at 3.0s
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for i in 0..spec.sample_rate * 5 {
+            let t = f64::from(i) / f64::from(spec.sample_rate);
+            let level = if (2.0..3.0).contains(&t) { 0.0 } else { 8000.0 };
+            writer.write_sample((level * (t * 440.0 * std::f64::consts::TAU).sin()) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let files = crate::audio::scan_audio_dir(&dir).unwrap();
+        let mut overlay: TimingOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [{ "track_title": "Recitativo", "disc_number": 1, "track_number": 1,
+                "number_ids": [], "segment_times": [
+                    { "segment_id": "a", "start": 0.0, "source": "estimated" },
+                    { "segment_id": "b", "start": 2.4, "source": "estimated" }
+                ] }]
+        }"#).unwrap();
+        let summary = snap_overlay_to_silence(&mut overlay, &files, &SilenceOptions::default());
+        assert_eq!((summary.snapped, summary.segments), (1, 2));
+        assert!(summary.skipped.is_empty());
+        assert!((overlay.track_timings[0].segment_times[1].start - 3.0).abs() < 0.05);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
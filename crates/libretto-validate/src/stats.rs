@@ -0,0 +1,647 @@
+// Document statistics and translation coverage of base librettos.
+//
+// After alignment some numbers end up with no translation at all (a page
+// missing from the source, a column that failed to align). Counting
+// translated segments per act and per number makes the gaps visible
+// before a bilingual libretto is published.
+//
+// Timing a recording is a long-running project too: `timing status`
+// counts, per track and per act, how far the overlay has got from a bare
+// scaffold (durations, resolved anchors) to times someone has checked.
+//
+// `libretto stats` describes the documents themselves: how a libretto's
+// numbers, segments and words fall across its acts and characters, and
+// how much of the recording an overlay's times account for per act and
+// number.
+
+use std::collections::{HashMap, HashSet};
+
+use libretto_model::base_libretto::split_character_attribution;
+use libretto_model::{BaseLibretto, SegmentTime, TimingOverlay, TimingSource};
+use serde::Serialize;
+
+use crate::Document;
+
+/// Size of a validated document.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DocumentStats {
+    /// Musical numbers in a base libretto, distinct numbers a timing overlay's tracks cover, or numbers
+    /// with a synopsis in a synopsis overlay.
+    pub numbers: usize,
+    /// Segments in a base libretto or interchange document, or segment times in a timing overlay.
+    pub segments: usize,
+    /// Tracks in a timing overlay or interchange document.
+    pub tracks: usize,
+    /// Annotations in an annotation overlay or interchange document.
+    pub annotations: usize,
+    /// Pronunciations in a pronunciation overlay, or segments with one in an interchange document.
+    pub pronunciations: usize,
+    /// Translation coverage, for bilingual base librettos.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<TranslationCoverage>,
+}
+
+impl DocumentStats {
+    pub fn compute(document: Document<'_>) -> Self {
+        match document {
+            Document::BaseLibretto(libretto) => DocumentStats {
+                numbers: libretto.numbers.len(),
+                segments: libretto.numbers.iter().map(|n| n.segments.len()).sum(),
+                translation: libretto.has_translations().then(|| TranslationCoverage::compute(libretto)),
+                ..Default::default()
+            },
+            Document::TimingOverlay(overlay) => {
+                let mut numbers: Vec<&str> = overlay.track_timings.iter()
+                    .flat_map(|t| t.number_ids.iter().map(String::as_str))
+                    .collect();
+                numbers.sort_unstable();
+                numbers.dedup();
+                DocumentStats {
+                    numbers: numbers.len(),
+                    segments: overlay.track_timings.iter().map(|t| t.segment_times.len()).sum(),
+                    tracks: overlay.track_timings.len(),
+                    ..Default::default()
+                }
+            }
+            Document::AnnotationOverlay(annotations) => DocumentStats {
+                annotations: annotations.annotations.len(),
+                ..Default::default()
+            },
+            Document::SynopsisOverlay(synopses) => DocumentStats {
+                numbers: synopses.synopses.len(),
+                ..Default::default()
+            },
+            Document::PronunciationOverlay(pronunciations) => DocumentStats {
+                pronunciations: pronunciations.pronunciations.len(),
+                ..Default::default()
+            },
+            Document::Interchange(doc) => DocumentStats {
+                segments: doc.tracks.iter().map(|t| t.segments.len()).sum(),
+                tracks: doc.tracks.len(),
+                annotations: doc.annotations.len(),
+                pronunciations: doc.tracks.iter()
+                    .flat_map(|t| &t.segments)
+                    .filter(|s| s.pronunciation.is_some())
+                    .count(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Translated vs. translatable segments for a whole libretto.
+///
+/// Only segments with original text count as translatable; directions
+/// and interludes without text are ignored.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationCoverage {
+    pub translatable: usize,
+    pub translated: usize,
+    pub by_act: Vec<TranslationCount>,
+    pub by_number: Vec<TranslationCount>,
+}
+
+/// Translation counts for one act or number.
+#[derive(Debug, Clone, Serialize)]
+pub struct TranslationCount {
+    /// Act label or number ID.
+    pub id: String,
+    pub translatable: usize,
+    pub translated: usize,
+}
+
+impl TranslationCount {
+    /// Percentage of translatable segments that are translated (100 when there are none).
+    pub fn percent(&self) -> f64 {
+        percent(self.translated, self.translatable)
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 100.0 } else { part as f64 * 100.0 / whole as f64 }
+}
+
+impl TranslationCoverage {
+    /// Count translations per act (in order of first appearance) and per number.
+    pub fn compute(libretto: &BaseLibretto) -> Self {
+        let mut by_act: Vec<TranslationCount> = Vec::new();
+        let mut by_number = Vec::new();
+        for number in &libretto.numbers {
+            let with_text = number.segments.iter().filter(|s| s.text.is_some());
+            let translatable = with_text.clone().count();
+            let translated = with_text.filter(|s| s.translation.is_some()).count();
+            by_number.push(TranslationCount { id: number.id.clone(), translatable, translated });
+
+            match by_act.iter_mut().find(|a| a.id == number.act) {
+                Some(act) => {
+                    act.translatable += translatable;
+                    act.translated += translated;
+                }
+                None => by_act.push(TranslationCount { id: number.act.clone(), translatable, translated }),
+            }
+        }
+
+        TranslationCoverage {
+            translatable: by_number.iter().map(|n| n.translatable).sum(),
+            translated: by_number.iter().map(|n| n.translated).sum(),
+            by_act,
+            by_number,
+        }
+    }
+
+    /// Percentage of all translatable segments that are translated.
+    pub fn percent(&self) -> f64 {
+        percent(self.translated, self.translatable)
+    }
+
+    /// Numbers with translatable text but no translation at all.
+    pub fn untranslated_numbers(&self) -> impl Iterator<Item = &TranslationCount> {
+        self.by_number.iter().filter(|n| n.translatable > 0 && n.translated == 0)
+    }
+}
+
+/// Size of a base libretto, per act, number and character.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibrettoStats {
+    pub numbers: usize,
+    pub segments: usize,
+    /// Words of original text.
+    pub words: usize,
+    /// Acts in order of first appearance.
+    pub by_act: Vec<ActSize>,
+    pub by_number: Vec<NumberSize>,
+    /// Characters by lines sung, most first.
+    pub characters: Vec<CharacterLines>,
+    /// Translation coverage, for bilingual librettos.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<TranslationCoverage>,
+}
+
+/// Size of one act.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActSize {
+    pub act: String,
+    pub numbers: usize,
+    pub segments: usize,
+    pub words: usize,
+}
+
+/// Size of one musical number.
+#[derive(Debug, Clone, Serialize)]
+pub struct NumberSize {
+    pub id: String,
+    pub act: String,
+    pub segments: usize,
+    pub words: usize,
+}
+
+/// What one character sings. Ensemble segments count for every character
+/// they name.
+#[derive(Debug, Clone, Serialize)]
+pub struct CharacterLines {
+    /// The cast member's name, or the attribution as written when no cast
+    /// member matches it.
+    pub character: String,
+    pub segments: usize,
+    pub lines: usize,
+    pub words: usize,
+}
+
+impl LibrettoStats {
+    pub fn compute(libretto: &BaseLibretto) -> Self {
+        let mut by_act: Vec<ActSize> = Vec::new();
+        let mut by_number = Vec::new();
+        let mut characters: Vec<CharacterLines> = Vec::new();
+        for number in &libretto.numbers {
+            let mut size = NumberSize { id: number.id.clone(), act: number.act.clone(), segments: 0, words: 0 };
+            for segment in &number.segments {
+                let text = segment.text.as_deref().unwrap_or("");
+                let words = text.split_whitespace().count();
+                size.segments += 1;
+                size.words += words;
+
+                let Some(attribution) = segment.character.as_deref().filter(|_| !text.trim().is_empty()) else {
+                    continue;
+                };
+                let lines = text.lines().filter(|l| !l.trim().is_empty()).count();
+                for name in split_character_attribution(attribution) {
+                    let name = libretto.find_cast_member(&name).map_or(name, |m| m.character.clone());
+                    let index = match characters.iter().position(|c| c.character == name) {
+                        Some(index) => index,
+                        None => {
+                            characters.push(CharacterLines { character: name, segments: 0, lines: 0, words: 0 });
+                            characters.len() - 1
+                        }
+                    };
+                    let character = &mut characters[index];
+                    character.segments += 1;
+                    character.lines += lines;
+                    character.words += words;
+                }
+            }
+
+            match by_act.iter_mut().find(|a| a.act == number.act) {
+                Some(act) => {
+                    act.numbers += 1;
+                    act.segments += size.segments;
+                    act.words += size.words;
+                }
+                None => by_act.push(ActSize {
+                    act: number.act.clone(),
+                    numbers: 1,
+                    segments: size.segments,
+                    words: size.words,
+                }),
+            }
+            by_number.push(size);
+        }
+        characters.sort_by_key(|c| std::cmp::Reverse(c.lines));
+
+        LibrettoStats {
+            numbers: by_number.len(),
+            segments: by_number.iter().map(|n| n.segments).sum(),
+            words: by_number.iter().map(|n| n.words).sum(),
+            by_act,
+            by_number,
+            characters,
+            translation: libretto.has_translations().then(|| TranslationCoverage::compute(libretto)),
+        }
+    }
+}
+
+/// Recording time a timing overlay gives each act and number of its base
+/// libretto.
+///
+/// A segment lasts until its `end`, or else until the next segment of its
+/// track starts, or else until the end of the track. The last segment of
+/// a track without a duration has no known length and is counted as open.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimedDurations {
+    pub seconds: f64,
+    /// Acts in order of first appearance, with only the numbers timed.
+    pub by_act: Vec<TimedDuration>,
+    pub by_number: Vec<TimedDuration>,
+    /// Timed segments whose length is not known.
+    pub open_segments: usize,
+}
+
+/// Recording time of one act or number.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimedDuration {
+    /// Act label or number ID.
+    pub id: String,
+    pub seconds: f64,
+    /// Segments with a time.
+    pub segments: usize,
+}
+
+impl TimedDurations {
+    pub fn compute(overlay: &TimingOverlay, base: &BaseLibretto) -> Self {
+        let mut lengths: HashMap<&str, Option<f64>> = HashMap::new();
+        for track in &overlay.track_timings {
+            let next_starts = track.segment_times.iter()
+                .skip(1)
+                .map(|st| Some(st.start))
+                .chain(std::iter::once(track.duration_seconds));
+            for (st, next) in track.segment_times.iter().zip(next_starts) {
+                let length = st.end.or(next).map(|end| (end - st.start).max(0.0));
+                lengths.insert(st.segment_id.as_str(), length);
+            }
+        }
+
+        let mut by_act: Vec<TimedDuration> = Vec::new();
+        let mut by_number = Vec::new();
+        let mut open_segments = 0;
+        for number in &base.numbers {
+            let mut timed = TimedDuration { id: number.id.clone(), seconds: 0.0, segments: 0 };
+            for segment in &number.segments {
+                match lengths.get(segment.id.as_str()) {
+                    Some(Some(length)) => timed.seconds += length,
+                    Some(None) => open_segments += 1,
+                    None => continue,
+                }
+                timed.segments += 1;
+            }
+            if timed.segments == 0 {
+                continue;
+            }
+            match by_act.iter_mut().find(|a| a.id == number.act) {
+                Some(act) => {
+                    act.seconds += timed.seconds;
+                    act.segments += timed.segments;
+                }
+                None => by_act.push(TimedDuration { id: number.act.clone(), ..timed.clone() }),
+            }
+            by_number.push(timed);
+        }
+
+        TimedDurations {
+            seconds: by_act.iter().map(|a| a.seconds).sum(),
+            by_act,
+            by_number,
+            open_segments,
+        }
+    }
+}
+
+/// Segment times by how they were made (see `TimingSource`; times
+/// without a source count as tapped).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SourceCounts {
+    pub estimated: usize,
+    pub aligned: usize,
+    pub imported: usize,
+    pub tapped: usize,
+    pub verified: usize,
+}
+
+impl SourceCounts {
+    fn add(&mut self, time: &SegmentTime) {
+        match time.trust() {
+            TimingSource::Estimated => self.estimated += 1,
+            TimingSource::Aligned => self.aligned += 1,
+            TimingSource::Imported => self.imported += 1,
+            TimingSource::Tapped => self.tapped += 1,
+            TimingSource::Verified => self.verified += 1,
+        }
+    }
+
+    pub fn total(&self) -> usize {
+        self.estimated + self.aligned + self.imported + self.tapped + self.verified
+    }
+}
+
+/// Progress of one overlay track.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrackProgress {
+    pub track_id: String,
+    pub track_title: String,
+    pub has_duration: bool,
+    /// Whether `timing resolve` (or a hand edit) set the track's first segment,
+    /// or marked it instrumental.
+    pub resolved: bool,
+    pub sources: SourceCounts,
+}
+
+/// Progress of one act of the base libretto.
+#[derive(Debug, Clone, Serialize)]
+pub struct ActProgress {
+    pub act: String,
+    /// Segments of the act's numbers, except numbers declared omitted.
+    pub segments: usize,
+    /// Those of them with a time, by source.
+    pub sources: SourceCounts,
+}
+
+impl ActProgress {
+    /// Percentage of the act's segments that have a time.
+    pub fn percent(&self) -> f64 {
+        percent(self.sources.total(), self.segments)
+    }
+}
+
+/// How far a timing overlay has got, for `timing status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimingProgress {
+    pub tracks: Vec<TrackProgress>,
+    pub by_act: Vec<ActProgress>,
+    /// Base numbers no track covers and the overlay does not declare omitted.
+    pub unaccounted_numbers: Vec<String>,
+}
+
+impl TimingProgress {
+    /// Count progress per track and per act (in order of first appearance).
+    pub fn compute(overlay: &TimingOverlay, base: &BaseLibretto) -> Self {
+        let tracks = overlay.track_timings.iter()
+            .enumerate()
+            .map(|(i, track)| {
+                let mut sources = SourceCounts::default();
+                track.segment_times.iter().for_each(|t| sources.add(t));
+                TrackProgress {
+                    track_id: track.track_id(i),
+                    track_title: track.track_title.clone(),
+                    has_duration: track.duration_seconds.is_some(),
+                    resolved: track.start_segment_id.is_some() || track.instrumental,
+                    sources,
+                }
+            })
+            .collect();
+
+        let times: HashMap<&str, &SegmentTime> = overlay.track_timings.iter()
+            .flat_map(|t| &t.segment_times)
+            .map(|t| (t.segment_id.as_str(), t))
+            .collect();
+        let covered: HashSet<&str> = overlay.covered_number_ids().into_iter().collect();
+        let omitted: HashSet<&str> = overlay.omitted_number_ids().into_iter().collect();
+        let mut by_act: Vec<ActProgress> = Vec::new();
+        let mut unaccounted_numbers = Vec::new();
+        for number in &base.numbers {
+            let is_omitted = omitted.contains(number.id.as_str());
+            if !is_omitted && !covered.contains(number.id.as_str()) {
+                unaccounted_numbers.push(number.id.clone());
+            }
+            let index = match by_act.iter().position(|a| a.act == number.act) {
+                Some(index) => index,
+                None => {
+                    by_act.push(ActProgress { act: number.act.clone(), segments: 0, sources: SourceCounts::default() });
+                    by_act.len() - 1
+                }
+            };
+            if is_omitted {
+                continue;
+            }
+            let act = &mut by_act[index];
+            act.segments += number.segments.len();
+            for segment in &number.segments {
+                if let Some(time) = times.get(segment.id.as_str()) {
+                    act.sources.add(time);
+                }
+            }
+        }
+
+        TimingProgress { tracks, by_act, unaccounted_numbers }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libretto_model::{MusicalNumber, NumberType, OperaMetadata, Segment, SegmentType};
+
+    fn segment(id: &str, text: Option<&str>, translation: Option<&str>) -> Segment {
+        Segment {
+            id: id.to_string(),
+            segment_type: if text.is_some() { SegmentType::Sung } else { SegmentType::Direction },
+            character: None,
+            text: text.map(String::from),
+            translation: translation.map(String::from),
+            direction: None,
+            group: None,
+        }
+    }
+
+    fn number(id: &str, act: &str, segments: Vec<Segment>) -> MusicalNumber {
+        MusicalNumber {
+            id: id.to_string(),
+            label: id.to_string(),
+            number_type: NumberType::Aria,
+            act: act.to_string(),
+            scene: None,
+            segments,
+        }
+    }
+
+    #[test]
+    fn test_translation_coverage() {
+        let mut libretto = BaseLibretto::new(OperaMetadata {
+            title: "T".to_string(),
+            composer: "C".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: Some("en".to_string()),
+            year: None,
+        });
+        libretto.numbers = vec![
+            number("no-1", "1", vec![
+                segment("no-1-001", Some("Ah"), Some("Ah")),
+                segment("no-1-002", Some("Oh"), None),
+                segment("no-1-003", None, None),
+            ]),
+            number("no-2", "2", vec![segment("no-2-001", Some("Sì"), None)]),
+            number("no-3", "1", vec![segment("no-3-001", Some("No"), Some("No"))]),
+        ];
+
+        let coverage = TranslationCoverage::compute(&libretto);
+        assert_eq!((coverage.translatable, coverage.translated), (4, 2));
+        assert_eq!(coverage.percent(), 50.0);
+        assert_eq!(coverage.by_act.len(), 2);
+        assert_eq!((coverage.by_act[0].translatable, coverage.by_act[0].translated), (3, 2));
+        let untranslated: Vec<_> = coverage.untranslated_numbers().map(|n| n.id.as_str()).collect();
+        assert_eq!(untranslated, vec!["no-2"]);
+    }
+
+    #[test]
+    fn test_libretto_stats() {
+        let mut libretto = BaseLibretto::new(OperaMetadata {
+            title: "T".to_string(),
+            composer: "C".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: None,
+            year: None,
+        });
+        libretto.cast.push(libretto_model::CastMember {
+            character: "Susanna".to_string(),
+            short_name: Some("SUSANNA".to_string()),
+            voice_type: None,
+            description: None,
+            aliases: vec![],
+        });
+        let sung = |id: &str, character: &str, text: &str| Segment {
+            character: Some(character.to_string()),
+            ..segment(id, Some(text), None)
+        };
+        libretto.numbers = vec![
+            number("no-1", "1", vec![
+                sung("no-1-001", "FIGARO", "Cinque... dieci...\nventi..."),
+                sung("no-1-002", "SUSANNA, FIGARO", "Ora sì"),
+                segment("no-1-003", None, None),
+            ]),
+            number("no-2", "2", vec![sung("no-2-001", "SUSANNA", "Giunse alfin il momento\nChe godrò\nsenz'affanno")]),
+        ];
+
+        let stats = LibrettoStats::compute(&libretto);
+        assert_eq!((stats.numbers, stats.segments, stats.words), (2, 4, 12));
+        assert_eq!((stats.by_act[0].numbers, stats.by_act[0].segments, stats.by_act[0].words), (1, 3, 5));
+        assert_eq!(stats.by_number[1].words, 7);
+        let characters: Vec<_> = stats.characters.iter().map(|c| (c.character.as_str(), c.segments, c.lines)).collect();
+        assert_eq!(characters, vec![("Susanna", 2, 4), ("FIGARO", 2, 3)]);
+        assert!(stats.translation.is_none());
+    }
+
+    #[test]
+    fn test_timed_durations() {
+        let mut libretto = BaseLibretto::new(OperaMetadata {
+            title: "T".to_string(),
+            composer: "C".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: None,
+            year: None,
+        });
+        libretto.numbers = vec![
+            number("no-1", "1", vec![segment("no-1-001", Some("Ah"), None), segment("no-1-002", Some("Oh"), None)]),
+            number("no-2", "1", vec![segment("no-2-001", Some("Sì"), None)]),
+            number("no-3", "2", vec![segment("no-3-001", Some("No"), None)]),
+            number("no-4", "2", vec![segment("no-4-001", Some("Là"), None)]),
+        ];
+        let overlay: TimingOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "track_timings": [
+                { "track_title": "One", "number_ids": ["no-1", "no-2"], "duration_seconds": 60.0, "segment_times": [
+                    { "segment_id": "no-1-001", "start": 2.0 },
+                    { "segment_id": "no-1-002", "start": 10.0, "end": 20.0 },
+                    { "segment_id": "no-2-001", "start": 30.0 }
+                ] },
+                { "track_title": "Two", "number_ids": ["no-3"], "segment_times": [
+                    { "segment_id": "no-3-001", "start": 0.0 }
+                ] }
+            ]
+        }"#).unwrap();
+
+        let durations = TimedDurations::compute(&overlay, &libretto);
+        let numbers: Vec<_> = durations.by_number.iter().map(|n| (n.id.as_str(), n.seconds, n.segments)).collect();
+        assert_eq!(numbers, vec![("no-1", 18.0, 2), ("no-2", 30.0, 1), ("no-3", 0.0, 1)]);
+        assert_eq!((durations.by_act[0].seconds, durations.by_act[1].seconds), (48.0, 0.0));
+        assert_eq!(durations.seconds, 48.0);
+        assert_eq!(durations.open_segments, 1);
+    }
+
+    #[test]
+    fn test_timing_progress() {
+        let mut libretto = BaseLibretto::new(OperaMetadata {
+            title: "T".to_string(),
+            composer: "C".to_string(),
+            librettist: None,
+            language: "it".to_string(),
+            translation_language: None,
+            year: None,
+        });
+        libretto.numbers = vec![
+            number("no-1", "1", vec![
+                segment("no-1-001", Some("Ah"), None),
+                segment("no-1-002", Some("Oh"), None),
+                segment("no-1-003", None, None),
+            ]),
+            number("no-2", "2", vec![segment("no-2-001", Some("Sì"), None)]),
+            number("no-3", "1", vec![segment("no-3-001", Some("No"), None)]),
+        ];
+        let overlay: TimingOverlay = serde_json::from_str(r#"{
+            "version": "1.0",
+            "base_libretto": "base.libretto.json",
+            "recording": {},
+            "omitted_numbers": [{ "number_id": "no-3" }],
+            "track_timings": [
+                { "track_title": "One", "disc_number": 1, "track_number": 1, "number_ids": ["no-1"],
+                  "duration_seconds": 60.0, "start_segment_id": "no-1-001", "segment_times": [
+                    { "segment_id": "no-1-001", "start": 0.0, "source": "estimated" },
+                    { "segment_id": "no-1-002", "start": 5.0, "source": "verified" }
+                ] },
+                { "track_title": "Two", "disc_number": 1, "track_number": 2, "number_ids": [] }
+            ]
+        }"#).unwrap();
+
+        let progress = TimingProgress::compute(&overlay, &libretto);
+        assert_eq!(progress.tracks.len(), 2);
+        assert!(progress.tracks[0].has_duration && progress.tracks[0].resolved);
+        assert!(!progress.tracks[1].has_duration);
+        assert_eq!((progress.tracks[0].sources.estimated, progress.tracks[0].sources.verified), (1, 1));
+        // The omitted number does not count against act 1
+        let act = &progress.by_act[0];
+        assert_eq!((act.act.as_str(), act.segments, act.sources.total()), ("1", 3, 2));
+        assert_eq!(progress.by_act[1].percent(), 0.0);
+        assert_eq!(progress.unaccounted_numbers, vec!["no-2"]);
+    }
+}